@@ -1,10 +1,16 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use renclave_enclave::seed_generator::SeedGenerator;
+use renclave_enclave::{ClockConfig, TrustedClock};
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 
+/// Unsynchronized clock, enough for anything but time-based signing policies
+fn clock() -> Arc<TrustedClock> {
+    Arc::new(TrustedClock::new(ClockConfig::default()))
+}
+
 async fn benchmark_concurrent_seed_generation(concurrency: usize) {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
     let mut handles = vec![];
 
     for _ in 0..concurrency {
@@ -17,7 +23,7 @@ async fn benchmark_concurrent_seed_generation(concurrency: usize) {
 }
 
 async fn benchmark_concurrent_seed_validation(concurrency: usize) {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
     let valid_seed = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
     let mut handles = vec![];
 
@@ -32,7 +38,7 @@ async fn benchmark_concurrent_seed_validation(concurrency: usize) {
 }
 
 async fn benchmark_mixed_concurrent_operations(concurrency: usize) {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
 
     // Run generation and validation operations separately to avoid type conflicts
     let mut generation_handles = vec![];
@@ -1,23 +1,30 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use renclave_enclave::seed_generator::SeedGenerator;
+use renclave_enclave::{ClockConfig, TrustedClock};
+use std::sync::Arc;
 use tokio::runtime::Runtime;
 
+/// Unsynchronized clock, enough for anything but time-based signing policies
+fn clock() -> Arc<TrustedClock> {
+    Arc::new(TrustedClock::new(ClockConfig::default()))
+}
+
 async fn benchmark_seed_generation_128() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     for _ in 0..100 {
         let _seed = seed_generator.generate_seed(128, None).await.unwrap();
     }
 }
 
 async fn benchmark_seed_generation_256() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     for _ in 0..100 {
         let _seed = seed_generator.generate_seed(256, None).await.unwrap();
     }
 }
 
 async fn benchmark_seed_generation_with_passphrase() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     for _ in 0..100 {
         let _seed = seed_generator
             .generate_seed(256, Some("test-passphrase"))
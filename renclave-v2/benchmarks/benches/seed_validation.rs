@@ -1,9 +1,16 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use renclave_enclave::seed_generator::SeedGenerator;
+use renclave_enclave::{ClockConfig, TrustedClock};
+use std::sync::Arc;
 use tokio::runtime::Runtime;
 
+/// Unsynchronized clock, enough for anything but time-based signing policies
+fn clock() -> Arc<TrustedClock> {
+    Arc::new(TrustedClock::new(ClockConfig::default()))
+}
+
 async fn benchmark_seed_validation_valid() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     let valid_seed = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
 
     for _ in 0..1000 {
@@ -12,7 +19,7 @@ async fn benchmark_seed_validation_valid() {
 }
 
 async fn benchmark_seed_validation_invalid() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     let invalid_seed = "invalid seed phrase that should fail validation";
 
     for _ in 0..1000 {
@@ -21,7 +28,7 @@ async fn benchmark_seed_validation_invalid() {
 }
 
 async fn benchmark_seed_validation_with_passphrase() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     let seed_with_passphrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about test-passphrase";
 
     for _ in 0..1000 {
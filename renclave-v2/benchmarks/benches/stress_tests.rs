@@ -1,10 +1,16 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use renclave_enclave::seed_generator::SeedGenerator;
+use renclave_enclave::{ClockConfig, TrustedClock};
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 
+/// Unsynchronized clock, enough for anything but time-based signing policies
+fn clock() -> Arc<TrustedClock> {
+    Arc::new(TrustedClock::new(ClockConfig::default()))
+}
+
 async fn benchmark_stress_seed_generation_burst(operations: usize) {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
     let mut handles = vec![];
 
     // Burst of operations
@@ -18,7 +24,7 @@ async fn benchmark_stress_seed_generation_burst(operations: usize) {
 }
 
 async fn benchmark_stress_mixed_operations_sustained(operations: usize) {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
     let valid_seed = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
 
     for _ in 0..operations {
@@ -37,7 +43,7 @@ async fn benchmark_stress_mixed_operations_sustained(operations: usize) {
 }
 
 async fn benchmark_stress_memory_pressure(iterations: usize) {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
     let mut seeds = Vec::new();
 
     for _ in 0..iterations {
@@ -47,7 +53,10 @@ async fn benchmark_stress_memory_pressure(iterations: usize) {
 
         // Validate all previously generated seeds
         for seed in &seeds {
-            let _is_valid = seed_generator.validate_seed(&seed.phrase).await.unwrap();
+            let _is_valid = seed_generator
+                .validate_seed(seed.phrase.expose_secret())
+                .await
+                .unwrap();
         }
 
         // Limit memory usage by keeping only last 100 seeds
@@ -58,7 +67,7 @@ async fn benchmark_stress_memory_pressure(iterations: usize) {
 }
 
 async fn benchmark_stress_error_conditions(iterations: usize) {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
 
     for _ in 0..iterations {
         // Test invalid strength
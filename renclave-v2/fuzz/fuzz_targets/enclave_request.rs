@@ -0,0 +1,18 @@
+//! Request frames from the host, deserialized inside the enclave
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use renclave_shared::EnclaveRequest;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(request) = serde_json::from_slice::<EnclaveRequest>(data) else {
+        return;
+    };
+
+    // Anything accepted must survive a round trip unchanged
+    let json = serde_json::to_string(&request).expect("accepted request must serialize");
+    let reparsed: EnclaveRequest =
+        serde_json::from_str(&json).expect("serialized request must parse");
+    assert_eq!(reparsed.id, request.id);
+    assert_eq!(reparsed.operation.name(), request.operation.name());
+});
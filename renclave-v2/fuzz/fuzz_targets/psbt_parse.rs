@@ -0,0 +1,15 @@
+//! Base64 PSBTs submitted for signing
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use renclave_enclave::psbt;
+
+fuzz_target!(|data: &str| {
+    let Ok(parsed) = psbt::parse(data) else {
+        return;
+    };
+
+    let encoded = parsed.to_string();
+    let reparsed = psbt::parse(&encoded).expect("re-encoded PSBT must parse");
+    assert_eq!(reparsed, parsed);
+});
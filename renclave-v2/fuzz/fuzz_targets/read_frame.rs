@@ -0,0 +1,41 @@
+//! Newline framing on the enclave socket
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use renclave_enclave::limits::{read_frame, Frame, SocketLimits};
+use std::time::Duration;
+
+fuzz_target!(|input: (u8, &[u8])| {
+    let (max_frame_bytes, mut data) = input;
+    let limits = SocketLimits {
+        max_frame_bytes: max_frame_bytes as usize + 1,
+        read_timeout: Duration::from_secs(1),
+        max_connections: 1,
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let mut buffer = String::new();
+        loop {
+            buffer.clear();
+            match read_frame(&mut data, &mut buffer, &limits).await {
+                Ok(Frame::Line) => {
+                    // Only the last frame of the input may lack its newline
+                    assert!(buffer.len() <= limits.max_frame_bytes + 1);
+                    assert!(buffer.ends_with('\n') || data.is_empty());
+                }
+                Ok(Frame::TooLarge) => {
+                    assert_eq!(buffer.len(), limits.max_frame_bytes + 1);
+                    break;
+                }
+                // Invalid UTF-8 ends the connection
+                Ok(Frame::Eof) | Err(_) => break,
+                Ok(Frame::TimedOut) => unreachable!("in-memory reads never block"),
+            }
+        }
+    });
+});
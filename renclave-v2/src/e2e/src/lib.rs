@@ -0,0 +1,231 @@
+//! End-to-end harness for renclave-v2
+//!
+//! Launches the `enclave` and `host` binaries as child processes, connected through a Unix
+//! socket in a temporary directory, and talks to the host over real HTTP. Tests built on
+//! the harness exercise the full host ↔ enclave wire protocol.
+
+use anyhow::{anyhow, bail, Context, Result};
+use renclave_shared::ENCLAVE_SOCKET_ENV;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::File;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+pub use reqwest::StatusCode;
+
+/// Environment variable for the directory holding the `enclave` and `host` binaries
+pub const BIN_DIR_ENV: &str = "RENCLAVE_E2E_BIN_DIR";
+
+/// Admin key the host is started with; sent on every request so admin routes are reachable
+const ADMIN_API_KEY: &str = "renclave-e2e-admin";
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Running enclave and host processes.
+///
+/// Both processes are killed when the harness is dropped. The temporary directory with the
+/// socket and the process logs is removed, unless the test is panicking, in which case its
+/// path is printed so the logs can be inspected. The host runs with authentication enabled
+/// and every request carries its admin key.
+pub struct Harness {
+    dir: PathBuf,
+    bin_dir: PathBuf,
+    base_url: String,
+    enclave: Child,
+    host: Child,
+    enclave_starts: u32,
+    http: reqwest::Client,
+}
+
+impl Harness {
+    /// Start an enclave and a host on a free local port and wait until the host reaches
+    /// the enclave
+    pub async fn start() -> Result<Self> {
+        let bin_dir = bin_dir()?;
+        for binary in ["enclave", "host"] {
+            if !bin_dir.join(binary).is_file() {
+                bail!(
+                    "{} not found in {}; build it with `cargo build -p renclave-enclave -p renclave-host` or set {}",
+                    binary,
+                    bin_dir.display(),
+                    BIN_DIR_ENV
+                );
+            }
+        }
+
+        let dir = std::env::temp_dir().join(format!("renclave-e2e-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).context("Failed to create harness directory")?;
+
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let bind_addr = format!("127.0.0.1:{}", port);
+
+        let enclave = spawn(&bin_dir, &dir, "enclave", "enclave-0.log", &[])?;
+        let host = spawn(
+            &bin_dir,
+            &dir,
+            "host",
+            "host.log",
+            &[
+                ("RENCLAVE_HOST_BIND_ADDR", bind_addr.as_str()),
+                ("RENCLAVE_ADMIN_API_KEY", ADMIN_API_KEY),
+            ],
+        )?;
+
+        let mut harness = Self {
+            dir,
+            bin_dir,
+            base_url: format!("http://{}", bind_addr),
+            enclave,
+            host,
+            enclave_starts: 1,
+            http: reqwest::Client::new(),
+        };
+        harness.wait_for_enclave(None).await?;
+        Ok(harness)
+    }
+
+    /// Directory holding the enclave socket and process logs
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// GET a host route, returning the status and JSON body
+    pub async fn get(&self, path: &str) -> Result<(StatusCode, Value)> {
+        let response = self
+            .http
+            .get(format!("{}{}", self.base_url, path))
+            .bearer_auth(ADMIN_API_KEY)
+            .send()
+            .await
+            .with_context(|| format!("GET {} failed", path))?;
+        body(path, response).await
+    }
+
+    /// POST a JSON body to a host route, returning the status and JSON body
+    pub async fn post<B: Serialize>(
+        &self,
+        path: &str,
+        body_json: &B,
+    ) -> Result<(StatusCode, Value)> {
+        let response = self
+            .http
+            .post(format!("{}{}", self.base_url, path))
+            .bearer_auth(ADMIN_API_KEY)
+            .json(body_json)
+            .send()
+            .await
+            .with_context(|| format!("POST {} failed", path))?;
+        body(path, response).await
+    }
+
+    /// Kill the enclave and start a new instance, waiting until the host reports it
+    pub async fn restart_enclave(&mut self) -> Result<()> {
+        let (_, info) = self.get("/enclave/info").await?;
+        let previous_id = info["enclave_id"].as_str().map(str::to_string);
+
+        self.enclave.kill().ok();
+        self.enclave.wait().ok();
+
+        let log = format!("enclave-{}.log", self.enclave_starts);
+        self.enclave = spawn(&self.bin_dir, &self.dir, "enclave", &log, &[])?;
+        self.enclave_starts += 1;
+
+        self.wait_for_enclave(previous_id.as_deref()).await
+    }
+
+    /// Poll `/enclave/info` until it succeeds with an enclave other than `previous_id`
+    async fn wait_for_enclave(&mut self, previous_id: Option<&str>) -> Result<()> {
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+
+        while Instant::now() < deadline {
+            for (name, child) in [("enclave", &mut self.enclave), ("host", &mut self.host)] {
+                if let Some(status) = child.try_wait()? {
+                    bail!(
+                        "{} exited with {} during startup; logs in {}",
+                        name,
+                        status,
+                        self.dir.display()
+                    );
+                }
+            }
+
+            if let Ok((StatusCode::OK, info)) = self.get("/enclave/info").await {
+                if info["enclave_id"].as_str() != previous_id {
+                    return Ok(());
+                }
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+
+        Err(anyhow!(
+            "Host did not reach the enclave within {:?}; logs in {}",
+            STARTUP_TIMEOUT,
+            self.dir.display()
+        ))
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        for child in [&mut self.host, &mut self.enclave] {
+            child.kill().ok();
+            child.wait().ok();
+        }
+
+        if std::thread::panicking() {
+            eprintln!("renclave-e2e: logs kept in {}", self.dir.display());
+        } else {
+            std::fs::remove_dir_all(&self.dir).ok();
+        }
+    }
+}
+
+/// Directory of the binaries: `RENCLAVE_E2E_BIN_DIR`, or the cargo target directory of the
+/// running test
+fn bin_dir() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var(BIN_DIR_ENV) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    // Test executables live in target/<profile>/deps
+    let exe = std::env::current_exe()?;
+    exe.parent()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .ok_or_else(|| anyhow!("Cannot locate target directory from {}", exe.display()))
+}
+
+/// Spawn a binary with its working directory and socket in `dir`, logging to `log`
+fn spawn(
+    bin_dir: &Path,
+    dir: &Path,
+    binary: &str,
+    log: &str,
+    env: &[(&str, &str)],
+) -> Result<Child> {
+    let log = File::create(dir.join(log))?;
+
+    Command::new(bin_dir.join(binary))
+        .current_dir(dir)
+        .env(ENCLAVE_SOCKET_ENV, dir.join("enclave.sock"))
+        .envs(env.iter().copied())
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .spawn()
+        .with_context(|| format!("Failed to spawn {}", binary))
+}
+
+async fn body(path: &str, response: reqwest::Response) -> Result<(StatusCode, Value)> {
+    let status = response.status();
+    let body = response
+        .json()
+        .await
+        .with_context(|| format!("{} returned a non-JSON body", path))?;
+    Ok((status, body))
+}
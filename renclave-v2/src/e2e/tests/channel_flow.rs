@@ -0,0 +1,102 @@
+//! Encrypted channel handshake and sealed requests through the host.
+//!
+//! Spawns the enclave and host binaries, so it is ignored by default:
+//!
+//! ```bash
+//! cargo build -p renclave-enclave -p renclave-host
+//! cargo test -p renclave-e2e -- --ignored
+//! ```
+
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use renclave_e2e::{Harness, StatusCode};
+use renclave_shared::channel::{self, ChannelKeys, Direction};
+use renclave_shared::{
+    ChannelAttestation, EnclaveOperation, EnclaveResult, SealedPayload, SeedLanguage,
+};
+use serde_json::{json, Value};
+
+#[tokio::test]
+#[ignore = "spawns the enclave and host binaries"]
+async fn test_sealed_seed_generation() {
+    let harness = Harness::start().await.unwrap();
+
+    let secp = Secp256k1::new();
+    let client_secret = SecretKey::from_slice(&[0x42; 32]).unwrap();
+    let client_public = client_secret.public_key(&secp);
+
+    let (status, opened) = harness
+        .post(
+            "/enclave/channel",
+            &json!({
+                "client_public_key": hex::encode(client_public.serialize()),
+                "nonce": "c0ffee",
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::OK, "{}", opened);
+
+    let session_id = opened["session_id"].as_str().unwrap();
+    let enclave_public =
+        channel::parse_public_key(opened["enclave_public_key"].as_str().unwrap()).unwrap();
+
+    // The attestation commits to this handshake
+    let expected = channel::handshake_hash(session_id, &client_public, &enclave_public);
+    assert_eq!(opened["attestation"]["user_data"], hex::encode(expected));
+    let attestation: ChannelAttestation =
+        serde_json::from_value(opened["attestation"].clone()).unwrap();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    assert!(attestation.is_fresh(&[0xc0, 0xff, 0xee], 60, now));
+
+    let keys = ChannelKeys::derive(
+        &client_secret,
+        &enclave_public,
+        &client_public,
+        &enclave_public,
+    );
+    let operation = EnclaveOperation::GenerateSeed {
+        strength: 128,
+        passphrase: None,
+        language: SeedLanguage::English,
+        attest: false,
+        fresh_attestation: false,
+    };
+    let request = keys.seal(
+        Direction::ClientToEnclave,
+        session_id,
+        1,
+        &serde_json::to_vec(&operation).unwrap(),
+    );
+    let message = json!({ "session_id": session_id, "payload": request });
+
+    let (status, sealed) = harness
+        .post("/enclave/channel/sealed", &message)
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::OK, "{}", sealed);
+
+    // The host only relays ciphertext
+    let body = sealed.to_string();
+    assert!(!body.contains("seed_phrase"));
+
+    let payload: SealedPayload = serde_json::from_value(sealed["payload"].clone()).unwrap();
+    let plaintext = keys
+        .open(Direction::EnclaveToClient, session_id, &payload)
+        .unwrap();
+    let result: EnclaveResult = serde_json::from_slice(plaintext.expose_secret()).unwrap();
+    match result {
+        EnclaveResult::SeedGenerated { word_count, .. } => assert_eq!(word_count, 12),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Replaying the same request is rejected
+    let (status, replayed): (StatusCode, Value) = harness
+        .post("/enclave/channel/sealed", &message)
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(replayed["enclave_error"]["code"], 1001);
+}
@@ -0,0 +1,166 @@
+//! Seed → derivation → policy → signing over real HTTP, across an enclave restart.
+//!
+//! Spawns the enclave and host binaries, so it is ignored by default:
+//!
+//! ```bash
+//! cargo build -p renclave-enclave -p renclave-host
+//! cargo test -p renclave-e2e -- --ignored
+//! ```
+
+use bitcoin::absolute::LockTime;
+use bitcoin::bip32::{DerivationPath, Fingerprint, Xpub};
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::transaction::Version;
+use bitcoin::{
+    Address, Amount, CompressedPublicKey, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut,
+    Txid, Witness,
+};
+use renclave_e2e::{Harness, StatusCode};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+const ACCOUNT_PATH: &str = "m/84'/0'/0'";
+const DESTINATION: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+/// PSBT spending a P2WPKH output of the account's first receive address to `DESTINATION`
+fn spend_psbt(xpub: &Xpub, fingerprint: Fingerprint, sats: u64) -> String {
+    let secp = Secp256k1::new();
+    let child = DerivationPath::from_str("m/0/0").unwrap();
+    let key = xpub.derive_pub(&secp, &child).unwrap().public_key;
+    let script = ScriptBuf::new_p2wpkh(&CompressedPublicKey(key).wpubkey_hash());
+
+    let tx = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint {
+                txid: Txid::all_zeros(),
+                vout: 0,
+            },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::from_sat(sats),
+            script_pubkey: Address::from_str(DESTINATION)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey(),
+        }],
+    };
+
+    let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+    psbt.inputs[0].witness_utxo = Some(TxOut {
+        value: Amount::from_sat(sats + 1_000),
+        script_pubkey: script,
+    });
+    let path = DerivationPath::from_str(&format!("{}/0/0", ACCOUNT_PATH)).unwrap();
+    psbt.inputs[0]
+        .bip32_derivation
+        .insert(key, (fingerprint, path));
+
+    psbt.to_string()
+}
+
+fn session_epoch(info: &Value) -> u64 {
+    info["session"]["epoch"].as_u64().unwrap()
+}
+
+#[tokio::test]
+#[ignore = "spawns the enclave and host binaries"]
+async fn test_seed_to_signature_across_restart() {
+    let mut harness = Harness::start().await.unwrap();
+
+    let (status, info) = harness.get("/enclave/info").await.unwrap();
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(info["healthy"], true);
+    assert_eq!(info["build"]["profile"], "debug");
+    assert!(info["build"]["rustc_version"]
+        .as_str()
+        .unwrap()
+        .starts_with("rustc "));
+    let first_epoch = session_epoch(&info);
+
+    // Seed generation and transcription check
+    let (status, seed) = harness
+        .post("/generate-seed", &json!({ "strength": 128 }))
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(seed["word_count"], 12);
+    let phrase = seed["seed_phrase"].as_str().unwrap().to_string();
+
+    let (status, validated) = harness
+        .post(
+            "/validate-seed",
+            &json!({ "seed_phrase": phrase, "entropy": seed["entropy"] }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(validated["valid"], true);
+    assert_eq!(validated["entropy_match"], true);
+
+    // Watch-only account key, used to build a PSBT outside the enclave
+    let (status, account) = harness
+        .post(
+            "/derive-xpub",
+            &json!({ "seed_phrase": phrase, "path": ACCOUNT_PATH }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::OK);
+    let xpub = Xpub::from_str(account["xpub"].as_str().unwrap()).unwrap();
+    let fingerprint =
+        Fingerprint::from_str(account["master_fingerprint"].as_str().unwrap()).unwrap();
+
+    // Policy allowing one 50k sat spend per day
+    let (status, _) = harness
+        .post(
+            "/enclave/policy",
+            &json!({ "destination_allowlist": [DESTINATION], "daily_limit_sats": 60_000 }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::OK);
+
+    let sign_request = json!({
+        "seed_phrase": phrase,
+        "psbt": spend_psbt(&xpub, fingerprint, 50_000),
+    });
+    let (status, signed) = harness
+        .post("/enclave/sign-psbt", &sign_request)
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::OK, "{}", signed);
+    assert_eq!(signed["signed_inputs"], json!([0]));
+    let signed_psbt = Psbt::from_str(signed["psbt"].as_str().unwrap()).unwrap();
+    assert_eq!(signed_psbt.inputs[0].partial_sigs.len(), 1);
+
+    // The second spend exceeds the daily limit
+    let (status, refused) = harness
+        .post("/enclave/sign-psbt", &sign_request)
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(refused["enclave_error"]["label"], "policy_denied");
+
+    // A restarted enclave is a new session with the policy and spend counter reset
+    harness.restart_enclave().await.unwrap();
+
+    let (_, info) = harness.get("/enclave/info").await.unwrap();
+    assert_eq!(session_epoch(&info), first_epoch + 1);
+
+    let (status, policy) = harness.get("/enclave/policy").await.unwrap();
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(policy["daily_limit_sats"], Value::Null);
+
+    let (status, _) = harness
+        .post("/enclave/sign-psbt", &sign_request)
+        .await
+        .unwrap();
+    assert_eq!(status, StatusCode::OK);
+}
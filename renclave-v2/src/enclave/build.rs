@@ -0,0 +1,50 @@
+//! Records the build provenance reported by GetInfo: git commit, compiler version and
+//! enabled features.
+
+use std::process::Command;
+
+/// Overrides the commit for builds without a git checkout, e.g. Docker builds
+const GIT_COMMIT_ENV: &str = "RENCLAVE_GIT_COMMIT";
+
+fn main() {
+    println!("cargo:rerun-if-env-changed={}", GIT_COMMIT_ENV);
+
+    let git_commit = std::env::var(GIT_COMMIT_ENV)
+        .ok()
+        .filter(|commit| !commit.trim().is_empty())
+        .or_else(git_head)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = output(&rustc, &["--version"]).unwrap_or_else(|| "unknown".to_string());
+
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_string))
+        .map(|feature| feature.to_lowercase().replace('_', "-"))
+        .collect();
+    features.sort();
+
+    println!("cargo:rustc-env=RENCLAVE_GIT_COMMIT={}", git_commit.trim());
+    println!("cargo:rustc-env=RENCLAVE_RUSTC_VERSION={}", rustc_version);
+    println!("cargo:rustc-env=RENCLAVE_FEATURES={}", features.join(","));
+}
+
+/// Commit of the checkout, rebuilding when HEAD moves
+fn git_head() -> Option<String> {
+    let git_dir = output("git", &["rev-parse", "--absolute-git-dir"])?;
+    println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+    if let Some(reference) = output("git", &["symbolic-ref", "-q", "HEAD"]) {
+        println!("cargo:rerun-if-changed={}/{}", git_dir, reference);
+    }
+
+    output("git", &["rev-parse", "HEAD"])
+}
+
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    Some(stdout.trim().to_string())
+}
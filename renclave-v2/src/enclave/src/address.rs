@@ -0,0 +1,161 @@
+use bitcoin::bech32::{self, Bech32, Hrp};
+use bitcoin::hashes::{hash160, Hash};
+use bitcoin::{Address, CompressedPublicKey, KnownHrp};
+use renclave_shared::validation::Curve;
+use renclave_shared::Chain;
+use secp256k1::{PublicKey, Secp256k1};
+use sha3::{Digest, Keccak256};
+
+/// Bech32 prefix of Cosmos Hub account addresses
+const COSMOS_HRP: &str = "cosmos";
+
+/// Reasons an address cannot be produced for a chain
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("Chain {chain} does not use curve '{curve}'")]
+    CurveMismatch { chain: &'static str, curve: String },
+
+    #[error("Chain {0} does not use secp256k1 keys")]
+    NotSecp256k1(&'static str),
+
+    #[error("Address encoding failed: {0}")]
+    Encoding(String),
+}
+
+/// Reject an explicit curve that contradicts the chain's key type.
+///
+/// Curve names are parsed like `Curve` (`secp256k1`, `CURVE_SECP256K1`, `ed25519`, ...);
+/// unknown names are left to the caller.
+pub fn check_curve(chain: Chain, curve: &str) -> Result<(), AddressError> {
+    let Ok(parsed) = curve.parse::<Curve>() else {
+        return Ok(());
+    };
+
+    if chain.uses_ed25519() != (parsed == Curve::Ed25519) {
+        return Err(AddressError::CurveMismatch {
+            chain: chain.as_str(),
+            curve: curve.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Encode a secp256k1 public key in the chain's address format
+pub fn encode_secp256k1(chain: Chain, public_key: &PublicKey) -> Result<String, AddressError> {
+    match chain {
+        Chain::Bitcoin => Ok(bitcoin_p2wpkh(public_key)),
+        Chain::BitcoinTaproot => Ok(bitcoin_p2tr(public_key)),
+        Chain::Ethereum => Ok(ethereum_address(public_key)),
+        Chain::Cosmos => cosmos_address(public_key),
+        Chain::Solana => Err(AddressError::NotSecp256k1(chain.as_str())),
+    }
+}
+
+/// BIP-84 native SegWit address (`bc1q...`)
+pub fn bitcoin_p2wpkh(public_key: &PublicKey) -> String {
+    Address::p2wpkh(&CompressedPublicKey(*public_key), KnownHrp::Mainnet).to_string()
+}
+
+/// BIP-86 key-path-only Taproot address (`bc1p...`)
+pub fn bitcoin_p2tr(public_key: &PublicKey) -> String {
+    let secp = Secp256k1::verification_only();
+    let (internal_key, _) = public_key.x_only_public_key();
+    Address::p2tr(&secp, internal_key, None, KnownHrp::Mainnet).to_string()
+}
+
+/// EIP-55 mixed-case checksummed address of the Keccak-256 hash of the uncompressed key
+pub fn ethereum_address(public_key: &PublicKey) -> String {
+    let uncompressed = public_key.serialize_uncompressed();
+    let hash = Keccak256::digest(&uncompressed[1..]);
+    let address = hex::encode(&hash[12..]);
+
+    let checksum = Keccak256::digest(address.as_bytes());
+    let checksummed: String = address
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (checksum[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    format!("0x{}", checksummed)
+}
+
+/// Cosmos SDK account address: bech32 of RIPEMD-160(SHA-256(compressed key))
+pub fn cosmos_address(public_key: &PublicKey) -> Result<String, AddressError> {
+    let hash = hash160::Hash::hash(&public_key.serialize());
+    let hrp = Hrp::parse(COSMOS_HRP).map_err(|e| AddressError::Encoding(e.to_string()))?;
+
+    bech32::encode::<Bech32>(hrp, hash.as_byte_array())
+        .map_err(|e| AddressError::Encoding(e.to_string()))
+}
+
+/// Solana address: base58 of the raw ed25519 public key
+pub fn solana_address(public_key: &[u8; 32]) -> String {
+    bs58::encode(public_key).into_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn public_key(hex_key: &str) -> PublicKey {
+        PublicKey::from_str(hex_key).unwrap()
+    }
+
+    #[test]
+    fn test_ethereum_eip55_checksum() {
+        // Private key 1: generator point
+        let key = public_key("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+        assert_eq!(
+            ethereum_address(&key),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+    }
+
+    #[test]
+    fn test_bitcoin_p2wpkh_bip173_vector() {
+        let key = public_key("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+        assert_eq!(
+            bitcoin_p2wpkh(&key),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+    }
+
+    #[test]
+    fn test_solana_base58() {
+        assert_eq!(
+            solana_address(&[0u8; 32]),
+            "11111111111111111111111111111111"
+        );
+    }
+
+    #[test]
+    fn test_curve_must_match_chain() {
+        assert!(check_curve(Chain::Ethereum, "CURVE_SECP256K1").is_ok());
+        assert!(check_curve(Chain::Solana, "CURVE_ED25519").is_ok());
+        assert!(check_curve(Chain::Solana, "secp256k1").is_err());
+        assert_eq!(
+            check_curve(Chain::Cosmos, "ed25519"),
+            Err(AddressError::CurveMismatch {
+                chain: "cosmos",
+                curve: "ed25519".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_solana_has_no_secp256k1_encoding() {
+        let key = public_key("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+        assert_eq!(
+            encode_secp256k1(Chain::Solana, &key),
+            Err(AddressError::NotSecp256k1("solana"))
+        );
+    }
+}
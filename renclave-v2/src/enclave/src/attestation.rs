@@ -0,0 +1,253 @@
+use anyhow::Result;
+use log::debug;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::clock::TrustedClock;
+use crate::nitro::{AttestationDocument, NitroAttestation};
+
+/// Environment variable for how long attestation documents are reused, in seconds; 0 turns
+/// caching off
+pub const ATTESTATION_CACHE_TTL_SECS_ENV: &str = "RENCLAVE_ATTESTATION_CACHE_TTL_SECS";
+/// Environment variable for the most attestation documents generated per second
+pub const ATTESTATION_MAX_PER_SEC_ENV: &str = "RENCLAVE_ATTESTATION_MAX_PER_SEC";
+
+/// Cached documents beyond this evict the oldest
+const MAX_CACHED_DOCUMENTS: usize = 1024;
+
+/// Caching and rate limits for attestation document generation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestationLimits {
+    /// How long a document is reused for the same user data and nonce; zero disables caching
+    pub cache_ttl: Duration,
+    /// Documents generated per second, beyond which requests fail until the next second
+    pub max_per_sec: u32,
+}
+
+impl Default for AttestationLimits {
+    fn default() -> Self {
+        Self {
+            cache_ttl: Duration::from_secs(30),
+            max_per_sec: 50,
+        }
+    }
+}
+
+impl AttestationLimits {
+    /// Default limits with overrides from the environment
+    pub fn from_env() -> Self {
+        let env = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+
+        let mut limits = Self::default();
+        // 0 is meaningful here: never reuse a document
+        if let Some(secs) = env(ATTESTATION_CACHE_TTL_SECS_ENV) {
+            limits.cache_ttl = Duration::from_secs(secs);
+        }
+        if let Some(max) = env(ATTESTATION_MAX_PER_SEC_ENV).filter(|max| *max > 0) {
+            limits.max_per_sec = max.min(u32::MAX as u64) as u32;
+        }
+        limits
+    }
+}
+
+/// More documents were requested in the current second than the NSM is allowed to produce
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Attestation rate limit of {max_per_sec} documents per second reached")]
+pub struct AttestationThrottled {
+    pub max_per_sec: u32,
+}
+
+/// What a document attests to: its user data and nonce
+type CacheKey = (Vec<u8>, Option<Vec<u8>>);
+
+#[derive(Default)]
+struct State {
+    cached: HashMap<CacheKey, (Instant, AttestationDocument)>,
+    window_start: Option<Instant>,
+    issued_in_window: u32,
+}
+
+/// Produces the enclave's attestation documents, reusing recent ones for the same user data
+/// and nonce so bursts of identical requests do not each reach the NSM.
+///
+/// Since a cached document commits to exactly the same user data and nonce, reusing it only
+/// makes its timestamp older. Callers that need a document generated for them pass `fresh`.
+pub struct Attester {
+    attestation: NitroAttestation,
+    clock: Arc<TrustedClock>,
+    limits: AttestationLimits,
+    state: Mutex<State>,
+}
+
+impl Attester {
+    pub fn new(enclave_id: String, limits: AttestationLimits, clock: Arc<TrustedClock>) -> Self {
+        Self {
+            attestation: NitroAttestation::new(enclave_id),
+            clock,
+            limits,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn enclave_id(&self) -> &str {
+        &self.attestation.enclave_id
+    }
+
+    /// Attestation document over `user_data` and `nonce`, from the cache unless `fresh`
+    pub async fn document(
+        &self,
+        user_data: &[u8],
+        nonce: Option<&[u8]>,
+        fresh: bool,
+    ) -> Result<AttestationDocument> {
+        let key = (user_data.to_vec(), nonce.map(<[u8]>::to_vec));
+        self.reserve(&key, fresh, Instant::now())?;
+        if let Some(document) = self.cached(&key, fresh, Instant::now()) {
+            debug!("📋 Reusing cached attestation document");
+            return Ok(document);
+        }
+
+        let document = self
+            .attestation
+            .generate_attestation_document(Some(user_data), nonce, &self.clock.now())
+            .await?;
+        self.store(key, &document, Instant::now());
+        Ok(document)
+    }
+
+    /// Take a slot in the current one-second window unless a cached document will be served
+    fn reserve(&self, key: &CacheKey, fresh: bool, now: Instant) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !fresh && Self::lookup(&state, key, self.limits.cache_ttl, now).is_some() {
+            return Ok(());
+        }
+        let window_expired = state
+            .window_start
+            .is_none_or(|start| now.duration_since(start) >= Duration::from_secs(1));
+        if window_expired {
+            state.window_start = Some(now);
+            state.issued_in_window = 0;
+        }
+        if state.issued_in_window >= self.limits.max_per_sec {
+            return Err(AttestationThrottled {
+                max_per_sec: self.limits.max_per_sec,
+            }
+            .into());
+        }
+        state.issued_in_window += 1;
+        Ok(())
+    }
+
+    fn cached(&self, key: &CacheKey, fresh: bool, now: Instant) -> Option<AttestationDocument> {
+        if fresh {
+            return None;
+        }
+        let state = self.state.lock().unwrap();
+        Self::lookup(&state, key, self.limits.cache_ttl, now)
+    }
+
+    fn lookup(
+        state: &State,
+        key: &CacheKey,
+        ttl: Duration,
+        now: Instant,
+    ) -> Option<AttestationDocument> {
+        state
+            .cached
+            .get(key)
+            .filter(|(issued, _)| now.duration_since(*issued) < ttl)
+            .map(|(_, document)| document.clone())
+    }
+
+    fn store(&self, key: CacheKey, document: &AttestationDocument, now: Instant) {
+        let ttl = self.limits.cache_ttl;
+        if ttl.is_zero() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state
+            .cached
+            .retain(|_, (issued, _)| now.duration_since(*issued) < ttl);
+        if state.cached.len() >= MAX_CACHED_DOCUMENTS {
+            let oldest = state
+                .cached
+                .iter()
+                .min_by_key(|(_, (issued, _))| *issued)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                state.cached.remove(&oldest);
+            }
+        }
+        state.cached.insert(key, (now, document.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ClockConfig;
+
+    fn attester(cache_ttl: Duration, max_per_sec: u32) -> Attester {
+        Attester::new(
+            "test-enclave".to_string(),
+            AttestationLimits {
+                cache_ttl,
+                max_per_sec,
+            },
+            Arc::new(TrustedClock::new(ClockConfig::default())),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reuses_documents_for_the_same_inputs() {
+        let attester = attester(Duration::from_secs(30), 50);
+        let first = attester.document(b"digest", None, false).await.unwrap();
+        std::thread::sleep(Duration::from_millis(1100));
+        let second = attester.document(b"digest", None, false).await.unwrap();
+        assert_eq!(first.timestamp, second.timestamp);
+
+        let fresh = attester.document(b"digest", None, true).await.unwrap();
+        assert!(fresh.timestamp > first.timestamp);
+
+        let other = attester
+            .document(b"digest", Some(b"nonce"), false)
+            .await
+            .unwrap();
+        assert_eq!(other.nonce.as_deref(), Some(&b"nonce"[..]));
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_disables_caching() {
+        let attester = attester(Duration::ZERO, 50);
+        attester.document(b"digest", None, false).await.unwrap();
+        assert!(attester.state.lock().unwrap().cached.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limits_generation_but_not_cache_hits() {
+        let attester = attester(Duration::from_secs(30), 2);
+        attester.document(b"a", None, false).await.unwrap();
+        attester.document(b"b", None, false).await.unwrap();
+        let error = attester.document(b"c", None, false).await.unwrap_err();
+        assert!(error.downcast_ref::<AttestationThrottled>().is_some());
+        assert!(attester.document(b"a", None, false).await.is_ok());
+        assert!(attester.document(b"a", None, true).await.is_err());
+    }
+
+    #[test]
+    fn test_window_resets_after_a_second() {
+        let attester = attester(Duration::ZERO, 1);
+        let start = Instant::now();
+        let key = (b"a".to_vec(), None);
+        attester.reserve(&key, false, start).unwrap();
+        assert!(attester.reserve(&key, false, start).is_err());
+        assert!(attester
+            .reserve(&key, false, start + Duration::from_secs(1))
+            .is_ok());
+    }
+}
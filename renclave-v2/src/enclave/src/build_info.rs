@@ -0,0 +1,36 @@
+use renclave_shared::BuildInfo;
+
+/// Provenance of this binary, as recorded by `build.rs`
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        git_commit: env!("RENCLAVE_GIT_COMMIT").to_string(),
+        rustc_version: env!("RENCLAVE_RUSTC_VERSION").to_string(),
+        features: env!("RENCLAVE_FEATURES")
+            .split(',')
+            .filter(|feature| !feature.is_empty())
+            .map(str::to_string)
+            .collect(),
+        profile: if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        }
+        .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info() {
+        let info = build_info();
+        assert!(!info.git_commit.is_empty());
+        assert!(info.rustc_version.starts_with("rustc "));
+        assert_eq!(
+            info.features.contains(&"deterministic-test".to_string()),
+            cfg!(feature = "deterministic-test")
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use log::{debug, info};
+use renclave_shared::channel::{self, ChannelKeys, Direction};
+use renclave_shared::{ChannelError, SealedPayload, SecretBytes};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Environment variable for the lifetime of an encrypted channel session in seconds
+pub const CHANNEL_TTL_SECS_ENV: &str = "RENCLAVE_CHANNEL_TTL_SECS";
+
+const DEFAULT_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Open sessions beyond this evict the one closest to expiry
+const MAX_SESSIONS: usize = 256;
+
+struct Session {
+    keys: ChannelKeys,
+    last_counter: Option<u64>,
+    expires_at: u64,
+}
+
+/// A session created by `ChannelSessions::open`
+pub struct OpenedChannel {
+    pub session_id: String,
+    pub client_public: PublicKey,
+    pub enclave_public: PublicKey,
+    pub expires_at: u64,
+}
+
+/// Encrypted channel sessions with clients.
+///
+/// Session keys live only in enclave memory, so every session ends when the enclave restarts.
+pub struct ChannelSessions {
+    ttl: Duration,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl ChannelSessions {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sessions with the lifetime from the environment
+    pub fn from_env() -> Self {
+        let ttl = std::env::var(CHANNEL_TTL_SECS_ENV)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+        Self::new(ttl)
+    }
+
+    /// Complete a handshake with the client's public key and our fresh ephemeral secret
+    pub fn open(
+        &self,
+        client_public_key: &str,
+        enclave_secret: SecretKey,
+    ) -> Result<OpenedChannel, ChannelError> {
+        let client_public = channel::parse_public_key(client_public_key)?;
+        let enclave_public = enclave_secret.public_key(&Secp256k1::new());
+
+        let keys = ChannelKeys::derive(
+            &enclave_secret,
+            &client_public,
+            &client_public,
+            &enclave_public,
+        );
+        let session_id = Uuid::new_v4().to_string();
+        let now = now_secs();
+        let expires_at = now + self.ttl.as_secs();
+
+        let mut sessions = self.lock();
+        sessions.retain(|_, session| session.expires_at > now);
+        if sessions.len() >= MAX_SESSIONS {
+            if let Some(oldest) = sessions
+                .iter()
+                .min_by_key(|(_, session)| session.expires_at)
+                .map(|(id, _)| id.clone())
+            {
+                debug!("🔐 Evicting channel session {}", oldest);
+                sessions.remove(&oldest);
+            }
+        }
+        sessions.insert(
+            session_id.clone(),
+            Session {
+                keys,
+                last_counter: None,
+                expires_at,
+            },
+        );
+
+        info!(
+            "🔐 Opened encrypted channel {} ({} active)",
+            session_id,
+            sessions.len()
+        );
+        Ok(OpenedChannel {
+            session_id,
+            client_public,
+            enclave_public,
+            expires_at,
+        })
+    }
+
+    /// Decrypt a client request, rejecting replayed or out-of-order counters
+    pub fn unseal(
+        &self,
+        session_id: &str,
+        payload: &SealedPayload,
+    ) -> Result<SecretBytes, ChannelError> {
+        let mut sessions = self.lock();
+        let session = live_session(&mut sessions, session_id)?;
+
+        if session
+            .last_counter
+            .is_some_and(|last| payload.counter <= last)
+        {
+            return Err(ChannelError::Replay {
+                counter: payload.counter,
+            });
+        }
+
+        let plaintext = session
+            .keys
+            .open(Direction::ClientToEnclave, session_id, payload)?;
+        session.last_counter = Some(payload.counter);
+        Ok(plaintext)
+    }
+
+    /// Check that a client request would be accepted by `unseal`, without decrypting it or
+    /// using up its counter
+    pub fn verify(&self, session_id: &str, payload: &SealedPayload) -> Result<(), ChannelError> {
+        let mut sessions = self.lock();
+        let session = live_session(&mut sessions, session_id)?;
+
+        if session
+            .last_counter
+            .is_some_and(|last| payload.counter <= last)
+        {
+            return Err(ChannelError::Replay {
+                counter: payload.counter,
+            });
+        }
+        session
+            .keys
+            .verify(Direction::ClientToEnclave, session_id, payload)
+    }
+
+    /// Encrypt the response to the request with `counter`
+    pub fn seal(
+        &self,
+        session_id: &str,
+        counter: u64,
+        plaintext: &[u8],
+    ) -> Result<SealedPayload, ChannelError> {
+        let mut sessions = self.lock();
+        let session = live_session(&mut sessions, session_id)?;
+        Ok(session
+            .keys
+            .seal(Direction::EnclaveToClient, session_id, counter, plaintext))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Session>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn live_session<'a>(
+    sessions: &'a mut HashMap<String, Session>,
+    session_id: &str,
+) -> Result<&'a mut Session, ChannelError> {
+    if sessions
+        .get(session_id)
+        .is_some_and(|session| session.expires_at <= now_secs())
+    {
+        sessions.remove(session_id);
+    }
+    sessions
+        .get_mut(session_id)
+        .ok_or(ChannelError::UnknownSession)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> (SecretKey, PublicKey) {
+        let secret = SecretKey::from_slice(&[7; 32]).unwrap();
+        (secret, secret.public_key(&Secp256k1::new()))
+    }
+
+    fn open(sessions: &ChannelSessions) -> (OpenedChannel, ChannelKeys) {
+        let (client_secret, client_public) = client();
+        let enclave_secret = SecretKey::from_slice(&[9; 32]).unwrap();
+
+        let opened = sessions
+            .open(&hex::encode(client_public.serialize()), enclave_secret)
+            .unwrap();
+        let keys = ChannelKeys::derive(
+            &client_secret,
+            &opened.enclave_public,
+            &client_public,
+            &opened.enclave_public,
+        );
+        (opened, keys)
+    }
+
+    #[test]
+    fn test_request_and_response() {
+        let sessions = ChannelSessions::new(DEFAULT_TTL);
+        let (opened, client_keys) = open(&sessions);
+        let id = opened.session_id.as_str();
+
+        let request = client_keys.seal(Direction::ClientToEnclave, id, 1, b"GetInfo");
+        assert_eq!(
+            sessions.unseal(id, &request).unwrap().expose_secret(),
+            b"GetInfo"
+        );
+
+        let response = sessions.seal(id, 1, b"Info").unwrap();
+        let opened = client_keys
+            .open(Direction::EnclaveToClient, id, &response)
+            .unwrap();
+        assert_eq!(opened.expose_secret(), b"Info");
+    }
+
+    #[test]
+    fn test_replayed_counter_is_rejected() {
+        let sessions = ChannelSessions::new(DEFAULT_TTL);
+        let (opened, client_keys) = open(&sessions);
+        let id = opened.session_id.as_str();
+
+        let first = client_keys.seal(Direction::ClientToEnclave, id, 5, b"one");
+        sessions.unseal(id, &first).unwrap();
+
+        let earlier = client_keys.seal(Direction::ClientToEnclave, id, 4, b"two");
+        for payload in [&first, &earlier] {
+            assert_eq!(
+                sessions.unseal(id, payload),
+                Err(ChannelError::Replay {
+                    counter: payload.counter
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_does_not_consume_counter() {
+        let sessions = ChannelSessions::new(DEFAULT_TTL);
+        let (opened, client_keys) = open(&sessions);
+        let id = opened.session_id.as_str();
+
+        let request = client_keys.seal(Direction::ClientToEnclave, id, 1, b"GetInfo");
+        sessions.verify(id, &request).unwrap();
+        sessions.verify(id, &request).unwrap();
+
+        let mut tampered = request.clone();
+        tampered.counter = 2;
+        assert_eq!(
+            sessions.verify(id, &tampered),
+            Err(ChannelError::Authentication)
+        );
+
+        sessions.unseal(id, &request).unwrap();
+        assert_eq!(
+            sessions.verify(id, &request),
+            Err(ChannelError::Replay { counter: 1 })
+        );
+    }
+
+    #[test]
+    fn test_expired_and_unknown_sessions() {
+        let sessions = ChannelSessions::new(Duration::ZERO);
+        let (opened, client_keys) = open(&sessions);
+        let id = opened.session_id.as_str();
+
+        let request = client_keys.seal(Direction::ClientToEnclave, id, 1, b"late");
+        assert_eq!(
+            sessions.unseal(id, &request),
+            Err(ChannelError::UnknownSession)
+        );
+        assert_eq!(
+            sessions.unseal("missing", &request),
+            Err(ChannelError::UnknownSession)
+        );
+    }
+
+    #[test]
+    fn test_invalid_client_key() {
+        let sessions = ChannelSessions::new(DEFAULT_TTL);
+        let result = sessions.open("02abcd", SecretKey::from_slice(&[9; 32]).unwrap());
+        assert!(matches!(result, Err(ChannelError::InvalidPublicKey(_))));
+    }
+}
@@ -0,0 +1,415 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+use rand::RngCore;
+use renclave_shared::{EnclaveTime, TimeSource};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+
+/// Environment variable for the comma-separated NTP servers the clock syncs with, as `host` or
+/// `host:port`; unset leaves the clock to host-provided time
+pub const NTP_SERVERS_ENV: &str = "RENCLAVE_NTP_SERVERS";
+/// Environment variable for the seconds between NTP syncs
+pub const NTP_SYNC_SECS_ENV: &str = "RENCLAVE_NTP_SYNC_SECS";
+/// Environment variable for the largest drift of the guest clock, in parts per million
+pub const MAX_DRIFT_PPM_ENV: &str = "RENCLAVE_CLOCK_MAX_DRIFT_PPM";
+/// Environment variable for the error assumed for time offered by the host, in milliseconds
+pub const HOST_TIME_UNCERTAINTY_MS_ENV: &str = "RENCLAVE_HOST_TIME_UNCERTAINTY_MS";
+
+const NTP_PORT: u16 = 123;
+const NTP_TIMEOUT: Duration = Duration::from_secs(3);
+/// Seconds from the NTP era (1900) to the Unix epoch
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Synchronization settings of the enclave's clock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockConfig {
+    pub ntp_servers: Vec<String>,
+    pub ntp_interval: Duration,
+    /// Bound on how fast the guest clock drifts; the uncertainty grows at this rate
+    pub max_drift_ppm: u64,
+    /// Error assumed for host-provided time, covering the host's own clock and the socket hop
+    pub host_uncertainty_ms: u64,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            ntp_servers: Vec::new(),
+            ntp_interval: Duration::from_secs(300),
+            max_drift_ppm: 500,
+            host_uncertainty_ms: 1000,
+        }
+    }
+}
+
+impl ClockConfig {
+    /// Default settings with overrides from the environment
+    pub fn from_env() -> Self {
+        let env = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+
+        let mut config = Self::default();
+        if let Ok(servers) = std::env::var(NTP_SERVERS_ENV) {
+            config.ntp_servers = servers
+                .split(',')
+                .map(|server| server.trim().to_string())
+                .filter(|server| !server.is_empty())
+                .collect();
+        }
+        if let Some(secs) = env(NTP_SYNC_SECS_ENV).filter(|secs| *secs > 0) {
+            config.ntp_interval = Duration::from_secs(secs);
+        }
+        if let Some(ppm) = env(MAX_DRIFT_PPM_ENV) {
+            config.max_drift_ppm = ppm;
+        }
+        if let Some(ms) = env(HOST_TIME_UNCERTAINTY_MS_ENV) {
+            config.host_uncertainty_ms = ms;
+        }
+        config
+    }
+}
+
+/// Time as of the last accepted synchronization, advanced with the monotonic clock
+#[derive(Debug, Clone, Copy)]
+struct Anchor {
+    unix_ms: u64,
+    at: Instant,
+    uncertainty_ms: u64,
+    source: TimeSource,
+}
+
+/// The enclave's clock.
+///
+/// The guest clock drifts under QEMU and the host can set it, so timestamps come from the last
+/// synchronization advanced by the monotonic clock instead. Every reading carries a bound on
+/// its error that grows by `max_drift_ppm` until the next sync. A new time is only accepted
+/// if it lies within the current bound, so the host cannot move the clock further than the
+/// uncertainty the clock already admits to, and it replaces the reading only when it is more
+/// precise.
+pub struct TrustedClock {
+    config: ClockConfig,
+    anchor: Mutex<Option<Anchor>>,
+}
+
+impl TrustedClock {
+    pub fn new(config: ClockConfig) -> Self {
+        Self {
+            config,
+            anchor: Mutex::new(None),
+        }
+    }
+
+    /// Current time with its uncertainty
+    pub fn now(&self) -> EnclaveTime {
+        self.read(Instant::now())
+    }
+
+    fn read(&self, now: Instant) -> EnclaveTime {
+        let Some(anchor) = *self.anchor.lock().unwrap() else {
+            let unix_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            return EnclaveTime {
+                unix_ms,
+                uncertainty_ms: None,
+                source: TimeSource::Guest,
+                synced_at_ms: None,
+            };
+        };
+
+        let elapsed_ms = now.saturating_duration_since(anchor.at).as_millis() as u64;
+        let drift_ms = (elapsed_ms * self.config.max_drift_ppm).div_ceil(1_000_000);
+        EnclaveTime {
+            unix_ms: anchor.unix_ms + elapsed_ms,
+            uncertainty_ms: Some(anchor.uncertainty_ms + drift_ms),
+            source: anchor.source,
+            synced_at_ms: Some(anchor.unix_ms),
+        }
+    }
+
+    /// Offer the host's time. Returns whether it was consistent with the clock.
+    pub fn sync_host(&self, unix_ms: u64) -> bool {
+        self.offer(
+            unix_ms,
+            self.config.host_uncertainty_ms,
+            TimeSource::Host,
+            Instant::now(),
+        )
+    }
+
+    /// Take `unix_ms ± uncertainty_ms` as the time at `now` if it is consistent with the
+    /// current reading and at least as precise
+    fn offer(&self, unix_ms: u64, uncertainty_ms: u64, source: TimeSource, now: Instant) -> bool {
+        let current = self.read(now);
+        let mut anchor = self.anchor.lock().unwrap();
+        if let Some(current_uncertainty) = current.uncertainty_ms {
+            let offset = unix_ms.abs_diff(current.unix_ms);
+            if offset > current_uncertainty + uncertainty_ms {
+                warn!(
+                    "⚠️  Rejecting {:?} time: {} ms from the clock, outside its ±{} ms bound",
+                    source, offset, current_uncertainty
+                );
+                return false;
+            }
+            if uncertainty_ms > current_uncertainty {
+                return true;
+            }
+        }
+
+        if anchor.is_none() {
+            info!(
+                "🕒 Clock synchronized from {:?}, ±{} ms",
+                source, uncertainty_ms
+            );
+        }
+        *anchor = Some(Anchor {
+            unix_ms,
+            at: now,
+            uncertainty_ms,
+            source,
+        });
+        true
+    }
+
+    /// Sync with the first configured NTP server that answers
+    pub async fn sync_ntp(&self) -> Result<()> {
+        let mut last_error = anyhow!("No NTP servers configured");
+        for server in &self.config.ntp_servers {
+            match query_ntp(server).await {
+                Ok((unix_ms, uncertainty_ms, at)) => {
+                    debug!(
+                        "🕒 NTP time from {}: {} ms, ±{} ms",
+                        server, unix_ms, uncertainty_ms
+                    );
+                    if self.offer(unix_ms, uncertainty_ms, TimeSource::Ntp, at) {
+                        return Ok(());
+                    }
+                    last_error = anyhow!("{} disagrees with the clock", server);
+                }
+                Err(e) => last_error = e.context(format!("NTP query to {} failed", server)),
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Sync with NTP now and every `ntp_interval`, if servers are configured
+    pub fn spawn_ntp_sync(self: &Arc<Self>) {
+        if self.config.ntp_servers.is_empty() {
+            return;
+        }
+        info!(
+            "🕒 Syncing the clock with {} every {:?}",
+            self.config.ntp_servers.join(", "),
+            self.config.ntp_interval
+        );
+
+        let clock = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(clock.config.ntp_interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = clock.sync_ntp().await {
+                    warn!("⚠️  NTP sync failed: {:#}", e);
+                }
+            }
+        });
+    }
+}
+
+/// Ask `server` for the time, returning Unix milliseconds, their uncertainty and the instant
+/// they apply to
+async fn query_ntp(server: &str) -> Result<(u64, u64, Instant)> {
+    let address = match server.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, NTP_PORT).to_string(),
+        Err(_) if server.contains(':') => server.to_string(),
+        Err(_) => format!("{}:{}", server, NTP_PORT),
+    };
+    let target = tokio::net::lookup_host(&address)
+        .await
+        .with_context(|| format!("Failed to resolve {}", address))?
+        .next()
+        .ok_or_else(|| anyhow!("{} has no addresses", address))?;
+    let bind: SocketAddr = if target.is_ipv4() {
+        "0.0.0.0:0".parse()?
+    } else {
+        "[::]:0".parse()?
+    };
+    let socket = UdpSocket::bind(bind).await?;
+    socket.connect(target).await?;
+
+    // The server echoes our transmit timestamp, so random bytes there tie the reply to us
+    let mut origin = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut origin);
+    let mut request = [0u8; 48];
+    request[0] = 0x23; // LI 0, version 4, client mode
+    request[40..48].copy_from_slice(&origin);
+
+    let sent = Instant::now();
+    socket.send(&request).await?;
+    let mut reply = [0u8; 128];
+    let len = tokio::time::timeout(NTP_TIMEOUT, socket.recv(&mut reply))
+        .await
+        .map_err(|_| anyhow!("No reply within {:?}", NTP_TIMEOUT))??;
+    let received = Instant::now();
+
+    let (unix_ms, uncertainty_ms) = parse_ntp_reply(&reply[..len], &origin, received - sent)?;
+    Ok((unix_ms, uncertainty_ms, received))
+}
+
+/// Time at receipt of an SNTP reply and its uncertainty: half the network round trip plus
+/// the server's own error bound (half its root delay plus its root dispersion)
+fn parse_ntp_reply(reply: &[u8], origin: &[u8; 8], round_trip: Duration) -> Result<(u64, u64)> {
+    if reply.len() < 48 {
+        return Err(anyhow!("Reply of {} bytes is too short", reply.len()));
+    }
+    let leap = reply[0] >> 6;
+    let mode = reply[0] & 0x07;
+    let stratum = reply[1];
+    if mode != 4 {
+        return Err(anyhow!("Reply is not in server mode"));
+    }
+    if leap == 3 || !(1..=15).contains(&stratum) {
+        return Err(anyhow!("Server is not synchronized"));
+    }
+    if reply[24..32] != origin[..] {
+        return Err(anyhow!("Reply does not answer this request"));
+    }
+
+    let short = |at: usize| u32::from_be_bytes(reply[at..at + 4].try_into().unwrap()) as u64;
+    // NTP short format: 16.16 fixed point seconds
+    let short_ms = |at: usize| (short(at) * 1000).div_ceil(1 << 16);
+    let timestamp_ms = |at: usize| {
+        let secs = short(at);
+        let fraction = short(at + 4);
+        secs.checked_sub(NTP_UNIX_OFFSET)
+            .map(|secs| secs * 1000 + ((fraction * 1000) >> 32))
+    };
+    let received = timestamp_ms(32).ok_or_else(|| anyhow!("Receive time predates 1970"))?;
+    let transmitted = timestamp_ms(40).ok_or_else(|| anyhow!("Transmit time predates 1970"))?;
+
+    // Time the request spent in the server does not count towards the network round trip
+    let network_ms =
+        (round_trip.as_millis() as u64).saturating_sub(transmitted.saturating_sub(received));
+    let one_way_ms = network_ms.div_ceil(2);
+    let uncertainty_ms = one_way_ms + short_ms(4).div_ceil(2) + short_ms(8) + 1;
+    Ok((transmitted + one_way_ms, uncertainty_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock() -> TrustedClock {
+        TrustedClock::new(ClockConfig {
+            max_drift_ppm: 1000,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_unsynchronized_clock_has_no_bound() {
+        let time = clock().now();
+        assert_eq!(time.source, TimeSource::Guest);
+        assert_eq!(time.uncertainty_ms, None);
+    }
+
+    #[test]
+    fn test_uncertainty_grows_with_drift() {
+        let clock = clock();
+        let start = Instant::now();
+        assert!(clock.offer(1_000_000, 50, TimeSource::Ntp, start));
+
+        let later = clock.read(start + Duration::from_secs(10));
+        assert_eq!(later.unix_ms, 1_010_000);
+        assert_eq!(later.uncertainty_ms, Some(60));
+        assert_eq!(later.source, TimeSource::Ntp);
+        assert_eq!(later.synced_at_ms, Some(1_000_000));
+    }
+
+    #[test]
+    fn test_rejects_times_outside_the_bound() {
+        let clock = clock();
+        let start = Instant::now();
+        assert!(clock.offer(1_000_000, 50, TimeSource::Ntp, start));
+
+        // ±50 ms around the clock plus ±1000 ms around the offer
+        assert!(!clock.offer(1_001_100, 1000, TimeSource::Host, start));
+        assert_eq!(clock.read(start).unix_ms, 1_000_000);
+
+        // Consistent but less precise, so the NTP reading stays
+        assert!(clock.offer(1_000_900, 1000, TimeSource::Host, start));
+        assert_eq!(clock.read(start).source, TimeSource::Ntp);
+
+        // Once drift has widened the bound past the host's, host time takes over
+        let later = start + Duration::from_secs(1000);
+        assert!(clock.offer(2_000_500, 1000, TimeSource::Host, later));
+        let time = clock.read(later);
+        assert_eq!(time.source, TimeSource::Host);
+        assert_eq!(time.unix_ms, 2_000_500);
+    }
+
+    fn ntp_reply(origin: &[u8; 8], received: (u32, u32), transmitted: (u32, u32)) -> Vec<u8> {
+        let mut reply = vec![0u8; 48];
+        reply[0] = 0x24; // LI 0, version 4, server mode
+        reply[1] = 2;
+        reply[4..8].copy_from_slice(&(1u32 << 12).to_be_bytes()); // root delay 62.5 ms
+        reply[8..12].copy_from_slice(&(1u32 << 10).to_be_bytes()); // root dispersion 15.6 ms
+        reply[24..32].copy_from_slice(origin);
+        reply[32..36].copy_from_slice(&received.0.to_be_bytes());
+        reply[36..40].copy_from_slice(&received.1.to_be_bytes());
+        reply[40..44].copy_from_slice(&transmitted.0.to_be_bytes());
+        reply[44..48].copy_from_slice(&transmitted.1.to_be_bytes());
+        reply
+    }
+
+    #[test]
+    fn test_parse_ntp_reply() {
+        let origin = [7u8; 8];
+        let secs = (NTP_UNIX_OFFSET + 1_700_000_000) as u32;
+        // Received at .000, sent back at .250
+        let reply = ntp_reply(&origin, (secs, 0), (secs, 1 << 30));
+
+        let (unix_ms, uncertainty_ms) =
+            parse_ntp_reply(&reply, &origin, Duration::from_millis(290)).unwrap();
+        // 40 ms on the network, half of it after the server answered
+        assert_eq!(unix_ms, 1_700_000_000_270);
+        // 20 ms one way, 32 ms half root delay, 16 ms root dispersion, 1 ms rounding
+        assert_eq!(uncertainty_ms, 69);
+
+        assert!(parse_ntp_reply(&reply, &[0u8; 8], Duration::ZERO).is_err());
+        let mut unsynchronized = reply.clone();
+        unsynchronized[1] = 0;
+        assert!(parse_ntp_reply(&unsynchronized, &origin, Duration::ZERO).is_err());
+        assert!(parse_ntp_reply(&reply[..40], &origin, Duration::ZERO).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sync_ntp_against_local_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let mut request = [0u8; 48];
+            let (_, peer) = server.recv_from(&mut request).await.unwrap();
+            let origin: [u8; 8] = request[40..48].try_into().unwrap();
+            let secs = (NTP_UNIX_OFFSET + 1_700_000_000) as u32;
+            let reply = ntp_reply(&origin, (secs, 0), (secs, 0));
+            server.send_to(&reply, peer).await.unwrap();
+        });
+
+        let clock = TrustedClock::new(ClockConfig {
+            ntp_servers: vec![address],
+            ..Default::default()
+        });
+        clock.sync_ntp().await.unwrap();
+        let time = clock.now();
+        assert_eq!(time.source, TimeSource::Ntp);
+        assert!(time.unix_ms >= 1_700_000_000_000);
+        assert!(time.uncertainty_ms.unwrap() < 1000);
+    }
+}
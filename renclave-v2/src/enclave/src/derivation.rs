@@ -0,0 +1,330 @@
+use bitcoin::bip32::{ChildNumber, DerivationPath};
+use renclave_shared::validation::{self, ChildIndex};
+use renclave_shared::FieldError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Environment variable requiring every path component to be hardened ("true" or "1")
+pub const HARDENED_ONLY_ENV: &str = "RENCLAVE_DERIVATION_HARDENED_ONLY";
+/// Environment variable with a comma-separated list of allowed path templates
+pub const PATH_TEMPLATES_ENV: &str = "RENCLAVE_DERIVATION_PATH_TEMPLATES";
+
+/// BIP-43 purposes that follow the BIP-44 `purpose'/coin_type'/account'/change/index` layout
+const BIP44_PURPOSES: &[u32] = &[44, 49, 84, 86];
+
+/// Reasons a derivation path is rejected
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// Not a syntactically valid path, as reported by `renclave_shared::validation`
+    #[error("{0}")]
+    Syntax(String),
+
+    #[error("BIP-44 path level {position} must be hardened")]
+    Bip44NotHardened { position: usize },
+
+    #[error("BIP-44 path has {0} levels, maximum is 5")]
+    Bip44TooDeep(usize),
+
+    #[error("Non-hardened index at position {0} is not allowed by policy")]
+    HardenedRequired(usize),
+
+    #[error("ed25519 derivation only supports hardened indices, position {0} is not hardened")]
+    Ed25519RequiresHardened(usize),
+
+    #[error("Derivation path {0} does not match any allowed template")]
+    NotAllowed(String),
+
+    #[error("Invalid path template '{template}': {reason}")]
+    InvalidTemplate { template: String, reason: String },
+}
+
+impl From<FieldError> for PathError {
+    fn from(error: FieldError) -> Self {
+        PathError::Syntax(error.message)
+    }
+}
+
+/// Parse and validate a BIP-32 path such as `m/44'/60'/0'/0/0`.
+///
+/// The syntax is that of `validation::DerivationPath`. Paths whose purpose is a BIP-44
+/// family purpose (44', 49', 84', 86') must harden purpose, coin type and account and may
+/// not be deeper than five levels.
+pub fn parse_path(path: &str) -> Result<DerivationPath, PathError> {
+    let parsed: validation::DerivationPath = path.parse()?;
+    let children: Vec<ChildNumber> = parsed
+        .children()
+        .iter()
+        .copied()
+        .map(child_number)
+        .collect();
+
+    if let Some(ChildNumber::Hardened { index }) = children.first() {
+        if BIP44_PURPOSES.contains(index) {
+            validate_bip44(&children)?;
+        }
+    }
+
+    Ok(DerivationPath::from(children))
+}
+
+fn child_number(child: ChildIndex) -> ChildNumber {
+    let index = child.index;
+    if child.hardened {
+        ChildNumber::Hardened { index }
+    } else {
+        ChildNumber::Normal { index }
+    }
+}
+
+fn validate_bip44(children: &[ChildNumber]) -> Result<(), PathError> {
+    if children.len() > 5 {
+        return Err(PathError::Bip44TooDeep(children.len()));
+    }
+
+    // purpose', coin_type', account'
+    for (i, child) in children.iter().enumerate().take(3) {
+        if child.is_normal() {
+            return Err(PathError::Bip44NotHardened { position: i + 1 });
+        }
+    }
+
+    Ok(())
+}
+
+/// One component of a path template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TemplateComponent {
+    /// Exactly this child
+    Exact(ChildNumber),
+    /// Any index with the given hardening (`*` or `*'`)
+    Any { hardened: bool },
+}
+
+/// Allowed path shape, e.g. `m/44'/60'/*'/0/*`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    components: Vec<TemplateComponent>,
+}
+
+impl PathTemplate {
+    /// Whether `path` has the same depth and every component matches
+    pub fn matches(&self, path: &DerivationPath) -> bool {
+        let children: &[ChildNumber] = path.as_ref();
+
+        children.len() == self.components.len()
+            && self
+                .components
+                .iter()
+                .zip(children)
+                .all(|(template, child)| match template {
+                    TemplateComponent::Exact(expected) => expected == child,
+                    TemplateComponent::Any { hardened } => child.is_hardened() == *hardened,
+                })
+    }
+}
+
+impl FromStr for PathTemplate {
+    type Err = PathError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| PathError::InvalidTemplate {
+            template: template.to_string(),
+            reason,
+        };
+
+        let components = validation::DerivationPath::components(template)
+            .map_err(|e| invalid(e.message))?
+            .into_iter()
+            .enumerate()
+            .map(
+                |(i, component)| match ChildIndex::split_hardened(component) {
+                    ("*", hardened) => Ok(TemplateComponent::Any { hardened }),
+                    _ => ChildIndex::parse(component, i + 1)
+                        .map(|child| TemplateComponent::Exact(child_number(child))),
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| invalid(e.message))?;
+
+        Ok(Self { components })
+    }
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("m")?;
+        for component in &self.components {
+            match component {
+                TemplateComponent::Exact(ChildNumber::Hardened { index }) => {
+                    write!(f, "/{}'", index)?
+                }
+                TemplateComponent::Exact(ChildNumber::Normal { index }) => write!(f, "/{}", index)?,
+                TemplateComponent::Any { hardened: true } => f.write_str("/*'")?,
+                TemplateComponent::Any { hardened: false } => f.write_str("/*")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Restrictions applied to every derivation request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DerivationPolicy {
+    /// Reject any non-hardened component
+    pub hardened_only: bool,
+    /// Allowed path templates; empty allows any valid path
+    pub templates: Vec<PathTemplate>,
+}
+
+impl DerivationPolicy {
+    /// Policy from the environment; unset variables leave the default (allow any valid path)
+    pub fn from_env() -> Result<Self, PathError> {
+        let hardened_only = std::env::var(HARDENED_ONLY_ENV)
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+            .unwrap_or(false);
+
+        let templates = match std::env::var(PATH_TEMPLATES_ENV) {
+            Ok(value) => Self::parse_templates(&value)?,
+            Err(_) => Vec::new(),
+        };
+
+        Ok(Self {
+            hardened_only,
+            templates,
+        })
+    }
+
+    /// Parse a comma-separated template list
+    pub fn parse_templates(value: &str) -> Result<Vec<PathTemplate>, PathError> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(PathTemplate::from_str)
+            .collect()
+    }
+
+    /// Parse `path` and check it against this policy
+    pub fn validate(&self, path: &str) -> Result<DerivationPath, PathError> {
+        let derivation_path = parse_path(path)?;
+        self.check(&derivation_path)?;
+        Ok(derivation_path)
+    }
+
+    /// Check an already parsed path, e.g. one taken from a PSBT, against this policy
+    pub fn check(&self, derivation_path: &DerivationPath) -> Result<(), PathError> {
+        if self.hardened_only {
+            if let Some(position) = derivation_path.as_ref().iter().position(|c| c.is_normal()) {
+                return Err(PathError::HardenedRequired(position + 1));
+            }
+        }
+
+        if !self.templates.is_empty() && !self.templates.iter().any(|t| t.matches(derivation_path))
+        {
+            return Err(PathError::NotAllowed(derivation_path.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accepts_hardened_notations() {
+        let apostrophe = parse_path("m/44'/60'/0'/0/0").unwrap();
+        let letter = parse_path("m/44h/60H/0'/0/0").unwrap();
+
+        assert_eq!(apostrophe, letter);
+        assert_eq!(apostrophe.to_string(), "44'/60'/0'/0/0");
+        assert_eq!(parse_path("m").unwrap(), DerivationPath::master());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_paths() {
+        let syntax = |message: &str| Err(PathError::Syntax(message.to_string()));
+        assert_eq!(parse_path(""), syntax("must not be empty"));
+        assert_eq!(
+            parse_path("44'/0'"),
+            syntax("Derivation path must start with 'm', got '44''")
+        );
+        assert_eq!(
+            parse_path("m/44'//0"),
+            syntax("Empty component at position 2")
+        );
+        assert_eq!(
+            parse_path("m/44'/-1"),
+            syntax("Invalid index '-1' at position 2")
+        );
+        assert_eq!(
+            parse_path("m/0/2147483648"),
+            syntax("Index 2147483648 at position 2 is out of range (must be below 2^31)")
+        );
+        assert_eq!(
+            parse_path(&format!("m{}", "/0".repeat(256))),
+            syntax("Derivation path is 256 levels deep, maximum is 255")
+        );
+    }
+
+    #[test]
+    fn test_bip44_layout_is_enforced() {
+        assert!(parse_path("m/44'/501'/0'/0'").is_ok());
+        assert_eq!(
+            parse_path("m/44'/60/0'/0/0"),
+            Err(PathError::Bip44NotHardened { position: 2 })
+        );
+        assert_eq!(
+            parse_path("m/84'/0'/0'/0/0/1"),
+            Err(PathError::Bip44TooDeep(6))
+        );
+        // Other purposes are not held to the BIP-44 layout
+        assert!(parse_path("m/0/1/2/3/4/5").is_ok());
+    }
+
+    #[test]
+    fn test_hardened_only_policy() {
+        let policy = DerivationPolicy {
+            hardened_only: true,
+            templates: Vec::new(),
+        };
+
+        assert!(policy.validate("m/44'/501'/0'/0'").is_ok());
+        assert_eq!(
+            policy.validate("m/44'/60'/0'/0/0"),
+            Err(PathError::HardenedRequired(4))
+        );
+    }
+
+    #[test]
+    fn test_template_whitelist() {
+        let policy = DerivationPolicy {
+            hardened_only: false,
+            templates: DerivationPolicy::parse_templates("m/44'/60'/*'/0/*, m/44'/501'/*'/0'")
+                .unwrap(),
+        };
+
+        assert!(policy.validate("m/44'/60'/3'/0/7").is_ok());
+        assert!(policy.validate("m/44'/501'/0'/0'").is_ok());
+        assert_eq!(
+            policy.validate("m/44'/0'/0'/0/0"),
+            Err(PathError::NotAllowed("44'/0'/0'/0/0".to_string()))
+        );
+        // Wildcards keep their hardening
+        assert!(policy.validate("m/44'/60'/3/0/7").is_err());
+        // Depth must match exactly
+        assert!(policy.validate("m/44'/60'/3'/0").is_err());
+    }
+
+    #[test]
+    fn test_template_round_trip_and_errors() {
+        let template: PathTemplate = "m/44'/60'/*'/0/*".parse().unwrap();
+        assert_eq!(template.to_string(), "m/44'/60'/*'/0/*");
+
+        assert!(matches!(
+            "m/44'/**".parse::<PathTemplate>(),
+            Err(PathError::InvalidTemplate { .. })
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use renclave_shared::OperationClass;
+
+/// Environment variables for the concurrent operations per class
+pub const CONTROL_CONCURRENCY_ENV: &str = "RENCLAVE_ENCLAVE_CONTROL_CONCURRENCY";
+pub const READ_CONCURRENCY_ENV: &str = "RENCLAVE_ENCLAVE_READ_CONCURRENCY";
+pub const SIGN_CONCURRENCY_ENV: &str = "RENCLAVE_ENCLAVE_SIGN_CONCURRENCY";
+pub const CEREMONY_CONCURRENCY_ENV: &str = "RENCLAVE_ENCLAVE_CEREMONY_CONCURRENCY";
+/// Environment variable for how many operations of a class may wait for a slot
+pub const QUEUE_DEPTH_ENV: &str = "RENCLAVE_ENCLAVE_QUEUE_DEPTH";
+
+/// Concurrency limits of the dispatch lanes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispatchLimits {
+    /// Operations of each class running at once, indexed like `OperationClass::ALL`
+    pub concurrency: [usize; 4],
+    /// Operations of a class waiting for a slot; further ones are shed
+    pub queue_depth: usize,
+}
+
+impl Default for DispatchLimits {
+    fn default() -> Self {
+        Self {
+            concurrency: [16, 16, 8, 2],
+            queue_depth: 16,
+        }
+    }
+}
+
+impl DispatchLimits {
+    /// Default limits with overrides from the environment
+    pub fn from_env() -> Self {
+        let env = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|v| *v > 0)
+        };
+
+        let mut limits = Self::default();
+        let names = [
+            CONTROL_CONCURRENCY_ENV,
+            READ_CONCURRENCY_ENV,
+            SIGN_CONCURRENCY_ENV,
+            CEREMONY_CONCURRENCY_ENV,
+        ];
+        for (slot, name) in limits.concurrency.iter_mut().zip(names) {
+            if let Some(concurrency) = env(name) {
+                *slot = concurrency;
+            }
+        }
+        // 0 is meaningful here: shed whenever the lane is busy
+        if let Some(depth) = std::env::var(QUEUE_DEPTH_ENV)
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+        {
+            limits.queue_depth = depth;
+        }
+        limits
+    }
+}
+
+/// The lane of `class` was full and its queue too
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Enclave overloaded: {} operations at capacity", class.as_str())]
+pub struct Overloaded {
+    pub class: OperationClass,
+}
+
+struct Lane {
+    slots: Arc<Semaphore>,
+    waiting: AtomicUsize,
+}
+
+/// Runs each operation class in its own lane so cheap calls never queue behind expensive ones.
+///
+/// A lane runs up to its concurrency limit at once and queues up to `queue_depth` more.
+/// Beyond that, operations are shed immediately instead of piling up behind a burst.
+pub struct Dispatcher {
+    lanes: [Lane; 4],
+    queue_depth: usize,
+}
+
+impl Dispatcher {
+    pub fn new(limits: &DispatchLimits) -> Self {
+        Self {
+            lanes: limits.concurrency.map(|concurrency| Lane {
+                slots: Arc::new(Semaphore::new(concurrency.max(1))),
+                waiting: AtomicUsize::new(0),
+            }),
+            queue_depth: limits.queue_depth,
+        }
+    }
+
+    /// Wait for a slot in the lane of `class`; the operation runs while the permit is held
+    pub async fn acquire(&self, class: OperationClass) -> Result<OwnedSemaphorePermit, Overloaded> {
+        let lane = &self.lanes[class as usize];
+        if let Ok(permit) = Arc::clone(&lane.slots).try_acquire_owned() {
+            return Ok(permit);
+        }
+
+        // Counted until the wait ends, even if the caller gives up
+        let (_waiting, ahead) = Waiting::enter(&lane.waiting);
+        if ahead >= self.queue_depth {
+            return Err(Overloaded { class });
+        }
+
+        // The semaphore is never closed
+        Arc::clone(&lane.slots)
+            .acquire_owned()
+            .await
+            .map_err(|_| Overloaded { class })
+    }
+}
+
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl<'a> Waiting<'a> {
+    /// Count a waiter, returning the number already waiting
+    fn enter(counter: &'a AtomicUsize) -> (Self, usize) {
+        let ahead = counter.fetch_add(1, Ordering::SeqCst);
+        (Self(counter), ahead)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dispatcher(queue_depth: usize) -> Dispatcher {
+        Dispatcher::new(&DispatchLimits {
+            concurrency: [1, 1, 1, 1],
+            queue_depth,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_busy_ceremony_lane_does_not_block_control() {
+        let dispatcher = dispatcher(0);
+        let _ceremony = dispatcher.acquire(OperationClass::Ceremony).await.unwrap();
+
+        assert!(dispatcher.acquire(OperationClass::Control).await.is_ok());
+        assert_eq!(
+            dispatcher
+                .acquire(OperationClass::Ceremony)
+                .await
+                .unwrap_err(),
+            Overloaded {
+                class: OperationClass::Ceremony
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_queued_operation_runs_when_slot_frees() {
+        let dispatcher = Arc::new(dispatcher(1));
+        let running = dispatcher.acquire(OperationClass::Sign).await.unwrap();
+
+        let queued = tokio::spawn({
+            let dispatcher = Arc::clone(&dispatcher);
+            async move { dispatcher.acquire(OperationClass::Sign).await.is_ok() }
+        });
+        tokio::task::yield_now().await;
+
+        // The queue holds one, so a third is shed
+        assert!(dispatcher.acquire(OperationClass::Sign).await.is_err());
+
+        drop(running);
+        assert!(queued.await.unwrap());
+    }
+}
@@ -0,0 +1,474 @@
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use log::{debug, error, info, warn};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::sync::Mutex;
+use std::time::Instant;
+use zeroize::Zeroize;
+
+use crate::nitro::NitroSecureModule;
+
+/// Environment variable with the comma-separated entropy sources (`os`, `nsm`, `jitter`)
+pub const ENTROPY_SOURCES_ENV: &str = "RENCLAVE_ENTROPY_SOURCES";
+
+/// Capabilities that depend on a healthy entropy pool
+pub const ENTROPY_CAPABILITIES: &[&str] = &["seed_generation", "secure_entropy"];
+
+/// False positive rate of the health tests, as a negative power of two (SP 800-90B 4.4)
+const ALPHA_LOG2: f64 = 40.0;
+/// Adaptive proportion test window for non-binary samples
+const APT_WINDOW: usize = 512;
+/// Samples run through the health tests of every source at start-up (SP 800-90B 4.3)
+const STARTUP_SAMPLES: usize = 1024;
+/// Timing samples gathered from the jitter source per fill
+const JITTER_SAMPLES: usize = 1024;
+/// Bytes taken from each byte-oriented source per output block
+const BLOCK_BYTES: usize = 32;
+const DOMAIN: &[u8] = b"renclave-entropy-v1";
+
+/// Reasons the entropy pool cannot produce output
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum EntropyError {
+    #[error("Unknown entropy source '{0}'")]
+    UnknownSource(String),
+
+    #[error("No entropy sources configured")]
+    NoSources,
+
+    #[error("Entropy source {source_name} failed: {reason}")]
+    SourceFailed {
+        source_name: &'static str,
+        reason: String,
+    },
+
+    #[error("Entropy source {source_name} failed the {test} health test")]
+    HealthTestFailed {
+        source_name: &'static str,
+        test: &'static str,
+    },
+}
+
+type ReadFn = Box<dyn Fn(&mut [u8]) -> Result<(), String> + Send + Sync>;
+
+/// A raw entropy source with its assessed min-entropy per 8-bit sample
+pub struct EntropySource {
+    name: &'static str,
+    min_entropy_bits: f64,
+    read: ReadFn,
+}
+
+impl EntropySource {
+    pub fn new(
+        name: &'static str,
+        min_entropy_bits: f64,
+        read: impl Fn(&mut [u8]) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name,
+            min_entropy_bits,
+            read: Box::new(read),
+        }
+    }
+
+    /// Operating system CSPRNG (`getrandom`)
+    pub fn os() -> Self {
+        Self::new("os", 8.0, |buffer| {
+            OsRng.try_fill_bytes(buffer).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Nitro Secure Module random bytes
+    pub fn nsm() -> Self {
+        Self::new("nsm", 8.0, |buffer| {
+            let mut bytes =
+                NitroSecureModule::get_random(buffer.len()).map_err(|e| e.to_string())?;
+            buffer.copy_from_slice(&bytes);
+            bytes.zeroize();
+            Ok(())
+        })
+    }
+
+    /// Low byte of CPU execution-time jitter, credited conservatively at 0.25 bits per sample
+    pub fn jitter() -> Self {
+        Self::new("jitter", 0.25, |buffer| {
+            let mut state = 0u64;
+            for sample in buffer.iter_mut() {
+                let start = Instant::now();
+                for i in 0..64u64 {
+                    state = state.rotate_left(7) ^ i.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+                }
+                std::hint::black_box(state);
+                *sample = start.elapsed().as_nanos() as u8;
+            }
+            Ok(())
+        })
+    }
+
+    /// Seeded ChaCha20 stream for reproducible tests
+    #[cfg(feature = "deterministic-test")]
+    #[allow(dead_code)]
+    pub fn deterministic(seed: u64) -> Self {
+        use rand::SeedableRng;
+
+        let rng = Mutex::new(rand_chacha::ChaCha20Rng::seed_from_u64(seed));
+        Self::new("deterministic", 8.0, move |buffer| {
+            rng.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .fill_bytes(buffer);
+            Ok(())
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Repetition count and adaptive proportion tests (SP 800-90B 4.4.1, 4.4.2)
+struct HealthTests {
+    rct_cutoff: usize,
+    apt_cutoff: usize,
+    last_sample: Option<u8>,
+    repetitions: usize,
+    apt_reference: u8,
+    apt_matches: usize,
+    apt_seen: usize,
+}
+
+impl HealthTests {
+    fn new(min_entropy_bits: f64) -> Self {
+        Self {
+            rct_cutoff: repetition_count_cutoff(min_entropy_bits, ALPHA_LOG2),
+            apt_cutoff: adaptive_proportion_cutoff(APT_WINDOW, min_entropy_bits, ALPHA_LOG2),
+            last_sample: None,
+            repetitions: 0,
+            apt_reference: 0,
+            apt_matches: 0,
+            apt_seen: 0,
+        }
+    }
+
+    fn feed(&mut self, sample: u8) -> Result<(), &'static str> {
+        if self.last_sample == Some(sample) {
+            self.repetitions += 1;
+            if self.repetitions >= self.rct_cutoff {
+                return Err("repetition count");
+            }
+        } else {
+            self.last_sample = Some(sample);
+            self.repetitions = 1;
+        }
+
+        if self.apt_seen == 0 {
+            self.apt_reference = sample;
+            self.apt_matches = 1;
+        } else if sample == self.apt_reference {
+            self.apt_matches += 1;
+            if self.apt_matches >= self.apt_cutoff {
+                return Err("adaptive proportion");
+            }
+        }
+        self.apt_seen = (self.apt_seen + 1) % APT_WINDOW;
+        Ok(())
+    }
+}
+
+/// `C = 1 + ceil(-log2(alpha) / H)`
+fn repetition_count_cutoff(min_entropy_bits: f64, alpha_log2: f64) -> usize {
+    1 + (alpha_log2 / min_entropy_bits).ceil() as usize
+}
+
+/// `C = 1 + CRITBINOM(W, 2^-H, 1 - alpha)`: one more than the smallest count whose upper
+/// binomial tail is at most alpha
+fn adaptive_proportion_cutoff(window: usize, min_entropy_bits: f64, alpha_log2: f64) -> usize {
+    let p = 2f64.powf(-min_entropy_bits);
+    let alpha = 2f64.powf(-alpha_log2);
+
+    // Binomial pmf in log space, then tail sums from the top to avoid cancellation
+    let mut log_pmf = Vec::with_capacity(window + 1);
+    let mut log_binom = 0.0;
+    for k in 0..=window {
+        if k > 0 {
+            log_binom += ((window - k + 1) as f64).ln() - (k as f64).ln();
+        }
+        log_pmf.push(log_binom + k as f64 * p.ln() + (window - k) as f64 * (1.0 - p).ln());
+    }
+
+    let mut tail = 0.0;
+    for k in (0..=window).rev() {
+        tail += log_pmf[k].exp();
+        // P(X >= k) > alpha but P(X >= k + 1) <= alpha, so CRITBINOM is k
+        if tail > alpha {
+            return k + 1;
+        }
+    }
+    1
+}
+
+struct PoolState {
+    tests: Vec<HealthTests>,
+    failure: Option<EntropyError>,
+    counter: u64,
+}
+
+/// Mixes several entropy sources through SHA-256 and health-tests their raw output.
+///
+/// A health test failure latches: the pool refuses every later request until the enclave
+/// restarts.
+pub struct EntropyPool {
+    sources: Vec<EntropySource>,
+    state: Mutex<PoolState>,
+}
+
+impl EntropyPool {
+    /// Pool over the given sources, after running the start-up health tests
+    pub fn new(sources: Vec<EntropySource>) -> Result<Self, EntropyError> {
+        if sources.is_empty() {
+            return Err(EntropyError::NoSources);
+        }
+
+        let tests = sources
+            .iter()
+            .map(|s| HealthTests::new(s.min_entropy_bits))
+            .collect();
+        let pool = Self {
+            sources,
+            state: Mutex::new(PoolState {
+                tests,
+                failure: None,
+                counter: 0,
+            }),
+        };
+
+        let mut state = pool.lock();
+        for index in 0..pool.sources.len() {
+            let mut samples = vec![0u8; STARTUP_SAMPLES];
+            let result = pool.sample(&mut state, index, &mut samples);
+            samples.zeroize();
+            result?;
+        }
+        drop(state);
+
+        info!(
+            "🎲 Entropy pool ready with sources: {}",
+            pool.source_names().join(", ")
+        );
+        Ok(pool)
+    }
+
+    /// Pool over the sources named in the environment, by default all available sources.
+    ///
+    /// `nsm` is skipped when `/dev/nsm` is absent unless it is requested explicitly.
+    pub fn from_env() -> Result<Self, EntropyError> {
+        let nsm_available = std::path::Path::new("/dev/nsm").exists();
+
+        let sources = match std::env::var(ENTROPY_SOURCES_ENV) {
+            Ok(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| match name {
+                    "os" => Ok(EntropySource::os()),
+                    "nsm" => Ok(EntropySource::nsm()),
+                    "jitter" => Ok(EntropySource::jitter()),
+                    other => Err(EntropyError::UnknownSource(other.to_string())),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(_) => {
+                let mut sources = vec![EntropySource::os(), EntropySource::jitter()];
+                if nsm_available {
+                    sources.push(EntropySource::nsm());
+                } else {
+                    debug!("🎲 /dev/nsm not present, NSM entropy source disabled");
+                }
+                sources
+            }
+        };
+
+        Self::new(sources)
+    }
+
+    /// Pool over a single seeded source, producing the same output on every run
+    #[cfg(feature = "deterministic-test")]
+    #[allow(dead_code)]
+    pub fn deterministic(seed: u64) -> Self {
+        Self::new(vec![EntropySource::deterministic(seed)])
+            .expect("seeded ChaCha20 output passes the health tests")
+    }
+
+    pub fn source_names(&self) -> Vec<&'static str> {
+        self.sources.iter().map(EntropySource::name).collect()
+    }
+
+    /// The latched health test failure, if any
+    pub fn health(&self) -> Result<(), EntropyError> {
+        match &self.lock().failure {
+            Some(failure) => Err(failure.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Fill `output` with mixed entropy.
+    ///
+    /// Each 32-byte block is SHA-256 over a block counter, fresh output from every byte source
+    /// and a digest of the jitter samples.
+    pub fn fill(&self, output: &mut [u8]) -> Result<(), EntropyError> {
+        let mut state = self.lock();
+        if let Some(failure) = &state.failure {
+            return Err(failure.clone());
+        }
+
+        let mut samples = vec![0u8; BLOCK_BYTES.max(JITTER_SAMPLES)];
+        let result = self.fill_blocks(&mut state, output, &mut samples);
+        samples.zeroize();
+        result
+    }
+
+    fn fill_blocks(
+        &self,
+        state: &mut PoolState,
+        output: &mut [u8],
+        samples: &mut [u8],
+    ) -> Result<(), EntropyError> {
+        for block in output.chunks_mut(32) {
+            let mut engine = sha256::Hash::engine();
+            engine.input(DOMAIN);
+            engine.input(&state.counter.to_le_bytes());
+            state.counter += 1;
+
+            for index in 0..self.sources.len() {
+                let len = if self.sources[index].name == "jitter" {
+                    JITTER_SAMPLES
+                } else {
+                    BLOCK_BYTES
+                };
+                self.sample(state, index, &mut samples[..len])?;
+                engine.input(&samples[..len]);
+            }
+
+            let digest = sha256::Hash::from_engine(engine);
+            block.copy_from_slice(&digest.as_byte_array()[..block.len()]);
+        }
+        Ok(())
+    }
+
+    /// Read raw samples from one source through its health tests, latching any failure
+    fn sample(
+        &self,
+        state: &mut PoolState,
+        index: usize,
+        samples: &mut [u8],
+    ) -> Result<(), EntropyError> {
+        let source = &self.sources[index];
+
+        let result = (source.read)(samples)
+            .map_err(|reason| EntropyError::SourceFailed {
+                source_name: source.name,
+                reason,
+            })
+            .and_then(|()| {
+                let tests = &mut state.tests[index];
+                samples.iter().try_for_each(|&sample| {
+                    tests
+                        .feed(sample)
+                        .map_err(|test| EntropyError::HealthTestFailed {
+                            source_name: source.name,
+                            test,
+                        })
+                })
+            });
+
+        if let Err(failure) = &result {
+            error!("🚨 Entropy pool disabled: {}", failure);
+            state.failure = Some(failure.clone());
+        }
+        result
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|e| {
+            warn!("⚠️  Entropy pool lock was poisoned");
+            e.into_inner()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_cutoffs_match_sp800_90b() {
+        assert_eq!(repetition_count_cutoff(8.0, ALPHA_LOG2), 6);
+        assert_eq!(repetition_count_cutoff(1.0, 20.0), 21);
+
+        // SP 800-90B section 4.4.2 tables, alpha = 2^-20
+        assert_eq!(adaptive_proportion_cutoff(1024, 1.0, 20.0), 589);
+        assert_eq!(adaptive_proportion_cutoff(512, 1.0, 20.0), 311);
+        assert_eq!(adaptive_proportion_cutoff(512, 8.0, 20.0), 13);
+    }
+
+    #[test]
+    fn test_pool_mixes_sources() {
+        let pool = EntropyPool::new(vec![EntropySource::os(), EntropySource::jitter()]).unwrap();
+
+        let mut first = [0u8; 48];
+        let mut second = [0u8; 48];
+        pool.fill(&mut first).unwrap();
+        pool.fill(&mut second).unwrap();
+
+        assert_ne!(first, second);
+        assert!(pool.health().is_ok());
+        assert_eq!(pool.source_names(), vec!["os", "jitter"]);
+    }
+
+    #[test]
+    fn test_stuck_source_fails_startup() {
+        let stuck = EntropySource::new("stuck", 8.0, |buffer| {
+            buffer.fill(0x42);
+            Ok(())
+        });
+
+        assert!(matches!(
+            EntropyPool::new(vec![EntropySource::os(), stuck]),
+            Err(EntropyError::HealthTestFailed {
+                source_name: "stuck",
+                test: "repetition count"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_failure_latches() {
+        let broken = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&broken);
+        let source = EntropySource::new("flaky", 8.0, move |buffer| {
+            if flag.load(Ordering::Relaxed) {
+                buffer.fill(0);
+            } else {
+                OsRng.fill_bytes(buffer);
+            }
+            Ok(())
+        });
+        let pool = EntropyPool::new(vec![source]).unwrap();
+
+        broken.store(true, Ordering::Relaxed);
+        let mut output = [0u8; 32];
+        assert!(pool.fill(&mut output).is_err());
+
+        // Source recovers, but the pool stays disabled
+        broken.store(false, Ordering::Relaxed);
+        assert!(pool.fill(&mut output).is_err());
+        assert!(pool.health().is_err());
+    }
+
+    #[test]
+    fn test_biased_source_fails_adaptive_proportion() {
+        let mut tests = HealthTests::new(8.0);
+        let result =
+            (0..APT_WINDOW).try_for_each(|i| tests.feed(if i % 2 == 0 { 7 } else { i as u8 }));
+        assert_eq!(result, Err("adaptive proportion"));
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{bail, Result};
+use log::{info, warn};
+use renclave_shared::MemoryHardening;
+use std::sync::OnceLock;
+
+/// Environment variable that makes the enclave refuse to start without full memory hardening
+pub const REQUIRE_MEMORY_HARDENING_ENV: &str = "RENCLAVE_REQUIRE_MEMORY_HARDENING";
+
+const SWAPS_PATH: &str = "/proc/swaps";
+
+static HARDENING: OnceLock<MemoryHardening> = OnceLock::new();
+
+/// Whether startup must fail when a protection cannot be applied
+pub fn required_from_env() -> bool {
+    std::env::var(REQUIRE_MEMORY_HARDENING_ENV)
+        .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Keep secrets out of swap and core dumps, before any secret is created.
+///
+/// Checks that no swap is active, locks all current and future pages in RAM and disables core
+/// dumps. Under QEMU without privileges some steps fail; they are logged and reported in
+/// `GetInfo`, and only fail startup when `required` is set.
+pub fn harden(required: bool) -> Result<MemoryHardening> {
+    let hardening = MemoryHardening {
+        swap_disabled: swap_disabled(),
+        memory_locked: lock_all_memory(),
+        core_dumps_disabled: disable_core_dumps(),
+    };
+    let _ = HARDENING.set(hardening);
+
+    if hardening.is_complete() {
+        info!("🛡️  Memory hardened: no swap, all pages locked, core dumps disabled");
+    } else {
+        warn!("⚠️  Memory hardening incomplete: {:?}", hardening);
+        if required {
+            bail!(
+                "Memory hardening required by {} is incomplete: {:?}",
+                REQUIRE_MEMORY_HARDENING_ENV,
+                hardening
+            );
+        }
+    }
+    Ok(hardening)
+}
+
+/// Hardening applied at startup, `None` before `harden` ran
+pub fn status() -> Option<MemoryHardening> {
+    HARDENING.get().copied()
+}
+
+fn swap_disabled() -> bool {
+    match std::fs::read_to_string(SWAPS_PATH) {
+        Ok(swaps) => active_swaps(&swaps) == 0,
+        Err(e) => {
+            warn!("⚠️  Cannot read {}: {}", SWAPS_PATH, e);
+            false
+        }
+    }
+}
+
+/// Swap areas listed in `/proc/swaps`, whose first line is a header
+fn active_swaps(swaps: &str) -> usize {
+    swaps
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .count()
+}
+
+/// `mlockall` only once the memlock limit is unlimited. With a finite limit, locking future
+/// pages would make allocations fail once the limit is reached.
+fn lock_all_memory() -> bool {
+    let unlimited = libc::rlimit {
+        rlim_cur: libc::RLIM_INFINITY,
+        rlim_max: libc::RLIM_INFINITY,
+    };
+    // SAFETY: setrlimit and getrlimit only read and write the rlimit structs passed in
+    unsafe {
+        libc::setrlimit(libc::RLIMIT_MEMLOCK, &unlimited);
+        let mut limit = std::mem::zeroed::<libc::rlimit>();
+        if libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) != 0
+            || limit.rlim_cur != libc::RLIM_INFINITY
+        {
+            warn!("⚠️  Memory lock limit is finite, not locking memory");
+            return false;
+        }
+    }
+
+    // SAFETY: mlockall takes only flags
+    if unsafe { libc::mlockall(libc::MCL_CURRENT | libc::MCL_FUTURE) } != 0 {
+        warn!("⚠️  mlockall failed: {}", std::io::Error::last_os_error());
+        return false;
+    }
+    true
+}
+
+fn disable_core_dumps() -> bool {
+    let zero = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: prctl with PR_SET_DUMPABLE/PR_GET_DUMPABLE and setrlimit take plain values
+    unsafe {
+        let no_core = libc::setrlimit(libc::RLIMIT_CORE, &zero) == 0;
+        let undumpable = libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) == 0
+            && libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0) == 0;
+        if !(no_core && undumpable) {
+            warn!(
+                "⚠️  Failed to disable core dumps: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+        no_core && undumpable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_active_swaps() {
+        let header = "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n";
+        assert_eq!(active_swaps(header), 0);
+        assert_eq!(active_swaps(""), 0);
+        assert_eq!(
+            active_swaps(&format!(
+                "{}/swapfile                               file\t\t2097148\t\t0\t\t-2\n",
+                header
+            )),
+            1
+        );
+    }
+}
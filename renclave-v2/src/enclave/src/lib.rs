@@ -3,11 +3,45 @@
 //! This library provides the core enclave functionality for secure seed generation
 //! and cryptographic operations.
 
+#[cfg(all(feature = "deterministic-test", not(debug_assertions)))]
+compile_error!("the deterministic-test feature must never be enabled in release builds");
+
+pub mod address;
+pub mod attestation;
+pub mod build_info;
+pub mod channel;
+pub mod clock;
+pub mod derivation;
+pub mod dispatch;
+pub mod entropy;
+pub mod hardening;
+pub mod limits;
+pub mod metrics;
 pub mod nitro;
+pub mod privileges;
+pub mod psbt;
+pub mod sandbox;
 pub mod seed_generator;
+pub mod signing_policy;
+pub mod threshold;
+pub mod tls;
+pub mod verify;
 
 // Re-export main types for convenience
+pub use attestation::{AttestationLimits, AttestationThrottled, Attester};
+pub use channel::ChannelSessions;
+pub use clock::{ClockConfig, TrustedClock};
+pub use derivation::{DerivationPolicy, PathError};
+pub use dispatch::{DispatchLimits, Dispatcher, Overloaded};
+pub use entropy::{EntropyError, EntropyPool};
+pub use limits::SocketLimits;
+pub use metrics::EnclaveMetrics;
 pub use seed_generator::AddressDerivationResult;
 pub use seed_generator::KeyDerivationResult;
+pub use seed_generator::PsbtSigningResult;
 pub use seed_generator::SeedGenerator;
 pub use seed_generator::SeedResult;
+pub use seed_generator::XpubDerivationResult;
+pub use signing_policy::PolicyEngine;
+pub use threshold::{ThresholdError, ThresholdSessions};
+pub use tls::TlsIdentity;
@@ -0,0 +1,157 @@
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+use tokio::time::timeout;
+
+pub use renclave_shared::MAX_FRAME_BYTES_ENV;
+/// Environment variable for the per-connection read timeout in seconds
+pub const READ_TIMEOUT_SECS_ENV: &str = "RENCLAVE_ENCLAVE_READ_TIMEOUT_SECS";
+/// Environment variable for the maximum number of concurrent host connections
+pub const MAX_CONNECTIONS_ENV: &str = "RENCLAVE_ENCLAVE_MAX_CONNECTIONS";
+
+/// Resource limits for the enclave Unix socket server
+#[derive(Debug, Clone)]
+pub struct SocketLimits {
+    /// Largest accepted request line, excluding the trailing newline
+    pub max_frame_bytes: usize,
+    /// How long a connection may stay silent before it is closed
+    pub read_timeout: Duration,
+    /// Connections beyond this are rejected immediately
+    pub max_connections: usize,
+}
+
+impl Default for SocketLimits {
+    fn default() -> Self {
+        Self {
+            max_frame_bytes: renclave_shared::DEFAULT_MAX_FRAME_BYTES,
+            read_timeout: Duration::from_secs(30),
+            max_connections: 64,
+        }
+    }
+}
+
+impl SocketLimits {
+    /// Default limits with overrides from the environment
+    pub fn from_env() -> Self {
+        let env = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+        };
+
+        let mut limits = Self::default();
+        if let Some(bytes) = env(MAX_FRAME_BYTES_ENV) {
+            limits.max_frame_bytes = bytes as usize;
+        }
+        if let Some(secs) = env(READ_TIMEOUT_SECS_ENV) {
+            limits.read_timeout = Duration::from_secs(secs);
+        }
+        if let Some(connections) = env(MAX_CONNECTIONS_ENV) {
+            limits.max_connections = connections as usize;
+        }
+        limits
+    }
+}
+
+/// Outcome of reading one request frame
+#[derive(Debug, PartialEq, Eq)]
+pub enum Frame {
+    /// A complete line was read into the buffer
+    Line,
+    /// The peer closed the connection
+    Eof,
+    /// The frame exceeded `max_frame_bytes`; the stream can no longer be trusted
+    TooLarge,
+    /// Nothing arrived within `read_timeout`
+    TimedOut,
+}
+
+/// Read one newline-terminated frame, never buffering more than `max_frame_bytes + 1` bytes
+pub async fn read_frame<R>(
+    reader: &mut R,
+    buffer: &mut String,
+    limits: &SocketLimits,
+) -> std::io::Result<Frame>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let limit = limits.max_frame_bytes as u64 + 1;
+    let read = timeout(
+        limits.read_timeout,
+        (&mut *reader).take(limit).read_line(buffer),
+    )
+    .await;
+
+    match read {
+        Err(_) => Ok(Frame::TimedOut),
+        Ok(Err(e)) => Err(e),
+        Ok(Ok(0)) => Ok(Frame::Eof),
+        Ok(Ok(n)) if n as u64 == limit && !buffer.ends_with('\n') => Ok(Frame::TooLarge),
+        Ok(Ok(_)) => Ok(Frame::Line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::BufReader;
+
+    fn limits(max_frame_bytes: usize) -> SocketLimits {
+        SocketLimits {
+            max_frame_bytes,
+            read_timeout: Duration::from_millis(50),
+            max_connections: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_lines_within_limit() {
+        let mut reader = BufReader::new(&b"{\"a\":1}\nnext\n"[..]);
+        let mut buffer = String::new();
+
+        let frame = read_frame(&mut reader, &mut buffer, &limits(7))
+            .await
+            .unwrap();
+        assert_eq!(frame, Frame::Line);
+        assert_eq!(buffer, "{\"a\":1}\n");
+
+        buffer.clear();
+        let frame = read_frame(&mut reader, &mut buffer, &limits(7))
+            .await
+            .unwrap();
+        assert_eq!(frame, Frame::Line);
+        assert_eq!(buffer, "next\n");
+
+        buffer.clear();
+        let frame = read_frame(&mut reader, &mut buffer, &limits(7))
+            .await
+            .unwrap();
+        assert_eq!(frame, Frame::Eof);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_frame() {
+        let data = vec![b'x'; 1024];
+        let mut reader = BufReader::new(&data[..]);
+        let mut buffer = String::new();
+
+        let frame = read_frame(&mut reader, &mut buffer, &limits(16))
+            .await
+            .unwrap();
+        assert_eq!(frame, Frame::TooLarge);
+        assert_eq!(buffer.len(), 17);
+    }
+
+    #[tokio::test]
+    async fn test_times_out_on_silent_peer() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut reader = BufReader::new(server);
+        let mut buffer = String::new();
+
+        let frame = read_frame(&mut reader, &mut buffer, &limits(16))
+            .await
+            .unwrap();
+        assert_eq!(frame, Frame::TimedOut);
+        drop(client);
+    }
+}
@@ -1,22 +1,72 @@
 use log::{debug, error, info, warn};
+use opentelemetry::trace::{FutureExt, SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{Context as TraceContext, KeyValue};
+use std::future::Future;
 use std::sync::Arc;
 use tokio::fs;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{UnixListener, UnixStream};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use uuid::Uuid;
+use zeroize::Zeroize;
 
+mod address;
+mod attestation;
+mod build_info;
+mod channel;
+mod clock;
+mod derivation;
+mod dispatch;
+mod entropy;
+mod hardening;
+mod limits;
+mod metrics;
 mod nitro;
+mod privileges;
+mod psbt;
+mod sandbox;
 mod seed_generator;
+mod signing_policy;
+mod threshold;
+mod tls;
+mod verify;
 
+use attestation::{AttestationLimits, Attester};
+use channel::ChannelSessions;
+use clock::{ClockConfig, TrustedClock};
+use dispatch::{DispatchLimits, Dispatcher};
+use limits::{read_frame, Frame, SocketLimits};
+use metrics::EnclaveMetrics;
 use renclave_network::{NetworkConfig, NetworkManager};
-use renclave_shared::{EnclaveOperation, EnclaveRequest, EnclaveResponse, EnclaveResult};
+use renclave_shared::channel::handshake_hash;
+use renclave_shared::telemetry;
+use renclave_shared::{
+    response_digest, ChannelAttestation, ChannelError, DeriveKeyResponse, EnclaveOperation,
+    EnclaveRequest, EnclaveResponse, EnclaveResult, GenerateSeedResponse, RenclaveErrorCode,
+    SealedPayload, SecretBytes, SecretString, MAX_ATTESTATION_NONCE_BYTES, PROTOCOL_VERSION,
+    SUPPORTED_OPERATIONS,
+};
 use seed_generator::SeedGenerator;
+use serde::Serialize;
+use threshold::ThresholdSessions;
+use tls::TlsIdentity;
 
 /// QEMU Nitro Enclave for secure seed generation
 pub struct NitroEnclave {
     seed_generator: Arc<SeedGenerator>,
     network_manager: Arc<NetworkManager>,
-    enclave_id: String,
+    metrics: Arc<EnclaveMetrics>,
+    channels: Arc<ChannelSessions>,
+    dispatcher: Arc<Dispatcher>,
+    limits: SocketLimits,
+    /// Produces and caches attestation documents; also holds the enclave ID
+    attester: Arc<Attester>,
+    /// Clock for timestamps, synchronized over NTP or by the host
+    clock: Arc<TrustedClock>,
+    /// Identity for TLS terminated in the enclave, when a TLS socket is configured
+    tls: Option<Arc<TlsIdentity>>,
+    /// Threshold signing sessions, when the coordinator is compiled in
+    threshold: Option<Arc<ThresholdSessions>>,
 }
 
 impl NitroEnclave {
@@ -28,28 +78,91 @@ impl NitroEnclave {
         let enclave_id = Uuid::new_v4().to_string();
         info!("🆔 Enclave ID: {}", enclave_id);
 
+        // Timestamps and signing policy times come from a clock synchronized over NTP or by
+        // the host, since the guest clock drifts
+        let clock = Arc::new(TrustedClock::new(ClockConfig::from_env()));
+
         // Initialize seed generator
         info!("🌱 Initializing secure seed generator...");
-        let seed_generator = Arc::new(SeedGenerator::new().await?);
+        let seed_generator = Arc::new(SeedGenerator::new(Arc::clone(&clock)).await?);
         info!("✅ Seed generator initialized");
 
         // Initialize network manager
         info!("🌐 Initializing network manager...");
-        let network_config = NetworkConfig::default();
+        let network_config = NetworkConfig::from_env();
         let network_manager = Arc::new(NetworkManager::new(network_config));
 
         // Initialize network
         if let Err(e) = network_manager.initialize().await {
             warn!("⚠️  Network initialization failed: {}", e);
-            info!("ℹ️  Continuing without full network setup (may be running outside QEMU)");
+            info!("ℹ️  Continuing in degraded network mode (may be running outside QEMU)");
+        }
+
+        if let Some(interval) = NetworkManager::monitor_interval_from_env() {
+            network_manager.spawn_monitor(interval);
         }
 
         info!("✅ Network manager initialized");
 
+        // NTP goes over the network, so it only starts once the network is up
+        clock.spawn_ntp_sync();
+
+        // Initialize signed metrics
+        let metrics = Arc::new(EnclaveMetrics::new(enclave_id.clone(), Arc::clone(&clock)));
+        if let Some(interval) = EnclaveMetrics::snapshot_interval_from_env() {
+            metrics.spawn_periodic_snapshots(interval);
+        }
+
+        let limits = SocketLimits::from_env();
+        info!(
+            "🛡️  Socket limits: {} byte frames, {:?} read timeout, {} connections",
+            limits.max_frame_bytes, limits.read_timeout, limits.max_connections
+        );
+        let tls = match renclave_shared::enclave_tls_socket_path() {
+            Some(_) => {
+                info!("🔐 Generating in-enclave TLS certificate...");
+                let identity = TlsIdentity::from_env()?;
+                info!(
+                    "✅ TLS certificate ready, SHA-256 fingerprint {}\n{}",
+                    identity.fingerprint(),
+                    identity.certificate_pem().trim_end()
+                );
+                Some(Arc::new(identity))
+            }
+            None => None,
+        };
+
+        let attestation_limits = AttestationLimits::from_env();
+        info!(
+            "📋 Attestation documents cached for {:?}, at most {} generated per second",
+            attestation_limits.cache_ttl, attestation_limits.max_per_sec
+        );
+
+        let dispatch_limits = DispatchLimits::from_env();
+        info!(
+            "🚦 Dispatch lanes: {:?} concurrent control/read/sign/ceremony operations, {} queued per lane",
+            dispatch_limits.concurrency, dispatch_limits.queue_depth
+        );
+
         Ok(Self {
             seed_generator,
             network_manager,
-            enclave_id,
+            metrics,
+            channels: Arc::new(ChannelSessions::from_env()),
+            dispatcher: Arc::new(Dispatcher::new(&dispatch_limits)),
+            limits,
+            attester: Arc::new(Attester::new(
+                enclave_id,
+                attestation_limits,
+                Arc::clone(&clock),
+            )),
+            clock,
+            tls,
+            threshold: if cfg!(feature = "threshold-signing") {
+                Some(Arc::new(ThresholdSessions::from_env()?))
+            } else {
+                None
+            },
         })
     }
 
@@ -57,18 +170,24 @@ impl NitroEnclave {
     pub async fn start(&self) -> anyhow::Result<()> {
         info!("🚀 Starting QEMU Nitro Enclave");
 
+        if let (Some(identity), Some(path)) =
+            (&self.tls, renclave_shared::enclave_tls_socket_path())
+        {
+            self.spawn_tls_listener(Arc::clone(identity), &path).await?;
+        }
+
         // Setup Unix socket for communication with host
-        let socket_path = "/tmp/enclave.sock";
+        let socket_path = renclave_shared::enclave_socket_path();
 
         // Robust socket cleanup - remove anything at the socket path
-        if let Ok(metadata) = fs::metadata(socket_path).await {
+        if let Ok(metadata) = fs::metadata(&socket_path).await {
             if metadata.is_dir() {
                 // If it's a directory, remove it recursively
-                fs::remove_dir_all(socket_path).await?;
+                fs::remove_dir_all(&socket_path).await?;
                 debug!("🗑️  Removed existing directory at socket path");
             } else {
                 // If it's a file (including socket), remove it
-                fs::remove_file(socket_path).await?;
+                fs::remove_file(&socket_path).await?;
                 debug!("🗑️  Removed existing file at socket path");
             }
 
@@ -81,7 +200,7 @@ impl NitroEnclave {
         const MAX_ATTEMPTS: u32 = 5;
 
         while attempts < MAX_ATTEMPTS {
-            match UnixListener::bind(socket_path) {
+            match UnixListener::bind(&socket_path) {
                 Ok(listener) => {
                     info!("🔗 Creating Unix socket listener at: {}", socket_path);
 
@@ -89,10 +208,10 @@ impl NitroEnclave {
                     #[cfg(unix)]
                     {
                         use std::os::unix::fs::PermissionsExt;
-                        if let Ok(metadata) = tokio::fs::metadata(socket_path).await {
+                        if let Ok(metadata) = tokio::fs::metadata(&socket_path).await {
                             let mut perms = metadata.permissions();
                             perms.set_mode(0o666);
-                            if let Err(e) = tokio::fs::set_permissions(socket_path, perms).await {
+                            if let Err(e) = tokio::fs::set_permissions(&socket_path, perms).await {
                                 warn!("⚠️  Failed to set socket permissions: {}", e);
                             } else {
                                 debug!("🔐 Set socket permissions to 666");
@@ -103,30 +222,34 @@ impl NitroEnclave {
                     info!("✅ Unix socket listener created successfully");
                     info!("🔒 Enclave ready to handle secure seed generation requests");
 
+                    // Bound the number of concurrently served connections
+                    let connection_slots = Arc::new(Semaphore::new(self.limits.max_connections));
+
                     // Accept connections from host
                     loop {
                         match listener.accept().await {
-                            Ok((stream, addr)) => {
+                            Ok((mut stream, addr)) => {
                                 info!("📞 Host connected to enclave: {:?}", addr);
 
-                                // Clone references for this connection
-                                let seed_generator = Arc::clone(&self.seed_generator);
-                                let network_manager = Arc::clone(&self.network_manager);
-                                let enclave_id = self.enclave_id.clone();
+                                let Ok(permit) = Arc::clone(&connection_slots).try_acquire_owned()
+                                else {
+                                    warn!(
+                                        "⚠️  Rejecting connection, {} connections already open",
+                                        self.limits.max_connections
+                                    );
+                                    tokio::spawn(async move {
+                                        Self::send_error(
+                                            &mut stream,
+                                            "Too many concurrent connections".to_string(),
+                                            RenclaveErrorCode::Unavailable,
+                                        )
+                                        .await;
+                                    });
+                                    continue;
+                                };
 
                                 // Handle client in a separate task
-                                tokio::spawn(async move {
-                                    if let Err(e) = Self::handle_client(
-                                        stream,
-                                        seed_generator,
-                                        network_manager,
-                                        enclave_id,
-                                    )
-                                    .await
-                                    {
-                                        error!("❌ Error handling client: {}", e);
-                                    }
-                                });
+                                self.spawn_client(async move { Ok(stream) }, permit);
                             }
                             Err(e) => {
                                 error!("❌ Failed to accept connection: {}", e);
@@ -147,11 +270,11 @@ impl NitroEnclave {
                     warn!("⚠️  Socket bind attempt {} failed: {}", attempts, e);
 
                     // Try to clean up again and wait
-                    if let Ok(metadata) = fs::metadata(socket_path).await {
+                    if let Ok(metadata) = fs::metadata(&socket_path).await {
                         if metadata.is_dir() {
-                            let _ = fs::remove_dir_all(socket_path).await;
+                            let _ = fs::remove_dir_all(&socket_path).await;
                         } else {
-                            let _ = fs::remove_file(socket_path).await;
+                            let _ = fs::remove_file(&socket_path).await;
                         }
                     }
 
@@ -163,12 +286,128 @@ impl NitroEnclave {
         unreachable!("Should have either succeeded or returned an error by now");
     }
 
+    /// Terminate TLS on a second Unix socket at `path`, which the host forwards client
+    /// connections to without seeing their contents. Requests inside the TLS stream use the
+    /// same framing as the plaintext socket.
+    async fn spawn_tls_listener(
+        &self,
+        identity: Arc<TlsIdentity>,
+        path: &str,
+    ) -> anyhow::Result<()> {
+        if fs::metadata(path).await.is_ok() {
+            fs::remove_file(path).await?;
+        }
+        let listener = UnixListener::bind(path)
+            .map_err(|e| anyhow::anyhow!("Failed to bind TLS socket {}: {}", path, e))?;
+        info!("🔐 Terminating TLS on {}", path);
+
+        let connection_slots = Arc::new(Semaphore::new(self.limits.max_connections));
+        let handshake_timeout = self.limits.read_timeout;
+        let enclave = self.clone_handles();
+        tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        error!("❌ Failed to accept TLS connection: {}", e);
+                        continue;
+                    }
+                };
+                // Nothing can be sent before the handshake, so excess connections are closed
+                let Ok(permit) = Arc::clone(&connection_slots).try_acquire_owned() else {
+                    warn!("⚠️  Rejecting TLS connection, too many connections open");
+                    continue;
+                };
+
+                let acceptor = identity.acceptor();
+                let handshake = async move {
+                    tokio::time::timeout(handshake_timeout, acceptor.accept(stream))
+                        .await
+                        .map_err(|_| anyhow::anyhow!("TLS handshake timed out"))?
+                        .map_err(|e| anyhow::anyhow!("TLS handshake failed: {}", e))
+                };
+                enclave.spawn_client(handshake, permit);
+            }
+        });
+        Ok(())
+    }
+
+    /// Handles shared with connection tasks
+    fn clone_handles(&self) -> Self {
+        Self {
+            seed_generator: Arc::clone(&self.seed_generator),
+            network_manager: Arc::clone(&self.network_manager),
+            metrics: Arc::clone(&self.metrics),
+            channels: Arc::clone(&self.channels),
+            dispatcher: Arc::clone(&self.dispatcher),
+            limits: self.limits.clone(),
+            attester: Arc::clone(&self.attester),
+            clock: Arc::clone(&self.clock),
+            tls: self.tls.clone(),
+            threshold: self.threshold.clone(),
+        }
+    }
+
+    /// Serve the client connection `stream` resolves to on its own task, holding `permit`
+    /// until it closes
+    fn spawn_client<F, S>(&self, stream: F, permit: OwnedSemaphorePermit)
+    where
+        F: Future<Output = anyhow::Result<S>> + Send + 'static,
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        // Clone references for this connection
+        let seed_generator = Arc::clone(&self.seed_generator);
+        let network_manager = Arc::clone(&self.network_manager);
+        let metrics = Arc::clone(&self.metrics);
+        let channels = Arc::clone(&self.channels);
+        let dispatcher = Arc::clone(&self.dispatcher);
+        let limits = self.limits.clone();
+        let attester = Arc::clone(&self.attester);
+        let clock = Arc::clone(&self.clock);
+        let tls = self.tls.clone();
+        let threshold = self.threshold.clone();
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            let result = match stream.await {
+                Ok(stream) => {
+                    Self::handle_client(
+                        stream,
+                        seed_generator,
+                        network_manager,
+                        metrics,
+                        channels,
+                        dispatcher,
+                        limits,
+                        attester,
+                        clock,
+                        tls,
+                        threshold,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                error!("❌ Error handling client: {}", e);
+            }
+        });
+    }
+
     /// Handle client connection
-    async fn handle_client(
-        stream: UnixStream,
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_client<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: S,
         seed_generator: Arc<SeedGenerator>,
         network_manager: Arc<NetworkManager>,
-        enclave_id: String,
+        metrics: Arc<EnclaveMetrics>,
+        channels: Arc<ChannelSessions>,
+        dispatcher: Arc<Dispatcher>,
+        limits: SocketLimits,
+        attester: Arc<Attester>,
+        clock: Arc<TrustedClock>,
+        tls: Option<Arc<TlsIdentity>>,
+        threshold: Option<Arc<ThresholdSessions>>,
     ) -> anyhow::Result<()> {
         debug!("🔍 Handling client connection");
 
@@ -176,36 +415,74 @@ impl NitroEnclave {
         let mut buffer = String::new();
 
         loop {
-            buffer.clear();
+            // Request frames carry seed phrases
+            buffer.zeroize();
 
-            match reader.read_line(&mut buffer).await {
-                Ok(0) => {
+            match read_frame(&mut reader, &mut buffer, &limits).await {
+                Ok(Frame::Eof) => {
                     debug!("🔌 Client disconnected");
                     break;
                 }
-                Ok(_) => {
+                Ok(Frame::TimedOut) => {
+                    debug!("⏱️  Closing idle client connection");
+                    break;
+                }
+                Ok(Frame::TooLarge) => {
+                    warn!(
+                        "⚠️  Request frame exceeds {} bytes, closing connection",
+                        limits.max_frame_bytes
+                    );
+                    let mut stream = reader.into_inner();
+                    Self::send_error(
+                        &mut stream,
+                        format!(
+                            "Request exceeds maximum frame size of {} bytes",
+                            limits.max_frame_bytes
+                        ),
+                        RenclaveErrorCode::InvalidRequest,
+                    )
+                    .await;
+                    return Ok(());
+                }
+                Ok(Frame::Line) => {
                     let request_json = buffer.trim();
-                    debug!("📨 Received request: {}", request_json);
+                    debug!("📨 Received request ({} bytes)", request_json.len());
 
                     // Parse request
                     match serde_json::from_str::<EnclaveRequest>(request_json) {
                         Ok(request) => {
+                            if request.protocol_version > PROTOCOL_VERSION {
+                                debug!(
+                                    "📨 Request {} uses protocol version {} (enclave speaks {})",
+                                    request.id, request.protocol_version, PROTOCOL_VERSION
+                                );
+                            }
+
                             // Process request
                             let response = Self::process_request(
                                 request,
                                 &seed_generator,
                                 &network_manager,
-                                &enclave_id,
+                                &metrics,
+                                &channels,
+                                &dispatcher,
+                                &attester,
+                                &clock,
+                                tls.as_deref(),
+                                threshold.as_deref(),
                             )
                             .await;
 
                             // Send response
                             match serde_json::to_string(&response) {
                                 Ok(response_json) => {
-                                    debug!("📤 Sending response: {}", response_json);
+                                    debug!("📤 Sending response: {}", response.id);
+                                    let response_json = SecretString::new(response_json);
 
                                     let mut stream = reader.into_inner();
-                                    if let Err(e) = stream.write_all(response_json.as_bytes()).await
+                                    if let Err(e) = stream
+                                        .write_all(response_json.expose_secret().as_bytes())
+                                        .await
                                     {
                                         error!("❌ Failed to send response: {}", e);
                                         break;
@@ -223,7 +500,7 @@ impl NitroEnclave {
                                     let error_response = EnclaveResponse::error(
                                         "unknown".to_string(),
                                         format!("Serialization error: {}", e),
-                                        500,
+                                        RenclaveErrorCode::Internal,
                                     );
                                     if let Ok(error_json) = serde_json::to_string(&error_response) {
                                         let mut stream = reader.into_inner();
@@ -235,12 +512,28 @@ impl NitroEnclave {
                             }
                         }
                         Err(e) => {
-                            error!("❌ Failed to parse request: {}", e);
-                            let error_response = EnclaveResponse::error(
-                                "unknown".to_string(),
-                                format!("Invalid request format: {}", e),
-                                400,
-                            );
+                            let error_response =
+                                match EnclaveRequest::unsupported_operation(request_json) {
+                                    Some((id, operation)) => {
+                                        warn!(
+                                            "⚠️  Unsupported operation from newer host: {}",
+                                            operation
+                                        );
+                                        EnclaveResponse::error(
+                                            id,
+                                            format!("Unsupported operation: {}", operation),
+                                            RenclaveErrorCode::UnsupportedOperation,
+                                        )
+                                    }
+                                    None => {
+                                        error!("❌ Failed to parse request: {}", e);
+                                        EnclaveResponse::error(
+                                            "unknown".to_string(),
+                                            format!("Invalid request format: {}", e),
+                                            RenclaveErrorCode::InvalidRequest,
+                                        )
+                                    }
+                                };
                             if let Ok(error_json) = serde_json::to_string(&error_response) {
                                 let mut stream = reader.into_inner();
                                 let _ = stream.write_all(error_json.as_bytes()).await;
@@ -257,65 +550,460 @@ impl NitroEnclave {
             }
         }
 
+        buffer.zeroize();
         debug!("🔌 Client connection closed");
         Ok(())
     }
 
+    /// Send an error response that is not tied to a parsed request
+    async fn send_error<S: AsyncWrite + Unpin>(
+        stream: &mut S,
+        message: String,
+        code: RenclaveErrorCode,
+    ) {
+        let error_response = EnclaveResponse::error("unknown".to_string(), message, code);
+        if let Ok(error_json) = serde_json::to_string(&error_response) {
+            let _ = stream.write_all(error_json.as_bytes()).await;
+            let _ = stream.write_all(b"\n").await;
+        }
+    }
+
     /// Process enclave request
+    #[allow(clippy::too_many_arguments)]
     async fn process_request(
         request: EnclaveRequest,
         seed_generator: &SeedGenerator,
         network_manager: &NetworkManager,
-        enclave_id: &str,
+        metrics: &EnclaveMetrics,
+        channels: &ChannelSessions,
+        dispatcher: &Dispatcher,
+        attester: &Attester,
+        clock: &TrustedClock,
+        tls: Option<&TlsIdentity>,
+        threshold: Option<&ThresholdSessions>,
     ) -> EnclaveResponse {
         debug!("⚙️  Processing request: {:?}", request.operation);
+        let operation_name = request.operation.name();
+
+        // Child of the host's span when the host is tracing
+        let tracer = telemetry::tracer();
+        let span = tracer
+            .span_builder(operation_name)
+            .with_kind(SpanKind::Server)
+            .with_attributes([KeyValue::new("enclave.request_id", request.id.clone())])
+            .start_with_context(
+                &tracer,
+                &telemetry::extract(request.trace_context.as_deref()),
+            );
+        let cx = TraceContext::current_with_span(span);
+
+        let operation = request.operation;
+        let result = async move {
+            // Sealed requests take a slot for their inner operation once decrypted
+            let _permit = match &operation {
+                EnclaveOperation::SealedRequest { .. } => None,
+                operation => match dispatcher.acquire(operation.class()).await {
+                    Ok(permit) => Some(permit),
+                    Err(e) => return Self::shed(e),
+                },
+            };
+
+            match operation {
+                EnclaveOperation::OpenChannel {
+                    client_public_key,
+                    nonce,
+                } => {
+                    info!("🔐 Opening encrypted channel");
+                    Self::open_channel(
+                        &client_public_key,
+                        nonce.as_deref(),
+                        seed_generator,
+                        channels,
+                        attester,
+                    )
+                    .await
+                }
+
+                EnclaveOperation::SealedRequest {
+                    session_id,
+                    payload,
+                } => {
+                    Self::process_sealed(
+                        session_id,
+                        payload,
+                        seed_generator,
+                        network_manager,
+                        metrics,
+                        channels,
+                        dispatcher,
+                        attester,
+                        clock,
+                        tls,
+                        threshold,
+                    )
+                    .await
+                }
+
+                EnclaveOperation::VerifyCiphertext {
+                    session_id,
+                    payload,
+                } => {
+                    info!("🔍 Verifying sealed payload");
+                    Self::verify_ciphertext(&session_id, &payload, channels)
+                }
+
+                operation => {
+                    Self::execute(
+                        operation,
+                        seed_generator,
+                        network_manager,
+                        metrics,
+                        attester,
+                        clock,
+                        tls,
+                        threshold,
+                    )
+                    .await
+                }
+            }
+        }
+        .with_context(cx.clone())
+        .await;
+
+        metrics.record_operation(
+            operation_name,
+            !matches!(result, EnclaveResult::Error { .. }),
+        );
+        if let EnclaveResult::Error { code, .. } = &result {
+            cx.span().set_status(Status::error(code.as_str()));
+        }
+        cx.span().end();
+
+        EnclaveResponse::new(request.id, result)
+    }
+
+    /// Complete a channel handshake with a fresh ephemeral key from the entropy pool
+    async fn open_channel(
+        client_public_key: &str,
+        nonce: Option<&str>,
+        seed_generator: &SeedGenerator,
+        channels: &ChannelSessions,
+        attester: &Attester,
+    ) -> EnclaveResult {
+        let nonce = match nonce.map(parse_nonce).transpose() {
+            Ok(nonce) => nonce,
+            Err(message) => {
+                warn!("⚠️  Rejected channel nonce: {}", message);
+                return EnclaveResult::Error {
+                    message: format!("Invalid nonce: {}", message),
+                    code: RenclaveErrorCode::InvalidRequest,
+                };
+            }
+        };
+
+        let mut secret = SecretBytes::zeroed(32);
+        let enclave_secret = seed_generator
+            .entropy()
+            .fill(secret.expose_secret_mut())
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(secp256k1::SecretKey::from_slice(secret.expose_secret())?));
+        let enclave_secret = match enclave_secret {
+            Ok(key) => key,
+            Err(e) => {
+                error!("❌ Failed to create channel key: {}", e);
+                return EnclaveResult::Error {
+                    message: format!("Channel key generation failed: {}", e),
+                    code: operation_error_code(&e),
+                };
+            }
+        };
+
+        let opened = match channels.open(client_public_key, enclave_secret) {
+            Ok(opened) => opened,
+            Err(e) => {
+                warn!("⚠️  Rejected channel handshake: {}", e);
+                return EnclaveResult::Error {
+                    message: format!("Channel handshake failed: {}", e),
+                    code: e.code(),
+                };
+            }
+        };
+
+        let user_data = handshake_hash(
+            &opened.session_id,
+            &opened.client_public,
+            &opened.enclave_public,
+        );
+        match attester.document(&user_data, nonce.as_deref(), false).await {
+            Ok(document) => EnclaveResult::ChannelOpened {
+                session_id: opened.session_id,
+                enclave_public_key: hex::encode(opened.enclave_public.serialize()),
+                expires_at: opened.expires_at,
+                attestation: document.to_channel_attestation(),
+            },
+            Err(e) => {
+                error!("❌ Failed to attest channel: {}", e);
+                EnclaveResult::Error {
+                    message: format!("Channel attestation failed: {}", e),
+                    code: operation_error_code(&e),
+                }
+            }
+        }
+    }
+
+    /// Report whether a sealed request would be accepted; unknown sessions are an error since
+    /// there is nothing to verify against
+    fn verify_ciphertext(
+        session_id: &str,
+        payload: &SealedPayload,
+        channels: &ChannelSessions,
+    ) -> EnclaveResult {
+        match channels.verify(session_id, payload) {
+            Ok(()) => EnclaveResult::CiphertextVerified {
+                valid: true,
+                reason: None,
+            },
+            Err(e @ ChannelError::UnknownSession) => EnclaveResult::Error {
+                message: format!("Ciphertext verification failed: {}", e),
+                code: e.code(),
+            },
+            Err(e) => {
+                debug!("🔍 Sealed payload is invalid: {}", e);
+                EnclaveResult::CiphertextVerified {
+                    valid: false,
+                    reason: Some(e.to_string()),
+                }
+            }
+        }
+    }
+
+    /// Decrypt a sealed operation, execute it and encrypt its result on the same channel
+    #[allow(clippy::too_many_arguments)]
+    async fn process_sealed(
+        session_id: String,
+        payload: SealedPayload,
+        seed_generator: &SeedGenerator,
+        network_manager: &NetworkManager,
+        metrics: &EnclaveMetrics,
+        channels: &ChannelSessions,
+        dispatcher: &Dispatcher,
+        attester: &Attester,
+        clock: &TrustedClock,
+        tls: Option<&TlsIdentity>,
+        threshold: Option<&ThresholdSessions>,
+    ) -> EnclaveResult {
+        let plaintext = match channels.unseal(&session_id, &payload) {
+            Ok(plaintext) => plaintext,
+            Err(e) => {
+                warn!("⚠️  Rejected sealed request: {}", e);
+                return EnclaveResult::Error {
+                    message: format!("Sealed request rejected: {}", e),
+                    code: e.code(),
+                };
+            }
+        };
+
+        let operation: EnclaveOperation = match serde_json::from_slice(plaintext.expose_secret()) {
+            Ok(operation) => operation,
+            Err(e) => {
+                return EnclaveResult::Error {
+                    message: format!("Invalid sealed operation: {}", e),
+                    code: RenclaveErrorCode::InvalidRequest,
+                }
+            }
+        };
+        drop(plaintext);
+
+        let operation_name = operation.name();
+        debug!("🔐 Executing sealed {} operation", operation_name);
+        let _permit = match dispatcher.acquire(operation.class()).await {
+            Ok(permit) => permit,
+            Err(e) => return Self::shed(e),
+        };
+        let result = Self::execute(
+            operation,
+            seed_generator,
+            network_manager,
+            metrics,
+            attester,
+            clock,
+            tls,
+            threshold,
+        )
+        .await;
+        metrics.record_operation(
+            operation_name,
+            !matches!(result, EnclaveResult::Error { .. }),
+        );
+
+        let sealed = serde_json::to_string(&result)
+            .map(SecretString::new)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                channels
+                    .seal(
+                        &session_id,
+                        payload.counter,
+                        json.expose_secret().as_bytes(),
+                    )
+                    .map_err(|e| e.to_string())
+            });
+        match sealed {
+            Ok(payload) => EnclaveResult::SealedResponse {
+                session_id,
+                payload,
+            },
+            Err(e) => {
+                error!("❌ Failed to seal response: {}", e);
+                EnclaveResult::Error {
+                    message: format!("Failed to seal response: {}", e),
+                    code: RenclaveErrorCode::CryptoFailure,
+                }
+            }
+        }
+    }
+
+    /// Answer for an operation shed because its lane is full
+    fn shed(overloaded: dispatch::Overloaded) -> EnclaveResult {
+        warn!(
+            "🚦 Shedding {} operation: lane full",
+            overloaded.class.as_str()
+        );
+        EnclaveResult::Error {
+            message: overloaded.to_string(),
+            code: RenclaveErrorCode::Unavailable,
+        }
+    }
+
+    /// Attestation committing to the HTTP form of a result, if the caller asked for one. A
+    /// recent document for an identical result is reused unless `fresh` is set.
+    async fn attest_response<T: Serialize>(
+        attest: bool,
+        fresh: bool,
+        response: &T,
+        attester: &Attester,
+    ) -> anyhow::Result<Option<ChannelAttestation>> {
+        if !attest {
+            return Ok(None);
+        }
+
+        let digest = response_digest(response)?;
+        let document = attester.document(&digest, None, fresh).await?;
+        Ok(Some(document.to_channel_attestation()))
+    }
+
+    /// Execute a plaintext operation
+    #[allow(clippy::too_many_arguments)]
+    async fn execute(
+        operation: EnclaveOperation,
+        seed_generator: &SeedGenerator,
+        network_manager: &NetworkManager,
+        metrics: &EnclaveMetrics,
+        attester: &Attester,
+        clock: &TrustedClock,
+        tls: Option<&TlsIdentity>,
+        threshold: Option<&ThresholdSessions>,
+    ) -> EnclaveResult {
+        match operation {
+            EnclaveOperation::OpenChannel { .. }
+            | EnclaveOperation::SealedRequest { .. }
+            | EnclaveOperation::VerifyCiphertext { .. } => EnclaveResult::Error {
+                message: "Channel operations cannot be sent on a channel".to_string(),
+                code: RenclaveErrorCode::InvalidRequest,
+            },
 
-        let result = match request.operation {
             EnclaveOperation::GenerateSeed {
                 strength,
                 passphrase,
+                language,
+                attest,
+                fresh_attestation,
             } => {
                 info!("🔑 Generating seed phrase (strength: {} bits)", strength);
 
                 match seed_generator
-                    .generate_seed(strength, passphrase.as_deref())
+                    .generate_seed_in(
+                        language,
+                        strength,
+                        passphrase.as_ref().map(|p| p.expose_secret()),
+                    )
                     .await
                 {
                     Ok(seed_result) => {
                         info!("✅ Seed phrase generated successfully");
-                        EnclaveResult::SeedGenerated {
+                        let response = GenerateSeedResponse {
                             seed_phrase: seed_result.phrase,
                             entropy: seed_result.entropy,
                             strength: seed_result.strength,
                             word_count: seed_result.word_count,
+                            attestation: None,
+                        };
+                        match Self::attest_response(attest, fresh_attestation, &response, attester)
+                            .await
+                        {
+                            Ok(attestation) => EnclaveResult::SeedGenerated {
+                                seed_phrase: response.seed_phrase,
+                                entropy: response.entropy,
+                                strength: response.strength,
+                                word_count: response.word_count,
+                                attestation,
+                            },
+                            Err(e) => {
+                                error!("❌ Failed to attest seed phrase: {}", e);
+                                EnclaveResult::Error {
+                                    message: format!("Seed attestation failed: {}", e),
+                                    code: operation_error_code(&e),
+                                }
+                            }
                         }
                     }
                     Err(e) => {
                         error!("❌ Failed to generate seed phrase: {}", e);
                         EnclaveResult::Error {
                             message: format!("Seed generation failed: {}", e),
-                            code: 500,
+                            code: operation_error_code(&e),
                         }
                     }
                 }
             }
 
-            EnclaveOperation::ValidateSeed { seed_phrase } => {
+            EnclaveOperation::ValidateSeed {
+                seed_phrase,
+                language,
+                entropy,
+            } => {
                 info!("🔍 Validating seed phrase");
 
-                match seed_generator.validate_seed(&seed_phrase).await {
-                    Ok(is_valid) => {
+                let result = seed_generator
+                    .validate_seed_in(language, seed_phrase.expose_secret())
+                    .await
+                    .and_then(|is_valid| {
+                        let entropy_match = entropy
+                            .map(|entropy| {
+                                seed_generator.entropy_matches(
+                                    language,
+                                    seed_phrase.expose_secret(),
+                                    entropy.expose_secret(),
+                                )
+                            })
+                            .transpose()?;
+                        Ok((is_valid, entropy_match))
+                    });
+
+                match result {
+                    Ok((is_valid, entropy_match)) => {
                         info!("✅ Seed phrase validation completed");
                         EnclaveResult::SeedValidated {
                             valid: is_valid,
-                            word_count: seed_phrase.split_whitespace().count(),
+                            word_count: seed_phrase.expose_secret().split_whitespace().count(),
+                            entropy_match,
                         }
                     }
                     Err(e) => {
                         error!("❌ Failed to validate seed phrase: {}", e);
                         EnclaveResult::Error {
                             message: format!("Seed validation failed: {}", e),
-                            code: 500,
+                            code: RenclaveErrorCode::Internal,
                         }
                     }
                 }
@@ -324,20 +1012,50 @@ impl NitroEnclave {
             EnclaveOperation::GetInfo => {
                 info!("ℹ️  Providing enclave information");
 
-                let _network_status = network_manager.get_status().await;
-                let capabilities = vec![
-                    "seed_generation".to_string(),
-                    "bip39_compliance".to_string(),
-                    "secure_entropy".to_string(),
-                    "network_connectivity".to_string(),
-                    "key_derivation".to_string(),
-                    "address_derivation".to_string(),
-                ];
+                let (mut capabilities, mut unavailable_capabilities) =
+                    network_manager.state().partition_capabilities(vec![
+                        "seed_generation".to_string(),
+                        "bip39_compliance".to_string(),
+                        "secure_entropy".to_string(),
+                        "network_connectivity".to_string(),
+                        "key_derivation".to_string(),
+                        "address_derivation".to_string(),
+                        "xpub_export".to_string(),
+                        "psbt_signing".to_string(),
+                        "signing_policy".to_string(),
+                        "signed_metrics".to_string(),
+                        "signature_verification".to_string(),
+                    ]);
+
+                let (runtime, unavailable_runtime) = network_manager.runtime_capabilities();
+                capabilities.extend(runtime);
+                unavailable_capabilities.extend(unavailable_runtime);
+
+                if let Err(e) = seed_generator.entropy().health() {
+                    warn!("⚠️  Reporting entropy capabilities as unavailable: {}", e);
+                    let (unhealthy, healthy): (Vec<String>, Vec<String>) = capabilities
+                        .into_iter()
+                        .partition(|c| entropy::ENTROPY_CAPABILITIES.contains(&c.as_str()));
+                    capabilities = healthy;
+                    unavailable_capabilities.extend(unhealthy);
+                }
 
                 EnclaveResult::Info {
                     version: env!("CARGO_PKG_VERSION").to_string(),
-                    enclave_id: enclave_id.to_string(),
+                    enclave_id: attester.enclave_id().to_string(),
                     capabilities,
+                    unavailable_capabilities,
+                    protocol_version: PROTOCOL_VERSION,
+                    operations: SUPPORTED_OPERATIONS
+                        .iter()
+                        .filter(|op| threshold.is_some() || **op != "ThresholdSigning")
+                        .map(|op| op.to_string())
+                        .collect(),
+                    uptime_secs: metrics.uptime().as_secs(),
+                    build: build_info::build_info(),
+                    tls_fingerprint: tls.map(TlsIdentity::fingerprint),
+                    time: Some(clock.now()),
+                    hardening: hardening::status(),
                 }
             }
 
@@ -345,25 +1063,50 @@ impl NitroEnclave {
                 seed_phrase,
                 path,
                 curve,
+                attest,
+                fresh_attestation,
             } => {
                 info!("🔑 Deriving key (path: {}, curve: {})", path, curve);
 
-                match seed_generator.derive_key(&seed_phrase, &path, &curve).await {
+                match seed_generator
+                    .derive_key(seed_phrase.expose_secret(), &path, &curve)
+                    .await
+                {
                     Ok(key_result) => {
                         info!("✅ Key derivation successful");
-                        EnclaveResult::KeyDerived {
+                        let response = DeriveKeyResponse {
                             private_key: key_result.private_key,
                             public_key: key_result.public_key,
                             address: key_result.address,
                             path,
                             curve,
+                            attestation: None,
+                        };
+                        match Self::attest_response(attest, fresh_attestation, &response, attester)
+                            .await
+                        {
+                            Ok(attestation) => EnclaveResult::KeyDerived {
+                                private_key: response.private_key,
+                                public_key: response.public_key,
+                                address: response.address,
+                                path: response.path,
+                                curve: response.curve,
+                                attestation,
+                            },
+                            Err(e) => {
+                                error!("❌ Failed to attest derived key: {}", e);
+                                EnclaveResult::Error {
+                                    message: format!("Key attestation failed: {}", e),
+                                    code: operation_error_code(&e),
+                                }
+                            }
                         }
                     }
                     Err(e) => {
                         error!("❌ Failed to derive key: {}", e);
                         EnclaveResult::Error {
                             message: format!("Key derivation failed: {}", e),
-                            code: 500,
+                            code: operation_error_code(&e),
                         }
                     }
                 }
@@ -373,11 +1116,12 @@ impl NitroEnclave {
                 seed_phrase,
                 path,
                 curve,
+                chain,
             } => {
                 info!("📍 Deriving address (path: {}, curve: {})", path, curve);
 
                 match seed_generator
-                    .derive_address(&seed_phrase, &path, &curve)
+                    .derive_address(seed_phrase.expose_secret(), &path, &curve, chain)
                     .await
                 {
                     Ok(address_result) => {
@@ -386,28 +1130,300 @@ impl NitroEnclave {
                             address: address_result.address,
                             path,
                             curve,
+                            chain,
                         }
                     }
                     Err(e) => {
                         error!("❌ Failed to derive address: {}", e);
                         EnclaveResult::Error {
                             message: format!("Address derivation failed: {}", e),
-                            code: 500,
+                            code: operation_error_code(&e),
                         }
                     }
                 }
             }
-        };
 
-        EnclaveResponse::new(request.id, result)
+            EnclaveOperation::DeriveXpub {
+                seed_phrase,
+                path,
+                format,
+            } => {
+                info!("🧾 Deriving extended public key (path: {})", path);
+
+                match seed_generator
+                    .derive_xpub(seed_phrase.expose_secret(), &path, format)
+                    .await
+                {
+                    Ok(xpub_result) => {
+                        info!("✅ Extended public key derivation successful");
+                        EnclaveResult::XpubDerived {
+                            xpub: xpub_result.xpub,
+                            path,
+                            format,
+                            master_fingerprint: xpub_result.master_fingerprint,
+                        }
+                    }
+                    Err(e) => {
+                        error!("❌ Failed to derive extended public key: {}", e);
+                        EnclaveResult::Error {
+                            message: format!("Extended public key derivation failed: {}", e),
+                            code: operation_error_code(&e),
+                        }
+                    }
+                }
+            }
+
+            EnclaveOperation::SignPsbt { seed_phrase, psbt } => {
+                info!("✍️  Signing PSBT ({} bytes)", psbt.len());
+
+                match seed_generator
+                    .sign_psbt(seed_phrase.expose_secret(), &psbt)
+                    .await
+                {
+                    Ok(signing_result) => {
+                        info!("✅ PSBT signing successful");
+                        EnclaveResult::PsbtSigned {
+                            psbt: signing_result.psbt,
+                            signed_inputs: signing_result.signed_inputs,
+                            retiring_keys: signing_result.retiring_keys,
+                        }
+                    }
+                    Err(e) => {
+                        error!("❌ Failed to sign PSBT: {}", e);
+                        EnclaveResult::Error {
+                            message: format!("PSBT signing failed: {}", e),
+                            code: operation_error_code(&e),
+                        }
+                    }
+                }
+            }
+
+            EnclaveOperation::GetPolicy => {
+                info!("📜 Providing signing policy");
+                EnclaveResult::Policy {
+                    policy: seed_generator.signing_policy().policy(),
+                }
+            }
+
+            EnclaveOperation::GetKeyUsage => {
+                info!("🔑 Providing key usage");
+                EnclaveResult::KeyUsageReport {
+                    keys: seed_generator.signing_policy().key_usage(&clock.now()),
+                }
+            }
+
+            EnclaveOperation::SetPolicy { policy } => {
+                info!("📜 Updating signing policy");
+
+                match seed_generator.signing_policy().set_policy(policy) {
+                    Ok(()) => EnclaveResult::Policy {
+                        policy: seed_generator.signing_policy().policy(),
+                    },
+                    Err(e) => {
+                        error!("❌ Failed to update signing policy: {}", e);
+                        EnclaveResult::Error {
+                            message: format!("Policy update failed: {}", e),
+                            code: e.code(),
+                        }
+                    }
+                }
+            }
+
+            EnclaveOperation::GetMetricsSnapshot => {
+                info!("📊 Providing signed metrics snapshot");
+
+                match metrics.current_snapshot().await {
+                    Ok(snapshot) => EnclaveResult::MetricsSnapshot { snapshot },
+                    Err(e) => {
+                        error!("❌ Failed to snapshot metrics: {}", e);
+                        EnclaveResult::Error {
+                            message: format!("Metrics snapshot failed: {}", e),
+                            code: RenclaveErrorCode::CryptoFailure,
+                        }
+                    }
+                }
+            }
+
+            EnclaveOperation::RepairNetwork => {
+                info!("🔧 Network repair requested");
+
+                EnclaveResult::NetworkRepaired {
+                    state: network_manager.repair().await,
+                    unavailable: network_manager.reconfiguration_disabled(),
+                }
+            }
+
+            EnclaveOperation::VerifySignature {
+                curve,
+                public_key,
+                message,
+                signature,
+            } => {
+                info!("🔍 Verifying {} signature", curve);
+
+                match verify::verify_signature(&curve, &public_key, &message, &signature) {
+                    Ok(valid) => EnclaveResult::SignatureVerified { valid },
+                    Err(e) => {
+                        warn!("⚠️  Cannot verify signature: {}", e);
+                        EnclaveResult::Error {
+                            message: format!("Signature verification failed: {}", e),
+                            code: RenclaveErrorCode::InvalidRequest,
+                        }
+                    }
+                }
+            }
+
+            EnclaveOperation::SetLogLevel { level: None } => EnclaveResult::LogLevel {
+                level: renclave_shared::redact::log_level(),
+            },
+
+            EnclaveOperation::SetLogLevel { level: Some(level) } => {
+                match renclave_shared::redact::set_log_level(&level) {
+                    Ok(level) => {
+                        info!("📝 Log level set to {}", level);
+                        EnclaveResult::LogLevel {
+                            level: renclave_shared::redact::log_level(),
+                        }
+                    }
+                    Err(e) => EnclaveResult::Error {
+                        message: e,
+                        code: RenclaveErrorCode::InvalidRequest,
+                    },
+                }
+            }
+
+            EnclaveOperation::CreateTlsCsr { domains } => {
+                let Some(tls) = tls else {
+                    return Self::tls_disabled();
+                };
+                info!("🔐 Creating TLS certificate request for {:?}", domains);
+                match tls.csr(&domains) {
+                    Ok(csr) => EnclaveResult::TlsCsr { csr },
+                    Err(e) => EnclaveResult::Error {
+                        message: format!("{:#}", e),
+                        code: RenclaveErrorCode::InvalidRequest,
+                    },
+                }
+            }
+
+            EnclaveOperation::InstallTlsCertificate { certificate_chain } => {
+                let Some(tls) = tls else {
+                    return Self::tls_disabled();
+                };
+                match tls.install(&certificate_chain) {
+                    Ok((fingerprint, not_after)) => {
+                        info!(
+                            "✅ Serving TLS certificate {}, valid until {}",
+                            fingerprint, not_after
+                        );
+                        EnclaveResult::TlsCertificate {
+                            fingerprint,
+                            not_after,
+                        }
+                    }
+                    Err(e) => {
+                        warn!("⚠️  Rejected TLS certificate: {:#}", e);
+                        EnclaveResult::Error {
+                            message: format!("{:#}", e),
+                            code: RenclaveErrorCode::InvalidRequest,
+                        }
+                    }
+                }
+            }
+
+            EnclaveOperation::SyncTime { unix_ms } => {
+                debug!("🕒 Host offered time {} ms", unix_ms);
+                let accepted = clock.sync_host(unix_ms);
+                EnclaveResult::TimeSynced {
+                    accepted,
+                    time: clock.now(),
+                }
+            }
+
+            EnclaveOperation::ThresholdSigning { message } => {
+                let Some(threshold) = threshold else {
+                    return EnclaveResult::Error {
+                        message: "Threshold signing is not enabled in this enclave build"
+                            .to_string(),
+                        code: RenclaveErrorCode::UnsupportedOperation,
+                    };
+                };
+                match threshold.handle(message) {
+                    Ok(session) => EnclaveResult::ThresholdSession { session },
+                    Err(e) => {
+                        warn!("⚠️  Threshold signing message rejected: {}", e);
+                        EnclaveResult::Error {
+                            message: e.to_string(),
+                            code: e.code(),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Answer for TLS operations when the enclave does not terminate TLS
+    fn tls_disabled() -> EnclaveResult {
+        EnclaveResult::Error {
+            message: format!(
+                "The enclave does not terminate TLS; set {}",
+                renclave_shared::ENCLAVE_TLS_SOCKET_ENV
+            ),
+            code: RenclaveErrorCode::NotProvisioned,
+        }
+    }
+}
+
+/// Decode a hex attestation nonce of at most `MAX_ATTESTATION_NONCE_BYTES`
+fn parse_nonce(value: &str) -> Result<Vec<u8>, String> {
+    let nonce = hex::decode(value.trim()).map_err(|e| e.to_string())?;
+    if nonce.len() > MAX_ATTESTATION_NONCE_BYTES {
+        return Err(format!(
+            "{} bytes exceeds the limit of {}",
+            nonce.len(),
+            MAX_ATTESTATION_NONCE_BYTES
+        ));
+    }
+    Ok(nonce)
+}
+
+/// Rejected paths, chain/curve mismatches and unusable PSBTs are client errors, policy
+/// violations are refusals; anything else is an enclave failure
+fn operation_error_code(error: &anyhow::Error) -> RenclaveErrorCode {
+    if let Some(violation) = error.downcast_ref::<signing_policy::PolicyViolation>() {
+        violation.code()
+    } else if error.downcast_ref::<entropy::EntropyError>().is_some()
+        || error
+            .downcast_ref::<attestation::AttestationThrottled>()
+            .is_some()
+    {
+        RenclaveErrorCode::Unavailable
+    } else if error.downcast_ref::<derivation::PathError>().is_some()
+        || error.downcast_ref::<address::AddressError>().is_some()
+        || error.downcast_ref::<psbt::PsbtError>().is_some()
+    {
+        RenclaveErrorCode::InvalidRequest
+    } else {
+        RenclaveErrorCode::Internal
     }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    std::env::set_var("RUST_LOG", "debug");
-    env_logger::init();
+    // Initialize logging at the RUST_LOG level. The inner logger passes everything so
+    // SetLogLevel can raise the level later.
+    let env = || env_logger::Env::default().default_filter_or("info");
+    let max_level = env_logger::Builder::from_env(env()).build().filter();
+    let logger = env_logger::Builder::from_env(env())
+        .filter_level(log::LevelFilter::Trace)
+        .build();
+    renclave_shared::redact::init_logger(Box::new(logger), max_level)?;
+
+    // Export operation traces when an OTLP endpoint is configured
+    let tracer_provider = renclave_shared::telemetry::init_tracing("renclave-enclave")?;
+    if tracer_provider.is_some() {
+        info!("🔭 Exporting traces over OTLP");
+    }
 
     info!("🔒 QEMU Nitro Enclave - Secure Seed Generation");
     info!("🔍 Process ID: {}", std::process::id());
@@ -416,9 +1432,30 @@ async fn main() -> anyhow::Result<()> {
         std::env::current_dir()?
     );
 
+    // Before any secret exists, so none can be swapped out or dumped
+    hardening::harden(hardening::required_from_env())?;
+
+    // Checked before initialization so a typo does not leave the enclave unfiltered
+    let sandbox_mode = sandbox::SandboxMode::from_env()?;
+    let run_as = privileges::RunAs::from_env()?;
+
     // Create and start enclave
     let enclave = NitroEnclave::new().await?;
-    enclave.start().await?;
+    // Network setup is done; request processing needs no privileges, but nothing may change
+    // the network any more
+    if let Some(run_as) = run_as {
+        enclave
+            .network_manager
+            .disable_reconfiguration(format!("enclave runs unprivileged as uid {}", run_as.uid));
+    }
+    privileges::drop_privileges(run_as)?;
+    sandbox::apply(sandbox_mode)?;
+    let result = enclave.start().await;
 
-    Ok(())
+    if let Some(provider) = tracer_provider {
+        if let Err(e) = provider.shutdown() {
+            warn!("⚠️  Failed to flush traces: {}", e);
+        }
+    }
+    result
 }
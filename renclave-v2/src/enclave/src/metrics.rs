@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use rand::RngCore;
+use renclave_shared::{MetricsSnapshot, SignedMetricsSnapshot};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::clock::TrustedClock;
+
+/// Environment variable controlling the periodic snapshot interval (seconds, 0 disables)
+pub const SNAPSHOT_INTERVAL_ENV: &str = "RENCLAVE_METRICS_SNAPSHOT_INTERVAL_SECS";
+
+/// Operation counters kept inside the enclave and exported as signed snapshots.
+///
+/// The signing key is generated at startup and never leaves the enclave, so a snapshot can
+/// only be produced by this enclave instance. Snapshots are hash-chained through
+/// `previous_hash` and carry a monotonically increasing sequence number.
+pub struct EnclaveMetrics {
+    enclave_id: String,
+    clock: Arc<TrustedClock>,
+    counters: std::sync::Mutex<BTreeMap<String, u64>>,
+    signing_key: SecretKey,
+    public_key: PublicKey,
+    latest: Mutex<Option<SignedMetricsSnapshot>>,
+    periodic: AtomicBool,
+    started: Instant,
+}
+
+impl EnclaveMetrics {
+    /// Create metrics registry with a fresh in-enclave signing key
+    pub fn new(enclave_id: String, clock: Arc<TrustedClock>) -> Self {
+        let signing_key = loop {
+            let mut bytes = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            if let Ok(key) = SecretKey::from_slice(&bytes) {
+                break key;
+            }
+        };
+        Self::with_signing_key(enclave_id, clock, signing_key)
+    }
+
+    /// Create metrics registry with a fixed signing key, for reproducible snapshots in tests
+    #[cfg(feature = "deterministic-test")]
+    #[allow(dead_code)]
+    pub fn with_fixed_key(
+        enclave_id: String,
+        clock: Arc<TrustedClock>,
+        signing_key: SecretKey,
+    ) -> Self {
+        Self::with_signing_key(enclave_id, clock, signing_key)
+    }
+
+    fn with_signing_key(
+        enclave_id: String,
+        clock: Arc<TrustedClock>,
+        signing_key: SecretKey,
+    ) -> Self {
+        let secp = Secp256k1::new();
+        let public_key = PublicKey::from_secret_key(&secp, &signing_key);
+
+        info!(
+            "📊 Metrics signing key initialized: {}",
+            hex::encode(public_key.serialize())
+        );
+
+        Self {
+            enclave_id,
+            clock,
+            counters: std::sync::Mutex::new(BTreeMap::new()),
+            signing_key,
+            public_key,
+            latest: Mutex::new(None),
+            periodic: AtomicBool::new(false),
+            started: Instant::now(),
+        }
+    }
+
+    /// Hex-encoded compressed public key used to sign snapshots
+    pub fn public_key(&self) -> String {
+        hex::encode(self.public_key.serialize())
+    }
+
+    /// Time since the registry, and with it the enclave, was created
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Record the outcome of a processed operation
+    pub fn record_operation(&self, operation: &str, success: bool) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+
+        *counters.entry("operations_total".to_string()).or_default() += 1;
+        *counters.entry(format!("{}.count", operation)).or_default() += 1;
+        if !success {
+            *counters.entry("errors_total".to_string()).or_default() += 1;
+            *counters.entry(format!("{}.errors", operation)).or_default() += 1;
+        }
+    }
+
+    /// Take a new snapshot of the counters, chain it to the previous one and sign it
+    pub async fn snapshot(&self) -> Result<SignedMetricsSnapshot> {
+        let counters = self
+            .counters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        let mut latest = self.latest.lock().await;
+        let (sequence, previous_hash) = match latest.as_ref() {
+            Some(previous) => (
+                previous.snapshot.sequence + 1,
+                previous.snapshot_hash.clone(),
+            ),
+            None => (0, hex::encode([0u8; 32])),
+        };
+
+        let now = self.clock.now();
+        let snapshot = MetricsSnapshot {
+            enclave_id: self.enclave_id.clone(),
+            sequence,
+            timestamp: now.unix_secs(),
+            counters,
+            previous_hash,
+            timestamp_uncertainty_ms: now.uncertainty_ms,
+        };
+
+        let digest = snapshot.digest().context("Failed to encode snapshot")?;
+        let secp = Secp256k1::new();
+        let message = Message::from_digest(digest);
+        let signature = secp.sign_ecdsa(&message, &self.signing_key);
+
+        let signed = SignedMetricsSnapshot {
+            snapshot,
+            snapshot_hash: hex::encode(digest),
+            signature: hex::encode(signature.serialize_compact()),
+            public_key: self.public_key(),
+        };
+
+        debug!("📊 Metrics snapshot {} signed", signed.snapshot.sequence);
+        *latest = Some(signed.clone());
+        Ok(signed)
+    }
+
+    /// Snapshot to hand out on request.
+    ///
+    /// With periodic snapshots enabled this is the latest periodic snapshot, so external
+    /// systems observe a steady sequence; otherwise a fresh snapshot is taken.
+    pub async fn current_snapshot(&self) -> Result<SignedMetricsSnapshot> {
+        if self.periodic.load(Ordering::Relaxed) {
+            if let Some(snapshot) = self.latest.lock().await.clone() {
+                return Ok(snapshot);
+            }
+        }
+        self.snapshot().await
+    }
+
+    /// Periodically snapshot counters in the background
+    pub fn spawn_periodic_snapshots(self: &Arc<Self>, interval: Duration) {
+        info!("📊 Signing metrics snapshots every {:?}", interval);
+        self.periodic.store(true, Ordering::Relaxed);
+
+        let metrics = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = metrics.snapshot().await {
+                    warn!("⚠️  Failed to take metrics snapshot: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Read the snapshot interval from the environment
+    pub fn snapshot_interval_from_env() -> Option<Duration> {
+        std::env::var(SNAPSHOT_INTERVAL_ENV)
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ClockConfig;
+
+    fn clock() -> Arc<TrustedClock> {
+        Arc::new(TrustedClock::new(ClockConfig::default()))
+    }
+
+    #[tokio::test]
+    async fn test_record_operation_counters() {
+        let metrics = EnclaveMetrics::new("test-enclave".to_string(), clock());
+        metrics.record_operation("generate_seed", true);
+        metrics.record_operation("generate_seed", false);
+        metrics.record_operation("get_info", true);
+
+        let snapshot = metrics.snapshot().await.unwrap().snapshot;
+        assert_eq!(snapshot.counters["operations_total"], 3);
+        assert_eq!(snapshot.counters["errors_total"], 1);
+        assert_eq!(snapshot.counters["generate_seed.count"], 2);
+        assert_eq!(snapshot.counters["generate_seed.errors"], 1);
+        assert_eq!(snapshot.counters["get_info.count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_signature_verifies() {
+        let metrics = EnclaveMetrics::new("test-enclave".to_string(), clock());
+        metrics.record_operation("derive_key", true);
+
+        let signed = metrics.snapshot().await.unwrap();
+        assert_eq!(signed.public_key, metrics.public_key());
+        assert!(signed.verify());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_snapshot_fails_verification() {
+        let metrics = EnclaveMetrics::new("test-enclave".to_string(), clock());
+        metrics.record_operation("derive_key", true);
+
+        let mut signed = metrics.snapshot().await.unwrap();
+        signed
+            .snapshot
+            .counters
+            .insert("derive_key.count".to_string(), 0);
+        assert!(!signed.verify());
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_are_chained() {
+        let metrics = EnclaveMetrics::new("test-enclave".to_string(), clock());
+
+        let first = metrics.snapshot().await.unwrap();
+        let second = metrics.snapshot().await.unwrap();
+
+        assert_eq!(first.snapshot.sequence, 0);
+        assert_eq!(first.snapshot.previous_hash, hex::encode([0u8; 32]));
+        assert_eq!(second.snapshot.sequence, 1);
+        assert_eq!(second.snapshot.previous_hash, first.snapshot_hash);
+        assert!(second.follows(&first));
+        assert!(!first.follows(&second));
+        assert!(!metrics.snapshot().await.unwrap().follows(&first));
+    }
+
+    #[tokio::test]
+    async fn test_current_snapshot_reuses_periodic_snapshot() {
+        let metrics = Arc::new(EnclaveMetrics::new("test-enclave".to_string(), clock()));
+
+        let fresh = metrics.current_snapshot().await.unwrap();
+        let next = metrics.current_snapshot().await.unwrap();
+        assert_eq!(next.snapshot.sequence, fresh.snapshot.sequence + 1);
+
+        metrics.spawn_periodic_snapshots(Duration::from_secs(3600));
+        let periodic = metrics.current_snapshot().await.unwrap();
+        let again = metrics.current_snapshot().await.unwrap();
+        assert_eq!(periodic.snapshot_hash, again.snapshot_hash);
+    }
+}
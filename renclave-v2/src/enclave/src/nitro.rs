@@ -1,5 +1,6 @@
 use anyhow::Result;
 use log::{debug, info, warn};
+use renclave_shared::{ChannelAttestation, EnclaveTime};
 use std::fs;
 use std::process::Command;
 
@@ -51,22 +52,21 @@ impl NitroAttestation {
     pub async fn generate_attestation_document(
         &self,
         user_data: Option<&[u8]>,
+        nonce: Option<&[u8]>,
+        now: &EnclaveTime,
     ) -> Result<AttestationDocument> {
         info!("📋 Generating attestation document");
 
         // In real Nitro Enclaves, this would use aws-nitro-enclaves-cose
         // For QEMU testing, we'll create a mock document
 
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
         let document = AttestationDocument {
             enclave_id: self.enclave_id.clone(),
             measurements: self.measurements.clone(),
-            timestamp,
+            timestamp: now.unix_secs(),
+            timestamp_uncertainty_ms: now.uncertainty_ms,
             user_data: user_data.map(|data| data.to_vec()),
+            nonce: nonce.map(|nonce| nonce.to_vec()),
             signature: "mock_signature_for_qemu_testing".to_string(),
         };
 
@@ -143,10 +143,42 @@ pub struct AttestationDocument {
     pub enclave_id: String,
     pub measurements: NitroMeasurements,
     pub timestamp: u64,
+    /// Uncertainty of `timestamp` by the enclave's clock, if it was synchronized
+    pub timestamp_uncertainty_ms: Option<u64>,
     pub user_data: Option<Vec<u8>>,
+    pub nonce: Option<Vec<u8>>,
     pub signature: String,
 }
 
+impl AttestationDocument {
+    /// Wire form of the document for a channel handshake
+    pub fn to_channel_attestation(&self) -> ChannelAttestation {
+        let measurements = [
+            ("pcr0", &self.measurements.pcr0),
+            ("pcr1", &self.measurements.pcr1),
+            ("pcr2", &self.measurements.pcr2),
+            ("pcr3", &self.measurements.pcr3),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect();
+
+        ChannelAttestation {
+            enclave_id: self.enclave_id.clone(),
+            timestamp: self.timestamp,
+            measurements,
+            user_data: self
+                .user_data
+                .as_deref()
+                .map(hex::encode)
+                .unwrap_or_default(),
+            nonce: self.nonce.as_deref().map(hex::encode),
+            signature: self.signature.clone(),
+            timestamp_uncertainty_ms: self.timestamp_uncertainty_ms,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct EnclaveEnvironment {
@@ -0,0 +1,218 @@
+use anyhow::{anyhow, bail, Context, Result};
+use log::{info, warn};
+
+/// Environment variable naming the unprivileged `user[:group]` the enclave switches to once
+/// network setup is done. Names are looked up in `/etc/passwd` and `/etc/group`; numeric ids
+/// are used as given.
+pub const RUN_AS_ENV: &str = "RENCLAVE_RUN_AS";
+
+const PASSWD_PATH: &str = "/etc/passwd";
+const GROUP_PATH: &str = "/etc/group";
+const STATUS_PATH: &str = "/proc/self/status";
+
+/// User and group the request-processing phase runs as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunAs {
+    pub uid: libc::uid_t,
+    pub gid: libc::gid_t,
+}
+
+impl RunAs {
+    /// Target from the environment, `None` when unset
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var(RUN_AS_ENV) {
+            Ok(value) if !value.trim().is_empty() => {
+                let passwd = std::fs::read_to_string(PASSWD_PATH).unwrap_or_default();
+                let group = std::fs::read_to_string(GROUP_PATH).unwrap_or_default();
+                Self::parse(&value, &passwd, &group).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolve `user[:group]` against the contents of `/etc/passwd` and `/etc/group`.
+    /// Without a group, the user's primary group from `/etc/passwd` is used.
+    fn parse(value: &str, passwd: &str, group: &str) -> Result<Self> {
+        let (user, group_name) = match value.trim().split_once(':') {
+            Some((user, group)) => (user.trim(), Some(group.trim())),
+            None => (value.trim(), None),
+        };
+
+        let entry = passwd_entry(passwd, user);
+        let uid = match user.parse::<libc::uid_t>() {
+            Ok(uid) => uid,
+            Err(_) => entry
+                .map(|(uid, _)| uid)
+                .ok_or_else(|| anyhow!("Invalid {}: unknown user '{}'", RUN_AS_ENV, user))?,
+        };
+        let gid = match group_name {
+            Some(name) => match name.parse::<libc::gid_t>() {
+                Ok(gid) => gid,
+                Err(_) => group_id(group, name)
+                    .ok_or_else(|| anyhow!("Invalid {}: unknown group '{}'", RUN_AS_ENV, name))?,
+            },
+            None => entry.map(|(_, gid)| gid).ok_or_else(|| {
+                anyhow!(
+                    "Invalid {}: user '{}' has no /etc/passwd entry, specify user:group",
+                    RUN_AS_ENV,
+                    user
+                )
+            })?,
+        };
+
+        if uid == 0 || gid == 0 {
+            bail!("Invalid {}: '{}' is not unprivileged", RUN_AS_ENV, value);
+        }
+        Ok(Self { uid, gid })
+    }
+}
+
+/// `(uid, gid)` of a user by name or numeric id
+fn passwd_entry(passwd: &str, user: &str) -> Option<(libc::uid_t, libc::gid_t)> {
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() < 4 || (fields[0] != user && fields[2] != user) {
+            return None;
+        }
+        Some((fields[2].parse().ok()?, fields[3].parse().ok()?))
+    })
+}
+
+fn group_id(group: &str, name: &str) -> Option<libc::gid_t> {
+    group.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.len() >= 3 && fields[0] == name)
+            .then(|| fields[2].parse().ok())
+            .flatten()
+    })
+}
+
+/// Switch every thread of the process to `run_as` and drop all capabilities.
+///
+/// Called once `NetworkManager` has configured the interface, and before the seccomp filter,
+/// which does not allow the id-changing syscalls. Moving all user ids away from 0 clears the
+/// permitted, effective and ambient capability sets, so nothing can be regained afterwards.
+/// Network repair, DHCP lease renewal and the connectivity monitor need those capabilities, so
+/// the caller disables them first with `NetworkManager::disable_reconfiguration`. The enclave
+/// socket directories must be writable by the new user.
+pub fn drop_privileges(run_as: Option<RunAs>) -> Result<()> {
+    let Some(run_as) = run_as else {
+        // SAFETY: geteuid has no preconditions
+        if unsafe { libc::geteuid() } == 0 {
+            warn!(
+                "⚠️  Enclave keeps running as root; set {} to drop privileges",
+                RUN_AS_ENV
+            );
+        }
+        return Ok(());
+    };
+
+    // glibc applies these to every thread of the process, including the runtime's workers
+    // SAFETY: the calls only read the group list passed in and take plain ids
+    unsafe {
+        if libc::setgroups(1, &run_as.gid) != 0 {
+            bail!(
+                "Failed to set supplementary groups: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+        if libc::setresgid(run_as.gid, run_as.gid, run_as.gid) != 0 {
+            bail!(
+                "Failed to switch to group {}: {}",
+                run_as.gid,
+                std::io::Error::last_os_error()
+            );
+        }
+        if libc::setresuid(run_as.uid, run_as.uid, run_as.uid) != 0 {
+            bail!(
+                "Failed to switch to user {}: {}",
+                run_as.uid,
+                std::io::Error::last_os_error()
+            );
+        }
+        // Changing credentials resets the dumpable flag to the fs.suid_dumpable default
+        libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0);
+
+        if libc::setuid(0) == 0 {
+            bail!("Privilege drop did not stick: root could be regained");
+        }
+    }
+
+    let status = std::fs::read_to_string(STATUS_PATH)
+        .with_context(|| format!("Failed to read {}", STATUS_PATH))?;
+    for set in ["CapPrm", "CapEff", "CapAmb"] {
+        match capabilities(&status, set) {
+            Some(0) => {}
+            Some(mask) => bail!("Capabilities still held after privilege drop: {set} {mask:#x}"),
+            None => bail!("{} has no {} line", STATUS_PATH, set),
+        }
+    }
+
+    info!(
+        "👤 Dropped root: running as uid {} gid {} without capabilities",
+        run_as.uid, run_as.gid
+    );
+    Ok(())
+}
+
+/// Capability mask of a set such as `CapEff` in `/proc/<pid>/status`
+fn capabilities(status: &str, set: &str) -> Option<u64> {
+    status.lines().find_map(|line| {
+        let (name, mask) = line.split_once(':')?;
+        (name == set)
+            .then(|| u64::from_str_radix(mask.trim(), 16).ok())
+            .flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = "root:x:0:0:root:/root:/bin/sh\n\
+                          renclave:x:1000:1000::/home/renclave:/sbin/nologin\n";
+    const GROUP: &str = "root:x:0:\nrenclave:x:1000:\nnogroup:x:65534:\n";
+
+    #[test]
+    fn test_run_as_parsing() {
+        let run_as = |value| RunAs::parse(value, PASSWD, GROUP);
+        assert_eq!(
+            run_as("renclave").unwrap(),
+            RunAs {
+                uid: 1000,
+                gid: 1000
+            }
+        );
+        assert_eq!(
+            run_as("renclave:nogroup").unwrap(),
+            RunAs {
+                uid: 1000,
+                gid: 65534
+            }
+        );
+        assert_eq!(
+            run_as("2000:3000").unwrap(),
+            RunAs {
+                uid: 2000,
+                gid: 3000
+            }
+        );
+        assert_eq!(run_as("1000").unwrap().gid, 1000);
+
+        assert!(run_as("root").is_err());
+        assert!(run_as("renclave:root").is_err());
+        assert!(run_as("missing").is_err());
+        assert!(run_as("renclave:missing").is_err());
+        // A numeric uid without a passwd entry needs an explicit group
+        assert!(run_as("2000").is_err());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let status = "Name:\trenclave-enclave\nCapInh:\t0000000000000000\n\
+                      CapPrm:\t000001ffffffffff\nCapEff:\t0000000000000000\n";
+        assert_eq!(capabilities(status, "CapPrm"), Some(0x1ff_ffff_ffff));
+        assert_eq!(capabilities(status, "CapEff"), Some(0));
+        assert_eq!(capabilities(status, "CapAmb"), None);
+    }
+}
@@ -0,0 +1,352 @@
+use bitcoin::bip32::{DerivationPath, Fingerprint, Xpriv};
+use bitcoin::psbt::{Input, Output, Psbt, SigningKeys};
+use bitcoin::{CompressedPublicKey, ScriptBuf, TxOut};
+use log::{debug, warn};
+use secp256k1::{PublicKey, Secp256k1, Signing, Verification};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use crate::derivation::{DerivationPolicy, PathError};
+
+/// Reasons a PSBT cannot be signed
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum PsbtError {
+    #[error("Invalid PSBT: {0}")]
+    Invalid(String),
+
+    #[error("No PSBT input is derived from this seed")]
+    NoMatchingInputs,
+
+    #[error("Failed to sign input {index}: {reason}")]
+    SigningFailed { index: usize, reason: String },
+}
+
+/// Inputs signed by `sign` and the keys that signed them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedInputs {
+    pub inputs: Vec<usize>,
+    /// Hex public keys, compressed for ECDSA and x-only for Taproot
+    pub keys: Vec<String>,
+}
+
+/// Parse a base64-encoded PSBT
+pub fn parse(psbt: &str) -> Result<Psbt, PsbtError> {
+    Psbt::from_str(psbt.trim()).map_err(|e| PsbtError::Invalid(e.to_string()))
+}
+
+/// Whether any key origin of the input belongs to the master key with `fingerprint`
+fn is_own_input(input: &Input, fingerprint: Fingerprint) -> bool {
+    input
+        .bip32_derivation
+        .values()
+        .any(|(fp, _)| *fp == fingerprint)
+        || input
+            .tap_key_origins
+            .values()
+            .any(|(_, (fp, _))| *fp == fingerprint)
+}
+
+/// Check every derivation path the PSBT asks this seed to sign with against the policy
+pub fn check_key_origins(
+    psbt: &Psbt,
+    fingerprint: Fingerprint,
+    policy: &DerivationPolicy,
+) -> Result<(), PathError> {
+    for input in &psbt.inputs {
+        let bip32_paths = input.bip32_derivation.values().map(|(fp, path)| (fp, path));
+        let taproot_paths = input
+            .tap_key_origins
+            .values()
+            .map(|(_, (fp, path))| (fp, path));
+
+        for (_, path) in bip32_paths
+            .chain(taproot_paths)
+            .filter(|(fp, _)| **fp == fingerprint)
+        {
+            policy.check(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Sign every input whose key origins match `master_key`, returning the signed inputs.
+///
+/// ECDSA signatures are added to `partial_sigs` and Taproot key-path signatures to
+/// `tap_key_sig`. Inputs belonging to other signers are left untouched.
+pub fn sign<C: Signing + Verification>(
+    psbt: &mut Psbt,
+    master_key: &Xpriv,
+    secp: &Secp256k1<C>,
+) -> Result<SignedInputs, PsbtError> {
+    let fingerprint = master_key.fingerprint(secp);
+
+    let (used, errors) = match psbt.sign(master_key, secp) {
+        Ok(used) => (used, Default::default()),
+        Err((used, errors)) => (used, errors),
+    };
+
+    for (index, error) in errors {
+        if is_own_input(&psbt.inputs[index], fingerprint) {
+            warn!("⚠️  Failed to sign PSBT input {}: {}", index, error);
+            return Err(PsbtError::SigningFailed {
+                index,
+                reason: error.to_string(),
+            });
+        }
+        debug!("⏭️  Skipping PSBT input {}: {}", index, error);
+    }
+
+    let mut inputs = Vec::new();
+    let mut keys = BTreeSet::new();
+    for (index, signing_keys) in used {
+        let used: Vec<String> = match signing_keys {
+            SigningKeys::Ecdsa(used) => used.iter().map(|key| key.to_string()).collect(),
+            SigningKeys::Schnorr(used) => used.iter().map(|key| key.to_string()).collect(),
+        };
+        if !used.is_empty() {
+            inputs.push(index);
+            keys.extend(used);
+        }
+    }
+
+    if inputs.is_empty() {
+        return Err(PsbtError::NoMatchingInputs);
+    }
+    Ok(SignedInputs {
+        inputs,
+        keys: keys.into_iter().collect(),
+    })
+}
+
+/// Indices of outputs that provably pay back to `master_key`.
+///
+/// A key origin alone is not trusted: the origin path must derive the listed key and the
+/// output script must pay to that key (P2WPKH, P2SH-P2WPKH, P2PKH or key-path P2TR).
+pub fn change_outputs<C: Signing + Verification>(
+    psbt: &Psbt,
+    master_key: &Xpriv,
+    secp: &Secp256k1<C>,
+) -> Vec<usize> {
+    let fingerprint = master_key.fingerprint(secp);
+
+    psbt.outputs
+        .iter()
+        .zip(&psbt.unsigned_tx.output)
+        .enumerate()
+        .filter(|(_, (output, txout))| {
+            pays_to_own_ecdsa_key(output, txout, master_key, fingerprint, secp)
+                || pays_to_own_taproot_key(output, txout, master_key, fingerprint, secp)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn derive_public_key<C: Signing>(
+    master_key: &Xpriv,
+    path: &DerivationPath,
+    secp: &Secp256k1<C>,
+) -> Option<PublicKey> {
+    let mut child_key = master_key.derive_priv(secp, path).ok()?;
+    let public_key = child_key.private_key.public_key(secp);
+    child_key.private_key.non_secure_erase();
+    Some(public_key)
+}
+
+fn pays_to_own_ecdsa_key<C: Signing>(
+    output: &Output,
+    txout: &TxOut,
+    master_key: &Xpriv,
+    fingerprint: Fingerprint,
+    secp: &Secp256k1<C>,
+) -> bool {
+    output
+        .bip32_derivation
+        .iter()
+        .filter(|(_, (fp, _))| *fp == fingerprint)
+        .any(|(key, (_, path))| {
+            if derive_public_key(master_key, path, secp) != Some(*key) {
+                return false;
+            }
+            let p2wpkh = ScriptBuf::new_p2wpkh(&CompressedPublicKey(*key).wpubkey_hash());
+            let p2sh_p2wpkh = ScriptBuf::new_p2sh(&p2wpkh.script_hash());
+            let p2pkh = ScriptBuf::new_p2pkh(&bitcoin::PublicKey::new(*key).pubkey_hash());
+            [p2wpkh, p2sh_p2wpkh, p2pkh].contains(&txout.script_pubkey)
+        })
+}
+
+fn pays_to_own_taproot_key<C: Signing + Verification>(
+    output: &Output,
+    txout: &TxOut,
+    master_key: &Xpriv,
+    fingerprint: Fingerprint,
+    secp: &Secp256k1<C>,
+) -> bool {
+    output
+        .tap_key_origins
+        .iter()
+        .filter(|(_, (_, (fp, _)))| *fp == fingerprint)
+        .any(|(key, (_, (_, path)))| {
+            derive_public_key(master_key, path, secp).map(|k| k.x_only_public_key().0) == Some(*key)
+                && output.tap_internal_key == Some(*key)
+                && output.tap_tree.is_none()
+                && txout.script_pubkey == ScriptBuf::new_p2tr(secp, *key, None)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::absolute::LockTime;
+    use bitcoin::bip32::DerivationPath;
+    use bitcoin::hashes::Hash;
+    use bitcoin::transaction::Version;
+    use bitcoin::{
+        Amount, CompressedPublicKey, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid,
+        Witness,
+    };
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn master_key() -> Xpriv {
+        let seed = bip39::Mnemonic::parse(MNEMONIC).unwrap().to_seed("");
+        Xpriv::new_master(bitcoin::Network::Bitcoin, &seed).unwrap()
+    }
+
+    /// PSBT spending a P2WPKH output at m/84'/0'/0'/0/0 and a P2TR output at m/86'/0'/0'/0/0
+    fn test_psbt(master_key: &Xpriv) -> Psbt {
+        let secp = Secp256k1::new();
+        let fingerprint = master_key.fingerprint(&secp);
+
+        let input = |vout| TxIn {
+            previous_output: OutPoint {
+                txid: Txid::all_zeros(),
+                vout,
+            },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::new(),
+        };
+
+        let segwit_path = DerivationPath::from_str("m/84'/0'/0'/0/0").unwrap();
+        let segwit_key = master_key
+            .derive_priv(&secp, &segwit_path)
+            .unwrap()
+            .private_key
+            .public_key(&secp);
+        let segwit_script = ScriptBuf::new_p2wpkh(&CompressedPublicKey(segwit_key).wpubkey_hash());
+
+        let taproot_path = DerivationPath::from_str("m/86'/0'/0'/0/0").unwrap();
+        let (taproot_key, _) = master_key
+            .derive_priv(&secp, &taproot_path)
+            .unwrap()
+            .private_key
+            .x_only_public_key(&secp);
+        let taproot_script = ScriptBuf::new_p2tr(&secp, taproot_key, None);
+
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![input(0), input(1)],
+            output: vec![TxOut {
+                value: Amount::from_sat(190_000),
+                script_pubkey: segwit_script.clone(),
+            }],
+        };
+
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: Amount::from_sat(100_000),
+            script_pubkey: segwit_script,
+        });
+        psbt.inputs[0]
+            .bip32_derivation
+            .insert(segwit_key, (fingerprint, segwit_path));
+
+        psbt.inputs[1].witness_utxo = Some(TxOut {
+            value: Amount::from_sat(100_000),
+            script_pubkey: taproot_script,
+        });
+        psbt.inputs[1].tap_internal_key = Some(taproot_key);
+        psbt.inputs[1]
+            .tap_key_origins
+            .insert(taproot_key, (vec![], (fingerprint, taproot_path)));
+
+        psbt
+    }
+
+    #[test]
+    fn test_signs_segwit_and_taproot_inputs() {
+        let master_key = master_key();
+        let mut psbt = parse(&test_psbt(&master_key).to_string()).unwrap();
+
+        let signed = sign(&mut psbt, &master_key, &Secp256k1::new()).unwrap();
+
+        assert_eq!(signed.inputs, vec![0, 1]);
+        assert_eq!(signed.keys.len(), 2);
+        assert_eq!(psbt.inputs[0].partial_sigs.len(), 1);
+        assert!(psbt.inputs[1].tap_key_sig.is_some());
+    }
+
+    #[test]
+    fn test_foreign_psbt_is_rejected() {
+        let other_seed = [7u8; 32];
+        let other_key = Xpriv::new_master(bitcoin::Network::Bitcoin, &other_seed).unwrap();
+        let mut psbt = test_psbt(&other_key);
+
+        assert_eq!(
+            sign(&mut psbt, &master_key(), &Secp256k1::new()),
+            Err(PsbtError::NoMatchingInputs)
+        );
+    }
+
+    #[test]
+    fn test_key_origins_follow_policy() {
+        let master_key = master_key();
+        let psbt = test_psbt(&master_key);
+        let fingerprint = master_key.fingerprint(&Secp256k1::new());
+
+        let policy = DerivationPolicy {
+            hardened_only: false,
+            templates: DerivationPolicy::parse_templates("m/84'/0'/*'/0/*").unwrap(),
+        };
+        assert_eq!(
+            check_key_origins(&psbt, fingerprint, &policy),
+            Err(PathError::NotAllowed("86'/0'/0'/0/0".to_string()))
+        );
+        assert!(check_key_origins(&psbt, fingerprint, &DerivationPolicy::default()).is_ok());
+    }
+
+    #[test]
+    fn test_change_outputs_must_derive_to_the_script() {
+        let master_key = master_key();
+        let secp = Secp256k1::new();
+        let fingerprint = master_key.fingerprint(&secp);
+        let mut psbt = test_psbt(&master_key);
+        let (key, _) = psbt.inputs[0].bip32_derivation.iter().next().unwrap();
+        let key = *key;
+
+        // Origin points at a different path than the key in the output script
+        psbt.outputs[0].bip32_derivation.insert(
+            key,
+            (
+                fingerprint,
+                DerivationPath::from_str("m/84'/0'/0'/0/1").unwrap(),
+            ),
+        );
+        assert!(change_outputs(&psbt, &master_key, &secp).is_empty());
+
+        psbt.outputs[0].bip32_derivation.insert(
+            key,
+            (
+                fingerprint,
+                DerivationPath::from_str("m/84'/0'/0'/0/0").unwrap(),
+            ),
+        );
+        assert_eq!(change_outputs(&psbt, &master_key, &secp), vec![0]);
+    }
+
+    #[test]
+    fn test_invalid_psbt() {
+        assert!(matches!(parse("not a psbt"), Err(PsbtError::Invalid(_))));
+    }
+}
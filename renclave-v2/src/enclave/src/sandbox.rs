@@ -0,0 +1,318 @@
+use anyhow::{bail, Result};
+use log::{info, warn};
+
+/// Environment variable selecting the seccomp mode: `off`, `audit` or `enforce`
+pub const SECCOMP_MODE_ENV: &str = "RENCLAVE_SECCOMP";
+
+/// How the seccomp filter treats syscalls outside the allowlist
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SandboxMode {
+    /// No filter is installed
+    #[default]
+    Off,
+    /// Disallowed syscalls still run and are logged by the kernel audit log
+    Audit,
+    /// Disallowed syscalls kill the process
+    Enforce,
+}
+
+impl SandboxMode {
+    /// Mode from the environment, `Off` when unset
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(SECCOMP_MODE_ENV) {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Self::Off),
+        }
+    }
+}
+
+impl std::str::FromStr for SandboxMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "off" => Ok(Self::Off),
+            "audit" => Ok(Self::Audit),
+            "enforce" => Ok(Self::Enforce),
+            other => bail!(
+                "Invalid {} '{}': expected off, audit or enforce",
+                SECCOMP_MODE_ENV,
+                other
+            ),
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xC000_003E;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xC000_00B7;
+
+/// Offsets into `struct seccomp_data`
+const SYSCALL_NR_OFFSET: u32 = 0;
+const ARCH_OFFSET: u32 = 4;
+
+/// Syscalls the enclave needs once initialized: the async runtime, socket IO, memory
+/// management, the TLS stack, and the `ip`, `ping` and `nft` commands the network monitor runs.
+/// Children inherit the filter, so their syscalls are listed too. Left out are, among others,
+/// ptrace, mount, module loading, kexec, bpf, perf events, keyrings, namespaces, swapon and
+/// cross-process memory access.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn allowed_syscalls() -> Vec<libc::c_long> {
+    let mut syscalls = vec![
+        // File and socket IO
+        libc::SYS_read,
+        libc::SYS_write,
+        libc::SYS_readv,
+        libc::SYS_writev,
+        libc::SYS_pread64,
+        libc::SYS_pwrite64,
+        libc::SYS_openat,
+        libc::SYS_close,
+        libc::SYS_lseek,
+        libc::SYS_fstat,
+        libc::SYS_newfstatat,
+        libc::SYS_statx,
+        libc::SYS_statfs,
+        libc::SYS_fstatfs,
+        libc::SYS_readlinkat,
+        libc::SYS_getdents64,
+        libc::SYS_faccessat,
+        libc::SYS_faccessat2,
+        libc::SYS_unlinkat,
+        libc::SYS_mkdirat,
+        libc::SYS_fchmodat,
+        libc::SYS_fcntl,
+        libc::SYS_ioctl,
+        libc::SYS_dup,
+        libc::SYS_dup3,
+        libc::SYS_pipe2,
+        libc::SYS_fsync,
+        libc::SYS_getcwd,
+        libc::SYS_chdir,
+        libc::SYS_socket,
+        libc::SYS_socketpair,
+        libc::SYS_connect,
+        libc::SYS_accept4,
+        libc::SYS_bind,
+        libc::SYS_listen,
+        libc::SYS_shutdown,
+        libc::SYS_sendto,
+        libc::SYS_recvfrom,
+        libc::SYS_sendmsg,
+        libc::SYS_recvmsg,
+        libc::SYS_sendmmsg,
+        libc::SYS_recvmmsg,
+        libc::SYS_getsockopt,
+        libc::SYS_setsockopt,
+        libc::SYS_getsockname,
+        libc::SYS_getpeername,
+        // Event loop
+        libc::SYS_epoll_create1,
+        libc::SYS_epoll_ctl,
+        libc::SYS_epoll_pwait,
+        libc::SYS_eventfd2,
+        libc::SYS_ppoll,
+        libc::SYS_pselect6,
+        // Memory
+        libc::SYS_brk,
+        libc::SYS_mmap,
+        libc::SYS_munmap,
+        libc::SYS_mprotect,
+        libc::SYS_mremap,
+        libc::SYS_madvise,
+        libc::SYS_mlock,
+        libc::SYS_munlock,
+        // Threads, signals and time
+        libc::SYS_futex,
+        libc::SYS_clone,
+        libc::SYS_clone3,
+        libc::SYS_set_robust_list,
+        libc::SYS_rseq,
+        libc::SYS_sched_yield,
+        libc::SYS_sched_getaffinity,
+        libc::SYS_rt_sigaction,
+        libc::SYS_rt_sigprocmask,
+        libc::SYS_rt_sigreturn,
+        libc::SYS_sigaltstack,
+        libc::SYS_tgkill,
+        libc::SYS_kill,
+        libc::SYS_nanosleep,
+        libc::SYS_clock_nanosleep,
+        libc::SYS_clock_gettime,
+        libc::SYS_gettimeofday,
+        libc::SYS_getrandom,
+        libc::SYS_exit,
+        libc::SYS_exit_group,
+        // Process information and child processes
+        libc::SYS_getpid,
+        libc::SYS_getppid,
+        libc::SYS_gettid,
+        libc::SYS_getuid,
+        libc::SYS_geteuid,
+        libc::SYS_getgid,
+        libc::SYS_getegid,
+        libc::SYS_uname,
+        libc::SYS_prctl,
+        libc::SYS_prlimit64,
+        libc::SYS_getrlimit,
+        libc::SYS_setrlimit,
+        libc::SYS_sysinfo,
+        libc::SYS_execve,
+        libc::SYS_wait4,
+        libc::SYS_waitid,
+        libc::SYS_set_tid_address,
+    ];
+    #[cfg(target_arch = "x86_64")]
+    syscalls.extend([
+        libc::SYS_open,
+        libc::SYS_stat,
+        libc::SYS_lstat,
+        libc::SYS_access,
+        libc::SYS_readlink,
+        libc::SYS_unlink,
+        libc::SYS_pipe,
+        libc::SYS_dup2,
+        libc::SYS_poll,
+        libc::SYS_select,
+        libc::SYS_accept,
+        libc::SYS_epoll_wait,
+        libc::SYS_epoll_create,
+        libc::SYS_arch_prctl,
+        libc::SYS_vfork,
+        libc::SYS_fork,
+        libc::SYS_time,
+    ]);
+    syscalls
+}
+
+/// BPF program that allows `allowed_syscalls` and applies the mode's action to the rest
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn filter(mode: SandboxMode) -> Vec<libc::sock_filter> {
+    let denied = match mode {
+        SandboxMode::Enforce => libc::SECCOMP_RET_KILL_PROCESS,
+        SandboxMode::Audit | SandboxMode::Off => libc::SECCOMP_RET_LOG,
+    };
+    let load = |offset| instruction(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, offset, 0, 0);
+    let ret = |action| instruction(libc::BPF_RET | libc::BPF_K, action, 0, 0);
+    let jeq = libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K;
+
+    // Syscall numbers differ by architecture, so any other architecture is denied outright
+    let mut program = vec![
+        load(ARCH_OFFSET),
+        instruction(jeq, AUDIT_ARCH, 1, 0),
+        ret(denied),
+        load(SYSCALL_NR_OFFSET),
+    ];
+    for syscall in allowed_syscalls() {
+        program.push(instruction(jeq, syscall as u32, 0, 1));
+        program.push(ret(libc::SECCOMP_RET_ALLOW));
+    }
+    program.push(ret(denied));
+    program
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+/// BPF instruction; `jt` and `jf` are the jump offsets of conditional jumps
+fn instruction(code: u32, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter {
+        code: code as u16,
+        jt,
+        jf,
+        k,
+    }
+}
+
+/// Install the seccomp filter for every thread of the process.
+///
+/// Called once initialization is done, so setup steps such as creating the TAP device are not
+/// restricted. The filter cannot be removed afterwards.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub fn apply(mode: SandboxMode) -> Result<()> {
+    if mode == SandboxMode::Off {
+        info!(
+            "🧱 Seccomp filter disabled; set {} to enable it",
+            SECCOMP_MODE_ENV
+        );
+        return Ok(());
+    }
+
+    let mut program = filter(mode);
+    let prog = libc::sock_fprog {
+        len: program.len() as u16,
+        filter: program.as_mut_ptr(),
+    };
+    // SAFETY: `prog` points at `program`, which outlives both calls; the kernel copies it
+    unsafe {
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+            bail!(
+                "Failed to set no_new_privs: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+        if libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            libc::SECCOMP_FILTER_FLAG_TSYNC,
+            &prog as *const libc::sock_fprog,
+        ) != 0
+        {
+            bail!(
+                "Failed to install seccomp filter: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    match mode {
+        SandboxMode::Enforce => info!(
+            "🧱 Seccomp filter enforced: {} syscalls allowed",
+            allowed_syscalls().len()
+        ),
+        _ => warn!(
+            "🧱 Seccomp filter in audit mode: disallowed syscalls are logged to the kernel audit log, not blocked"
+        ),
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn apply(mode: SandboxMode) -> Result<()> {
+    if mode != SandboxMode::Off {
+        bail!("Seccomp filtering is not supported on this architecture");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_parsing() {
+        assert_eq!("".parse::<SandboxMode>().unwrap(), SandboxMode::Off);
+        assert_eq!("Audit".parse::<SandboxMode>().unwrap(), SandboxMode::Audit);
+        assert_eq!(
+            " enforce ".parse::<SandboxMode>().unwrap(),
+            SandboxMode::Enforce
+        );
+        assert!("strict".parse::<SandboxMode>().is_err());
+    }
+
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    #[test]
+    fn test_filter_denies_by_mode() {
+        let syscalls = allowed_syscalls();
+        assert!(!syscalls.contains(&libc::SYS_ptrace));
+        assert!(!syscalls.contains(&libc::SYS_init_module));
+
+        let enforce = filter(SandboxMode::Enforce);
+        assert_eq!(enforce.len(), 4 + 2 * syscalls.len() + 1);
+        assert!(enforce.len() <= u16::MAX as usize);
+        assert_eq!(enforce.last().unwrap().k, libc::SECCOMP_RET_KILL_PROCESS);
+        assert_eq!(enforce[2].k, libc::SECCOMP_RET_KILL_PROCESS);
+
+        let audit = filter(SandboxMode::Audit);
+        assert_eq!(audit.last().unwrap().k, libc::SECCOMP_RET_LOG);
+    }
+}
@@ -1,29 +1,42 @@
 use anyhow::{anyhow, Result};
 use bip39::{Language, Mnemonic};
-use bitcoin::bip32::{DerivationPath, Xpriv, Xpub};
+use bitcoin::bip32::{ChildNumber, DerivationPath, Xpriv, Xpub};
+use bitcoin::hashes::{hmac, sha512, Hash, HashEngine};
+use ed25519_dalek::SigningKey;
 use log::{debug, info, warn};
-use rand::{RngCore, SeedableRng};
+use renclave_shared::validation::Strength;
+use renclave_shared::{Chain, KeyUsage, SecretBytes, SecretString, SeedLanguage, XpubFormat};
 use secp256k1::Secp256k1;
-use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use crate::address;
+use crate::clock::TrustedClock;
+use crate::derivation::{DerivationPolicy, PathError};
+use crate::entropy::EntropyPool;
+use crate::psbt;
+use crate::signing_policy::PolicyEngine;
 
 /// Secure seed phrase generator for Nitro Enclave
 pub struct SeedGenerator {
-    rng: Arc<Mutex<rand::rngs::StdRng>>,
+    entropy: EntropyPool,
+    derivation_policy: DerivationPolicy,
+    signing_policy: PolicyEngine,
+    clock: Arc<TrustedClock>,
 }
 
 #[derive(Debug, Clone)]
 pub struct SeedResult {
-    pub phrase: String,
-    pub entropy: String,
+    pub phrase: SecretString,
+    pub entropy: SecretString,
     pub strength: u32,
     pub word_count: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct KeyDerivationResult {
-    pub private_key: String,
+    pub private_key: SecretString,
     pub public_key: String,
     pub address: String,
 }
@@ -33,29 +46,133 @@ pub struct AddressDerivationResult {
     pub address: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct PsbtSigningResult {
+    pub psbt: String,
+    pub signed_inputs: Vec<usize>,
+    /// Signing keys now due for retirement
+    pub retiring_keys: Vec<KeyUsage>,
+}
+
+#[derive(Debug, Clone)]
+pub struct XpubDerivationResult {
+    pub xpub: String,
+    pub master_fingerprint: String,
+}
+
+/// SLIP-132 version bytes for the base58 extended public key formats
+fn xpub_version(format: XpubFormat) -> [u8; 4] {
+    match format {
+        XpubFormat::Xpub | XpubFormat::Raw => [0x04, 0x88, 0xb2, 0x1e],
+        XpubFormat::Ypub => [0x04, 0x9d, 0x7c, 0xb2],
+        XpubFormat::Zpub => [0x04, 0xb2, 0x47, 0x46],
+    }
+}
+
+/// bip39 wordlist for a seed language
+fn bip39_language(language: SeedLanguage) -> Language {
+    match language {
+        SeedLanguage::English => Language::English,
+        SeedLanguage::ChineseSimplified => Language::SimplifiedChinese,
+        SeedLanguage::ChineseTraditional => Language::TraditionalChinese,
+        SeedLanguage::Czech => Language::Czech,
+        SeedLanguage::French => Language::French,
+        SeedLanguage::Italian => Language::Italian,
+        SeedLanguage::Japanese => Language::Japanese,
+        SeedLanguage::Korean => Language::Korean,
+        SeedLanguage::Portuguese => Language::Portuguese,
+        SeedLanguage::Spanish => Language::Spanish,
+    }
+}
+
+/// NFKD-normalize a mnemonic in any supported language and parse it.
+///
+/// Words shared between wordlists can make the language ambiguous; each candidate is then
+/// tried and the one whose checksum verifies wins.
+fn parse_mnemonic(phrase: &str) -> Result<Mnemonic> {
+    match Mnemonic::parse(phrase) {
+        Err(bip39::Error::AmbiguousLanguages(candidates)) => candidates
+            .iter()
+            .find_map(|language| Mnemonic::parse_in(language, phrase).ok())
+            .ok_or_else(|| anyhow!("Invalid mnemonic: ambiguous word list")),
+        result => result.map_err(|e| anyhow!("Invalid mnemonic: {}", e)),
+    }
+}
+
 impl SeedGenerator {
-    /// Create new seed generator with secure entropy
-    pub async fn new() -> Result<Self> {
+    /// Create new seed generator with secure entropy, checking signing policy times against
+    /// `clock`
+    pub async fn new(clock: Arc<TrustedClock>) -> Result<Self> {
         info!("🌱 Initializing secure seed generator in Nitro Enclave");
 
-        // Initialize with hardware entropy
-        let rng = rand::rngs::StdRng::from_entropy();
-        debug!("🔐 Initialized RNG with hardware entropy");
+        // Initialize the health-tested entropy pool
+        let entropy = EntropyPool::from_env()?;
+        debug!("🔐 Initialized entropy pool");
+
+        let derivation_policy = DerivationPolicy::from_env()?;
+        if derivation_policy.hardened_only || !derivation_policy.templates.is_empty() {
+            info!(
+                "🧭 Derivation policy: hardened only: {}, {} allowed path templates",
+                derivation_policy.hardened_only,
+                derivation_policy.templates.len()
+            );
+        }
+
+        let signing_policy = PolicyEngine::from_env()?;
 
         Ok(Self {
-            rng: Arc::new(Mutex::new(rng)),
+            entropy,
+            derivation_policy,
+            signing_policy,
+            clock,
         })
     }
 
-    /// Generate secure seed phrase
+    /// Health-tested entropy pool used for seed generation
+    pub fn entropy(&self) -> &EntropyPool {
+        &self.entropy
+    }
+
+    /// Policy enforced on every signed transaction
+    pub fn signing_policy(&self) -> &PolicyEngine {
+        &self.signing_policy
+    }
+
+    /// Replace the entropy pool read from the environment
+    #[allow(dead_code)]
+    pub fn with_entropy(mut self, entropy: EntropyPool) -> Self {
+        self.entropy = entropy;
+        self
+    }
+
+    /// Replace the derivation policy read from the environment
+    #[allow(dead_code)]
+    pub fn with_derivation_policy(mut self, derivation_policy: DerivationPolicy) -> Self {
+        self.derivation_policy = derivation_policy;
+        self
+    }
+
+    /// Generate secure English seed phrase
+    #[allow(dead_code)]
     pub async fn generate_seed(
         &self,
         strength: u32,
         passphrase: Option<&str>,
+    ) -> Result<SeedResult> {
+        self.generate_seed_in(SeedLanguage::English, strength, passphrase)
+            .await
+    }
+
+    /// Generate secure seed phrase from the given BIP-39 wordlist
+    pub async fn generate_seed_in(
+        &self,
+        language: SeedLanguage,
+        strength: u32,
+        passphrase: Option<&str>,
     ) -> Result<SeedResult> {
         info!(
-            "🔑 Generating secure seed phrase (strength: {} bits)",
-            strength
+            "🔑 Generating secure seed phrase (strength: {} bits, language: {:?})",
+            strength, language
         );
 
         // Validate strength
@@ -67,17 +184,17 @@ impl SeedGenerator {
         debug!("🎲 Generated {} bytes of entropy", entropy.len());
 
         // Create BIP39 mnemonic
-        let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy)
+        let mnemonic = Mnemonic::from_entropy_in(bip39_language(language), entropy.expose_secret())
             .map_err(|e| anyhow!("Failed to create mnemonic: {}", e))?;
 
-        let phrase = mnemonic.to_string();
+        let phrase = SecretString::new(mnemonic.to_string());
         debug!(
             "📝 Generated mnemonic with {} words",
-            phrase.split_whitespace().count()
+            phrase.expose_secret().split_whitespace().count()
         );
 
         // Validate word count
-        let actual_word_count = phrase.split_whitespace().count();
+        let actual_word_count = phrase.expose_secret().split_whitespace().count();
         if actual_word_count != word_count {
             return Err(anyhow!(
                 "Word count mismatch: expected {}, got {}",
@@ -89,14 +206,14 @@ impl SeedGenerator {
         // Apply passphrase if provided
         let final_phrase = if let Some(pass) = passphrase {
             info!("🔐 Applying passphrase to seed phrase");
-            format!("{} {}", phrase, pass)
+            SecretString::new(format!("{} {}", phrase.expose_secret(), pass))
         } else {
             phrase
         };
 
         let result = SeedResult {
             phrase: final_phrase,
-            entropy: hex::encode(&entropy),
+            entropy: SecretString::new(hex::encode(entropy.expose_secret())),
             strength,
             word_count: actual_word_count,
         };
@@ -110,9 +227,24 @@ impl SeedGenerator {
         Ok(result)
     }
 
-    /// Validate existing seed phrase
+    /// Validate existing English seed phrase
+    #[allow(dead_code)]
     pub async fn validate_seed(&self, seed_phrase: &str) -> Result<bool> {
-        info!("🔍 Validating seed phrase");
+        self.validate_seed_in(SeedLanguage::English, seed_phrase)
+            .await
+    }
+
+    /// Validate existing seed phrase against the given BIP-39 wordlist.
+    ///
+    /// The phrase is NFKD-normalized first, so composed and decomposed accents and
+    /// ideographic spaces are accepted.
+    pub async fn validate_seed_in(
+        &self,
+        language: SeedLanguage,
+        seed_phrase: &str,
+    ) -> Result<bool> {
+        info!("🔍 Validating seed phrase (language: {:?})", language);
+        let language = bip39_language(language);
 
         if seed_phrase.trim().is_empty() {
             warn!("⚠️  Empty seed phrase provided");
@@ -129,7 +261,7 @@ impl SeedGenerator {
         debug!("🔍 Validating {} words", words.len());
 
         // Try to parse as BIP39 mnemonic
-        match Mnemonic::parse_in_normalized(Language::English, seed_phrase) {
+        match Mnemonic::parse_in(language, seed_phrase) {
             Ok(_) => {
                 info!("✅ Seed phrase is valid BIP39 mnemonic");
                 Ok(true)
@@ -140,7 +272,7 @@ impl SeedGenerator {
                 // If it fails, try without the last word (might be passphrase)
                 if words.len() > 12 {
                     let without_last = words[..words.len() - 1].join(" ");
-                    match Mnemonic::parse_in_normalized(Language::English, &without_last) {
+                    match Mnemonic::parse_in(language, without_last) {
                         Ok(_) => {
                             info!("✅ Seed phrase is valid BIP39 mnemonic (with passphrase)");
                             Ok(true)
@@ -158,48 +290,62 @@ impl SeedGenerator {
         }
     }
 
+    /// Whether the seed phrase encodes `expected_entropy`, the hex entropy from generation.
+    ///
+    /// Like `validate_seed`, a passphrase appended as the last word is ignored. A phrase that
+    /// is not a valid mnemonic does not match.
+    pub fn entropy_matches(
+        &self,
+        language: SeedLanguage,
+        seed_phrase: &str,
+        expected_entropy: &str,
+    ) -> Result<bool> {
+        let language = bip39_language(language);
+        let expected = SecretBytes::new(
+            hex::decode(expected_entropy.trim())
+                .map_err(|e| anyhow!("Invalid entropy hex: {}", e))?,
+        );
+
+        let words: Vec<&str> = seed_phrase.split_whitespace().collect();
+        let mnemonic =
+            Mnemonic::parse_in(language, words.join(" ")).or_else(|e| match words.len() {
+                n if n > 12 => Mnemonic::parse_in(language, words[..n - 1].join(" ")),
+                _ => Err(e),
+            });
+        let Ok(mnemonic) = mnemonic else {
+            return Ok(false);
+        };
+
+        let entropy = SecretBytes::new(mnemonic.to_entropy());
+        let matches = entropy.len() == expected.len()
+            && bool::from(entropy.expose_secret().ct_eq(expected.expose_secret()));
+
+        debug!("🔍 Entropy cross-check: match = {}", matches);
+        Ok(matches)
+    }
+
     /// Validate seed strength and return expected word count
     fn validate_strength(&self, strength: u32) -> Result<usize> {
-        let word_count = match strength {
-            128 => 12,
-            160 => 15,
-            192 => 18,
-            224 => 21,
-            256 => 24,
-            _ => {
-                return Err(anyhow!(
-                    "Invalid strength: {}. Must be 128, 160, 192, 224, or 256 bits",
-                    strength
-                ))
-            }
-        };
+        let word_count = Strength::new(strength)
+            .map_err(|e| anyhow!(e.message))?
+            .word_count();
 
         debug!("✅ Strength {} validated -> {} words", strength, word_count);
         Ok(word_count)
     }
 
     /// Generate cryptographically secure entropy
-    async fn generate_entropy(&self, strength: u32) -> Result<Vec<u8>> {
+    async fn generate_entropy(&self, strength: u32) -> Result<SecretBytes> {
         let entropy_bytes = (strength / 8) as usize;
-        let mut entropy = vec![0u8; entropy_bytes];
+        let mut entropy = SecretBytes::zeroed(entropy_bytes);
 
         debug!(
             "🎲 Generating {} bytes of entropy for {} bits",
             entropy_bytes, strength
         );
 
-        // Use secure RNG to generate entropy
-        {
-            let mut rng = self.rng.lock().await;
-            rng.fill_bytes(&mut entropy);
-        }
-
-        // Verify entropy is not all zeros (extremely unlikely but good practice)
-        if entropy.iter().all(|&b| b == 0) {
-            warn!("⚠️  Generated entropy is all zeros, regenerating...");
-            let mut rng = self.rng.lock().await;
-            rng.fill_bytes(&mut entropy);
-        }
+        // Refuses to produce output once any source fails its health tests
+        self.entropy.fill(entropy.expose_secret_mut())?;
 
         debug!("✅ Generated {} bytes of secure entropy", entropy.len());
         Ok(entropy)
@@ -207,27 +353,31 @@ impl SeedGenerator {
 
     /// Get entropy from existing mnemonic (for testing/verification)
     #[allow(dead_code)]
-    pub fn get_entropy_from_mnemonic(&self, mnemonic: &str) -> Result<Vec<u8>> {
-        let mnemonic_obj = Mnemonic::parse_in_normalized(Language::English, mnemonic)
-            .map_err(|e| anyhow!("Invalid mnemonic: {}", e))?;
+    pub fn get_entropy_from_mnemonic(&self, mnemonic: &str) -> Result<SecretBytes> {
+        let mnemonic_obj = parse_mnemonic(mnemonic)?;
 
-        Ok(mnemonic_obj.to_entropy().to_vec())
+        Ok(SecretBytes::new(mnemonic_obj.to_entropy()))
     }
 
     /// Derive seed from mnemonic and passphrase
-    pub async fn derive_seed(&self, mnemonic: &str, passphrase: Option<&str>) -> Result<Vec<u8>> {
+    pub async fn derive_seed(
+        &self,
+        mnemonic: &str,
+        passphrase: Option<&str>,
+    ) -> Result<SecretBytes> {
         info!("🌱 Deriving seed from mnemonic");
 
-        let mnemonic_obj = Mnemonic::parse_in_normalized(Language::English, mnemonic)
-            .map_err(|e| anyhow!("Invalid mnemonic: {}", e))?;
+        let mnemonic_obj = parse_mnemonic(mnemonic)?;
 
         let passphrase = passphrase.unwrap_or("");
 
         // Derive 64-byte seed using PBKDF2
-        let seed = mnemonic_obj.to_seed(passphrase);
+        let mut seed_bytes = mnemonic_obj.to_seed(passphrase);
+        let seed = SecretBytes::from(&seed_bytes[..]);
+        seed_bytes.zeroize();
 
         info!("✅ Derived {}-byte seed from mnemonic", seed.len());
-        Ok(seed.to_vec())
+        Ok(seed)
     }
 
     /// Verify entropy matches mnemonic
@@ -248,20 +398,19 @@ impl SeedGenerator {
     ) -> Result<KeyDerivationResult> {
         info!("🔑 Deriving key (path: {}, curve: {})", path, curve);
 
-        // Parse derivation path
-        let derivation_path = DerivationPath::from_str(path)
-            .map_err(|e| anyhow!("Invalid derivation path: {}", e))?;
+        // Parse and validate derivation path against the policy
+        let derivation_path = self.derivation_policy.validate(path)?;
 
         // Derive seed from mnemonic
         let seed = self.derive_seed(seed_phrase, None).await?;
 
         // Create extended private key
         let secp = Secp256k1::new();
-        let master_key = Xpriv::new_master(bitcoin::Network::Bitcoin, &seed)
+        let mut master_key = Xpriv::new_master(bitcoin::Network::Bitcoin, seed.expose_secret())
             .map_err(|e| anyhow!("Failed to create master key: {}", e))?;
 
         // Derive child key
-        let child_key = master_key
+        let mut child_key = master_key
             .derive_priv(&secp, &derivation_path)
             .map_err(|e| anyhow!("Failed to derive child key: {}", e))?;
 
@@ -275,42 +424,198 @@ impl SeedGenerator {
         );
 
         let result = KeyDerivationResult {
-            private_key: hex::encode(child_key.private_key.secret_bytes()),
+            private_key: SecretString::new(hex::encode(child_key.private_key.secret_bytes())),
             public_key: hex::encode(public_key.public_key.serialize()),
             address,
         };
 
+        // Erase extended private keys; the seed is zeroized when dropped
+        master_key.private_key.non_secure_erase();
+        child_key.private_key.non_secure_erase();
+
         info!("✅ Key derivation successful");
         Ok(result)
     }
 
-    /// Derive address from seed phrase
+    /// Derive address from seed phrase.
+    ///
+    /// Without a `chain` the legacy hex format from `derive_key` is returned.
     pub async fn derive_address(
         &self,
         seed_phrase: &str,
         path: &str,
         curve: &str,
+        chain: Option<Chain>,
     ) -> Result<AddressDerivationResult> {
-        info!("📍 Deriving address (path: {}, curve: {})", path, curve);
+        info!(
+            "📍 Deriving address (path: {}, curve: {}, chain: {})",
+            path,
+            curve,
+            chain.map(|c| c.as_str()).unwrap_or("legacy")
+        );
 
-        // For now, we'll derive the key and return just the address
-        // In production, you might want to optimize this to only derive the public key
-        let key_result = self.derive_key(seed_phrase, path, curve).await?;
+        let Some(chain) = chain else {
+            let key_result = self.derive_key(seed_phrase, path, curve).await?;
+            return Ok(AddressDerivationResult {
+                address: key_result.address,
+            });
+        };
 
-        let result = AddressDerivationResult {
-            address: key_result.address,
+        address::check_curve(chain, curve)?;
+        let derivation_path = self.derivation_policy.validate(path)?;
+        let seed = self.derive_seed(seed_phrase, None).await?;
+
+        let address = if chain.uses_ed25519() {
+            let private_key = derive_ed25519(seed.expose_secret(), &derivation_path)?;
+            let mut key_bytes: [u8; 32] = private_key
+                .expose_secret()
+                .try_into()
+                .map_err(|_| anyhow!("Invalid ed25519 key length"))?;
+            let signing_key = SigningKey::from_bytes(&key_bytes);
+            key_bytes.zeroize();
+
+            address::solana_address(&signing_key.verifying_key().to_bytes())
+        } else {
+            let secp = Secp256k1::new();
+            let mut master_key = Xpriv::new_master(bitcoin::Network::Bitcoin, seed.expose_secret())
+                .map_err(|e| anyhow!("Failed to create master key: {}", e))?;
+            let mut child_key = master_key
+                .derive_priv(&secp, &derivation_path)
+                .map_err(|e| anyhow!("Failed to derive child key: {}", e))?;
+            let public_key = child_key.private_key.public_key(&secp);
+
+            master_key.private_key.non_secure_erase();
+            child_key.private_key.non_secure_erase();
+
+            address::encode_secp256k1(chain, &public_key)?
         };
 
         info!("✅ Address derivation successful");
-        Ok(result)
+        Ok(AddressDerivationResult { address })
+    }
+
+    /// Derive the extended public key at `path` for watch-only wallets.
+    ///
+    /// Only public material leaves the enclave; the path is checked against the derivation
+    /// policy like any other derivation.
+    pub async fn derive_xpub(
+        &self,
+        seed_phrase: &str,
+        path: &str,
+        format: XpubFormat,
+    ) -> Result<XpubDerivationResult> {
+        info!(
+            "🧾 Deriving extended public key (path: {}, format: {:?})",
+            path, format
+        );
+
+        let derivation_path = self.derivation_policy.validate(path)?;
+        let seed = self.derive_seed(seed_phrase, None).await?;
+
+        let secp = Secp256k1::new();
+        let mut master_key = Xpriv::new_master(bitcoin::Network::Bitcoin, seed.expose_secret())
+            .map_err(|e| anyhow!("Failed to create master key: {}", e))?;
+        let mut account_key = master_key
+            .derive_priv(&secp, &derivation_path)
+            .map_err(|e| anyhow!("Failed to derive account key: {}", e))?;
+
+        let master_fingerprint = master_key.fingerprint(&secp).to_string();
+        let account_xpub = Xpub::from_priv(&secp, &account_key);
+
+        master_key.private_key.non_secure_erase();
+        account_key.private_key.non_secure_erase();
+
+        let mut serialized = account_xpub.encode();
+        serialized[..4].copy_from_slice(&xpub_version(format));
+
+        let xpub = match format {
+            XpubFormat::Raw => hex::encode(serialized),
+            _ => bitcoin::base58::encode_check(&serialized),
+        };
+
+        info!("✅ Extended public key derivation successful");
+        Ok(XpubDerivationResult {
+            xpub,
+            master_fingerprint,
+        })
+    }
+
+    /// Sign the inputs of a base64 PSBT whose key origins belong to this seed.
+    ///
+    /// Every key origin path for this seed is checked against the derivation policy before
+    /// anything is signed, and the signed PSBT is only released if it passes the signing policy.
+    pub async fn sign_psbt(&self, seed_phrase: &str, psbt: &str) -> Result<PsbtSigningResult> {
+        info!("✍️  Signing PSBT");
+
+        let mut psbt = psbt::parse(psbt)?;
+        let seed = self.derive_seed(seed_phrase, None).await?;
+
+        let secp = Secp256k1::new();
+        let mut master_key = Xpriv::new_master(bitcoin::Network::Bitcoin, seed.expose_secret())
+            .map_err(|e| anyhow!("Failed to create master key: {}", e))?;
+
+        let fingerprint = master_key.fingerprint(&secp);
+        let signed = match psbt::check_key_origins(&psbt, fingerprint, &self.derivation_policy) {
+            Ok(()) => psbt::sign(&mut psbt, &master_key, &secp).map_err(anyhow::Error::from),
+            Err(e) => Err(e.into()),
+        };
+        let change_outputs = psbt::change_outputs(&psbt, &master_key, &secp);
+        master_key.private_key.non_secure_erase();
+        let signed = signed?;
+
+        let authorization = self.signing_policy.authorize(
+            &psbt,
+            &change_outputs,
+            &signed.keys,
+            &self.clock.now(),
+        )?;
+        debug!(
+            "📜 PSBT sends {} sats to external destinations",
+            authorization.external_sats
+        );
+
+        info!("✅ Signed {} PSBT inputs", signed.inputs.len());
+        Ok(PsbtSigningResult {
+            psbt: psbt.to_string(),
+            signed_inputs: signed.inputs,
+            retiring_keys: authorization.retiring_keys,
+        })
     }
 }
 
+/// SLIP-10 ed25519 derivation; every path component must be hardened
+fn derive_ed25519(seed: &[u8], path: &DerivationPath) -> Result<SecretBytes> {
+    let mut engine = hmac::HmacEngine::<sha512::Hash>::new(b"ed25519 seed");
+    engine.input(seed);
+    let mut node = SecretBytes::new(hmac::Hmac::from_engine(engine).to_byte_array().to_vec());
+
+    for (i, child) in path.into_iter().enumerate() {
+        let ChildNumber::Hardened { index } = *child else {
+            return Err(PathError::Ed25519RequiresHardened(i + 1).into());
+        };
+
+        // I = HMAC-SHA512(chain code, 0x00 || key || ser32(index | 2^31))
+        let (key, chain_code) = node.expose_secret().split_at(32);
+        let mut engine = hmac::HmacEngine::<sha512::Hash>::new(chain_code);
+        engine.input(&[0u8]);
+        engine.input(key);
+        engine.input(&(index | 0x8000_0000).to_be_bytes());
+        node = SecretBytes::new(hmac::Hmac::from_engine(engine).to_byte_array().to_vec());
+    }
+
+    Ok(SecretBytes::new(node.expose_secret()[..32].to_vec()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::ClockConfig;
     use tokio::runtime::Runtime;
 
+    fn clock() -> Arc<TrustedClock> {
+        Arc::new(TrustedClock::new(ClockConfig::default()))
+    }
+
     fn create_test_runtime() -> Runtime {
         tokio::runtime::Builder::new_current_thread()
             .enable_all()
@@ -318,20 +623,53 @@ mod tests {
             .unwrap()
     }
 
+    #[cfg(feature = "deterministic-test")]
+    #[test]
+    fn test_deterministic_seed_golden() {
+        let runtime = create_test_runtime();
+        let generate = |seed| {
+            let generator = runtime
+                .block_on(SeedGenerator::new(clock()))
+                .unwrap()
+                .with_entropy(EntropyPool::deterministic(seed));
+            runtime
+                .block_on(generator.generate_seed(128, None))
+                .unwrap()
+        };
+
+        let first = generate(7);
+        assert_eq!(
+            first.phrase.expose_secret(),
+            "disorder tide fade dash cricket leave lab hip gorilla captain knock rigid"
+        );
+        assert_eq!(
+            first.entropy.expose_secret(),
+            "3f7c39471bd336fddf035f64a445ef5c"
+        );
+        assert_eq!(
+            generate(7).phrase.expose_secret(),
+            first.phrase.expose_secret()
+        );
+        assert_ne!(
+            generate(8).phrase.expose_secret(),
+            first.phrase.expose_secret()
+        );
+    }
+
     #[test]
     fn test_seed_generator_new() {
         let runtime = create_test_runtime();
-        let result = runtime.block_on(SeedGenerator::new());
+        let result = runtime.block_on(SeedGenerator::new(clock()));
 
         assert!(result.is_ok());
         let generator = result.unwrap();
-        assert!(generator.rng.try_lock().is_ok());
+        assert!(generator.entropy().health().is_ok());
     }
 
     #[test]
     fn test_validate_strength() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         // Test valid strengths
         assert_eq!(generator.validate_strength(128).unwrap(), 12);
@@ -349,7 +687,7 @@ mod tests {
     #[test]
     fn test_generate_seed_128_bits() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         let result = runtime.block_on(generator.generate_seed(128, None));
         assert!(result.is_ok());
@@ -357,14 +695,21 @@ mod tests {
         let seed_result = result.unwrap();
         assert_eq!(seed_result.strength, 128);
         assert_eq!(seed_result.word_count, 12);
-        assert_eq!(seed_result.phrase.split_whitespace().count(), 12);
-        assert!(!seed_result.entropy.is_empty());
+        assert_eq!(
+            seed_result
+                .phrase
+                .expose_secret()
+                .split_whitespace()
+                .count(),
+            12
+        );
+        assert!(!seed_result.entropy.expose_secret().is_empty());
     }
 
     #[test]
     fn test_generate_seed_256_bits() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         let result = runtime.block_on(generator.generate_seed(256, None));
         assert!(result.is_ok());
@@ -372,14 +717,21 @@ mod tests {
         let seed_result = result.unwrap();
         assert_eq!(seed_result.strength, 256);
         assert_eq!(seed_result.word_count, 24);
-        assert_eq!(seed_result.phrase.split_whitespace().count(), 24);
-        assert!(!seed_result.entropy.is_empty());
+        assert_eq!(
+            seed_result
+                .phrase
+                .expose_secret()
+                .split_whitespace()
+                .count(),
+            24
+        );
+        assert!(!seed_result.entropy.expose_secret().is_empty());
     }
 
     #[test]
     fn test_generate_seed_with_passphrase() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         let passphrase = "test123";
         let result = runtime.block_on(generator.generate_seed(192, Some(passphrase)));
@@ -388,13 +740,13 @@ mod tests {
         let seed_result = result.unwrap();
         assert_eq!(seed_result.strength, 192);
         assert_eq!(seed_result.word_count, 18);
-        assert!(seed_result.phrase.ends_with(passphrase));
+        assert!(seed_result.phrase.expose_secret().ends_with(passphrase));
     }
 
     #[test]
     fn test_generate_seed_invalid_strength() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         let result = runtime.block_on(generator.generate_seed(100, None));
         assert!(result.is_err());
@@ -406,7 +758,7 @@ mod tests {
     #[test]
     fn test_validate_seed_valid() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         // Valid BIP39 seed phrase (12 words)
         let valid_seed = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
@@ -418,21 +770,103 @@ mod tests {
     #[test]
     fn test_validate_seed_valid_24_words() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         // Generate a valid 24-word seed first
         let seed_result = runtime
             .block_on(generator.generate_seed(256, None))
             .unwrap();
-        let result = runtime.block_on(generator.validate_seed(&seed_result.phrase));
+        let result = runtime.block_on(generator.validate_seed(seed_result.phrase.expose_secret()));
         assert!(result.is_ok());
         assert!(result.unwrap());
     }
 
+    #[test]
+    fn test_entropy_cross_check() {
+        let runtime = create_test_runtime();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
+
+        let seed_result = runtime
+            .block_on(generator.generate_seed(192, Some("test123")))
+            .unwrap();
+        let phrase = seed_result.phrase.expose_secret();
+        assert!(generator
+            .entropy_matches(
+                SeedLanguage::English,
+                phrase,
+                seed_result.entropy.expose_secret()
+            )
+            .unwrap());
+
+        let abandon = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert!(generator
+            .entropy_matches(
+                SeedLanguage::English,
+                abandon,
+                "00000000000000000000000000000000"
+            )
+            .unwrap());
+        assert!(!generator
+            .entropy_matches(
+                SeedLanguage::English,
+                abandon,
+                seed_result.entropy.expose_secret()
+            )
+            .unwrap());
+        assert!(generator
+            .entropy_matches(SeedLanguage::English, abandon, "not hex")
+            .is_err());
+    }
+
+    #[test]
+    fn test_non_english_seed_round_trip() {
+        let runtime = create_test_runtime();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
+
+        for language in [
+            SeedLanguage::Japanese,
+            SeedLanguage::Spanish,
+            SeedLanguage::ChineseTraditional,
+        ] {
+            let seed_result = runtime
+                .block_on(generator.generate_seed_in(language, 128, None))
+                .unwrap();
+            let phrase = seed_result.phrase.expose_secret();
+
+            assert!(runtime
+                .block_on(generator.validate_seed_in(language, phrase))
+                .unwrap());
+            assert!(!runtime.block_on(generator.validate_seed(phrase)).unwrap());
+            // Derivation detects the wordlist on its own
+            assert!(runtime
+                .block_on(generator.derive_seed(phrase, None))
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_validation_normalizes_unicode() {
+        let runtime = create_test_runtime();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
+
+        // BIP-39 Japanese test vector for zero entropy, separated by ideographic spaces
+        let japanese = "あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あおぞら";
+        assert!(runtime
+            .block_on(generator.validate_seed_in(SeedLanguage::Japanese, japanese))
+            .unwrap());
+        assert!(generator
+            .entropy_matches(
+                SeedLanguage::Japanese,
+                japanese,
+                "00000000000000000000000000000000"
+            )
+            .unwrap());
+    }
+
     #[test]
     fn test_validate_seed_invalid() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         let invalid_seed = "invalid seed phrase here";
         let result = runtime.block_on(generator.validate_seed(invalid_seed));
@@ -443,7 +877,7 @@ mod tests {
     #[test]
     fn test_validate_seed_empty() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         let result = runtime.block_on(generator.validate_seed(""));
         assert!(result.is_ok());
@@ -453,7 +887,7 @@ mod tests {
     #[test]
     fn test_validate_seed_whitespace_only() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         let result = runtime.block_on(generator.validate_seed("   \n\t  "));
         assert!(result.is_ok());
@@ -463,7 +897,7 @@ mod tests {
     #[test]
     fn test_generate_entropy() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         // Test different entropy sizes
         let entropy_128 = runtime.block_on(generator.generate_entropy(128)).unwrap();
@@ -476,13 +910,13 @@ mod tests {
     #[test]
     fn test_entropy_not_all_zeros() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         // Generate multiple entropy samples to ensure they're not all zeros
         let mut has_non_zero = false;
         for _ in 0..10 {
             let entropy = runtime.block_on(generator.generate_entropy(256)).unwrap();
-            if entropy.iter().any(|&b| b != 0) {
+            if entropy.expose_secret().iter().any(|&b| b != 0) {
                 has_non_zero = true;
                 break;
             }
@@ -493,7 +927,7 @@ mod tests {
     #[test]
     fn test_seed_phrase_uniqueness() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         let mut phrases = std::collections::HashSet::new();
 
@@ -503,7 +937,7 @@ mod tests {
                 .block_on(generator.generate_seed(256, None))
                 .unwrap();
             assert!(
-                phrases.insert(result.phrase),
+                phrases.insert(result.phrase.expose_secret().to_string()),
                 "Duplicate seed phrase generated"
             );
         }
@@ -512,31 +946,136 @@ mod tests {
     #[test]
     fn test_entropy_hex_encoding() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
         let result = runtime
             .block_on(generator.generate_seed(128, None))
             .unwrap();
 
         // Verify entropy is valid hex
-        assert!(result.entropy.len() == 32); // 128 bits = 16 bytes = 32 hex chars
-        assert!(result.entropy.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(result.entropy.expose_secret().len() == 32); // 128 bits = 16 bytes = 32 hex chars
+        assert!(result
+            .entropy
+            .expose_secret()
+            .chars()
+            .all(|c| c.is_ascii_hexdigit()));
     }
 
     #[test]
     fn test_word_count_consistency() {
         let runtime = create_test_runtime();
-        let generator = runtime.block_on(SeedGenerator::new()).unwrap();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
 
-        let strengths = vec![128, 160, 192, 224, 256];
-        let expected_words = vec![12, 15, 18, 21, 24];
+        let strengths = [128, 160, 192, 224, 256];
+        let expected_words = [12, 15, 18, 21, 24];
 
         for (strength, expected) in strengths.iter().zip(expected_words.iter()) {
             let result = runtime
                 .block_on(generator.generate_seed(*strength, None))
                 .unwrap();
             assert_eq!(result.word_count, *expected);
-            assert_eq!(result.phrase.split_whitespace().count(), *expected);
+            assert_eq!(
+                result.phrase.expose_secret().split_whitespace().count(),
+                *expected
+            );
         }
     }
+
+    /// BIP-39 test mnemonic used by the published address vectors below
+    const VECTOR_MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn derive_vector(path: &str, curve: &str, chain: Chain) -> Result<String> {
+        let runtime = create_test_runtime();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
+        runtime
+            .block_on(generator.derive_address(VECTOR_MNEMONIC, path, curve, Some(chain)))
+            .map(|result| result.address)
+    }
+
+    #[test]
+    fn test_bitcoin_golden_vectors() {
+        // BIP-84 and BIP-86 test vectors
+        assert_eq!(
+            derive_vector("m/84'/0'/0'/0/0", "secp256k1", Chain::Bitcoin).unwrap(),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        assert_eq!(
+            derive_vector("m/86'/0'/0'/0/0", "secp256k1", Chain::BitcoinTaproot).unwrap(),
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+        );
+    }
+
+    #[test]
+    fn test_ethereum_golden_vector() {
+        assert_eq!(
+            derive_vector("m/44'/60'/0'/0/0", "CURVE_SECP256K1", Chain::Ethereum).unwrap(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+    }
+
+    #[test]
+    fn test_solana_golden_vector() {
+        assert_eq!(
+            derive_vector("m/44'/501'/0'/0'", "CURVE_ED25519", Chain::Solana).unwrap(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+
+        let error = derive_vector("m/44'/501'/0'/0", "ed25519", Chain::Solana).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PathError>(),
+            Some(&PathError::Ed25519RequiresHardened(4))
+        );
+    }
+
+    #[test]
+    fn test_cosmos_golden_vector() {
+        assert_eq!(
+            derive_vector("m/44'/118'/0'/0/0", "secp256k1", Chain::Cosmos).unwrap(),
+            "cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4"
+        );
+    }
+
+    #[test]
+    fn test_xpub_golden_vectors() {
+        let runtime = create_test_runtime();
+        let generator = runtime.block_on(SeedGenerator::new(clock())).unwrap();
+
+        // BIP-44 and BIP-84 account keys for the test mnemonic
+        let xpub = runtime
+            .block_on(generator.derive_xpub(VECTOR_MNEMONIC, "m/44'/0'/0'", XpubFormat::Xpub))
+            .unwrap();
+        assert_eq!(
+            xpub.xpub,
+            "xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj"
+        );
+        assert_eq!(xpub.master_fingerprint, "73c5da0a");
+
+        let zpub = runtime
+            .block_on(generator.derive_xpub(VECTOR_MNEMONIC, "m/84'/0'/0'", XpubFormat::Zpub))
+            .unwrap();
+        assert_eq!(
+            zpub.xpub,
+            "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs"
+        );
+
+        let raw = runtime
+            .block_on(generator.derive_xpub(VECTOR_MNEMONIC, "m/44'/0'/0'", XpubFormat::Raw))
+            .unwrap();
+        assert_eq!(
+            bitcoin::base58::encode_check(&hex::decode(&raw.xpub).unwrap()),
+            xpub.xpub
+        );
+    }
+
+    #[test]
+    fn test_slip10_ed25519_vector() {
+        // SLIP-0010 test vector 1 for ed25519, chain m/0'
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let path = DerivationPath::from(vec![ChildNumber::Hardened { index: 0 }]);
+        let key = derive_ed25519(&seed, &path).unwrap();
+        assert_eq!(
+            hex::encode(key.expose_secret()),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+    }
 }
@@ -0,0 +1,750 @@
+use bitcoin::psbt::Psbt;
+use bitcoin::{Address, ScriptBuf};
+use log::{info, warn};
+use renclave_shared::{
+    EnclaveTime, KeyRetirementPolicy, KeyUsage, RenclaveErrorCode, SigningPolicy,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Environment variable with the initial signing policy as JSON
+pub const SIGNING_POLICY_ENV: &str = "RENCLAVE_SIGNING_POLICY";
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Reasons a policy update or a signing request is refused
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    #[error("Signing policy is locked until the enclave restarts")]
+    Locked,
+
+    #[error("Invalid signing policy: {0}")]
+    Invalid(String),
+
+    #[error("Output {index} pays a destination that is not on the allowlist")]
+    DestinationNotAllowed { index: usize },
+
+    #[error("Sending {requested} sats exceeds the daily limit of {limit} sats ({spent} sats already sent today)")]
+    DailyLimitExceeded {
+        requested: u64,
+        spent: u64,
+        limit: u64,
+    },
+
+    #[error("Signing is only allowed between {start_hour}:00 and {end_hour}:00 UTC")]
+    OutsideSigningWindow { start_hour: u8, end_hour: u8 },
+
+    #[error("Key {public_key} is due for retirement; sign with a rotated key")]
+    KeyRetired { public_key: String },
+
+    #[error("Input {index} has no UTXO data, so the value it spends cannot be checked")]
+    MissingInputValue { index: usize },
+
+    #[error("The enclave clock has not been synchronized, so time-based limits cannot be checked")]
+    ClockUnsynchronized,
+}
+
+impl PolicyViolation {
+    /// Enclave error code: malformed policies are bad requests, everything else is refused
+    pub fn code(&self) -> RenclaveErrorCode {
+        match self {
+            PolicyViolation::Invalid(_) => RenclaveErrorCode::InvalidRequest,
+            _ => RenclaveErrorCode::PolicyDenied,
+        }
+    }
+}
+
+/// Signatures made with one key
+struct KeyUse {
+    signatures: u64,
+    first_used_at: u64,
+    last_used_at: u64,
+}
+
+struct PolicyState {
+    policy: SigningPolicy,
+    allowed_scripts: Vec<ScriptBuf>,
+    day: u64,
+    spent_sats: u64,
+    /// By hex public key
+    key_usage: HashMap<String, KeyUse>,
+}
+
+impl PolicyState {
+    /// Whether the policy has limits that depend on the time of day or the date
+    fn needs_time(&self) -> bool {
+        self.policy.signing_window.is_some()
+            || self.policy.daily_limit_sats.is_some()
+            || self
+                .policy
+                .key_retirement
+                .is_some_and(|r| r.enforce && r.max_age_days.is_some())
+    }
+
+    /// Refuse signing with any of `keys` that is due for retirement, if the policy enforces
+    /// retirement
+    fn check_keys(&self, keys: &[String], now: u64) -> Result<(), PolicyViolation> {
+        let Some(retirement) = self.policy.key_retirement.filter(|r| r.enforce) else {
+            return Ok(());
+        };
+
+        for key in keys {
+            if let Some(usage) = self.key_usage.get(key) {
+                if retirement_due(&retirement, usage, now) {
+                    warn!("🚫 Refusing to sign with retired key {}", key);
+                    return Err(PolicyViolation::KeyRetired {
+                        public_key: key.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the PSBT against the window, allowlist and daily limit, returning the value it
+    /// sends to external destinations without recording it
+    fn check_spend(
+        &mut self,
+        psbt: &Psbt,
+        change_outputs: &[usize],
+        now: u64,
+    ) -> Result<u64, PolicyViolation> {
+        if let Some(window) = self.policy.signing_window {
+            let hour = ((now % SECS_PER_DAY) / 3600) as u8;
+            if !window.contains(hour) {
+                return Err(PolicyViolation::OutsideSigningWindow {
+                    start_hour: window.start_hour,
+                    end_hour: window.end_hour,
+                });
+            }
+        }
+
+        let mut output_sats: u64 = 0;
+        let mut change_sats: u64 = 0;
+        for (index, txout) in psbt.unsigned_tx.output.iter().enumerate() {
+            if change_outputs.contains(&index) {
+                change_sats = change_sats.saturating_add(txout.value.to_sat());
+                continue;
+            }
+            if !self.allowed_scripts.is_empty()
+                && !self.allowed_scripts.contains(&txout.script_pubkey)
+            {
+                return Err(PolicyViolation::DestinationNotAllowed { index });
+            }
+            output_sats = output_sats.saturating_add(txout.value.to_sat());
+        }
+
+        let mut input_sats: u64 = 0;
+        for index in 0..psbt.inputs.len() {
+            let utxo = psbt
+                .spend_utxo(index)
+                .map_err(|_| PolicyViolation::MissingInputValue { index })?;
+            input_sats = input_sats.saturating_add(utxo.value.to_sat());
+        }
+        // Outputs above the inputs make the transaction invalid, but never count for less
+        let external_sats = input_sats.saturating_sub(change_sats).max(output_sats);
+
+        let day = now / SECS_PER_DAY;
+        if self.day != day {
+            self.day = day;
+            self.spent_sats = 0;
+        }
+
+        if let Some(limit) = self.policy.daily_limit_sats {
+            if self.spent_sats.saturating_add(external_sats) > limit {
+                return Err(PolicyViolation::DailyLimitExceeded {
+                    requested: external_sats,
+                    spent: self.spent_sats,
+                    limit,
+                });
+            }
+        }
+
+        Ok(external_sats)
+    }
+
+    /// Count a signature by each of `keys`, returning those now due for retirement
+    fn record_signatures(&mut self, keys: &[String], now: u64) -> Vec<KeyUsage> {
+        for key in keys {
+            let usage = self.key_usage.entry(key.clone()).or_insert(KeyUse {
+                signatures: 0,
+                first_used_at: now,
+                last_used_at: now,
+            });
+            usage.signatures = usage.signatures.saturating_add(1);
+            usage.last_used_at = now;
+        }
+
+        let retiring: Vec<KeyUsage> = keys
+            .iter()
+            .map(|key| report(&self.policy, key, &self.key_usage[key], now))
+            .filter(|usage| usage.retirement_due)
+            .collect();
+        for usage in &retiring {
+            warn!(
+                "⚠️  Key {} is due for retirement after {} signatures",
+                usage.public_key, usage.signatures
+            );
+        }
+        retiring
+    }
+}
+
+/// Outcome of a signing request that passed the policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authorization {
+    /// Value in satoshis leaving the seed, fee included
+    pub external_sats: u64,
+    /// Keys that signed and are now due for retirement
+    pub retiring_keys: Vec<KeyUsage>,
+}
+
+/// Enforces the signing policy on PSBTs and tracks the value sent per UTC day and the
+/// signatures made with each key.
+///
+/// The policy and counters live in enclave memory and reset when the enclave restarts.
+pub struct PolicyEngine {
+    state: Mutex<PolicyState>,
+}
+
+impl Default for PolicyEngine {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(PolicyState {
+                policy: SigningPolicy::default(),
+                allowed_scripts: Vec::new(),
+                day: 0,
+                spent_sats: 0,
+                key_usage: HashMap::new(),
+            }),
+        }
+    }
+}
+
+impl PolicyEngine {
+    /// Engine with the policy from the environment, or an unrestricted policy if unset
+    pub fn from_env() -> Result<Self, PolicyViolation> {
+        let engine = Self::default();
+
+        if let Ok(value) = std::env::var(SIGNING_POLICY_ENV) {
+            let policy: SigningPolicy = serde_json::from_str(&value)
+                .map_err(|e| PolicyViolation::Invalid(e.to_string()))?;
+            engine.set_policy(policy)?;
+        }
+
+        Ok(engine)
+    }
+
+    /// Current policy
+    pub fn policy(&self) -> SigningPolicy {
+        self.lock().policy.clone()
+    }
+
+    /// Replace the policy unless the current one is locked
+    pub fn set_policy(&self, policy: SigningPolicy) -> Result<(), PolicyViolation> {
+        let allowed_scripts = validate(&policy)?;
+
+        let mut state = self.lock();
+        if state.policy.locked {
+            warn!("🚫 Refusing to replace locked signing policy");
+            return Err(PolicyViolation::Locked);
+        }
+
+        info!(
+            "📜 Signing policy updated: {} allowed destinations, daily limit: {:?}, window: {:?}, key retirement: {:?}, locked: {}",
+            policy.destination_allowlist.len(),
+            policy.daily_limit_sats,
+            policy.signing_window,
+            policy.key_retirement,
+            policy.locked
+        );
+        state.policy = policy;
+        state.allowed_scripts = allowed_scripts;
+        Ok(())
+    }
+
+    /// Check a signed PSBT and the keys that signed it against the policy, and record both.
+    ///
+    /// Outputs listed in `change_outputs` pay back to the signing seed and are exempt. The
+    /// value leaving the seed is everything the inputs spend except change, so the fee counts
+    /// towards the daily limit; every input must carry its UTXO for this. Keys due for
+    /// retirement are refused if the policy enforces retirement.
+    ///
+    /// All checks and the recording happen under one lock, so concurrent requests cannot both
+    /// pass at a limit and a refused request leaves the day's spend and key usage untouched.
+    ///
+    /// `now` comes from the enclave's trusted clock. Until that clock has synchronized, a
+    /// policy with a signing window, a daily limit or a maximum key age is refused rather than
+    /// checked against the guest clock, which the host controls.
+    pub fn authorize(
+        &self,
+        psbt: &Psbt,
+        change_outputs: &[usize],
+        keys: &[String],
+        now: &EnclaveTime,
+    ) -> Result<Authorization, PolicyViolation> {
+        let mut state = self.lock();
+        if now.uncertainty_ms.is_none() && state.needs_time() {
+            warn!("🚫 Refusing to sign before the enclave clock has synchronized");
+            return Err(PolicyViolation::ClockUnsynchronized);
+        }
+
+        let now = now.unix_secs();
+        state.check_keys(keys, now)?;
+        let external_sats = state.check_spend(psbt, change_outputs, now)?;
+
+        state.spent_sats = state.spent_sats.saturating_add(external_sats);
+        let retiring_keys = state.record_signatures(keys, now);
+        Ok(Authorization {
+            external_sats,
+            retiring_keys,
+        })
+    }
+
+    /// Usage of every key signed with since the enclave started, ordered by public key.
+    ///
+    /// Age-based retirement is reported against `now` even if the clock has not synchronized
+    /// yet, since the report is informational; signing itself refuses an unsynchronized clock.
+    pub fn key_usage(&self, now: &EnclaveTime) -> Vec<KeyUsage> {
+        let now = now.unix_secs();
+        let state = self.lock();
+        let mut keys: Vec<KeyUsage> = state
+            .key_usage
+            .iter()
+            .map(|(key, usage)| report(&state.policy, key, usage, now))
+            .collect();
+        keys.sort_by(|a, b| a.public_key.cmp(&b.public_key));
+        keys
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PolicyState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn retirement_due(retirement: &KeyRetirementPolicy, usage: &KeyUse, now: u64) -> bool {
+    retirement
+        .max_signatures
+        .is_some_and(|max| usage.signatures >= max)
+        || retirement.max_age_days.is_some_and(|days| {
+            now.saturating_sub(usage.first_used_at) >= u64::from(days) * SECS_PER_DAY
+        })
+}
+
+fn report(policy: &SigningPolicy, key: &str, usage: &KeyUse, now: u64) -> KeyUsage {
+    KeyUsage {
+        public_key: key.to_string(),
+        signatures: usage.signatures,
+        first_used_at: usage.first_used_at,
+        last_used_at: usage.last_used_at,
+        retirement_due: policy
+            .key_retirement
+            .is_some_and(|retirement| retirement_due(&retirement, usage, now)),
+    }
+}
+
+/// Check the policy and resolve the allowlist to output scripts
+fn validate(policy: &SigningPolicy) -> Result<Vec<ScriptBuf>, PolicyViolation> {
+    if let Some(window) = policy.signing_window {
+        if window.start_hour > 23 || window.end_hour > 23 || window.start_hour == window.end_hour {
+            return Err(PolicyViolation::Invalid(format!(
+                "signing window {}-{} must use distinct hours between 0 and 23",
+                window.start_hour, window.end_hour
+            )));
+        }
+    }
+
+    if let Some(retirement) = policy.key_retirement {
+        if retirement.max_signatures == Some(0) || retirement.max_age_days == Some(0) {
+            return Err(PolicyViolation::Invalid(
+                "key retirement limits must be positive".to_string(),
+            ));
+        }
+    }
+
+    policy
+        .destination_allowlist
+        .iter()
+        .map(|address| {
+            Address::from_str(address)
+                .map(|a| a.assume_checked().script_pubkey())
+                .map_err(|e| PolicyViolation::Invalid(format!("address {}: {}", address, e)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::absolute::LockTime;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Amount, OutPoint, Sequence, Transaction, TxIn, TxOut, Witness};
+    use renclave_shared::{SigningWindow, TimeSource};
+
+    const DESTINATION: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+    const OTHER_DESTINATION: &str = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu";
+    /// The second output of every test PSBT is change
+    const CHANGE: &[usize] = &[1];
+
+    fn script(address: &str) -> ScriptBuf {
+        Address::from_str(address)
+            .unwrap()
+            .assume_checked()
+            .script_pubkey()
+    }
+
+    /// PSBT paying `sats` to `destination` plus a change output, with a 1000 sat fee
+    fn psbt(destination: &str, sats: u64) -> Psbt {
+        psbt_with_fee(destination, sats, 1_000)
+    }
+
+    /// PSBT paying `sats` to `destination` plus a 50k sat change output, leaving `fee`
+    fn psbt_with_fee(destination: &str, sats: u64, fee: u64) -> Psbt {
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![
+                TxOut {
+                    value: Amount::from_sat(sats),
+                    script_pubkey: script(destination),
+                },
+                TxOut {
+                    value: Amount::from_sat(50_000),
+                    script_pubkey: script(OTHER_DESTINATION),
+                },
+            ],
+        };
+
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: Amount::from_sat(sats + 50_000 + fee),
+            script_pubkey: script(OTHER_DESTINATION),
+        });
+        psbt
+    }
+
+    /// Synchronized clock reading `hours` after the epoch
+    fn at(hours: u64) -> EnclaveTime {
+        EnclaveTime {
+            unix_ms: hours * 3600 * 1000,
+            uncertainty_ms: Some(100),
+            source: TimeSource::Ntp,
+            synced_at_ms: Some(0),
+        }
+    }
+
+    #[test]
+    fn test_default_policy_allows_everything() {
+        let engine = PolicyEngine::default();
+        let authorization = engine
+            .authorize(&psbt(DESTINATION, 10_000), CHANGE, &[], &at(0))
+            .unwrap();
+
+        // Change output is not counted, the fee is
+        assert_eq!(authorization.external_sats, 11_000);
+    }
+
+    #[test]
+    fn test_fee_counts_towards_daily_limit() {
+        let engine = PolicyEngine::default();
+        engine
+            .set_policy(SigningPolicy {
+                destination_allowlist: vec![DESTINATION.to_string()],
+                daily_limit_sats: Some(100_000),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Nothing to the allowlisted address, everything else burnt as fee
+        assert_eq!(
+            engine.authorize(&psbt_with_fee(DESTINATION, 0, 950_000), CHANGE, &[], &at(0)),
+            Err(PolicyViolation::DailyLimitExceeded {
+                requested: 950_000,
+                spent: 0,
+                limit: 100_000
+            })
+        );
+
+        let mut unknown_input = psbt(DESTINATION, 10_000);
+        unknown_input.inputs[0].witness_utxo = None;
+        let error = engine
+            .authorize(&unknown_input, CHANGE, &[], &at(0))
+            .unwrap_err();
+        assert_eq!(error, PolicyViolation::MissingInputValue { index: 0 });
+        assert_eq!(error.code(), RenclaveErrorCode::PolicyDenied);
+    }
+
+    #[test]
+    fn test_destination_allowlist() {
+        let engine = PolicyEngine::default();
+        engine
+            .set_policy(SigningPolicy {
+                destination_allowlist: vec![DESTINATION.to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(engine
+            .authorize(&psbt(DESTINATION, 10_000), CHANGE, &[], &at(0))
+            .is_ok());
+        assert_eq!(
+            engine.authorize(&psbt(OTHER_DESTINATION, 10_000), CHANGE, &[], &at(0)),
+            Err(PolicyViolation::DestinationNotAllowed { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_daily_limit_accumulates_and_resets() {
+        let engine = PolicyEngine::default();
+        engine
+            .set_policy(SigningPolicy {
+                daily_limit_sats: Some(25_000),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let spend = psbt(DESTINATION, 10_000);
+        assert!(engine.authorize(&spend, CHANGE, &[], &at(1)).is_ok());
+        assert!(engine.authorize(&spend, CHANGE, &[], &at(2)).is_ok());
+        assert_eq!(
+            engine.authorize(&spend, CHANGE, &[], &at(3)),
+            Err(PolicyViolation::DailyLimitExceeded {
+                requested: 11_000,
+                spent: 22_000,
+                limit: 25_000
+            })
+        );
+
+        // Next UTC day
+        assert!(engine.authorize(&spend, CHANGE, &[], &at(25)).is_ok());
+    }
+
+    #[test]
+    fn test_signing_window() {
+        let engine = PolicyEngine::default();
+        engine
+            .set_policy(SigningPolicy {
+                signing_window: Some(SigningWindow {
+                    start_hour: 9,
+                    end_hour: 17,
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let spend = psbt(DESTINATION, 10_000);
+        assert!(engine.authorize(&spend, CHANGE, &[], &at(10)).is_ok());
+        assert_eq!(
+            engine.authorize(&spend, CHANGE, &[], &at(20)),
+            Err(PolicyViolation::OutsideSigningWindow {
+                start_hour: 9,
+                end_hour: 17
+            })
+        );
+    }
+
+    #[test]
+    fn test_time_based_limits_need_a_synchronized_clock() {
+        let engine = PolicyEngine::default();
+        let spend = psbt(DESTINATION, 10_000);
+        let guest = EnclaveTime {
+            uncertainty_ms: None,
+            source: TimeSource::Guest,
+            synced_at_ms: None,
+            ..at(10)
+        };
+
+        // Nothing depends on the time yet
+        assert!(engine.authorize(&spend, CHANGE, &[], &guest).is_ok());
+
+        engine
+            .set_policy(SigningPolicy {
+                daily_limit_sats: Some(100_000),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            engine.authorize(&spend, CHANGE, &[], &guest),
+            Err(PolicyViolation::ClockUnsynchronized)
+        );
+        assert!(engine.authorize(&spend, CHANGE, &[], &at(10)).is_ok());
+    }
+
+    #[test]
+    fn test_locked_policy_cannot_be_replaced() {
+        let engine = PolicyEngine::default();
+        engine
+            .set_policy(SigningPolicy {
+                locked: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(
+            engine.set_policy(SigningPolicy::default()),
+            Err(PolicyViolation::Locked)
+        );
+        assert!(engine.policy().locked);
+    }
+
+    #[test]
+    fn test_invalid_policy_is_rejected() {
+        let engine = PolicyEngine::default();
+
+        let error = engine
+            .set_policy(SigningPolicy {
+                destination_allowlist: vec!["not-an-address".to_string()],
+                ..Default::default()
+            })
+            .unwrap_err();
+        assert_eq!(error.code(), RenclaveErrorCode::InvalidRequest);
+
+        assert!(engine
+            .set_policy(SigningPolicy {
+                signing_window: Some(SigningWindow {
+                    start_hour: 9,
+                    end_hour: 9,
+                }),
+                ..Default::default()
+            })
+            .is_err());
+
+        assert!(engine
+            .set_policy(SigningPolicy {
+                key_retirement: Some(KeyRetirementPolicy {
+                    max_signatures: Some(0),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_keys_retire_after_max_signatures() {
+        let engine = PolicyEngine::default();
+        let keys = ["02aa".to_string(), "02bb".to_string()];
+
+        let spend = psbt(DESTINATION, 10_000);
+
+        // Without a retirement policy keys are only counted
+        let authorization = engine.authorize(&spend, CHANGE, &keys[..1], &at(0));
+        assert!(authorization.unwrap().retiring_keys.is_empty());
+        engine
+            .set_policy(SigningPolicy {
+                key_retirement: Some(KeyRetirementPolicy {
+                    max_signatures: Some(2),
+                    enforce: true,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let retiring = engine
+            .authorize(&spend, CHANGE, &keys, &at(1))
+            .unwrap()
+            .retiring_keys;
+        assert_eq!(retiring.len(), 1);
+        assert_eq!(retiring[0].public_key, "02aa");
+        assert_eq!(retiring[0].signatures, 2);
+
+        let error = engine.authorize(&spend, CHANGE, &keys, &at(2)).unwrap_err();
+        assert_eq!(
+            error,
+            PolicyViolation::KeyRetired {
+                public_key: "02aa".to_string()
+            }
+        );
+        assert_eq!(error.code(), RenclaveErrorCode::PolicyDenied);
+
+        // The refused request did not count a signature
+        let usage = engine.key_usage(&at(2));
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].signatures, 2);
+        assert_eq!(usage[0].first_used_at, 0);
+        assert_eq!(usage[0].last_used_at, 3600);
+        assert!(usage[0].retirement_due);
+        assert_eq!(usage[1].signatures, 1);
+        assert!(!usage[1].retirement_due);
+    }
+
+    #[test]
+    fn test_concurrent_requests_at_the_limits() {
+        let engine = PolicyEngine::default();
+        engine
+            .set_policy(SigningPolicy {
+                daily_limit_sats: Some(22_000),
+                key_retirement: Some(KeyRetirementPolicy {
+                    max_signatures: Some(2),
+                    enforce: true,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+        let spend = psbt(DESTINATION, 10_000);
+        let key = ["02aa".to_string()];
+        engine.authorize(&spend, CHANGE, &key, &at(0)).unwrap();
+
+        // One signature and one spend left for the key and the day
+        let barrier = std::sync::Barrier::new(2);
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let requests: Vec<_> = (0..2)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        engine.authorize(&spend, CHANGE, &key, &at(1))
+                    })
+                })
+                .collect();
+            requests.into_iter().map(|r| r.join().unwrap()).collect()
+        });
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        assert!(results.contains(&Err(PolicyViolation::KeyRetired {
+            public_key: "02aa".to_string()
+        })));
+        assert_eq!(engine.key_usage(&at(1))[0].signatures, 2);
+
+        // A request refused by the daily limit does not count a signature
+        let other_key = ["02bb".to_string()];
+        assert!(matches!(
+            engine.authorize(&spend, CHANGE, &other_key, &at(2)),
+            Err(PolicyViolation::DailyLimitExceeded { spent: 22_000, .. })
+        ));
+        assert_eq!(engine.key_usage(&at(2)).len(), 1);
+    }
+
+    #[test]
+    fn test_keys_retire_by_age_and_warn_unless_enforced() {
+        let engine = PolicyEngine::default();
+        engine
+            .set_policy(SigningPolicy {
+                key_retirement: Some(KeyRetirementPolicy {
+                    max_age_days: Some(1),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+        let keys = ["02aa".to_string()];
+
+        let spend = psbt(DESTINATION, 10_000);
+
+        let authorization = engine.authorize(&spend, CHANGE, &keys, &at(0)).unwrap();
+        assert!(authorization.retiring_keys.is_empty());
+        let retiring = engine
+            .authorize(&spend, CHANGE, &keys, &at(24))
+            .unwrap()
+            .retiring_keys;
+        assert_eq!(retiring.len(), 1);
+        assert!(retiring[0].retirement_due);
+    }
+}
@@ -0,0 +1,768 @@
+use bitcoin::hashes::{sha512, Hash, HashEngine};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, IsIdentity};
+use ed25519_dalek::{Signature, VerifyingKey};
+use log::{debug, info, warn};
+use renclave_shared::validation::Threshold;
+use renclave_shared::{
+    RenclaveErrorCode, ThresholdCommitment, ThresholdMessage, ThresholdPolicy, ThresholdRound,
+    ThresholdSessionStatus,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Environment variable for the lifetime of a threshold signing session in seconds
+pub const THRESHOLD_SESSION_TTL_SECS_ENV: &str = "RENCLAVE_THRESHOLD_SESSION_TTL_SECS";
+
+const DEFAULT_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Open sessions beyond this evict the one closest to expiry
+const MAX_SESSIONS: usize = 256;
+
+/// RFC 9591 context string of the FROST(Ed25519, SHA-512) ciphersuite
+const CONTEXT: &[u8] = b"FROST-ED25519-SHA512-v1";
+
+/// Errors driving a threshold signing session
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ThresholdError {
+    #[error("Unknown or expired threshold signing session")]
+    UnknownSession,
+
+    #[error("Invalid {0}")]
+    Malformed(String),
+
+    #[error("Invalid session parameters: {0}")]
+    Parameters(String),
+
+    #[error("Participant {0} is not a signer in this session")]
+    NotASigner(u16),
+
+    #[error("Session is not collecting {0}")]
+    WrongRound(&'static str),
+
+    #[error("Participant {0} already submitted this round")]
+    AlreadySubmitted(u16),
+
+    #[error("Signature share from participant {0} does not verify")]
+    InvalidShare(u16),
+
+    #[error("Aggregate signature does not verify under the group key")]
+    InvalidSignature,
+}
+
+impl ThresholdError {
+    /// Enclave error code: unknown sessions are gone, everything else is a bad request
+    pub fn code(&self) -> RenclaveErrorCode {
+        match self {
+            ThresholdError::UnknownSession => RenclaveErrorCode::NotFound,
+            _ => RenclaveErrorCode::InvalidRequest,
+        }
+    }
+}
+
+struct Session {
+    group_key: EdwardsPoint,
+    /// Public key share of each signer
+    verifying_shares: BTreeMap<u16, EdwardsPoint>,
+    message: Vec<u8>,
+    signers: Vec<u16>,
+    /// Hiding and binding nonce commitments by signer
+    commitments: BTreeMap<u16, (EdwardsPoint, EdwardsPoint)>,
+    shares: BTreeMap<u16, Scalar>,
+    signature: Option<[u8; 64]>,
+    expires_at: u64,
+}
+
+impl Session {
+    fn round(&self) -> ThresholdRound {
+        if self.signature.is_some() {
+            ThresholdRound::Complete
+        } else if self.commitments.len() < self.signers.len() {
+            ThresholdRound::Commit
+        } else {
+            ThresholdRound::Sign
+        }
+    }
+
+    fn status(&self, session_id: &str) -> ThresholdSessionStatus {
+        let round = self.round();
+        let waiting_for = match round {
+            ThresholdRound::Commit => self
+                .signers
+                .iter()
+                .filter(|id| !self.commitments.contains_key(id))
+                .copied()
+                .collect(),
+            ThresholdRound::Sign => self
+                .signers
+                .iter()
+                .filter(|id| !self.shares.contains_key(id))
+                .copied()
+                .collect(),
+            ThresholdRound::Complete => Vec::new(),
+        };
+        let commitments = if round == ThresholdRound::Commit {
+            BTreeMap::new()
+        } else {
+            self.commitments
+                .iter()
+                .map(|(id, (hiding, binding))| {
+                    (
+                        *id,
+                        ThresholdCommitment {
+                            hiding: encode_point(hiding),
+                            binding: encode_point(binding),
+                        },
+                    )
+                })
+                .collect()
+        };
+
+        ThresholdSessionStatus {
+            session_id: session_id.to_string(),
+            round,
+            group_public_key: encode_point(&self.group_key),
+            message: hex::encode(&self.message),
+            signers: self.signers.clone(),
+            waiting_for,
+            commitments,
+            signature: self.signature.map(hex::encode),
+            expires_at: self.expires_at,
+        }
+    }
+
+    /// Binding factor of every signer, the group commitment and the challenge
+    fn signing_context(&self) -> (BTreeMap<u16, Scalar>, EdwardsPoint, Scalar) {
+        let mut encoded_commitments = Vec::new();
+        for (id, (hiding, binding)) in &self.commitments {
+            encoded_commitments.extend_from_slice(identifier(*id).as_bytes());
+            encoded_commitments.extend_from_slice(hiding.compress().as_bytes());
+            encoded_commitments.extend_from_slice(binding.compress().as_bytes());
+        }
+
+        let mut prefix = self.group_key.compress().to_bytes().to_vec();
+        prefix.extend_from_slice(&h4(&self.message));
+        prefix.extend_from_slice(&h5(&encoded_commitments));
+
+        let mut binding_factors = BTreeMap::new();
+        let mut group_commitment = EdwardsPoint::identity();
+        for (id, (hiding, binding)) in &self.commitments {
+            let mut input = prefix.clone();
+            input.extend_from_slice(identifier(*id).as_bytes());
+            let rho = h1(&input);
+            group_commitment += hiding + binding * rho;
+            binding_factors.insert(*id, rho);
+        }
+
+        let challenge = challenge(&group_commitment, &self.group_key, &self.message);
+        (binding_factors, group_commitment, challenge)
+    }
+}
+
+/// Coordinator for FROST(Ed25519, SHA-512) threshold signing sessions (RFC 9591).
+///
+/// Participants keep their key shares; the enclave only relays commitments, checks each
+/// signature share against the signer's public key share and aggregates the result, so it
+/// never learns the group secret or any share. Sessions live only in enclave memory.
+pub struct ThresholdSessions {
+    ttl: Duration,
+    policy: ThresholdPolicy,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl ThresholdSessions {
+    pub fn new(ttl: Duration, policy: ThresholdPolicy) -> Self {
+        Self {
+            ttl,
+            policy,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sessions with the lifetime and threshold policy from the environment
+    pub fn from_env() -> anyhow::Result<Self> {
+        let ttl = std::env::var(THRESHOLD_SESSION_TTL_SECS_ENV)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+        let policy = ThresholdPolicy::from_env().map_err(anyhow::Error::msg)?;
+        info!(
+            "✍️  Threshold signing sessions need at least {} signers and {}% of participants",
+            policy.min_threshold, policy.min_percent
+        );
+        Ok(Self::new(ttl, policy))
+    }
+
+    /// Apply a round message, returning the session's state afterwards
+    pub fn handle(
+        &self,
+        message: ThresholdMessage,
+    ) -> Result<ThresholdSessionStatus, ThresholdError> {
+        match message {
+            ThresholdMessage::Start {
+                group_public_key,
+                verifying_shares,
+                threshold,
+                signers,
+                message,
+            } => self.start(
+                &group_public_key,
+                &verifying_shares,
+                threshold,
+                signers,
+                &message,
+            ),
+            ThresholdMessage::Commit {
+                session_id,
+                identifier,
+                hiding,
+                binding,
+            } => self.commit(&session_id, identifier, &hiding, &binding),
+            ThresholdMessage::Sign {
+                session_id,
+                identifier,
+                share,
+            } => self.sign(&session_id, identifier, &share),
+            ThresholdMessage::Status { session_id } => {
+                let mut sessions = self.lock();
+                Ok(live_session(&mut sessions, &session_id)?.status(&session_id))
+            }
+        }
+    }
+
+    fn start(
+        &self,
+        group_public_key: &str,
+        verifying_shares: &BTreeMap<u16, String>,
+        threshold: u16,
+        mut signers: Vec<u16>,
+        message: &str,
+    ) -> Result<ThresholdSessionStatus, ThresholdError> {
+        let group_key = parse_point("group public key", group_public_key)?;
+        let message = hex::decode(message.trim())
+            .map_err(|e| ThresholdError::Malformed(format!("message: {}", e)))?;
+
+        signers.sort_unstable();
+        signers.dedup();
+        Threshold::new(threshold, verifying_shares.len())
+            .and_then(|scheme| scheme.check_signers(signers.len()))
+            .map_err(|e| ThresholdError::Parameters(e.message))?;
+        self.policy
+            .check(threshold, verifying_shares.len())
+            .map_err(ThresholdError::Parameters)?;
+        if signers.contains(&0) {
+            return Err(ThresholdError::Parameters(
+                "identifier 0 is reserved".to_string(),
+            ));
+        }
+
+        let mut signer_shares = BTreeMap::new();
+        for id in &signers {
+            let share = verifying_shares.get(id).ok_or_else(|| {
+                ThresholdError::Parameters(format!("no verifying share for {}", id))
+            })?;
+            signer_shares.insert(*id, parse_point(&format!("verifying share {}", id), share)?);
+        }
+
+        // The signers' public shares must interpolate to the group key, or no set of valid
+        // signature shares could produce a valid signature
+        let interpolated: EdwardsPoint = signer_shares
+            .iter()
+            .map(|(id, share)| share * lagrange(*id, &signers))
+            .sum();
+        if interpolated != group_key {
+            return Err(ThresholdError::Parameters(
+                "verifying shares do not match the group public key".to_string(),
+            ));
+        }
+
+        let session_id = Uuid::new_v4().to_string();
+        let now = now_secs();
+        let session = Session {
+            group_key,
+            verifying_shares: signer_shares,
+            message,
+            signers,
+            commitments: BTreeMap::new(),
+            shares: BTreeMap::new(),
+            signature: None,
+            expires_at: now + self.ttl.as_secs(),
+        };
+        let status = session.status(&session_id);
+
+        let mut sessions = self.lock();
+        sessions.retain(|_, session| session.expires_at > now);
+        if sessions.len() >= MAX_SESSIONS {
+            if let Some(oldest) = sessions
+                .iter()
+                .min_by_key(|(_, session)| session.expires_at)
+                .map(|(id, _)| id.clone())
+            {
+                debug!("✍️  Evicting threshold signing session {}", oldest);
+                sessions.remove(&oldest);
+            }
+        }
+        sessions.insert(session_id.clone(), session);
+
+        info!(
+            "✍️  Started threshold signing session {} with {} signers",
+            session_id,
+            status.signers.len()
+        );
+        Ok(status)
+    }
+
+    fn commit(
+        &self,
+        session_id: &str,
+        id: u16,
+        hiding: &str,
+        binding: &str,
+    ) -> Result<ThresholdSessionStatus, ThresholdError> {
+        let mut sessions = self.lock();
+        let session = live_session(&mut sessions, session_id)?;
+
+        if !session.signers.contains(&id) {
+            return Err(ThresholdError::NotASigner(id));
+        }
+        if session.round() != ThresholdRound::Commit {
+            return Err(ThresholdError::WrongRound("commitments"));
+        }
+        if session.commitments.contains_key(&id) {
+            return Err(ThresholdError::AlreadySubmitted(id));
+        }
+
+        let hiding = parse_point("hiding commitment", hiding)?;
+        let binding = parse_point("binding commitment", binding)?;
+        session.commitments.insert(id, (hiding, binding));
+        debug!("✍️  Commitment from participant {} for {}", id, session_id);
+        Ok(session.status(session_id))
+    }
+
+    fn sign(
+        &self,
+        session_id: &str,
+        id: u16,
+        share: &str,
+    ) -> Result<ThresholdSessionStatus, ThresholdError> {
+        let mut sessions = self.lock();
+        let session = live_session(&mut sessions, session_id)?;
+
+        if !session.signers.contains(&id) {
+            return Err(ThresholdError::NotASigner(id));
+        }
+        if session.round() != ThresholdRound::Sign {
+            return Err(ThresholdError::WrongRound("signature shares"));
+        }
+        if session.shares.contains_key(&id) {
+            return Err(ThresholdError::AlreadySubmitted(id));
+        }
+
+        let share = parse_scalar("signature share", share)?;
+        let (binding_factors, group_commitment, challenge) = session.signing_context();
+
+        // z_i·G = D_i + ρ_i·E_i + (c·λ_i)·Y_i for an honest signer
+        let (hiding, binding) = &session.commitments[&id];
+        let expected = hiding
+            + binding * binding_factors[&id]
+            + session.verifying_shares[&id] * (challenge * lagrange(id, &session.signers));
+        if EdwardsPoint::mul_base(&share) != expected {
+            warn!(
+                "⚠️  Invalid signature share from participant {} in {}",
+                id, session_id
+            );
+            return Err(ThresholdError::InvalidShare(id));
+        }
+        session.shares.insert(id, share);
+
+        if session.shares.len() == session.signers.len() {
+            let z: Scalar = session.shares.values().sum();
+            let mut signature = [0u8; 64];
+            signature[..32].copy_from_slice(group_commitment.compress().as_bytes());
+            signature[32..].copy_from_slice(z.as_bytes());
+
+            let verified = VerifyingKey::from_bytes(session.group_key.compress().as_bytes())
+                .and_then(|key| {
+                    key.verify_strict(&session.message, &Signature::from_bytes(&signature))
+                });
+            if let Err(e) = verified {
+                // Every share verified, so this is a bug rather than a bad participant
+                warn!("⚠️  Aggregate signature for {} failed: {}", session_id, e);
+                sessions.remove(session_id);
+                return Err(ThresholdError::InvalidSignature);
+            }
+            session.signature = Some(signature);
+            info!("✍️  Threshold signing session {} complete", session_id);
+        }
+        Ok(session.status(session_id))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Session>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn live_session<'a>(
+    sessions: &'a mut HashMap<String, Session>,
+    session_id: &str,
+) -> Result<&'a mut Session, ThresholdError> {
+    if sessions
+        .get(session_id)
+        .is_some_and(|session| session.expires_at <= now_secs())
+    {
+        sessions.remove(session_id);
+    }
+    sessions
+        .get_mut(session_id)
+        .ok_or(ThresholdError::UnknownSession)
+}
+
+/// Decode a point, rejecting the identity and points with a small-order component
+fn parse_point(field: &str, value: &str) -> Result<EdwardsPoint, ThresholdError> {
+    let invalid = || ThresholdError::Malformed(field.to_string());
+    let bytes: [u8; 32] = hex::decode(value.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(invalid)?;
+    CompressedEdwardsY(bytes)
+        .decompress()
+        .filter(|point| !point.is_identity() && point.is_torsion_free())
+        .ok_or_else(invalid)
+}
+
+/// Decode a canonical scalar
+fn parse_scalar(field: &str, value: &str) -> Result<Scalar, ThresholdError> {
+    hex::decode(value.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| Option::from(Scalar::from_canonical_bytes(bytes)))
+        .ok_or_else(|| ThresholdError::Malformed(field.to_string()))
+}
+
+fn encode_point(point: &EdwardsPoint) -> String {
+    hex::encode(point.compress().as_bytes())
+}
+
+/// Participant identifiers are the scalars of their share indices
+fn identifier(id: u16) -> Scalar {
+    Scalar::from(u64::from(id))
+}
+
+/// Lagrange coefficient of `id` for interpolating at zero over `signers`
+fn lagrange(id: u16, signers: &[u16]) -> Scalar {
+    let x_i = identifier(id);
+    let mut numerator = Scalar::ONE;
+    let mut denominator = Scalar::ONE;
+    for x_j in signers.iter().filter(|j| **j != id).map(|j| identifier(*j)) {
+        numerator *= x_j;
+        denominator *= x_j - x_i;
+    }
+    numerator * denominator.invert()
+}
+
+fn sha512(parts: &[&[u8]]) -> [u8; 64] {
+    let mut engine = sha512::Hash::engine();
+    for part in parts {
+        engine.input(part);
+    }
+    sha512::Hash::from_engine(engine).to_byte_array()
+}
+
+fn h1(input: &[u8]) -> Scalar {
+    Scalar::from_bytes_mod_order_wide(&sha512(&[CONTEXT, b"rho", input]))
+}
+
+fn h4(input: &[u8]) -> [u8; 64] {
+    sha512(&[CONTEXT, b"msg", input])
+}
+
+fn h5(input: &[u8]) -> [u8; 64] {
+    sha512(&[CONTEXT, b"com", input])
+}
+
+/// H2 of the ciphersuite, which is the Ed25519 challenge
+fn challenge(group_commitment: &EdwardsPoint, group_key: &EdwardsPoint, message: &[u8]) -> Scalar {
+    Scalar::from_bytes_mod_order_wide(&sha512(&[
+        group_commitment.compress().as_bytes(),
+        group_key.compress().as_bytes(),
+        message,
+    ]))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    const MESSAGE: &[u8] = b"threshold signed message";
+
+    fn random_scalar() -> Scalar {
+        let mut bytes = [0u8; 64];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+
+    /// Trusted dealer split of a fresh key into `count` shares, `threshold` needed to sign
+    fn deal(threshold: u16, count: u16) -> (EdwardsPoint, BTreeMap<u16, Scalar>) {
+        let coefficients: Vec<Scalar> = (0..threshold).map(|_| random_scalar()).collect();
+        let shares = (1..=count)
+            .map(|id| {
+                let x = identifier(id);
+                let share = coefficients
+                    .iter()
+                    .rev()
+                    .fold(Scalar::ZERO, |acc, coefficient| acc * x + coefficient);
+                (id, share)
+            })
+            .collect();
+        (EdwardsPoint::mul_base(&coefficients[0]), shares)
+    }
+
+    fn start(
+        sessions: &ThresholdSessions,
+        group_key: &EdwardsPoint,
+        shares: &BTreeMap<u16, Scalar>,
+        threshold: u16,
+        signers: Vec<u16>,
+    ) -> Result<ThresholdSessionStatus, ThresholdError> {
+        sessions.handle(ThresholdMessage::Start {
+            group_public_key: encode_point(group_key),
+            verifying_shares: shares
+                .iter()
+                .map(|(id, share)| (*id, encode_point(&EdwardsPoint::mul_base(share))))
+                .collect(),
+            threshold,
+            signers,
+            message: hex::encode(MESSAGE),
+        })
+    }
+
+    /// Run round one for every signer, returning each signer's nonces
+    fn commit_all(
+        sessions: &ThresholdSessions,
+        status: &ThresholdSessionStatus,
+    ) -> BTreeMap<u16, (Scalar, Scalar)> {
+        let mut nonces = BTreeMap::new();
+        for id in &status.signers {
+            let (hiding, binding) = (random_scalar(), random_scalar());
+            sessions
+                .handle(ThresholdMessage::Commit {
+                    session_id: status.session_id.clone(),
+                    identifier: *id,
+                    hiding: encode_point(&EdwardsPoint::mul_base(&hiding)),
+                    binding: encode_point(&EdwardsPoint::mul_base(&binding)),
+                })
+                .unwrap();
+            nonces.insert(*id, (hiding, binding));
+        }
+        nonces
+    }
+
+    /// A participant's signature share, computed from the published commitments
+    fn signature_share(
+        status: &ThresholdSessionStatus,
+        id: u16,
+        secret_share: &Scalar,
+        (hiding, binding): (Scalar, Scalar),
+    ) -> Scalar {
+        let session = Session {
+            group_key: parse_point("", &status.group_public_key).unwrap(),
+            verifying_shares: BTreeMap::new(),
+            message: hex::decode(&status.message).unwrap(),
+            signers: status.signers.clone(),
+            commitments: status
+                .commitments
+                .iter()
+                .map(|(id, c)| {
+                    (
+                        *id,
+                        (
+                            parse_point("", &c.hiding).unwrap(),
+                            parse_point("", &c.binding).unwrap(),
+                        ),
+                    )
+                })
+                .collect(),
+            shares: BTreeMap::new(),
+            signature: None,
+            expires_at: 0,
+        };
+        let (binding_factors, _, challenge) = session.signing_context();
+        hiding
+            + binding * binding_factors[&id]
+            + lagrange(id, &status.signers) * secret_share * challenge
+    }
+
+    fn submit(
+        sessions: &ThresholdSessions,
+        session_id: &str,
+        id: u16,
+        share: &Scalar,
+    ) -> Result<ThresholdSessionStatus, ThresholdError> {
+        sessions.handle(ThresholdMessage::Sign {
+            session_id: session_id.to_string(),
+            identifier: id,
+            share: hex::encode(share.as_bytes()),
+        })
+    }
+
+    #[test]
+    fn test_signature_verifies_under_group_key() {
+        let sessions = ThresholdSessions::new(DEFAULT_TTL, ThresholdPolicy::default());
+        let (group_key, shares) = deal(2, 3);
+        let status = start(&sessions, &group_key, &shares, 2, vec![3, 1]).unwrap();
+        assert_eq!(status.round, ThresholdRound::Commit);
+        assert_eq!(status.signers, vec![1, 3]);
+        assert_eq!(status.waiting_for, vec![1, 3]);
+
+        let nonces = commit_all(&sessions, &status);
+        let status = sessions
+            .handle(ThresholdMessage::Status {
+                session_id: status.session_id,
+            })
+            .unwrap();
+        assert_eq!(status.round, ThresholdRound::Sign);
+        assert_eq!(status.commitments.len(), 2);
+
+        let mut last = None;
+        for (id, nonce) in &nonces {
+            let share = signature_share(&status, *id, &shares[id], *nonce);
+            last = Some(submit(&sessions, &status.session_id, *id, &share).unwrap());
+        }
+        let done = last.unwrap();
+        assert_eq!(done.round, ThresholdRound::Complete);
+
+        let signature: [u8; 64] = hex::decode(done.signature.unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        VerifyingKey::from_bytes(group_key.compress().as_bytes())
+            .unwrap()
+            .verify_strict(MESSAGE, &Signature::from_bytes(&signature))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_invalid_share_names_the_participant() {
+        let sessions = ThresholdSessions::new(DEFAULT_TTL, ThresholdPolicy::default());
+        let (group_key, shares) = deal(2, 2);
+        let status = start(&sessions, &group_key, &shares, 2, vec![1, 2]).unwrap();
+        let nonces = commit_all(&sessions, &status);
+        let status = sessions
+            .handle(ThresholdMessage::Status {
+                session_id: status.session_id,
+            })
+            .unwrap();
+
+        let wrong = signature_share(&status, 2, &random_scalar(), nonces[&2]);
+        assert_eq!(
+            submit(&sessions, &status.session_id, 2, &wrong),
+            Err(ThresholdError::InvalidShare(2))
+        );
+
+        // The honest share is still accepted afterwards
+        let right = signature_share(&status, 2, &shares[&2], nonces[&2]);
+        let status = submit(&sessions, &status.session_id, 2, &right).unwrap();
+        assert_eq!(status.waiting_for, vec![1]);
+        assert_eq!(
+            submit(&sessions, &status.session_id, 2, &right),
+            Err(ThresholdError::AlreadySubmitted(2))
+        );
+    }
+
+    #[test]
+    fn test_rounds_are_enforced() {
+        let sessions = ThresholdSessions::new(DEFAULT_TTL, ThresholdPolicy::default());
+        let (group_key, shares) = deal(2, 3);
+        let status = start(&sessions, &group_key, &shares, 2, vec![1, 2]).unwrap();
+        assert!(status.commitments.is_empty());
+
+        assert_eq!(
+            submit(&sessions, &status.session_id, 1, &Scalar::ONE),
+            Err(ThresholdError::WrongRound("signature shares"))
+        );
+        assert_eq!(
+            submit(&sessions, &status.session_id, 3, &Scalar::ONE),
+            Err(ThresholdError::NotASigner(3))
+        );
+        assert_eq!(
+            sessions.handle(ThresholdMessage::Commit {
+                session_id: status.session_id.clone(),
+                identifier: 1,
+                hiding: encode_point(&EdwardsPoint::identity()),
+                binding: encode_point(&group_key),
+            }),
+            Err(ThresholdError::Malformed("hiding commitment".to_string()))
+        );
+        assert_eq!(
+            sessions.handle(ThresholdMessage::Status {
+                session_id: "missing".to_string(),
+            }),
+            Err(ThresholdError::UnknownSession)
+        );
+    }
+
+    #[test]
+    fn test_start_rejects_inconsistent_parameters() {
+        let sessions = ThresholdSessions::new(DEFAULT_TTL, ThresholdPolicy::default());
+        let (group_key, shares) = deal(2, 3);
+        let (other_key, _) = deal(2, 3);
+
+        for (key, threshold, signers) in [
+            (&group_key, 2, vec![1]),
+            (&group_key, 1, vec![1, 2]),
+            (&group_key, 4, vec![1, 2, 3]),
+            (&group_key, 2, vec![1, 4]),
+            (&other_key, 2, vec![1, 2]),
+        ] {
+            assert!(matches!(
+                start(&sessions, key, &shares, threshold, signers),
+                Err(ThresholdError::Parameters(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_start_enforces_threshold_policy() {
+        let policy = ThresholdPolicy {
+            min_threshold: 2,
+            min_percent: 51,
+        };
+        let sessions = ThresholdSessions::new(DEFAULT_TTL, policy);
+        let (group_key, shares) = deal(2, 4);
+        assert_eq!(
+            start(&sessions, &group_key, &shares, 2, vec![1, 2]),
+            Err(ThresholdError::Parameters(
+                "threshold 2 of 4 participants is below the required 3".to_string()
+            ))
+        );
+
+        let (group_key, shares) = deal(3, 4);
+        assert!(start(&sessions, &group_key, &shares, 3, vec![1, 2, 4]).is_ok());
+    }
+
+    #[test]
+    fn test_expired_sessions_are_unknown() {
+        let sessions = ThresholdSessions::new(Duration::ZERO, ThresholdPolicy::default());
+        let (group_key, shares) = deal(2, 2);
+        let status = start(&sessions, &group_key, &shares, 2, vec![1, 2]).unwrap();
+        assert_eq!(
+            sessions.handle(ThresholdMessage::Status {
+                session_id: status.session_id,
+            }),
+            Err(ThresholdError::UnknownSession)
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use anyhow::{anyhow, Context, Result};
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use openssl::bn::{BigNum, MsbOption};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::stack::Stack;
+use openssl::x509::extension::{BasicConstraints, ExtendedKeyUsage, SubjectAlternativeName};
+use openssl::x509::{X509Builder, X509NameBuilder, X509ReqBuilder, X509};
+use std::sync::RwLock;
+use tokio_native_tls::TlsAcceptor;
+use zeroize::Zeroizing;
+
+/// Environment variable for the DNS name in the enclave's TLS certificate
+pub const TLS_SERVER_NAME_ENV: &str = "RENCLAVE_TLS_SERVER_NAME";
+
+const DEFAULT_SERVER_NAME: &str = "localhost";
+
+/// The certificate only lives as long as the enclave, which restarts well within this
+const CERTIFICATE_DAYS: u32 = 365;
+
+/// Largest number of names in a certificate signing request, Let's Encrypt's limit
+const MAX_CSR_DOMAINS: usize = 100;
+
+/// TLS server identity whose private key never leaves enclave memory.
+///
+/// The key and a self-signed certificate are generated when the enclave starts, so every
+/// restart presents a new certificate. Clients pin it by its SHA-256 fingerprint, which
+/// `GetInfo` reports, until a CA-issued certificate for the same key is installed with
+/// [`install`](Self::install).
+pub struct TlsIdentity {
+    key: PKey<Private>,
+    served: RwLock<Served>,
+}
+
+/// Certificate currently presented to clients
+struct Served {
+    acceptor: TlsAcceptor,
+    certificate_pem: String,
+    fingerprint: String,
+}
+
+impl TlsIdentity {
+    /// Generate a P-256 key and a certificate for `server_name`
+    pub fn generate(server_name: &str) -> Result<Self> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+        let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+
+        let mut name = X509NameBuilder::new()?;
+        name.append_entry_by_nid(Nid::COMMONNAME, server_name)?;
+        let name = name.build();
+
+        let mut serial = BigNum::new()?;
+        serial.rand(128, MsbOption::MAYBE_ZERO, false)?;
+
+        let serial = serial.to_asn1_integer()?;
+        let not_before = Asn1Time::days_from_now(0)?;
+        let not_after = Asn1Time::days_from_now(CERTIFICATE_DAYS)?;
+
+        let mut builder = X509Builder::new()?;
+        builder.set_version(2)?;
+        builder.set_serial_number(&serial)?;
+        builder.set_subject_name(&name)?;
+        builder.set_issuer_name(&name)?;
+        builder.set_not_before(&not_before)?;
+        builder.set_not_after(&not_after)?;
+        builder.set_pubkey(&key)?;
+        builder.append_extension(BasicConstraints::new().critical().build()?)?;
+        builder.append_extension(ExtendedKeyUsage::new().server_auth().build()?)?;
+        let alternative_names = SubjectAlternativeName::new()
+            .dns(server_name)
+            .build(&builder.x509v3_context(None, None))
+            .with_context(|| format!("Invalid TLS server name '{}'", server_name))?;
+        builder.append_extension(alternative_names)?;
+        builder.sign(&key, MessageDigest::sha256())?;
+        let certificate = builder.build();
+
+        let served = Served::new(&key, &[certificate])?;
+        Ok(Self {
+            key,
+            served: RwLock::new(served),
+        })
+    }
+
+    /// Identity for the server name from the environment
+    pub fn from_env() -> Result<Self> {
+        let server_name = std::env::var(TLS_SERVER_NAME_ENV)
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| DEFAULT_SERVER_NAME.to_string());
+        Self::generate(&server_name)
+    }
+
+    /// Acceptor for the certificate served now; connections keep the certificate they
+    /// were accepted with
+    pub fn acceptor(&self) -> TlsAcceptor {
+        self.served().acceptor.clone()
+    }
+
+    pub fn certificate_pem(&self) -> String {
+        self.served().certificate_pem.clone()
+    }
+
+    /// Hex SHA-256 of the DER leaf certificate
+    pub fn fingerprint(&self) -> String {
+        self.served().fingerprint.clone()
+    }
+
+    /// PEM certificate signing request for the enclave key, with the first of `domains` as
+    /// common name and all of them as DNS names
+    pub fn csr(&self, domains: &[String]) -> Result<String> {
+        let Some(common_name) = domains.first() else {
+            return Err(anyhow!(
+                "A certificate signing request needs at least one domain"
+            ));
+        };
+        if domains.len() > MAX_CSR_DOMAINS {
+            return Err(anyhow!(
+                "A certificate signing request names at most {} domains, got {}",
+                MAX_CSR_DOMAINS,
+                domains.len()
+            ));
+        }
+
+        let mut name = X509NameBuilder::new()?;
+        name.append_entry_by_nid(Nid::COMMONNAME, common_name)
+            .with_context(|| format!("Invalid domain '{}'", common_name))?;
+        let name = name.build();
+
+        let mut builder = X509ReqBuilder::new()?;
+        builder.set_version(0)?;
+        builder.set_subject_name(&name)?;
+        builder.set_pubkey(&self.key)?;
+        let mut alternative_names = SubjectAlternativeName::new();
+        for domain in domains {
+            alternative_names.dns(domain);
+        }
+        let mut extensions = Stack::new()?;
+        extensions.push(
+            alternative_names
+                .build(&builder.x509v3_context(None))
+                .context("Invalid domain in certificate signing request")?,
+        )?;
+        builder.add_extensions(&extensions)?;
+        builder.sign(&self.key, MessageDigest::sha256())?;
+
+        Ok(String::from_utf8(builder.build().to_pem()?)?)
+    }
+
+    /// Serve the PEM `chain`, leaf first, from now on. The leaf must certify the enclave
+    /// key. Returns the leaf's fingerprint and expiry as Unix time.
+    pub fn install(&self, chain: &str) -> Result<(String, u64)> {
+        let chain = X509::stack_from_pem(chain.as_bytes()).context("Invalid certificate chain")?;
+        let Some(leaf) = chain.first() else {
+            return Err(anyhow!("The certificate chain is empty"));
+        };
+        if !leaf.public_key()?.public_eq(&self.key) {
+            return Err(anyhow!(
+                "The certificate does not belong to the enclave's TLS key"
+            ));
+        }
+        let now = Asn1Time::days_from_now(0)?;
+        if leaf.not_after() < now {
+            return Err(anyhow!("The certificate expired at {}", leaf.not_after()));
+        }
+        let not_after = unix_time(leaf.not_after())?;
+
+        let served = Served::new(&self.key, &chain)?;
+        let fingerprint = served.fingerprint.clone();
+        *self.served.write().unwrap_or_else(|e| e.into_inner()) = served;
+        Ok((fingerprint, not_after))
+    }
+
+    fn served(&self) -> std::sync::RwLockReadGuard<'_, Served> {
+        self.served.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Served {
+    /// Acceptor presenting `chain`, leaf first, for `key`
+    fn new(key: &PKey<Private>, chain: &[X509]) -> Result<Self> {
+        let mut chain_pem = Vec::new();
+        for certificate in chain {
+            chain_pem.extend(certificate.to_pem()?);
+        }
+        let leaf = &chain[0];
+        let certificate_pem = String::from_utf8(leaf.to_pem()?)?;
+        let fingerprint = hex::encode(leaf.digest(MessageDigest::sha256())?);
+
+        let key_pem = Zeroizing::new(key.private_key_to_pem_pkcs8()?);
+        let identity = native_tls::Identity::from_pkcs8(&chain_pem, &key_pem)
+            .context("Failed to load the TLS identity")?;
+        let acceptor = native_tls::TlsAcceptor::builder(identity)
+            .min_protocol_version(Some(native_tls::Protocol::Tlsv12))
+            .build()
+            .context("Failed to build the TLS acceptor")?;
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(acceptor),
+            certificate_pem,
+            fingerprint,
+        })
+    }
+}
+
+fn unix_time(time: &Asn1TimeRef) -> Result<u64> {
+    let diff = Asn1Time::from_unix(0)?.diff(time)?;
+    Ok((diff.days as i64 * 86_400 + diff.secs as i64).max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    #[tokio::test]
+    async fn test_handshake_with_pinned_certificate() {
+        let identity = TlsIdentity::generate("enclave.test").unwrap();
+        assert_eq!(identity.fingerprint().len(), 64);
+
+        let (client, server) = tokio::io::duplex(16 * 1024);
+        let acceptor = identity.acceptor().clone();
+        let server = tokio::spawn(async move {
+            let mut stream = BufReader::new(acceptor.accept(server).await.unwrap());
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            stream.get_mut().write_all(line.as_bytes()).await.unwrap();
+            stream.get_mut().shutdown().await.unwrap();
+        });
+
+        let certificate =
+            native_tls::Certificate::from_pem(identity.certificate_pem().as_bytes()).unwrap();
+        let connector = native_tls::TlsConnector::builder()
+            .add_root_certificate(certificate)
+            .disable_built_in_roots(true)
+            .build()
+            .unwrap();
+        let connector = tokio_native_tls::TlsConnector::from(connector);
+        let mut stream = BufReader::new(connector.connect("enclave.test", client).await.unwrap());
+
+        stream.get_mut().write_all(b"ping\n").await.unwrap();
+        let mut echoed = String::new();
+        stream.read_line(&mut echoed).await.unwrap();
+        assert_eq!(echoed, "ping\n");
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_certificates_are_fresh() {
+        let first = TlsIdentity::generate("localhost").unwrap();
+        let second = TlsIdentity::generate("localhost").unwrap();
+        assert_ne!(first.fingerprint(), second.fingerprint());
+    }
+
+    /// Certificate for the key in `csr`, issued by a throwaway CA
+    fn issue(csr: &str) -> String {
+        let csr = openssl::x509::X509Req::from_pem(csr.as_bytes()).unwrap();
+        let public_key = csr.public_key().unwrap();
+        assert!(csr.verify(&public_key).unwrap());
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ca_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut issuer = X509NameBuilder::new().unwrap();
+        issuer
+            .append_entry_by_nid(Nid::COMMONNAME, "Test CA")
+            .unwrap();
+        let issuer = issuer.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(csr.subject_name()).unwrap();
+        builder.set_issuer_name(&issuer).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(90).unwrap())
+            .unwrap();
+        builder.set_pubkey(&public_key).unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        String::from_utf8(builder.build().to_pem().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_install_certificate_for_csr() {
+        let identity = TlsIdentity::generate("localhost").unwrap();
+        let self_signed = identity.fingerprint();
+
+        let csr = identity
+            .csr(&["a.example".to_string(), "b.example".to_string()])
+            .unwrap();
+        let (fingerprint, not_after) = identity.install(&issue(&csr)).unwrap();
+        assert_eq!(identity.fingerprint(), fingerprint);
+        assert_ne!(fingerprint, self_signed);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(not_after > now + 89 * 86_400 && not_after <= now + 91 * 86_400);
+
+        // A certificate for another key is refused and the current one kept
+        let other = TlsIdentity::generate("localhost").unwrap();
+        let foreign = issue(&other.csr(&["a.example".to_string()]).unwrap());
+        assert!(identity.install(&foreign).is_err());
+        assert_eq!(identity.fingerprint(), fingerprint);
+
+        assert!(identity.csr(&[]).is_err());
+        assert!(identity.install("").is_err());
+    }
+}
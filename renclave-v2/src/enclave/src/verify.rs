@@ -0,0 +1,156 @@
+use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey};
+use renclave_shared::validation::Curve;
+use secp256k1::ecdsa::Signature;
+use secp256k1::{Message, PublicKey, Secp256k1};
+
+/// Reasons a signature cannot be checked at all, as opposed to not matching
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    #[error("Unsupported curve '{0}'")]
+    UnsupportedCurve(String),
+
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+}
+
+/// Verify a hex signature over a hex message.
+///
+/// secp256k1 signatures are ECDSA over a 32-byte digest, in compact or DER form; high-S
+/// signatures are accepted. ed25519 signatures are over the message itself. Curve names are
+/// parsed like `Curve`.
+pub fn verify_signature(
+    curve: &str,
+    public_key: &str,
+    message: &str,
+    signature: &str,
+) -> Result<bool, VerifyError> {
+    let message =
+        hex::decode(message.trim()).map_err(|e| VerifyError::InvalidMessage(e.to_string()))?;
+    let signature =
+        hex::decode(signature.trim()).map_err(|e| VerifyError::InvalidSignature(e.to_string()))?;
+    let public_key =
+        hex::decode(public_key.trim()).map_err(|e| VerifyError::InvalidPublicKey(e.to_string()))?;
+
+    match curve.parse::<Curve>() {
+        Ok(Curve::Secp256k1) => verify_secp256k1(&public_key, &message, &signature),
+        Ok(Curve::Ed25519) => verify_ed25519(&public_key, &message, &signature),
+        Err(_) => Err(VerifyError::UnsupportedCurve(curve.to_string())),
+    }
+}
+
+fn verify_secp256k1(
+    public_key: &[u8],
+    digest: &[u8],
+    signature: &[u8],
+) -> Result<bool, VerifyError> {
+    let public_key = PublicKey::from_slice(public_key)
+        .map_err(|e| VerifyError::InvalidPublicKey(e.to_string()))?;
+    let digest: [u8; 32] = digest.try_into().map_err(|_| {
+        VerifyError::InvalidMessage(format!(
+            "secp256k1 signatures are over a 32-byte digest, got {} bytes",
+            digest.len()
+        ))
+    })?;
+    let mut signature = if signature.len() == 64 {
+        Signature::from_compact(signature)
+    } else {
+        Signature::from_der(signature)
+    }
+    .map_err(|e| VerifyError::InvalidSignature(e.to_string()))?;
+    signature.normalize_s();
+
+    Ok(Secp256k1::verification_only()
+        .verify_ecdsa(&Message::from_digest(digest), &signature, &public_key)
+        .is_ok())
+}
+
+fn verify_ed25519(
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, VerifyError> {
+    let public_key: [u8; 32] = public_key.try_into().map_err(|_| {
+        VerifyError::InvalidPublicKey(format!(
+            "ed25519 public keys are 32 bytes, got {}",
+            public_key.len()
+        ))
+    })?;
+    let public_key = VerifyingKey::from_bytes(&public_key)
+        .map_err(|e| VerifyError::InvalidPublicKey(e.to_string()))?;
+    let signature = Ed25519Signature::from_slice(signature)
+        .map_err(|e| VerifyError::InvalidSignature(e.to_string()))?;
+
+    Ok(public_key.verify_strict(message, &signature).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use secp256k1::SecretKey;
+
+    #[test]
+    fn test_secp256k1_compact_and_der() {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[3; 32]).unwrap();
+        let public_key = hex::encode(secret.public_key(&secp).serialize());
+        let digest = [0x42; 32];
+        let signature = secp.sign_ecdsa(&Message::from_digest(digest), &secret);
+
+        let message = hex::encode(digest);
+        for encoded in [
+            hex::encode(signature.serialize_compact()),
+            hex::encode(signature.serialize_der()),
+        ] {
+            assert_eq!(
+                verify_signature("secp256k1", &public_key, &message, &encoded),
+                Ok(true)
+            );
+        }
+
+        let other = hex::encode([0x43; 32]);
+        let compact = hex::encode(signature.serialize_compact());
+        assert_eq!(
+            verify_signature("CURVE_SECP256K1", &public_key, &other, &compact),
+            Ok(false)
+        );
+        assert!(matches!(
+            verify_signature("secp256k1", &public_key, "abcd", &compact),
+            Err(VerifyError::InvalidMessage(_))
+        ));
+    }
+
+    #[test]
+    fn test_ed25519() {
+        let key = SigningKey::from_bytes(&[5; 32]);
+        let public_key = hex::encode(key.verifying_key().to_bytes());
+        let signature = hex::encode(key.sign(b"hello").to_bytes());
+
+        assert_eq!(
+            verify_signature("ed25519", &public_key, &hex::encode(b"hello"), &signature),
+            Ok(true)
+        );
+        assert_eq!(
+            verify_signature("ed25519", &public_key, &hex::encode(b"hellO"), &signature),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_curve_and_bad_hex() {
+        assert_eq!(
+            verify_signature("p256", "02", "00", "00"),
+            Err(VerifyError::UnsupportedCurve("p256".to_string()))
+        );
+        assert!(matches!(
+            verify_signature("secp256k1", "zz", "00", "00"),
+            Err(VerifyError::InvalidPublicKey(_))
+        ));
+    }
+}
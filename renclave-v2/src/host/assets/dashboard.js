@@ -0,0 +1,201 @@
+"use strict";
+
+// Polls the host's JSON routes and renders them. Values are only ever inserted as text.
+
+const REFRESH_MS = 5000;
+const KEY_STORAGE = "renclave.apiKey";
+
+let previousCounters = null;
+
+function row(table, label, value, cls) {
+  const tr = table.insertRow();
+  tr.insertCell().textContent = label;
+  const cell = tr.insertCell();
+  cell.textContent = value === undefined || value === null ? "-" : String(value);
+  if (cls) {
+    cell.className = cls;
+  }
+}
+
+function fill(id, rows) {
+  const table = document.getElementById(id);
+  table.replaceChildren();
+  for (const [label, value, cls] of rows) {
+    row(table, label, value, cls);
+  }
+}
+
+async function fetchJson(path, auth) {
+  const headers = {};
+  const key = sessionStorage.getItem(KEY_STORAGE);
+  if (auth && key) {
+    headers.Authorization = "Bearer " + key;
+  }
+  const response = await fetch(path, { headers, cache: "no-store" });
+  const body = await response.json().catch(() => null);
+  if (!response.ok && !(body && body.checks)) {
+    const message = body && body.error ? body.error : response.statusText;
+    throw new Error(response.status + " " + message);
+  }
+  return body;
+}
+
+function duration(secs) {
+  const days = Math.floor(secs / 86400);
+  const hours = Math.floor((secs % 86400) / 3600);
+  const minutes = Math.floor((secs % 3600) / 60);
+  return (days ? days + "d " : "") + hours + "h " + minutes + "m";
+}
+
+function time(unix) {
+  return unix ? new Date(unix * 1000).toISOString() : null;
+}
+
+function yesNo(value) {
+  return [value ? "yes" : "no", value ? "pass" : "fail"];
+}
+
+async function readiness() {
+  const report = await fetchJson("/health/ready");
+  const overall = document.getElementById("overall");
+  overall.textContent = report.status;
+  overall.className = "badge " + report.status;
+  fill(
+    "checks",
+    report.checks.map((check) => [
+      check.name,
+      check.status + (check.message ? ": " + check.message : ""),
+      check.status,
+    ])
+  );
+}
+
+async function enclaves() {
+  const { enclaves } = await fetchJson("/enclaves");
+  fill(
+    "enclaves",
+    enclaves.map((enclave) => [
+      enclave.name,
+      enclave.available
+        ? "available, session " + (enclave.epoch ?? "-") + " (" + (enclave.enclave_id ?? "-") + ")"
+        : "unreachable at " + enclave.socket_path,
+      enclave.available ? "pass" : "fail",
+    ])
+  );
+}
+
+async function enclave() {
+  const info = await fetchJson("/enclave/info");
+  const rows = [
+    ["Enclave id", info.enclave_id],
+    ["Version", info.version],
+    ["Protocol version", info.protocol_version],
+    ["Uptime", duration(info.uptime_secs)],
+    ["Session established", info.session && time(info.session.established_at)],
+    [
+      "Unavailable capabilities",
+      info.unavailable_capabilities.length ? info.unavailable_capabilities.join(", ") : "none",
+      info.unavailable_capabilities.length ? "warn" : "pass",
+    ],
+  ];
+  if (info.hardening) {
+    rows.push(["Swap disabled", ...yesNo(info.hardening.swap_disabled)]);
+    rows.push(["Memory locked", ...yesNo(info.hardening.memory_locked)]);
+    rows.push(["Core dumps disabled", ...yesNo(info.hardening.core_dumps_disabled)]);
+  }
+  if (info.build) {
+    rows.push(["Build", info.build.git_commit + " (" + info.build.profile + ")"]);
+  }
+  fill("enclave", rows);
+}
+
+async function network() {
+  const status = await fetchJson("/network/status");
+  const state = status.state.state + (status.state.reason ? ": " + status.state.reason : "");
+  const connectivity = status.connectivity;
+  fill("network", [
+    ["State", state, status.state.state === "ready" ? "pass" : "fail"],
+    ["TAP interface", status.tap_interface],
+    ["Guest IP", status.guest_ip],
+    ["Gateway IP", status.gateway_ip],
+    ["MTU", status.mtu],
+    ["Gateway reachable", ...yesNo(connectivity.gateway)],
+    ["External reachable", ...yesNo(connectivity.external)],
+    ["DNS", ...yesNo(connectivity.dns)],
+    ["DHCP lease", status.lease && status.lease.state + ", expires " + time(status.lease.expires_at)],
+  ]);
+}
+
+async function operations() {
+  const { snapshot } = await fetchJson("/enclave/metrics/snapshot");
+  const counters = snapshot.counters;
+  const totals = Object.entries(counters).filter(([name]) => name.endsWith(".count"));
+
+  fill(
+    "operations",
+    totals.map(([name, count]) => {
+      const operation = name.slice(0, -".count".length);
+      const errors = counters[operation + ".errors"] || 0;
+      return [operation, count + (errors ? " (" + errors + " failed)" : ""), errors ? "warn" : ""];
+    })
+  );
+
+  if (previousCounters) {
+    const recent = totals
+      .map(([name, count]) => [name.slice(0, -".count".length), count - (previousCounters[name] || 0)])
+      .filter(([, delta]) => delta > 0);
+    fill(
+      "recent",
+      recent.length ? recent.map(([operation, delta]) => [operation, "+" + delta]) : [["none", ""]]
+    );
+  } else {
+    fill("recent", [["waiting for the next refresh", ""]]);
+  }
+  previousCounters = counters;
+}
+
+async function enclaveProcess() {
+  const status = await fetchJson("/admin/enclave/process", true);
+  fill("process", [
+    ["Command", status.command],
+    ["Running", ...yesNo(status.running)],
+    ["Pid", status.pid],
+    ["Restarts", status.restarts, status.restarts ? "warn" : ""],
+    ["Started", time(status.started_at)],
+    ["Last exit code", status.last_exit_code],
+    ["Last exit", time(status.last_exit_at)],
+    ["Stopped", status.stopped ? "yes" : "no", status.stopped ? "fail" : ""],
+  ]);
+}
+
+async function refresh() {
+  const panels = [
+    ["checks", readiness],
+    ["enclaves", enclaves],
+    ["enclave", enclave],
+    ["network", network],
+    ["operations", operations],
+    ["process", enclaveProcess],
+  ];
+  await Promise.all(
+    panels.map(([id, load]) =>
+      load().catch((error) => fill(id, [["Unavailable", error.message, "fail"]]))
+    )
+  );
+  document.getElementById("updated").textContent = "updated " + new Date().toLocaleTimeString();
+}
+
+document.getElementById("key-form").addEventListener("submit", (event) => {
+  event.preventDefault();
+  const input = document.getElementById("api-key");
+  if (input.value) {
+    sessionStorage.setItem(KEY_STORAGE, input.value);
+  } else {
+    sessionStorage.removeItem(KEY_STORAGE);
+  }
+  input.value = "";
+  refresh();
+});
+
+refresh();
+setInterval(refresh, REFRESH_MS);
@@ -0,0 +1,869 @@
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use log::{debug, info, warn};
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::nid::Nid;
+use openssl::pkey::Private;
+use openssl::x509::X509Req;
+use renclave_network::ProxyConfig;
+use renclave_shared::{AcmeCertificate, AcmeStatus, EnclaveResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use zeroize::Zeroizing;
+
+use crate::enclave_client::EnclaveApi;
+use crate::session::EnclaveEvent;
+use crate::storage::{Storage, StorageExt};
+use crate::webhook::reqwest_proxy;
+
+/// Environment variable listing the domains to obtain a certificate for, comma-separated;
+/// unset disables ACME
+pub const ACME_DOMAINS_ENV: &str = "RENCLAVE_ACME_DOMAINS";
+/// Environment variable for the ACME directory URL
+pub const ACME_DIRECTORY_ENV: &str = "RENCLAVE_ACME_DIRECTORY";
+/// Environment variable for the contact email registered with the ACME account
+pub const ACME_EMAIL_ENV: &str = "RENCLAVE_ACME_EMAIL";
+/// Environment variable for how many days before expiry the certificate is renewed
+pub const ACME_RENEW_DAYS_ENV: &str = "RENCLAVE_ACME_RENEW_DAYS";
+
+/// Let's Encrypt production directory
+pub const LETS_ENCRYPT_DIRECTORY: &str = "https://acme-v02.api.letsencrypt.org/directory";
+
+const DEFAULT_RENEW_DAYS: u64 = 30;
+/// How often the certificate is checked for renewal, besides after enclave restarts
+const CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+/// Delay between polls of pending authorizations and orders
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const POLL_ATTEMPTS: u32 = 30;
+
+/// Storage namespace of the account key and the issued certificate
+const NAMESPACE: &str = "acme";
+const ACCOUNT_KEY: &str = "account_key";
+const CERTIFICATE_KEY: &str = "certificate";
+
+const BAD_NONCE: &str = "urn:ietf:params:acme:error:badNonce";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcmeConfig {
+    pub directory_url: String,
+    pub domains: Vec<String>,
+    pub contact_email: Option<String>,
+    /// Renew once the certificate expires within this long
+    pub renew_before: Duration,
+}
+
+impl AcmeConfig {
+    /// Configuration from the environment, `None` unless domains are set
+    pub fn from_env() -> Result<Option<Self>> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let Some(domains) = var(ACME_DOMAINS_ENV) else {
+            return Ok(None);
+        };
+        let domains: Vec<String> = domains
+            .split(',')
+            .map(|domain| domain.trim().to_ascii_lowercase())
+            .filter(|domain| !domain.is_empty())
+            .collect();
+        if domains.is_empty() {
+            return Ok(None);
+        }
+        let renew_days = match var(ACME_RENEW_DAYS_ENV) {
+            Some(value) => value.parse().ok().filter(|days| *days > 0).ok_or_else(|| {
+                anyhow!(
+                    "{} must be a positive number of days, got '{}'",
+                    ACME_RENEW_DAYS_ENV,
+                    value
+                )
+            })?,
+            None => DEFAULT_RENEW_DAYS,
+        };
+
+        Ok(Some(Self {
+            directory_url: var(ACME_DIRECTORY_ENV)
+                .unwrap_or_else(|| LETS_ENCRYPT_DIRECTORY.to_string()),
+            domains,
+            contact_email: var(ACME_EMAIL_ENV),
+            renew_before: Duration::from_secs(renew_days * 86_400),
+        }))
+    }
+}
+
+/// Certificate chain issued for the enclave's key, kept in storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredCertificate {
+    domains: Vec<String>,
+    certificate_chain: String,
+    fingerprint: String,
+    not_after: u64,
+    issued_at: u64,
+}
+
+impl StoredCertificate {
+    fn summary(&self) -> AcmeCertificate {
+        AcmeCertificate {
+            fingerprint: self.fingerprint.clone(),
+            not_after: self.not_after,
+            issued_at: self.issued_at,
+        }
+    }
+}
+
+/// Obtains and renews the enclave's TLS certificate from an ACME CA such as Let's Encrypt.
+///
+/// The certificate key is generated inside the enclave and never leaves it: the enclave
+/// produces the certificate signing request and installs the issued chain. The host only
+/// holds the ACME account key, answers HTTP-01 challenges at
+/// `/.well-known/acme-challenge/{token}` and keeps the issued chain in storage. Since the
+/// enclave key lives in memory, a new certificate is issued after every enclave restart.
+pub struct AcmeManager {
+    config: AcmeConfig,
+    client: reqwest::Client,
+    storage: Arc<dyn Storage>,
+    /// Talks to the enclave directly, so restarts show up in `GetInfo` right away
+    enclave: Arc<dyn EnclaveApi>,
+    account_key: EcKey<Private>,
+    /// Key authorizations of pending HTTP-01 challenges by token
+    challenges: Mutex<HashMap<String, String>>,
+    status: Mutex<AcmeStatus>,
+    /// Held while a certificate is being issued
+    issuing: tokio::sync::Mutex<()>,
+}
+
+impl AcmeManager {
+    /// Manager for `config`, reaching the CA through `proxy` when one is configured. The
+    /// account key is loaded from `storage`, or generated and stored on first use.
+    pub fn new(
+        config: AcmeConfig,
+        proxy: Option<&ProxyConfig>,
+        storage: Arc<dyn Storage>,
+        enclave: Arc<dyn EnclaveApi>,
+    ) -> Result<Self> {
+        let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(30));
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(reqwest_proxy(proxy)?);
+        }
+        let account_key = account_key(storage.as_ref())?;
+        let certificate = storage
+            .get_json::<StoredCertificate>(NAMESPACE, CERTIFICATE_KEY)?
+            .map(|stored| stored.summary());
+
+        Ok(Self {
+            status: Mutex::new(AcmeStatus {
+                directory_url: config.directory_url.clone(),
+                domains: config.domains.clone(),
+                certificate,
+                ..Default::default()
+            }),
+            config,
+            client: builder.build().context("Failed to build ACME client")?,
+            storage,
+            enclave,
+            account_key,
+            challenges: Mutex::new(HashMap::new()),
+            issuing: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn status(&self) -> AcmeStatus {
+        lock(&self.status).clone()
+    }
+
+    /// Key authorization to serve for the HTTP-01 challenge `token`, while it is pending
+    pub fn challenge_response(&self, token: &str) -> Option<String> {
+        lock(&self.challenges).get(token).cloned()
+    }
+
+    /// Check the certificate now, then hourly and whenever the enclave restarts
+    pub fn spawn_renewal(self: &Arc<Self>, mut events: broadcast::Receiver<EnclaveEvent>) {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = manager.ensure_certificate().await {
+                    warn!("⚠️  ACME certificate check failed: {:#}", e);
+                }
+                let restarted = async {
+                    loop {
+                        match events.recv().await {
+                            Ok(EnclaveEvent::Restarted { .. }) => break,
+                            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => {
+                                std::future::pending::<()>().await
+                            }
+                        }
+                    }
+                };
+                tokio::select! {
+                    _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+                    _ = restarted => info!("🔄 Enclave restarted, checking its TLS certificate"),
+                }
+            }
+        });
+    }
+
+    /// Issue a certificate if the enclave serves none from this CA, or it expires soon.
+    /// Returns whether one was issued.
+    pub async fn ensure_certificate(&self) -> Result<bool> {
+        let _issuing = self.issuing.lock().await;
+        let result = self.renew_if_needed().await;
+        self.record(&result);
+        result
+    }
+
+    async fn renew_if_needed(&self) -> Result<bool> {
+        let fingerprint = self.enclave_fingerprint().await?;
+        let stored = self
+            .storage
+            .get_json::<StoredCertificate>(NAMESPACE, CERTIFICATE_KEY)?;
+
+        match renewal_reason(
+            stored.as_ref(),
+            &fingerprint,
+            &self.config.domains,
+            unix_now(),
+            self.config.renew_before,
+        ) {
+            Some(reason) => {
+                info!("📜 Requesting ACME certificate: {}", reason);
+                self.issue().await.map(|_| true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Issue and install a new certificate now, regardless of the current one
+    pub async fn renew(&self) -> Result<AcmeCertificate> {
+        let _issuing = self.issuing.lock().await;
+        let result = self.issue().await;
+        self.record(&result);
+        result
+    }
+
+    fn record<T>(&self, result: &Result<T>) {
+        let mut status = lock(&self.status);
+        status.last_checked_at = Some(unix_now());
+        status.last_error = result.as_ref().err().map(|e| format!("{:#}", e));
+    }
+
+    async fn enclave_fingerprint(&self) -> Result<String> {
+        match self.enclave.get_info().await?.result {
+            EnclaveResult::Info {
+                tls_fingerprint, ..
+            } => tls_fingerprint.ok_or_else(|| {
+                anyhow!(
+                    "The enclave does not terminate TLS; set {}",
+                    renclave_shared::ENCLAVE_TLS_SOCKET_ENV
+                )
+            }),
+            EnclaveResult::Error { message, .. } => Err(anyhow!(message)),
+            _ => Err(anyhow!("Unexpected enclave response to GetInfo")),
+        }
+    }
+
+    async fn issue(&self) -> Result<AcmeCertificate> {
+        let result = self.order().await;
+        lock(&self.challenges).clear();
+        let certificate_chain = result?;
+
+        let (fingerprint, not_after) = match self
+            .enclave
+            .install_tls_certificate(certificate_chain.clone())
+            .await?
+            .result
+        {
+            EnclaveResult::TlsCertificate {
+                fingerprint,
+                not_after,
+            } => (fingerprint, not_after),
+            EnclaveResult::Error { message, .. } => {
+                return Err(anyhow!("The enclave refused the certificate: {}", message))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Unexpected enclave response to InstallTlsCertificate"
+                ))
+            }
+        };
+        let stored = StoredCertificate {
+            domains: self.config.domains.clone(),
+            certificate_chain,
+            fingerprint,
+            not_after,
+            issued_at: unix_now(),
+        };
+        self.storage
+            .put_json(NAMESPACE, CERTIFICATE_KEY, &stored)
+            .context("Failed to store the certificate")?;
+        info!(
+            "✅ Enclave serves ACME certificate {} for {}, valid until {}",
+            stored.fingerprint,
+            stored.domains.join(", "),
+            stored.not_after
+        );
+
+        let summary = stored.summary();
+        lock(&self.status).certificate = Some(summary.clone());
+        Ok(summary)
+    }
+
+    /// Run an order for the configured domains through to the PEM certificate chain
+    async fn order(&self) -> Result<String> {
+        let mut session =
+            Session::open(&self.client, &self.account_key, &self.config.directory_url).await?;
+        session
+            .register(self.config.contact_email.as_deref())
+            .await?;
+
+        let identifiers: Vec<Value> = self
+            .config
+            .domains
+            .iter()
+            .map(|domain| json!({ "type": "dns", "value": domain }))
+            .collect();
+        let new_order = session.directory.new_order.clone();
+        let response = session
+            .post(&new_order, Some(&json!({ "identifiers": identifiers })))
+            .await?;
+        let order_url = location(&response)?;
+        let order: Order = response.json().await.context("Invalid ACME order")?;
+
+        let thumbprint = thumbprint(&self.account_key)?;
+        for authorization in &order.authorizations {
+            self.authorize(&mut session, authorization, &thumbprint)
+                .await?;
+        }
+
+        let order = session.poll_order(&order_url, "ready").await?;
+        let csr = match self
+            .enclave
+            .create_tls_csr(self.config.domains.clone())
+            .await?
+            .result
+        {
+            EnclaveResult::TlsCsr { csr } => csr,
+            EnclaveResult::Error { message, .. } => {
+                return Err(anyhow!(
+                    "The enclave refused the certificate request: {}",
+                    message
+                ))
+            }
+            _ => return Err(anyhow!("Unexpected enclave response to CreateTlsCsr")),
+        };
+        let csr = X509Req::from_pem(csr.as_bytes())
+            .and_then(|csr| csr.to_der())
+            .context("Invalid certificate request from the enclave")?;
+        session
+            .post(
+                &order.finalize,
+                Some(&json!({ "csr": URL_SAFE_NO_PAD.encode(csr) })),
+            )
+            .await?;
+
+        let order = session.poll_order(&order_url, "valid").await?;
+        let certificate_url = order
+            .certificate
+            .ok_or_else(|| anyhow!("The ACME order is valid but has no certificate"))?;
+        let chain = session
+            .post(&certificate_url, None)
+            .await?
+            .text()
+            .await
+            .context("Failed to download the certificate")?;
+        Ok(chain)
+    }
+
+    /// Complete the HTTP-01 challenge of the authorization at `url`
+    async fn authorize(
+        &self,
+        session: &mut Session<'_>,
+        url: &str,
+        thumbprint: &str,
+    ) -> Result<()> {
+        let authorization: Authorization = session.post_json(url, None).await?;
+        if authorization.status == "valid" {
+            return Ok(());
+        }
+        let domain = authorization.identifier.value.clone();
+        let challenge = authorization
+            .challenges
+            .iter()
+            .find(|challenge| challenge.kind == "http-01")
+            .ok_or_else(|| anyhow!("The CA offers no HTTP-01 challenge for {}", domain))?;
+        let token = challenge
+            .token
+            .clone()
+            .ok_or_else(|| anyhow!("The HTTP-01 challenge for {} has no token", domain))?;
+
+        debug!("🔑 Answering HTTP-01 challenge for {}", domain);
+        lock(&self.challenges).insert(token.clone(), key_authorization(&token, thumbprint));
+        session.post(&challenge.url, Some(&json!({}))).await?;
+
+        for _ in 0..POLL_ATTEMPTS {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let authorization: Authorization = session.post_json(url, None).await?;
+            match authorization.status.as_str() {
+                "pending" | "processing" => continue,
+                "valid" => {
+                    info!("✅ {} validated", domain);
+                    lock(&self.challenges).remove(&token);
+                    return Ok(());
+                }
+                status => {
+                    let error = authorization
+                        .challenges
+                        .iter()
+                        .find_map(|challenge| challenge.error.as_ref())
+                        .map(problem_detail)
+                        .unwrap_or_default();
+                    return Err(anyhow!(
+                        "Validation of {} failed ({}){}",
+                        domain,
+                        status,
+                        error
+                    ));
+                }
+            }
+        }
+        Err(anyhow!("Validation of {} timed out", domain))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Order {
+    status: String,
+    #[serde(default)]
+    authorizations: Vec<String>,
+    finalize: String,
+    #[serde(default)]
+    certificate: Option<String>,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Authorization {
+    status: String,
+    identifier: Identifier,
+    #[serde(default)]
+    challenges: Vec<Challenge>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identifier {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Challenge {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+/// Signed requests to one ACME server, tracking the replay nonce and account URL
+struct Session<'a> {
+    client: &'a reqwest::Client,
+    key: &'a EcKey<Private>,
+    directory: Directory,
+    nonce: Option<String>,
+    account_url: Option<String>,
+}
+
+impl<'a> Session<'a> {
+    async fn open(
+        client: &'a reqwest::Client,
+        key: &'a EcKey<Private>,
+        directory_url: &str,
+    ) -> Result<Self> {
+        let directory = client
+            .get(directory_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to fetch ACME directory {}", directory_url))?
+            .json()
+            .await
+            .context("Invalid ACME directory")?;
+        Ok(Self {
+            client,
+            key,
+            directory,
+            nonce: None,
+            account_url: None,
+        })
+    }
+
+    /// Find or create the account for the key, agreeing to the CA's terms of service
+    async fn register(&mut self, contact_email: Option<&str>) -> Result<()> {
+        let mut account = json!({ "termsOfServiceAgreed": true });
+        if let Some(email) = contact_email {
+            account["contact"] = json!([format!("mailto:{}", email)]);
+        }
+        let new_account = self.directory.new_account.clone();
+        let response = self.post(&new_account, Some(&account)).await?;
+        let account_url = location(&response)?;
+        debug!("👤 Using ACME account {}", account_url);
+        self.account_url = Some(account_url);
+        Ok(())
+    }
+
+    async fn nonce(&mut self) -> Result<String> {
+        if let Some(nonce) = self.nonce.take() {
+            return Ok(nonce);
+        }
+        let response = self
+            .client
+            .head(&self.directory.new_nonce)
+            .send()
+            .await
+            .context("Failed to fetch an ACME nonce")?;
+        replay_nonce(&response).ok_or_else(|| anyhow!("The ACME server sent no nonce"))
+    }
+
+    /// POST `payload` signed with the account key, or POST-as-GET when it is `None`.
+    /// A rejected nonce is retried once with the fresh one the server sent.
+    async fn post(&mut self, url: &str, payload: Option<&Value>) -> Result<reqwest::Response> {
+        let mut retried = false;
+        loop {
+            let mut protected = json!({
+                "alg": "ES256",
+                "nonce": self.nonce().await?,
+                "url": url,
+            });
+            match &self.account_url {
+                Some(account_url) => protected["kid"] = json!(account_url),
+                None => protected["jwk"] = jwk(self.key)?,
+            }
+            let body = sign_jws(self.key, &protected, payload)?;
+
+            let response = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/jose+json")
+                .body(body.to_string())
+                .send()
+                .await
+                .with_context(|| format!("ACME request to {} failed", url))?;
+            self.nonce = replay_nonce(&response);
+            if response.status().is_success() {
+                return Ok(response);
+            }
+
+            let status = response.status();
+            let problem: Value = response.json().await.unwrap_or(Value::Null);
+            if problem["type"] == BAD_NONCE && !retried {
+                retried = true;
+                continue;
+            }
+            return Err(anyhow!(
+                "ACME request to {} failed with {}{}",
+                url,
+                status,
+                problem_detail(&problem)
+            ));
+        }
+    }
+
+    async fn post_json<T: DeserializeOwned>(
+        &mut self,
+        url: &str,
+        payload: Option<&Value>,
+    ) -> Result<T> {
+        self.post(url, payload)
+            .await?
+            .json()
+            .await
+            .with_context(|| format!("Invalid ACME response from {}", url))
+    }
+
+    /// Poll the order at `url` until it reaches `status`
+    async fn poll_order(&mut self, url: &str, status: &str) -> Result<Order> {
+        for attempt in 0..POLL_ATTEMPTS {
+            let order: Order = self.post_json(url, None).await?;
+            if order.status == status {
+                return Ok(order);
+            }
+            if order.status == "invalid" {
+                return Err(anyhow!(
+                    "The ACME order is invalid{}",
+                    order.error.as_ref().map(problem_detail).unwrap_or_default()
+                ));
+            }
+            if attempt + 1 < POLL_ATTEMPTS {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+        Err(anyhow!("The ACME order did not become {} in time", status))
+    }
+}
+
+/// Why the enclave needs a new certificate, or `None` if the stored one is current
+fn renewal_reason(
+    stored: Option<&StoredCertificate>,
+    enclave_fingerprint: &str,
+    domains: &[String],
+    now: u64,
+    renew_before: Duration,
+) -> Option<&'static str> {
+    let Some(stored) = stored else {
+        return Some("no certificate issued yet");
+    };
+    if stored.fingerprint != enclave_fingerprint {
+        return Some("the enclave does not serve the issued certificate");
+    }
+    if stored.domains != domains {
+        return Some("the configured domains changed");
+    }
+    if stored.not_after <= now.saturating_add(renew_before.as_secs()) {
+        return Some("the certificate expires soon");
+    }
+    None
+}
+
+/// Load the ACME account key from `storage`, generating and storing one if there is none
+fn account_key(storage: &dyn Storage) -> Result<EcKey<Private>> {
+    if let Some(pem) = storage.get(NAMESPACE, ACCOUNT_KEY)? {
+        let pem = Zeroizing::new(pem);
+        return EcKey::private_key_from_pem(&pem).context("Corrupt ACME account key");
+    }
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let key = EcKey::generate(&group)?;
+    let pem = Zeroizing::new(key.private_key_to_pem()?);
+    storage.put(NAMESPACE, ACCOUNT_KEY, &pem)?;
+    info!("👤 Generated a new ACME account key");
+    Ok(key)
+}
+
+/// Public JWK of a P-256 key
+fn jwk(key: &EcKey<Private>) -> Result<Value> {
+    let mut x = BigNum::new()?;
+    let mut y = BigNum::new()?;
+    let mut context = BigNumContext::new()?;
+    key.public_key()
+        .affine_coordinates(key.group(), &mut x, &mut y, &mut context)?;
+    Ok(json!({
+        "crv": "P-256",
+        "kty": "EC",
+        "x": URL_SAFE_NO_PAD.encode(x.to_vec_padded(32)?),
+        "y": URL_SAFE_NO_PAD.encode(y.to_vec_padded(32)?),
+    }))
+}
+
+/// RFC 7638 thumbprint of the account key
+fn thumbprint(key: &EcKey<Private>) -> Result<String> {
+    // Required members only, sorted and without whitespace
+    let jwk = jwk(key)?;
+    let canonical = format!(
+        r#"{{"crv":"P-256","kty":"EC","x":{},"y":{}}}"#,
+        jwk["x"], jwk["y"]
+    );
+    Ok(URL_SAFE_NO_PAD.encode(openssl::sha::sha256(canonical.as_bytes())))
+}
+
+fn key_authorization(token: &str, thumbprint: &str) -> String {
+    format!("{}.{}", token, thumbprint)
+}
+
+/// Flattened JWS of `payload` signed with ES256; an empty payload for POST-as-GET
+fn sign_jws(key: &EcKey<Private>, protected: &Value, payload: Option<&Value>) -> Result<Value> {
+    let protected = URL_SAFE_NO_PAD.encode(protected.to_string());
+    let payload = payload
+        .map(|payload| URL_SAFE_NO_PAD.encode(payload.to_string()))
+        .unwrap_or_default();
+    let digest = openssl::sha::sha256(format!("{}.{}", protected, payload).as_bytes());
+    let signature = EcdsaSig::sign(&digest, key)?;
+
+    let mut raw = signature.r().to_vec_padded(32)?;
+    raw.extend(signature.s().to_vec_padded(32)?);
+    Ok(json!({
+        "protected": protected,
+        "payload": payload,
+        "signature": URL_SAFE_NO_PAD.encode(raw),
+    }))
+}
+
+fn replay_nonce(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get("Replay-Nonce")
+        .and_then(|nonce| nonce.to_str().ok())
+        .map(str::to_string)
+}
+
+fn location(response: &reqwest::Response) -> Result<String> {
+    response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("The ACME server sent no Location"))
+}
+
+/// `: <detail>` of an RFC 7807 problem document, empty if it has none
+fn problem_detail(problem: &Value) -> String {
+    problem["detail"]
+        .as_str()
+        .map(|detail| format!(": {}", detail))
+        .unwrap_or_default()
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_enclave::MockEnclave;
+    use crate::storage::MemoryStorage;
+    use openssl::bn::BigNum;
+
+    fn stored(fingerprint: &str, not_after: u64) -> StoredCertificate {
+        StoredCertificate {
+            domains: vec!["enclave.example".to_string()],
+            certificate_chain: String::new(),
+            fingerprint: fingerprint.to_string(),
+            not_after,
+            issued_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_renewal_reason() {
+        let domains = vec!["enclave.example".to_string()];
+        let month = Duration::from_secs(30 * 86_400);
+        let now = 1_700_000_000;
+
+        assert!(renewal_reason(None, "aa", &domains, now, month).is_some());
+        let current = stored("aa", now + 60 * 86_400);
+        assert_eq!(
+            renewal_reason(Some(&current), "aa", &domains, now, month),
+            None
+        );
+        // The enclave restarted with a new key
+        assert!(renewal_reason(Some(&current), "bb", &domains, now, month).is_some());
+        assert!(renewal_reason(Some(&current), "aa", &[], now, month).is_some());
+        let expiring = stored("aa", now + 10 * 86_400);
+        assert!(renewal_reason(Some(&expiring), "aa", &domains, now, month).is_some());
+    }
+
+    #[test]
+    fn test_jws_signature_verifies() {
+        let key = account_key(&MemoryStorage::default()).unwrap();
+        let protected = json!({ "alg": "ES256", "nonce": "n", "url": "https://ca.example" });
+        let jws = sign_jws(&key, &protected, Some(&json!({ "a": 1 }))).unwrap();
+
+        let decode = |field: &str| {
+            URL_SAFE_NO_PAD
+                .decode(jws[field].as_str().unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            serde_json::from_slice::<Value>(&decode("protected")).unwrap(),
+            protected
+        );
+        assert_eq!(decode("payload"), br#"{"a":1}"#);
+
+        let raw = decode("signature");
+        assert_eq!(raw.len(), 64);
+        let signature = EcdsaSig::from_private_components(
+            BigNum::from_slice(&raw[..32]).unwrap(),
+            BigNum::from_slice(&raw[32..]).unwrap(),
+        )
+        .unwrap();
+        let signed = format!(
+            "{}.{}",
+            jws["protected"].as_str().unwrap(),
+            jws["payload"].as_str().unwrap()
+        );
+        let digest = openssl::sha::sha256(signed.as_bytes());
+        assert!(signature.verify(&digest, &key).unwrap());
+
+        let get = sign_jws(&key, &protected, None).unwrap();
+        assert_eq!(get["payload"], "");
+    }
+
+    #[test]
+    fn test_jwk_and_thumbprint() {
+        let key = account_key(&MemoryStorage::default()).unwrap();
+        let jwk = jwk(&key).unwrap();
+        for coordinate in ["x", "y"] {
+            let bytes = URL_SAFE_NO_PAD
+                .decode(jwk[coordinate].as_str().unwrap())
+                .unwrap();
+            assert_eq!(bytes.len(), 32);
+        }
+
+        let thumbprint = thumbprint(&key).unwrap();
+        assert_eq!(URL_SAFE_NO_PAD.decode(&thumbprint).unwrap().len(), 32);
+        assert_eq!(
+            key_authorization("token", &thumbprint),
+            format!("token.{}", thumbprint)
+        );
+    }
+
+    #[test]
+    fn test_account_key_is_kept_in_storage() {
+        let storage = MemoryStorage::default();
+        let first = account_key(&storage).unwrap();
+        let second = account_key(&storage).unwrap();
+        assert_eq!(thumbprint(&first).unwrap(), thumbprint(&second).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_enclave_without_tls_is_reported() {
+        let config = AcmeConfig {
+            directory_url: "http://127.0.0.1:9/directory".to_string(),
+            domains: vec!["enclave.example".to_string()],
+            contact_email: None,
+            renew_before: Duration::from_secs(86_400),
+        };
+        let manager = AcmeManager::new(
+            config,
+            None,
+            Arc::new(MemoryStorage::default()),
+            Arc::new(MockEnclave::new()),
+        )
+        .unwrap();
+
+        assert!(manager.ensure_certificate().await.is_err());
+        let status = manager.status();
+        assert!(status
+            .last_error
+            .unwrap()
+            .contains("does not terminate TLS"));
+        assert!(status.last_checked_at.is_some());
+        assert_eq!(manager.challenge_response("token"), None);
+    }
+}
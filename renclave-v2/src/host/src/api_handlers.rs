@@ -1,27 +1,172 @@
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
 use log::{debug, error, info, warn};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+use crate::error::{ApiError, ApiJson};
+use crate::webhook::WebhookEvent;
 use crate::AppState;
-#[allow(unused_imports)]
-use renclave_network::HttpConnectivityResult;
 use renclave_shared::*;
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "service",
+    responses((status = 200, description = "Host is running"))
+)]
 pub async fn health_check() -> StatusCode {
     debug!("🏥 Health check endpoint called");
     StatusCode::OK
 }
 
+/// Upper bound for each readiness check, so probes answer well within k8s timeouts
+const READINESS_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Liveness probe: the HTTP server is running
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "service",
+    responses((status = 200, description = "Host process is alive", body = HealthReport))
+)]
+pub async fn health_live() -> Json<HealthReport> {
+    debug!("🏥 Liveness probe called");
+    Json(HealthReport::from_checks(Vec::new()))
+}
+
+/// Readiness probe: the enclave is reachable and the network state is known
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "service",
+    responses(
+        (status = 200, description = "Host is ready to serve requests", body = HealthReport),
+        (status = 503, description = "A critical check failed", body = HealthReport)
+    )
+)]
+pub async fn health_ready(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    debug!("🏥 Readiness probe called");
+
+    let checks = vec![check_enclave(&state).await, check_network(&state)];
+    let report = HealthReport::from_checks(checks);
+
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        warn!("⚠️  Readiness check failed: {:?}", report.checks);
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(report))
+}
+
+/// Enclave socket answers GetInfo and reports its capabilities
+async fn check_enclave(state: &AppState) -> HealthCheck {
+    let start = Instant::now();
+
+    let (status, message) = match tokio::time::timeout(
+        READINESS_CHECK_TIMEOUT,
+        state.enclave_client.get_info(),
+    )
+    .await
+    {
+        Ok(Ok(response)) => match response.result {
+            EnclaveResult::Info {
+                enclave_id,
+                unavailable_capabilities,
+                ..
+            } if unavailable_capabilities.is_empty() => {
+                state.session.observe(&enclave_id);
+                (
+                    HealthStatus::Pass,
+                    Some(format!("enclave {} reachable", enclave_id)),
+                )
+            }
+            EnclaveResult::Info {
+                enclave_id,
+                unavailable_capabilities,
+                ..
+            } => {
+                state.session.observe(&enclave_id);
+                (
+                    HealthStatus::Warn,
+                    Some(format!(
+                        "enclave {} reachable, unavailable capabilities: {}",
+                        enclave_id,
+                        unavailable_capabilities.join(", ")
+                    )),
+                )
+            }
+            EnclaveResult::Error { message, .. } => (HealthStatus::Fail, Some(message)),
+            _ => (
+                HealthStatus::Fail,
+                Some("Unexpected response from enclave".to_string()),
+            ),
+        },
+        Ok(Err(e)) => (
+            HealthStatus::Fail,
+            Some(format!("Enclave communication failed: {}", e)),
+        ),
+        Err(_) => (
+            HealthStatus::Fail,
+            Some(format!(
+                "Enclave did not answer within {:?}",
+                READINESS_CHECK_TIMEOUT
+            )),
+        ),
+    };
+
+    HealthCheck {
+        name: "enclave".to_string(),
+        status,
+        critical: true,
+        message,
+        duration_ms: start.elapsed().as_millis() as u64,
+    }
+}
+
+/// Host network state; degraded networking does not make the host unready
+fn check_network(state: &AppState) -> HealthCheck {
+    let (status, message) = match state.network_manager.state() {
+        NetworkState::Ready => (HealthStatus::Pass, None),
+        NetworkState::Initializing => (
+            HealthStatus::Warn,
+            Some("network initialization in progress".to_string()),
+        ),
+        NetworkState::NetworkUnavailable { reason, .. } => (HealthStatus::Fail, Some(reason)),
+    };
+
+    HealthCheck {
+        name: "network".to_string(),
+        status,
+        critical: false,
+        message,
+        duration_ms: 0,
+    }
+}
+
 /// Get service information
+#[utoipa::path(
+    get,
+    path = "/info",
+    tag = "service",
+    responses((status = 200, description = "Service information", body = InfoResponse))
+)]
 pub async fn get_info(
     State(state): State<AppState>,
-) -> std::result::Result<Json<InfoResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> std::result::Result<Json<InfoResponse>, ApiError> {
     info!("ℹ️  Service info requested");
 
     // Get network status
-    let network_status = state.network_manager.get_status().await;
-    let network_status_str = if network_status.connectivity.external {
+    let network_status = current_network_status(&state).await;
+    let network_status_str = if !network_status.state.is_available() {
+        "unavailable"
+    } else if network_status.connectivity.external {
         "connected"
     } else if network_status.connectivity.gateway {
         "limited"
@@ -33,22 +178,29 @@ pub async fn get_info(
     // Try to get enclave info
     let enclave_id = match state.enclave_client.get_info().await {
         Ok(response) => match response.result {
-            EnclaveResult::Info { enclave_id, .. } => enclave_id,
+            EnclaveResult::Info { enclave_id, .. } => {
+                state.session.observe(&enclave_id);
+                enclave_id
+            }
             _ => "unknown".to_string(),
         },
         Err(_) => "unavailable".to_string(),
     };
 
-    let info = InfoResponse {
-        version: env!("CARGO_PKG_VERSION").to_string(),
-        service: "QEMU Host API Gateway".to_string(),
-        enclave_id,
-        capabilities: vec![
+    let (capabilities, unavailable_capabilities) =
+        network_status.state.partition_capabilities(vec![
             "seed_generation".to_string(),
             "seed_validation".to_string(),
             "network_connectivity".to_string(),
             "enclave_communication".to_string(),
-        ],
+        ]);
+
+    let info = InfoResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        service: "QEMU Host API Gateway".to_string(),
+        enclave_id,
+        capabilities,
+        unavailable_capabilities,
         network_status: network_status_str,
     };
 
@@ -57,27 +209,34 @@ pub async fn get_info(
 }
 
 /// Generate seed phrase
+#[utoipa::path(
+    post,
+    path = "/generate-seed",
+    tag = "seed",
+    request_body = GenerateSeedRequest,
+    responses(
+        (status = 200, description = "Seed phrase generated", body = GenerateSeedResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
 pub async fn generate_seed(
     State(state): State<AppState>,
-    Json(request): Json<GenerateSeedRequest>,
-) -> std::result::Result<Json<GenerateSeedResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(request): ApiJson<GenerateSeedRequest>,
+) -> std::result::Result<Json<GenerateSeedResponse>, ApiError> {
     let request_id = Uuid::new_v4().to_string();
     info!("🔑 Seed generation requested (ID: {})", request_id);
 
     // Validate request
-    let strength = request.strength.unwrap_or(256);
-    if ![128, 160, 192, 224, 256].contains(&strength) {
-        warn!("❌ Invalid strength requested: {}", strength);
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid strength. Must be 128, 160, 192, 224, or 256 bits".to_string(),
-                code: 400,
-                request_id: Some(request_id),
-            }),
-        ));
+    if let Err(errors) = request.validate() {
+        warn!("❌ Invalid request: {}", errors);
+        return Err(ApiError::invalid_fields(errors, Some(request_id)));
     }
 
+    let strength = request
+        .strength
+        .unwrap_or(validation::Strength::DEFAULT.bits());
     debug!(
         "📋 Request validated - strength: {}, passphrase: {}",
         strength,
@@ -87,7 +246,13 @@ pub async fn generate_seed(
     // Send request to enclave
     match state
         .enclave_client
-        .generate_seed(strength, request.passphrase)
+        .generate_seed(
+            strength,
+            request.passphrase,
+            request.language,
+            request.attest,
+            request.fresh_attestation,
+        )
         .await
     {
         Ok(enclave_response) => match enclave_response.result {
@@ -96,6 +261,7 @@ pub async fn generate_seed(
                 entropy,
                 strength,
                 word_count,
+                attestation,
             } => {
                 info!("✅ Seed generation successful (ID: {})", request_id);
                 Ok(Json(GenerateSeedResponse {
@@ -103,180 +269,240 @@ pub async fn generate_seed(
                     entropy,
                     strength,
                     word_count,
+                    attestation,
                 }))
             }
             EnclaveResult::Error { message, code } => {
                 error!("❌ Enclave error during seed generation: {}", message);
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: message,
-                        code,
-                        request_id: Some(request_id),
-                    }),
+                Err(ApiError::enclave_operation_failed(
+                    message,
+                    code,
+                    Some(request_id),
                 ))
             }
             _ => {
                 error!("❌ Unexpected response type from enclave");
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: "Unexpected response from enclave".to_string(),
-                        code: 500,
-                        request_id: Some(request_id),
-                    }),
-                ))
+                Err(ApiError::unexpected_enclave_response(Some(request_id)))
             }
         },
         Err(e) => {
             error!("❌ Failed to communicate with enclave: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("Enclave communication failed: {}", e),
-                    code: 503,
-                    request_id: Some(request_id),
-                }),
-            ))
+            Err(ApiError::enclave_unavailable(e, Some(request_id)))
         }
     }
 }
 
 /// Validate seed phrase
+#[utoipa::path(
+    post,
+    path = "/validate-seed",
+    tag = "seed",
+    request_body = ValidateSeedRequest,
+    responses(
+        (status = 200, description = "Validation result", body = ValidateSeedResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
 pub async fn validate_seed(
     State(state): State<AppState>,
-    Json(request): Json<ValidateSeedRequest>,
-) -> std::result::Result<Json<ValidateSeedResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ApiJson(request): ApiJson<ValidateSeedRequest>,
+) -> std::result::Result<Json<ValidateSeedResponse>, ApiError> {
     let request_id = Uuid::new_v4().to_string();
     info!("🔍 Seed validation requested (ID: {})", request_id);
 
     // Validate request
-    if request.seed_phrase.trim().is_empty() {
-        warn!("❌ Empty seed phrase provided");
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Seed phrase cannot be empty".to_string(),
-                code: 400,
-                request_id: Some(request_id),
-            }),
-        ));
+    if let Err(errors) = request.validate() {
+        warn!("❌ Invalid request: {}", errors);
+        return Err(ApiError::invalid_fields(errors, Some(request_id)));
     }
 
     debug!(
         "📋 Request validated - seed phrase length: {}",
-        request.seed_phrase.len()
+        request.seed_phrase.expose_secret().len()
     );
 
     // Send request to enclave
     match state
         .enclave_client
-        .validate_seed(request.seed_phrase)
+        .validate_seed(request.seed_phrase, request.language, request.entropy)
         .await
     {
         Ok(enclave_response) => match enclave_response.result {
-            EnclaveResult::SeedValidated { valid, word_count } => {
+            EnclaveResult::SeedValidated {
+                valid,
+                word_count,
+                entropy_match,
+            } => {
                 info!(
-                    "✅ Seed validation completed (ID: {}, valid: {})",
-                    request_id, valid
+                    "✅ Seed validation completed (ID: {}, valid: {}, entropy match: {:?})",
+                    request_id, valid, entropy_match
                 );
-                Ok(Json(ValidateSeedResponse { valid, word_count }))
+                Ok(Json(ValidateSeedResponse {
+                    valid,
+                    word_count,
+                    entropy_match,
+                }))
             }
             EnclaveResult::Error { message, code } => {
                 error!("❌ Enclave error during seed validation: {}", message);
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: message,
-                        code,
-                        request_id: Some(request_id),
-                    }),
+                Err(ApiError::enclave_operation_failed(
+                    message,
+                    code,
+                    Some(request_id),
                 ))
             }
             _ => {
                 error!("❌ Unexpected response type from enclave");
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: "Unexpected response from enclave".to_string(),
-                        code: 500,
-                        request_id: Some(request_id),
-                    }),
-                ))
+                Err(ApiError::unexpected_enclave_response(Some(request_id)))
             }
         },
         Err(e) => {
             error!("❌ Failed to communicate with enclave: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("Enclave communication failed: {}", e),
-                    code: 503,
-                    request_id: Some(request_id),
-                }),
-            ))
+            Err(ApiError::enclave_unavailable(e, Some(request_id)))
         }
     }
 }
 
 /// Get network status
+#[utoipa::path(
+    get,
+    path = "/network/status",
+    tag = "network",
+    responses((status = 200, description = "TAP network status", body = serde_json::Value))
+)]
 pub async fn network_status(State(state): State<AppState>) -> Json<serde_json::Value> {
     debug!("🌐 Network status requested");
 
-    let status = state.network_manager.get_status().await;
+    let status = current_network_status(&state).await;
 
     let response = serde_json::json!({
+        "state": status.state,
         "tap_interface": status.tap_interface,
         "guest_ip": status.guest_ip,
         "gateway_ip": status.gateway_ip,
+        "guest_ipv6": status.guest_ipv6,
+        "gateway_ipv6": status.gateway_ipv6,
+        "ip_preference": status.ip_preference.as_str(),
+        "mtu": status.mtu,
+        "path_mtu": status.path_mtu,
+        "proxy": status.proxy,
+        "lease": status.lease.map(|lease| serde_json::json!({
+            "state": lease.state.as_str(),
+            "address": format!("{}/{}", lease.address, lease.prefix_len),
+            "gateway": lease.gateway,
+            "dns_servers": lease.dns_servers,
+            "server": lease.server,
+            "renew_at": lease.renew_at(),
+            "rebind_at": lease.rebind_at(),
+            "expires_at": lease.expires_at(),
+        })),
         "connectivity": {
             "loopback": status.connectivity.loopback,
             "gateway": status.connectivity.gateway,
             "external": status.connectivity.external,
             "dns": status.connectivity.dns,
-        }
+        },
+        "monitor": status.monitor,
     });
 
     debug!("✅ Network status response prepared");
     Json(response)
 }
 
+/// Egress allowlist and the firewall rules enforcing it
+#[utoipa::path(
+    get,
+    path = "/network/egress",
+    tag = "network",
+    responses((status = 200, description = "Egress policy and active nftables rules", body = serde_json::Value))
+)]
+pub async fn egress_policy(State(state): State<AppState>) -> Json<renclave_network::EgressStatus> {
+    Json(state.network_manager.egress_status())
+}
+
+/// Network status, served from cache while fresh
+async fn current_network_status(state: &AppState) -> renclave_network::NetworkStatus {
+    if let Some(status) = state.network_status_cache.get() {
+        return status;
+    }
+    let status = state.network_manager.get_status().await;
+    state.network_status_cache.put(status.clone());
+    status
+}
+
 /// Test network connectivity
-pub async fn test_connectivity(State(state): State<AppState>) -> Json<serde_json::Value> {
+#[utoipa::path(
+    post,
+    path = "/network/test",
+    tag = "network",
+    responses(
+        (status = 200, description = "Connectivity test report", body = serde_json::Value),
+        (status = 503, description = "Network unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn test_connectivity(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<serde_json::Value>, ApiError> {
     info!("🔍 Network connectivity test requested");
 
+    let network_state = state.network_manager.state();
+    if !network_state.is_available() {
+        warn!("⚠️  Skipping connectivity test, network unavailable");
+        return Err(ApiError::NetworkUnavailable {
+            state: network_state,
+        });
+    }
+
     let report = match state.connectivity_tester.run_comprehensive_test().await {
         Ok(report) => report,
         Err(e) => {
             error!("❌ Connectivity test failed: {}", e);
-            return Json(serde_json::json!({
+            return Ok(Json(serde_json::json!({
                 "success": false,
                 "error": format!("Connectivity test failed: {}", e)
-            }));
+            })));
         }
     };
 
+    let ping_json = |ping: &renclave_network::PingResult| {
+        serde_json::json!({
+            "success": ping.success,
+            "target": ping.target,
+            "packets_sent": ping.packets_sent,
+            "packets_received": ping.packets_received,
+            "packet_loss_percent": ping.packet_loss_percent,
+            "avg_time_ms": ping.avg_time_ms,
+            "min_time_ms": ping.min_time_ms,
+            "max_time_ms": ping.max_time_ms,
+            "jitter_ms": ping.jitter_ms,
+        })
+    };
+    let dns_json = |dns: &renclave_network::DnsResult| {
+        serde_json::json!({
+            "success": dns.success,
+            "hostname": dns.hostname,
+            "duration_ms": dns.duration.as_millis(),
+            "server": dns.server,
+            "queries": dns.queries.iter().map(|query| serde_json::json!({
+                "type": query.record_type.as_str(),
+                "server": query.server.ip(),
+                "duration_ms": query.duration.as_secs_f64() * 1000.0,
+                "answers": query.answers,
+                "error": query.error,
+            })).collect::<Vec<_>>(),
+        })
+    };
+
     let response = serde_json::json!({
         "success": true,
-        "gateway_ping": {
-            "success": report.gateway_ping.success,
-            "target": report.gateway_ping.target,
-            "packets_sent": report.gateway_ping.packets_sent,
-            "packets_received": report.gateway_ping.packets_received,
-            "avg_time_ms": report.gateway_ping.avg_time_ms,
-        },
-        "external_ping": {
-            "success": report.external_ping.success,
-            "target": report.external_ping.target,
-            "packets_sent": report.external_ping.packets_sent,
-            "packets_received": report.external_ping.packets_received,
-            "avg_time_ms": report.external_ping.avg_time_ms,
-        },
-        "dns_test": {
-            "success": report.dns_test.success,
-            "hostname": report.dns_test.hostname,
-            "duration_ms": report.dns_test.duration.as_millis(),
-        },
+        "gateway_ping": ping_json(&report.gateway_ping),
+        // The preferred target, kept for clients reading a single result
+        "external_ping": report.external_pings.first().map(ping_json),
+        "external_pings": report.external_pings.iter().map(ping_json).collect::<Vec<_>>(),
+        "dns_test": report.dns_tests.first().map(dns_json),
+        "dns_tests": report.dns_tests.iter().map(dns_json).collect::<Vec<_>>(),
         "http_test": {
             "success": report.http_test.success,
             "url": report.http_test.url,
@@ -286,13 +512,83 @@ pub async fn test_connectivity(State(state): State<AppState>) -> Json<serde_json
     });
 
     info!("✅ Network connectivity test completed");
-    Json(response)
+    Ok(Json(response))
+}
+
+/// Re-run network initialization on the host and in the enclave
+#[utoipa::path(
+    post,
+    path = "/network/repair",
+    tag = "network",
+    responses((status = 200, description = "Network state after repair", body = NetworkRepairResponse))
+)]
+pub async fn repair_network(State(state): State<AppState>) -> Json<NetworkRepairResponse> {
+    info!("🔧 Network repair requested");
+
+    let host = state.network_manager.repair().await;
+    state.network_status_cache.invalidate();
+
+    let (enclave, enclave_unavailable) = match state.enclave_client.repair_network().await {
+        Ok(response) => match response.result {
+            EnclaveResult::NetworkRepaired { state, unavailable } => {
+                if let Some(reason) = &unavailable {
+                    warn!("⚠️  Enclave cannot repair its network: {}", reason);
+                }
+                (Some(state), unavailable)
+            }
+            other => {
+                warn!("⚠️  Unexpected enclave repair response: {:?}", other);
+                (None, None)
+            }
+        },
+        Err(e) => {
+            warn!("⚠️  Enclave network repair failed: {}", e);
+            (None, None)
+        }
+    };
+
+    info!(
+        "✅ Network repair completed (host ready: {})",
+        host.is_available()
+    );
+    Json(NetworkRepairResponse {
+        host,
+        enclave,
+        enclave_unavailable,
+    })
+}
+
+/// List the enclaves served by this host
+#[utoipa::path(
+    get,
+    path = "/enclaves",
+    tag = "enclave",
+    responses((status = 200, description = "Enclaves and their sessions", body = EnclavesResponse))
+)]
+pub async fn list_enclaves(State(state): State<AppState>) -> Json<EnclavesResponse> {
+    debug!("🗂️  Enclave list requested");
+
+    let mut enclaves = Vec::with_capacity(state.enclaves.len());
+    for enclave in state.enclaves.iter() {
+        enclaves.push(enclave.status().await);
+    }
+    Json(EnclavesResponse { enclaves })
 }
 
 /// Get enclave information
+#[utoipa::path(
+    get,
+    path = "/enclave/info",
+    tag = "enclave",
+    responses(
+        (status = 200, description = "Enclave information", body = serde_json::Value),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
 pub async fn enclave_info(
     State(state): State<AppState>,
-) -> std::result::Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+) -> std::result::Result<Json<serde_json::Value>, ApiError> {
     debug!("🔒 Enclave info requested");
 
     // Check enclave health first
@@ -300,14 +596,10 @@ pub async fn enclave_info(
 
     if !is_healthy {
         warn!("⚠️  Enclave is not healthy");
-        return Err((
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "Enclave is not available".to_string(),
-                code: 503,
-                request_id: None,
-            }),
-        ));
+        return Err(ApiError::EnclaveUnavailable {
+            message: "Enclave is not available".to_string(),
+            request_id: None,
+        });
     }
 
     // Get enclave information
@@ -317,12 +609,30 @@ pub async fn enclave_info(
                 version,
                 enclave_id,
                 capabilities,
+                unavailable_capabilities,
+                protocol_version,
+                operations,
+                uptime_secs,
+                build,
+                tls_fingerprint,
+                time,
+                hardening,
             } => {
+                state.session.observe(&enclave_id);
                 let response = serde_json::json!({
                     "healthy": true,
                     "version": version,
                     "enclave_id": enclave_id,
                     "capabilities": capabilities,
+                    "unavailable_capabilities": unavailable_capabilities,
+                    "protocol_version": protocol_version,
+                    "operations": operations,
+                    "uptime_secs": uptime_secs,
+                    "build": build,
+                    "tls_fingerprint": tls_fingerprint,
+                    "time": time,
+                    "hardening": hardening,
+                    "session": state.session.current(),
                 });
 
                 debug!("✅ Enclave info response prepared");
@@ -330,391 +640,1786 @@ pub async fn enclave_info(
             }
             EnclaveResult::Error { message, code } => {
                 error!("❌ Enclave error: {}", message);
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: message,
-                        code,
-                        request_id: None,
-                    }),
-                ))
+                Err(ApiError::enclave_operation_failed(message, code, None))
             }
             _ => {
                 error!("❌ Unexpected response type from enclave");
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: "Unexpected response from enclave".to_string(),
-                        code: 500,
-                        request_id: None,
-                    }),
-                ))
+                Err(ApiError::unexpected_enclave_response(None))
             }
         },
         Err(e) => {
             error!("❌ Failed to communicate with enclave: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("Enclave communication failed: {}", e),
-                    code: 503,
-                    request_id: None,
-                }),
-            ))
+            Err(ApiError::enclave_unavailable(e, None))
         }
     }
 }
 
-/// Derive key from seed phrase
-pub async fn derive_key(
+/// Get signed enclave metrics snapshot
+#[utoipa::path(
+    get,
+    path = "/enclave/metrics/snapshot",
+    tag = "enclave",
+    responses(
+        (status = 200, description = "Signed metrics snapshot", body = SignedMetricsSnapshot),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn enclave_metrics_snapshot(
     State(state): State<AppState>,
-    Json(request): Json<DeriveKeyRequest>,
-) -> std::result::Result<Json<DeriveKeyResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let request_id = Uuid::new_v4().to_string();
-    info!("🔑 Key derivation requested (ID: {})", request_id);
+) -> std::result::Result<Json<SignedMetricsSnapshot>, ApiError> {
+    debug!("📊 Enclave metrics snapshot requested");
 
-    // Validate request
-    if request.seed_phrase.trim().is_empty() {
-        warn!("❌ Empty seed phrase provided");
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Seed phrase cannot be empty".to_string(),
-                code: 400,
-                request_id: Some(request_id),
-            }),
-        ));
+    match state.enclave_client.get_metrics_snapshot().await {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::MetricsSnapshot { snapshot } => {
+                debug!(
+                    "✅ Metrics snapshot {} received",
+                    snapshot.snapshot.sequence
+                );
+                Ok(Json(snapshot))
+            }
+            EnclaveResult::Error { message, code } => {
+                error!("❌ Enclave error during metrics snapshot: {}", message);
+                Err(ApiError::enclave_operation_failed(message, code, None))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(None))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, None))
+        }
     }
+}
 
-    if request.path.trim().is_empty() {
-        warn!("❌ Empty derivation path provided");
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Derivation path cannot be empty".to_string(),
-                code: 400,
-                request_id: Some(request_id),
-            }),
-        ));
+/// Get the enclave signing policy
+#[utoipa::path(
+    get,
+    path = "/enclave/policy",
+    tag = "enclave",
+    responses(
+        (status = 200, description = "Current signing policy", body = SigningPolicy),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn get_policy(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<SigningPolicy>, ApiError> {
+    debug!("📜 Signing policy requested");
+
+    policy_response(state.enclave_client.get_policy().await)
+}
+
+/// Signature counts and ages of the keys the enclave has signed with since it started
+#[utoipa::path(
+    get,
+    path = "/enclave/keys",
+    tag = "enclave",
+    responses(
+        (status = 200, description = "Usage of each signing key, ordered by public key", body = [KeyUsage]),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn key_usage(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<Vec<KeyUsage>>, ApiError> {
+    debug!("🔑 Key usage requested");
+
+    match state.enclave_client.get_key_usage().await {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::KeyUsageReport { keys } => Ok(Json(keys)),
+            EnclaveResult::Error { message, code } => {
+                error!("❌ Enclave error reading key usage: {}", message);
+                Err(ApiError::enclave_operation_failed(message, code, None))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(None))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, None))
+        }
     }
+}
 
-    if request.curve.trim().is_empty() {
-        warn!("❌ Empty curve provided");
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Curve cannot be empty".to_string(),
-                code: 400,
-                request_id: Some(request_id),
-            }),
-        ));
+/// Replace the enclave signing policy
+#[utoipa::path(
+    post,
+    path = "/enclave/policy",
+    tag = "enclave",
+    request_body = SigningPolicy,
+    responses(
+        (status = 200, description = "Updated signing policy", body = SigningPolicy),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Policy rejected or locked", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn set_policy(
+    State(state): State<AppState>,
+    ApiJson(policy): ApiJson<SigningPolicy>,
+) -> std::result::Result<Json<SigningPolicy>, ApiError> {
+    info!("📜 Signing policy update requested");
+
+    policy_response(state.enclave_client.set_policy(policy).await)
+}
+
+fn policy_response(
+    response: anyhow::Result<EnclaveResponse>,
+) -> std::result::Result<Json<SigningPolicy>, ApiError> {
+    match response {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::Policy { policy } => Ok(Json(policy)),
+            EnclaveResult::Error { message, code } => {
+                error!("❌ Enclave error during policy request: {}", message);
+                Err(ApiError::enclave_operation_failed(message, code, None))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(None))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, None))
+        }
     }
+}
 
-    debug!(
-        "📋 Request validated - path: {}, curve: {}",
-        request.path, request.curve
-    );
+/// Open an end-to-end encrypted channel to the enclave
+#[utoipa::path(
+    post,
+    path = "/enclave/channel",
+    tag = "enclave",
+    request_body = OpenChannelRequest,
+    responses(
+        (status = 200, description = "Enclave handshake key and attestation", body = OpenChannelResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn open_channel(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<OpenChannelRequest>,
+) -> std::result::Result<Json<OpenChannelResponse>, ApiError> {
+    info!("🔐 Encrypted channel requested");
+
+    // Validate request
+    if let Err(errors) = request.validate() {
+        warn!("❌ Invalid request: {}", errors);
+        return Err(ApiError::invalid_fields(errors, None));
+    }
 
-    // Send request to enclave
     match state
         .enclave_client
-        .derive_key(request.seed_phrase, request.path, request.curve)
+        .open_channel(request.client_public_key, request.nonce)
         .await
     {
         Ok(enclave_response) => match enclave_response.result {
-            EnclaveResult::KeyDerived {
-                private_key,
-                public_key,
-                address,
-                path,
-                curve,
+            EnclaveResult::ChannelOpened {
+                session_id,
+                enclave_public_key,
+                expires_at,
+                attestation,
             } => {
-                info!("✅ Key derivation successful (ID: {})", request_id);
-                Ok(Json(DeriveKeyResponse {
-                    private_key,
-                    public_key,
-                    address,
-                    path,
-                    curve,
+                info!("✅ Encrypted channel {} opened", session_id);
+                Ok(Json(OpenChannelResponse {
+                    session_id,
+                    enclave_public_key,
+                    expires_at,
+                    attestation,
                 }))
             }
             EnclaveResult::Error { message, code } => {
-                error!("❌ Enclave error during key derivation: {}", message);
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: message,
-                        code,
-                        request_id: Some(request_id),
-                    }),
-                ))
+                error!("❌ Enclave error during channel handshake: {}", message);
+                Err(ApiError::enclave_operation_failed(message, code, None))
             }
             _ => {
                 error!("❌ Unexpected response type from enclave");
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: "Unexpected response from enclave".to_string(),
-                        code: 500,
-                        request_id: Some(request_id),
-                    }),
-                ))
+                Err(ApiError::unexpected_enclave_response(None))
             }
         },
         Err(e) => {
             error!("❌ Failed to communicate with enclave: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("Enclave communication failed: {}", e),
-                    code: 503,
-                    request_id: Some(request_id),
-                }),
-            ))
+            Err(ApiError::enclave_unavailable(e, None))
         }
     }
 }
 
-/// Derive address from seed phrase
-pub async fn derive_address(
+/// Drive a FROST threshold signing session coordinated by the enclave
+#[utoipa::path(
+    post,
+    path = "/enclave/threshold",
+    tag = "enclave",
+    request_body = ThresholdMessage,
+    responses(
+        (status = 200, description = "Session state after the message", body = ThresholdSessionStatus),
+        (status = 400, description = "Invalid message or signature share", body = ErrorResponse),
+        (status = 500, description = "Unknown session or threshold signing not enabled", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn threshold_signing(
     State(state): State<AppState>,
-    Json(request): Json<DeriveAddressRequest>,
-) -> std::result::Result<Json<DeriveAddressResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let request_id = Uuid::new_v4().to_string();
-    info!("📍 Address derivation requested (ID: {})", request_id);
+    ApiJson(message): ApiJson<ThresholdMessage>,
+) -> std::result::Result<Json<ThresholdSessionStatus>, ApiError> {
+    debug!("✍️  Threshold signing message");
 
     // Validate request
-    if request.seed_phrase.trim().is_empty() {
-        warn!("❌ Empty seed phrase provided");
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Seed phrase cannot be empty".to_string(),
-                code: 400,
-                request_id: Some(request_id),
-            }),
-        ));
+    if let Err(errors) = message.validate() {
+        warn!("❌ Invalid request: {}", errors);
+        return Err(ApiError::invalid_fields(errors, None));
     }
 
-    if request.path.trim().is_empty() {
-        warn!("❌ Empty derivation path provided");
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Derivation path cannot be empty".to_string(),
-                code: 400,
-                request_id: Some(request_id),
-            }),
-        ));
+    if let ThresholdMessage::Start {
+        threshold,
+        verifying_shares,
+        ..
+    } = &message
+    {
+        if let Err(e) = state
+            .threshold_policy
+            .check(*threshold, verifying_shares.len())
+        {
+            warn!("❌ Threshold signing session refused: {}", e);
+            return Err(ApiError::invalid_fields(
+                FieldError::new("threshold", e).into(),
+                None,
+            ));
+        }
     }
 
-    if request.curve.trim().is_empty() {
-        warn!("❌ Empty curve provided");
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Curve cannot be empty".to_string(),
-                code: 400,
-                request_id: Some(request_id),
-            }),
-        ));
+    match state.enclave_client.threshold_signing(message).await {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::ThresholdSession { session } => Ok(Json(session)),
+            EnclaveResult::Error { message, code } => {
+                warn!(
+                    "⚠️  Enclave rejected threshold signing message: {}",
+                    message
+                );
+                Err(ApiError::enclave_operation_failed(message, code, None))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(None))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, None))
+        }
     }
+}
 
-    debug!(
-        "📋 Request validated - path: {}, curve: {}",
-        request.path, request.curve
-    );
+/// Forward an encrypted request to the enclave; the host only sees ciphertext
+#[utoipa::path(
+    post,
+    path = "/enclave/channel/sealed",
+    tag = "enclave",
+    request_body = SealedChannelMessage,
+    responses(
+        (status = 200, description = "Encrypted enclave result", body = SealedChannelMessage),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Unknown session, replay or failed authentication", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn sealed_request(
+    State(state): State<AppState>,
+    ApiJson(message): ApiJson<SealedChannelMessage>,
+) -> std::result::Result<Json<SealedChannelMessage>, ApiError> {
+    debug!("🔐 Sealed request on channel {}", message.session_id);
 
-    // Send request to enclave
     match state
         .enclave_client
-        .derive_address(request.seed_phrase, request.path, request.curve)
+        .sealed_request(message.session_id, message.payload)
         .await
     {
         Ok(enclave_response) => match enclave_response.result {
-            EnclaveResult::AddressDerived {
-                address,
-                path,
-                curve,
-            } => {
-                info!("✅ Address derivation successful (ID: {})", request_id);
-                Ok(Json(DeriveAddressResponse {
-                    address,
-                    path,
-                    curve,
-                }))
-            }
+            EnclaveResult::SealedResponse {
+                session_id,
+                payload,
+            } => Ok(Json(SealedChannelMessage {
+                session_id,
+                payload,
+            })),
             EnclaveResult::Error { message, code } => {
-                error!("❌ Enclave error during address derivation: {}", message);
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: message,
-                        code,
-                        request_id: Some(request_id),
-                    }),
-                ))
+                warn!("⚠️  Enclave rejected sealed request: {}", message);
+                Err(ApiError::enclave_operation_failed(message, code, None))
             }
             _ => {
                 error!("❌ Unexpected response type from enclave");
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: "Unexpected response from enclave".to_string(),
-                        code: 500,
-                        request_id: Some(request_id),
-                    }),
-                ))
+                Err(ApiError::unexpected_enclave_response(None))
             }
         },
         Err(e) => {
             error!("❌ Failed to communicate with enclave: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("Enclave communication failed: {}", e),
-                    code: 503,
-                    request_id: Some(request_id),
-                }),
-            ))
+            Err(ApiError::enclave_unavailable(e, None))
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use axum::http::StatusCode;
-    use axum::Json;
-    use renclave_shared::*;
-    use std::sync::Arc;
-
-    // Mock implementations for testing
-    #[derive(Clone)]
-    struct MockEnclaveClient;
-
-    #[derive(Clone)]
-    struct MockNetworkManager;
-
-    #[derive(Clone)]
-    struct MockConnectivityTester;
-
-    impl MockEnclaveClient {
-        async fn generate_seed(
-            &self,
-            _strength: u32,
-            _passphrase: Option<String>,
-        ) -> Result<EnclaveResponse> {
-            Ok(EnclaveResponse::new(
-                "mock-id".to_string(),
-                EnclaveResult::SeedGenerated {
-                    seed_phrase: "test seed phrase".to_string(),
-                    entropy: "test entropy".to_string(),
-                    strength: 256,
-                    word_count: 24,
-                },
-            ))
-        }
+/// Verify a Nitro attestation document for a third party, without involving the enclave
+#[utoipa::path(
+    post,
+    path = "/attestation/verify",
+    tag = "verification",
+    request_body = VerifyAttestationRequest,
+    responses(
+        (status = 200, description = "Result of each check and the attested values", body = AttestationReport),
+        (status = 400, description = "Undecodable document or invalid expected values", body = ErrorResponse)
+    )
+)]
+pub async fn verify_attestation(
+    ApiJson(request): ApiJson<VerifyAttestationRequest>,
+) -> std::result::Result<Json<AttestationReport>, ApiError> {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let report = crate::attestation::verify(
+        &request,
+        &crate::attestation::trusted_root_fingerprint(),
+        now_ms,
+    )
+    .map_err(|e| ApiError::invalid_request(format!("{:#}", e), None))?;
+    debug!(
+        "🔍 Attestation document from {} verified: {}",
+        report.module_id,
+        if report.valid { "valid" } else { "invalid" }
+    );
+    Ok(Json(report))
+}
 
-        async fn validate_seed(&self, _phrase: String) -> Result<EnclaveResponse> {
-            Ok(EnclaveResponse::new(
-                "mock-id".to_string(),
-                EnclaveResult::SeedValidated {
-                    valid: true,
-                    word_count: 24,
-                },
-            ))
-        }
+/// Verify a signature against a public key
+#[utoipa::path(
+    post,
+    path = "/verify-signature",
+    tag = "verification",
+    request_body = VerifySignatureRequest,
+    responses(
+        (status = 200, description = "Whether the signature is valid", body = VerifySignatureResponse),
+        (status = 400, description = "Invalid request or unsupported curve", body = ErrorResponse),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn verify_signature(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<VerifySignatureRequest>,
+) -> std::result::Result<Json<VerifySignatureResponse>, ApiError> {
+    debug!("🔍 Signature verification requested ({})", request.curve);
 
-        async fn get_info(&self) -> Result<EnclaveResponse> {
-            Ok(EnclaveResponse::new(
-                "mock-id".to_string(),
-                EnclaveResult::Info {
-                    version: "1.0.0".to_string(),
-                    enclave_id: "test-enclave".to_string(),
-                    capabilities: vec!["test".to_string()],
-                },
-            ))
-        }
+    // Validate request
+    if let Err(errors) = request.validate() {
+        warn!("❌ Invalid request: {}", errors);
+        return Err(ApiError::invalid_fields(errors, None));
     }
 
-    impl MockNetworkManager {
-        async fn get_status(&self) -> NetworkStatus {
-            NetworkStatus {
-                connectivity: ConnectivityStatus {
-                    external: true,
-                    gateway: true,
-                },
-                interfaces: vec!["eth0".to_string(), "tap0".to_string()],
-                qemu_detected: true,
+    match state
+        .enclave_client
+        .verify_signature(
+            request.curve,
+            request.public_key,
+            request.message,
+            request.signature,
+        )
+        .await
+    {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::SignatureVerified { valid } => {
+                debug!("✅ Signature verified: {}", valid);
+                Ok(Json(VerifySignatureResponse { valid }))
             }
+            EnclaveResult::Error { message, code } => {
+                warn!("⚠️  Enclave could not verify signature: {}", message);
+                Err(ApiError::enclave_operation_failed(message, code, None))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(None))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, None))
         }
     }
+}
+
+/// Check that a sealed request authenticates on its channel; the payload is not decrypted
+/// and its counter stays unused
+#[utoipa::path(
+    post,
+    path = "/verify-ciphertext",
+    tag = "verification",
+    request_body = SealedChannelMessage,
+    responses(
+        (status = 200, description = "Whether the enclave would accept the payload", body = VerifyCiphertextResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Unknown or expired session", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn verify_ciphertext(
+    State(state): State<AppState>,
+    ApiJson(message): ApiJson<SealedChannelMessage>,
+) -> std::result::Result<Json<VerifyCiphertextResponse>, ApiError> {
+    debug!(
+        "🔍 Ciphertext verification requested on channel {}",
+        message.session_id
+    );
+
+    match state
+        .enclave_client
+        .verify_ciphertext(message.session_id, message.payload)
+        .await
+    {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::CiphertextVerified { valid, reason } => {
+                Ok(Json(VerifyCiphertextResponse { valid, reason }))
+            }
+            EnclaveResult::Error { message, code } => {
+                warn!("⚠️  Enclave could not verify ciphertext: {}", message);
+                Err(ApiError::enclave_operation_failed(message, code, None))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(None))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, None))
+        }
+    }
+}
+
+/// Derive key from seed phrase
+#[utoipa::path(
+    post,
+    path = "/derive-key",
+    tag = "derivation",
+    request_body = DeriveKeyRequest,
+    responses(
+        (status = 200, description = "Key derivation result", body = DeriveKeyResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn derive_key(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<DeriveKeyRequest>,
+) -> std::result::Result<Json<DeriveKeyResponse>, ApiError> {
+    let request_id = Uuid::new_v4().to_string();
+    info!("🔑 Key derivation requested (ID: {})", request_id);
+
+    // Validate request
+    if let Err(errors) = request.validate() {
+        warn!("❌ Invalid request: {}", errors);
+        return Err(ApiError::invalid_fields(errors, Some(request_id)));
+    }
+
+    debug!(
+        "📋 Request validated - path: {}, curve: {}",
+        request.path, request.curve
+    );
+
+    // Send request to enclave
+    match state
+        .enclave_client
+        .derive_key(
+            request.seed_phrase,
+            request.path,
+            request.curve,
+            request.attest,
+            request.fresh_attestation,
+        )
+        .await
+    {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::KeyDerived {
+                private_key,
+                public_key,
+                address,
+                path,
+                curve,
+                attestation,
+            } => {
+                info!("✅ Key derivation successful (ID: {})", request_id);
+                Ok(Json(DeriveKeyResponse {
+                    private_key,
+                    public_key,
+                    address,
+                    path,
+                    curve,
+                    attestation,
+                }))
+            }
+            EnclaveResult::Error { message, code } => {
+                error!("❌ Enclave error during key derivation: {}", message);
+                Err(ApiError::enclave_operation_failed(
+                    message,
+                    code,
+                    Some(request_id),
+                ))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(Some(request_id)))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, Some(request_id)))
+        }
+    }
+}
+
+/// Derive address from seed phrase
+#[utoipa::path(
+    post,
+    path = "/derive-address",
+    tag = "derivation",
+    request_body = DeriveAddressRequest,
+    responses(
+        (status = 200, description = "Address derivation result", body = DeriveAddressResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn derive_address(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<DeriveAddressRequest>,
+) -> std::result::Result<Json<DeriveAddressResponse>, ApiError> {
+    let request_id = Uuid::new_v4().to_string();
+    info!("📍 Address derivation requested (ID: {})", request_id);
+
+    // Validate request
+    if let Err(errors) = request.validate() {
+        warn!("❌ Invalid request: {}", errors);
+        return Err(ApiError::invalid_fields(errors, Some(request_id)));
+    }
+
+    debug!(
+        "📋 Request validated - path: {}, curve: {}",
+        request.path, request.curve
+    );
+
+    // Send request to enclave
+    match state
+        .enclave_client
+        .derive_address(
+            request.seed_phrase,
+            request.path,
+            request.curve,
+            request.chain,
+        )
+        .await
+    {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::AddressDerived {
+                address,
+                path,
+                curve,
+                chain,
+            } => {
+                info!("✅ Address derivation successful (ID: {})", request_id);
+                Ok(Json(DeriveAddressResponse {
+                    address,
+                    path,
+                    curve,
+                    chain,
+                }))
+            }
+            EnclaveResult::Error { message, code } => {
+                error!("❌ Enclave error during address derivation: {}", message);
+                Err(ApiError::enclave_operation_failed(
+                    message,
+                    code,
+                    Some(request_id),
+                ))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(Some(request_id)))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, Some(request_id)))
+        }
+    }
+}
+
+/// Derive extended public key from seed phrase
+#[utoipa::path(
+    post,
+    path = "/derive-xpub",
+    tag = "derivation",
+    request_body = DeriveXpubRequest,
+    responses(
+        (status = 200, description = "Extended public key", body = DeriveXpubResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn derive_xpub(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<DeriveXpubRequest>,
+) -> std::result::Result<Json<DeriveXpubResponse>, ApiError> {
+    let request_id = Uuid::new_v4().to_string();
+    info!("🧾 Extended public key requested (ID: {})", request_id);
+
+    // Validate request
+    if let Err(errors) = request.validate() {
+        warn!("❌ Invalid request: {}", errors);
+        return Err(ApiError::invalid_fields(errors, Some(request_id)));
+    }
+
+    debug!(
+        "📋 Request validated - path: {}, format: {:?}",
+        request.path, request.format
+    );
+
+    // Send request to enclave
+    match state
+        .enclave_client
+        .derive_xpub(request.seed_phrase, request.path, request.format)
+        .await
+    {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::XpubDerived {
+                xpub,
+                path,
+                format,
+                master_fingerprint,
+            } => {
+                info!("✅ Extended public key derived (ID: {})", request_id);
+                Ok(Json(DeriveXpubResponse {
+                    xpub,
+                    path,
+                    format,
+                    master_fingerprint,
+                }))
+            }
+            EnclaveResult::Error { message, code } => {
+                error!("❌ Enclave error during xpub derivation: {}", message);
+                Err(ApiError::enclave_operation_failed(
+                    message,
+                    code,
+                    Some(request_id),
+                ))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(Some(request_id)))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, Some(request_id)))
+        }
+    }
+}
+
+/// Sign a PSBT with keys derived from the seed phrase
+#[utoipa::path(
+    post,
+    path = "/enclave/sign-psbt",
+    tag = "enclave",
+    request_body = SignPsbtRequest,
+    responses(
+        (status = 200, description = "Signed PSBT", body = SignPsbtResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Enclave operation failed", body = ErrorResponse),
+        (status = 503, description = "Enclave unavailable", body = ErrorResponse)
+    )
+)]
+pub async fn sign_psbt(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<SignPsbtRequest>,
+) -> std::result::Result<Json<SignPsbtResponse>, ApiError> {
+    let request_id = Uuid::new_v4().to_string();
+    info!("✍️  PSBT signing requested (ID: {})", request_id);
+
+    // Validate request
+    if let Err(errors) = request.validate() {
+        warn!("❌ Invalid request: {}", errors);
+        return Err(ApiError::invalid_fields(errors, Some(request_id)));
+    }
+
+    // Send request to enclave
+    match state
+        .enclave_client
+        .sign_psbt(request.seed_phrase, request.psbt)
+        .await
+    {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::PsbtSigned {
+                psbt,
+                signed_inputs,
+                retiring_keys,
+            } => {
+                info!(
+                    "✅ PSBT signed, {} inputs (ID: {})",
+                    signed_inputs.len(),
+                    request_id
+                );
+                for key in &retiring_keys {
+                    warn!(
+                        "⚠️  Key {} is due for retirement after {} signatures (ID: {})",
+                        key.public_key, key.signatures, request_id
+                    );
+                    state.webhooks.notify(WebhookEvent::KeyRetirementDue {
+                        request_id: request_id.clone(),
+                        public_key: key.public_key.clone(),
+                        signatures: key.signatures,
+                        first_used_at: key.first_used_at,
+                    });
+                }
+                Ok(Json(SignPsbtResponse {
+                    psbt,
+                    signed_inputs,
+                    retiring_keys,
+                }))
+            }
+            EnclaveResult::Error { message, code } => {
+                error!("❌ Enclave error during PSBT signing: {}", message);
+                if code == RenclaveErrorCode::PolicyDenied {
+                    state.webhooks.notify(WebhookEvent::PolicyViolation {
+                        request_id: request_id.clone(),
+                        message: message.clone(),
+                    });
+                }
+                Err(ApiError::enclave_operation_failed(
+                    message,
+                    code,
+                    Some(request_id),
+                ))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(Some(request_id)))
+            }
+        },
+        Err(e) => {
+            error!("❌ Failed to communicate with enclave: {}", e);
+            Err(ApiError::enclave_unavailable(e, Some(request_id)))
+        }
+    }
+}
+
+/// List API identities
+#[utoipa::path(
+    get,
+    path = "/admin/identities",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Identities ordered by name", body = [Identity]),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing identities:manage scope", body = ErrorResponse)
+    )
+)]
+pub async fn list_identities(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<Vec<Identity>>, ApiError> {
+    debug!("🔐 API identities requested");
+
+    state
+        .identities
+        .list()
+        .map(Json)
+        .map_err(ApiError::storage_failed)
+}
+
+/// Create an API identity; its key is only returned in this response
+#[utoipa::path(
+    post,
+    path = "/admin/identities",
+    tag = "admin",
+    request_body = CreateIdentityRequest,
+    responses(
+        (status = 201, description = "Identity and its API key", body = CreateIdentityResponse),
+        (status = 400, description = "Invalid or duplicate name", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing identities:manage scope", body = ErrorResponse)
+    )
+)]
+pub async fn create_identity(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<CreateIdentityRequest>,
+) -> std::result::Result<(StatusCode, Json<CreateIdentityResponse>), ApiError> {
+    let created = state.identities.create(&request.name, request.scopes)?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// Replace the scopes of an API identity
+#[utoipa::path(
+    put,
+    path = "/admin/identities/{name}",
+    tag = "admin",
+    params(("name" = String, Path, description = "Identity name")),
+    request_body = UpdateIdentityRequest,
+    responses(
+        (status = 200, description = "Updated identity", body = Identity),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing identities:manage scope", body = ErrorResponse),
+        (status = 404, description = "Unknown identity", body = ErrorResponse)
+    )
+)]
+pub async fn update_identity(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(request): ApiJson<UpdateIdentityRequest>,
+) -> std::result::Result<Json<Identity>, ApiError> {
+    Ok(Json(state.identities.update(&name, request.scopes)?))
+}
+
+/// Delete an API identity
+#[utoipa::path(
+    delete,
+    path = "/admin/identities/{name}",
+    tag = "admin",
+    params(("name" = String, Path, description = "Identity name")),
+    responses(
+        (status = 204, description = "Identity deleted"),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing identities:manage scope", body = ErrorResponse),
+        (status = 404, description = "Unknown identity", body = ErrorResponse)
+    )
+)]
+pub async fn delete_identity(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> std::result::Result<StatusCode, ApiError> {
+    state.identities.delete(&name)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get the host and enclave log levels
+#[utoipa::path(
+    get,
+    path = "/admin/log-level",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Current log levels", body = LogLevelResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse)
+    )
+)]
+pub async fn get_log_level(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<LogLevelResponse>, ApiError> {
+    Ok(Json(LogLevelResponse {
+        host: renclave_shared::redact::log_level(),
+        enclave: enclave_log_level(state.enclave_client.set_log_level(None).await)?,
+    }))
+}
+
+/// Change the host and enclave log levels without a restart
+#[utoipa::path(
+    put,
+    path = "/admin/log-level",
+    tag = "admin",
+    request_body = SetLogLevelRequest,
+    responses(
+        (status = 200, description = "Updated log levels", body = LogLevelResponse),
+        (status = 400, description = "Unknown log level", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse)
+    )
+)]
+pub async fn set_log_level(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<SetLogLevelRequest>,
+) -> std::result::Result<Json<LogLevelResponse>, ApiError> {
+    let level = renclave_shared::redact::set_log_level(&request.level)
+        .map_err(|e| ApiError::invalid_request(e, None))?;
+    info!("📝 Host log level set to {}", level);
+
+    let response = state
+        .enclave_client
+        .set_log_level(Some(request.level))
+        .await;
+    Ok(Json(LogLevelResponse {
+        host: renclave_shared::redact::log_level(),
+        enclave: enclave_log_level(response)?,
+    }))
+}
+
+/// The enclave's level, or `None` if the enclave could not be reached
+fn enclave_log_level(
+    response: anyhow::Result<EnclaveResponse>,
+) -> std::result::Result<Option<String>, ApiError> {
+    match response {
+        Ok(enclave_response) => match enclave_response.result {
+            EnclaveResult::LogLevel { level } => Ok(Some(level)),
+            EnclaveResult::Error { message, code } => {
+                error!("❌ Enclave error during log level request: {}", message);
+                Err(ApiError::enclave_operation_failed(message, code, None))
+            }
+            _ => {
+                error!("❌ Unexpected response type from enclave");
+                Err(ApiError::unexpected_enclave_response(None))
+            }
+        },
+        Err(e) => {
+            warn!("⚠️  Enclave log level unavailable: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// Get the host settings that can be changed at runtime
+#[utoipa::path(
+    get,
+    path = "/admin/config",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Current runtime config", body = RuntimeConfig),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse)
+    )
+)]
+pub async fn get_runtime_config(State(state): State<AppState>) -> Json<RuntimeConfig> {
+    Json(state.settings.config())
+}
+
+/// Change enclave timeouts and retries without a restart; applies to the next request
+#[utoipa::path(
+    put,
+    path = "/admin/config",
+    tag = "admin",
+    request_body = UpdateRuntimeConfigRequest,
+    responses(
+        (status = 200, description = "Updated runtime config", body = RuntimeConfig),
+        (status = 400, description = "Setting out of range", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse)
+    )
+)]
+pub async fn update_runtime_config(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<UpdateRuntimeConfigRequest>,
+) -> std::result::Result<Json<RuntimeConfig>, ApiError> {
+    state
+        .settings
+        .update(&request)
+        .map(Json)
+        .map_err(|e| ApiError::invalid_request(e, None))
+}
+
+/// State of the enclave process when the host supervises it
+#[utoipa::path(
+    get,
+    path = "/admin/enclave/process",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Enclave process status", body = EnclaveProcessStatus),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse),
+        (status = 404, description = "The host does not supervise the enclave", body = ErrorResponse)
+    )
+)]
+pub async fn enclave_process(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<EnclaveProcessStatus>, ApiError> {
+    supervisor(&state).map(|supervisor| Json(supervisor.status()))
+}
+
+/// Start the supervised enclave process after it was stopped or given up on
+#[utoipa::path(
+    post,
+    path = "/admin/enclave/process/start",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Enclave process status", body = EnclaveProcessStatus),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse),
+        (status = 404, description = "The host does not supervise the enclave", body = ErrorResponse)
+    )
+)]
+pub async fn start_enclave_process(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<EnclaveProcessStatus>, ApiError> {
+    let supervisor = supervisor(&state)?;
+    supervisor.start();
+    Ok(Json(supervisor.status()))
+}
+
+/// Stop the supervised enclave process; it is not restarted until started again
+#[utoipa::path(
+    post,
+    path = "/admin/enclave/process/stop",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Enclave process status", body = EnclaveProcessStatus),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse),
+        (status = 404, description = "The host does not supervise the enclave", body = ErrorResponse)
+    )
+)]
+pub async fn stop_enclave_process(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<EnclaveProcessStatus>, ApiError> {
+    let supervisor = supervisor(&state)?;
+    supervisor.stop();
+    Ok(Json(supervisor.status()))
+}
+
+/// Recent console output of the supervised enclave process or QEMU guest
+#[utoipa::path(
+    get,
+    path = "/admin/enclave/console",
+    tag = "admin",
+    params(ConsoleQuery),
+    responses(
+        (status = 200, description = "Recent console lines", body = ConsoleResponse),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse),
+        (status = 404, description = "The host does not supervise the enclave", body = ErrorResponse)
+    )
+)]
+pub async fn enclave_console(
+    State(state): State<AppState>,
+    Query(query): Query<ConsoleQuery>,
+) -> std::result::Result<Json<ConsoleResponse>, ApiError> {
+    let lines = supervisor(&state)?.console(query.lines.unwrap_or(100));
+    Ok(Json(ConsoleResponse { lines }))
+}
+
+/// Key authorization for a pending ACME HTTP-01 challenge, fetched by the CA
+#[utoipa::path(
+    get,
+    path = "/.well-known/acme-challenge/{token}",
+    tag = "service",
+    params(("token" = String, Path, description = "Challenge token")),
+    responses(
+        (status = 200, description = "Key authorization", body = String, content_type = "text/plain"),
+        (status = 404, description = "No such challenge is pending", body = ErrorResponse)
+    )
+)]
+pub async fn acme_challenge(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> std::result::Result<String, ApiError> {
+    state
+        .acme
+        .as_ref()
+        .and_then(|acme| acme.challenge_response(&token))
+        .ok_or_else(|| ApiError::not_found("No such ACME challenge is pending"))
+}
+
+/// ACME issuance state of the enclave's TLS certificate
+#[utoipa::path(
+    get,
+    path = "/admin/acme",
+    tag = "admin",
+    responses(
+        (status = 200, description = "ACME status", body = AcmeStatus),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse),
+        (status = 404, description = "ACME is not configured", body = ErrorResponse)
+    )
+)]
+pub async fn acme_status(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<AcmeStatus>, ApiError> {
+    acme(&state).map(|acme| Json(acme.status()))
+}
+
+/// Issue a new certificate for the enclave now and install it
+#[utoipa::path(
+    post,
+    path = "/admin/acme/renew",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Installed certificate", body = AcmeCertificate),
+        (status = 401, description = "Missing or unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing config:manage scope", body = ErrorResponse),
+        (status = 404, description = "ACME is not configured", body = ErrorResponse),
+        (status = 503, description = "Issuance failed", body = ErrorResponse)
+    )
+)]
+pub async fn renew_acme_certificate(
+    State(state): State<AppState>,
+) -> std::result::Result<Json<AcmeCertificate>, ApiError> {
+    acme(&state)?.renew().await.map(Json).map_err(|e| {
+        ApiError::enclave_operation_failed(
+            format!("Certificate issuance failed: {:#}", e),
+            RenclaveErrorCode::Unavailable,
+            None,
+        )
+    })
+}
+
+fn acme(state: &AppState) -> std::result::Result<&crate::acme::AcmeManager, ApiError> {
+    state.acme.as_deref().ok_or_else(|| {
+        ApiError::not_found(format!(
+            "ACME is not configured; set {}",
+            crate::acme::ACME_DOMAINS_ENV
+        ))
+    })
+}
+
+fn supervisor(state: &AppState) -> std::result::Result<&crate::supervisor::Supervisor, ApiError> {
+    state.supervisor.as_deref().ok_or_else(|| {
+        ApiError::not_found(format!(
+            "The enclave process is not supervised; set {} or {}",
+            crate::supervisor::ENCLAVE_COMMAND_ENV,
+            crate::qemu_manager::QEMU_KERNEL_ENV
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::IdentityStore;
+    use crate::cache::TtlCache;
+    use crate::mock_enclave::{MockEnclave, MOCK_ADDRESS};
+    use crate::runtime_config::RuntimeSettings;
+    use crate::session::EnclaveSession;
+    use crate::storage::MemoryStorage;
+    use crate::webhook::WebhookNotifier;
+    use axum::http::StatusCode;
+    use renclave_network::{ConnectivityTester, NetworkConfig, NetworkManager};
+    use std::sync::Arc;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn state(enclave: Arc<MockEnclave>) -> AppState {
+        AppState {
+            enclave_client: enclave,
+            network_manager: Arc::new(NetworkManager::new(NetworkConfig::default())),
+            connectivity_tester: Arc::new(ConnectivityTester::default()),
+            session: Arc::new(EnclaveSession::new()),
+            webhooks: Arc::new(WebhookNotifier::disabled()),
+            storage: Arc::new(MemoryStorage::default()),
+            identities: Arc::new(IdentityStore::new(Arc::new(MemoryStorage::default()), None)),
+            settings: Arc::new(RuntimeSettings::default()),
+            network_status_cache: Arc::new(TtlCache::new(Duration::ZERO)),
+            enclaves: Arc::new(Vec::new()),
+            supervisor: None,
+            acme: None,
+            threshold_policy: ThresholdPolicy::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_attestation_rejects_undecodable_document() {
+        let request = VerifyAttestationRequest {
+            document: "bm90IGNib3I=".to_string(),
+            expected_manifest_hash: None,
+            expected_nonce: None,
+            expected_pcrs: Default::default(),
+            max_age_secs: None,
+        };
+        let error = verify_attestation(ApiJson(request)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_acme_routes_without_acme() {
+        let state = state(Arc::new(MockEnclave::new()));
+
+        let error = acme_challenge(State(state.clone()), Path("token".to_string()))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = acme_status(State(state.clone())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = renew_acme_certificate(State(state)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+    }
 
-    impl MockConnectivityTester {
-        async fn test_http_connectivity(&self) -> Result<HttpConnectivityResult> {
-            Ok(HttpConnectivityResult {
-                success: true,
-                url: "http://test.com".to_string(),
-                response: "test response".to_string(),
-                duration: std::time::Duration::from_millis(100),
-            })
-        }
-    }
-
-    // For testing, we need to create a different approach since the mock types
-    // don't implement the same traits as the real types
-    // We'll skip the mock AppState creation for now and test individual functions
-
     #[tokio::test]
     async fn test_health_check() {
         let status = health_check().await;
         assert_eq!(status, StatusCode::OK);
     }
 
-    // Note: These tests require proper mock implementations that implement the right traits
-    // For now, we'll skip them to get the basic compilation working
-    /*
     #[tokio::test]
-    async fn test_get_info_success() {
-        // Test implementation would go here
+    async fn test_get_info_observes_session() {
+        let enclave = Arc::new(MockEnclave::with_enclave_id("enclave-a"));
+        let state = state(Arc::clone(&enclave));
+
+        let info = get_info(State(state.clone())).await.unwrap();
+        assert_eq!(info.enclave_id, "enclave-a");
+        assert_eq!(state.session.current().unwrap().enclave_id, "enclave-a");
+
+        enclave.set_available(false);
+        let info = get_info(State(state)).await.unwrap();
+        assert_eq!(info.enclave_id, "unavailable");
     }
 
     #[tokio::test]
     async fn test_generate_seed_success() {
-        // Test implementation would go here
+        let enclave = Arc::new(MockEnclave::new());
+        let request = GenerateSeedRequest {
+            strength: Some(128),
+            passphrase: None,
+            language: SeedLanguage::English,
+            attest: false,
+            fresh_attestation: false,
+        };
+
+        let response = generate_seed(State(state(Arc::clone(&enclave))), ApiJson(request))
+            .await
+            .unwrap();
+        assert_eq!(response.seed_phrase.expose_secret(), PHRASE);
+        assert_eq!(response.word_count, 12);
+        assert_eq!(enclave.operations(), vec!["generate_seed"]);
     }
 
     #[tokio::test]
     async fn test_generate_seed_default_strength() {
-        // Test implementation would go here
+        let request = GenerateSeedRequest {
+            strength: None,
+            passphrase: None,
+            language: SeedLanguage::English,
+            attest: false,
+            fresh_attestation: false,
+        };
+
+        let response = generate_seed(State(state(Arc::new(MockEnclave::new()))), ApiJson(request))
+            .await
+            .unwrap();
+        assert_eq!(response.strength, 256);
+        assert_eq!(response.word_count, 24);
+    }
+
+    #[tokio::test]
+    async fn test_generate_seed_attestation_commits_to_response() {
+        let request = GenerateSeedRequest {
+            strength: Some(128),
+            passphrase: None,
+            language: SeedLanguage::English,
+            attest: true,
+            fresh_attestation: false,
+        };
+
+        let Json(mut response) =
+            generate_seed(State(state(Arc::new(MockEnclave::new()))), ApiJson(request))
+                .await
+                .unwrap();
+        let attestation = response.attestation.take().unwrap();
+        assert_eq!(
+            attestation.user_data,
+            hex::encode(response_digest(&response).unwrap())
+        );
     }
 
     #[tokio::test]
     async fn test_generate_seed_invalid_strength() {
-        // Test implementation would go here
+        let enclave = Arc::new(MockEnclave::new());
+        let request = GenerateSeedRequest {
+            strength: Some(100),
+            passphrase: None,
+            language: SeedLanguage::English,
+            attest: false,
+            fresh_attestation: false,
+        };
+
+        let error = generate_seed(State(state(Arc::clone(&enclave))), ApiJson(request))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        // Rejected before reaching the enclave
+        assert!(enclave.operations().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_validate_seed() {
+        let state = state(Arc::new(MockEnclave::new()));
+
+        let request = ValidateSeedRequest {
+            seed_phrase: PHRASE.into(),
+            language: SeedLanguage::English,
+            entropy: Some("00".repeat(16).into()),
+        };
+        let response = validate_seed(State(state.clone()), ApiJson(request))
+            .await
+            .unwrap();
+        assert!(response.valid);
+        assert_eq!(response.entropy_match, Some(true));
+
+        let request = ValidateSeedRequest {
+            seed_phrase: "abandon abandon abandon".into(),
+            language: SeedLanguage::English,
+            entropy: None,
+        };
+        let response = validate_seed(State(state), ApiJson(request)).await.unwrap();
+        assert!(!response.valid);
+        assert_eq!(response.entropy_match, None);
+    }
+
+    #[tokio::test]
+    async fn test_derive_address() {
+        let request = DeriveAddressRequest {
+            seed_phrase: PHRASE.into(),
+            path: "m/84'/0'/0'/0/0".to_string(),
+            curve: "secp256k1".to_string(),
+            chain: Some(Chain::Bitcoin),
+        };
+
+        let response = derive_address(State(state(Arc::new(MockEnclave::new()))), ApiJson(request))
+            .await
+            .unwrap();
+        assert_eq!(response.address, MOCK_ADDRESS);
+        assert_eq!(response.path, "m/84'/0'/0'/0/0");
+    }
+
+    #[tokio::test]
+    async fn test_derive_address_reports_each_invalid_field() {
+        let enclave = Arc::new(MockEnclave::new());
+        let request = DeriveAddressRequest {
+            seed_phrase: PHRASE.into(),
+            path: "m/84'//0".to_string(),
+            curve: "p256".to_string(),
+            chain: Some(Chain::Bitcoin),
+        };
+
+        let error = derive_address(State(state(Arc::clone(&enclave))), ApiJson(request))
+            .await
+            .unwrap_err();
+        let body = error.to_response();
+        assert_eq!(body.code, 400);
+        assert!(body.request_id.is_some());
+        let fields: Vec<&str> = body.details.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["path", "curve"]);
+        assert!(enclave.operations().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_enclave_error_is_reported() {
+        let enclave = Arc::new(MockEnclave::new());
+        enclave.respond_with(
+            "sign_psbt",
+            EnclaveResult::Error {
+                message: "Signing policy violation".to_string(),
+                code: RenclaveErrorCode::PolicyDenied,
+            },
+        );
+        let request = SignPsbtRequest {
+            seed_phrase: PHRASE.into(),
+            psbt: "cHNidP8BAA==".to_string(),
+        };
+
+        let error = sign_psbt(State(state(enclave)), ApiJson(request))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert_eq!(error.to_response().code, 403);
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_enclave() {
+        let enclave = Arc::new(MockEnclave::new());
+        enclave.set_available(false);
+        let state = state(enclave);
+
+        let error = get_policy(State(state.clone())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let error = enclave_info(State(state)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_enclave_info_reports_protocol() {
+        let state = state(Arc::new(MockEnclave::new()));
+
+        let Json(info) = enclave_info(State(state)).await.unwrap();
+        assert_eq!(info["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(
+            info["operations"].as_array().unwrap().len(),
+            SUPPORTED_OPERATIONS.len()
+        );
+        assert_eq!(info["uptime_secs"], 0);
+        assert!(info["build"]["git_commit"].is_string());
+    }
+
+    /// Status of `method path` with `api_key` through the authorization middleware
+    async fn authorized_status(
+        state: &AppState,
+        method: &str,
+        path: &str,
+        api_key: Option<&str>,
+    ) -> StatusCode {
+        use axum::{
+            body::Body,
+            http::Request,
+            middleware,
+            routing::{get, post},
+            Router,
+        };
+        use tower::ServiceExt;
+
+        // The middleware only looks at the matched route, so admin routes need no real handler
+        let admin = || async { StatusCode::NO_CONTENT };
+        let app = Router::new()
+            .route("/enclave/info", get(enclave_info))
+            .route("/admin/config", get(admin).post(admin))
+            .route("/admin/log-level", post(admin))
+            .route("/admin/enclave/process/stop", post(admin))
+            .route("/admin/acme/renew", post(admin))
+            .route("/network/repair", post(admin))
+            .route("/enclave/policy", get(get_policy).post(set_policy))
+            .route(
+                "/admin/identities",
+                get(list_identities).post(create_identity),
+            )
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                crate::auth::authorize,
+            ))
+            .with_state(state.clone());
+
+        let mut request = Request::builder().method(method).uri(path);
+        if let Some(api_key) = api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        response.status()
+    }
+
+    #[tokio::test]
+    async fn test_scopes_are_enforced() {
+        let mut state = state(Arc::new(MockEnclave::new()));
+
+        // Without an admin key every route but the admin routes is open
+        assert_eq!(
+            authorized_status(&state, "GET", "/enclave/policy", None).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            authorized_status(&state, "POST", "/enclave/policy", None).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            authorized_status(&state, "GET", "/admin/identities", None).await,
+            StatusCode::FORBIDDEN
+        );
+
+        state.identities = Arc::new(IdentityStore::new(
+            Arc::new(MemoryStorage::default()),
+            Some("admin-key"),
+        ));
+        let reader = state
+            .identities
+            .create("reader", vec![Scope::PolicyRead])
+            .unwrap();
+
+        for (method, path, api_key, expected) in [
+            ("GET", "/enclave/info", None, StatusCode::OK),
+            ("GET", "/enclave/policy", None, StatusCode::UNAUTHORIZED),
+            (
+                "GET",
+                "/enclave/policy",
+                Some("rk_wrong"),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                "GET",
+                "/enclave/policy",
+                Some(reader.api_key.as_str()),
+                StatusCode::OK,
+            ),
+            (
+                "GET",
+                "/admin/identities",
+                Some(reader.api_key.as_str()),
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                "GET",
+                "/admin/identities",
+                Some("admin-key"),
+                StatusCode::OK,
+            ),
+        ] {
+            assert_eq!(
+                authorized_status(&state, method, path, api_key).await,
+                expected,
+                "{} {} with {:?}",
+                method,
+                path,
+                api_key
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_refused_without_admin_key() {
+        let state = state(Arc::new(MockEnclave::new()));
+        assert!(!state.identities.enabled());
+
+        for (method, path) in [
+            ("GET", "/admin/config"),
+            ("POST", "/admin/config"),
+            ("POST", "/admin/log-level"),
+            ("POST", "/admin/enclave/process/stop"),
+            ("POST", "/admin/acme/renew"),
+            ("POST", "/network/repair"),
+            ("POST", "/admin/identities"),
+            ("POST", "/enclave/policy"),
+        ] {
+            assert_eq!(
+                authorized_status(&state, method, path, None).await,
+                StatusCode::FORBIDDEN,
+                "{} {}",
+                method,
+                path
+            );
+            // A bearer token does not help while there is nothing to check it against
+            assert_eq!(
+                authorized_status(&state, method, path, Some("admin-key")).await,
+                StatusCode::FORBIDDEN,
+                "{} {}",
+                method,
+                path
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_identity_handlers() {
+        let state = state(Arc::new(MockEnclave::new()));
+        let request = CreateIdentityRequest {
+            name: "signer".to_string(),
+            scopes: vec![Scope::SignPsbt],
+        };
+
+        let (status, Json(created)) = create_identity(State(state.clone()), ApiJson(request))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(created.api_key.starts_with("rk_"));
+
+        let Json(updated) = update_identity(
+            State(state.clone()),
+            Path("signer".to_string()),
+            ApiJson(UpdateIdentityRequest {
+                scopes: vec![Scope::SignPsbt, Scope::PolicyRead],
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.scopes, vec![Scope::SignPsbt, Scope::PolicyRead]);
+
+        let status = delete_identity(State(state.clone()), Path("signer".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let error = delete_identity(State(state.clone()), Path("signer".to_string()))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+
+        let Json(identities) = list_identities(State(state)).await.unwrap();
+        assert!(identities.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_log_level_handlers() {
+        let original = log::max_level();
+        let enclave = Arc::new(MockEnclave::new());
+        let state = state(Arc::clone(&enclave));
+
+        let request = SetLogLevelRequest {
+            level: "TRACE".to_string(),
+        };
+        let Json(levels) = set_log_level(State(state.clone()), ApiJson(request))
+            .await
+            .unwrap();
+        assert_eq!(levels.host, "trace");
+        assert_eq!(levels.enclave.as_deref(), Some("trace"));
+
+        let request = SetLogLevelRequest {
+            level: "loud".to_string(),
+        };
+        let error = set_log_level(State(state.clone()), ApiJson(request))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+        enclave.set_available(false);
+        let Json(levels) = get_log_level(State(state)).await.unwrap();
+        assert_eq!(levels.host, "trace");
+        assert_eq!(levels.enclave, None);
+
+        log::set_max_level(original);
+    }
+
+    #[tokio::test]
+    async fn test_runtime_config_handlers() {
+        let state = state(Arc::new(MockEnclave::new()));
+
+        let request = UpdateRuntimeConfigRequest {
+            enclave_timeout_secs: Some(5),
+            ..Default::default()
+        };
+        let Json(config) = update_runtime_config(State(state.clone()), ApiJson(request))
+            .await
+            .unwrap();
+        assert_eq!(config.enclave_timeout_secs, 5);
+        assert_eq!(state.settings.enclave_timeout(), Duration::from_secs(5));
+
+        let request = UpdateRuntimeConfigRequest {
+            enclave_timeout_secs: Some(0),
+            ..Default::default()
+        };
+        let error = update_runtime_config(State(state.clone()), ApiJson(request))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+        let Json(config) = get_runtime_config(State(state)).await;
+        assert_eq!(config.enclave_timeout_secs, 5);
+    }
+
+    #[tokio::test]
+    async fn test_enclave_process_without_supervisor() {
+        let state = state(Arc::new(MockEnclave::new()));
+
+        let error = enclave_process(State(state.clone())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = enclave_console(State(state), Query(ConsoleQuery { lines: None }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn test_validate_seed_success() {
-        // Test implementation would go here
+    async fn test_threshold_policy_checked_before_enclave() {
+        let enclave = Arc::new(MockEnclave::new());
+        let mut state = state(Arc::clone(&enclave));
+        state.threshold_policy = ThresholdPolicy {
+            min_threshold: 2,
+            min_percent: 51,
+        };
+        let message = ThresholdMessage::Start {
+            group_public_key: "00".to_string(),
+            verifying_shares: (1..=4).map(|id| (id, String::new())).collect(),
+            threshold: 2,
+            signers: vec![1, 2],
+            message: "00".to_string(),
+        };
+
+        let error = threshold_signing(State(state), ApiJson(message))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(error.to_response().details[0].field, "threshold");
+        assert!(enclave.operations().is_empty());
     }
 
     #[tokio::test]
-    async fn test_validate_seed_invalid() {
-        // Test implementation would go here
+    async fn test_policy_round_trip() {
+        let state = state(Arc::new(MockEnclave::new()));
+        let policy = SigningPolicy {
+            daily_limit_sats: Some(100_000),
+            locked: true,
+            ..Default::default()
+        };
+
+        let updated = set_policy(State(state.clone()), ApiJson(policy))
+            .await
+            .unwrap();
+        assert!(updated.locked);
+        let current = get_policy(State(state.clone())).await.unwrap();
+        assert_eq!(current.daily_limit_sats, Some(100_000));
+
+        let error = set_policy(State(state), ApiJson(SigningPolicy::default()))
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_response().code, 403);
     }
-    */
 
-    /*
     #[tokio::test]
-    async fn test_network_status() {
-        // Test implementation would go here
+    async fn test_sealed_request_is_forwarded_opaquely() {
+        let enclave = Arc::new(MockEnclave::new());
+        let reply = SealedPayload {
+            counter: 1,
+            ciphertext: "c0ffee".to_string(),
+            tag: "ab".repeat(32),
+        };
+        enclave.respond_with(
+            "sealed_request",
+            EnclaveResult::SealedResponse {
+                session_id: "session".to_string(),
+                payload: reply.clone(),
+            },
+        );
+        let message = SealedChannelMessage {
+            session_id: "session".to_string(),
+            payload: SealedPayload {
+                counter: 1,
+                ciphertext: "00".to_string(),
+                tag: "cd".repeat(32),
+            },
+        };
+
+        let response = sealed_request(State(state(Arc::clone(&enclave))), ApiJson(message))
+            .await
+            .unwrap();
+        assert_eq!(response.payload, reply);
+        assert_eq!(enclave.operations(), vec!["sealed_request"]);
+
+        let error = open_channel(
+            State(state(enclave)),
+            ApiJson(OpenChannelRequest {
+                client_public_key: " ".to_string(),
+                nonce: None,
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
-    async fn test_enclave_info() {
-        // Test implementation would go here
+    async fn test_verify_signature() {
+        let enclave = Arc::new(MockEnclave::new());
+        enclave.respond_with(
+            "verify_signature",
+            EnclaveResult::SignatureVerified { valid: true },
+        );
+        let request = |signature: &str| VerifySignatureRequest {
+            curve: "secp256k1".to_string(),
+            public_key: format!("02{}", "11".repeat(32)),
+            message: "22".repeat(32),
+            signature: signature.to_string(),
+        };
+
+        let response = verify_signature(
+            State(state(Arc::clone(&enclave))),
+            ApiJson(request(&"33".repeat(64))),
+        )
+        .await
+        .unwrap();
+        assert!(response.valid);
+
+        let error = verify_signature(State(state(Arc::clone(&enclave))), ApiJson(request("")))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(enclave.operations(), vec!["verify_signature"]);
     }
-    */
 }
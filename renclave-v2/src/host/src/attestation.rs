@@ -0,0 +1,658 @@
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ciborium::Value;
+use openssl::bn::BigNum;
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{X509StoreContext, X509};
+use renclave_shared::{
+    AttestationCheck, AttestationReport, CertificateSummary, VerifyAttestationRequest,
+};
+use std::collections::BTreeMap;
+
+/// Environment variable for the hex SHA-256 fingerprint of the root certificate that
+/// attestation documents must chain to
+pub const NITRO_ROOT_FINGERPRINT_ENV: &str = "RENCLAVE_NITRO_ROOT_FINGERPRINT";
+
+/// SHA-256 fingerprint of the AWS Nitro Enclaves root certificate, as published by AWS
+pub const AWS_NITRO_ROOT_FINGERPRINT: &str =
+    "641a0321a3e244efe456463195d606317ed7cdcc3c1756e09893f3c68f79bb5b";
+
+const DEFAULT_MAX_AGE_SECS: u64 = 300;
+/// CBOR tag of a COSE_Sign1 structure (RFC 9052); documents may also omit it
+const COSE_SIGN1_TAG: u64 = 18;
+/// COSE algorithm identifier of ECDSA with SHA-384
+const ES384: i128 = -35;
+
+/// Root fingerprint from the environment, the AWS Nitro root by default
+pub fn trusted_root_fingerprint() -> String {
+    std::env::var(NITRO_ROOT_FINGERPRINT_ENV)
+        .ok()
+        .map(|value| normalize_fingerprint(&value))
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| AWS_NITRO_ROOT_FINGERPRINT.to_string())
+}
+
+/// Verify a Nitro attestation document against `request` and the root certificate with
+/// fingerprint `root_fingerprint`, at `now_ms` (Unix milliseconds).
+///
+/// Documents that cannot be decoded are errors. Anything decodable yields a report, with
+/// `valid` set only if every check passed.
+pub fn verify(
+    request: &VerifyAttestationRequest,
+    root_fingerprint: &str,
+    now_ms: u64,
+) -> Result<AttestationReport> {
+    let bytes = STANDARD
+        .decode(request.document.trim())
+        .context("The document is not valid base64")?;
+    let sign1 = CoseSign1::decode(&bytes)?;
+    let document = Document::decode(&sign1.payload)?;
+    let leaf = X509::from_der(&document.certificate).context("Invalid signing certificate")?;
+    let bundle = document
+        .cabundle
+        .iter()
+        .map(|der| X509::from_der(der))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Invalid certificate in cabundle")?;
+
+    let mut checks = vec![
+        signature_check(&sign1, &leaf),
+        chain_check(&leaf, &bundle, root_fingerprint),
+        freshness_check(
+            document.timestamp,
+            now_ms,
+            request.max_age_secs.unwrap_or(DEFAULT_MAX_AGE_SECS),
+        ),
+    ];
+    if let Some(expected) = &request.expected_manifest_hash {
+        checks.push(bytes_check(
+            "manifest_hash",
+            "user_data",
+            expected,
+            document.user_data.as_deref(),
+        )?);
+    }
+    if let Some(expected) = &request.expected_nonce {
+        checks.push(bytes_check(
+            "nonce",
+            "nonce",
+            expected,
+            document.nonce.as_deref(),
+        )?);
+    }
+    if !request.expected_pcrs.is_empty() {
+        checks.push(pcrs_check(&request.expected_pcrs, &document.pcrs)?);
+    }
+
+    let mut certificate_chain = vec![summary(&leaf)?];
+    for certificate in bundle.iter().rev() {
+        certificate_chain.push(summary(certificate)?);
+    }
+
+    Ok(AttestationReport {
+        valid: checks.iter().all(|check| check.passed),
+        checks,
+        module_id: document.module_id,
+        timestamp: document.timestamp,
+        pcrs: document
+            .pcrs
+            .iter()
+            .map(|(index, value)| (*index, hex::encode(value)))
+            .collect(),
+        user_data: document.user_data.as_deref().map(hex::encode),
+        nonce: document.nonce.as_deref().map(hex::encode),
+        public_key: document.public_key.as_deref().map(hex::encode),
+        certificate_chain,
+    })
+}
+
+/// COSE_Sign1 structure: protected header, payload and signature
+struct CoseSign1 {
+    protected: Vec<u8>,
+    payload: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl CoseSign1 {
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let value: Value =
+            ciborium::de::from_reader(bytes).context("The document is not valid CBOR")?;
+        let value = match value {
+            Value::Tag(COSE_SIGN1_TAG, inner) => *inner,
+            value => value,
+        };
+        let Value::Array(items) = value else {
+            return Err(anyhow!("The document is not a COSE_Sign1 structure"));
+        };
+        let [Value::Bytes(protected), _, Value::Bytes(payload), Value::Bytes(signature)] =
+            <[Value; 4]>::try_from(items)
+                .map_err(|_| anyhow!("A COSE_Sign1 structure has four items"))?
+        else {
+            return Err(anyhow!("Malformed COSE_Sign1 structure"));
+        };
+        Ok(Self {
+            protected,
+            payload,
+            signature,
+        })
+    }
+
+    /// Algorithm in the protected header
+    fn algorithm(&self) -> Option<i128> {
+        let header: Value = ciborium::de::from_reader(self.protected.as_slice()).ok()?;
+        header.as_map()?.iter().find_map(|(key, value)| {
+            (key.as_integer().map(i128::from) == Some(1))
+                .then(|| value.as_integer().map(i128::from))
+                .flatten()
+        })
+    }
+
+    /// Bytes the signature covers (`Sig_structure` of RFC 9052, without external data)
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let structure = Value::Array(vec![
+            Value::Text("Signature1".to_string()),
+            Value::Bytes(self.protected.clone()),
+            Value::Bytes(Vec::new()),
+            Value::Bytes(self.payload.clone()),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(&structure, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Payload of a Nitro attestation document
+struct Document {
+    module_id: String,
+    timestamp: u64,
+    pcrs: BTreeMap<u8, Vec<u8>>,
+    certificate: Vec<u8>,
+    /// Root first
+    cabundle: Vec<Vec<u8>>,
+    public_key: Option<Vec<u8>>,
+    user_data: Option<Vec<u8>>,
+    nonce: Option<Vec<u8>>,
+}
+
+impl Document {
+    fn decode(payload: &[u8]) -> Result<Self> {
+        let value: Value =
+            ciborium::de::from_reader(payload).context("The payload is not valid CBOR")?;
+        let fields = value
+            .as_map()
+            .ok_or_else(|| anyhow!("The payload is not a map"))?;
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(key, _)| key.as_text() == Some(name))
+                .map(|(_, value)| value)
+        };
+        let required =
+            |name: &str| field(name).ok_or_else(|| anyhow!("The payload has no {}", name));
+        let optional_bytes = |name: &str| match field(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::Bytes(bytes)) => Ok(Some(bytes.clone())),
+            Some(_) => Err(anyhow!("{} is not a byte string", name)),
+        };
+
+        let module_id = required("module_id")?
+            .as_text()
+            .ok_or_else(|| anyhow!("module_id is not text"))?
+            .to_string();
+        let digest = required("digest")?.as_text().unwrap_or_default();
+        if digest != "SHA384" {
+            return Err(anyhow!("Unsupported PCR digest '{}'", digest));
+        }
+        let timestamp = required("timestamp")?
+            .as_integer()
+            .and_then(|timestamp| u64::try_from(timestamp).ok())
+            .ok_or_else(|| anyhow!("timestamp is not an unsigned integer"))?;
+
+        let mut pcrs = BTreeMap::new();
+        for (index, value) in required("pcrs")?
+            .as_map()
+            .ok_or_else(|| anyhow!("pcrs is not a map"))?
+        {
+            let index = index
+                .as_integer()
+                .and_then(|index| u8::try_from(index).ok())
+                .ok_or_else(|| anyhow!("Invalid PCR index"))?;
+            let value = value
+                .as_bytes()
+                .ok_or_else(|| anyhow!("PCR{} is not a byte string", index))?;
+            pcrs.insert(index, value.clone());
+        }
+
+        let certificate = required("certificate")?
+            .as_bytes()
+            .ok_or_else(|| anyhow!("certificate is not a byte string"))?
+            .clone();
+        let cabundle = required("cabundle")?
+            .as_array()
+            .ok_or_else(|| anyhow!("cabundle is not an array"))?
+            .iter()
+            .map(|der| {
+                der.as_bytes()
+                    .cloned()
+                    .ok_or_else(|| anyhow!("cabundle entry is not a byte string"))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            module_id,
+            timestamp,
+            pcrs,
+            certificate,
+            cabundle,
+            public_key: optional_bytes("public_key")?,
+            user_data: optional_bytes("user_data")?,
+            nonce: optional_bytes("nonce")?,
+        })
+    }
+}
+
+fn check(name: &str, passed: bool, detail: impl Into<String>) -> AttestationCheck {
+    AttestationCheck {
+        name: name.to_string(),
+        passed,
+        detail: detail.into(),
+    }
+}
+
+fn signature_check(sign1: &CoseSign1, leaf: &X509) -> AttestationCheck {
+    let result = (|| -> Result<bool> {
+        if sign1.algorithm() != Some(ES384) {
+            return Err(anyhow!("the protected header does not name ES384"));
+        }
+        if sign1.signature.len() != 96 {
+            return Err(anyhow!(
+                "an ES384 signature is 96 bytes, got {}",
+                sign1.signature.len()
+            ));
+        }
+        let signature = EcdsaSig::from_private_components(
+            BigNum::from_slice(&sign1.signature[..48])?,
+            BigNum::from_slice(&sign1.signature[48..])?,
+        )?;
+        let key = leaf
+            .public_key()?
+            .ec_key()
+            .context("the signing certificate has no EC key")?;
+        let digest = openssl::sha::sha384(&sign1.signed_bytes()?);
+        Ok(signature.verify(&digest, &key)?)
+    })();
+    match result {
+        Ok(true) => check("signature", true, "Signed by the signing certificate's key"),
+        Ok(false) => check("signature", false, "The signature does not match"),
+        Err(e) => check("signature", false, format!("Cannot verify: {:#}", e)),
+    }
+}
+
+fn chain_check(leaf: &X509, bundle: &[X509], root_fingerprint: &str) -> AttestationCheck {
+    let result = (|| -> Result<std::result::Result<String, String>> {
+        let Some(root) = bundle.first() else {
+            return Ok(Err("The cabundle is empty".to_string()));
+        };
+        let fingerprint = hex::encode(root.digest(MessageDigest::sha256())?);
+        if fingerprint != normalize_fingerprint(root_fingerprint) {
+            return Ok(Err(format!("Root {} is not trusted", fingerprint)));
+        }
+
+        let mut store = X509StoreBuilder::new()?;
+        store.add_cert(root.clone())?;
+        let store = store.build();
+        let mut intermediates = Stack::new()?;
+        for certificate in &bundle[1..] {
+            intermediates.push(certificate.clone())?;
+        }
+        let mut context = X509StoreContext::new()?;
+        let verified = context.init(&store, leaf, &intermediates, |context| {
+            Ok(if context.verify_cert()? {
+                Ok(format!(
+                    "Chains to trusted root {} through {} intermediates",
+                    fingerprint,
+                    bundle.len() - 1
+                ))
+            } else {
+                Err(context.error().to_string())
+            })
+        })?;
+        Ok(verified)
+    })();
+    match result {
+        Ok(Ok(detail)) => check("certificate_chain", true, detail),
+        Ok(Err(detail)) => check("certificate_chain", false, detail),
+        Err(e) => check(
+            "certificate_chain",
+            false,
+            format!("Cannot verify: {:#}", e),
+        ),
+    }
+}
+
+fn freshness_check(timestamp: u64, now_ms: u64, max_age_secs: u64) -> AttestationCheck {
+    let age_ms = now_ms.abs_diff(timestamp);
+    let detail = if timestamp > now_ms {
+        format!("Produced {} ms in the future", age_ms)
+    } else {
+        format!("Produced {} s ago", age_ms / 1000)
+    };
+    check(
+        "freshness",
+        age_ms <= max_age_secs.saturating_mul(1000),
+        detail,
+    )
+}
+
+/// Compare the document's `field` with the `expected` hex. Invalid hex is an error.
+fn bytes_check(
+    name: &str,
+    field: &str,
+    expected: &str,
+    actual: Option<&[u8]>,
+) -> Result<AttestationCheck> {
+    let expected = hex::decode(expected.trim())
+        .map_err(|e| anyhow!("Expected {} is not valid hex: {}", name, e))?;
+    Ok(match actual {
+        Some(actual) if actual == expected => check(name, true, format!("{} matches", field)),
+        Some(actual) => check(name, false, format!("{} is {}", field, hex::encode(actual))),
+        None => check(name, false, format!("The document has no {}", field)),
+    })
+}
+
+fn pcrs_check(
+    expected: &BTreeMap<u8, String>,
+    actual: &BTreeMap<u8, Vec<u8>>,
+) -> Result<AttestationCheck> {
+    let mut mismatches = Vec::new();
+    for (index, value) in expected {
+        let value = hex::decode(value.trim())
+            .map_err(|e| anyhow!("Expected PCR{} is not valid hex: {}", index, e))?;
+        match actual.get(index) {
+            Some(actual) if *actual == value => {}
+            Some(_) => mismatches.push(format!("PCR{} differs", index)),
+            None => mismatches.push(format!("PCR{} is missing", index)),
+        }
+    }
+    Ok(if mismatches.is_empty() {
+        check("pcrs", true, format!("{} PCRs match", expected.len()))
+    } else {
+        check("pcrs", false, mismatches.join(", "))
+    })
+}
+
+fn summary(certificate: &X509) -> Result<CertificateSummary> {
+    let subject = certificate
+        .subject_name()
+        .entries()
+        .map(|entry| {
+            let name = entry.object().nid().short_name().unwrap_or("?");
+            let value = entry
+                .data()
+                .as_utf8()
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            format!("{}={}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(CertificateSummary {
+        subject,
+        not_before: certificate.not_before().to_string(),
+        not_after: certificate.not_after().to_string(),
+        fingerprint: hex::encode(certificate.digest(MessageDigest::sha256())?),
+    })
+}
+
+/// Lowercase hex without the colons fingerprints are often written with
+fn normalize_fingerprint(value: &str) -> String {
+    value.trim().replace(':', "").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::x509::extension::BasicConstraints;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+
+    const NOW_MS: u64 = 1_700_000_000_000;
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    /// Certificate for `key` named `name`, self-signed unless `issuer` is given
+    fn certificate(
+        name: &str,
+        key: &PKey<Private>,
+        issuer: Option<(&X509, &PKey<Private>)>,
+        ca: bool,
+    ) -> X509 {
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject.append_entry_by_nid(Nid::COMMONNAME, name).unwrap();
+        let subject = subject.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&subject).unwrap();
+        builder
+            .set_issuer_name(issuer.map_or(&subject, |(issuer, _)| issuer.subject_name()))
+            .unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.set_pubkey(key).unwrap();
+        if ca {
+            builder
+                .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+                .unwrap();
+        }
+        let signer = issuer.map_or(key, |(_, key)| key);
+        builder.sign(signer, MessageDigest::sha384()).unwrap();
+        builder.build()
+    }
+
+    struct Fixture {
+        root: X509,
+        document: Vec<u8>,
+    }
+
+    /// Nitro-style document chained root -> intermediate -> leaf
+    fn fixture(timestamp: u64, user_data: &[u8]) -> Fixture {
+        let root_key = key();
+        let root = certificate("root", &root_key, None, true);
+        let intermediate_key = key();
+        let intermediate = certificate(
+            "intermediate",
+            &intermediate_key,
+            Some((&root, &root_key)),
+            true,
+        );
+        let leaf_key = key();
+        let leaf = certificate(
+            "enclave",
+            &leaf_key,
+            Some((&intermediate, &intermediate_key)),
+            false,
+        );
+
+        let text = |value: &str| Value::Text(value.to_string());
+        let payload = Value::Map(vec![
+            (text("module_id"), text("i-0123-enc0123")),
+            (text("digest"), text("SHA384")),
+            (text("timestamp"), Value::Integer(timestamp.into())),
+            (
+                text("pcrs"),
+                Value::Map(vec![
+                    (Value::Integer(0.into()), Value::Bytes(vec![0xaa; 48])),
+                    (Value::Integer(1.into()), Value::Bytes(vec![0xbb; 48])),
+                ]),
+            ),
+            (text("certificate"), Value::Bytes(leaf.to_der().unwrap())),
+            (
+                text("cabundle"),
+                Value::Array(vec![
+                    Value::Bytes(root.to_der().unwrap()),
+                    Value::Bytes(intermediate.to_der().unwrap()),
+                ]),
+            ),
+            (text("public_key"), Value::Null),
+            (text("user_data"), Value::Bytes(user_data.to_vec())),
+            (text("nonce"), Value::Bytes(vec![1, 2, 3])),
+        ]);
+        let mut payload_bytes = Vec::new();
+        ciborium::ser::into_writer(&payload, &mut payload_bytes).unwrap();
+        let mut protected = Vec::new();
+        ciborium::ser::into_writer(
+            &Value::Map(vec![(
+                Value::Integer(1.into()),
+                Value::Integer(ES384.try_into().unwrap()),
+            )]),
+            &mut protected,
+        )
+        .unwrap();
+
+        let mut sign1 = CoseSign1 {
+            protected,
+            payload: payload_bytes,
+            signature: Vec::new(),
+        };
+        let digest = openssl::sha::sha384(&sign1.signed_bytes().unwrap());
+        let signature = EcdsaSig::sign(&digest, &leaf_key.ec_key().unwrap()).unwrap();
+        sign1.signature = signature.r().to_vec_padded(48).unwrap();
+        sign1
+            .signature
+            .extend(signature.s().to_vec_padded(48).unwrap());
+
+        let value = Value::Tag(
+            COSE_SIGN1_TAG,
+            Box::new(Value::Array(vec![
+                Value::Bytes(sign1.protected),
+                Value::Map(Vec::new()),
+                Value::Bytes(sign1.payload),
+                Value::Bytes(sign1.signature),
+            ])),
+        );
+        let mut document = Vec::new();
+        ciborium::ser::into_writer(&value, &mut document).unwrap();
+        Fixture { root, document }
+    }
+
+    fn request(document: &[u8]) -> VerifyAttestationRequest {
+        VerifyAttestationRequest {
+            document: STANDARD.encode(document),
+            expected_manifest_hash: Some(hex::encode([7u8; 32])),
+            expected_nonce: Some("010203".to_string()),
+            expected_pcrs: BTreeMap::from([(0, hex::encode([0xaa; 48]))]),
+            max_age_secs: None,
+        }
+    }
+
+    fn root_fingerprint(root: &X509) -> String {
+        hex::encode(root.digest(MessageDigest::sha256()).unwrap())
+    }
+
+    fn failed(report: &AttestationReport) -> Vec<&str> {
+        report
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_document() {
+        let fixture = fixture(NOW_MS - 10_000, &[7; 32]);
+        let report = verify(
+            &request(&fixture.document),
+            &root_fingerprint(&fixture.root),
+            NOW_MS,
+        )
+        .unwrap();
+
+        assert!(report.valid, "{:?}", report.checks);
+        assert_eq!(report.checks.len(), 6);
+        assert_eq!(report.module_id, "i-0123-enc0123");
+        assert_eq!(report.pcrs[&1], hex::encode([0xbb; 48]));
+        assert_eq!(report.nonce.as_deref(), Some("010203"));
+        assert_eq!(report.public_key, None);
+        let subjects: Vec<&str> = report
+            .certificate_chain
+            .iter()
+            .map(|certificate| certificate.subject.as_str())
+            .collect();
+        assert_eq!(subjects, ["CN=enclave", "CN=intermediate", "CN=root"]);
+    }
+
+    #[test]
+    fn test_failed_checks_are_reported() {
+        let fixture = fixture(NOW_MS - 600_000, &[8; 32]);
+        let mut request = request(&fixture.document);
+        request.expected_pcrs.insert(2, hex::encode([0; 48]));
+
+        let report = verify(&request, AWS_NITRO_ROOT_FINGERPRINT, NOW_MS).unwrap();
+        assert!(!report.valid);
+        assert_eq!(
+            failed(&report),
+            ["certificate_chain", "freshness", "manifest_hash", "pcrs"]
+        );
+    }
+
+    #[test]
+    fn test_tampered_payload_fails_signature() {
+        let fixture = fixture(NOW_MS, &[7; 32]);
+        let Value::Tag(_, inner) = ciborium::de::from_reader(fixture.document.as_slice()).unwrap()
+        else {
+            panic!("untagged fixture");
+        };
+        let Value::Array(mut items) = *inner else {
+            panic!("fixture is not an array");
+        };
+        let Value::Bytes(payload) = &mut items[2] else {
+            panic!("payload is not bytes");
+        };
+        let last = payload.len() - 1;
+        payload[last] ^= 1;
+        let mut document = Vec::new();
+        ciborium::ser::into_writer(&Value::Array(items), &mut document).unwrap();
+
+        let report = verify(
+            &request(&document),
+            &root_fingerprint(&fixture.root),
+            NOW_MS,
+        )
+        .unwrap();
+        assert_eq!(failed(&report), ["signature", "nonce"]);
+    }
+
+    #[test]
+    fn test_undecodable_documents_are_errors() {
+        let mut request = request(b"not cbor");
+        assert!(verify(&request, AWS_NITRO_ROOT_FINGERPRINT, NOW_MS).is_err());
+        request.document = "%%%".to_string();
+        assert!(verify(&request, AWS_NITRO_ROOT_FINGERPRINT, NOW_MS).is_err());
+
+        let fixture = fixture(NOW_MS, &[7; 32]);
+        let mut request = self::request(&fixture.document);
+        request.expected_nonce = Some("zz".to_string());
+        assert!(verify(&request, &root_fingerprint(&fixture.root), NOW_MS).is_err());
+    }
+
+    #[test]
+    fn test_normalize_fingerprint() {
+        assert_eq!(normalize_fingerprint(" 64:1A:03 "), "641a03");
+    }
+}
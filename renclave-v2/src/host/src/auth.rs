@@ -0,0 +1,502 @@
+use anyhow::Result;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+use bitcoin_hashes::{sha256, Hash};
+use log::{info, warn};
+use rand::RngCore;
+use renclave_shared::validation::NamespaceName;
+use renclave_shared::{CreateIdentityResponse, FieldError, Identity, Scope};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::ApiError;
+use crate::storage::{Storage, StorageExt};
+use crate::AppState;
+
+/// Environment variable with the API key of the built-in admin identity; setting it enables
+/// authentication
+pub const ADMIN_API_KEY_ENV: &str = "RENCLAVE_ADMIN_API_KEY";
+
+/// Name of the built-in identity holding every scope
+pub const ADMIN_IDENTITY: &str = "admin";
+
+/// Storage namespace of API identities, keyed by name
+const IDENTITY_NAMESPACE: &str = "identities";
+
+#[derive(Serialize, Deserialize)]
+struct StoredIdentity {
+    scopes: Vec<Scope>,
+    /// Hex SHA-256 of the API key
+    key_hash: String,
+    created_at: u64,
+}
+
+/// Reasons an identity cannot be created, changed or removed
+#[derive(Debug)]
+pub enum IdentityError {
+    InvalidName(FieldError),
+    AlreadyExists(String),
+    NotFound(String),
+    Storage(anyhow::Error),
+}
+
+impl From<IdentityError> for ApiError {
+    fn from(error: IdentityError) -> Self {
+        match error {
+            IdentityError::InvalidName(error) => ApiError::invalid_fields(error.into(), None),
+            IdentityError::AlreadyExists(name) => {
+                ApiError::invalid_request(format!("Identity '{}' already exists", name), None)
+            }
+            IdentityError::NotFound(name) => {
+                ApiError::not_found(format!("Identity '{}' does not exist", name))
+            }
+            IdentityError::Storage(e) => ApiError::storage_failed(e),
+        }
+    }
+}
+
+/// API identities and their scopes.
+///
+/// Authentication is off unless an admin key is configured. The enclave routes are then open,
+/// as before identities existed, and routes needing one of `ADMIN_SCOPES` are refused. Identities live in host storage and only the SHA-256 of their
+/// keys is kept. The admin identity is not stored and holds every scope.
+pub struct IdentityStore {
+    storage: Arc<dyn Storage>,
+    admin_key_hash: Option<String>,
+}
+
+impl IdentityStore {
+    pub fn new(storage: Arc<dyn Storage>, admin_api_key: Option<&str>) -> Self {
+        Self {
+            storage,
+            admin_key_hash: admin_api_key.map(key_hash),
+        }
+    }
+
+    /// Store with the admin key from `RENCLAVE_ADMIN_API_KEY`, if set
+    pub fn from_env(storage: Arc<dyn Storage>) -> Self {
+        let admin_api_key = std::env::var(ADMIN_API_KEY_ENV)
+            .ok()
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty());
+
+        match admin_api_key {
+            Some(_) => info!("🔐 API authentication enabled"),
+            None => warn!(
+                "⚠️  {} is not set, API authentication is disabled",
+                ADMIN_API_KEY_ENV
+            ),
+        }
+        Self::new(storage, admin_api_key.as_deref())
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.admin_key_hash.is_some()
+    }
+
+    /// Identity holding `api_key`, if any
+    pub fn authenticate(&self, api_key: &str) -> Result<Option<Identity>> {
+        let hash = key_hash(api_key);
+        if self.admin_key_hash.as_deref() == Some(hash.as_str()) {
+            return Ok(Some(Identity {
+                name: ADMIN_IDENTITY.to_string(),
+                scopes: Scope::ALL.to_vec(),
+                created_at: 0,
+            }));
+        }
+
+        Ok(self
+            .storage
+            .list_json::<StoredIdentity>(IDENTITY_NAMESPACE)?
+            .into_iter()
+            .find(|(_, stored)| stored.key_hash == hash)
+            .map(|(name, stored)| identity(name, stored)))
+    }
+
+    /// Stored identities ordered by name
+    pub fn list(&self) -> Result<Vec<Identity>> {
+        Ok(self
+            .storage
+            .list_json::<StoredIdentity>(IDENTITY_NAMESPACE)?
+            .into_iter()
+            .map(|(name, stored)| identity(name, stored))
+            .collect())
+    }
+
+    /// Create an identity with a new random API key
+    pub fn create(
+        &self,
+        name: &str,
+        scopes: Vec<Scope>,
+    ) -> Result<CreateIdentityResponse, IdentityError> {
+        NamespaceName::new("name", name).map_err(IdentityError::InvalidName)?;
+        if name == ADMIN_IDENTITY || self.load(name)?.is_some() {
+            return Err(IdentityError::AlreadyExists(name.to_string()));
+        }
+
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        let api_key = format!("rk_{}", hex::encode(key));
+
+        let stored = StoredIdentity {
+            scopes: normalize(scopes),
+            key_hash: key_hash(&api_key),
+            created_at: unix_now(),
+        };
+        self.save(name, &stored)?;
+        info!("🔐 Created API identity {}", name);
+
+        Ok(CreateIdentityResponse {
+            identity: identity(name.to_string(), stored),
+            api_key,
+        })
+    }
+
+    /// Replace the scopes of an identity
+    pub fn update(&self, name: &str, scopes: Vec<Scope>) -> Result<Identity, IdentityError> {
+        let mut stored = self
+            .load(name)?
+            .ok_or_else(|| IdentityError::NotFound(name.to_string()))?;
+        stored.scopes = normalize(scopes);
+        self.save(name, &stored)?;
+        info!("🔐 Updated scopes of API identity {}", name);
+
+        Ok(identity(name.to_string(), stored))
+    }
+
+    /// Remove an identity; its key stops working immediately
+    pub fn delete(&self, name: &str) -> Result<(), IdentityError> {
+        if self.load(name)?.is_none() {
+            return Err(IdentityError::NotFound(name.to_string()));
+        }
+        self.storage
+            .delete(IDENTITY_NAMESPACE, name)
+            .map_err(IdentityError::Storage)?;
+        info!("🔐 Deleted API identity {}", name);
+        Ok(())
+    }
+
+    fn load(&self, name: &str) -> Result<Option<StoredIdentity>, IdentityError> {
+        self.storage
+            .get_json(IDENTITY_NAMESPACE, name)
+            .map_err(IdentityError::Storage)
+    }
+
+    fn save(&self, name: &str, stored: &StoredIdentity) -> Result<(), IdentityError> {
+        self.storage
+            .put_json(IDENTITY_NAMESPACE, name, stored)
+            .map_err(IdentityError::Storage)
+    }
+}
+
+/// Scopes that are refused while authentication is disabled.
+///
+/// These routes manage identities, stop or reconfigure the host and the enclave, change the
+/// enclave's signing policy or change the network, so they must never be reachable without an
+/// API key.
+pub const ADMIN_SCOPES: [Scope; 4] = [
+    Scope::IdentitiesManage,
+    Scope::ConfigManage,
+    Scope::NetworkManage,
+    Scope::PolicyWrite,
+];
+
+/// Scope a route requires, or `None` for public routes.
+///
+/// Health, service info and read-only enclave status are public. Routes not listed here
+/// require `identities:manage`, so a new route is never public by accident.
+pub fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    if path.starts_with("/swagger-ui") || path == "/openapi.json" {
+        return None;
+    }
+    // Named enclaves serve the same routes under /enclaves/{name}
+    let path = path
+        .strip_prefix("/enclaves/")
+        .and_then(|rest| rest.find('/').map(|slash| &rest[slash..]))
+        .unwrap_or(path);
+
+    match (method.as_str(), path) {
+        (
+            "GET",
+            "/health"
+            | "/health/live"
+            | "/health/ready"
+            | "/info"
+            | "/network/status"
+            | "/enclaves"
+            | "/enclave/info"
+            | "/enclave/metrics/snapshot"
+            | "/.well-known/acme-challenge/:token",
+        ) => None,
+        // Static dashboard assets; the data they show comes from the routes above
+        ("GET", path) if path.starts_with("/admin/dashboard") => None,
+        (_, "/generate-seed") => Some(Scope::SeedGenerate),
+        (_, "/validate-seed") => Some(Scope::SeedValidate),
+        (_, "/derive-key" | "/derive-address" | "/derive-xpub") => Some(Scope::KeyDerive),
+        (_, "/enclave/sign-psbt") => Some(Scope::SignPsbt),
+        ("GET", "/enclave/policy" | "/enclave/keys") => Some(Scope::PolicyRead),
+        (_, "/enclave/policy") => Some(Scope::PolicyWrite),
+        (_, "/enclave/channel" | "/enclave/channel/sealed") => Some(Scope::ChannelUse),
+        (_, "/enclave/threshold") => Some(Scope::SignThreshold),
+        (_, "/verify-signature" | "/verify-ciphertext" | "/attestation/verify") => {
+            Some(Scope::CryptoVerify)
+        }
+        (_, "/network/test" | "/network/repair" | "/network/egress") => Some(Scope::NetworkManage),
+        (_, "/admin/log-level" | "/admin/config" | "/admin/acme" | "/admin/acme/renew") => {
+            Some(Scope::ConfigManage)
+        }
+        (_, path) if path.starts_with("/admin/enclave/") => Some(Scope::ConfigManage),
+        _ => Some(Scope::IdentitiesManage),
+    }
+}
+
+/// Middleware enforcing `required_scope` with `Authorization: Bearer <api key>`
+pub async fn authorize(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    // Unrouted requests fall through to the 404 fallback
+    let Some(path) = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+    else {
+        return Ok(next.run(request).await);
+    };
+    let Some(scope) = required_scope(request.method(), &path) else {
+        return Ok(next.run(request).await);
+    };
+
+    if !state.identities.enabled() {
+        if ADMIN_SCOPES.contains(&scope) {
+            warn!(
+                "🚫 Refusing {} {} while authentication is disabled",
+                request.method(),
+                path
+            );
+            return Err(ApiError::forbidden(format!(
+                "{} requires scope {}, which is unavailable while authentication is disabled; set {}",
+                path,
+                scope.as_str(),
+                ADMIN_API_KEY_ENV
+            )));
+        }
+        return Ok(next.run(request).await);
+    }
+
+    let api_key = bearer_token(request.headers()).ok_or(ApiError::Unauthorized)?;
+    let identity = state
+        .identities
+        .authenticate(api_key)
+        .map_err(ApiError::storage_failed)?
+        .ok_or(ApiError::Unauthorized)?;
+
+    if !identity.scopes.contains(&scope) {
+        warn!(
+            "🚫 Identity {} lacks scope {} for {} {}",
+            identity.name,
+            scope.as_str(),
+            request.method(),
+            path
+        );
+        return Err(ApiError::forbidden(format!(
+            "Identity '{}' lacks scope {}",
+            identity.name,
+            scope.as_str()
+        )));
+    }
+
+    request.extensions_mut().insert(identity);
+    Ok(next.run(request).await)
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| token.trim())
+        .filter(|token| !token.is_empty())
+}
+
+fn key_hash(api_key: &str) -> String {
+    sha256::Hash::hash(api_key.as_bytes()).to_string()
+}
+
+fn normalize(mut scopes: Vec<Scope>) -> Vec<Scope> {
+    scopes.sort();
+    scopes.dedup();
+    scopes
+}
+
+fn identity(name: String, stored: StoredIdentity) -> Identity {
+    Identity {
+        name,
+        scopes: stored.scopes,
+        created_at: stored.created_at,
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use crate::ApiDoc;
+    use utoipa::OpenApi;
+
+    fn store() -> IdentityStore {
+        IdentityStore::new(Arc::new(MemoryStorage::default()), Some("admin-key"))
+    }
+
+    #[test]
+    fn test_identity_lifecycle() {
+        let store = store();
+        let created = store
+            .create(
+                "wallet",
+                vec![Scope::SignPsbt, Scope::SeedGenerate, Scope::SignPsbt],
+            )
+            .unwrap();
+        assert_eq!(
+            created.identity.scopes,
+            vec![Scope::SeedGenerate, Scope::SignPsbt]
+        );
+
+        let identity = store.authenticate(&created.api_key).unwrap().unwrap();
+        assert_eq!(identity.name, "wallet");
+        assert_eq!(store.authenticate("rk_unknown").unwrap(), None);
+
+        let updated = store.update("wallet", vec![Scope::KeyDerive]).unwrap();
+        assert_eq!(updated.scopes, vec![Scope::KeyDerive]);
+        assert_eq!(store.list().unwrap(), vec![updated]);
+
+        store.delete("wallet").unwrap();
+        assert_eq!(store.authenticate(&created.api_key).unwrap(), None);
+        assert!(matches!(
+            store.delete("wallet"),
+            Err(IdentityError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_bad_and_duplicate_names() {
+        let store = store();
+        store.create("ops", vec![]).unwrap();
+
+        for name in ["ops", ADMIN_IDENTITY] {
+            assert!(matches!(
+                store.create(name, vec![]),
+                Err(IdentityError::AlreadyExists(_))
+            ));
+        }
+        for name in ["", "has space", "slash/name", ".."] {
+            assert!(matches!(
+                store.create(name, vec![]),
+                Err(IdentityError::InvalidName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_admin_key_holds_every_scope() {
+        let identity = store().authenticate("admin-key").unwrap().unwrap();
+        assert_eq!(identity.name, ADMIN_IDENTITY);
+        assert_eq!(identity.scopes, Scope::ALL.to_vec());
+
+        let disabled = IdentityStore::new(Arc::new(MemoryStorage::default()), None);
+        assert!(!disabled.enabled());
+        assert_eq!(disabled.authenticate("admin-key").unwrap(), None);
+    }
+
+    #[test]
+    fn test_documented_routes_have_explicit_access() {
+        let public = [
+            "/health",
+            "/health/live",
+            "/health/ready",
+            "/info",
+            "/network/status",
+            "/enclaves",
+            "/enclave/info",
+            "/enclave/metrics/snapshot",
+            "/.well-known/acme-challenge/:token",
+        ];
+
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for (path, operations) in spec["paths"].as_object().unwrap() {
+            let route = path.replace("{name}", ":name").replace("{token}", ":token");
+            for method in operations.as_object().unwrap().keys() {
+                let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+                let scope = required_scope(&method, &route);
+
+                if public.contains(&route.as_str()) {
+                    assert_eq!(scope, None, "{} {}", method, route);
+                } else if route.starts_with("/admin/identities") {
+                    assert_eq!(scope, Some(Scope::IdentitiesManage), "{} {}", method, route);
+                } else {
+                    assert!(
+                        scope.is_some() && scope != Some(Scope::IdentitiesManage),
+                        "{} {} has no scope of its own",
+                        method,
+                        route
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_named_enclave_routes_share_scopes() {
+        for (method, route) in [
+            (Method::POST, "/enclave/sign-psbt"),
+            (Method::GET, "/enclave/policy"),
+            (Method::GET, "/enclave/info"),
+        ] {
+            assert_eq!(
+                required_scope(&method, &format!("/enclaves/signer{}", route)),
+                required_scope(&method, route)
+            );
+        }
+        assert_eq!(
+            required_scope(&Method::POST, "/enclaves/signer"),
+            Some(Scope::IdentitiesManage)
+        );
+    }
+
+    #[test]
+    fn test_dashboard_assets_are_public() {
+        assert_eq!(required_scope(&Method::GET, "/admin/dashboard"), None);
+        assert_eq!(
+            required_scope(&Method::GET, "/admin/dashboard/dashboard.js"),
+            None
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/admin/dashboard"),
+            Some(Scope::IdentitiesManage)
+        );
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(header::AUTHORIZATION, "Bearer rk_abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("rk_abc"));
+        headers.insert(header::AUTHORIZATION, "Basic dXNlcg==".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+    }
+}
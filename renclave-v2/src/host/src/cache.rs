@@ -0,0 +1,227 @@
+use anyhow::Result;
+use axum::async_trait;
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::enclave_client::EnclaveApi;
+use crate::session::EnclaveEvent;
+use renclave_shared::{EnclaveOperation, EnclaveResponse, EnclaveResult};
+
+/// Environment variable for how long idempotent reads are served from cache, in milliseconds
+pub const CACHE_TTL_MS_ENV: &str = "RENCLAVE_HOST_CACHE_TTL_MS";
+
+const DEFAULT_CACHE_TTL: Duration = Duration::from_millis(1000);
+
+/// Read the cache TTL from the environment; zero disables caching
+pub fn ttl_from_env() -> Duration {
+    std::env::var(CACHE_TTL_MS_ENV)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_CACHE_TTL)
+}
+
+/// A single value that expires `ttl` after it was stored
+pub struct TtlCache<T> {
+    ttl: Duration,
+    entry: Mutex<Option<(Instant, T)>>,
+}
+
+impl<T: Clone> TtlCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: Mutex::new(None),
+        }
+    }
+
+    /// The stored value, if it has not expired
+    pub fn get(&self) -> Option<T> {
+        let entry = self.entry.lock().unwrap();
+        entry
+            .as_ref()
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn put(&self, value: T) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        *self.entry.lock().unwrap() = Some((Instant::now(), value));
+    }
+
+    pub fn invalidate(&self) {
+        *self.entry.lock().unwrap() = None;
+    }
+}
+
+/// Enclave client serving idempotent reads from a short-lived cache.
+///
+/// Only GetInfo and GetPolicy are cached, and only successful responses. Operations that
+/// change what those report clear the cache, and so do failed round trips, which usually
+/// mean the enclave went away. Sealed requests clear it too since the host cannot see
+/// whether they carry a policy change.
+pub struct CachingEnclave {
+    inner: Arc<dyn EnclaveApi>,
+    ttl: Duration,
+    entries: Mutex<HashMap<&'static str, (Instant, EnclaveResponse)>>,
+}
+
+impl CachingEnclave {
+    pub fn new(inner: Arc<dyn EnclaveApi>, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Drop every cached response
+    pub fn invalidate(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Drop cached responses whenever the enclave session changes
+    pub fn invalidate_on(self: &Arc<Self>, mut events: broadcast::Receiver<EnclaveEvent>) {
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(_) => cache.invalidate(),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("⚠️  Missed {} enclave session events", missed);
+                        cache.invalidate();
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    fn cached(&self, operation: &EnclaveOperation) -> Option<EnclaveResponse> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(operation.name())
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, response)| response.clone())
+    }
+}
+
+fn is_cacheable(operation: &EnclaveOperation) -> bool {
+    matches!(
+        operation,
+        EnclaveOperation::GetInfo | EnclaveOperation::GetPolicy
+    )
+}
+
+fn invalidates_cache(operation: &EnclaveOperation) -> bool {
+    matches!(
+        operation,
+        EnclaveOperation::SetPolicy { .. }
+            | EnclaveOperation::RepairNetwork
+            | EnclaveOperation::SealedRequest { .. }
+            | EnclaveOperation::InstallTlsCertificate { .. }
+    )
+}
+
+#[async_trait]
+impl EnclaveApi for CachingEnclave {
+    async fn send_request(&self, operation: EnclaveOperation) -> Result<EnclaveResponse> {
+        let cacheable = is_cacheable(&operation) && !self.ttl.is_zero();
+        if cacheable {
+            if let Some(response) = self.cached(&operation) {
+                debug!("📦 Serving {} from cache", operation.name());
+                return Ok(response);
+            }
+        }
+
+        let name = operation.name();
+        let invalidates = invalidates_cache(&operation);
+        let result = self.inner.send_request(operation).await;
+
+        match &result {
+            Ok(response)
+                if cacheable && !matches!(response.result, EnclaveResult::Error { .. }) =>
+            {
+                self.entries
+                    .lock()
+                    .unwrap()
+                    .insert(name, (Instant::now(), response.clone()));
+            }
+            Ok(_) if !invalidates => {}
+            _ => self.invalidate(),
+        }
+        result
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_enclave::MockEnclave;
+    use renclave_shared::SigningPolicy;
+
+    #[tokio::test]
+    async fn test_reads_are_served_from_cache() {
+        let mock = Arc::new(MockEnclave::new());
+        let cache = CachingEnclave::new(mock.clone(), Duration::from_secs(60));
+
+        cache.get_info().await.unwrap();
+        cache.get_info().await.unwrap();
+        assert_eq!(mock.operations(), vec!["get_info"]);
+
+        cache.invalidate();
+        cache.get_info().await.unwrap();
+        assert_eq!(mock.operations(), vec!["get_info", "get_info"]);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_not_cached() {
+        let mock = Arc::new(MockEnclave::new());
+        let cache = CachingEnclave::new(mock.clone(), Duration::from_secs(60));
+
+        mock.set_available(false);
+        assert!(cache.get_info().await.is_err());
+        mock.set_available(true);
+        assert!(cache.get_info().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_policy_change_invalidates() {
+        let mock = Arc::new(MockEnclave::new());
+        let cache = CachingEnclave::new(mock, Duration::from_secs(60));
+        cache.get_policy().await.unwrap();
+
+        let policy = SigningPolicy {
+            daily_limit_sats: Some(1_000),
+            ..Default::default()
+        };
+        cache.set_policy(policy.clone()).await.unwrap();
+
+        match cache.get_policy().await.unwrap().result {
+            EnclaveResult::Policy { policy: current } => assert_eq!(current, policy),
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_zero_ttl_stores_nothing() {
+        let cache = TtlCache::new(Duration::ZERO);
+        cache.put(1);
+        assert_eq!(cache.get(), None);
+
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.put(1);
+        assert_eq!(cache.get(), Some(1));
+        cache.invalidate();
+        assert_eq!(cache.get(), None);
+    }
+}
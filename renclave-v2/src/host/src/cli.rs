@@ -0,0 +1,448 @@
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::{Parser, Subcommand};
+use renclave_shared::{
+    Chain, DeriveAddressRequest, DeriveXpubRequest, GenerateSeedRequest, SecretString,
+    SeedLanguage, SignPsbtRequest, SigningPolicy, ValidateSeedRequest, VerifyAttestationRequest,
+    XpubFormat,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+/// Operator CLI for the renclave-v2 host HTTP API.
+///
+/// Seed phrases and PSBTs are read from files (`-` for stdin) so they never appear in the
+/// process list or shell history.
+#[derive(Parser, Debug)]
+#[command(name = "renclave-cli", version)]
+struct Cli {
+    /// Base URL of the host API
+    #[arg(
+        long,
+        env = "RENCLAVE_HOST_URL",
+        default_value = "http://localhost:3000"
+    )]
+    url: String,
+
+    /// API key sent as a bearer token when the host has authentication enabled
+    #[arg(long, env = "RENCLAVE_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show host readiness, service info and enclave info
+    Status,
+
+    /// Generate a new BIP39 seed phrase in the enclave
+    GenerateSeed {
+        /// Entropy strength in bits (128, 160, 192, 224 or 256)
+        #[arg(long, default_value_t = 256)]
+        strength: u32,
+        /// File containing the BIP39 passphrase
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+        /// Wordlist, e.g. english, japanese, spanish or chinese_simplified
+        #[arg(long, default_value = "english", value_parser = parse_enum::<SeedLanguage>)]
+        language: SeedLanguage,
+        /// Include an enclave attestation committing to the response
+        #[arg(long)]
+        attest: bool,
+        /// Require the attestation to be generated for this request rather than reused
+        #[arg(long, requires = "attest")]
+        fresh_attestation: bool,
+    },
+
+    /// Validate a seed phrase
+    ValidateSeed {
+        /// File containing the seed phrase
+        #[arg(long)]
+        seed_file: PathBuf,
+        /// Wordlist the phrase is checked against
+        #[arg(long, default_value = "english", value_parser = parse_enum::<SeedLanguage>)]
+        language: SeedLanguage,
+        /// File containing the hex entropy from generation, to check the phrase against
+        #[arg(long)]
+        entropy_file: Option<PathBuf>,
+    },
+
+    /// Derive an address from a seed phrase
+    DeriveAddress {
+        #[arg(long)]
+        seed_file: PathBuf,
+        /// BIP32 derivation path, e.g. m/84'/0'/0'/0/0
+        #[arg(long)]
+        path: String,
+        #[arg(long, default_value = "secp256k1")]
+        curve: String,
+        /// Address format: bitcoin, bitcoin_taproot, ethereum, solana or cosmos
+        #[arg(long, value_parser = parse_enum::<Chain>)]
+        chain: Option<Chain>,
+    },
+
+    /// Export an account-level extended public key
+    DeriveXpub {
+        #[arg(long)]
+        seed_file: PathBuf,
+        /// Account-level path, e.g. m/84'/0'/0'
+        #[arg(long)]
+        path: String,
+        /// Serialization: xpub, ypub, zpub or raw
+        #[arg(long, default_value = "xpub", value_parser = parse_enum::<XpubFormat>)]
+        format: XpubFormat,
+    },
+
+    /// Sign a base64 PSBT and print the signed PSBT
+    Sign {
+        #[arg(long)]
+        seed_file: PathBuf,
+        /// File containing the base64-encoded PSBT
+        #[arg(long)]
+        psbt_file: PathBuf,
+    },
+
+    /// Show or replace the enclave signing policy
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
+
+    /// Verify a Nitro attestation document and print the report
+    VerifyAttestation {
+        /// File containing the COSE_Sign1 document, raw or base64
+        #[arg(long)]
+        document_file: PathBuf,
+        /// Hex manifest hash the document's user data must equal
+        #[arg(long)]
+        expected_manifest_hash: Option<String>,
+        /// Hex nonce the document must echo
+        #[arg(long)]
+        expected_nonce: Option<String>,
+        /// Expected PCR value as INDEX=HEX; repeat for several PCRs
+        #[arg(long = "pcr", value_parser = parse_pcr)]
+        pcrs: Vec<(u8, String)>,
+        /// Oldest accepted document age in seconds
+        #[arg(long)]
+        max_age_secs: Option<u64>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PolicyAction {
+    /// Print the current signing policy
+    Get,
+    /// Replace the signing policy with the JSON policy in a file
+    Set {
+        #[arg(long)]
+        file: PathBuf,
+    },
+}
+
+/// Parse a shared enum from its serde (snake_case) name
+fn parse_enum<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(Value::String(value.to_string())).map_err(|e| e.to_string())
+}
+
+/// Parse `INDEX=HEX`
+fn parse_pcr(value: &str) -> Result<(u8, String), String> {
+    let (index, hex) = value
+        .split_once('=')
+        .ok_or_else(|| "expected INDEX=HEX".to_string())?;
+    let index = index
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid PCR index '{}'", index))?;
+    Ok((index, hex.trim().to_string()))
+}
+
+/// Read an attestation document as base64, encoding it if the file holds raw CBOR
+fn read_document(path: &Path) -> Result<String> {
+    let mut bytes = Vec::new();
+    if path == Path::new("-") {
+        std::io::stdin()
+            .read_to_end(&mut bytes)
+            .context("Failed to read stdin")?;
+    } else {
+        bytes =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    }
+    match std::str::from_utf8(&bytes).map(str::trim) {
+        Ok(text) if STANDARD.decode(text).is_ok() => Ok(text.to_string()),
+        _ => Ok(STANDARD.encode(&bytes)),
+    }
+}
+
+/// Read a file, or stdin for `-`
+fn read_raw(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin()).context("Failed to read stdin")
+    } else {
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
+
+/// Read an input file with surrounding whitespace removed
+fn read_input(path: &Path) -> Result<String> {
+    Ok(read_raw(path)?.trim().to_string())
+}
+
+/// Read a secret file, zeroizing the untrimmed copy
+fn read_secret(path: &Path) -> Result<SecretString> {
+    let mut contents = read_raw(path)?;
+    let secret = SecretString::new(contents.trim());
+    contents.zeroize();
+    Ok(secret)
+}
+
+struct ApiClient {
+    base_url: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    async fn get(&self, path: &str) -> Result<Value> {
+        let response = self
+            .authorize(self.http.get(format!("{}{}", self.base_url, path)))
+            .send()
+            .await
+            .with_context(|| format!("GET {} failed", path))?;
+        Self::body(path, response).await
+    }
+
+    async fn post<B: Serialize>(&self, path: &str, body: &B) -> Result<Value> {
+        let response = self
+            .authorize(self.http.post(format!("{}{}", self.base_url, path)))
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("POST {} failed", path))?;
+        Self::body(path, response).await
+    }
+
+    /// JSON body of a successful response; error responses become errors with their message
+    async fn body(path: &str, response: reqwest::Response) -> Result<Value> {
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .with_context(|| format!("{} returned a non-JSON body", path))?;
+
+        if !status.is_success() {
+            let message = body["error"].as_str().unwrap_or("unknown error");
+            return Err(anyhow!("{} returned {}: {}", path, status, message));
+        }
+        Ok(body)
+    }
+}
+
+async fn run(cli: Cli) -> Result<Value> {
+    let client = ApiClient::new(&cli.url, cli.api_key);
+
+    match cli.command {
+        Command::Status => Ok(serde_json::json!({
+            "ready": client.get("/health/ready").await?,
+            "info": client.get("/info").await?,
+            "enclave": client.get("/enclave/info").await?,
+        })),
+        Command::GenerateSeed {
+            strength,
+            passphrase_file,
+            language,
+            attest,
+            fresh_attestation,
+        } => {
+            let passphrase = passphrase_file.as_deref().map(read_secret).transpose()?;
+            let request = GenerateSeedRequest {
+                strength: Some(strength),
+                passphrase,
+                language,
+                attest,
+                fresh_attestation,
+            };
+            client.post("/generate-seed", &request).await
+        }
+        Command::ValidateSeed {
+            seed_file,
+            language,
+            entropy_file,
+        } => {
+            let request = ValidateSeedRequest {
+                seed_phrase: read_secret(&seed_file)?,
+                language,
+                entropy: entropy_file.as_deref().map(read_secret).transpose()?,
+            };
+            client.post("/validate-seed", &request).await
+        }
+        Command::DeriveAddress {
+            seed_file,
+            path,
+            curve,
+            chain,
+        } => {
+            let request = DeriveAddressRequest {
+                seed_phrase: read_secret(&seed_file)?,
+                path,
+                curve,
+                chain,
+            };
+            client.post("/derive-address", &request).await
+        }
+        Command::DeriveXpub {
+            seed_file,
+            path,
+            format,
+        } => {
+            let request = DeriveXpubRequest {
+                seed_phrase: read_secret(&seed_file)?,
+                path,
+                format,
+            };
+            client.post("/derive-xpub", &request).await
+        }
+        Command::Sign {
+            seed_file,
+            psbt_file,
+        } => {
+            let request = SignPsbtRequest {
+                seed_phrase: read_secret(&seed_file)?,
+                psbt: read_input(&psbt_file)?,
+            };
+            client.post("/enclave/sign-psbt", &request).await
+        }
+        Command::Policy { action } => match action {
+            PolicyAction::Get => client.get("/enclave/policy").await,
+            PolicyAction::Set { file } => {
+                let policy: SigningPolicy = serde_json::from_str(&read_input(&file)?)
+                    .with_context(|| format!("Invalid policy in {}", file.display()))?;
+                client.post("/enclave/policy", &policy).await
+            }
+        },
+        Command::VerifyAttestation {
+            document_file,
+            expected_manifest_hash,
+            expected_nonce,
+            pcrs,
+            max_age_secs,
+        } => {
+            let request = VerifyAttestationRequest {
+                document: read_document(&document_file)?,
+                expected_manifest_hash,
+                expected_nonce,
+                expected_pcrs: pcrs.into_iter().collect::<BTreeMap<_, _>>(),
+                max_age_secs,
+            };
+            client.post("/attestation/verify", &request).await
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    match run(cli).await {
+        Ok(output) => match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parses_shared_enums() {
+        let cli = Cli::try_parse_from([
+            "renclave-cli",
+            "derive-xpub",
+            "--seed-file",
+            "seed.txt",
+            "--path",
+            "m/84'/0'/0'",
+            "--format",
+            "zpub",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::DeriveXpub {
+                format: XpubFormat::Zpub,
+                ..
+            }
+        ));
+
+        assert!(Cli::try_parse_from([
+            "renclave-cli",
+            "derive-address",
+            "--seed-file",
+            "seed.txt",
+            "--path",
+            "m/0",
+            "--chain",
+            "dogecoin",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parses_expected_pcrs() {
+        let cli = Cli::try_parse_from([
+            "renclave-cli",
+            "verify-attestation",
+            "--document-file",
+            "doc.cbor",
+            "--pcr",
+            "0=aa",
+            "--pcr",
+            "8 = bb",
+        ])
+        .unwrap();
+        let Command::VerifyAttestation { pcrs, .. } = cli.command else {
+            panic!("wrong command");
+        };
+        assert_eq!(pcrs, [(0, "aa".to_string()), (8, "bb".to_string())]);
+
+        assert!(parse_pcr("aa").is_err());
+        assert!(parse_pcr("256=aa").is_err());
+    }
+}
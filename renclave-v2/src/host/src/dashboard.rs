@@ -0,0 +1,101 @@
+use axum::{
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+/// Environment variable that serves the admin dashboard at `/admin/dashboard`
+pub const DASHBOARD_ENV: &str = "RENCLAVE_ADMIN_DASHBOARD";
+
+const INDEX_HTML: &str = include_str!("../assets/dashboard.html");
+const DASHBOARD_JS: &str = include_str!("../assets/dashboard.js");
+const DASHBOARD_CSS: &str = include_str!("../assets/dashboard.css");
+
+/// The assets load nothing but themselves and call only this host's API
+const CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; script-src 'self'; style-src 'self'; connect-src 'self'; frame-ancestors 'none'";
+
+/// Whether the dashboard is enabled, off by default
+pub fn enabled_from_env() -> bool {
+    std::env::var(DASHBOARD_ENV)
+        .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Static dashboard assets compiled into the binary.
+///
+/// The pages hold no data and need no scope. The browser reads enclave, network and metrics
+/// state from the public JSON routes, and the supervised process state with an API key the
+/// operator enters, which stays in session storage.
+pub fn routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route(
+            "/admin/dashboard",
+            get(|| async { asset("text/html; charset=utf-8", INDEX_HTML) }),
+        )
+        .route(
+            "/admin/dashboard/dashboard.js",
+            get(|| async { asset("text/javascript; charset=utf-8", DASHBOARD_JS) }),
+        )
+        .route(
+            "/admin/dashboard/dashboard.css",
+            get(|| async { asset("text/css; charset=utf-8", DASHBOARD_CSS) }),
+        )
+}
+
+fn asset(content_type: &'static str, body: &'static str) -> Response {
+    let mut response = body.into_response();
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    headers.insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static(CONTENT_SECURITY_POLICY),
+    );
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_assets_are_served_with_csp() {
+        for (path, content_type) in [
+            ("/admin/dashboard", "text/html"),
+            ("/admin/dashboard/dashboard.js", "text/javascript"),
+            ("/admin/dashboard/dashboard.css", "text/css"),
+        ] {
+            let response = routes::<()>()
+                .oneshot(Request::get(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", path);
+            let headers = response.headers();
+            assert!(headers[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with(content_type));
+            assert_eq!(
+                headers[header::CONTENT_SECURITY_POLICY],
+                CONTENT_SECURITY_POLICY
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_loads_served_assets() {
+        assert!(INDEX_HTML.contains("src=\"/admin/dashboard/dashboard.js\""));
+        assert!(INDEX_HTML.contains("href=\"/admin/dashboard/dashboard.css\""));
+        // No inline script, which the CSP would block
+        assert_eq!(INDEX_HTML.matches("<script").count(), 1);
+    }
+}
@@ -1,21 +1,55 @@
 use anyhow::{anyhow, Context, Result};
+use axum::async_trait;
 use log::{debug, info, warn};
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{Context as TraceContext, KeyValue};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
 use tokio::time::{sleep, timeout};
 
-use renclave_shared::{EnclaveOperation, EnclaveRequest, EnclaveResponse};
+use crate::retry::CircuitBreaker;
+use crate::runtime_config::RuntimeSettings;
+use renclave_shared::{
+    telemetry, Chain, EnclaveOperation, EnclaveRequest, EnclaveResponse, EnclaveResult,
+    SealedPayload, SecretString, SeedLanguage, SigningPolicy, ThresholdMessage, XpubFormat,
+};
 
 /// Client for communicating with the Nitro Enclave
 pub struct EnclaveClient {
     socket_path: String,
+    settings: Arc<RuntimeSettings>,
+    circuit_breaker: CircuitBreaker,
 }
 
 impl EnclaveClient {
-    /// Create new enclave client
+    /// Create new enclave client with retry, timeout and circuit breaker settings from the
+    /// environment
     pub fn new(socket_path: String) -> Self {
-        Self { socket_path }
+        Self::with_policies(
+            socket_path,
+            Arc::new(RuntimeSettings::from_env()),
+            CircuitBreaker::from_env(),
+        )
+    }
+
+    /// Create new enclave client with explicit settings
+    pub fn with_policies(
+        socket_path: String,
+        settings: Arc<RuntimeSettings>,
+        circuit_breaker: CircuitBreaker,
+    ) -> Self {
+        Self {
+            socket_path,
+            settings,
+            circuit_breaker,
+        }
+    }
+
+    /// Retry and timeout settings, which can be changed while the client is in use
+    pub fn settings(&self) -> Arc<RuntimeSettings> {
+        Arc::clone(&self.settings)
     }
 
     /// Wait for enclave to become available
@@ -61,30 +95,15 @@ impl EnclaveClient {
         Ok(())
     }
 
-    /// Send request to enclave and get response
-    pub async fn send_request(&self, operation: EnclaveOperation) -> Result<EnclaveResponse> {
-        let request = EnclaveRequest::new(operation);
-        debug!("📤 Sending request to enclave: {}", request.id);
-
-        // Connect to enclave with timeout
-        let stream = timeout(
+    /// Connect to the enclave socket with timeout
+    async fn connect(&self) -> Result<UnixStream> {
+        timeout(
             Duration::from_secs(5),
             UnixStream::connect(&self.socket_path),
         )
         .await
         .context("Timeout connecting to enclave")?
-        .context("Failed to connect to enclave socket")?;
-
-        // Send request with timeout
-        let response = timeout(
-            Duration::from_secs(30),
-            self.send_request_internal(stream, request),
-        )
-        .await
-        .context("Timeout waiting for enclave response")??;
-
-        debug!("📨 Received response from enclave: {}", response.id);
-        Ok(response)
+        .context("Failed to connect to enclave socket")
     }
 
     /// Internal method to send request and receive response
@@ -94,11 +113,13 @@ impl EnclaveClient {
         request: EnclaveRequest,
     ) -> Result<EnclaveResponse> {
         // Serialize and send request
-        let request_json =
-            serde_json::to_string(&request).context("Failed to serialize request")?;
+        // Frames carry seed phrases, so both directions are zeroized after use
+        let request_json = SecretString::new(
+            serde_json::to_string(&request).context("Failed to serialize request")?,
+        );
 
         stream
-            .write_all(request_json.as_bytes())
+            .write_all(request_json.expose_secret().as_bytes())
             .await
             .context("Failed to write request to socket")?;
         stream
@@ -116,26 +137,117 @@ impl EnclaveClient {
             .read_line(&mut response_line)
             .await
             .context("Failed to read response from enclave")?;
+        let response_line = SecretString::new(response_line);
 
-        if response_line.trim().is_empty() {
+        if response_line.expose_secret().trim().is_empty() {
             return Err(anyhow!("Received empty response from enclave"));
         }
 
-        debug!("📥 Raw response from enclave: {}", response_line.trim());
+        debug!(
+            "📥 Raw response from enclave ({} bytes)",
+            response_line.expose_secret().len()
+        );
 
         // Deserialize response
-        let response: EnclaveResponse = serde_json::from_str(response_line.trim())
+        let response: EnclaveResponse = serde_json::from_str(response_line.expose_secret().trim())
             .context("Failed to deserialize response from enclave")?;
 
         debug!("✅ Response deserialized successfully");
         Ok(response)
     }
 
+    /// Connect, with retries, then send `request` and wait for the response
+    async fn round_trip(
+        &self,
+        request: EnclaveRequest,
+        cx: &TraceContext,
+    ) -> Result<EnclaveResponse> {
+        debug!("📤 Sending request to enclave: {}", request.id);
+        let retry_policy = self.settings.retry_policy();
+
+        let mut attempt = 0;
+        let stream = loop {
+            attempt += 1;
+
+            if !self.circuit_breaker.allow_request() {
+                return Err(anyhow!(
+                    "Enclave circuit breaker open, not sending request {}",
+                    request.id
+                ));
+            }
+
+            match self.connect().await {
+                Ok(stream) => {
+                    cx.span().add_event(
+                        "connected",
+                        vec![KeyValue::new(
+                            "enclave.connect_attempts",
+                            i64::from(attempt),
+                        )],
+                    );
+                    break stream;
+                }
+                Err(e) => {
+                    self.circuit_breaker.record_failure();
+
+                    if attempt >= retry_policy.max_attempts {
+                        return Err(
+                            e.context(format!("Enclave unreachable after {} attempts", attempt))
+                        );
+                    }
+
+                    let delay = retry_policy.jittered_backoff(attempt);
+                    warn!(
+                        "⚠️  Enclave connection attempt {} failed: {:#}; retrying in {:?}",
+                        attempt, e, delay
+                    );
+                    sleep(delay).await;
+                }
+            }
+        };
+
+        // Send request with timeout
+        let result = timeout(
+            self.settings.enclave_timeout(),
+            self.send_request_internal(stream, request),
+        )
+        .await
+        .context("Timeout waiting for enclave response")
+        .and_then(|response| response);
+
+        match &result {
+            Ok(response) => {
+                self.circuit_breaker.record_success();
+                debug!("📨 Received response from enclave: {}", response.id);
+            }
+            Err(_) => self.circuit_breaker.record_failure(),
+        }
+
+        result
+    }
+}
+
+/// Operations the host performs against the enclave.
+///
+/// Implemented by [`EnclaveClient`] over the enclave socket and by
+/// `MockEnclave` in memory for tests (built with the `mock-enclave` feature). The typed
+/// operations are built on `send_request`.
+#[async_trait]
+pub trait EnclaveApi: Send + Sync {
+    /// Send an operation to the enclave and return its response
+    async fn send_request(&self, operation: EnclaveOperation) -> Result<EnclaveResponse>;
+
+    /// Check enclave health
+    async fn health_check(&self) -> Result<bool>;
+
     /// Generate seed phrase via enclave
-    pub async fn generate_seed(
+    async fn generate_seed(
         &self,
         strength: u32,
-        passphrase: Option<String>,
+        passphrase: Option<SecretString>,
+        language: SeedLanguage,
+        attest: bool,
+        fresh_attestation: bool,
     ) -> Result<EnclaveResponse> {
         info!(
             "🔑 Requesting seed generation (strength: {} bits)",
@@ -145,32 +257,62 @@ impl EnclaveClient {
         let operation = EnclaveOperation::GenerateSeed {
             strength,
             passphrase,
+            language,
+            attest,
+            fresh_attestation,
         };
         self.send_request(operation).await
     }
 
     /// Validate seed phrase via enclave
-    pub async fn validate_seed(&self, seed_phrase: String) -> Result<EnclaveResponse> {
+    async fn validate_seed(
+        &self,
+        seed_phrase: SecretString,
+        language: SeedLanguage,
+        entropy: Option<SecretString>,
+    ) -> Result<EnclaveResponse> {
         info!("🔍 Requesting seed validation");
 
-        let operation = EnclaveOperation::ValidateSeed { seed_phrase };
+        let operation = EnclaveOperation::ValidateSeed {
+            seed_phrase,
+            language,
+            entropy,
+        };
         self.send_request(operation).await
     }
 
     /// Get enclave information
-    pub async fn get_info(&self) -> Result<EnclaveResponse> {
+    async fn get_info(&self) -> Result<EnclaveResponse> {
         debug!("ℹ️  Requesting enclave information");
 
         let operation = EnclaveOperation::GetInfo;
         self.send_request(operation).await
     }
 
+    /// Get signed metrics snapshot from enclave
+    async fn get_metrics_snapshot(&self) -> Result<EnclaveResponse> {
+        debug!("📊 Requesting signed metrics snapshot");
+
+        let operation = EnclaveOperation::GetMetricsSnapshot;
+        self.send_request(operation).await
+    }
+
+    /// Re-run network initialization inside the enclave
+    async fn repair_network(&self) -> Result<EnclaveResponse> {
+        info!("🔧 Requesting enclave network repair");
+
+        let operation = EnclaveOperation::RepairNetwork;
+        self.send_request(operation).await
+    }
+
     /// Derive key from seed phrase via enclave
-    pub async fn derive_key(
+    async fn derive_key(
         &self,
-        seed_phrase: String,
+        seed_phrase: SecretString,
         path: String,
         curve: String,
+        attest: bool,
+        fresh_attestation: bool,
     ) -> Result<EnclaveResponse> {
         info!(
             "🔑 Requesting key derivation (path: {}, curve: {})",
@@ -181,16 +323,19 @@ impl EnclaveClient {
             seed_phrase,
             path,
             curve,
+            attest,
+            fresh_attestation,
         };
         self.send_request(operation).await
     }
 
     /// Derive address from seed phrase via enclave
-    pub async fn derive_address(
+    async fn derive_address(
         &self,
-        seed_phrase: String,
+        seed_phrase: SecretString,
         path: String,
         curve: String,
+        chain: Option<Chain>,
     ) -> Result<EnclaveResponse> {
         info!(
             "📍 Requesting address derivation (path: {}, curve: {})",
@@ -201,12 +346,210 @@ impl EnclaveClient {
             seed_phrase,
             path,
             curve,
+            chain,
+        };
+        self.send_request(operation).await
+    }
+
+    /// Derive extended public key from seed phrase via enclave
+    async fn derive_xpub(
+        &self,
+        seed_phrase: SecretString,
+        path: String,
+        format: XpubFormat,
+    ) -> Result<EnclaveResponse> {
+        info!(
+            "🧾 Requesting extended public key (path: {}, format: {:?})",
+            path, format
+        );
+
+        let operation = EnclaveOperation::DeriveXpub {
+            seed_phrase,
+            path,
+            format,
+        };
+        self.send_request(operation).await
+    }
+
+    /// Sign a base64 PSBT via enclave
+    async fn sign_psbt(&self, seed_phrase: SecretString, psbt: String) -> Result<EnclaveResponse> {
+        info!("✍️  Requesting PSBT signing ({} bytes)", psbt.len());
+
+        let operation = EnclaveOperation::SignPsbt { seed_phrase, psbt };
+        self.send_request(operation).await
+    }
+
+    /// Get the enclave signing policy
+    async fn get_policy(&self) -> Result<EnclaveResponse> {
+        debug!("📜 Requesting signing policy");
+
+        let operation = EnclaveOperation::GetPolicy;
+        self.send_request(operation).await
+    }
+
+    /// Get signature counts and ages of the enclave's signing keys
+    async fn get_key_usage(&self) -> Result<EnclaveResponse> {
+        debug!("🔑 Requesting key usage");
+
+        let operation = EnclaveOperation::GetKeyUsage;
+        self.send_request(operation).await
+    }
+
+    /// Replace the enclave signing policy
+    async fn set_policy(&self, policy: SigningPolicy) -> Result<EnclaveResponse> {
+        info!("📜 Requesting signing policy update");
+
+        let operation = EnclaveOperation::SetPolicy { policy };
+        self.send_request(operation).await
+    }
+
+    /// Start an encrypted channel handshake with the client's ephemeral public key
+    async fn open_channel(
+        &self,
+        client_public_key: String,
+        nonce: Option<String>,
+    ) -> Result<EnclaveResponse> {
+        info!("🔐 Requesting encrypted channel");
+
+        let operation = EnclaveOperation::OpenChannel {
+            client_public_key,
+            nonce,
+        };
+        self.send_request(operation).await
+    }
+
+    /// Forward an encrypted request on an open channel
+    async fn sealed_request(
+        &self,
+        session_id: String,
+        payload: SealedPayload,
+    ) -> Result<EnclaveResponse> {
+        debug!("🔐 Forwarding sealed request on channel {}", session_id);
+
+        let operation = EnclaveOperation::SealedRequest {
+            session_id,
+            payload,
+        };
+        self.send_request(operation).await
+    }
+
+    /// Verify a signature against a public key in the enclave
+    async fn verify_signature(
+        &self,
+        curve: String,
+        public_key: String,
+        message: String,
+        signature: String,
+    ) -> Result<EnclaveResponse> {
+        debug!("🔍 Requesting {} signature verification", curve);
+
+        let operation = EnclaveOperation::VerifySignature {
+            curve,
+            public_key,
+            message,
+            signature,
         };
         self.send_request(operation).await
     }
 
+    /// Check a sealed request against its channel without decrypting it
+    async fn verify_ciphertext(
+        &self,
+        session_id: String,
+        payload: SealedPayload,
+    ) -> Result<EnclaveResponse> {
+        debug!(
+            "🔍 Requesting verification of sealed payload on channel {}",
+            session_id
+        );
+
+        let operation = EnclaveOperation::VerifyCiphertext {
+            session_id,
+            payload,
+        };
+        self.send_request(operation).await
+    }
+
+    /// Change the enclave's log level, or read it when `level` is `None`
+    async fn set_log_level(&self, level: Option<String>) -> Result<EnclaveResponse> {
+        debug!("📝 Requesting enclave log level change: {:?}", level);
+
+        let operation = EnclaveOperation::SetLogLevel { level };
+        self.send_request(operation).await
+    }
+
+    /// Certificate signing request for the enclave's TLS key, naming `domains`
+    async fn create_tls_csr(&self, domains: Vec<String>) -> Result<EnclaveResponse> {
+        debug!("🔐 Requesting TLS certificate request for {:?}", domains);
+
+        let operation = EnclaveOperation::CreateTlsCsr { domains };
+        self.send_request(operation).await
+    }
+
+    /// Have the enclave serve the PEM `certificate_chain`, leaf first
+    async fn install_tls_certificate(&self, certificate_chain: String) -> Result<EnclaveResponse> {
+        debug!("🔐 Installing TLS certificate in the enclave");
+
+        let operation = EnclaveOperation::InstallTlsCertificate { certificate_chain };
+        self.send_request(operation).await
+    }
+
+    /// Offer the host's wall clock, Unix milliseconds, to the enclave's clock
+    async fn sync_time(&self, unix_ms: u64) -> Result<EnclaveResponse> {
+        debug!("🕒 Offering host time {} to the enclave", unix_ms);
+
+        let operation = EnclaveOperation::SyncTime { unix_ms };
+        self.send_request(operation).await
+    }
+
+    /// Send a round message of a threshold signing session
+    async fn threshold_signing(&self, message: ThresholdMessage) -> Result<EnclaveResponse> {
+        debug!("✍️  Sending threshold signing message");
+
+        let operation = EnclaveOperation::ThresholdSigning { message };
+        self.send_request(operation).await
+    }
+}
+
+#[async_trait]
+impl EnclaveApi for EnclaveClient {
+    /// Send request to enclave and get response.
+    ///
+    /// Connection failures are retried with jittered exponential backoff. Once the request
+    /// has been written it is never resent, so operations are not executed twice. While the
+    /// circuit breaker is open requests fail fast.
+    ///
+    /// Each request is a client span, child of the current context, whose trace context
+    /// travels with the request so the enclave's span joins the trace.
+    async fn send_request(&self, operation: EnclaveOperation) -> Result<EnclaveResponse> {
+        let tracer = telemetry::tracer();
+        let span = tracer
+            .span_builder(format!("enclave {}", operation.name()))
+            .with_kind(SpanKind::Client)
+            .with_attributes([KeyValue::new("enclave.operation", operation.name())])
+            .start(&tracer);
+        let cx = TraceContext::current_with_span(span);
+
+        let mut request = EnclaveRequest::new(operation);
+        request.trace_context = telemetry::inject(&cx);
+        let result = self.round_trip(request, &cx).await;
+
+        let span = cx.span();
+        match &result {
+            Ok(EnclaveResponse {
+                result: EnclaveResult::Error { code, .. },
+                ..
+            }) => span.set_status(Status::error(code.as_str())),
+            Ok(_) => {}
+            Err(e) => span.set_status(Status::error(format!("{:#}", e))),
+        }
+        span.end();
+
+        result
+    }
+
     /// Check enclave health
-    pub async fn health_check(&self) -> Result<bool> {
+    async fn health_check(&self) -> Result<bool> {
         debug!("🏥 Performing enclave health check");
 
         match self.test_connection().await {
@@ -225,7 +568,7 @@ impl EnclaveClient {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::time::sleep;
+    use crate::retry::RetryPolicy;
 
     #[tokio::test]
     async fn test_enclave_client_creation() {
@@ -233,6 +576,94 @@ mod tests {
         assert_eq!(client.socket_path, "/tmp/test_enclave.sock");
     }
 
+    #[tokio::test]
+    async fn test_send_request_retries_then_opens_breaker() {
+        let client = EnclaveClient::with_policies(
+            "/tmp/nonexistent_enclave.sock".to_string(),
+            Arc::new(RuntimeSettings::new(
+                RetryPolicy {
+                    max_attempts: 3,
+                    initial_backoff: Duration::from_millis(1),
+                    max_backoff: Duration::from_millis(5),
+                    multiplier: 2.0,
+                },
+                Duration::from_secs(30),
+            )),
+            CircuitBreaker::new(3, Duration::from_secs(60)),
+        );
+
+        let error = client
+            .send_request(EnclaveOperation::GetInfo)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("after 3 attempts"));
+
+        let error = client
+            .send_request(EnclaveOperation::GetInfo)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("circuit breaker open"));
+    }
+
+    #[tokio::test]
+    async fn test_largest_psbt_body_fits_in_enclave_frame() {
+        use crate::limits::BodyLimits;
+        use renclave_shared::{SignPsbtRequest, DEFAULT_MAX_FRAME_BYTES};
+        use tokio::io::AsyncReadExt;
+        use tokio::net::UnixListener;
+
+        let socket_path =
+            std::env::temp_dir().join(format!("renclave-frame-{}", uuid::Uuid::new_v4()));
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        // Reads the way the enclave does: one line of at most the default frame limit
+        let enclave = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut frame = String::new();
+            (&mut reader)
+                .take(DEFAULT_MAX_FRAME_BYTES as u64 + 1)
+                .read_line(&mut frame)
+                .await
+                .unwrap();
+            assert!(frame.ends_with('\n'), "frame exceeds the enclave limit");
+
+            let request: EnclaveRequest = serde_json::from_str(frame.trim()).unwrap();
+            let response = EnclaveResponse {
+                id: request.id,
+                result: EnclaveResult::PsbtSigned {
+                    psbt: String::new(),
+                    signed_inputs: Vec::new(),
+                    retiring_keys: Vec::new(),
+                },
+            };
+            let mut stream = reader.into_inner();
+            let mut line = serde_json::to_string(&response).unwrap();
+            line.push('\n');
+            stream.write_all(line.as_bytes()).await.unwrap();
+            frame.len()
+        });
+
+        // A body one byte under the host's PSBT limit
+        let limit = BodyLimits::default().max_psbt_body_bytes;
+        let prefix = r#"{"seed_phrase":"abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about","psbt":""#;
+        let padding = "A".repeat(limit - 1 - prefix.len() - 2);
+        let body = format!("{}{}\"}}", prefix, padding);
+        assert_eq!(body.len(), limit - 1);
+        let request: SignPsbtRequest = serde_json::from_str(&body).unwrap();
+
+        let client = EnclaveClient::new(socket_path.to_string_lossy().into_owned());
+        let response = client
+            .sign_psbt(request.seed_phrase, request.psbt)
+            .await
+            .unwrap();
+        assert!(matches!(response.result, EnclaveResult::PsbtSigned { .. }));
+
+        let frame_len = enclave.await.unwrap();
+        assert!(frame_len > body.len());
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
     #[tokio::test]
     async fn test_wait_for_enclave_timeout() {
         let client = EnclaveClient::new("/tmp/nonexistent_enclave.sock".to_string());
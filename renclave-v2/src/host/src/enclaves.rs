@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::enclave_client::EnclaveApi;
+use crate::session::EnclaveSession;
+use renclave_shared::validation::NamespaceName;
+use renclave_shared::EnclaveStatus;
+
+/// Environment variable listing further enclaves as `name=socket_path`, comma separated
+pub const ENCLAVES_ENV: &str = "RENCLAVE_ENCLAVES";
+
+/// Name of the enclave at `RENCLAVE_ENCLAVE_SOCKET`, which also serves the unprefixed routes
+pub const DEFAULT_ENCLAVE_NAME: &str = "default";
+
+/// An enclave socket the host serves under `/enclaves/{name}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnclaveTarget {
+    pub name: String,
+    pub socket_path: String,
+}
+
+/// Parse `name=socket_path` pairs; names must be unique, URL-safe and not `default`
+pub fn parse_targets(value: &str) -> Result<Vec<EnclaveTarget>, String> {
+    let mut names = HashSet::new();
+    let mut targets = Vec::new();
+
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, socket_path) = entry
+            .split_once('=')
+            .map(|(name, path)| (name.trim(), path.trim()))
+            .ok_or_else(|| format!("Expected name=socket_path, got '{}'", entry))?;
+
+        NamespaceName::new("name", name).map_err(|e| format!("Invalid enclave {}", e))?;
+        if name == DEFAULT_ENCLAVE_NAME {
+            return Err(format!(
+                "'{}' names the enclave at {}",
+                DEFAULT_ENCLAVE_NAME,
+                renclave_shared::ENCLAVE_SOCKET_ENV
+            ));
+        }
+        if socket_path.is_empty() {
+            return Err(format!("Enclave '{}' has no socket path", name));
+        }
+        if !names.insert(name.to_string()) {
+            return Err(format!("Enclave '{}' is listed twice", name));
+        }
+
+        targets.push(EnclaveTarget {
+            name: name.to_string(),
+            socket_path: socket_path.to_string(),
+        });
+    }
+
+    Ok(targets)
+}
+
+/// Further enclaves from the environment, none if unset
+pub fn targets_from_env() -> anyhow::Result<Vec<EnclaveTarget>> {
+    match std::env::var(ENCLAVES_ENV) {
+        Ok(value) => parse_targets(&value).map_err(|e| anyhow::anyhow!("{}: {}", ENCLAVES_ENV, e)),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// An enclave the host serves, with its own client and session
+#[derive(Clone)]
+pub struct NamedEnclave {
+    pub target: EnclaveTarget,
+    pub client: Arc<dyn EnclaveApi>,
+    pub session: Arc<EnclaveSession>,
+}
+
+impl NamedEnclave {
+    /// Current reachability and session of the enclave
+    pub async fn status(&self) -> EnclaveStatus {
+        let available = self.client.health_check().await.unwrap_or(false);
+        let session = self.session.current();
+        EnclaveStatus {
+            name: self.target.name.clone(),
+            socket_path: self.target.socket_path.clone(),
+            available,
+            enclave_id: session.as_ref().map(|s| s.enclave_id.clone()),
+            epoch: session.map(|s| s.epoch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_targets() {
+        let targets =
+            parse_targets(" signer=/run/signer.sock, backup-1=/run/backup.sock ,").unwrap();
+        assert_eq!(
+            targets,
+            vec![
+                EnclaveTarget {
+                    name: "signer".to_string(),
+                    socket_path: "/run/signer.sock".to_string(),
+                },
+                EnclaveTarget {
+                    name: "backup-1".to_string(),
+                    socket_path: "/run/backup.sock".to_string(),
+                },
+            ]
+        );
+        assert!(parse_targets("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_targets_rejects_invalid() {
+        for value in [
+            "signer",
+            "sign/er=/run/signer.sock",
+            "default=/run/other.sock",
+            "signer=",
+            "a=/run/a.sock,a=/run/b.sock",
+        ] {
+            assert!(parse_targets(value).is_err(), "accepted {}", value);
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use axum::{
+    body::Bytes,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use renclave_shared::{
+    ApiErrorCode, ErrorResponse, FieldError, NetworkState, RenclaveErrorCode, ValidationErrors,
+};
+use serde::de::DeserializeOwned;
+
+use crate::limits::{exceeds_json_depth, MAX_JSON_DEPTH};
+
+/// Typed error returned by every API handler
+#[derive(Debug)]
+pub enum ApiError {
+    /// Request was malformed or failed validation
+    InvalidRequest {
+        message: String,
+        request_id: Option<String>,
+        /// Fields that failed validation, empty for malformed bodies
+        details: Vec<FieldError>,
+    },
+    /// Enclave returned an error result for the operation
+    EnclaveOperationFailed {
+        message: String,
+        code: RenclaveErrorCode,
+        request_id: Option<String>,
+    },
+    /// Enclave returned a result of the wrong type
+    UnexpectedEnclaveResponse { request_id: Option<String> },
+    /// Enclave socket could not be reached or timed out
+    EnclaveUnavailable {
+        message: String,
+        request_id: Option<String>,
+    },
+    /// Operation needs the network, which is in degraded mode
+    NetworkUnavailable { state: NetworkState },
+    /// Request body exceeded the route's size limit
+    PayloadTooLarge { message: String },
+    /// Route needs an API key and none or an unknown one was sent
+    Unauthorized,
+    /// API identity lacks the route's scope, or identities cannot be managed
+    Forbidden { message: String },
+    /// Addressed resource does not exist
+    NotFound { message: String },
+    /// Host storage could not be read or written
+    StorageFailed { message: String },
+}
+
+impl ApiError {
+    pub fn invalid_request(message: impl Into<String>, request_id: Option<String>) -> Self {
+        ApiError::InvalidRequest {
+            message: message.into(),
+            request_id,
+            details: Vec::new(),
+        }
+    }
+
+    /// Request whose fields failed validation, listing each field
+    pub fn invalid_fields(errors: ValidationErrors, request_id: Option<String>) -> Self {
+        ApiError::InvalidRequest {
+            message: format!("Invalid request: {}", errors),
+            request_id,
+            details: errors.into_errors(),
+        }
+    }
+
+    pub fn enclave_operation_failed(
+        message: impl Into<String>,
+        code: RenclaveErrorCode,
+        request_id: Option<String>,
+    ) -> Self {
+        ApiError::EnclaveOperationFailed {
+            message: message.into(),
+            code,
+            request_id,
+        }
+    }
+
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        ApiError::PayloadTooLarge {
+            message: message.into(),
+        }
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::Forbidden {
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    pub fn storage_failed(error: impl std::fmt::Display) -> Self {
+        ApiError::StorageFailed {
+            message: format!("Storage failed: {}", error),
+        }
+    }
+
+    pub fn unexpected_enclave_response(request_id: Option<String>) -> Self {
+        ApiError::UnexpectedEnclaveResponse { request_id }
+    }
+
+    pub fn enclave_unavailable(error: impl std::fmt::Display, request_id: Option<String>) -> Self {
+        ApiError::EnclaveUnavailable {
+            message: format!("Enclave communication failed: {}", error),
+            request_id,
+        }
+    }
+
+    /// Machine-readable error code
+    pub fn error_code(&self) -> ApiErrorCode {
+        match self {
+            ApiError::InvalidRequest { .. } => ApiErrorCode::InvalidRequest,
+            ApiError::EnclaveOperationFailed { .. } => ApiErrorCode::EnclaveOperationFailed,
+            ApiError::UnexpectedEnclaveResponse { .. } => ApiErrorCode::UnexpectedEnclaveResponse,
+            ApiError::EnclaveUnavailable { .. } => ApiErrorCode::EnclaveUnavailable,
+            ApiError::NetworkUnavailable { .. } => ApiErrorCode::NetworkUnavailable,
+            ApiError::PayloadTooLarge { .. } => ApiErrorCode::PayloadTooLarge,
+            ApiError::Unauthorized => ApiErrorCode::Unauthorized,
+            ApiError::Forbidden { .. } => ApiErrorCode::Forbidden,
+            ApiError::NotFound { .. } => ApiErrorCode::NotFound,
+            ApiError::StorageFailed { .. } => ApiErrorCode::StorageFailed,
+        }
+    }
+
+    /// HTTP status code for the error
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidRequest { .. } => StatusCode::BAD_REQUEST,
+            ApiError::EnclaveOperationFailed { code, .. } => enclave_error_status(*code),
+            ApiError::UnexpectedEnclaveResponse { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::EnclaveUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::NetworkUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            ApiError::NotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::StorageFailed { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// JSON body for the error
+    pub fn to_response(&self) -> ErrorResponse {
+        let mut enclave_error = None;
+        let mut details = Vec::new();
+        let (error, code, request_id) = match self {
+            ApiError::InvalidRequest {
+                message,
+                request_id,
+                details: fields,
+            } => {
+                details = fields.clone();
+                (message.clone(), 400, request_id.clone())
+            }
+            ApiError::EnclaveOperationFailed {
+                message,
+                code,
+                request_id,
+            } => {
+                enclave_error = Some(*code);
+                (
+                    message.clone(),
+                    u32::from(self.status().as_u16()),
+                    request_id.clone(),
+                )
+            }
+            ApiError::UnexpectedEnclaveResponse { request_id } => (
+                "Unexpected response from enclave".to_string(),
+                500,
+                request_id.clone(),
+            ),
+            ApiError::EnclaveUnavailable {
+                message,
+                request_id,
+            } => (message.clone(), 503, request_id.clone()),
+            ApiError::NetworkUnavailable { state } => {
+                let reason = match state {
+                    NetworkState::NetworkUnavailable { reason, .. } => reason.as_str(),
+                    _ => "network is not initialized",
+                };
+                (format!("Network unavailable: {}", reason), 503, None)
+            }
+            ApiError::PayloadTooLarge { message } => (message.clone(), 413, None),
+            ApiError::Unauthorized => ("Missing or unknown API key".to_string(), 401, None),
+            ApiError::Forbidden { message } => (message.clone(), 403, None),
+            ApiError::NotFound { message } => (message.clone(), 404, None),
+            ApiError::StorageFailed { message } => (message.clone(), 500, None),
+        };
+
+        ErrorResponse {
+            error,
+            code,
+            error_code: self.error_code(),
+            request_id,
+            enclave_error,
+            details,
+        }
+    }
+}
+
+/// HTTP status for an enclave error code
+fn enclave_error_status(code: RenclaveErrorCode) -> StatusCode {
+    match code {
+        RenclaveErrorCode::InvalidRequest => StatusCode::BAD_REQUEST,
+        RenclaveErrorCode::NotFound => StatusCode::NOT_FOUND,
+        RenclaveErrorCode::PolicyDenied => StatusCode::FORBIDDEN,
+        RenclaveErrorCode::NotProvisioned => StatusCode::CONFLICT,
+        RenclaveErrorCode::UnsupportedOperation => StatusCode::NOT_IMPLEMENTED,
+        RenclaveErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        RenclaveErrorCode::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        RenclaveErrorCode::CryptoFailure
+        | RenclaveErrorCode::StorageFailure
+        | RenclaveErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status(), Json(self.to_response())).into_response();
+        if matches!(self, ApiError::Unauthorized) {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        response
+    }
+}
+
+/// JSON extractor that reports malformed bodies as typed `ApiError`s.
+///
+/// Bodies over the route's `DefaultBodyLimit` are rejected with `PayloadTooLarge`, and bodies
+/// nested deeper than `MAX_JSON_DEPTH` are rejected before deserialization.
+pub struct ApiJson<T>(pub T);
+
+#[axum::async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_json_content_type(req.headers()) {
+            return Err(ApiError::invalid_request(
+                "Invalid JSON body: Expected request with `Content-Type: application/json`",
+                None,
+            ));
+        }
+
+        let bytes = Bytes::from_request(req, state).await.map_err(|rejection| {
+            match rejection.status() {
+                StatusCode::PAYLOAD_TOO_LARGE => ApiError::payload_too_large(rejection.body_text()),
+                _ => ApiError::invalid_request(
+                    format!("Invalid JSON body: {}", rejection.body_text()),
+                    None,
+                ),
+            }
+        })?;
+
+        if exceeds_json_depth(&bytes, MAX_JSON_DEPTH) {
+            return Err(ApiError::invalid_request(
+                format!(
+                    "Invalid JSON body: nested deeper than {} levels",
+                    MAX_JSON_DEPTH
+                ),
+                None,
+            ));
+        }
+
+        match Json::<T>::from_bytes(&bytes) {
+            Ok(Json(value)) => Ok(ApiJson(value)),
+            Err(rejection) => Err(ApiError::invalid_request(
+                rejection_message(&rejection),
+                None,
+            )),
+        }
+    }
+}
+
+/// `application/json` or any `+json` media type
+fn has_json_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return false;
+    };
+
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence == "application/json"
+        || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+fn rejection_message(rejection: &JsonRejection) -> String {
+    format!("Invalid JSON body: {}", rejection.body_text())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_status_mapping() {
+        assert_eq!(
+            ApiError::invalid_request("bad", None).status(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            ApiError::enclave_operation_failed("failed", RenclaveErrorCode::Internal, None)
+                .status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            ApiError::unexpected_enclave_response(None).status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            ApiError::enclave_unavailable("refused", None).status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[test]
+    fn test_error_response_body() {
+        let error = ApiError::enclave_unavailable("connection refused", Some("req-1".to_string()));
+        let body = error.to_response();
+
+        assert_eq!(body.error_code, ApiErrorCode::EnclaveUnavailable);
+        assert_eq!(body.code, 503);
+        assert_eq!(body.request_id.as_deref(), Some("req-1"));
+        assert!(body.error.contains("connection refused"));
+    }
+
+    #[test]
+    fn test_invalid_fields_body() {
+        let mut errors = ValidationErrors::default();
+        errors.check::<()>(Err(FieldError::new("path", "must not be empty")));
+        errors.check::<()>(Err(FieldError::new("curve", "unsupported")));
+        let body = ApiError::invalid_fields(errors, None).to_response();
+
+        assert_eq!(body.code, 400);
+        assert_eq!(body.error_code, ApiErrorCode::InvalidRequest);
+        assert_eq!(
+            body.error,
+            "Invalid request: path: must not be empty; curve: unsupported"
+        );
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["details"][1]["field"], "curve");
+
+        let json = serde_json::to_value(ApiError::invalid_request("bad", None).to_response());
+        assert!(json.unwrap().get("details").is_none());
+    }
+
+    #[test]
+    fn test_network_unavailable_error() {
+        let error = ApiError::NetworkUnavailable {
+            state: NetworkState::NetworkUnavailable {
+                reason: "TAP interface tap0 not found".to_string(),
+                since: 0,
+            },
+        };
+        let body = error.to_response();
+
+        assert_eq!(error.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body.error_code, ApiErrorCode::NetworkUnavailable);
+        assert!(body.error.contains("tap0 not found"));
+    }
+
+    /// Status and body of posting `body` to an `ApiJson` route limited to `limit` bytes
+    async fn post_json(body: &str, limit: usize) -> (StatusCode, String) {
+        use axum::{body::Body, extract::DefaultBodyLimit, routing::post, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/",
+                post(|ApiJson(value): ApiJson<serde_json::Value>| async move { Json(value) }),
+            )
+            .layer(DefaultBodyLimit::max(limit));
+        let request = Request::builder()
+            .method("POST")
+            .uri("/")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_json_body_limits() {
+        let (status, _) = post_json(r#"{"strength":256}"#, 1024).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = post_json(&format!(r#"{{"pad":"{}"}}"#, "x".repeat(2048)), 1024).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body.contains("payload_too_large"));
+
+        let deep = format!("{}{}", "[".repeat(64), "]".repeat(64));
+        let (status, body) = post_json(&deep, 1024).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("nested deeper"));
+    }
+
+    #[test]
+    fn test_enclave_error_keeps_enclave_code() {
+        let error = ApiError::enclave_operation_failed(
+            "boom",
+            RenclaveErrorCode::UnsupportedOperation,
+            None,
+        );
+        let body = error.to_response();
+        assert_eq!(error.status(), StatusCode::NOT_IMPLEMENTED);
+        assert_eq!(body.code, 501);
+        assert_eq!(body.error_code, ApiErrorCode::EnclaveOperationFailed);
+        assert_eq!(
+            body.enclave_error,
+            Some(RenclaveErrorCode::UnsupportedOperation)
+        );
+
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["enclave_error"]["label"], "unsupported_operation");
+        let unrelated = serde_json::to_value(ApiError::payload_too_large("big").to_response());
+        assert!(unrelated.unwrap().get("enclave_error").is_none());
+    }
+
+    #[test]
+    fn test_enclave_error_status_mapping() {
+        for (code, status) in [
+            (RenclaveErrorCode::InvalidRequest, StatusCode::BAD_REQUEST),
+            (RenclaveErrorCode::NotFound, StatusCode::NOT_FOUND),
+            (RenclaveErrorCode::PolicyDenied, StatusCode::FORBIDDEN),
+            (
+                RenclaveErrorCode::Unavailable,
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (RenclaveErrorCode::Timeout, StatusCode::GATEWAY_TIMEOUT),
+            (
+                RenclaveErrorCode::CryptoFailure,
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        ] {
+            let error = ApiError::enclave_operation_failed("failed", code, None);
+            assert_eq!(error.status(), status);
+            assert_eq!(error.to_response().code, u32::from(status.as_u16()));
+        }
+    }
+}
@@ -3,22 +3,73 @@
 //! This library provides the host-side API functionality for communicating with
 //! the enclave and handling HTTP requests.
 
+pub mod acme;
 pub mod api_handlers;
+pub mod attestation;
+pub mod auth;
+pub mod cache;
+pub mod dashboard;
 pub mod enclave_client;
+pub mod enclaves;
+pub mod error;
+pub mod limits;
+#[cfg(any(test, feature = "mock-enclave"))]
+pub mod mock_enclave;
+pub mod openapi;
+pub mod qemu_manager;
+pub mod retry;
+pub mod runtime_config;
+pub mod session;
+pub mod storage;
+pub mod supervisor;
+pub mod telemetry;
+pub mod time_sync;
+pub mod tls_passthrough;
+pub mod webhook;
 
 // Re-export main types for convenience
+pub use acme::{AcmeConfig, AcmeManager};
 pub use api_handlers::*;
+pub use auth::IdentityStore;
+pub use cache::{CachingEnclave, TtlCache};
 #[allow(unused_imports)]
 pub use enclave_client::*;
+pub use enclaves::{EnclaveTarget, NamedEnclave};
+pub use error::{ApiError, ApiJson};
+pub use limits::BodyLimits;
+#[cfg(any(test, feature = "mock-enclave"))]
+pub use mock_enclave::MockEnclave;
+pub use openapi::ApiDoc;
+pub use qemu_manager::QemuConfig;
+pub use retry::{CircuitBreaker, CircuitState, RetryPolicy};
+pub use runtime_config::RuntimeSettings;
+pub use session::{EnclaveEvent, EnclaveSession, SessionInfo};
+pub use storage::{MemoryStorage, Storage, StorageExt};
+pub use supervisor::{RestartPolicy, Supervisor, SupervisorConfig};
+pub use tls_passthrough::TlsPassthrough;
+pub use webhook::{WebhookEvent, WebhookNotifier};
 
-use enclave_client::EnclaveClient;
-use renclave_network::{ConnectivityTester, NetworkManager};
+use renclave_network::{ConnectivityTester, NetworkManager, NetworkStatus};
+use renclave_shared::ThresholdPolicy;
 use std::sync::Arc;
 
 /// Application state shared across handlers
 #[derive(Clone)]
 pub struct AppState {
-    pub enclave_client: Arc<EnclaveClient>,
+    pub enclave_client: Arc<dyn EnclaveApi>,
     pub network_manager: Arc<NetworkManager>,
     pub connectivity_tester: Arc<ConnectivityTester>,
+    pub session: Arc<EnclaveSession>,
+    pub webhooks: Arc<WebhookNotifier>,
+    pub storage: Arc<dyn Storage>,
+    pub identities: Arc<IdentityStore>,
+    pub settings: Arc<RuntimeSettings>,
+    pub network_status_cache: Arc<TtlCache<NetworkStatus>>,
+    pub enclaves: Arc<Vec<NamedEnclave>>,
+    /// Set when the host launched the enclave process itself
+    pub supervisor: Option<Arc<Supervisor>>,
+    /// Set when the enclave's TLS certificate is obtained over ACME
+    pub acme: Option<Arc<AcmeManager>>,
+    /// Checked before a threshold signing session start is forwarded to the enclave
+    pub threshold_policy: ThresholdPolicy,
 }
@@ -0,0 +1,140 @@
+use log::warn;
+use renclave_shared::{max_forwarded_body_bytes, DEFAULT_MAX_FRAME_BYTES, MAX_FRAME_BYTES_ENV};
+
+/// Environment variable for the request body limit of ordinary routes, in bytes
+pub const MAX_BODY_BYTES_ENV: &str = "RENCLAVE_HOST_MAX_BODY_BYTES";
+/// Environment variable for the request body limit of PSBT signing, in bytes
+pub const MAX_PSBT_BODY_BYTES_ENV: &str = "RENCLAVE_HOST_MAX_PSBT_BODY_BYTES";
+
+/// Deepest array/object nesting accepted in a JSON request body
+pub const MAX_JSON_DEPTH: usize = 32;
+
+/// Request body limits for the host HTTP routes.
+///
+/// Every body is forwarded to the enclave in a single request frame, so neither limit may
+/// exceed what fits in the enclave's frame limit; a larger body would be cut off by the
+/// enclave as a socket error instead of being rejected here with a 413.
+#[derive(Debug, Clone)]
+pub struct BodyLimits {
+    /// Limit for seed, derivation and policy requests, which are a few hundred bytes
+    pub max_body_bytes: usize,
+    /// Limit for `/enclave/sign-psbt`, whose PSBTs grow with the number of inputs
+    pub max_psbt_body_bytes: usize,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self::for_frame_limit(DEFAULT_MAX_FRAME_BYTES)
+    }
+}
+
+impl BodyLimits {
+    /// Default limits for an enclave reading frames of at most `max_frame_bytes`
+    pub fn for_frame_limit(max_frame_bytes: usize) -> Self {
+        let max_forwarded = max_forwarded_body_bytes(max_frame_bytes);
+        Self {
+            max_body_bytes: max_forwarded.min(16 * 1024),
+            // PSBTs may use everything a frame leaves after the request envelope
+            max_psbt_body_bytes: max_forwarded,
+        }
+    }
+
+    /// Default limits with overrides from the environment.
+    ///
+    /// The defaults follow `RENCLAVE_ENCLAVE_MAX_FRAME_BYTES` when the host is given the
+    /// enclave's setting.
+    pub fn from_env() -> Self {
+        let env = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|v| *v > 0)
+        };
+
+        let max_frame_bytes = env(MAX_FRAME_BYTES_ENV).unwrap_or(DEFAULT_MAX_FRAME_BYTES);
+        let mut limits = Self::for_frame_limit(max_frame_bytes);
+        if let Some(bytes) = env(MAX_BODY_BYTES_ENV) {
+            limits.max_body_bytes = bytes;
+        }
+        if let Some(bytes) = env(MAX_PSBT_BODY_BYTES_ENV) {
+            limits.max_psbt_body_bytes = bytes;
+        }
+
+        let max_forwarded = max_forwarded_body_bytes(max_frame_bytes);
+        if limits.max_body_bytes.max(limits.max_psbt_body_bytes) > max_forwarded {
+            warn!(
+                "⚠️  Request body limits exceed the {} bytes that fit in an enclave frame of {} bytes; raise {} to match",
+                max_forwarded, max_frame_bytes, MAX_FRAME_BYTES_ENV
+            );
+        }
+        limits
+    }
+}
+
+/// Whether `json` nests arrays or objects deeper than `max_depth`.
+///
+/// Brackets inside strings are ignored. The body is not otherwise validated, so this is cheap
+/// enough to run before deserialization.
+pub fn exceeds_json_depth(json: &[u8], max_depth: usize) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &byte in json {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_depth() {
+        assert!(!exceeds_json_depth(br#"{"a":[1,{"b":2}]}"#, 3));
+        assert!(exceeds_json_depth(br#"{"a":[1,{"b":2}]}"#, 2));
+
+        let deep = format!("{}{}", "[".repeat(100), "]".repeat(100));
+        assert!(exceeds_json_depth(deep.as_bytes(), MAX_JSON_DEPTH));
+    }
+
+    #[test]
+    fn test_limits_fit_in_enclave_frame() {
+        let limits = BodyLimits::default();
+        assert_eq!(
+            limits.max_psbt_body_bytes,
+            DEFAULT_MAX_FRAME_BYTES - renclave_shared::FRAME_ENVELOPE_BYTES
+        );
+        assert!(limits.max_body_bytes <= limits.max_psbt_body_bytes);
+
+        let small = BodyLimits::for_frame_limit(8 * 1024);
+        assert_eq!(small.max_body_bytes, small.max_psbt_body_bytes);
+        assert_eq!(small.max_psbt_body_bytes, 7 * 1024);
+    }
+
+    #[test]
+    fn test_brackets_in_strings_are_ignored() {
+        assert!(!exceeds_json_depth(br#"{"a":"[[[[\"[[[["}"#, 1));
+    }
+}
@@ -1,5 +1,7 @@
 use axum::{
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{get, post, put},
     Router,
 };
 use std::net::SocketAddr;
@@ -7,44 +9,131 @@ use std::sync::Arc;
 use std::time::Duration;
 // Removed tower imports for now
 use log::{info, warn};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
+mod acme;
 mod api_handlers;
+mod attestation;
+mod auth;
+mod cache;
+mod dashboard;
 mod enclave_client;
+mod enclaves;
+mod error;
+mod limits;
+#[cfg(test)]
+mod mock_enclave;
+mod openapi;
+mod qemu_manager;
+mod retry;
+mod runtime_config;
+mod session;
+mod storage;
+mod supervisor;
+mod telemetry;
+mod time_sync;
+mod tls_passthrough;
+mod webhook;
 
-use enclave_client::EnclaveClient;
-use renclave_network::{ConnectivityTester, NetworkConfig, NetworkManager};
+use acme::{AcmeConfig, AcmeManager};
+use auth::IdentityStore;
+use cache::{CachingEnclave, TtlCache};
+use enclave_client::{EnclaveApi, EnclaveClient};
+use enclaves::{EnclaveTarget, NamedEnclave};
+use limits::BodyLimits;
+use openapi::ApiDoc;
+use qemu_manager::QemuConfig;
+use renclave_network::{ConnectivityTester, NetworkConfig, NetworkManager, NetworkStatus};
+use renclave_shared::{EnclaveResult, ThresholdPolicy};
+use retry::CircuitBreaker;
+use runtime_config::RuntimeSettings;
+use session::EnclaveSession;
+use storage::Storage;
+use supervisor::{Supervisor, SupervisorConfig};
+use tls_passthrough::TlsPassthrough;
+use webhook::WebhookNotifier;
+
+/// Environment variable for the HTTP listen address
+const BIND_ADDR_ENV: &str = "RENCLAVE_HOST_BIND_ADDR";
 
 /// QEMU Host - HTTP API Gateway for Nitro Enclave
 pub struct QemuHost {
     enclave_client: Arc<EnclaveClient>,
     network_manager: Arc<NetworkManager>,
     connectivity_tester: Arc<ConnectivityTester>,
+    session: Arc<EnclaveSession>,
+    webhooks: Arc<WebhookNotifier>,
+    storage: Arc<dyn Storage>,
+    identities: Arc<IdentityStore>,
+    /// The default enclave first, then those from `RENCLAVE_ENCLAVES`
+    enclaves: Vec<NamedEnclave>,
+    cache_ttl: Duration,
+    supervisor: Option<Arc<Supervisor>>,
+    acme: Option<Arc<AcmeManager>>,
 }
 
 impl QemuHost {
     /// Create new QEMU host instance
     pub async fn new() -> anyhow::Result<Self> {
         info!("🏠 Initializing QEMU Host (API Gateway)");
+        let targets = enclaves::targets_from_env()?;
 
         // Initialize network manager
         info!("🌐 Initializing network manager...");
-        let network_config = NetworkConfig::default();
+        let network_config = NetworkConfig::from_env();
+        let supervisor_config = match (
+            SupervisorConfig::from_env().map_err(anyhow::Error::msg)?,
+            QemuConfig::from_env(&network_config).map_err(anyhow::Error::msg)?,
+        ) {
+            (Some(_), Some(_)) => anyhow::bail!(
+                "Set {} or {}, not both",
+                supervisor::ENCLAVE_COMMAND_ENV,
+                qemu_manager::QEMU_KERNEL_ENV
+            ),
+            (Some(config), None) => Some(config),
+            (None, Some(qemu)) => Some(qemu.supervisor_config().map_err(anyhow::Error::msg)?),
+            (None, None) => None,
+        };
+        // The supervised enclave joins the network namespace, if there is one
+        let supervisor_config = supervisor_config.map(|config| SupervisorConfig {
+            netns: network_config.netns.as_ref().map(|netns| netns.path()),
+            ..config
+        });
+        let connectivity_tester = Arc::new(ConnectivityTester::for_config(&network_config));
+        let storage = storage::open_from_env()?;
+        let webhooks = Arc::new(WebhookNotifier::from_env(
+            network_config.proxy.as_ref(),
+            Arc::clone(&storage),
+        )?);
+        webhooks.resume_pending();
+        let identities = Arc::new(IdentityStore::from_env(Arc::clone(&storage)));
+        let proxy = network_config.proxy.clone();
         let network_manager = Arc::new(NetworkManager::new(network_config));
 
         // Initialize network (non-blocking)
         let network_manager_clone = Arc::clone(&network_manager);
-        tokio::spawn(async move {
+        let network_init = tokio::spawn(async move {
             if let Err(e) = network_manager_clone.initialize().await {
                 warn!("⚠️  Network initialization failed: {}", e);
             }
         });
-
-        // Initialize connectivity tester
-        let connectivity_tester = Arc::new(ConnectivityTester::default());
+        if let Some(interval) = NetworkManager::monitor_interval_from_env() {
+            network_manager.spawn_monitor(interval);
+        }
 
         // Initialize enclave client
         info!("🔗 Initializing enclave client...");
-        let enclave_client = Arc::new(EnclaveClient::new("/tmp/enclave.sock".to_string()));
+        let enclave_client = Arc::new(EnclaveClient::new(renclave_shared::enclave_socket_path()));
+
+        // Launch the enclave ourselves when configured to, once its namespace exists
+        if supervisor_config
+            .as_ref()
+            .is_some_and(|config| config.netns.is_some())
+        {
+            let _ = network_init.await;
+        }
+        let supervisor = supervisor_config.map(Supervisor::spawn);
 
         // Wait for enclave to be available
         info!("⏳ Waiting for enclave to be available...");
@@ -53,10 +142,95 @@ impl QemuHost {
             .await?;
         info!("✅ Enclave is available");
 
+        // Track the enclave session to detect restarts
+        let session = Arc::new(EnclaveSession::new());
+        webhooks.forward_session_events(session.subscribe());
+        match enclave_client.get_info().await {
+            Ok(response) => {
+                if let EnclaveResult::Info { enclave_id, .. } = response.result {
+                    session.observe(&enclave_id);
+                }
+            }
+            Err(e) => warn!("⚠️  Failed to read initial enclave session: {}", e),
+        }
+        if let Some(interval) = EnclaveSession::poll_interval_from_env() {
+            session.spawn_monitor(enclave_client.clone(), interval);
+        }
+        let time_sync_interval = time_sync::interval_from_env();
+        if let Some(interval) = time_sync_interval {
+            time_sync::spawn(enclave_client.clone(), interval);
+        }
+
+        // Monitoring reads go through a short-lived cache; the session monitors keep
+        // polling the enclaves directly so restarts are still noticed
+        let cache_ttl = cache::ttl_from_env();
+        info!("📦 Read cache TTL: {:?}", cache_ttl);
+        let mut enclaves = vec![NamedEnclave {
+            target: EnclaveTarget {
+                name: enclaves::DEFAULT_ENCLAVE_NAME.to_string(),
+                socket_path: renclave_shared::enclave_socket_path(),
+            },
+            client: cached(enclave_client.clone(), &session, cache_ttl),
+            session: Arc::clone(&session),
+        }];
+
+        // Further enclaves are not waited for; their status shows under /enclaves
+        for target in targets {
+            info!(
+                "🔗 Adding enclave {} at {}",
+                target.name, target.socket_path
+            );
+            let client = Arc::new(EnclaveClient::with_policies(
+                target.socket_path.clone(),
+                enclave_client.settings(),
+                CircuitBreaker::from_env(),
+            ));
+            let session = Arc::new(EnclaveSession::new());
+            webhooks.forward_session_events(session.subscribe());
+            if let Some(interval) = EnclaveSession::poll_interval_from_env() {
+                session.spawn_monitor(client.clone(), interval);
+            }
+            if let Some(interval) = time_sync_interval {
+                time_sync::spawn(client.clone(), interval);
+            }
+            enclaves.push(NamedEnclave {
+                target,
+                client: cached(client, &session, cache_ttl),
+                session,
+            });
+        }
+
+        // Obtain the enclave's TLS certificate over ACME when domains are configured
+        let acme = match AcmeConfig::from_env()? {
+            Some(config) => {
+                info!(
+                    "📜 Obtaining TLS certificates for {}",
+                    config.domains.join(", ")
+                );
+                let acme = Arc::new(AcmeManager::new(
+                    config,
+                    proxy.as_ref(),
+                    Arc::clone(&storage),
+                    enclave_client.clone(),
+                )?);
+                acme.spawn_renewal(session.subscribe());
+                Some(acme)
+            }
+            None => None,
+        };
+
         Ok(Self {
             enclave_client,
             network_manager,
             connectivity_tester,
+            session,
+            webhooks,
+            storage,
+            identities,
+            enclaves,
+            cache_ttl,
+            supervisor,
+            acme,
         })
     }
 
@@ -66,50 +240,229 @@ impl QemuHost {
 
         // Create application state
         let app_state = AppState {
-            enclave_client: Arc::clone(&self.enclave_client),
+            enclave_client: Arc::clone(&self.enclaves[0].client),
             network_manager: Arc::clone(&self.network_manager),
             connectivity_tester: Arc::clone(&self.connectivity_tester),
+            session: Arc::clone(&self.session),
+            webhooks: Arc::clone(&self.webhooks),
+            storage: Arc::clone(&self.storage),
+            identities: Arc::clone(&self.identities),
+            settings: self.enclave_client.settings(),
+            network_status_cache: Arc::new(TtlCache::new(self.cache_ttl)),
+            enclaves: Arc::new(self.enclaves.clone()),
+            supervisor: self.supervisor.clone(),
+            acme: self.acme.clone(),
+            threshold_policy: ThresholdPolicy::from_env().map_err(anyhow::Error::msg)?,
         };
 
+        let limits = BodyLimits::from_env();
+        info!(
+            "📏 Request body limits: {} bytes, {} bytes for PSBT signing",
+            limits.max_body_bytes, limits.max_psbt_body_bytes
+        );
+
         // Build router
-        let app = Router::new()
+        let mut app = Router::new()
             .route("/health", get(api_handlers::health_check))
+            .route("/health/live", get(api_handlers::health_live))
+            .route("/health/ready", get(api_handlers::health_ready))
             .route("/info", get(api_handlers::get_info))
-            .route("/generate-seed", post(api_handlers::generate_seed))
-            .route("/validate-seed", post(api_handlers::validate_seed))
-            .route("/derive-key", post(api_handlers::derive_key))
-            .route("/derive-address", post(api_handlers::derive_address))
+            .merge(enclave_routes(&limits))
+            .route("/enclaves", get(api_handlers::list_enclaves))
             .route("/network/status", get(api_handlers::network_status))
             .route("/network/test", post(api_handlers::test_connectivity))
-            .route("/enclave/info", get(api_handlers::enclave_info))
+            .route("/network/repair", post(api_handlers::repair_network))
+            .route("/network/egress", get(api_handlers::egress_policy))
+            .route(
+                "/attestation/verify",
+                post(api_handlers::verify_attestation),
+            )
+            .route(
+                "/admin/identities",
+                get(api_handlers::list_identities).post(api_handlers::create_identity),
+            )
+            .route(
+                "/admin/identities/:name",
+                put(api_handlers::update_identity).delete(api_handlers::delete_identity),
+            )
+            .route(
+                "/admin/log-level",
+                get(api_handlers::get_log_level).put(api_handlers::set_log_level),
+            )
+            .route(
+                "/admin/config",
+                get(api_handlers::get_runtime_config).put(api_handlers::update_runtime_config),
+            )
+            .route("/admin/enclave/process", get(api_handlers::enclave_process))
+            .route(
+                "/admin/enclave/process/start",
+                post(api_handlers::start_enclave_process),
+            )
+            .route(
+                "/admin/enclave/process/stop",
+                post(api_handlers::stop_enclave_process),
+            )
+            .route("/admin/enclave/console", get(api_handlers::enclave_console))
+            .route(
+                "/.well-known/acme-challenge/:token",
+                get(api_handlers::acme_challenge),
+            )
+            .route("/admin/acme", get(api_handlers::acme_status))
+            .route(
+                "/admin/acme/renew",
+                post(api_handlers::renew_acme_certificate),
+            );
+        if dashboard::enabled_from_env() {
+            info!("📋 Admin dashboard served at /admin/dashboard");
+            app = app.merge(dashboard::routes());
+        }
+
+        // Every enclave also serves the enclave routes under /enclaves/{name}
+        for enclave in &self.enclaves {
+            let state = AppState {
+                enclave_client: Arc::clone(&enclave.client),
+                session: Arc::clone(&enclave.session),
+                ..app_state.clone()
+            };
+            app = app.nest(
+                &format!("/enclaves/{}", enclave.target.name),
+                enclave_routes(&limits).with_state(state),
+            );
+        }
+
+        let app = app
+            .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi()))
+            .layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                auth::authorize,
+            ))
+            .layer(middleware::from_fn(telemetry::trace_request))
+            .layer(DefaultBodyLimit::max(limits.max_body_bytes))
             .with_state(app_state);
 
         info!("✅ HTTP router configured with all endpoints");
         info!("🔗 Binding to address: {}", bind_addr);
 
+        // Forward raw TLS to the enclave when it terminates TLS itself
+        if let Some(passthrough) = TlsPassthrough::from_env()? {
+            passthrough.spawn().await?;
+        }
+
         // Start server
         let listener = tokio::net::TcpListener::bind(bind_addr).await?;
         info!("🚀 QEMU Host HTTP server started on {}", bind_addr);
 
-        axum::serve(listener, app).await?;
+        axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown_signal())
+            .await?;
 
+        // Remove a TAP device or network namespace created at startup
+        self.network_manager.shutdown().await;
         Ok(())
     }
 }
 
+/// Resolves on Ctrl-C or SIGTERM
+async fn shutdown_signal() {
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("⚠️  Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate => {}
+    }
+    info!("🛑 Shutting down");
+}
+
+/// Routes answered by a single enclave
+fn enclave_routes(limits: &BodyLimits) -> Router<AppState> {
+    Router::new()
+        .route("/generate-seed", post(api_handlers::generate_seed))
+        .route("/validate-seed", post(api_handlers::validate_seed))
+        .route("/derive-key", post(api_handlers::derive_key))
+        .route("/derive-address", post(api_handlers::derive_address))
+        .route("/derive-xpub", post(api_handlers::derive_xpub))
+        .route("/verify-signature", post(api_handlers::verify_signature))
+        .route("/verify-ciphertext", post(api_handlers::verify_ciphertext))
+        .route("/enclave/info", get(api_handlers::enclave_info))
+        .route(
+            "/enclave/metrics/snapshot",
+            get(api_handlers::enclave_metrics_snapshot),
+        )
+        .route(
+            "/enclave/sign-psbt",
+            post(api_handlers::sign_psbt).layer(DefaultBodyLimit::max(limits.max_psbt_body_bytes)),
+        )
+        .route(
+            "/enclave/policy",
+            get(api_handlers::get_policy).post(api_handlers::set_policy),
+        )
+        .route("/enclave/channel", post(api_handlers::open_channel))
+        .route(
+            "/enclave/channel/sealed",
+            post(api_handlers::sealed_request),
+        )
+        .route("/enclave/threshold", post(api_handlers::threshold_signing))
+        .route("/enclave/keys", get(api_handlers::key_usage))
+}
+
+/// Serve `client` through the read cache, cleared whenever `session` changes
+fn cached(
+    client: Arc<EnclaveClient>,
+    session: &EnclaveSession,
+    ttl: Duration,
+) -> Arc<dyn EnclaveApi> {
+    let cached = Arc::new(CachingEnclave::new(client, ttl));
+    cached.invalidate_on(session.subscribe());
+    cached
+}
+
 /// Application state shared across handlers
 #[derive(Clone)]
 pub struct AppState {
-    pub enclave_client: Arc<EnclaveClient>,
+    pub enclave_client: Arc<dyn EnclaveApi>,
     pub network_manager: Arc<NetworkManager>,
     pub connectivity_tester: Arc<ConnectivityTester>,
+    pub session: Arc<EnclaveSession>,
+    pub webhooks: Arc<WebhookNotifier>,
+    pub storage: Arc<dyn Storage>,
+    pub identities: Arc<IdentityStore>,
+    pub settings: Arc<RuntimeSettings>,
+    pub network_status_cache: Arc<TtlCache<NetworkStatus>>,
+    pub enclaves: Arc<Vec<NamedEnclave>>,
+    /// Set when the host launched the enclave process itself
+    pub supervisor: Option<Arc<Supervisor>>,
+    /// Set when the enclave's TLS certificate is obtained over ACME
+    pub acme: Option<Arc<AcmeManager>>,
+    /// Checked before a threshold signing session start is forwarded to the enclave
+    pub threshold_policy: ThresholdPolicy,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    std::env::set_var("RUST_LOG", "debug");
-    env_logger::init();
+    // Initialize logging at the RUST_LOG level. The inner logger passes everything so
+    // `/admin/log-level` can raise the level later.
+    let env = || env_logger::Env::default().default_filter_or("info");
+    let max_level = env_logger::Builder::from_env(env()).build().filter();
+    let logger = env_logger::Builder::from_env(env())
+        .filter_level(log::LevelFilter::Trace)
+        .build();
+    renclave_shared::redact::init_logger(Box::new(logger), max_level)?;
+
+    // Export request traces when an OTLP endpoint is configured
+    let tracer_provider = renclave_shared::telemetry::init_tracing("renclave-host")?;
+    if tracer_provider.is_some() {
+        info!("🔭 Exporting traces over OTLP");
+    }
 
     info!("🏠 QEMU Host - HTTP API Gateway for Nitro Enclave");
     info!("🔍 Process ID: {}", std::process::id());
@@ -121,9 +474,19 @@ async fn main() -> anyhow::Result<()> {
     // Create and start host
     let host = QemuHost::new().await?;
 
-    // Start HTTP server on all interfaces
-    let bind_addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    host.start(bind_addr).await?;
+    // Start HTTP server, on all interfaces unless overridden
+    let bind_addr = match std::env::var(BIND_ADDR_ENV) {
+        Ok(value) => value
+            .parse::<SocketAddr>()
+            .map_err(|e| anyhow::anyhow!("Invalid {} '{}': {}", BIND_ADDR_ENV, value, e))?,
+        Err(_) => SocketAddr::from(([0, 0, 0, 0], 3000)),
+    };
+    let result = host.start(bind_addr).await;
 
-    Ok(())
+    if let Some(provider) = tracer_provider {
+        if let Err(e) = provider.shutdown() {
+            warn!("⚠️  Failed to flush traces: {}", e);
+        }
+    }
+    result
 }
@@ -0,0 +1,328 @@
+use anyhow::{anyhow, Result};
+use axum::async_trait;
+use renclave_shared::{
+    response_digest, BuildInfo, ChannelAttestation, DeriveKeyResponse, EnclaveOperation,
+    EnclaveResponse, EnclaveResult, EnclaveTime, GenerateSeedResponse, MetricsSnapshot,
+    NetworkState, RenclaveErrorCode, SecretString, SignedMetricsSnapshot, SigningPolicy,
+    TimeSource, PROTOCOL_VERSION, SUPPORTED_OPERATIONS,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::enclave_client::EnclaveApi;
+
+/// Address returned for every derivation (BIP-173 test vector)
+pub const MOCK_ADDRESS: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+/// In-memory stand-in for the enclave, for exercising the host without a socket.
+///
+/// Operations answer with fixed, well-formed results: generated seeds are the all-zero
+/// BIP-39 test vectors and the signing policy is kept in memory. Individual operations can
+/// be overridden with [`respond_with`](Self::respond_with) and the enclave can be taken
+/// offline with [`set_available`](Self::set_available).
+pub struct MockEnclave {
+    enclave_id: String,
+    available: AtomicBool,
+    policy: Mutex<SigningPolicy>,
+    log_level: Mutex<String>,
+    overrides: Mutex<HashMap<&'static str, EnclaveResult>>,
+    operations: Mutex<Vec<&'static str>>,
+}
+
+impl Default for MockEnclave {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockEnclave {
+    pub fn new() -> Self {
+        Self::with_enclave_id(format!("mock-{}", Uuid::new_v4()))
+    }
+
+    /// Mock reporting a fixed enclave id, e.g. to simulate a restart with a second mock
+    pub fn with_enclave_id(enclave_id: impl Into<String>) -> Self {
+        Self {
+            enclave_id: enclave_id.into(),
+            available: AtomicBool::new(true),
+            policy: Mutex::new(SigningPolicy::default()),
+            log_level: Mutex::new("info".to_string()),
+            overrides: Mutex::new(HashMap::new()),
+            operations: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Answer every request for `operation` (see [`EnclaveOperation::name`]) with `result`
+    pub fn respond_with(&self, operation: &'static str, result: EnclaveResult) {
+        lock(&self.overrides).insert(operation, result);
+    }
+
+    /// Make requests fail as if the enclave socket were unreachable
+    pub fn set_available(&self, available: bool) {
+        self.available.store(available, Ordering::SeqCst);
+    }
+
+    /// Names of the operations received so far, in order
+    pub fn operations(&self) -> Vec<&'static str> {
+        lock(&self.operations).clone()
+    }
+
+    /// Placeholder attestation committing to `response` like the enclave's
+    fn attestation<T: Serialize>(&self, response: &T) -> ChannelAttestation {
+        ChannelAttestation {
+            enclave_id: self.enclave_id.clone(),
+            timestamp: 0,
+            measurements: BTreeMap::new(),
+            user_data: response_digest(response)
+                .map(hex::encode)
+                .unwrap_or_default(),
+            nonce: None,
+            signature: "mock".to_string(),
+            timestamp_uncertainty_ms: None,
+        }
+    }
+
+    fn execute(&self, operation: EnclaveOperation) -> EnclaveResult {
+        match operation {
+            EnclaveOperation::GenerateSeed {
+                strength, attest, ..
+            } => {
+                if ![128, 160, 192, 224, 256].contains(&strength) {
+                    return error(
+                        RenclaveErrorCode::InvalidRequest,
+                        format!("Invalid entropy strength: {}", strength),
+                    );
+                }
+                let word_count = (strength / 32 * 3) as usize;
+                let response = GenerateSeedResponse {
+                    seed_phrase: SecretString::new(
+                        zero_entropy_phrase(word_count).unwrap_or_default(),
+                    ),
+                    entropy: SecretString::new("00".repeat(strength as usize / 8)),
+                    strength,
+                    word_count,
+                    attestation: None,
+                };
+                let attestation = attest.then(|| self.attestation(&response));
+                EnclaveResult::SeedGenerated {
+                    seed_phrase: response.seed_phrase,
+                    entropy: response.entropy,
+                    strength,
+                    word_count,
+                    attestation,
+                }
+            }
+            EnclaveOperation::ValidateSeed {
+                seed_phrase,
+                entropy,
+                ..
+            } => {
+                let phrase = seed_phrase.expose_secret().trim();
+                let word_count = phrase.split_whitespace().count();
+                let valid =
+                    zero_entropy_phrase(word_count).is_some_and(|expected| phrase == expected);
+                EnclaveResult::SeedValidated {
+                    valid,
+                    word_count,
+                    entropy_match: entropy.map(|entropy| {
+                        valid && entropy.expose_secret() == "00".repeat(word_count / 3 * 4)
+                    }),
+                }
+            }
+            EnclaveOperation::DeriveKey {
+                path,
+                curve,
+                attest,
+                ..
+            } => {
+                let response = DeriveKeyResponse {
+                    private_key: SecretString::new("11".repeat(32)),
+                    public_key: format!("02{}", "22".repeat(32)),
+                    address: MOCK_ADDRESS.to_string(),
+                    path,
+                    curve,
+                    attestation: None,
+                };
+                let attestation = attest.then(|| self.attestation(&response));
+                EnclaveResult::KeyDerived {
+                    private_key: response.private_key,
+                    public_key: response.public_key,
+                    address: response.address,
+                    path: response.path,
+                    curve: response.curve,
+                    attestation,
+                }
+            }
+            EnclaveOperation::DeriveAddress {
+                path, curve, chain, ..
+            } => EnclaveResult::AddressDerived {
+                address: MOCK_ADDRESS.to_string(),
+                path,
+                curve,
+                chain,
+            },
+            EnclaveOperation::DeriveXpub { path, format, .. } => EnclaveResult::XpubDerived {
+                xpub: format!("mock-xpub-{}", path),
+                path,
+                format,
+                master_fingerprint: "00000000".to_string(),
+            },
+            EnclaveOperation::SignPsbt { psbt, .. } => EnclaveResult::PsbtSigned {
+                psbt,
+                signed_inputs: vec![0],
+                retiring_keys: Vec::new(),
+            },
+            EnclaveOperation::GetKeyUsage => EnclaveResult::KeyUsageReport { keys: Vec::new() },
+            EnclaveOperation::GetPolicy => EnclaveResult::Policy {
+                policy: lock(&self.policy).clone(),
+            },
+            EnclaveOperation::SetPolicy { policy } => {
+                let mut current = lock(&self.policy);
+                if current.locked {
+                    return error(
+                        RenclaveErrorCode::PolicyDenied,
+                        "Signing policy is locked until the enclave restarts",
+                    );
+                }
+                *current = policy.clone();
+                EnclaveResult::Policy { policy }
+            }
+            EnclaveOperation::GetInfo => EnclaveResult::Info {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                enclave_id: self.enclave_id.clone(),
+                capabilities: vec![
+                    "seed_generation".to_string(),
+                    "seed_validation".to_string(),
+                    "key_derivation".to_string(),
+                ],
+                unavailable_capabilities: Vec::new(),
+                protocol_version: PROTOCOL_VERSION,
+                operations: SUPPORTED_OPERATIONS
+                    .iter()
+                    .map(|op| op.to_string())
+                    .collect(),
+                uptime_secs: 0,
+                build: BuildInfo::default(),
+                tls_fingerprint: None,
+                time: None,
+                hardening: None,
+            },
+            EnclaveOperation::GetMetricsSnapshot => EnclaveResult::MetricsSnapshot {
+                snapshot: SignedMetricsSnapshot {
+                    snapshot: MetricsSnapshot {
+                        enclave_id: self.enclave_id.clone(),
+                        sequence: 1,
+                        timestamp: 0,
+                        counters: BTreeMap::new(),
+                        previous_hash: "00".repeat(32),
+                        timestamp_uncertainty_ms: None,
+                    },
+                    snapshot_hash: "00".repeat(32),
+                    signature: "00".repeat(64),
+                    public_key: format!("02{}", "00".repeat(32)),
+                },
+            },
+            EnclaveOperation::RepairNetwork => EnclaveResult::NetworkRepaired {
+                state: NetworkState::Ready,
+                unavailable: None,
+            },
+            EnclaveOperation::OpenChannel { .. }
+            | EnclaveOperation::SealedRequest { .. }
+            | EnclaveOperation::VerifyCiphertext { .. } => error(
+                RenclaveErrorCode::UnsupportedOperation,
+                "Encrypted channels are not supported by the mock enclave",
+            ),
+            EnclaveOperation::ThresholdSigning { .. } => error(
+                RenclaveErrorCode::UnsupportedOperation,
+                "Threshold signing is not supported by the mock enclave",
+            ),
+            EnclaveOperation::VerifySignature { .. } => error(
+                RenclaveErrorCode::UnsupportedOperation,
+                "Signature verification is not supported by the mock enclave",
+            ),
+            EnclaveOperation::SetLogLevel { level } => {
+                let mut current = lock(&self.log_level);
+                if let Some(level) = level {
+                    match level.parse::<log::LevelFilter>() {
+                        Ok(level) => *current = level.as_str().to_ascii_lowercase(),
+                        Err(_) => {
+                            return error(
+                                RenclaveErrorCode::InvalidRequest,
+                                format!("Unknown log level '{}'", level),
+                            )
+                        }
+                    }
+                }
+                EnclaveResult::LogLevel {
+                    level: current.clone(),
+                }
+            }
+            EnclaveOperation::CreateTlsCsr { .. }
+            | EnclaveOperation::InstallTlsCertificate { .. } => error(
+                RenclaveErrorCode::NotProvisioned,
+                "The mock enclave does not terminate TLS",
+            ),
+            EnclaveOperation::SyncTime { unix_ms } => EnclaveResult::TimeSynced {
+                accepted: true,
+                time: EnclaveTime {
+                    unix_ms,
+                    uncertainty_ms: Some(0),
+                    source: TimeSource::Host,
+                    synced_at_ms: Some(unix_ms),
+                },
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl EnclaveApi for MockEnclave {
+    async fn send_request(&self, operation: EnclaveOperation) -> Result<EnclaveResponse> {
+        if !self.available.load(Ordering::SeqCst) {
+            return Err(anyhow!("Mock enclave is unavailable"));
+        }
+
+        let name = operation.name();
+        lock(&self.operations).push(name);
+
+        let result = match lock(&self.overrides).get(name) {
+            Some(result) => result.clone(),
+            None => self.execute(operation),
+        };
+        Ok(EnclaveResponse::new(Uuid::new_v4().to_string(), result))
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(self.available.load(Ordering::SeqCst))
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn error(code: RenclaveErrorCode, message: impl Into<String>) -> EnclaveResult {
+    EnclaveResult::Error {
+        message: message.into(),
+        code,
+    }
+}
+
+/// BIP-39 phrase for all-zero entropy, if `word_count` is a valid phrase length
+fn zero_entropy_phrase(word_count: usize) -> Option<String> {
+    let last = match word_count {
+        12 => "about",
+        15 => "address",
+        18 => "agent",
+        21 => "admit",
+        24 => "art",
+        _ => return None,
+    };
+
+    let mut words = vec!["abandon"; word_count - 1];
+    words.push(last);
+    Some(words.join(" "))
+}
@@ -0,0 +1,196 @@
+use utoipa::OpenApi;
+
+use crate::api_handlers;
+use renclave_shared::*;
+
+/// OpenAPI description of the host HTTP API, served at `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "renclave-v2 Host API",
+        description = "HTTP API gateway for the QEMU Nitro Enclave"
+    ),
+    paths(
+        api_handlers::health_check,
+        api_handlers::health_live,
+        api_handlers::health_ready,
+        api_handlers::get_info,
+        api_handlers::generate_seed,
+        api_handlers::validate_seed,
+        api_handlers::derive_key,
+        api_handlers::derive_address,
+        api_handlers::derive_xpub,
+        api_handlers::network_status,
+        api_handlers::test_connectivity,
+        api_handlers::repair_network,
+        api_handlers::egress_policy,
+        api_handlers::list_enclaves,
+        api_handlers::enclave_info,
+        api_handlers::enclave_metrics_snapshot,
+        api_handlers::sign_psbt,
+        api_handlers::get_policy,
+        api_handlers::set_policy,
+        api_handlers::key_usage,
+        api_handlers::open_channel,
+        api_handlers::sealed_request,
+        api_handlers::threshold_signing,
+        api_handlers::verify_signature,
+        api_handlers::verify_ciphertext,
+        api_handlers::verify_attestation,
+        api_handlers::list_identities,
+        api_handlers::create_identity,
+        api_handlers::update_identity,
+        api_handlers::delete_identity,
+        api_handlers::get_log_level,
+        api_handlers::set_log_level,
+        api_handlers::get_runtime_config,
+        api_handlers::update_runtime_config,
+        api_handlers::enclave_process,
+        api_handlers::start_enclave_process,
+        api_handlers::stop_enclave_process,
+        api_handlers::enclave_console,
+        api_handlers::acme_challenge,
+        api_handlers::acme_status,
+        api_handlers::renew_acme_certificate,
+    ),
+    components(schemas(
+        GenerateSeedRequest,
+        GenerateSeedResponse,
+        SeedLanguage,
+        ValidateSeedRequest,
+        ValidateSeedResponse,
+        DeriveKeyRequest,
+        DeriveKeyResponse,
+        DeriveAddressRequest,
+        DeriveAddressResponse,
+        Chain,
+        DeriveXpubRequest,
+        DeriveXpubResponse,
+        XpubFormat,
+        SignPsbtRequest,
+        SignPsbtResponse,
+        SigningPolicy,
+        SigningWindow,
+        KeyRetirementPolicy,
+        KeyUsage,
+        OpenChannelRequest,
+        OpenChannelResponse,
+        ChannelAttestation,
+        SealedChannelMessage,
+        SealedPayload,
+        ThresholdMessage,
+        ThresholdRound,
+        ThresholdCommitment,
+        ThresholdSessionStatus,
+        VerifySignatureRequest,
+        VerifySignatureResponse,
+        VerifyAttestationRequest,
+        AttestationReport,
+        AttestationCheck,
+        CertificateSummary,
+        Scope,
+        Identity,
+        CreateIdentityRequest,
+        CreateIdentityResponse,
+        UpdateIdentityRequest,
+        SetLogLevelRequest,
+        LogLevelResponse,
+        RuntimeConfig,
+        UpdateRuntimeConfigRequest,
+        EnclaveProcessStatus,
+        ConsoleResponse,
+        AcmeStatus,
+        AcmeCertificate,
+        VerifyCiphertextResponse,
+        InfoResponse,
+        HealthStatus,
+        HealthCheck,
+        HealthReport,
+        NetworkState,
+        NetworkRepairResponse,
+        EnclaveStatus,
+        EnclavesResponse,
+        MetricsSnapshot,
+        SignedMetricsSnapshot,
+        ErrorResponse,
+        FieldError,
+        ApiErrorCode,
+        LabelledErrorCode,
+    )),
+    tags(
+        (name = "service", description = "Host service endpoints"),
+        (name = "seed", description = "BIP39 seed generation and validation"),
+        (name = "derivation", description = "Key and address derivation"),
+        (name = "network", description = "TAP network status and tests"),
+        (name = "verification", description = "Signature and ciphertext verification"),
+        (name = "enclave", description = "Enclave information"),
+        (name = "admin", description = "API identities and runtime settings")
+    )
+)]
+pub struct ApiDoc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_lists_all_routes() {
+        let spec = ApiDoc::openapi();
+        let paths: Vec<&String> = spec.paths.paths.keys().collect();
+
+        for route in [
+            "/health",
+            "/health/live",
+            "/health/ready",
+            "/info",
+            "/generate-seed",
+            "/validate-seed",
+            "/derive-key",
+            "/derive-address",
+            "/derive-xpub",
+            "/network/status",
+            "/network/test",
+            "/network/repair",
+            "/network/egress",
+            "/enclaves",
+            "/enclave/info",
+            "/enclave/metrics/snapshot",
+            "/enclave/sign-psbt",
+            "/enclave/policy",
+            "/enclave/channel",
+            "/enclave/channel/sealed",
+            "/enclave/threshold",
+            "/enclave/keys",
+            "/verify-signature",
+            "/verify-ciphertext",
+            "/attestation/verify",
+            "/admin/identities",
+            "/admin/identities/{name}",
+            "/admin/log-level",
+            "/admin/config",
+            "/admin/enclave/process",
+            "/admin/enclave/process/start",
+            "/admin/enclave/process/stop",
+            "/admin/enclave/console",
+            "/.well-known/acme-challenge/{token}",
+            "/admin/acme",
+            "/admin/acme/renew",
+        ] {
+            assert!(
+                paths.iter().any(|p| p.as_str() == route),
+                "missing {}",
+                route
+            );
+        }
+    }
+
+    #[test]
+    fn test_openapi_includes_error_schema() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &spec["components"]["schemas"];
+
+        assert!(schemas.get("ErrorResponse").is_some());
+        assert!(schemas.get("ApiErrorCode").is_some());
+        assert!(schemas.get("LabelledErrorCode").is_some());
+    }
+}
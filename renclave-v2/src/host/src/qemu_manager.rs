@@ -0,0 +1,192 @@
+use std::path::Path;
+
+use crate::supervisor::SupervisorConfig;
+use renclave_network::NetworkConfig;
+
+/// Environment variable with the guest kernel; setting it makes the host launch QEMU
+pub const QEMU_KERNEL_ENV: &str = "RENCLAVE_QEMU_KERNEL";
+pub const QEMU_INITRD_ENV: &str = "RENCLAVE_QEMU_INITRD";
+pub const QEMU_BINARY_ENV: &str = "RENCLAVE_QEMU_BINARY";
+pub const QEMU_APPEND_ENV: &str = "RENCLAVE_QEMU_APPEND";
+pub const QEMU_MEMORY_MIB_ENV: &str = "RENCLAVE_QEMU_MEMORY_MIB";
+pub const QEMU_CPUS_ENV: &str = "RENCLAVE_QEMU_CPUS";
+/// Environment variable for the guest vsock CID; unset attaches no vsock device
+pub const QEMU_VSOCK_CID_ENV: &str = "RENCLAVE_QEMU_VSOCK_CID";
+
+/// Smallest guest memory accepted
+const MIN_MEMORY_MIB: u32 = 64;
+/// CIDs below 3 are reserved for the hypervisor and the host
+const MIN_GUEST_CID: u32 = 3;
+
+/// QEMU guest running the enclave
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QemuConfig {
+    pub binary: String,
+    pub kernel: String,
+    pub initrd: Option<String>,
+    /// Kernel command line
+    pub append: String,
+    pub memory_mib: u32,
+    pub cpus: u32,
+    /// Existing TAP interface the guest network is attached to
+    pub tap_interface: String,
+    pub vsock_cid: Option<u32>,
+    /// Use KVM acceleration instead of TCG emulation
+    pub kvm: bool,
+}
+
+impl QemuConfig {
+    /// Guest booting `kernel` with default resources, on `tap_interface`
+    pub fn new(kernel: impl Into<String>, tap_interface: impl Into<String>) -> Self {
+        Self {
+            binary: "qemu-system-x86_64".to_string(),
+            kernel: kernel.into(),
+            initrd: None,
+            // A guest panic ends QEMU, so the supervisor sees the crash
+            append: "console=ttyS0 panic=-1".to_string(),
+            memory_mib: 512,
+            cpus: 2,
+            tap_interface: tap_interface.into(),
+            vsock_cid: None,
+            kvm: Path::new("/dev/kvm").exists(),
+        }
+    }
+
+    /// Configuration from the environment, `None` if the host does not launch QEMU
+    pub fn from_env(network: &NetworkConfig) -> Result<Option<Self>, String> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let number = |name: &str| -> Result<Option<u32>, String> {
+            var(name)
+                .map(|v| {
+                    v.parse::<u32>()
+                        .map_err(|_| format!("{} must be a number, got '{}'", name, v))
+                })
+                .transpose()
+        };
+
+        let Some(kernel) = var(QEMU_KERNEL_ENV) else {
+            return Ok(None);
+        };
+        let mut config = Self::new(kernel, &network.tap_interface);
+        if let Some(binary) = var(QEMU_BINARY_ENV) {
+            config.binary = binary;
+        }
+        config.initrd = var(QEMU_INITRD_ENV);
+        if let Some(append) = var(QEMU_APPEND_ENV) {
+            config.append = append;
+        }
+        if let Some(memory_mib) = number(QEMU_MEMORY_MIB_ENV)? {
+            config.memory_mib = memory_mib;
+        }
+        if let Some(cpus) = number(QEMU_CPUS_ENV)? {
+            config.cpus = cpus;
+        }
+        config.vsock_cid = number(QEMU_VSOCK_CID_ENV)?;
+
+        config.validate()?;
+        Ok(Some(config))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.memory_mib < MIN_MEMORY_MIB {
+            return Err(format!(
+                "{} must be at least {}",
+                QEMU_MEMORY_MIB_ENV, MIN_MEMORY_MIB
+            ));
+        }
+        if self.cpus == 0 {
+            return Err(format!("{} must be at least 1", QEMU_CPUS_ENV));
+        }
+        if self.vsock_cid.is_some_and(|cid| cid < MIN_GUEST_CID) {
+            return Err(format!(
+                "{} must be at least {}",
+                QEMU_VSOCK_CID_ENV, MIN_GUEST_CID
+            ));
+        }
+        Ok(())
+    }
+
+    /// QEMU arguments: headless with the serial console on stdout, exiting instead of
+    /// rebooting so the supervisor handles restarts
+    pub fn args(&self) -> Vec<String> {
+        let mut args: Vec<String> = vec!["-nographic".into(), "-no-reboot".into()];
+        if self.kvm {
+            args.extend(["-accel", "kvm", "-cpu", "host"].map(String::from));
+        } else {
+            args.extend(["-accel", "tcg"].map(String::from));
+        }
+        args.extend([
+            "-m".to_string(),
+            self.memory_mib.to_string(),
+            "-smp".to_string(),
+            self.cpus.to_string(),
+            "-kernel".to_string(),
+            self.kernel.clone(),
+        ]);
+        if let Some(initrd) = &self.initrd {
+            args.extend(["-initrd".to_string(), initrd.clone()]);
+        }
+        args.extend([
+            "-append".to_string(),
+            self.append.clone(),
+            "-netdev".to_string(),
+            format!(
+                "tap,id=net0,ifname={},script=no,downscript=no",
+                self.tap_interface
+            ),
+            "-device".to_string(),
+            "virtio-net-pci,netdev=net0".to_string(),
+        ]);
+        if let Some(cid) = self.vsock_cid {
+            args.extend([
+                "-device".to_string(),
+                format!("vhost-vsock-pci,guest-cid={}", cid),
+            ]);
+        }
+        args
+    }
+
+    /// Supervise QEMU like any other enclave command
+    pub fn supervisor_config(&self) -> Result<SupervisorConfig, String> {
+        SupervisorConfig::for_command(self.binary.clone(), self.args())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let mut config = QemuConfig::new("/boot/vmlinuz", "tap0");
+        config.kvm = false;
+        config.initrd = Some("/boot/initramfs.img".to_string());
+        config.vsock_cid = Some(16);
+
+        assert_eq!(
+            config.args().join(" "),
+            "-nographic -no-reboot -accel tcg -m 512 -smp 2 -kernel /boot/vmlinuz \
+             -initrd /boot/initramfs.img -append console=ttyS0 panic=-1 \
+             -netdev tap,id=net0,ifname=tap0,script=no,downscript=no \
+             -device virtio-net-pci,netdev=net0 -device vhost-vsock-pci,guest-cid=16"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = QemuConfig::new("/boot/vmlinuz", "tap0");
+        assert!(config.validate().is_ok());
+
+        config.vsock_cid = Some(2);
+        assert!(config.validate().is_err());
+
+        config.vsock_cid = None;
+        config.memory_mib = 16;
+        assert!(config.validate().is_err());
+    }
+}
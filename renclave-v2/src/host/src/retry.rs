@@ -0,0 +1,248 @@
+use log::{info, warn};
+use rand::Rng;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Environment variable for the number of connection attempts per enclave request
+pub const RETRY_ATTEMPTS_ENV: &str = "RENCLAVE_ENCLAVE_RETRY_ATTEMPTS";
+/// Environment variable for the initial retry backoff in milliseconds
+pub const RETRY_BACKOFF_MS_ENV: &str = "RENCLAVE_ENCLAVE_RETRY_BACKOFF_MS";
+/// Environment variable for consecutive failures before the circuit breaker opens
+pub const BREAKER_THRESHOLD_ENV: &str = "RENCLAVE_ENCLAVE_BREAKER_THRESHOLD";
+/// Environment variable for how long the circuit breaker stays open, in seconds
+pub const BREAKER_RESET_SECS_ENV: &str = "RENCLAVE_ENCLAVE_BREAKER_RESET_SECS";
+
+fn env_u64(name: &str) -> Option<u64> {
+    std::env::var(name).ok().and_then(|v| v.parse().ok())
+}
+
+/// Exponential backoff policy for enclave connection attempts
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Default policy with overrides from the environment
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Some(attempts) = env_u64(RETRY_ATTEMPTS_ENV) {
+            policy.max_attempts = attempts.max(1) as u32;
+        }
+        if let Some(backoff_ms) = env_u64(RETRY_BACKOFF_MS_ENV) {
+            policy.initial_backoff = Duration::from_millis(backoff_ms);
+        }
+        policy
+    }
+
+    /// Backoff before retrying after failed attempt `attempt` (1-based), without jitter
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()))
+    }
+
+    /// Backoff with jitter, uniformly distributed between half and all of `backoff(attempt)`
+    pub fn jittered_backoff(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff(attempt);
+        let half = backoff / 2;
+        half + rand::thread_rng().gen_range(Duration::ZERO..=half)
+    }
+}
+
+/// State of the enclave circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+    /// Requests fail fast without touching the socket
+    Open,
+    /// Reset timeout elapsed; a single trial request is allowed through
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct BreakerInner {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+/// Circuit breaker guarding the enclave socket.
+///
+/// Opens after `failure_threshold` consecutive communication failures so the host stops
+/// piling requests onto a restarting enclave, then lets one trial request through after
+/// `reset_timeout`.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    reset_timeout: Duration,
+    inner: Mutex<BreakerInner>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(10))
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, reset_timeout: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            reset_timeout,
+            inner: Mutex::new(BreakerInner::default()),
+        }
+    }
+
+    /// Default breaker with overrides from the environment
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self::new(
+            env_u64(BREAKER_THRESHOLD_ENV)
+                .map(|t| t as u32)
+                .unwrap_or(default.failure_threshold),
+            env_u64(BREAKER_RESET_SECS_ENV)
+                .map(Duration::from_secs)
+                .unwrap_or(default.reset_timeout),
+        )
+    }
+
+    fn state_of(&self, inner: &BreakerInner) -> CircuitState {
+        match inner.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() >= self.reset_timeout => CircuitState::HalfOpen,
+            Some(_) => CircuitState::Open,
+        }
+    }
+
+    /// Current breaker state
+    #[allow(dead_code)]
+    pub fn state(&self) -> CircuitState {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        self.state_of(&inner)
+    }
+
+    /// Whether a request may be attempted now
+    pub fn allow_request(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        match self.state_of(&inner) {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen if inner.trial_in_flight => false,
+            CircuitState::HalfOpen => {
+                inner.trial_in_flight = true;
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.opened_at.is_some() {
+            info!("✅ Enclave circuit breaker closed");
+        }
+        *inner = BreakerInner::default();
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.consecutive_failures += 1;
+        inner.trial_in_flight = false;
+
+        let reopen = inner.opened_at.is_some();
+        if reopen || inner.consecutive_failures >= self.failure_threshold {
+            if !reopen {
+                warn!(
+                    "⚠️  Enclave circuit breaker opened after {} consecutive failures",
+                    inner.consecutive_failures
+                );
+            }
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            multiplier: 2.0,
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(50), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_jittered_backoff_bounds() {
+        let policy = RetryPolicy::default();
+        for attempt in 1..6 {
+            let base = policy.backoff(attempt);
+            let jittered = policy.jittered_backoff(attempt);
+            assert!(jittered >= base / 2 && jittered <= base);
+        }
+    }
+
+    #[test]
+    fn test_breaker_opens_after_threshold() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+
+        for _ in 0..2 {
+            assert!(breaker.allow_request());
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow_request());
+    }
+
+    #[test]
+    fn test_breaker_half_open_allows_single_trial() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.record_failure();
+
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.allow_request());
+        assert!(!breaker.allow_request());
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.allow_request());
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
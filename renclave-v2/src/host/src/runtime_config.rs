@@ -0,0 +1,149 @@
+use log::info;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::retry::RetryPolicy;
+use renclave_shared::{RuntimeConfig, UpdateRuntimeConfigRequest};
+
+/// Environment variable for how long to wait for an enclave response, in seconds
+pub const ENCLAVE_TIMEOUT_SECS_ENV: &str = "RENCLAVE_ENCLAVE_TIMEOUT_SECS";
+
+/// Longest accepted enclave response timeout
+const MAX_ENCLAVE_TIMEOUT_SECS: u64 = 600;
+/// Most connection attempts accepted per enclave request
+const MAX_RETRY_ATTEMPTS: u32 = 20;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    retry_policy: RetryPolicy,
+    enclave_timeout: Duration,
+}
+
+/// Host settings that can be changed through `/admin/config` while requests are in flight.
+///
+/// Requests read the settings when they start, so a change applies to the next request.
+/// Body limits and listen addresses are fixed at startup.
+#[derive(Debug)]
+pub struct RuntimeSettings {
+    inner: RwLock<Settings>,
+}
+
+impl RuntimeSettings {
+    pub fn new(retry_policy: RetryPolicy, enclave_timeout: Duration) -> Self {
+        Self {
+            inner: RwLock::new(Settings {
+                retry_policy,
+                enclave_timeout,
+            }),
+        }
+    }
+
+    /// Default settings with overrides from the environment
+    pub fn from_env() -> Self {
+        let enclave_timeout = std::env::var(ENCLAVE_TIMEOUT_SECS_ENV)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| (1..=MAX_ENCLAVE_TIMEOUT_SECS).contains(secs))
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(30));
+
+        Self::new(RetryPolicy::from_env(), enclave_timeout)
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.inner.read().unwrap().retry_policy.clone()
+    }
+
+    pub fn enclave_timeout(&self) -> Duration {
+        self.inner.read().unwrap().enclave_timeout
+    }
+
+    /// Current settings as reported by the API
+    pub fn config(&self) -> RuntimeConfig {
+        let settings = self.inner.read().unwrap();
+        RuntimeConfig {
+            enclave_timeout_secs: settings.enclave_timeout.as_secs(),
+            retry_attempts: settings.retry_policy.max_attempts,
+            retry_backoff_ms: settings.retry_policy.initial_backoff.as_millis() as u64,
+        }
+    }
+
+    /// Apply `update`, all or nothing, and return the new settings
+    pub fn update(&self, update: &UpdateRuntimeConfigRequest) -> Result<RuntimeConfig, String> {
+        if let Some(secs) = update.enclave_timeout_secs {
+            if !(1..=MAX_ENCLAVE_TIMEOUT_SECS).contains(&secs) {
+                return Err(format!(
+                    "enclave_timeout_secs must be between 1 and {}",
+                    MAX_ENCLAVE_TIMEOUT_SECS
+                ));
+            }
+        }
+        if let Some(attempts) = update.retry_attempts {
+            if !(1..=MAX_RETRY_ATTEMPTS).contains(&attempts) {
+                return Err(format!(
+                    "retry_attempts must be between 1 and {}",
+                    MAX_RETRY_ATTEMPTS
+                ));
+            }
+        }
+
+        {
+            let mut settings = self.inner.write().unwrap();
+            if let Some(secs) = update.enclave_timeout_secs {
+                settings.enclave_timeout = Duration::from_secs(secs);
+            }
+            if let Some(attempts) = update.retry_attempts {
+                settings.retry_policy.max_attempts = attempts;
+            }
+            if let Some(backoff_ms) = update.retry_backoff_ms {
+                settings.retry_policy.initial_backoff = Duration::from_millis(backoff_ms);
+            }
+        }
+
+        let config = self.config();
+        info!("⚙️  Runtime config updated: {:?}", config);
+        Ok(config)
+    }
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self::new(RetryPolicy::default(), Duration::from_secs(30))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_is_partial() {
+        let settings = RuntimeSettings::default();
+
+        let config = settings
+            .update(&UpdateRuntimeConfigRequest {
+                retry_attempts: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(config.retry_attempts, 2);
+        assert_eq!(config.enclave_timeout_secs, 30);
+        assert_eq!(settings.retry_policy().max_attempts, 2);
+    }
+
+    #[test]
+    fn test_invalid_update_changes_nothing() {
+        let settings = RuntimeSettings::default();
+        let before = settings.config();
+
+        let result = settings.update(&UpdateRuntimeConfigRequest {
+            enclave_timeout_secs: Some(5),
+            retry_attempts: Some(0),
+            retry_backoff_ms: None,
+        });
+
+        assert!(result.is_err());
+        assert_eq!(settings.config(), before);
+    }
+}
@@ -0,0 +1,208 @@
+use log::{debug, info, warn};
+use renclave_shared::EnclaveResult;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+use crate::enclave_client::EnclaveApi;
+
+/// Environment variable for the enclave session poll interval (seconds, 0 disables)
+pub const SESSION_POLL_INTERVAL_ENV: &str = "RENCLAVE_ENCLAVE_SESSION_POLL_SECS";
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Enclave session as seen by the host.
+///
+/// The enclave generates a fresh `enclave_id` on every start, so a changed id in GetInfo
+/// means the enclave restarted and lost all in-memory state. Each restart starts a new
+/// host-side `epoch`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionInfo {
+    pub enclave_id: String,
+    pub epoch: u64,
+    pub established_at: u64,
+}
+
+/// Session lifecycle events
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EnclaveEvent {
+    /// First enclave instance observed by this host
+    Connected { enclave_id: String, epoch: u64 },
+    /// Enclave instance changed since the last observation
+    Restarted {
+        previous_enclave_id: String,
+        enclave_id: String,
+        epoch: u64,
+    },
+}
+
+/// Tracks the enclave session and broadcasts restart events
+pub struct EnclaveSession {
+    current: Mutex<Option<SessionInfo>>,
+    events: broadcast::Sender<EnclaveEvent>,
+}
+
+impl Default for EnclaveSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnclaveSession {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(16);
+        Self {
+            current: Mutex::new(None),
+            events,
+        }
+    }
+
+    /// Current session, if an enclave has been observed
+    pub fn current(&self) -> Option<SessionInfo> {
+        self.current
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Subscribe to session events
+    pub fn subscribe(&self) -> broadcast::Receiver<EnclaveEvent> {
+        self.events.subscribe()
+    }
+
+    /// Record the enclave id reported by GetInfo, emitting an event if the session changed
+    pub fn observe(&self, enclave_id: &str) -> Option<EnclaveEvent> {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+
+        let event = match current.as_ref() {
+            Some(session) if session.enclave_id == enclave_id => return None,
+            Some(session) => EnclaveEvent::Restarted {
+                previous_enclave_id: session.enclave_id.clone(),
+                enclave_id: enclave_id.to_string(),
+                epoch: session.epoch + 1,
+            },
+            None => EnclaveEvent::Connected {
+                enclave_id: enclave_id.to_string(),
+                epoch: 0,
+            },
+        };
+
+        let epoch = match &event {
+            EnclaveEvent::Connected { epoch, .. } | EnclaveEvent::Restarted { epoch, .. } => *epoch,
+        };
+        *current = Some(SessionInfo {
+            enclave_id: enclave_id.to_string(),
+            epoch,
+            established_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        });
+        drop(current);
+
+        match &event {
+            EnclaveEvent::Connected { enclave_id, .. } => {
+                info!("🔗 Enclave session established: {}", enclave_id)
+            }
+            EnclaveEvent::Restarted {
+                previous_enclave_id,
+                enclave_id,
+                epoch,
+            } => warn!(
+                "🔄 Enclave restarted ({} -> {}), session epoch {}",
+                previous_enclave_id, enclave_id, epoch
+            ),
+        }
+
+        // No subscribers is fine; events are also logged
+        let _ = self.events.send(event.clone());
+        Some(event)
+    }
+
+    /// Read the poll interval from the environment
+    pub fn poll_interval_from_env() -> Option<Duration> {
+        match std::env::var(SESSION_POLL_INTERVAL_ENV) {
+            Ok(value) => value
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            Err(_) => Some(DEFAULT_POLL_INTERVAL),
+        }
+    }
+
+    /// Poll GetInfo in the background to detect enclave restarts
+    pub fn spawn_monitor(self: &Arc<Self>, client: Arc<dyn EnclaveApi>, interval: Duration) {
+        info!("🔄 Monitoring enclave session every {:?}", interval);
+
+        let session = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match client.get_info().await {
+                    Ok(response) => {
+                        if let EnclaveResult::Info { enclave_id, .. } = response.result {
+                            session.observe(&enclave_id);
+                        }
+                    }
+                    Err(e) => debug!("🔄 Enclave session poll failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_observation_connects() {
+        let session = EnclaveSession::new();
+        assert!(session.current().is_none());
+
+        let event = session.observe("enclave-a").unwrap();
+        assert_eq!(
+            event,
+            EnclaveEvent::Connected {
+                enclave_id: "enclave-a".to_string(),
+                epoch: 0
+            }
+        );
+        assert_eq!(session.current().unwrap().enclave_id, "enclave-a");
+    }
+
+    #[test]
+    fn test_same_enclave_emits_nothing() {
+        let session = EnclaveSession::new();
+        session.observe("enclave-a");
+        assert!(session.observe("enclave-a").is_none());
+        assert_eq!(session.current().unwrap().epoch, 0);
+    }
+
+    #[tokio::test]
+    async fn test_restart_advances_epoch_and_broadcasts() {
+        let session = EnclaveSession::new();
+        let mut events = session.subscribe();
+
+        session.observe("enclave-a");
+        session.observe("enclave-b");
+
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            EnclaveEvent::Connected { .. }
+        ));
+        assert_eq!(
+            events.recv().await.unwrap(),
+            EnclaveEvent::Restarted {
+                previous_enclave_id: "enclave-a".to_string(),
+                enclave_id: "enclave-b".to_string(),
+                epoch: 1
+            }
+        );
+        assert_eq!(session.current().unwrap().epoch, 1);
+    }
+}
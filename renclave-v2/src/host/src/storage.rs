@@ -0,0 +1,383 @@
+use anyhow::{anyhow, Context, Result};
+use log::info;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Environment variable selecting the storage backend: `memory`, `sled` or `sqlite`
+pub const STORAGE_BACKEND_ENV: &str = "RENCLAVE_STORAGE_BACKEND";
+/// Environment variable for the database path of the persistent backends
+pub const STORAGE_PATH_ENV: &str = "RENCLAVE_STORAGE_PATH";
+
+const DEFAULT_PATH: &str = "/var/lib/renclave/host";
+
+/// Key-value storage for host-side data, split into namespaces (one per subsystem).
+///
+/// Only non-secret data belongs here: the host never sees plaintext seeds or keys, and nothing
+/// stored here is encrypted at rest.
+pub trait Storage: Send + Sync {
+    /// Backend name as accepted by `RENCLAVE_STORAGE_BACKEND`
+    fn backend(&self) -> &'static str;
+
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>>;
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<()>;
+
+    /// Remove `key`; removing a missing key is not an error
+    fn delete(&self, namespace: &str, key: &str) -> Result<()>;
+
+    /// All entries in `namespace`, ordered by key
+    fn list(&self, namespace: &str) -> Result<Vec<(String, Vec<u8>)>>;
+}
+
+/// JSON helpers over any [`Storage`]
+pub trait StorageExt {
+    fn get_json<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Result<Option<T>>;
+
+    fn put_json<T: Serialize>(&self, namespace: &str, key: &str, value: &T) -> Result<()>;
+
+    fn list_json<T: DeserializeOwned>(&self, namespace: &str) -> Result<Vec<(String, T)>>;
+}
+
+impl<S: Storage + ?Sized> StorageExt for S {
+    fn get_json<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Result<Option<T>> {
+        self.get(namespace, key)?
+            .map(|value| {
+                serde_json::from_slice(&value)
+                    .with_context(|| format!("Corrupt entry {}/{}", namespace, key))
+            })
+            .transpose()
+    }
+
+    fn put_json<T: Serialize>(&self, namespace: &str, key: &str, value: &T) -> Result<()> {
+        let value = serde_json::to_vec(value).context("Failed to encode entry")?;
+        self.put(namespace, key, &value)
+    }
+
+    fn list_json<T: DeserializeOwned>(&self, namespace: &str) -> Result<Vec<(String, T)>> {
+        self.list(namespace)?
+            .into_iter()
+            .map(|(key, value)| {
+                let value = serde_json::from_slice(&value)
+                    .with_context(|| format!("Corrupt entry {}/{}", namespace, key))?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+}
+
+/// Open the backend selected in the environment; defaults to in-memory storage
+pub fn open_from_env() -> Result<Arc<dyn Storage>> {
+    let backend = std::env::var(STORAGE_BACKEND_ENV).unwrap_or_else(|_| "memory".to_string());
+    let path = std::env::var(STORAGE_PATH_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_PATH));
+
+    let storage = open(backend.trim(), &path)?;
+    if storage.backend() == "memory" {
+        info!("💾 Using in-memory storage, host data is lost on restart");
+    } else {
+        info!(
+            "💾 Using {} storage at {}",
+            storage.backend(),
+            path.display()
+        );
+    }
+    Ok(storage)
+}
+
+/// Open `backend` with its database at `path` (ignored for `memory`)
+pub fn open(backend: &str, path: &Path) -> Result<Arc<dyn Storage>> {
+    let storage: Arc<dyn Storage> = match backend {
+        "memory" => Arc::new(MemoryStorage::default()),
+        #[cfg(feature = "sled")]
+        "sled" => Arc::new(SledStorage::open(path).with_context(|| open_failed(backend, path))?),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => {
+            Arc::new(SqliteStorage::open(path).with_context(|| open_failed(backend, path))?)
+        }
+        #[cfg(not(feature = "sled"))]
+        "sled" => return Err(not_compiled_in(backend, path)),
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => return Err(not_compiled_in(backend, path)),
+        other => return Err(anyhow!("Unknown storage backend {}", other)),
+    };
+    Ok(storage)
+}
+
+#[cfg(any(feature = "sled", feature = "sqlite"))]
+fn open_failed(backend: &str, path: &Path) -> String {
+    format!("Failed to open {} storage at {}", backend, path.display())
+}
+
+#[cfg(not(all(feature = "sled", feature = "sqlite")))]
+fn not_compiled_in(backend: &str, path: &Path) -> anyhow::Error {
+    anyhow!(
+        "Storage backend {} for {} is not compiled in (enable the `{}` feature)",
+        backend,
+        path.display(),
+        backend
+    )
+}
+
+/// Non-persistent storage, the default and the backend used by tests
+#[derive(Default)]
+pub struct MemoryStorage {
+    entries: Mutex<BTreeMap<(String, String), Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<(String, String), Vec<u8>>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn backend(&self) -> &'static str {
+        "memory"
+    }
+
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .lock()
+            .get(&(namespace.to_string(), key.to_string()))
+            .cloned())
+    }
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<()> {
+        self.lock()
+            .insert((namespace.to_string(), key.to_string()), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<()> {
+        self.lock()
+            .remove(&(namespace.to_string(), key.to_string()));
+        Ok(())
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        Ok(self
+            .lock()
+            .iter()
+            .filter(|((entry_namespace, _), _)| entry_namespace == namespace)
+            .map(|((_, key), value)| (key.clone(), value.clone()))
+            .collect())
+    }
+}
+
+/// Embedded sled database with one tree per namespace
+#[cfg(feature = "sled")]
+pub struct SledStorage {
+    db: sled::Db,
+}
+
+#[cfg(feature = "sled")]
+impl SledStorage {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            db: sled::open(path)?,
+        })
+    }
+}
+
+#[cfg(feature = "sled")]
+impl Storage for SledStorage {
+    fn backend(&self) -> &'static str {
+        "sled"
+    }
+
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .db
+            .open_tree(namespace)?
+            .get(key)?
+            .map(|value| value.to_vec()))
+    }
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<()> {
+        self.db.open_tree(namespace)?.insert(key, value)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<()> {
+        self.db.open_tree(namespace)?.remove(key)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        self.db
+            .open_tree(namespace)?
+            .iter()
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((String::from_utf8_lossy(&key).into_owned(), value.to_vec()))
+            })
+            .collect()
+    }
+}
+
+/// SQLite database with a single `(namespace, key, value)` table
+#[cfg(feature = "sqlite")]
+pub struct SqliteStorage {
+    connection: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStorage {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let connection = rusqlite::Connection::open(path)?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS entries (
+                 namespace TEXT NOT NULL,
+                 key TEXT NOT NULL,
+                 value BLOB NOT NULL,
+                 PRIMARY KEY (namespace, key)
+             );",
+        )?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, rusqlite::Connection> {
+        self.connection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Storage for SqliteStorage {
+    fn backend(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        use rusqlite::OptionalExtension;
+
+        Ok(self
+            .lock()
+            .query_row(
+                "SELECT value FROM entries WHERE namespace = ?1 AND key = ?2",
+                (namespace, key),
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<()> {
+        self.lock().execute(
+            "INSERT INTO entries (namespace, key, value) VALUES (?1, ?2, ?3)
+             ON CONFLICT (namespace, key) DO UPDATE SET value = excluded.value",
+            (namespace, key, value),
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<()> {
+        self.lock().execute(
+            "DELETE FROM entries WHERE namespace = ?1 AND key = ?2",
+            (namespace, key),
+        )?;
+        Ok(())
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let connection = self.lock();
+        let mut statement = connection
+            .prepare("SELECT key, value FROM entries WHERE namespace = ?1 ORDER BY key")?;
+        let rows = statement.query_map([namespace], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(storage: &dyn Storage) {
+        storage.put("jobs", "b", b"2").unwrap();
+        storage.put("jobs", "a", b"1").unwrap();
+        storage.put("other", "a", b"x").unwrap();
+        storage.put("jobs", "b", b"3").unwrap();
+
+        assert_eq!(storage.get("jobs", "a").unwrap(), Some(b"1".to_vec()));
+        assert_eq!(storage.get("jobs", "missing").unwrap(), None);
+        assert_eq!(
+            storage.list("jobs").unwrap(),
+            vec![
+                ("a".to_string(), b"1".to_vec()),
+                ("b".to_string(), b"3".to_vec())
+            ]
+        );
+
+        storage.delete("jobs", "a").unwrap();
+        storage.delete("jobs", "a").unwrap();
+        assert_eq!(storage.get("jobs", "a").unwrap(), None);
+        assert_eq!(storage.get("other", "a").unwrap(), Some(b"x".to_vec()));
+
+        storage.put_json("json", "k", &vec![1, 2]).unwrap();
+        assert_eq!(
+            storage.get_json::<Vec<u32>>("json", "k").unwrap(),
+            Some(vec![1, 2])
+        );
+    }
+
+    #[cfg(any(feature = "sled", feature = "sqlite"))]
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "renclave-storage-{}-{}",
+            name,
+            uuid::Uuid::new_v4()
+        ))
+    }
+
+    #[test]
+    fn test_memory_storage() {
+        exercise(&MemoryStorage::default());
+    }
+
+    #[cfg(feature = "sled")]
+    #[test]
+    fn test_sled_storage_persists() {
+        let path = temp_path("sled");
+        let storage = SledStorage::open(&path).unwrap();
+        exercise(&storage);
+        // Close the database before reopening it. sled's IO threads can still hold it for a
+        // moment after the last handle drops, so also wait on its lock file.
+        storage.db.flush().unwrap();
+        drop(storage);
+        let lock = std::fs::File::open(path.join("db")).unwrap();
+        lock.lock().unwrap();
+        drop(lock);
+
+        let reopened = open("sled", &path).unwrap();
+        assert_eq!(reopened.get("jobs", "b").unwrap(), Some(b"3".to_vec()));
+        drop(reopened);
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_storage_persists() {
+        let dir = temp_path("sqlite");
+        let path = dir.join("host.db");
+        exercise(open("sqlite", &path).unwrap().as_ref());
+
+        let reopened = open("sqlite", &path).unwrap();
+        assert_eq!(reopened.get("jobs", "b").unwrap(), Some(b"3".to_vec()));
+        drop(reopened);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_unknown_backend() {
+        assert!(open("postgres", Path::new("/tmp/unused")).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+use log::{error, info, warn};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::watch;
+
+use renclave_shared::EnclaveProcessStatus;
+
+/// Environment variable with the enclave command line; setting it enables supervision
+pub const ENCLAVE_COMMAND_ENV: &str = "RENCLAVE_ENCLAVE_COMMAND";
+/// Environment variable for when the enclave is restarted: `always`, `on-failure` or `never`
+pub const RESTART_POLICY_ENV: &str = "RENCLAVE_ENCLAVE_RESTART_POLICY";
+/// Environment variable for how often the enclave is restarted before giving up
+pub const MAX_RESTARTS_ENV: &str = "RENCLAVE_ENCLAVE_MAX_RESTARTS";
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// A process running this long is considered healthy and resets the backoff
+const STABLE_RUN: Duration = Duration::from_secs(60);
+/// Output lines kept for `/admin/enclave/console`
+const CONSOLE_LINES: usize = 1000;
+
+/// When an exited enclave process is started again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    Always,
+    OnFailure,
+    Never,
+}
+
+impl RestartPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "always" => Some(Self::Always),
+            "on-failure" => Some(Self::OnFailure),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+
+    fn restarts_after(self, status: &ExitStatus) -> bool {
+        match self {
+            Self::Always => true,
+            Self::OnFailure => !status.success(),
+            Self::Never => false,
+        }
+    }
+}
+
+/// How the host launches and restarts the enclave
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupervisorConfig {
+    pub program: String,
+    pub args: Vec<String>,
+    pub restart_policy: RestartPolicy,
+    /// `None` restarts without limit
+    pub max_restarts: Option<u32>,
+    /// Delay before the first restart, doubled for each quick successive crash
+    pub initial_backoff: Duration,
+    /// Network namespace the process is started in, e.g. `/var/run/netns/enclave`
+    pub netns: Option<PathBuf>,
+}
+
+impl SupervisorConfig {
+    /// Configuration from the environment, `None` if supervision is not enabled.
+    ///
+    /// The command line is split on whitespace; wrap it in a script if it needs quoting.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(command) = std::env::var(ENCLAVE_COMMAND_ENV)
+            .ok()
+            .filter(|command| !command.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+        let mut words = command.split_whitespace().map(str::to_string);
+        let program = words.next().unwrap_or_default();
+
+        Self::for_command(program, words.collect()).map(Some)
+    }
+
+    /// Supervise `program` with the restart settings from the environment
+    pub fn for_command(program: String, args: Vec<String>) -> Result<Self, String> {
+        let restart_policy = match std::env::var(RESTART_POLICY_ENV) {
+            Ok(value) => RestartPolicy::parse(&value).ok_or_else(|| {
+                format!(
+                    "{} must be always, on-failure or never, got '{}'",
+                    RESTART_POLICY_ENV, value
+                )
+            })?,
+            Err(_) => RestartPolicy::OnFailure,
+        };
+        let max_restarts = std::env::var(MAX_RESTARTS_ENV)
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|max| *max > 0);
+
+        Ok(Self {
+            program,
+            args,
+            restart_policy,
+            max_restarts,
+            initial_backoff: Duration::from_secs(1),
+            netns: None,
+        })
+    }
+
+    fn command_line(&self) -> String {
+        std::iter::once(&self.program)
+            .chain(&self.args)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Launches the enclave process, restarts it per policy and forwards its output to the log.
+///
+/// The last lines of output are kept for the console endpoint. The process can be stopped
+/// and started again through the admin API, and is killed when the host exits.
+pub struct Supervisor {
+    config: SupervisorConfig,
+    status: Mutex<EnclaveProcessStatus>,
+    console: Arc<Mutex<VecDeque<String>>>,
+    /// Whether the process should be running
+    wanted: watch::Sender<bool>,
+}
+
+/// Why a process run ended
+enum Exit {
+    /// Stopped through `Supervisor::stop`
+    Requested,
+    /// Exited on its own; `None` if it could not be started or waited on
+    Exited(Option<ExitStatus>),
+}
+
+impl Supervisor {
+    /// Start the enclave process and keep it running in the background
+    pub fn spawn(config: SupervisorConfig) -> Arc<Self> {
+        info!(
+            "🧭 Supervising enclave process: {} (restart {:?})",
+            config.command_line(),
+            config.restart_policy
+        );
+        let supervisor = Arc::new(Self {
+            status: Mutex::new(EnclaveProcessStatus {
+                command: config.command_line(),
+                ..Default::default()
+            }),
+            config,
+            console: Arc::new(Mutex::new(VecDeque::new())),
+            wanted: watch::channel(true).0,
+        });
+        tokio::spawn(Arc::clone(&supervisor).run());
+        supervisor
+    }
+
+    pub fn status(&self) -> EnclaveProcessStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Up to `lines` of the most recent process output, oldest first
+    pub fn console(&self, lines: usize) -> Vec<String> {
+        let console = self.console.lock().unwrap();
+        let skip = console.len().saturating_sub(lines);
+        console.iter().skip(skip).cloned().collect()
+    }
+
+    /// Start the process again after it was stopped or given up on
+    pub fn start(&self) {
+        info!("🧭 Enclave process start requested");
+        self.status.lock().unwrap().stopped = false;
+        self.wanted.send_replace(true);
+    }
+
+    /// Kill the process and keep it stopped until `start`
+    pub fn stop(&self) {
+        info!("🧭 Enclave process stop requested");
+        self.wanted.send_replace(false);
+    }
+
+    async fn run(self: Arc<Self>) {
+        let mut wanted = self.wanted.subscribe();
+        let mut backoff = self.config.initial_backoff;
+        loop {
+            wait_for(&mut wanted, true).await;
+
+            let started = Instant::now();
+            let exit = match self.run_once(&mut wanted).await {
+                Exit::Requested => {
+                    self.status.lock().unwrap().stopped = true;
+                    backoff = self.config.initial_backoff;
+                    continue;
+                }
+                Exit::Exited(exit) => exit,
+            };
+
+            let restart = match &exit {
+                Some(status) => self.config.restart_policy.restarts_after(status),
+                None => self.config.restart_policy != RestartPolicy::Never,
+            };
+            let restarts = self.status.lock().unwrap().restarts;
+            let limit_reached = self.config.max_restarts.is_some_and(|max| restarts >= max);
+            if !restart || limit_reached {
+                warn!(
+                    "🧭 Enclave process will not be restarted ({} restarts so far)",
+                    restarts
+                );
+                self.status.lock().unwrap().stopped = true;
+                self.wanted.send_replace(false);
+                backoff = self.config.initial_backoff;
+                continue;
+            }
+
+            if started.elapsed() >= STABLE_RUN {
+                backoff = self.config.initial_backoff;
+            }
+            info!("🧭 Restarting enclave process in {:?}", backoff);
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = wait_for(&mut wanted, false) => {
+                    self.status.lock().unwrap().stopped = true;
+                    continue;
+                }
+            }
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            self.status.lock().unwrap().restarts += 1;
+        }
+    }
+
+    /// Run the process until it exits or is stopped
+    async fn run_once(&self, wanted: &mut watch::Receiver<bool>) -> Exit {
+        let mut command = Command::new(&self.config.program);
+        if let Some(path) = &self.config.netns {
+            let netns = match File::open(path) {
+                Ok(netns) => netns,
+                Err(e) => {
+                    error!(
+                        "❌ Failed to open network namespace {}: {}",
+                        path.display(),
+                        e
+                    );
+                    self.record_exit(None);
+                    return Exit::Exited(None);
+                }
+            };
+            // SAFETY: setns only makes a system call, which is safe between fork and exec
+            unsafe {
+                command.pre_exec(move || renclave_network::netns::setns(&netns));
+            }
+        }
+
+        let mut child = match command
+            .args(&self.config.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                error!("❌ Failed to start enclave process: {}", e);
+                self.record_exit(None);
+                return Exit::Exited(None);
+            }
+        };
+
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_lines(stdout, Arc::clone(&self.console)));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_lines(stderr, Arc::clone(&self.console)));
+        }
+        {
+            let mut status = self.status.lock().unwrap();
+            status.running = true;
+            status.pid = child.id();
+            status.started_at = Some(unix_now());
+        }
+
+        let result = tokio::select! {
+            result = child.wait() => result,
+            _ = wait_for(wanted, false) => {
+                if let Err(e) = child.start_kill() {
+                    warn!("⚠️  Failed to kill enclave process: {}", e);
+                }
+                let exit = child.wait().await.ok();
+                info!("🧭 Enclave process stopped");
+                self.record_exit(exit.and_then(|exit| exit.code()));
+                return Exit::Requested;
+            }
+        };
+
+        match result {
+            Ok(exit) => {
+                warn!("🧭 Enclave process exited: {}", exit);
+                self.record_exit(exit.code());
+                Exit::Exited(Some(exit))
+            }
+            Err(e) => {
+                error!("❌ Failed to wait for enclave process: {}", e);
+                self.record_exit(None);
+                Exit::Exited(None)
+            }
+        }
+    }
+
+    fn record_exit(&self, code: Option<i32>) {
+        let mut status = self.status.lock().unwrap();
+        status.running = false;
+        status.pid = None;
+        status.last_exit_code = code;
+        status.last_exit_at = Some(unix_now());
+    }
+}
+
+/// Wait until the wanted state is `value`
+async fn wait_for(wanted: &mut watch::Receiver<bool>, value: bool) {
+    while *wanted.borrow_and_update() != value {
+        if wanted.changed().await.is_err() {
+            // The supervisor is gone, so the state never changes again
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Log each line the enclave writes, under the `enclave` target, and keep it for the console
+async fn forward_lines(output: impl AsyncRead + Unpin, console: Arc<Mutex<VecDeque<String>>>) {
+    let mut lines = BufReader::new(output).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        info!(target: "enclave", "{}", line);
+        let mut console = console.lock().unwrap();
+        if console.len() == CONSOLE_LINES {
+            console.pop_front();
+        }
+        console.push_back(line);
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(script: &str, restart_policy: RestartPolicy) -> SupervisorConfig {
+        SupervisorConfig {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            restart_policy,
+            max_restarts: Some(2),
+            initial_backoff: Duration::from_millis(1),
+            netns: None,
+        }
+    }
+
+    async fn wait_until(
+        supervisor: &Supervisor,
+        condition: impl Fn(&EnclaveProcessStatus) -> bool,
+    ) -> EnclaveProcessStatus {
+        for _ in 0..500 {
+            let status = supervisor.status();
+            if condition(&status) {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not reached: {:?}", supervisor.status());
+    }
+
+    async fn wait_until_stopped(supervisor: &Supervisor) -> EnclaveProcessStatus {
+        wait_until(supervisor, |status| status.stopped).await
+    }
+
+    #[tokio::test]
+    async fn test_crashing_process_restarts_up_to_limit() {
+        let supervisor = Supervisor::spawn(config("exit 3", RestartPolicy::OnFailure));
+
+        let status = wait_until_stopped(&supervisor).await;
+        assert_eq!(status.restarts, 2);
+        assert_eq!(status.last_exit_code, Some(3));
+        assert!(!status.running);
+    }
+
+    #[tokio::test]
+    async fn test_clean_exit_is_not_restarted_on_failure_policy() {
+        let supervisor = Supervisor::spawn(config("exit 0", RestartPolicy::OnFailure));
+
+        let status = wait_until_stopped(&supervisor).await;
+        assert_eq!(status.restarts, 0);
+        assert_eq!(status.last_exit_code, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_stop_and_start() {
+        let supervisor = Supervisor::spawn(config("echo ready; sleep 30", RestartPolicy::Always));
+        wait_until(&supervisor, |s| s.running).await;
+
+        supervisor.stop();
+        let status = wait_until_stopped(&supervisor).await;
+        assert!(!status.running);
+
+        supervisor.start();
+        let status = wait_until(&supervisor, |s| s.running).await;
+        assert_eq!(status.restarts, 0);
+        wait_until(&supervisor, |_| supervisor.console(10).len() == 2).await;
+        assert_eq!(supervisor.console(1), vec!["ready".to_string()]);
+        supervisor.stop();
+    }
+
+    #[test]
+    fn test_parse_restart_policy() {
+        assert_eq!(RestartPolicy::parse("Always"), Some(RestartPolicy::Always));
+        assert_eq!(
+            RestartPolicy::parse("on-failure"),
+            Some(RestartPolicy::OnFailure)
+        );
+        assert_eq!(RestartPolicy::parse("never"), Some(RestartPolicy::Never));
+        assert_eq!(RestartPolicy::parse("sometimes"), None);
+    }
+}
@@ -0,0 +1,85 @@
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use opentelemetry::trace::{FutureExt, SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{Context, KeyValue};
+use renclave_shared::telemetry;
+
+/// Middleware recording a server span for each routed request.
+///
+/// Continues the caller's trace when the request carries a `traceparent` header. Enclave
+/// requests made by the handler become child spans.
+pub async fn trace_request(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let method = request.method().to_string();
+    let parent = telemetry::extract(
+        request
+            .headers()
+            .get("traceparent")
+            .and_then(|value| value.to_str().ok()),
+    );
+
+    let tracer = telemetry::tracer();
+    let span = tracer
+        .span_builder(format!("{} {}", method, route))
+        .with_kind(SpanKind::Server)
+        .with_attributes([
+            KeyValue::new("http.request.method", method),
+            KeyValue::new("http.route", route),
+        ])
+        .start_with_context(&tracer, &parent);
+    let cx = Context::current_with_span(span);
+
+    let response = next.run(request).with_context(cx.clone()).await;
+
+    let span = cx.span();
+    let status = response.status();
+    span.set_attribute(KeyValue::new(
+        "http.response.status_code",
+        i64::from(status.as_u16()),
+    ));
+    if status.is_server_error() {
+        span.set_status(Status::error(status.to_string()));
+    }
+    span.end();
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::{middleware, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_handlers_continue_incoming_trace() {
+        let app = Router::new()
+            .route(
+                "/traced",
+                get(|| async { telemetry::inject(&Context::current()).unwrap_or_default() }),
+            )
+            .layer(middleware::from_fn(trace_request));
+
+        let request = axum::http::Request::get("/traced")
+            .header(
+                "traceparent",
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        let traceparent = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use renclave_shared::{EnclaveResult, EnclaveTime};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::enclave_client::EnclaveApi;
+
+/// Environment variable for how often the host offers its time to the enclave (seconds, 0
+/// disables)
+pub const TIME_SYNC_INTERVAL_ENV: &str = "RENCLAVE_HOST_TIME_SYNC_SECS";
+
+const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Interval for offering host time, `None` when disabled
+pub fn interval_from_env() -> Option<Duration> {
+    match std::env::var(TIME_SYNC_INTERVAL_ENV) {
+        Ok(value) => value
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs),
+        Err(_) => Some(DEFAULT_SYNC_INTERVAL),
+    }
+}
+
+/// Offer the host's current time to the enclave, returning whether the enclave accepted it
+/// and its clock afterwards
+pub async fn sync_once(client: &dyn EnclaveApi) -> Result<(bool, EnclaveTime)> {
+    let unix_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    match client.sync_time(unix_ms).await?.result {
+        EnclaveResult::TimeSynced { accepted, time } => Ok((accepted, time)),
+        EnclaveResult::Error { message, .. } => Err(anyhow!(message)),
+        _ => Err(anyhow!("Unexpected response to SyncTime")),
+    }
+}
+
+/// Offer host time to the enclave in the background.
+///
+/// The enclave treats host time as untrusted: it only moves its clock within the error bound
+/// it already has, so a host whose own clock is off gets its offers rejected.
+pub fn spawn(client: Arc<dyn EnclaveApi>, interval: Duration) {
+    info!("🕒 Offering host time to the enclave every {:?}", interval);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match sync_once(client.as_ref()).await {
+                Ok((true, time)) => debug!(
+                    "🕒 Enclave clock at {} ms, ±{:?} ms from {:?}",
+                    time.unix_ms, time.uncertainty_ms, time.source
+                ),
+                Ok((false, time)) => warn!(
+                    "⚠️  Enclave rejected host time; its clock reads {} ms ±{:?} ms",
+                    time.unix_ms, time.uncertainty_ms
+                ),
+                Err(e) => debug!("🕒 Time sync with the enclave failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_enclave::MockEnclave;
+    use renclave_shared::TimeSource;
+
+    #[tokio::test]
+    async fn test_sync_once() {
+        let enclave = MockEnclave::new();
+        let (accepted, time) = sync_once(&enclave).await.unwrap();
+        assert!(accepted);
+        assert_eq!(time.source, TimeSource::Host);
+        assert_eq!(enclave.operations(), vec!["sync_time"]);
+    }
+}
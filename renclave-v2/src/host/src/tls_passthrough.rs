@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream, UnixStream};
+
+/// Environment variable for the TCP address whose connections are forwarded to the enclave's
+/// TLS socket; unset disables forwarding
+pub const TLS_PASSTHROUGH_ADDR_ENV: &str = "RENCLAVE_TLS_PASSTHROUGH_ADDR";
+/// Environment variable for the most connections forwarded at once
+pub const TLS_PASSTHROUGH_MAX_CONNECTIONS_ENV: &str = "RENCLAVE_TLS_PASSTHROUGH_MAX_CONNECTIONS";
+
+const DEFAULT_MAX_CONNECTIONS: usize = 256;
+
+/// Forwards TCP connections byte for byte to the enclave, which terminates TLS itself.
+///
+/// The host never holds the TLS key and only sees ciphertext, so it cannot read or alter
+/// requests and responses on these connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsPassthrough {
+    pub listen_addr: SocketAddr,
+    /// Enclave socket that terminates TLS
+    pub enclave_socket: PathBuf,
+    pub max_connections: usize,
+}
+
+impl TlsPassthrough {
+    /// Configuration from the environment, `None` unless both the listen address and the
+    /// enclave's TLS socket are set
+    pub fn from_env() -> Result<Option<Self>> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let Some(listen_addr) = var(TLS_PASSTHROUGH_ADDR_ENV) else {
+            return Ok(None);
+        };
+        let listen_addr = listen_addr
+            .parse()
+            .with_context(|| format!("Invalid {} '{}'", TLS_PASSTHROUGH_ADDR_ENV, listen_addr))?;
+        let enclave_socket = renclave_shared::enclave_tls_socket_path().ok_or_else(|| {
+            anyhow!(
+                "{} needs {} to name the enclave's TLS socket",
+                TLS_PASSTHROUGH_ADDR_ENV,
+                renclave_shared::ENCLAVE_TLS_SOCKET_ENV
+            )
+        })?;
+        let max_connections = match var(TLS_PASSTHROUGH_MAX_CONNECTIONS_ENV) {
+            Some(value) => value.parse().ok().filter(|max| *max > 0).ok_or_else(|| {
+                anyhow!(
+                    "{} must be a positive number, got '{}'",
+                    TLS_PASSTHROUGH_MAX_CONNECTIONS_ENV,
+                    value
+                )
+            })?,
+            None => DEFAULT_MAX_CONNECTIONS,
+        };
+
+        Ok(Some(Self {
+            listen_addr,
+            enclave_socket: PathBuf::from(enclave_socket),
+            max_connections,
+        }))
+    }
+
+    /// Bind the listen address and forward connections in the background
+    pub async fn spawn(self) -> Result<()> {
+        let listener = TcpListener::bind(self.listen_addr)
+            .await
+            .with_context(|| format!("Failed to bind TLS passthrough on {}", self.listen_addr))?;
+        info!(
+            "🔐 Forwarding TLS on {} to the enclave at {}",
+            self.listen_addr,
+            self.enclave_socket.display()
+        );
+        tokio::spawn(self.serve(listener));
+        Ok(())
+    }
+
+    async fn serve(self, listener: TcpListener) {
+        let open = Arc::new(AtomicUsize::new(0));
+        loop {
+            let (client, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("⚠️  Failed to accept TLS passthrough connection: {}", e);
+                    continue;
+                }
+            };
+            if open.fetch_add(1, Ordering::SeqCst) >= self.max_connections {
+                open.fetch_sub(1, Ordering::SeqCst);
+                warn!(
+                    "⚠️  Rejecting TLS passthrough connection from {}, {} already open",
+                    peer, self.max_connections
+                );
+                continue;
+            }
+
+            let enclave_socket = self.enclave_socket.clone();
+            let open = Arc::clone(&open);
+            tokio::spawn(async move {
+                match forward(client, &enclave_socket).await {
+                    Ok((sent, received)) => debug!(
+                        "🔌 TLS passthrough from {} closed: {} bytes in, {} bytes out",
+                        peer, sent, received
+                    ),
+                    Err(e) => debug!("ℹ️  TLS passthrough from {} ended: {:#}", peer, e),
+                }
+                open.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    }
+}
+
+/// Copy bytes both ways between `client` and the enclave until either side closes, returning
+/// the byte counts in each direction
+async fn forward(mut client: TcpStream, enclave_socket: &Path) -> Result<(u64, u64)> {
+    let mut enclave = UnixStream::connect(enclave_socket)
+        .await
+        .with_context(|| format!("Failed to connect to {}", enclave_socket.display()))?;
+    let counts = tokio::io::copy_bidirectional(&mut client, &mut enclave).await?;
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+
+    #[tokio::test]
+    async fn test_forwards_bytes_unchanged() {
+        let dir =
+            std::env::temp_dir().join(format!("renclave-passthrough-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let enclave_socket = dir.join("tls.sock");
+        let enclave = UnixListener::bind(&enclave_socket).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = enclave.accept().await.unwrap();
+            let mut buffer = [0u8; 5];
+            stream.read_exact(&mut buffer).await.unwrap();
+            buffer.reverse();
+            stream.write_all(&buffer).await.unwrap();
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let passthrough = TlsPassthrough {
+            listen_addr: listener.local_addr().unwrap(),
+            enclave_socket,
+            max_connections: 1,
+        };
+        let addr = passthrough.listen_addr;
+        tokio::spawn(passthrough.serve(listener));
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"\x16\x03\x01\x00\x05").await.unwrap();
+        let mut reply = Vec::new();
+        client.read_to_end(&mut reply).await.unwrap();
+        assert_eq!(reply, b"\x05\x00\x01\x03\x16");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
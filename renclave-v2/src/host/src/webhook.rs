@@ -0,0 +1,498 @@
+use anyhow::{anyhow, Context, Result};
+use bitcoin_hashes::{hmac, sha256, Hash, HashEngine};
+use log::{debug, info, warn};
+use renclave_network::{ProxyConfig, ProxyKind};
+use renclave_shared::SecretString;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::retry::RetryPolicy;
+use crate::session::EnclaveEvent;
+use crate::storage::{MemoryStorage, Storage, StorageExt};
+
+/// Environment variable listing the webhook URLs, comma-separated
+pub const WEBHOOK_URLS_ENV: &str = "RENCLAVE_WEBHOOK_URLS";
+/// Environment variable for the HMAC-SHA256 key used to sign webhook bodies
+pub const WEBHOOK_SECRET_ENV: &str = "RENCLAVE_WEBHOOK_SECRET";
+/// Environment variable for the delivery attempts per webhook and event
+pub const WEBHOOK_ATTEMPTS_ENV: &str = "RENCLAVE_WEBHOOK_ATTEMPTS";
+
+/// Unix timestamp of the delivery, also covered by the signature
+pub const TIMESTAMP_HEADER: &str = "X-Renclave-Timestamp";
+/// `sha256=<hex HMAC of "{timestamp}.{body}">`
+pub const SIGNATURE_HEADER: &str = "X-Renclave-Signature";
+
+/// Storage namespace of deliveries that have not succeeded or been given up yet
+const PENDING_NAMESPACE: &str = "webhooks";
+
+/// Events delivered to webhooks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// The enclave restarted and lost its in-memory state
+    EnclaveRestarted {
+        previous_enclave_id: String,
+        enclave_id: String,
+        epoch: u64,
+    },
+    /// The signing policy refused a PSBT
+    PolicyViolation { request_id: String, message: String },
+    /// A signature left a key due for retirement under the signing policy
+    KeyRetirementDue {
+        request_id: String,
+        public_key: String,
+        signatures: u64,
+        first_used_at: u64,
+    },
+}
+
+/// Body of a webhook delivery
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    pub id: String,
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub event: WebhookEvent,
+}
+
+/// A delivery of one payload to one URL, kept in storage until it finishes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingDelivery {
+    url: String,
+    payload_id: String,
+    body: String,
+}
+
+/// Signed POST callbacks for critical events.
+///
+/// Each event is delivered to every URL in the background, retried with exponential backoff
+/// on connection errors, timeouts and 5xx, 408 or 429 responses. Deliveries go through the
+/// egress proxy when one is configured. Pending deliveries are kept in storage and resumed by
+/// [`resume_pending`](Self::resume_pending) after a restart.
+pub struct WebhookNotifier {
+    urls: Vec<String>,
+    secret: Option<SecretString>,
+    retry: RetryPolicy,
+    client: reqwest::Client,
+    storage: Arc<dyn Storage>,
+}
+
+impl WebhookNotifier {
+    pub fn new(
+        urls: Vec<String>,
+        secret: Option<SecretString>,
+        proxy: Option<&ProxyConfig>,
+        storage: Arc<dyn Storage>,
+    ) -> Result<Self> {
+        let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(10));
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(reqwest_proxy(proxy)?);
+        }
+        Ok(Self {
+            urls,
+            secret,
+            retry: RetryPolicy {
+                max_attempts: 5,
+                initial_backoff: Duration::from_secs(1),
+                max_backoff: Duration::from_secs(60),
+                multiplier: 2.0,
+            },
+            client: builder.build().context("Failed to build webhook client")?,
+            storage,
+        })
+    }
+
+    /// Notifier for the URLs and secret in the environment, sending through `proxy`
+    pub fn from_env(proxy: Option<&ProxyConfig>, storage: Arc<dyn Storage>) -> Result<Self> {
+        let urls = std::env::var(WEBHOOK_URLS_ENV)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let secret = std::env::var(WEBHOOK_SECRET_ENV)
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(SecretString::new);
+
+        let mut notifier = Self::new(urls, secret, proxy, storage)?;
+        if let Some(attempts) = std::env::var(WEBHOOK_ATTEMPTS_ENV)
+            .ok()
+            .and_then(|value| value.parse::<u32>().ok())
+        {
+            notifier.retry.max_attempts = attempts.max(1);
+        }
+        if !notifier.urls.is_empty() {
+            info!("🪝 Delivering webhooks to {} URLs", notifier.urls.len());
+            if notifier.secret.is_none() {
+                warn!(
+                    "⚠️  {} is not set, webhooks are unsigned",
+                    WEBHOOK_SECRET_ENV
+                );
+            }
+        }
+        Ok(notifier)
+    }
+
+    /// Notifier that delivers nothing
+    pub fn disabled() -> Self {
+        Self {
+            urls: Vec::new(),
+            secret: None,
+            retry: RetryPolicy::default(),
+            client: reqwest::Client::new(),
+            storage: Arc::new(MemoryStorage::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.urls.is_empty()
+    }
+
+    /// Deliver `event` to every webhook in the background
+    pub fn notify(self: &Arc<Self>, event: WebhookEvent) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let payload = WebhookPayload {
+            id: Uuid::new_v4().to_string(),
+            timestamp: unix_now(),
+            event,
+        };
+        let body = match serde_json::to_string(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("⚠️  Failed to encode webhook {}: {}", payload.id, e);
+                return;
+            }
+        };
+
+        for (index, url) in self.urls.iter().enumerate() {
+            let key = format!("{}-{}", payload.id, index);
+            let delivery = PendingDelivery {
+                url: url.clone(),
+                payload_id: payload.id.clone(),
+                body: body.clone(),
+            };
+            if let Err(e) = self.storage.put_json(PENDING_NAMESPACE, &key, &delivery) {
+                warn!("⚠️  Failed to persist webhook {}: {}", key, e);
+            }
+            self.spawn_delivery(key, delivery);
+        }
+    }
+
+    /// Restart deliveries left pending by a previous run
+    pub fn resume_pending(self: &Arc<Self>) {
+        let pending = match self.storage.list_json::<PendingDelivery>(PENDING_NAMESPACE) {
+            Ok(pending) => pending,
+            Err(e) => {
+                warn!("⚠️  Failed to read pending webhooks: {}", e);
+                return;
+            }
+        };
+        if !pending.is_empty() {
+            info!("🪝 Resuming {} pending webhook deliveries", pending.len());
+        }
+        for (key, delivery) in pending {
+            if self.urls.contains(&delivery.url) {
+                self.spawn_delivery(key, delivery);
+            } else if let Err(e) = self.storage.delete(PENDING_NAMESPACE, &key) {
+                warn!(
+                    "⚠️  Failed to drop webhook {} for a removed URL: {}",
+                    key, e
+                );
+            }
+        }
+    }
+
+    fn spawn_delivery(self: &Arc<Self>, key: String, delivery: PendingDelivery) {
+        let notifier = Arc::clone(self);
+        tokio::spawn(async move {
+            if let Err(e) = notifier.deliver(&delivery).await {
+                warn!(
+                    "⚠️  Giving up on webhook {} to {}: {}",
+                    delivery.payload_id, delivery.url, e
+                );
+            }
+            if let Err(e) = notifier.storage.delete(PENDING_NAMESPACE, &key) {
+                warn!("⚠️  Failed to remove finished webhook {}: {}", key, e);
+            }
+        });
+    }
+
+    /// Forward enclave restarts from `events` to the webhooks
+    pub fn forward_session_events(self: &Arc<Self>, mut events: broadcast::Receiver<EnclaveEvent>) {
+        let notifier = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(EnclaveEvent::Restarted {
+                        previous_enclave_id,
+                        enclave_id,
+                        epoch,
+                    }) => notifier.notify(WebhookEvent::EnclaveRestarted {
+                        previous_enclave_id,
+                        enclave_id,
+                        epoch,
+                    }),
+                    Ok(EnclaveEvent::Connected { .. }) => {}
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("⚠️  Missed {} enclave session events", missed)
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Deliver until the receiver accepts, rejects or the attempts run out. Every attempt is
+    /// signed with its own timestamp.
+    async fn deliver(&self, delivery: &PendingDelivery) -> Result<()> {
+        let (url, id, body) = (&delivery.url, &delivery.payload_id, &delivery.body);
+        let mut attempt = 1;
+        loop {
+            let timestamp = unix_now();
+            let mut request = self
+                .client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .body(body.clone());
+            if let Some(secret) = &self.secret {
+                request = request.header(SIGNATURE_HEADER, sign(secret, timestamp, body));
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    debug!("✅ Webhook {} delivered to {}", id, url);
+                    return Ok(());
+                }
+                Ok(response) if !is_retryable(response.status()) => {
+                    return Err(anyhow!("rejected with HTTP {}", response.status()));
+                }
+                Ok(response) => anyhow!("HTTP {}", response.status()),
+                Err(e) => anyhow!(e),
+            };
+
+            if attempt >= self.retry.max_attempts {
+                return Err(error.context(format!("{} attempts failed", attempt)));
+            }
+            let backoff = self.retry.jittered_backoff(attempt);
+            debug!(
+                "🔄 Webhook {} to {} failed ({}), retrying in {:?}",
+                id, url, error, backoff
+            );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `"{timestamp}.{body}"`
+pub fn sign(secret: &SecretString, timestamp: u64, body: &str) -> String {
+    let mut engine = hmac::HmacEngine::<sha256::Hash>::new(secret.expose_secret().as_bytes());
+    engine.input(timestamp.to_string().as_bytes());
+    engine.input(b".");
+    engine.input(body.as_bytes());
+    format!("sha256={}", hmac::Hmac::from_engine(engine))
+}
+
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    status.is_server_error()
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Proxy for the webhook client; SOCKS5 targets are resolved by the proxy
+pub(crate) fn reqwest_proxy(proxy: &ProxyConfig) -> Result<reqwest::Proxy> {
+    let scheme = match proxy.kind {
+        ProxyKind::HttpConnect => "http",
+        ProxyKind::Socks5 => "socks5h",
+    };
+    let host = if proxy.host.contains(':') {
+        format!("[{}]", proxy.host)
+    } else {
+        proxy.host.clone()
+    };
+    let mut url = reqwest::Url::parse(&format!("{}://{}:{}", scheme, host, proxy.port))
+        .context("Invalid proxy address")?;
+    if let Some(username) = &proxy.username {
+        let password = proxy.password.as_ref().map(|p| p.expose_secret());
+        url.set_username(username)
+            .and_then(|_| url.set_password(password))
+            .map_err(|_| anyhow!("Invalid proxy credentials"))?;
+    }
+    reqwest::Proxy::all(url).context("Invalid proxy")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Webhook receiver answering with each status in turn and recording the requests
+    async fn receiver(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let received = Arc::clone(&requests);
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                // Headers and the small body arrive before the client waits for a response
+                loop {
+                    let len = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..len]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| line.strip_prefix("content-length: "))
+                            .and_then(|len| len.parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break;
+                        }
+                    }
+                }
+                received
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&request).into_owned());
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn notifier(url: String, attempts: u32, storage: Arc<dyn Storage>) -> Arc<WebhookNotifier> {
+        let mut notifier = WebhookNotifier::new(
+            vec![url],
+            Some(SecretString::new("hook-secret")),
+            None,
+            storage,
+        )
+        .unwrap();
+        notifier.retry = RetryPolicy {
+            max_attempts: attempts,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+            multiplier: 1.0,
+        };
+        Arc::new(notifier)
+    }
+
+    async fn wait_for(requests: &Mutex<Vec<String>>, count: usize) {
+        for _ in 0..200 {
+            if requests.lock().unwrap().len() >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {} webhook requests", count);
+    }
+
+    #[test]
+    fn test_signature() {
+        let secret = SecretString::new("key");
+        let signature = sign(&secret, 1_700_000_000, "{}");
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+        assert_eq!(signature, sign(&secret, 1_700_000_000, "{}"));
+        assert_ne!(signature, sign(&secret, 1_700_000_001, "{}"));
+    }
+
+    #[tokio::test]
+    async fn test_delivery_is_signed_and_retried() {
+        let (url, requests) = receiver(vec![503, 200]).await;
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        notifier(url, 3, Arc::clone(&storage)).notify(WebhookEvent::PolicyViolation {
+            request_id: "req-1".to_string(),
+            message: "Daily limit exceeded".to_string(),
+        });
+        wait_for(&requests, 2).await;
+
+        let request = requests.lock().unwrap()[1].clone();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        let header = |name: &str| {
+            head.lines()
+                .find_map(|line| line.strip_prefix(&format!("{}: ", name.to_lowercase())))
+                .unwrap()
+                .to_string()
+        };
+        let timestamp: u64 = header(TIMESTAMP_HEADER).parse().unwrap();
+        assert_eq!(
+            header(SIGNATURE_HEADER),
+            sign(&SecretString::new("hook-secret"), timestamp, body)
+        );
+
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["event"], "policy_violation");
+        assert_eq!(payload["request_id"], "req-1");
+
+        // Finished deliveries leave storage
+        for _ in 0..100 {
+            if storage.list(PENDING_NAMESPACE).unwrap().is_empty() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("delivered webhook is still pending");
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let (url, requests) = receiver(vec![400, 200]).await;
+        let notifier = notifier(url, 3, Arc::new(MemoryStorage::default()));
+        notifier.notify(WebhookEvent::EnclaveRestarted {
+            previous_enclave_id: "a".to_string(),
+            enclave_id: "b".to_string(),
+            epoch: 1,
+        });
+        wait_for(&requests, 1).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pending_deliveries_resume() {
+        let (url, requests) = receiver(vec![200]).await;
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let delivery = PendingDelivery {
+            url: url.clone(),
+            payload_id: "left-over".to_string(),
+            body: r#"{"event":"enclave_restarted"}"#.to_string(),
+        };
+        storage
+            .put_json(PENDING_NAMESPACE, "left-over-0", &delivery)
+            .unwrap();
+
+        notifier(url, 1, storage).resume_pending();
+        wait_for(&requests, 1).await;
+        assert!(requests.lock().unwrap()[0].ends_with(&delivery.body));
+    }
+}
@@ -1,55 +1,184 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use log::{debug, info, warn};
+use std::net::IpAddr;
 use std::process::Command;
 use std::time::{Duration, Instant};
 
+use crate::dns::{DnsQuery, Resolver};
+use crate::{icmp, proxy, IpPreference, NetworkConfig, ProxyConfig, EXTERNAL_TEST_IPS};
+
+/// Environment variable listing the external IPs pinged, comma-separated
+pub const PING_TARGETS_ENV: &str = "RENCLAVE_CONNECTIVITY_PING_TARGETS";
+/// Environment variable listing the hostnames resolved, comma-separated
+pub const DNS_TEST_NAMES_ENV: &str = "RENCLAVE_CONNECTIVITY_DNS_NAMES";
+/// Environment variable listing the `http://` URLs fetched, comma-separated
+pub const HTTP_TEST_URLS_ENV: &str = "RENCLAVE_CONNECTIVITY_HTTP_URLS";
+/// Environment variable for the echo requests sent to each ping target
+pub const PING_COUNT_ENV: &str = "RENCLAVE_CONNECTIVITY_PING_COUNT";
+/// Environment variable for how long to wait for each echo reply, in milliseconds
+pub const PING_TIMEOUT_MS_ENV: &str = "RENCLAVE_CONNECTIVITY_PING_TIMEOUT_MS";
+/// Environment variable for how long to wait for each HTTP test, in milliseconds
+pub const HTTP_TIMEOUT_MS_ENV: &str = "RENCLAVE_CONNECTIVITY_HTTP_TIMEOUT_MS";
+
+/// What connectivity tests and the background monitor probe, and how hard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectivityTargets {
+    /// External IPs pinged; the monitor probes the first that answers
+    pub ping_targets: Vec<IpAddr>,
+    /// Hostnames resolved; the monitor resolves the first
+    pub dns_names: Vec<String>,
+    /// `http://` URLs fetched until one answers
+    pub http_urls: Vec<String>,
+    /// Echo requests per target in the comprehensive test
+    pub ping_count: u32,
+    pub ping_timeout: Duration,
+    pub http_timeout: Duration,
+}
+
+impl Default for ConnectivityTargets {
+    fn default() -> Self {
+        Self {
+            ping_targets: EXTERNAL_TEST_IPS.to_vec(),
+            dns_names: vec!["google.com".to_string()],
+            http_urls: vec![
+                "http://httpbin.org/ip".to_string(),
+                "http://ifconfig.me/ip".to_string(),
+                "http://api.ipify.org".to_string(),
+            ],
+            ping_count: 3,
+            ping_timeout: Duration::from_secs(5),
+            http_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ConnectivityTargets {
+    /// Defaults overridden from the environment; invalid values are logged and ignored
+    pub fn from_env() -> Self {
+        let mut targets = Self::default();
+        let list = |name: &str| -> Option<Vec<String>> {
+            let items: Vec<String> = std::env::var(name)
+                .ok()?
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect();
+            (!items.is_empty()).then_some(items)
+        };
+        let number = |name: &str| -> Option<u64> {
+            let value = std::env::var(name).ok()?;
+            match value.trim().parse::<u64>() {
+                Ok(number) if number > 0 => Some(number),
+                _ => {
+                    warn!("⚠️  Ignoring invalid {}: {}", name, value);
+                    None
+                }
+            }
+        };
+
+        if let Some(items) = list(PING_TARGETS_ENV) {
+            match items
+                .iter()
+                .map(|item| item.parse::<IpAddr>())
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(ping_targets) => targets.ping_targets = ping_targets,
+                Err(_) => warn!(
+                    "⚠️  Ignoring {}: expected IP addresses, got {}",
+                    PING_TARGETS_ENV,
+                    items.join(",")
+                ),
+            }
+        }
+        if let Some(dns_names) = list(DNS_TEST_NAMES_ENV) {
+            targets.dns_names = dns_names;
+        }
+        if let Some(http_urls) = list(HTTP_TEST_URLS_ENV) {
+            targets.http_urls = http_urls;
+        }
+        if let Some(count) = number(PING_COUNT_ENV) {
+            targets.ping_count = count.min(100) as u32;
+        }
+        if let Some(ms) = number(PING_TIMEOUT_MS_ENV) {
+            targets.ping_timeout = Duration::from_millis(ms);
+        }
+        if let Some(ms) = number(HTTP_TIMEOUT_MS_ENV) {
+            targets.http_timeout = Duration::from_millis(ms);
+        }
+        targets
+    }
+}
+
 /// Connectivity tester for network interfaces
 pub struct ConnectivityTester {
-    #[allow(dead_code)]
-    timeout: Duration,
+    targets: ConnectivityTargets,
+    gateways: Vec<IpAddr>,
+    preference: IpPreference,
+    proxy: Option<ProxyConfig>,
+    resolver: Resolver,
 }
 
 impl ConnectivityTester {
+    /// Tester for the default targets, waiting `timeout` for HTTP
     pub fn new(timeout: Duration) -> Self {
-        Self { timeout }
+        let mut config = NetworkConfig::default();
+        config.connectivity.http_timeout = timeout;
+        Self::for_config(&config)
+    }
+
+    /// Tester probing the targets, gateways and address families of `config`, resolving names
+    /// with its DNS servers and sending HTTP through its proxy
+    pub fn for_config(config: &NetworkConfig) -> Self {
+        let ipv4 = config.gateway_ip.parse().ok();
+        let ipv6 = config
+            .gateway_ipv6
+            .as_deref()
+            .and_then(|gateway| gateway.parse().ok());
+        Self {
+            targets: config.connectivity.clone(),
+            gateways: config.ip_preference.order(ipv4.into_iter().chain(ipv6)),
+            preference: config.ip_preference,
+            proxy: config.proxy.clone(),
+            resolver: Resolver::for_servers(
+                &config.dns_servers,
+                config.dns_timeout,
+                config.ip_preference,
+            ),
+        }
     }
 
     /// Test HTTP connectivity to external services
     pub async fn test_http_connectivity(&self) -> Result<HttpConnectivityResult> {
         info!("🌐 Testing HTTP connectivity");
 
-        let test_urls = [
-            "http://httpbin.org/ip",
-            "http://ifconfig.me/ip",
-            "http://api.ipify.org",
-        ];
-
         let start_time = Instant::now();
 
-        for url in &test_urls {
+        for url in &self.targets.http_urls {
             debug!("🔍 Testing HTTP connectivity to: {}", url);
 
-            let result = Command::new("curl")
-                .args(["-s", "--connect-timeout", "5", "--max-time", "10", url])
-                .output()
-                .context("Failed to execute curl command")?;
-
-            if result.status.success() {
-                let response = String::from_utf8_lossy(&result.stdout);
-                let duration = start_time.elapsed();
+            let timeout = self.targets.http_timeout;
+            match proxy::http_get(self.proxy.as_ref(), &self.resolver, url, timeout).await {
+                Ok(response) if response.status == 200 => {
+                    let duration = start_time.elapsed();
 
-                info!("✅ HTTP connectivity working via: {}", url);
-                debug!("Response: {}", response.trim());
+                    info!("✅ HTTP connectivity working via: {}", url);
+                    debug!("Response: {}", response.body.trim());
 
-                return Ok(HttpConnectivityResult {
-                    success: true,
-                    url: url.to_string(),
-                    response: response.trim().to_string(),
-                    duration,
-                });
-            } else {
-                warn!("⚠️  HTTP connectivity failed for: {}", url);
-                debug!("Error: {}", String::from_utf8_lossy(&result.stderr));
+                    return Ok(HttpConnectivityResult {
+                        success: true,
+                        url: url.to_string(),
+                        response: response.body.trim().to_string(),
+                        duration,
+                    });
+                }
+                Ok(response) => {
+                    warn!("⚠️  HTTP connectivity failed for: {}", url);
+                    debug!("Error: HTTP {}", response.status);
+                }
+                Err(e) => {
+                    warn!("⚠️  HTTP connectivity failed for: {}", url);
+                    debug!("Error: {:#}", e);
+                }
             }
         }
 
@@ -61,59 +190,84 @@ impl ConnectivityTester {
         })
     }
 
-    /// Test DNS resolution
+    /// Test DNS resolution against the configured servers, timing each query
     pub async fn test_dns_resolution(&self, hostname: &str) -> Result<DnsResult> {
         info!("🔍 Testing DNS resolution for: {}", hostname);
 
         let start_time = Instant::now();
-
-        let result = Command::new("nslookup")
-            .args([hostname])
-            .output()
-            .context("Failed to execute nslookup")?;
-
+        let result = self.resolver.lookup(hostname).await;
         let duration = start_time.elapsed();
 
-        if result.status.success() {
-            let output = String::from_utf8_lossy(&result.stdout);
-            info!("✅ DNS resolution successful for: {}", hostname);
-            debug!("DNS output: {}", output);
+        match result {
+            Ok(lookup) => {
+                let success = !lookup.addresses.is_empty();
+                let output = lookup
+                    .addresses
+                    .iter()
+                    .map(|address| address.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if success {
+                    info!("✅ DNS resolution successful for: {}", hostname);
+                } else {
+                    warn!(
+                        "⚠️  No {} addresses resolved for: {}",
+                        self.preference.as_str(),
+                        hostname
+                    );
+                }
+                debug!("DNS output: {}", output);
 
-            Ok(DnsResult {
-                success: true,
-                hostname: hostname.to_string(),
-                duration,
-                output: output.to_string(),
-            })
-        } else {
-            warn!("⚠️  DNS resolution failed for: {}", hostname);
-            let error = String::from_utf8_lossy(&result.stderr);
-            debug!("DNS error: {}", error);
+                Ok(DnsResult {
+                    success,
+                    hostname: hostname.to_string(),
+                    duration,
+                    output,
+                    server: lookup.server().map(|server| server.ip().to_string()),
+                    queries: lookup.queries,
+                })
+            }
+            Err(e) => {
+                warn!("⚠️  DNS resolution failed for: {}", hostname);
+                debug!("DNS error: {}", e);
 
-            Ok(DnsResult {
-                success: false,
-                hostname: hostname.to_string(),
-                duration,
-                output: error.to_string(),
-            })
+                Ok(DnsResult {
+                    success: false,
+                    hostname: hostname.to_string(),
+                    duration,
+                    output: e.to_string(),
+                    server: None,
+                    queries: Vec::new(),
+                })
+            }
         }
     }
 
-    /// Test ping connectivity
+    /// Test ping connectivity over ICMP, or with the `ping` binary when no ICMP socket can be
+    /// opened
     pub async fn test_ping(&self, target: &str, count: u32) -> Result<PingResult> {
         info!(
             "🏓 Testing ping connectivity to: {} ({} packets)",
             target, count
         );
 
+        let address: IpAddr = target
+            .parse()
+            .with_context(|| format!("Invalid IP address {}", target))?;
+        if icmp::is_available(&address) {
+            return self.test_icmp_ping(address, count).await;
+        }
+
         let start_time = Instant::now();
 
+        let wait_secs = self.targets.ping_timeout.as_secs().max(1).to_string();
         let result = Command::new("ping")
             .args([
+                if address.is_ipv6() { "-6" } else { "-4" },
                 "-c",
                 &count.to_string(),
                 "-W",
-                "5", // 5 second timeout
+                &wait_secs,
                 target,
             ])
             .output()
@@ -134,7 +288,12 @@ impl ConnectivityTester {
                 duration,
                 packets_sent: count,
                 packets_received: stats.packets_received,
+                packet_loss_percent: packet_loss_percent(count, stats.packets_received),
                 avg_time_ms: stats.avg_time_ms,
+                min_time_ms: stats.min_time_ms,
+                max_time_ms: stats.max_time_ms,
+                // ping reports the mean deviation rather than successive differences
+                jitter_ms: stats.mdev_ms,
                 output: output.to_string(),
             })
         } else {
@@ -142,22 +301,57 @@ impl ConnectivityTester {
             let error = String::from_utf8_lossy(&result.stderr);
             debug!("Ping error: {}", error);
 
-            Ok(PingResult {
-                success: false,
-                target: target.to_string(),
+            Ok(PingResult::from_round_trips(
+                target.to_string(),
                 duration,
-                packets_sent: count,
-                packets_received: 0,
-                avg_time_ms: 0.0,
-                output: error.to_string(),
-            })
+                count,
+                &[],
+                error.to_string(),
+            ))
         }
     }
 
+    async fn test_icmp_ping(&self, address: IpAddr, count: u32) -> Result<PingResult> {
+        let target = address.to_string();
+        let start_time = Instant::now();
+
+        let mut round_trips = Vec::new();
+        let mut last_error = None;
+        for sequence in 1..=count {
+            let timeout = self.targets.ping_timeout;
+            let reply =
+                tokio::task::spawn_blocking(move || icmp::ping(address, sequence as u16, timeout))
+                    .await?;
+            match reply {
+                Ok(rtt) => round_trips.push(rtt),
+                Err(e) => last_error = Some(e.to_string()),
+            }
+        }
+
+        let packets_received = round_trips.len() as u32;
+        if packets_received > 0 {
+            info!("✅ Ping successful to: {}", target);
+        } else {
+            warn!("⚠️  Ping failed to: {}", target);
+        }
+
+        Ok(PingResult::from_round_trips(
+            target,
+            start_time.elapsed(),
+            count,
+            &round_trips,
+            last_error.unwrap_or_else(|| {
+                format!(
+                    "{} packets transmitted, {} received",
+                    count, packets_received
+                )
+            }),
+        ))
+    }
+
     /// Parse ping statistics from output
     fn parse_ping_stats(&self, output: &str) -> PingStats {
-        let mut packets_received = 0;
-        let mut avg_time_ms = 0.0;
+        let mut stats = PingStats::default();
 
         // Look for statistics line like: "1 packets transmitted, 1 received, 0% packet loss"
         for line in output.lines() {
@@ -165,44 +359,67 @@ impl ConnectivityTester {
                 if let Some(received_part) = line.split("received").next() {
                     if let Some(received_str) = received_part.split(',').nth(1) {
                         if let Ok(received) = received_str.trim().parse::<u32>() {
-                            packets_received = received;
+                            stats.packets_received = received;
                         }
                     }
                 }
             }
 
-            // Look for average time like: "rtt min/avg/max/mdev = 1.234/2.345/3.456/0.123 ms"
+            // Look for times like: "rtt min/avg/max/mdev = 1.234/2.345/3.456/0.123 ms"
             if line.contains("rtt min/avg/max") {
                 if let Some(times_part) = line.split('=').nth(1) {
-                    if let Some(avg_str) = times_part.split('/').nth(1) {
-                        if let Ok(avg) = avg_str.trim().parse::<f64>() {
-                            avg_time_ms = avg;
-                        }
+                    let times: Vec<f64> = times_part
+                        .trim()
+                        .trim_end_matches("ms")
+                        .split('/')
+                        .filter_map(|time| time.trim().parse().ok())
+                        .collect();
+                    if let [min, avg, max, mdev] = times[..] {
+                        stats.min_time_ms = min;
+                        stats.avg_time_ms = avg;
+                        stats.max_time_ms = max;
+                        stats.mdev_ms = mdev;
                     }
                 }
             }
         }
 
-        PingStats {
-            packets_received,
-            avg_time_ms,
-        }
+        stats
     }
 
-    /// Run comprehensive connectivity test
+    /// Run comprehensive connectivity test: the preferred gateway and every configured
+    /// target, pinged, resolved and fetched
     pub async fn run_comprehensive_test(&self) -> Result<ConnectivityReport> {
         info!("🔍 Running comprehensive connectivity test");
 
         let start_time = Instant::now();
+        let count = self.targets.ping_count;
+
+        // Test ping to the preferred gateway
+        let gateway = self
+            .gateways
+            .first()
+            .ok_or_else(|| anyhow!("No {} gateway configured", self.preference.as_str()))?;
+        let gateway_ping = self.test_ping(&gateway.to_string(), count).await?;
+
+        // Test ping to each external IP of the allowed families, preferred family first
+        let external = self.preference.order(self.targets.ping_targets.clone());
+        if external.is_empty() {
+            return Err(anyhow!(
+                "No {} ping targets configured",
+                self.preference.as_str()
+            ));
+        }
+        let mut external_pings = Vec::new();
+        for target in external {
+            external_pings.push(self.test_ping(&target.to_string(), count).await?);
+        }
 
-        // Test ping to gateway
-        let gateway_ping = self.test_ping("192.168.100.1", 3).await?;
-
-        // Test ping to external IPs
-        let external_ping = self.test_ping("8.8.8.8", 3).await?;
-
-        // Test DNS resolution
-        let dns_test = self.test_dns_resolution("google.com").await?;
+        // Test DNS resolution of each hostname
+        let mut dns_tests = Vec::new();
+        for hostname in &self.targets.dns_names {
+            dns_tests.push(self.test_dns_resolution(hostname).await?);
+        }
 
         // Test HTTP connectivity
         let http_test = self.test_http_connectivity().await?;
@@ -211,8 +428,8 @@ impl ConnectivityTester {
 
         let report = ConnectivityReport {
             gateway_ping,
-            external_ping,
-            dns_test,
+            external_pings,
+            dns_tests,
             http_test,
             total_duration,
         };
@@ -246,6 +463,10 @@ pub struct DnsResult {
     pub hostname: String,
     pub duration: Duration,
     pub output: String,
+    /// Server that answered
+    pub server: Option<String>,
+    /// Every query sent, with its timing
+    pub queries: Vec<DnsQuery>,
 }
 
 #[derive(Debug, Clone)]
@@ -255,21 +476,125 @@ pub struct PingResult {
     pub duration: Duration,
     pub packets_sent: u32,
     pub packets_received: u32,
+    pub packet_loss_percent: f64,
     pub avg_time_ms: f64,
+    pub min_time_ms: f64,
+    pub max_time_ms: f64,
+    /// Mean difference between successive round trips
+    pub jitter_ms: f64,
     pub output: String,
 }
 
-#[derive(Debug, Clone)]
+impl PingResult {
+    fn from_round_trips(
+        target: String,
+        duration: Duration,
+        packets_sent: u32,
+        round_trips: &[Duration],
+        output: String,
+    ) -> Self {
+        let times: Vec<f64> = round_trips
+            .iter()
+            .map(|rtt| rtt.as_secs_f64() * 1000.0)
+            .collect();
+        let packets_received = times.len() as u32;
+        let (avg_time_ms, min_time_ms, max_time_ms) = if times.is_empty() {
+            (0.0, 0.0, 0.0)
+        } else {
+            (
+                times.iter().sum::<f64>() / times.len() as f64,
+                times.iter().copied().fold(f64::INFINITY, f64::min),
+                times.iter().copied().fold(0.0, f64::max),
+            )
+        };
+        let jitter_ms = if times.len() < 2 {
+            0.0
+        } else {
+            times.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>() / (times.len() - 1) as f64
+        };
+
+        Self {
+            success: packets_received > 0,
+            target,
+            duration,
+            packets_sent,
+            packets_received,
+            packet_loss_percent: packet_loss_percent(packets_sent, packets_received),
+            avg_time_ms,
+            min_time_ms,
+            max_time_ms,
+            jitter_ms,
+            output,
+        }
+    }
+}
+
+fn packet_loss_percent(sent: u32, received: u32) -> f64 {
+    if sent == 0 {
+        return 0.0;
+    }
+    f64::from(sent.saturating_sub(received)) * 100.0 / f64::from(sent)
+}
+
+#[derive(Debug, Clone, Default)]
 struct PingStats {
     packets_received: u32,
     avg_time_ms: f64,
+    min_time_ms: f64,
+    max_time_ms: f64,
+    mdev_ms: f64,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConnectivityReport {
     pub gateway_ping: PingResult,
-    pub external_ping: PingResult,
-    pub dns_test: DnsResult,
+    /// One result per external ping target
+    pub external_pings: Vec<PingResult>,
+    /// One result per DNS test hostname
+    pub dns_tests: Vec<DnsResult>,
     pub http_test: HttpConnectivityResult,
     pub total_duration: Duration,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_statistics() {
+        let round_trips = [10, 14, 12, 0].map(Duration::from_millis);
+        let result = PingResult::from_round_trips(
+            "1.1.1.1".into(),
+            Duration::ZERO,
+            5,
+            &round_trips[..3],
+            String::new(),
+        );
+
+        assert!(result.success);
+        assert_eq!(result.packets_received, 3);
+        assert_eq!(result.packet_loss_percent, 40.0);
+        assert_eq!(result.avg_time_ms, 12.0);
+        assert_eq!(result.min_time_ms, 10.0);
+        assert_eq!(result.max_time_ms, 14.0);
+        assert_eq!(result.jitter_ms, 3.0);
+
+        let lost =
+            PingResult::from_round_trips("1.1.1.1".into(), Duration::ZERO, 3, &[], String::new());
+        assert!(!lost.success);
+        assert_eq!(lost.packet_loss_percent, 100.0);
+        assert_eq!(lost.jitter_ms, 0.0);
+    }
+
+    #[test]
+    fn test_parse_ping_stats() {
+        let output = "3 packets transmitted, 2 received, 33% packet loss, time 2003ms\n\
+                      rtt min/avg/max/mdev = 1.100/2.200/3.300/0.400 ms\n";
+        let stats = ConnectivityTester::default().parse_ping_stats(output);
+        assert_eq!(stats.packets_received, 2);
+        assert_eq!(stats.min_time_ms, 1.1);
+        assert_eq!(stats.avg_time_ms, 2.2);
+        assert_eq!(stats.max_time_ms, 3.3);
+        assert_eq!(stats.mdev_ms, 0.4);
+    }
+}
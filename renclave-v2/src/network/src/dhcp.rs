@@ -0,0 +1,529 @@
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info, warn};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::HashMap;
+use std::fs;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+
+const SERVER_PORT: u16 = 67;
+const CLIENT_PORT: u16 = 68;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+const BOOTREQUEST: u8 = 1;
+const BOOTREPLY: u8 = 2;
+/// Fixed BOOTP header before the magic cookie
+const HEADER_LEN: usize = 236;
+
+const OPT_PAD: u8 = 0;
+const OPT_SUBNET_MASK: u8 = 1;
+const OPT_ROUTER: u8 = 3;
+const OPT_DNS: u8 = 6;
+const OPT_REQUESTED_IP: u8 = 50;
+const OPT_LEASE_TIME: u8 = 51;
+const OPT_MESSAGE_TYPE: u8 = 53;
+const OPT_SERVER_ID: u8 = 54;
+const OPT_PARAMETER_LIST: u8 = 55;
+const OPT_RENEWAL_TIME: u8 = 58;
+const OPT_REBINDING_TIME: u8 = 59;
+const OPT_END: u8 = 255;
+
+/// Time to wait for a server answer before retransmitting
+const REPLY_TIMEOUT: Duration = Duration::from_secs(4);
+const ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MessageType {
+    Discover = 1,
+    Offer = 2,
+    Request = 3,
+    Ack = 5,
+    Nak = 6,
+}
+
+impl MessageType {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Discover),
+            2 => Some(Self::Offer),
+            3 => Some(Self::Request),
+            5 => Some(Self::Ack),
+            6 => Some(Self::Nak),
+            _ => None,
+        }
+    }
+}
+
+/// Where a lease is in its lifecycle (RFC 2131 section 4.4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaseState {
+    Bound,
+    /// Past T1, renewing with the server that granted the lease
+    Renewing,
+    /// Past T2, asking any server to extend the lease
+    Rebinding,
+    Expired,
+}
+
+impl LeaseState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeaseState::Bound => "bound",
+            LeaseState::Renewing => "renewing",
+            LeaseState::Rebinding => "rebinding",
+            LeaseState::Expired => "expired",
+        }
+    }
+}
+
+/// Address configuration granted by a DHCP server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhcpLease {
+    pub address: Ipv4Addr,
+    pub prefix_len: u8,
+    pub gateway: Option<Ipv4Addr>,
+    pub dns_servers: Vec<Ipv4Addr>,
+    pub server: Ipv4Addr,
+    pub lease_secs: u32,
+    pub renew_secs: u32,
+    pub rebind_secs: u32,
+    /// Unix time the lease was granted or last extended
+    pub acquired_at: u64,
+    pub state: LeaseState,
+}
+
+impl DhcpLease {
+    /// Unix time of T1
+    pub fn renew_at(&self) -> u64 {
+        self.acquired_at + u64::from(self.renew_secs)
+    }
+
+    /// Unix time of T2
+    pub fn rebind_at(&self) -> u64 {
+        self.acquired_at + u64::from(self.rebind_secs)
+    }
+
+    pub fn expires_at(&self) -> u64 {
+        self.acquired_at + u64::from(self.lease_secs)
+    }
+}
+
+/// DHCPv4 client for a single interface.
+///
+/// Binds UDP port 68 on the interface, so it needs `CAP_NET_BIND_SERVICE` and
+/// `CAP_NET_RAW` (for `SO_BINDTODEVICE`), and the interface must be up.
+pub struct DhcpClient {
+    interface: String,
+    mac: [u8; 6],
+}
+
+impl DhcpClient {
+    /// Client for `interface`, identified by its hardware address
+    pub fn new(interface: &str) -> Result<Self> {
+        let path = format!("/sys/class/net/{}/address", interface);
+        let mac = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read hardware address of {}", interface))?;
+        Ok(Self {
+            interface: interface.to_string(),
+            mac: parse_mac(mac.trim())?,
+        })
+    }
+
+    /// Obtain a lease with DISCOVER → OFFER → REQUEST → ACK
+    pub async fn acquire(&self) -> Result<DhcpLease> {
+        info!("📡 Requesting DHCP lease on {}", self.interface);
+        let socket = self.socket()?;
+        let broadcast = SocketAddrV4::new(Ipv4Addr::BROADCAST, SERVER_PORT);
+
+        for attempt in 1..=ATTEMPTS {
+            let xid = rand_xid();
+            let discover = encode(
+                MessageType::Discover,
+                xid,
+                &self.mac,
+                Ipv4Addr::UNSPECIFIED,
+                None,
+                None,
+            );
+            let offer = match self
+                .exchange(&socket, &discover, broadcast, xid, MessageType::Offer)
+                .await
+            {
+                Ok(offer) => offer,
+                Err(e) => {
+                    debug!("ℹ️  DHCP discover attempt {} failed: {}", attempt, e);
+                    continue;
+                }
+            };
+            let server = offer
+                .ipv4(OPT_SERVER_ID)
+                .ok_or_else(|| anyhow!("DHCP offer without a server identifier"))?;
+
+            let request = encode(
+                MessageType::Request,
+                xid,
+                &self.mac,
+                Ipv4Addr::UNSPECIFIED,
+                Some(offer.yiaddr),
+                Some(server),
+            );
+            match self
+                .exchange(&socket, &request, broadcast, xid, MessageType::Ack)
+                .await
+            {
+                Ok(ack) => {
+                    let lease = ack.into_lease(unix_now())?;
+                    info!(
+                        "✅ DHCP lease {}/{} from {} for {}s",
+                        lease.address, lease.prefix_len, lease.server, lease.lease_secs
+                    );
+                    return Ok(lease);
+                }
+                Err(e) => debug!("ℹ️  DHCP request attempt {} failed: {}", attempt, e),
+            }
+        }
+
+        bail!(
+            "No DHCP lease on {} after {} attempts",
+            self.interface,
+            ATTEMPTS
+        )
+    }
+
+    /// Extend `lease`: unicast to the granting server while renewing, broadcast while
+    /// rebinding
+    pub async fn renew(&self, lease: &DhcpLease) -> Result<DhcpLease> {
+        let socket = self.socket()?;
+        let destination = match lease.state {
+            LeaseState::Rebinding => SocketAddrV4::new(Ipv4Addr::BROADCAST, SERVER_PORT),
+            _ => SocketAddrV4::new(lease.server, SERVER_PORT),
+        };
+
+        let xid = rand_xid();
+        let request = encode(
+            MessageType::Request,
+            xid,
+            &self.mac,
+            lease.address,
+            None,
+            None,
+        );
+        let ack = self
+            .exchange(&socket, &request, destination, xid, MessageType::Ack)
+            .await?;
+        ack.into_lease(unix_now())
+    }
+
+    fn socket(&self) -> Result<UdpSocket> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_reuse_address(true)?;
+        socket.set_broadcast(true)?;
+        socket
+            .bind_device(Some(self.interface.as_bytes()))
+            .with_context(|| format!("Failed to bind DHCP socket to {}", self.interface))?;
+        socket
+            .bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, CLIENT_PORT).into())
+            .context("Failed to bind DHCP client port")?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpSocket::from_std(socket.into())?)
+    }
+
+    /// Send `packet` and wait for a reply of type `expected` to `xid`
+    async fn exchange(
+        &self,
+        socket: &UdpSocket,
+        packet: &[u8],
+        destination: SocketAddrV4,
+        xid: u32,
+        expected: MessageType,
+    ) -> Result<Reply> {
+        socket.send_to(packet, destination).await?;
+        let deadline = Instant::now() + REPLY_TIMEOUT;
+
+        let mut buffer = [0u8; 1500];
+        loop {
+            let len = timeout_at(deadline, socket.recv(&mut buffer))
+                .await
+                .map_err(|_| anyhow!("No DHCP {:?} within {:?}", expected, REPLY_TIMEOUT))??;
+
+            let Some(reply) = parse(&buffer[..len], xid, &self.mac) else {
+                continue;
+            };
+            match reply.message_type {
+                t if t == expected => return Ok(reply),
+                MessageType::Nak => bail!("DHCP server declined the request"),
+                other => debug!("ℹ️  Ignoring DHCP {:?}", other),
+            }
+        }
+    }
+}
+
+/// Decoded server reply
+#[derive(Debug)]
+struct Reply {
+    message_type: MessageType,
+    yiaddr: Ipv4Addr,
+    options: HashMap<u8, Vec<u8>>,
+}
+
+impl Reply {
+    fn ipv4(&self, option: u8) -> Option<Ipv4Addr> {
+        self.ipv4_list(option).into_iter().next()
+    }
+
+    fn ipv4_list(&self, option: u8) -> Vec<Ipv4Addr> {
+        self.options
+            .get(&option)
+            .map(|value| {
+                value
+                    .chunks_exact(4)
+                    .map(|b| Ipv4Addr::new(b[0], b[1], b[2], b[3]))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn u32(&self, option: u8) -> Option<u32> {
+        let value = self.options.get(&option)?;
+        Some(u32::from_be_bytes(value.get(..4)?.try_into().ok()?))
+    }
+
+    fn into_lease(self, now: u64) -> Result<DhcpLease> {
+        let lease_secs = self
+            .u32(OPT_LEASE_TIME)
+            .ok_or_else(|| anyhow!("DHCP ACK without a lease time"))?;
+        let prefix_len = match self.ipv4(OPT_SUBNET_MASK) {
+            Some(mask) => crate::netlink::prefix_len(&mask.to_string())?,
+            None => {
+                warn!("⚠️  DHCP ACK without a subnet mask, assuming /24");
+                24
+            }
+        };
+
+        Ok(DhcpLease {
+            address: self.yiaddr,
+            prefix_len,
+            gateway: self.ipv4(OPT_ROUTER),
+            dns_servers: self.ipv4_list(OPT_DNS),
+            server: self
+                .ipv4(OPT_SERVER_ID)
+                .ok_or_else(|| anyhow!("DHCP ACK without a server identifier"))?,
+            lease_secs,
+            // RFC 2131 defaults: T1 = 0.5 and T2 = 0.875 of the lease
+            renew_secs: self.u32(OPT_RENEWAL_TIME).unwrap_or(lease_secs / 2),
+            rebind_secs: self.u32(OPT_REBINDING_TIME).unwrap_or(lease_secs / 8 * 7),
+            acquired_at: now,
+            state: LeaseState::Bound,
+        })
+    }
+}
+
+/// Client message (RFC 2131 section 2)
+fn encode(
+    message_type: MessageType,
+    xid: u32,
+    mac: &[u8; 6],
+    ciaddr: Ipv4Addr,
+    requested: Option<Ipv4Addr>,
+    server: Option<Ipv4Addr>,
+) -> Vec<u8> {
+    let mut packet = vec![0u8; HEADER_LEN];
+    packet[0] = BOOTREQUEST;
+    packet[1] = 1; // Ethernet
+    packet[2] = 6; // Hardware address length
+    packet[4..8].copy_from_slice(&xid.to_be_bytes());
+    if ciaddr.is_unspecified() {
+        // Ask for broadcast replies while we have no address
+        packet[10] = 0x80;
+    }
+    packet[12..16].copy_from_slice(&ciaddr.octets());
+    packet[28..34].copy_from_slice(mac);
+
+    packet.extend_from_slice(&MAGIC_COOKIE);
+    packet.extend_from_slice(&[OPT_MESSAGE_TYPE, 1, message_type as u8]);
+    if let Some(requested) = requested {
+        packet.extend_from_slice(&[OPT_REQUESTED_IP, 4]);
+        packet.extend_from_slice(&requested.octets());
+    }
+    if let Some(server) = server {
+        packet.extend_from_slice(&[OPT_SERVER_ID, 4]);
+        packet.extend_from_slice(&server.octets());
+    }
+    packet.extend_from_slice(&[
+        OPT_PARAMETER_LIST,
+        6,
+        OPT_SUBNET_MASK,
+        OPT_ROUTER,
+        OPT_DNS,
+        OPT_LEASE_TIME,
+        OPT_RENEWAL_TIME,
+        OPT_REBINDING_TIME,
+    ]);
+    packet.push(OPT_END);
+    packet
+}
+
+/// Server reply to `xid` for `mac`, or `None` for anything else
+fn parse(packet: &[u8], xid: u32, mac: &[u8; 6]) -> Option<Reply> {
+    if packet.len() < HEADER_LEN + MAGIC_COOKIE.len()
+        || packet[0] != BOOTREPLY
+        || packet[4..8] != xid.to_be_bytes()
+        || packet[28..34] != mac[..]
+        || packet[HEADER_LEN..HEADER_LEN + 4] != MAGIC_COOKIE
+    {
+        return None;
+    }
+    let yiaddr = Ipv4Addr::new(packet[16], packet[17], packet[18], packet[19]);
+
+    let mut options = HashMap::new();
+    let mut rest = &packet[HEADER_LEN + 4..];
+    while let Some((&code, tail)) = rest.split_first() {
+        match code {
+            OPT_PAD => rest = tail,
+            OPT_END => break,
+            _ => {
+                let (&len, tail) = tail.split_first()?;
+                let value = tail.get(..usize::from(len))?;
+                options.insert(code, value.to_vec());
+                rest = &tail[usize::from(len)..];
+            }
+        }
+    }
+
+    let message_type = options
+        .get(&OPT_MESSAGE_TYPE)
+        .and_then(|value| value.first())
+        .and_then(|&value| MessageType::from_u8(value))?;
+    Some(Reply {
+        message_type,
+        yiaddr,
+        options,
+    })
+}
+
+fn parse_mac(value: &str) -> Result<[u8; 6]> {
+    let bytes = value
+        .split(':')
+        .map(|part| u8::from_str_radix(part, 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .with_context(|| format!("Invalid hardware address {}", value))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Invalid hardware address {}", value))
+}
+
+fn rand_xid() -> u32 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    nanos ^ std::process::id().rotate_left(16)
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+
+    /// Server reply built from a client message with the given options
+    fn reply(xid: u32, message_type: MessageType, options: &[(u8, &[u8])]) -> Vec<u8> {
+        let mut packet = encode(message_type, xid, &MAC, Ipv4Addr::UNSPECIFIED, None, None);
+        packet.truncate(HEADER_LEN + 4);
+        packet[0] = BOOTREPLY;
+        packet[16..20].copy_from_slice(&[192, 168, 100, 50]);
+        packet.extend_from_slice(&[OPT_MESSAGE_TYPE, 1, message_type as u8, OPT_PAD]);
+        for (code, value) in options {
+            packet.push(*code);
+            packet.push(value.len() as u8);
+            packet.extend_from_slice(value);
+        }
+        packet.push(OPT_END);
+        packet
+    }
+
+    #[test]
+    fn test_encode_request() {
+        let packet = encode(
+            MessageType::Request,
+            0xdeadbeef,
+            &MAC,
+            Ipv4Addr::UNSPECIFIED,
+            Some(Ipv4Addr::new(192, 168, 100, 50)),
+            Some(Ipv4Addr::new(192, 168, 100, 1)),
+        );
+
+        assert_eq!(packet[0], BOOTREQUEST);
+        assert_eq!(&packet[4..8], &[0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(packet[10], 0x80);
+        assert_eq!(&packet[28..34], &MAC);
+        assert_eq!(&packet[HEADER_LEN..HEADER_LEN + 4], &MAGIC_COOKIE);
+
+        let options = &packet[HEADER_LEN + 4..];
+        assert_eq!(&options[..3], &[OPT_MESSAGE_TYPE, 1, 3]);
+        assert_eq!(&options[3..9], &[OPT_REQUESTED_IP, 4, 192, 168, 100, 50]);
+        assert_eq!(&options[9..15], &[OPT_SERVER_ID, 4, 192, 168, 100, 1]);
+        assert_eq!(options.last(), Some(&OPT_END));
+    }
+
+    #[test]
+    fn test_parse_ack_into_lease() {
+        let packet = reply(
+            7,
+            MessageType::Ack,
+            &[
+                (OPT_SUBNET_MASK, &[255, 255, 255, 0]),
+                (OPT_ROUTER, &[192, 168, 100, 1]),
+                (OPT_DNS, &[1, 1, 1, 1, 8, 8, 8, 8]),
+                (OPT_SERVER_ID, &[192, 168, 100, 1]),
+                (OPT_LEASE_TIME, &3600u32.to_be_bytes()),
+            ],
+        );
+
+        let reply = parse(&packet, 7, &MAC).unwrap();
+        assert_eq!(reply.message_type, MessageType::Ack);
+
+        let lease = reply.into_lease(1_000).unwrap();
+        assert_eq!(lease.address, Ipv4Addr::new(192, 168, 100, 50));
+        assert_eq!(lease.prefix_len, 24);
+        assert_eq!(lease.gateway, Some(Ipv4Addr::new(192, 168, 100, 1)));
+        assert_eq!(
+            lease.dns_servers,
+            vec![Ipv4Addr::new(1, 1, 1, 1), Ipv4Addr::new(8, 8, 8, 8)]
+        );
+        assert_eq!(lease.state, LeaseState::Bound);
+        // Default T1 and T2
+        assert_eq!(lease.renew_at(), 1_000 + 1_800);
+        assert_eq!(lease.rebind_at(), 1_000 + 3_150);
+        assert_eq!(lease.expires_at(), 1_000 + 3_600);
+    }
+
+    #[test]
+    fn test_parse_rejects_other_transactions() {
+        let packet = reply(7, MessageType::Offer, &[]);
+        assert!(parse(&packet, 8, &MAC).is_none());
+        assert!(parse(&packet, 7, &[0; 6]).is_none());
+        assert!(parse(&packet[..100], 7, &MAC).is_none());
+
+        // Truncated option
+        let mut truncated = packet.clone();
+        truncated.pop();
+        truncated.extend_from_slice(&[OPT_ROUTER, 4, 192]);
+        assert!(parse(&truncated, 7, &MAC).is_none());
+    }
+
+    #[test]
+    fn test_parse_mac() {
+        assert_eq!(parse_mac("52:54:00:12:34:56").unwrap(), MAC);
+        assert!(parse_mac("52:54:00").is_err());
+        assert!(parse_mac("zz:54:00:12:34:56").is_err());
+    }
+}
@@ -0,0 +1,570 @@
+use anyhow::{anyhow, bail, Context, Result};
+use futures::future::join_all;
+use log::debug;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+use crate::IpPreference;
+
+/// Environment variable listing the upstream DNS servers, comma-separated
+pub const DNS_SERVERS_ENV: &str = "RENCLAVE_DNS_SERVERS";
+
+/// Environment variable for how long to wait for each DNS server, in milliseconds
+pub const DNS_TIMEOUT_MS_ENV: &str = "RENCLAVE_DNS_TIMEOUT_MS";
+
+pub(crate) const DEFAULT_DNS_TIMEOUT: Duration = Duration::from_secs(2);
+
+const DNS_PORT: u16 = 53;
+const HEADER_LEN: usize = 12;
+const CLASS_IN: u16 = 1;
+/// Recursion desired
+const FLAG_RD: u16 = 0x0100;
+/// Response, as opposed to query
+const FLAG_QR: u16 = 0x8000;
+/// Truncated; the full answer needs TCP
+const FLAG_TC: u16 = 0x0200;
+const RCODE_NXDOMAIN: u16 = 3;
+/// Largest UDP response without EDNS (RFC 1035 section 4.2.1)
+const MAX_UDP_LEN: usize = 512;
+const MAX_NAME_LEN: usize = 253;
+
+/// Address record type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    A,
+    Aaaa,
+}
+
+impl RecordType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::A => "A",
+            Self::Aaaa => "AAAA",
+        }
+    }
+
+    fn code(&self) -> u16 {
+        match self {
+            Self::A => 1,
+            Self::Aaaa => 28,
+        }
+    }
+}
+
+/// One question sent to one server
+#[derive(Debug, Clone)]
+pub struct DnsQuery {
+    pub record_type: RecordType,
+    pub server: SocketAddr,
+    pub duration: Duration,
+    /// Addresses in the answer
+    pub answers: usize,
+    pub error: Option<String>,
+}
+
+/// Result of resolving a hostname, with every query it took
+#[derive(Debug, Clone)]
+pub struct Lookup {
+    pub hostname: String,
+    /// Resolved addresses, preferred family first
+    pub addresses: Vec<IpAddr>,
+    pub queries: Vec<DnsQuery>,
+    pub duration: Duration,
+}
+
+impl Lookup {
+    /// Server that answered, if any did
+    pub fn server(&self) -> Option<SocketAddr> {
+        self.queries
+            .iter()
+            .find(|query| query.error.is_none())
+            .map(|query| query.server)
+    }
+}
+
+/// Stub resolver asking the configured servers directly over UDP, falling back to TCP for
+/// truncated answers. It does not read `/etc/resolv.conf`, so lookups do not depend on the
+/// image's libc or tools.
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    servers: Vec<SocketAddr>,
+    timeout: Duration,
+    preference: IpPreference,
+}
+
+impl Resolver {
+    /// Resolver asking `servers` in order, waiting `timeout` for each
+    pub fn new(
+        servers: impl IntoIterator<Item = IpAddr>,
+        timeout: Duration,
+        preference: IpPreference,
+    ) -> Self {
+        Self {
+            servers: servers
+                .into_iter()
+                .map(|server| SocketAddr::new(server, DNS_PORT))
+                .collect(),
+            timeout,
+            preference,
+        }
+    }
+
+    /// Resolver for `dns_servers`, skipping entries that are not IP addresses
+    pub fn for_servers(
+        dns_servers: &[String],
+        timeout: Duration,
+        preference: IpPreference,
+    ) -> Self {
+        let servers =
+            dns_servers
+                .iter()
+                .filter_map(|server| match server.trim().parse::<IpAddr>() {
+                    Ok(address) => Some(address),
+                    Err(_) => {
+                        debug!("ℹ️  Ignoring invalid DNS server {}", server);
+                        None
+                    }
+                });
+        Self::new(servers, timeout, preference)
+    }
+
+    pub fn servers(&self) -> &[SocketAddr] {
+        &self.servers
+    }
+
+    /// Look up the addresses of `hostname` in the allowed families.
+    ///
+    /// Families are queried in parallel, and each asks the servers in order until one
+    /// answers. An IP address is returned as it is. The lookup only fails if the hostname
+    /// is invalid or no server is configured; check `addresses` for the outcome.
+    pub async fn lookup(&self, hostname: &str) -> Result<Lookup> {
+        let start = Instant::now();
+        if let Ok(address) = hostname.trim_matches(['[', ']']).parse::<IpAddr>() {
+            return Ok(Lookup {
+                hostname: hostname.to_string(),
+                addresses: vec![address],
+                queries: Vec::new(),
+                duration: start.elapsed(),
+            });
+        }
+        validate_hostname(hostname)?;
+        if self.servers.is_empty() {
+            bail!("No DNS servers configured");
+        }
+
+        let record_types = match self.preference {
+            IpPreference::Ipv4Only => vec![RecordType::A],
+            IpPreference::Ipv6Only => vec![RecordType::Aaaa],
+            _ => vec![RecordType::A, RecordType::Aaaa],
+        };
+        let results = join_all(
+            record_types
+                .into_iter()
+                .map(|record_type| self.query(hostname, record_type)),
+        )
+        .await;
+
+        let mut addresses = Vec::new();
+        let mut queries = Vec::new();
+        for (answers, attempts) in results {
+            addresses.extend(answers);
+            queries.extend(attempts);
+        }
+        Ok(Lookup {
+            hostname: hostname.to_string(),
+            addresses: self.preference.order(addresses),
+            queries,
+            duration: start.elapsed(),
+        })
+    }
+
+    /// Addresses of `hostname`, preferred family first; an error if there are none
+    pub async fn resolve(&self, hostname: &str) -> Result<Vec<IpAddr>> {
+        let lookup = self.lookup(hostname).await?;
+        if lookup.addresses.is_empty() {
+            let reason = lookup
+                .queries
+                .iter()
+                .rev()
+                .find_map(|query| query.error.clone())
+                .unwrap_or_else(|| "no addresses".to_string());
+            bail!("Failed to resolve {}: {}", hostname, reason);
+        }
+        Ok(lookup.addresses)
+    }
+
+    /// Ask the servers in order until one answers for `record_type`
+    async fn query(&self, hostname: &str, record_type: RecordType) -> (Vec<IpAddr>, Vec<DnsQuery>) {
+        let mut attempts = Vec::new();
+        for &server in &self.servers {
+            let start = Instant::now();
+            let result =
+                tokio::time::timeout(self.timeout, exchange(server, hostname, record_type))
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("No answer within {:?}", self.timeout)));
+            let duration = start.elapsed();
+
+            match result {
+                Ok(response) => {
+                    debug!(
+                        "✅ {} {} from {} in {:?}: {:?}",
+                        record_type.as_str(),
+                        hostname,
+                        server,
+                        duration,
+                        response.addresses
+                    );
+                    let error = (response.rcode == RCODE_NXDOMAIN)
+                        .then(|| format!("{} does not exist", hostname));
+                    attempts.push(DnsQuery {
+                        record_type,
+                        server,
+                        duration,
+                        answers: response.addresses.len(),
+                        error,
+                    });
+                    return (response.addresses, attempts);
+                }
+                Err(e) => {
+                    debug!(
+                        "ℹ️  {} {} from {} failed: {:#}",
+                        record_type.as_str(),
+                        hostname,
+                        server,
+                        e
+                    );
+                    attempts.push(DnsQuery {
+                        record_type,
+                        server,
+                        duration,
+                        answers: 0,
+                        error: Some(format!("{:#}", e)),
+                    });
+                }
+            }
+        }
+        (Vec::new(), attempts)
+    }
+}
+
+/// Upstream servers from the environment, if set
+pub(crate) fn servers_from_env() -> Option<Vec<String>> {
+    let servers: Vec<String> = std::env::var(DNS_SERVERS_ENV)
+        .ok()?
+        .split(',')
+        .map(|server| server.trim().to_string())
+        .filter(|server| !server.is_empty())
+        .collect();
+    (!servers.is_empty()).then_some(servers)
+}
+
+/// Per-server timeout from the environment, if set
+pub(crate) fn timeout_from_env() -> Option<Duration> {
+    std::env::var(DNS_TIMEOUT_MS_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|ms| *ms > 0)
+        .map(Duration::from_millis)
+}
+
+/// Decoded answer to one question
+#[derive(Debug)]
+struct Response {
+    rcode: u16,
+    truncated: bool,
+    addresses: Vec<IpAddr>,
+}
+
+/// Ask `server` one question over UDP, repeating it over TCP if the answer was truncated
+async fn exchange(server: SocketAddr, hostname: &str, record_type: RecordType) -> Result<Response> {
+    let id = query_id();
+    let query = encode(id, hostname, record_type)?;
+
+    let bind: SocketAddr = match server {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(bind).await?;
+    socket.connect(server).await?;
+    socket.send(&query).await?;
+
+    let mut buffer = [0u8; MAX_UDP_LEN];
+    let response = loop {
+        let len = socket.recv(&mut buffer).await?;
+        // Ignore stray datagrams, such as late answers to earlier queries
+        if let Some(response) = parse(&buffer[..len], id, record_type)? {
+            break response;
+        }
+    };
+    if !response.truncated {
+        return Ok(response);
+    }
+
+    debug!("ℹ️  Truncated answer from {}, retrying over TCP", server);
+    let mut stream = TcpStream::connect(server)
+        .await
+        .with_context(|| format!("Failed to connect to {} over TCP", server))?;
+    let mut message = (query.len() as u16).to_be_bytes().to_vec();
+    message.extend_from_slice(&query);
+    stream.write_all(&message).await?;
+
+    let len = stream.read_u16().await? as usize;
+    let mut answer = vec![0u8; len];
+    stream.read_exact(&mut answer).await?;
+    parse(&answer, id, record_type)?.ok_or_else(|| anyhow!("Mismatched answer from {}", server))
+}
+
+fn validate_hostname(hostname: &str) -> Result<()> {
+    let name = hostname.strip_suffix('.').unwrap_or(hostname);
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        });
+    if !valid {
+        bail!("Invalid hostname '{}'", hostname);
+    }
+    Ok(())
+}
+
+/// Query for `hostname` (RFC 1035 section 4.1)
+fn encode(id: u16, hostname: &str, record_type: RecordType) -> Result<Vec<u8>> {
+    validate_hostname(hostname)?;
+
+    let mut packet = Vec::with_capacity(HEADER_LEN + hostname.len() + 6);
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&FLAG_RD.to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes()); // One question
+    packet.extend_from_slice(&[0; 6]); // No answer, authority or additional records
+
+    for label in hostname.trim_end_matches('.').split('.') {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&record_type.code().to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+/// Decode an answer to query `id`; `None` if the packet answers something else
+fn parse(packet: &[u8], id: u16, record_type: RecordType) -> Result<Option<Response>> {
+    let u16_at = |pos: usize| -> Result<u16> {
+        packet
+            .get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| anyhow!("DNS answer truncated"))
+    };
+
+    if packet.len() < HEADER_LEN || u16_at(0)? != id {
+        return Ok(None);
+    }
+    let flags = u16_at(2)?;
+    if flags & FLAG_QR == 0 {
+        return Ok(None);
+    }
+    let rcode = flags & 0x000f;
+    let truncated = flags & FLAG_TC != 0;
+    if rcode != 0 && rcode != RCODE_NXDOMAIN {
+        bail!("Server answered with rcode {}", rcode);
+    }
+
+    let questions = u16_at(4)?;
+    let answers = u16_at(6)?;
+    let mut pos = HEADER_LEN;
+    for _ in 0..questions {
+        pos = skip_name(packet, pos)? + 4;
+    }
+
+    let mut addresses = Vec::new();
+    for _ in 0..answers {
+        pos = skip_name(packet, pos)?;
+        let rtype = u16_at(pos)?;
+        let class = u16_at(pos + 2)?;
+        let rdlength = u16_at(pos + 8)? as usize;
+        let rdata = packet
+            .get(pos + 10..pos + 10 + rdlength)
+            .ok_or_else(|| anyhow!("DNS answer truncated"))?;
+        pos += 10 + rdlength;
+
+        // CNAMEs and other records in the chain are skipped
+        if rtype != record_type.code() || class != CLASS_IN {
+            continue;
+        }
+        match rdata.len() {
+            4 => addresses.push(IpAddr::V4(Ipv4Addr::new(
+                rdata[0], rdata[1], rdata[2], rdata[3],
+            ))),
+            16 => {
+                let octets: [u8; 16] = rdata.try_into().expect("length checked");
+                addresses.push(IpAddr::V6(Ipv6Addr::from(octets)));
+            }
+            _ => bail!("Malformed {} record", record_type.as_str()),
+        }
+    }
+
+    Ok(Some(Response {
+        rcode,
+        truncated,
+        addresses,
+    }))
+}
+
+/// Position after the name starting at `pos`, which may end in a compression pointer
+fn skip_name(packet: &[u8], mut pos: usize) -> Result<usize> {
+    loop {
+        let len = *packet
+            .get(pos)
+            .ok_or_else(|| anyhow!("DNS answer truncated"))? as usize;
+        match len {
+            0 => return Ok(pos + 1),
+            len if len & 0xc0 == 0xc0 => return Ok(pos + 2),
+            len if len & 0xc0 == 0 => pos += 1 + len,
+            _ => bail!("Unsupported DNS label type"),
+        }
+    }
+}
+
+/// Query ID that differs between queries and processes
+fn query_id() -> u16 {
+    static COUNTER: AtomicU16 = AtomicU16::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    (nanos as u16) ^ (std::process::id() as u16) ^ COUNTER.fetch_add(0x9e37, Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answer to `encode(id, "example.com", A)`: a CNAME to `www.example.com` and its address,
+    /// both named through compression pointers
+    fn answer(id: u16, flags: u16) -> Vec<u8> {
+        let mut packet = encode(id, "example.com", RecordType::A).unwrap();
+        packet[2..4].copy_from_slice(&flags.to_be_bytes());
+        packet[6..8].copy_from_slice(&2u16.to_be_bytes());
+
+        // example.com CNAME www.example.com
+        packet.extend_from_slice(&[0xc0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 6]);
+        packet.extend_from_slice(&[3, b'w', b'w', b'w', 0xc0, 12]);
+        // www.example.com A 93.184.216.34
+        let www = (packet.len() - 6) as u8;
+        packet.extend_from_slice(&[0xc0, www, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4]);
+        packet.extend_from_slice(&[93, 184, 216, 34]);
+        packet
+    }
+
+    #[test]
+    fn test_encode() {
+        let packet = encode(0x1234, "example.com.", RecordType::Aaaa).unwrap();
+        assert_eq!(
+            packet,
+            [
+                &[0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0][..],
+                b"\x07example\x03com\x00",
+                &[0, 28, 0, 1],
+            ]
+            .concat()
+        );
+
+        assert!(encode(1, "", RecordType::A).is_err());
+        assert!(encode(1, "bad..name", RecordType::A).is_err());
+        assert!(encode(1, &format!("{}.com", "a".repeat(64)), RecordType::A).is_err());
+        assert!(encode(1, "space here.com", RecordType::A).is_err());
+    }
+
+    #[test]
+    fn test_parse_answer() {
+        let response = parse(&answer(7, 0x8180), 7, RecordType::A)
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.rcode, 0);
+        assert!(!response.truncated);
+        assert_eq!(
+            response.addresses,
+            vec![IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34))]
+        );
+
+        // Answers to other queries are skipped
+        assert!(parse(&answer(7, 0x8180), 8, RecordType::A)
+            .unwrap()
+            .is_none());
+        // So are queries
+        assert!(parse(&answer(7, 0x0100), 7, RecordType::A)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        let truncated = parse(&answer(7, 0x8380), 7, RecordType::A)
+            .unwrap()
+            .unwrap();
+        assert!(truncated.truncated);
+
+        let nxdomain = parse(&answer(7, 0x8183), 7, RecordType::A)
+            .unwrap()
+            .unwrap();
+        assert_eq!(nxdomain.rcode, RCODE_NXDOMAIN);
+
+        // SERVFAIL moves on to the next server
+        assert!(parse(&answer(7, 0x8182), 7, RecordType::A).is_err());
+
+        let packet = answer(7, 0x8180);
+        assert!(parse(&packet[..packet.len() - 2], 7, RecordType::A).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_against_local_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 512];
+            let (len, peer) = server.recv_from(&mut buffer).await.unwrap();
+            let id = u16::from_be_bytes([buffer[0], buffer[1]]);
+            assert_eq!(
+                &buffer[HEADER_LEN..len],
+                &encode(id, "example.com", RecordType::A).unwrap()[HEADER_LEN..]
+            );
+            server.send_to(&answer(id, 0x8180), peer).await.unwrap();
+        });
+
+        let mut resolver = Resolver::new(
+            [address.ip()],
+            Duration::from_secs(5),
+            IpPreference::Ipv4Only,
+        );
+        resolver.servers[0].set_port(address.port());
+
+        let lookup = resolver.lookup("example.com").await.unwrap();
+        assert_eq!(
+            lookup.addresses,
+            vec![IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34))]
+        );
+        assert_eq!(lookup.queries.len(), 1);
+        assert_eq!(lookup.queries[0].answers, 1);
+        assert_eq!(lookup.server(), Some(resolver.servers[0]));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_ip_literal() {
+        let resolver = Resolver::new([], Duration::from_secs(1), IpPreference::default());
+        let lookup = resolver.lookup("[2001:db8::1]").await.unwrap();
+        assert_eq!(
+            lookup.addresses,
+            vec!["2001:db8::1".parse::<IpAddr>().unwrap()]
+        );
+        assert!(lookup.queries.is_empty());
+
+        assert!(resolver.lookup("example.com").await.is_err());
+    }
+}
@@ -0,0 +1,362 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::net::IpAddr;
+use std::process::{Command, Stdio};
+
+/// Environment variable with the egress allowlist; setting it enables enforcement
+pub const EGRESS_ALLOW_ENV: &str = "RENCLAVE_EGRESS_ALLOW";
+
+/// nftables table holding the egress rules, replaced as a whole on every apply
+const TABLE_FAMILY: &str = "inet";
+const TABLE_NAME: &str = "renclave_egress";
+
+/// Transport protocol an egress rule is limited to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EgressProtocol {
+    Tcp,
+    Udp,
+}
+
+impl EgressProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+        }
+    }
+}
+
+/// Destination the enclave may reach: a network, optionally narrowed to a protocol and ports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EgressRule {
+    /// Network address, with the host bits cleared
+    pub network: IpAddr,
+    pub prefix_len: u8,
+    pub protocol: Option<EgressProtocol>,
+    /// Inclusive port range
+    pub ports: Option<(u16, u16)>,
+}
+
+impl EgressRule {
+    /// Parse `address[/prefix] [tcp|udp[/port[-port]]]`, e.g. `0.0.0.0/0 tcp/443` or
+    /// `1.1.1.1 udp/53`
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut words = value.split_whitespace();
+        let network = words.next().ok_or_else(|| anyhow!("Empty egress rule"))?;
+        let service = words.next();
+        if words.next().is_some() {
+            return Err(anyhow!("Unexpected text in egress rule '{}'", value));
+        }
+
+        let (address, prefix_len) = match network.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (network, None),
+        };
+        let address: IpAddr = address
+            .parse()
+            .with_context(|| format!("Invalid address in egress rule '{}'", value))?;
+        let max_prefix_len = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(len) => len
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_prefix_len)
+                .ok_or_else(|| anyhow!("Invalid prefix length in egress rule '{}'", value))?,
+            None => max_prefix_len,
+        };
+
+        let (protocol, ports) = match service {
+            Some(service) => {
+                let (protocol, ports) = match service.split_once('/') {
+                    Some((protocol, ports)) => (protocol, Some(ports)),
+                    None => (service, None),
+                };
+                let protocol = match protocol.to_ascii_lowercase().as_str() {
+                    "tcp" => EgressProtocol::Tcp,
+                    "udp" => EgressProtocol::Udp,
+                    _ => {
+                        return Err(anyhow!(
+                            "Egress rule '{}' must name tcp or udp, got '{}'",
+                            value,
+                            protocol
+                        ))
+                    }
+                };
+                let ports = ports
+                    .map(|ports| {
+                        parse_ports(ports)
+                            .ok_or_else(|| anyhow!("Invalid ports in egress rule '{}'", value))
+                    })
+                    .transpose()?;
+                (Some(protocol), ports)
+            }
+            None => (None, None),
+        };
+
+        Ok(Self {
+            network: mask(address, prefix_len),
+            prefix_len,
+            protocol,
+            ports,
+        })
+    }
+
+    /// The rule as an nftables statement accepting matching packets
+    fn nft(&self) -> String {
+        let family = if self.network.is_ipv4() { "ip" } else { "ip6" };
+        let mut rule = format!("{} daddr {}/{}", family, self.network, self.prefix_len);
+        match (self.protocol, self.ports) {
+            (Some(protocol), Some((first, last))) if first == last => {
+                rule.push_str(&format!(" {} dport {}", protocol.as_str(), first));
+            }
+            (Some(protocol), Some((first, last))) => {
+                rule.push_str(&format!(" {} dport {}-{}", protocol.as_str(), first, last));
+            }
+            (Some(protocol), None) => {
+                rule.push_str(&format!(" meta l4proto {}", protocol.as_str()));
+            }
+            (None, _) => {}
+        }
+        rule.push_str(" accept");
+        rule
+    }
+}
+
+impl fmt::Display for EgressRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)?;
+        if let Some(protocol) = self.protocol {
+            write!(f, " {}", protocol.as_str())?;
+        }
+        match self.ports {
+            Some((first, last)) if first == last => write!(f, "/{}", first),
+            Some((first, last)) => write!(f, "/{}-{}", first, last),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_ports(ports: &str) -> Option<(u16, u16)> {
+    let (first, last) = match ports.split_once('-') {
+        Some((first, last)) => (first.parse().ok()?, last.parse().ok()?),
+        None => {
+            let port = ports.parse().ok()?;
+            (port, port)
+        }
+    };
+    (first > 0 && first <= last).then_some((first, last))
+}
+
+/// `address` with the bits past `prefix_len` cleared
+fn mask(address: IpAddr, prefix_len: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(address) => {
+            let bits = u32::from(address)
+                & u32::MAX
+                    .checked_shl(32 - u32::from(prefix_len))
+                    .unwrap_or(0);
+            IpAddr::V4(bits.into())
+        }
+        IpAddr::V6(address) => {
+            let bits = u128::from(address)
+                & u128::MAX
+                    .checked_shl(128 - u32::from(prefix_len))
+                    .unwrap_or(0);
+            IpAddr::V6(bits.into())
+        }
+    }
+}
+
+/// Allowlist for traffic leaving the enclave; everything else is dropped.
+///
+/// Replies to allowed connections, DHCP and IPv6 neighbour discovery are always let
+/// through so the interface keeps working.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EgressPolicy {
+    pub rules: Vec<EgressRule>,
+}
+
+impl EgressPolicy {
+    /// Parse comma-separated rules (see [`EgressRule::parse`])
+    pub fn parse(value: &str) -> Result<Self> {
+        let rules = value
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(EgressRule::parse)
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Policy from the environment, `None` if egress is not restricted
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var(EGRESS_ALLOW_ENV) {
+            Ok(value) => Self::parse(&value)
+                .map(Some)
+                .with_context(|| format!("Invalid {}", EGRESS_ALLOW_ENV)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// nftables script replacing the egress table with this policy.
+    ///
+    /// Traffic from the enclave is filtered where it leaves through `interface` on the
+    /// guest (output hook) and where it arrives from `interface` on the host (forward hook).
+    pub fn ruleset(&self, interface: &str) -> String {
+        let table = format!("{} {}", TABLE_FAMILY, TABLE_NAME);
+        let mut script = String::new();
+        // Declaring the table first makes the delete succeed when it does not exist yet
+        script.push_str(&format!("table {}\ndelete table {}\n", table, table));
+        script.push_str(&format!("table {} {{\n", table));
+        script.push_str("    chain egress {\n");
+        script.push_str("        ct state established,related accept\n");
+        script.push_str("        udp sport 68 udp dport 67 accept\n");
+        script.push_str(
+            "        icmpv6 type { nd-router-solicit, nd-neighbor-solicit, nd-neighbor-advert } accept\n",
+        );
+        for rule in &self.rules {
+            script.push_str(&format!("        {}\n", rule.nft()));
+        }
+        script.push_str("        counter drop\n");
+        script.push_str("    }\n");
+        script.push_str("    chain output {\n");
+        script.push_str("        type filter hook output priority filter; policy accept;\n");
+        script.push_str(&format!("        oifname \"{}\" jump egress\n", interface));
+        script.push_str("    }\n");
+        script.push_str("    chain forward {\n");
+        script.push_str("        type filter hook forward priority filter; policy accept;\n");
+        script.push_str(&format!("        iifname \"{}\" jump egress\n", interface));
+        script.push_str("    }\n");
+        script.push_str("}\n");
+        script
+    }
+
+    /// Install the policy for `interface`, atomically replacing any previous one
+    pub fn apply(&self, interface: &str) -> Result<()> {
+        info!(
+            "🛡️  Enforcing egress policy on {} ({} rules)",
+            interface,
+            self.rules.len()
+        );
+        nft(&["-f", "-"], Some(&self.ruleset(interface)))?;
+        info!("✅ Egress policy active");
+        Ok(())
+    }
+}
+
+/// Remove the egress table, lifting any restriction
+pub fn remove_egress_policy() -> Result<()> {
+    debug!("🛡️  Removing egress policy");
+    nft(&["delete", "table", TABLE_FAMILY, TABLE_NAME], None).map(|_| ())
+}
+
+/// The egress table as currently loaded in the kernel
+pub fn active_egress_ruleset() -> Result<String> {
+    nft(&["list", "table", TABLE_FAMILY, TABLE_NAME], None)
+}
+
+/// Run `nft` with `args`, feeding `input` on stdin, and return its output
+fn nft(args: &[&str], input: Option<&str>) -> Result<String> {
+    let mut child = Command::new("nft")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run nft; is nftables installed?")?;
+    if let Some(input) = input {
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("nft stdin unavailable"))?
+            .write_all(input.as_bytes())
+            .context("Failed to pass the ruleset to nft")?;
+    } else {
+        drop(child.stdin.take());
+    }
+
+    let output = child.wait_with_output().context("Failed to run nft")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "nft {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Egress enforcement as reported by `/network/egress`
+#[derive(Debug, Clone, Serialize)]
+pub struct EgressStatus {
+    /// Whether an allowlist is configured
+    pub enabled: bool,
+    /// Whether the allowlist was installed successfully
+    pub enforced: bool,
+    pub interface: String,
+    pub rules: Vec<String>,
+    /// Why the allowlist could not be installed
+    pub error: Option<String>,
+    /// Rules loaded in the kernel, as listed by `nft`
+    pub active_ruleset: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = EgressRule::parse("10.1.2.3/8").unwrap();
+        assert_eq!(rule.to_string(), "10.0.0.0/8");
+        assert_eq!(rule.nft(), "ip daddr 10.0.0.0/8 accept");
+
+        let rule = EgressRule::parse("0.0.0.0/0 tcp/443").unwrap();
+        assert_eq!(rule.to_string(), "0.0.0.0/0 tcp/443");
+        assert_eq!(rule.nft(), "ip daddr 0.0.0.0/0 tcp dport 443 accept");
+
+        let rule = EgressRule::parse("2001:db8::1 UDP/5000-5010").unwrap();
+        assert_eq!(rule.to_string(), "2001:db8::1/128 udp/5000-5010");
+        assert_eq!(
+            rule.nft(),
+            "ip6 daddr 2001:db8::1/128 udp dport 5000-5010 accept"
+        );
+
+        let rule = EgressRule::parse("1.1.1.1 udp").unwrap();
+        assert_eq!(rule.nft(), "ip daddr 1.1.1.1/32 meta l4proto udp accept");
+    }
+
+    #[test]
+    fn test_parse_rule_rejects_invalid() {
+        for value in [
+            "",
+            "example.com",
+            "10.0.0.0/33",
+            "10.0.0.0/8 icmp",
+            "10.0.0.0/8 tcp/0",
+            "10.0.0.0/8 tcp/443-80",
+            "10.0.0.0/8 tcp/443 extra",
+        ] {
+            assert!(EgressRule::parse(value).is_err(), "accepted {:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_ruleset() {
+        let policy = EgressPolicy::parse("10.0.0.0/8, 1.1.1.1 udp/53,").unwrap();
+        assert_eq!(policy.rules.len(), 2);
+
+        let ruleset = policy.ruleset("tap0");
+        assert!(ruleset.starts_with("table inet renclave_egress\ndelete table"));
+        assert!(ruleset.contains("        ip daddr 10.0.0.0/8 accept\n"));
+        assert!(ruleset.contains("        ip daddr 1.1.1.1/32 udp dport 53 accept\n"));
+        assert!(ruleset.contains("oifname \"tap0\" jump egress"));
+        assert!(ruleset.contains("iifname \"tap0\" jump egress"));
+
+        let drop = ruleset.find("counter drop").unwrap();
+        assert!(ruleset.find("udp dport 53").unwrap() < drop);
+    }
+}
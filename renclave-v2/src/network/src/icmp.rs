@@ -0,0 +1,262 @@
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::io::{ErrorKind, Read};
+use std::net::{IpAddr, SocketAddr};
+use std::os::fd::AsRawFd;
+use std::time::{Duration, Instant};
+
+const ECHO_REQUEST: u8 = 8;
+const ECHO_REPLY: u8 = 0;
+const ECHO_REQUEST_V6: u8 = 128;
+const ECHO_REPLY_V6: u8 = 129;
+const PAYLOAD: &[u8] = b"renclave-ping";
+/// ICMP echo header length
+const ECHO_HEADER_LEN: usize = 8;
+
+/// Send one ICMP or ICMPv6 echo request to `target` and wait up to `timeout` for the reply.
+///
+/// Uses an unprivileged ping socket when the kernel allows it (`net.ipv4.ping_group_range`,
+/// which also covers IPv6) and a raw socket otherwise, which needs `CAP_NET_RAW`. Blocking;
+/// returns the round-trip time.
+pub fn ping(target: IpAddr, sequence: u16, timeout: Duration) -> Result<Duration> {
+    let (socket, raw) = open_socket(&target)?;
+    echo(socket, raw, target, sequence, PAYLOAD, timeout)
+}
+
+/// Like [`ping`], with an echo request padded to an IP packet of `packet_size` bytes that
+/// must not be fragmented.
+///
+/// The route's cached path MTU is ignored, so a packet larger than the interface MTU fails
+/// to send and one larger than a link further along gets no reply.
+pub fn ping_unfragmented(
+    target: IpAddr,
+    sequence: u16,
+    packet_size: usize,
+    timeout: Duration,
+) -> Result<Duration> {
+    let ip_header_len = match target {
+        IpAddr::V4(_) => 20,
+        IpAddr::V6(_) => 40,
+    };
+    let payload_len = packet_size
+        .checked_sub(ip_header_len + ECHO_HEADER_LEN)
+        .ok_or_else(|| anyhow!("Packet size {} is too small", packet_size))?;
+
+    let (socket, raw) = open_socket(&target)?;
+    let (level, name, value) = match target {
+        IpAddr::V4(_) => (
+            libc::IPPROTO_IP,
+            libc::IP_MTU_DISCOVER,
+            libc::IP_PMTUDISC_PROBE,
+        ),
+        IpAddr::V6(_) => (
+            libc::IPPROTO_IPV6,
+            libc::IPV6_MTU_DISCOVER,
+            libc::IPV6_PMTUDISC_PROBE,
+        ),
+    };
+    // SAFETY: the socket is open and `value` is a c_int that outlives the call
+    let result = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error())
+            .context("Failed to set the don't fragment flag");
+    }
+
+    echo(
+        socket,
+        raw,
+        target,
+        sequence,
+        &vec![0; payload_len],
+        timeout,
+    )
+}
+
+/// Send an echo request carrying `payload` over `socket` and wait for the reply
+fn echo(
+    mut socket: Socket,
+    raw: bool,
+    target: IpAddr,
+    sequence: u16,
+    payload: &[u8],
+    timeout: Duration,
+) -> Result<Duration> {
+    // Connecting filters out packets from other hosts
+    socket
+        .connect(&SockAddr::from(SocketAddr::new(target, 0)))
+        .with_context(|| format!("Failed to address ICMP socket to {}", target))?;
+
+    let identifier = std::process::id() as u16;
+    let (request_type, reply_type) = match target {
+        IpAddr::V4(_) => (ECHO_REQUEST, ECHO_REPLY),
+        IpAddr::V6(_) => (ECHO_REQUEST_V6, ECHO_REPLY_V6),
+    };
+    let request = echo_request(request_type, identifier, sequence, payload);
+    // Only raw IPv4 sockets deliver the IP header
+    let ip_header = raw && target.is_ipv4();
+
+    let start = Instant::now();
+    socket
+        .send(&request)
+        .with_context(|| format!("Failed to send ICMP echo to {}", target))?;
+
+    // Replies are as large as the request, up to the largest IP packet
+    let mut buffer = vec![0u8; 65536];
+    loop {
+        let remaining = timeout
+            .checked_sub(start.elapsed())
+            .filter(|d| !d.is_zero())
+            .ok_or_else(|| anyhow!("ICMP echo to {} timed out", target))?;
+        socket.set_read_timeout(Some(remaining))?;
+
+        let len = match socket.read(&mut buffer) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Err(anyhow!("ICMP echo to {} timed out", target));
+            }
+            Err(e) => return Err(e).context("Failed to receive ICMP reply"),
+        };
+        // Raw sockets see every ICMP packet, ping sockets get a kernel-chosen identifier
+        let expected_id = raw.then_some(identifier);
+        if is_echo_reply(&buffer[..len], ip_header, reply_type, expected_id, sequence) {
+            let rtt = start.elapsed();
+            debug!("✅ ICMP reply from {} in {:?}", target, rtt);
+            return Ok(rtt);
+        }
+    }
+}
+
+/// Whether this process can open an ICMP socket for the address family of `target`
+pub fn is_available(target: &IpAddr) -> bool {
+    open_socket(target).is_ok()
+}
+
+/// Ping socket if available, otherwise a raw socket; the flag is true for raw sockets
+fn open_socket(target: &IpAddr) -> Result<(Socket, bool)> {
+    let (domain, protocol) = match target {
+        IpAddr::V4(_) => (Domain::IPV4, Protocol::ICMPV4),
+        IpAddr::V6(_) => (Domain::IPV6, Protocol::ICMPV6),
+    };
+    match Socket::new(domain, Type::DGRAM, Some(protocol)) {
+        Ok(socket) => Ok((socket, false)),
+        Err(e) => {
+            debug!("ℹ️  Ping socket unavailable ({}), trying raw socket", e);
+            let socket = Socket::new(domain, Type::RAW, Some(protocol))
+                .context("Failed to open ICMP socket")?;
+            Ok((socket, true))
+        }
+    }
+}
+
+/// Echo request with a valid ICMP checksum; the kernel fills in ICMPv6 checksums itself
+fn echo_request(request_type: u8, identifier: u16, sequence: u16, payload: &[u8]) -> Vec<u8> {
+    let mut packet = vec![request_type, 0, 0, 0];
+    packet.extend_from_slice(&identifier.to_be_bytes());
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(payload);
+
+    let checksum = checksum(&packet);
+    packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+    packet
+}
+
+/// Whether `packet` is the echo reply for `sequence`, skipping the IPv4 header if present
+fn is_echo_reply(
+    packet: &[u8],
+    ip_header: bool,
+    reply_type: u8,
+    identifier: Option<u16>,
+    sequence: u16,
+) -> bool {
+    let icmp = if ip_header {
+        let header_len = match packet.first() {
+            Some(byte) => usize::from(byte & 0x0f) * 4,
+            None => return false,
+        };
+        match packet.get(header_len..) {
+            Some(icmp) => icmp,
+            None => return false,
+        }
+    } else {
+        packet
+    };
+
+    if icmp.len() < 8 || icmp[0] != reply_type {
+        return false;
+    }
+    let reply_id = u16::from_be_bytes([icmp[4], icmp[5]]);
+    let reply_sequence = u16::from_be_bytes([icmp[6], icmp[7]]);
+
+    reply_sequence == sequence && identifier.is_none_or(|id| id == reply_id)
+}
+
+/// RFC 1071 Internet checksum
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|pair| u32::from(u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])))
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_echo_request_checksum() {
+        let packet = echo_request(ECHO_REQUEST, 0x1234, 1, b"abc");
+        assert_eq!(&packet[..2], &[ECHO_REQUEST, 0]);
+        assert_eq!(&packet[4..8], &[0x12, 0x34, 0x00, 0x01]);
+        // A packet including its checksum sums to zero
+        assert_eq!(checksum(&packet), 0);
+    }
+
+    #[test]
+    fn test_reply_matching() {
+        let request = echo_request(ECHO_REQUEST, 0x1234, 7, PAYLOAD);
+        let mut reply = request.clone();
+        reply[0] = ECHO_REPLY;
+
+        assert!(is_echo_reply(&reply, false, ECHO_REPLY, None, 7));
+        assert!(!is_echo_reply(&reply, false, ECHO_REPLY, None, 8));
+        assert!(!is_echo_reply(&request, false, ECHO_REPLY, None, 7));
+
+        // Raw IPv4 sockets prepend a 20 byte header and must match the identifier
+        let mut raw = vec![0x45];
+        raw.extend_from_slice(&[0; 19]);
+        raw.extend_from_slice(&reply);
+        assert!(is_echo_reply(&raw, true, ECHO_REPLY, Some(0x1234), 7));
+        assert!(!is_echo_reply(&raw, true, ECHO_REPLY, Some(0x4321), 7));
+        assert!(!is_echo_reply(
+            &raw[..22],
+            true,
+            ECHO_REPLY,
+            Some(0x1234),
+            7
+        ));
+
+        let mut reply_v6 = echo_request(ECHO_REQUEST_V6, 0x1234, 7, PAYLOAD);
+        reply_v6[0] = ECHO_REPLY_V6;
+        assert!(is_echo_reply(
+            &reply_v6,
+            false,
+            ECHO_REPLY_V6,
+            Some(0x1234),
+            7
+        ));
+        assert!(!is_echo_reply(&reply, false, ECHO_REPLY_V6, None, 7));
+    }
+}
@@ -1,14 +1,128 @@
-use anyhow::{Context, Result};
-use log::{debug, error, info, warn};
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+use renclave_shared::NetworkState;
 use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::Path;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 
 pub mod connectivity;
+pub mod dhcp;
+pub mod dns;
+pub mod firewall;
+pub mod icmp;
+pub mod monitor;
+pub mod netlink;
+pub mod netns;
+pub mod proxy;
 pub mod tap;
+pub mod tuning;
 
 pub use connectivity::*;
+pub use dhcp::{DhcpClient, DhcpLease, LeaseState};
+pub use dns::{DnsQuery, Lookup, RecordType, Resolver, DNS_SERVERS_ENV, DNS_TIMEOUT_MS_ENV};
+pub use firewall::{EgressPolicy, EgressProtocol, EgressRule, EgressStatus, EGRESS_ALLOW_ENV};
+pub use monitor::{
+    ConnectivityEvent, ConnectivityMonitor, MonitorStatus, Probe, ProbeSample, ProbeStatus,
+    MONITOR_INTERVAL_ENV,
+};
+pub use netlink::Netlink;
+pub use netns::{NetnsConfig, NetworkNamespace};
+pub use proxy::{ProxyConfig, ProxyKind, PROXY_URL_ENV};
 pub use tap::*;
+pub use tuning::TransportTuning;
+
+/// Environment variable listing the interfaces addressed with DHCP, comma-separated
+pub const DHCP_INTERFACES_ENV: &str = "RENCLAVE_DHCP_INTERFACES";
+
+/// Environment variable for the static guest IPv6 address with prefix, e.g. `fd00:100::2/64`
+pub const GUEST_IPV6_ENV: &str = "RENCLAVE_GUEST_IPV6";
+
+/// Environment variable for the IPv6 default gateway
+pub const GATEWAY_IPV6_ENV: &str = "RENCLAVE_GATEWAY_IPV6";
+
+/// Environment variable for the address family preference (see [`IpPreference::parse`])
+pub const IP_PREFERENCE_ENV: &str = "RENCLAVE_IP_PREFERENCE";
+
+/// Public resolvers used to test external connectivity
+pub(crate) const EXTERNAL_TEST_IPS: [IpAddr; 4] = [
+    IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)),
+    IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)),
+    IpAddr::V6(Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888)),
+    IpAddr::V6(Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111)),
+];
+
+/// Delay between renewal attempts once T1 has passed
+const LEASE_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How the guest interface gets its address, gateway and DNS servers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Addressing {
+    /// `guest_ip`, `gateway_ip` and `dns_servers` from the configuration
+    #[default]
+    Static,
+    /// Leased from a DHCP server and renewed in the background; the static DNS servers are
+    /// used if the server offers none
+    Dhcp,
+}
+
+/// Address families the guest uses, in order of preference
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpPreference {
+    #[default]
+    Ipv4First,
+    Ipv6First,
+    /// IPv4 only; no IPv6 address or route is configured
+    Ipv4Only,
+    /// IPv6 only; no IPv4 address or route is configured
+    Ipv6Only,
+}
+
+impl IpPreference {
+    /// Parse `ipv4`, `ipv6`, `ipv4-only` or `ipv6-only`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ipv4" | "ipv4-first" => Some(Self::Ipv4First),
+            "ipv6" | "ipv6-first" => Some(Self::Ipv6First),
+            "ipv4-only" => Some(Self::Ipv4Only),
+            "ipv6-only" => Some(Self::Ipv6Only),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ipv4First => "ipv4",
+            Self::Ipv6First => "ipv6",
+            Self::Ipv4Only => "ipv4-only",
+            Self::Ipv6Only => "ipv6-only",
+        }
+    }
+
+    pub fn allows(&self, address: &IpAddr) -> bool {
+        match self {
+            Self::Ipv4Only => address.is_ipv4(),
+            Self::Ipv6Only => address.is_ipv6(),
+            _ => true,
+        }
+    }
+
+    /// Allowed addresses, preferred family first, keeping the order within a family
+    pub fn order(&self, addresses: impl IntoIterator<Item = IpAddr>) -> Vec<IpAddr> {
+        let mut ordered: Vec<IpAddr> = addresses
+            .into_iter()
+            .filter(|address| self.allows(address))
+            .collect();
+        let ipv6_first = *self == Self::Ipv6First;
+        ordered.sort_by_key(|address| address.is_ipv6() != ipv6_first);
+        ordered
+    }
+}
 
 /// Network configuration for QEMU TAP interface
 #[derive(Debug, Clone)]
@@ -17,7 +131,27 @@ pub struct NetworkConfig {
     pub guest_ip: String,
     pub guest_netmask: String,
     pub gateway_ip: String,
+    /// Static IPv6 address with prefix length; `None` leaves IPv6 addressing to SLAAC
+    pub guest_ipv6: Option<String>,
+    pub gateway_ipv6: Option<String>,
+    /// IPv4 and IPv6 servers
     pub dns_servers: Vec<String>,
+    /// How long the built-in resolver waits for each server
+    pub dns_timeout: Duration,
+    pub addressing: Addressing,
+    pub ip_preference: IpPreference,
+    /// Proxy for outbound HTTP
+    pub proxy: Option<ProxyConfig>,
+    /// Create the TAP device if it is missing; `None` leaves that to QEMU
+    pub create_tap: Option<TapOptions>,
+    /// Run the enclave in its own network namespace, addressed in place of the TAP interface
+    pub netns: Option<NetnsConfig>,
+    /// Allowlist for enclave-originated traffic; `None` leaves egress unrestricted
+    pub egress: Option<EgressPolicy>,
+    /// Targets of the connectivity tests and the background monitor
+    pub connectivity: ConnectivityTargets,
+    /// MTU, path MTU probing and socket buffer sizes
+    pub tuning: TransportTuning,
 }
 
 impl Default for NetworkConfig {
@@ -32,39 +166,317 @@ impl Default for NetworkConfig {
                 "8.8.4.4".to_string(),
                 "1.1.1.1".to_string(),
             ],
+            dns_timeout: dns::DEFAULT_DNS_TIMEOUT,
+            guest_ipv6: None,
+            gateway_ipv6: None,
+            addressing: Addressing::Static,
+            ip_preference: IpPreference::default(),
+            proxy: None,
+            create_tap: None,
+            netns: None,
+            egress: None,
+            connectivity: ConnectivityTargets::default(),
+            tuning: TransportTuning::default(),
         }
     }
 }
 
+impl NetworkConfig {
+    /// Default configuration, using DHCP if the TAP interface is listed in
+    /// `RENCLAVE_DHCP_INTERFACES`, with IPv6 settings from the environment
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        config.guest_ipv6 = var(GUEST_IPV6_ENV);
+        config.gateway_ipv6 = var(GATEWAY_IPV6_ENV);
+        if let Some(servers) = dns::servers_from_env() {
+            config.dns_servers = servers;
+        }
+        if let Some(timeout) = dns::timeout_from_env() {
+            config.dns_timeout = timeout;
+        }
+        config.create_tap = TapOptions::from_env();
+        config.connectivity = ConnectivityTargets::from_env();
+        match TransportTuning::from_env() {
+            Ok(tuning) => config.tuning = tuning,
+            Err(e) => warn!("⚠️  Ignoring transport tuning: {:#}", e),
+        }
+        match NetnsConfig::from_env() {
+            Ok(netns) => config.netns = netns,
+            Err(e) => warn!("⚠️  Ignoring network namespace: {:#}", e),
+        }
+        match EgressPolicy::from_env() {
+            Ok(egress) => config.egress = egress,
+            Err(e) => {
+                // Fail closed: an unreadable allowlist allows nothing
+                warn!("⚠️  {:#}, blocking all egress", e);
+                config.egress = Some(EgressPolicy::default());
+            }
+        }
+        match ProxyConfig::from_env() {
+            Ok(proxy) => config.proxy = proxy,
+            Err(e) => warn!("⚠️  Ignoring egress proxy: {:#}", e),
+        }
+        if let Some(value) = var(IP_PREFERENCE_ENV) {
+            match IpPreference::parse(&value) {
+                Some(preference) => config.ip_preference = preference,
+                None => warn!("⚠️  Ignoring invalid {}: {}", IP_PREFERENCE_ENV, value),
+            }
+        }
+
+        if let Ok(interfaces) = std::env::var(DHCP_INTERFACES_ENV) {
+            if interfaces
+                .split(',')
+                .any(|name| name.trim() == config.tap_interface)
+            {
+                config.addressing = Addressing::Dhcp;
+            }
+        }
+        config
+    }
+}
+
 /// Network manager for QEMU guest
 pub struct NetworkManager {
     config: NetworkConfig,
+    state: Arc<RwLock<NetworkState>>,
+    lease: Arc<RwLock<Option<DhcpLease>>>,
+    renewal: Mutex<Option<JoinHandle<()>>>,
+    monitor: ConnectivityMonitor,
+    monitor_task: Mutex<Option<JoinHandle<()>>>,
+    /// The TAP device was created by this manager and is removed on shutdown
+    created_tap: AtomicBool,
+    /// Likewise for the network namespace
+    created_netns: AtomicBool,
+    /// The egress policy is installed and is removed on shutdown
+    egress_applied: AtomicBool,
+    /// Why the egress policy could not be installed
+    egress_error: RwLock<Option<String>>,
+    /// Largest unfragmented packet that reached the gateway when last probed
+    path_mtu: RwLock<Option<u32>>,
+    /// Why the interface, firewall and lease can no longer be changed, e.g. after the
+    /// process dropped its capabilities
+    reconfiguration_disabled: RwLock<Option<String>>,
 }
 
 impl NetworkManager {
     pub fn new(config: NetworkConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            state: Arc::new(RwLock::new(NetworkState::Initializing)),
+            lease: Arc::new(RwLock::new(None)),
+            renewal: Mutex::new(None),
+            monitor: ConnectivityMonitor::new(),
+            monitor_task: Mutex::new(None),
+            created_tap: AtomicBool::new(false),
+            created_netns: AtomicBool::new(false),
+            egress_applied: AtomicBool::new(false),
+            egress_error: RwLock::new(None),
+            path_mtu: RwLock::new(None),
+            reconfiguration_disabled: RwLock::new(None),
+        }
     }
 
-    /// Initialize network interfaces and connectivity
+    /// Current initialization state
+    pub fn state(&self) -> NetworkState {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Whether the network was initialized successfully
+    pub fn is_available(&self) -> bool {
+        self.state().is_available()
+    }
+
+    fn set_state(&self, state: NetworkState) {
+        set_state(&self.state, state);
+    }
+
+    /// Current DHCP lease, if the interface is addressed with DHCP
+    pub fn lease(&self) -> Option<DhcpLease> {
+        self.lease.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Gateways to probe, preferred family first
+    fn gateways(&self) -> Vec<IpAddr> {
+        let ipv4 = self.gateway_ip().parse().ok();
+        let ipv6 = self
+            .config
+            .gateway_ipv6
+            .as_deref()
+            .and_then(|gateway| gateway.parse().ok());
+        self.config
+            .ip_preference
+            .order(ipv4.into_iter().chain(ipv6))
+    }
+
+    /// Gateway from the lease, or the configured one
+    fn gateway_ip(&self) -> String {
+        self.lease()
+            .and_then(|lease| lease.gateway)
+            .map(|gateway| gateway.to_string())
+            .unwrap_or_else(|| self.config.gateway_ip.clone())
+    }
+
+    /// DNS servers from the lease, or the configured ones
+    fn dns_servers(&self) -> Vec<String> {
+        match self.lease() {
+            Some(lease) if !lease.dns_servers.is_empty() => {
+                lease.dns_servers.iter().map(|ip| ip.to_string()).collect()
+            }
+            _ => self.config.dns_servers.clone(),
+        }
+    }
+
+    /// Initialize network interfaces and connectivity.
+    ///
+    /// On failure the manager switches to degraded mode (`NetworkUnavailable`) and the error is
+    /// returned so the caller can decide whether to continue without networking.
     pub async fn initialize(&self) -> Result<()> {
+        self.set_state(NetworkState::Initializing);
+
+        match self.initialize_interfaces().await {
+            Ok(()) => {
+                self.set_state(NetworkState::Ready);
+                Ok(())
+            }
+            Err(e) => {
+                warn!("⚠️  Network unavailable, running in degraded mode: {}", e);
+                let since = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                self.set_state(NetworkState::NetworkUnavailable {
+                    reason: e.to_string(),
+                    since,
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// Stop everything that changes the network after initialization.
+    ///
+    /// Repairs are refused, and DHCP lease renewal and the connectivity monitor are stopped,
+    /// since they need `CAP_NET_ADMIN` and `CAP_NET_RAW`. The interface, egress policy and
+    /// lease stay as initialization left them.
+    pub fn disable_reconfiguration(&self, reason: impl Into<String>) {
+        let reason = reason.into();
+        info!("🔒 Network reconfiguration disabled: {}", reason);
+
+        for task in [&self.renewal, &self.monitor_task] {
+            if let Some(task) = task.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                task.abort();
+            }
+        }
+        *self
+            .reconfiguration_disabled
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = Some(reason);
+    }
+
+    /// Why the network can no longer be reconfigured, if it cannot
+    pub fn reconfiguration_disabled(&self) -> Option<String> {
+        self.reconfiguration_disabled
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Runtime network features as (available, unavailable): `network_repair`, plus
+    /// `dhcp_lease_renewal` and `connectivity_monitor` when they were started
+    pub fn runtime_capabilities(&self) -> (Vec<String>, Vec<String>) {
+        let started = |task: &Mutex<Option<JoinHandle<()>>>| {
+            task.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+        };
+
+        let mut capabilities = vec!["network_repair".to_string()];
+        if started(&self.renewal) {
+            capabilities.push("dhcp_lease_renewal".to_string());
+        }
+        if started(&self.monitor_task) {
+            capabilities.push("connectivity_monitor".to_string());
+        }
+
+        match self.reconfiguration_disabled() {
+            Some(_) => (Vec::new(), capabilities),
+            None => (capabilities, Vec::new()),
+        }
+    }
+
+    /// Re-run network initialization on demand and return the resulting state.
+    ///
+    /// With reconfiguration disabled the current state is returned unchanged.
+    pub async fn repair(&self) -> NetworkState {
+        if let Some(reason) = self.reconfiguration_disabled() {
+            warn!("⚠️  Not repairing network: {}", reason);
+            return self.state();
+        }
+        info!("🔧 Repairing network configuration");
+        if self.initialize().await.is_ok() {
+            info!("✅ Network repaired");
+        }
+        self.state()
+    }
+
+    async fn initialize_interfaces(&self) -> Result<()> {
         info!("🌐 Initializing QEMU network configuration");
 
         // Check if we're in a QEMU environment
         self.detect_qemu_environment()?;
 
-        // Setup basic network interfaces
-        self.setup_loopback().await?;
+        self.config
+            .tuning
+            .validate(self.config.ip_preference)
+            .context("Invalid transport tuning")?;
 
-        // Setup TAP interface
-        self.setup_tap_interface().await?;
+        if let Some(netns) = &self.config.netns {
+            self.setup_netns(netns).await?;
+        } else {
+            if let Some(options) = &self.config.create_tap {
+                self.create_tap(options).await?;
+            }
 
-        // Configure routing
-        self.setup_routing().await?;
+            match Netlink::connect() {
+                Ok(netlink) => {
+                    debug!("🔧 Configuring interfaces over netlink");
+                    self.setup_with_netlink(&netlink, &self.config.tap_interface)
+                        .await?;
+                }
+                Err(e)
+                    if self.config.addressing == Addressing::Dhcp
+                        && self.config.ip_preference != IpPreference::Ipv6Only =>
+                {
+                    return Err(e.context("DHCP addressing requires netlink"));
+                }
+                Err(e) => {
+                    warn!("⚠️  Netlink unavailable ({}), falling back to `ip`", e);
+
+                    // Setup basic network interfaces
+                    self.setup_loopback().await?;
+
+                    // Setup TAP interface
+                    self.setup_tap_interface().await?;
+
+                    // Configure routing
+                    self.setup_routing().await?;
+                }
+            }
+        }
 
         // Configure DNS
         self.setup_dns().await?;
 
+        // Restrict enclave egress before anything else goes out
+        self.apply_egress_policy()?;
+
+        self.config.tuning.apply_sysctls();
+        self.check_path_mtu().await;
+
         // Test connectivity
         self.test_connectivity().await?;
 
@@ -72,6 +484,243 @@ impl NetworkManager {
         Ok(())
     }
 
+    /// Create the TAP device if it is missing and attach it to the configured bridge
+    async fn create_tap(&self, options: &TapOptions) -> Result<()> {
+        let tap = TapInterface::new(self.config.tap_interface.clone());
+        if !tap.exists() {
+            tap.create(options)?;
+            self.created_tap.store(true, Ordering::SeqCst);
+        }
+        if let Some(bridge) = &options.bridge {
+            let netlink = Netlink::connect()?;
+            tap.attach_to_bridge(&netlink, bridge).await?;
+        }
+        Ok(())
+    }
+
+    /// Create the enclave's network namespace and address the interface inside it
+    async fn setup_netns(&self, config: &NetnsConfig) -> Result<()> {
+        if self.config.addressing == Addressing::Dhcp {
+            return Err(anyhow!(
+                "DHCP addressing is not supported inside a network namespace"
+            ));
+        }
+        if self.config.create_tap.is_some() {
+            warn!("⚠️  Not creating a TAP interface, the enclave uses a network namespace");
+        }
+
+        let netns = NetworkNamespace::new(config.clone());
+        let existed = netns.exists();
+        let host = Netlink::connect()?;
+        netns.create(&host).await?;
+        if !existed {
+            self.created_netns.store(true, Ordering::SeqCst);
+        }
+        if let Some(mtu) = self.config.tuning.mtu {
+            // Both ends of the veth pair need the same MTU
+            if let Some(index) = host.link_index(&config.host_veth()).await? {
+                host.set_mtu(index, mtu).await?;
+            }
+        }
+
+        let netlink = netns.netlink()?;
+        self.setup_with_netlink(&netlink, &config.interface).await
+    }
+
+    /// Interface enclave traffic leaves through: the host end of the veth pair when the
+    /// enclave has its own namespace, the TAP interface otherwise
+    fn egress_interface(&self) -> String {
+        match &self.config.netns {
+            Some(netns) => netns.host_veth(),
+            None => self.config.tap_interface.clone(),
+        }
+    }
+
+    /// Install the egress allowlist, if one is configured
+    fn apply_egress_policy(&self) -> Result<()> {
+        let Some(policy) = &self.config.egress else {
+            return Ok(());
+        };
+        let result = policy.apply(&self.egress_interface());
+        self.egress_applied.store(result.is_ok(), Ordering::SeqCst);
+        *self.egress_error.write().unwrap_or_else(|e| e.into_inner()) =
+            result.as_ref().err().map(|e| format!("{:#}", e));
+        result.context("Failed to enforce egress policy")
+    }
+
+    /// Configured egress allowlist and the rules active in the kernel
+    pub fn egress_status(&self) -> EgressStatus {
+        let enforced = self.egress_applied.load(Ordering::SeqCst);
+        EgressStatus {
+            enabled: self.config.egress.is_some(),
+            enforced,
+            interface: self.egress_interface(),
+            rules: self
+                .config
+                .egress
+                .iter()
+                .flat_map(|policy| &policy.rules)
+                .map(|rule| rule.to_string())
+                .collect(),
+            error: self
+                .egress_error
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+            active_ruleset: enforced
+                .then(firewall::active_egress_ruleset)
+                .and_then(|ruleset| ruleset.ok()),
+        }
+    }
+
+    /// Remove the TAP device, network namespace and egress policy if this manager
+    /// created them.
+    ///
+    /// With reconfiguration disabled they are left in place, as removing them would fail.
+    pub async fn shutdown(&self) {
+        if self.reconfiguration_disabled().is_some() {
+            info!("ℹ️  Leaving network configuration in place on shutdown");
+            return;
+        }
+        if self.egress_applied.swap(false, Ordering::SeqCst) {
+            if let Err(e) = firewall::remove_egress_policy() {
+                warn!("⚠️  Failed to remove egress policy: {:#}", e);
+            }
+        }
+        if self.created_tap.swap(false, Ordering::SeqCst) {
+            let tap = TapInterface::new(self.config.tap_interface.clone());
+            if let Err(e) = tap.remove() {
+                warn!("⚠️  Failed to remove TAP interface: {:#}", e);
+            }
+        }
+        if self.created_netns.swap(false, Ordering::SeqCst) {
+            if let Some(config) = &self.config.netns {
+                let netns = NetworkNamespace::new(config.clone());
+                let removed = match Netlink::connect() {
+                    Ok(netlink) => netns.remove(&netlink).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = removed {
+                    warn!("⚠️  Failed to remove network namespace: {:#}", e);
+                }
+            }
+        }
+    }
+
+    /// Bring up loopback and the guest interface, assign the guest address and add the
+    /// default route over rtnetlink
+    async fn setup_with_netlink(&self, netlink: &Netlink, interface: &str) -> Result<()> {
+        info!("🔧 Setting up loopback interface");
+        let lo = netlink.set_link_up("lo").await?;
+        netlink
+            .add_address(lo, IpAddr::V4(Ipv4Addr::LOCALHOST), 8)
+            .await?;
+
+        info!("🔧 Setting up interface: {}", interface);
+        if netlink.link_index(interface).await?.is_none() {
+            return Err(anyhow!(
+                "Interface {} not found - a TAP interface may need to be created by QEMU",
+                interface
+            ));
+        }
+        let index = netlink.set_link_up(interface).await?;
+        if let Some(mtu) = self.config.tuning.mtu {
+            netlink.set_mtu(index, mtu).await?;
+            info!("✅ MTU of {} set to {}", interface, mtu);
+        }
+
+        if self.config.ip_preference != IpPreference::Ipv6Only {
+            self.setup_ipv4(netlink, index).await?;
+        }
+        if self.config.ip_preference != IpPreference::Ipv4Only {
+            self.setup_ipv6(netlink, index).await?;
+        }
+        Ok(())
+    }
+
+    /// Static or DHCP IPv4 address and default route
+    async fn setup_ipv4(&self, netlink: &Netlink, index: u32) -> Result<()> {
+        if self.config.addressing == Addressing::Dhcp {
+            let client = DhcpClient::new(&self.config.tap_interface)?;
+            let lease = client.acquire().await?;
+            apply_lease(netlink, index, &lease).await?;
+            *self.lease.write().unwrap_or_else(|e| e.into_inner()) = Some(lease);
+            self.spawn_lease_renewal(client, index);
+            return Ok(());
+        }
+
+        let guest_ip: Ipv4Addr = self
+            .config
+            .guest_ip
+            .parse()
+            .with_context(|| format!("Invalid guest IP {}", self.config.guest_ip))?;
+        let prefix_len = netlink::prefix_len(&self.config.guest_netmask)?;
+        netlink
+            .add_address(index, IpAddr::V4(guest_ip), prefix_len)
+            .await?;
+        info!(
+            "✅ TAP interface IP configured: {}/{}",
+            guest_ip, prefix_len
+        );
+
+        let gateway: Ipv4Addr = self
+            .config
+            .gateway_ip
+            .parse()
+            .with_context(|| format!("Invalid gateway IP {}", self.config.gateway_ip))?;
+        netlink
+            .add_default_route(index, IpAddr::V4(gateway))
+            .await?;
+        info!("✅ Default route configured via {}", gateway);
+
+        Ok(())
+    }
+
+    /// Static IPv6 address and default route, if configured
+    async fn setup_ipv6(&self, netlink: &Netlink, index: u32) -> Result<()> {
+        if let Some(cidr) = &self.config.guest_ipv6 {
+            let (address, prefix_len) = parse_ipv6_cidr(cidr)?;
+            netlink
+                .add_address(index, IpAddr::V6(address), prefix_len)
+                .await?;
+            info!(
+                "✅ TAP interface IPv6 configured: {}/{}",
+                address, prefix_len
+            );
+        } else {
+            debug!("ℹ️  No static IPv6 address, relying on SLAAC");
+        }
+
+        if let Some(gateway) = &self.config.gateway_ipv6 {
+            let gateway: Ipv6Addr = gateway
+                .parse()
+                .with_context(|| format!("Invalid IPv6 gateway {}", gateway))?;
+            netlink
+                .add_default_route(index, IpAddr::V6(gateway))
+                .await?;
+            info!("✅ IPv6 default route configured via {}", gateway);
+        }
+        Ok(())
+    }
+
+    /// Keep the lease alive in the background, replacing any previous renewal task
+    fn spawn_lease_renewal(&self, client: DhcpClient, index: u32) {
+        let task = tokio::spawn(maintain_lease(
+            client,
+            index,
+            Arc::clone(&self.lease),
+            Arc::clone(&self.state),
+        ));
+        let previous = self
+            .renewal
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(task);
+        if let Some(previous) = previous {
+            previous.abort();
+        }
+    }
+
     /// Detect if we're running in a QEMU environment
     fn detect_qemu_environment(&self) -> Result<()> {
         debug!("🔍 Detecting QEMU environment");
@@ -108,7 +757,7 @@ impl NetworkManager {
         Ok(())
     }
 
-    /// Setup loopback interface
+    /// Setup loopback interface with `ip`
     async fn setup_loopback(&self) -> Result<()> {
         info!("🔧 Setting up loopback interface");
 
@@ -146,7 +795,7 @@ impl NetworkManager {
         Ok(())
     }
 
-    /// Setup TAP interface
+    /// Setup TAP interface with `ip`
     async fn setup_tap_interface(&self) -> Result<()> {
         info!("🔧 Setting up TAP interface: {}", self.config.tap_interface);
 
@@ -157,11 +806,10 @@ impl NetworkManager {
             .context("Failed to check TAP interface")?;
 
         if !result.status.success() {
-            warn!(
-                "⚠️  TAP interface {} not found - may need to be created by QEMU",
+            return Err(anyhow!(
+                "TAP interface {} not found - may need to be created by QEMU",
                 self.config.tap_interface
-            );
-            return Ok(());
+            ));
         }
 
         info!("✅ TAP interface {} found", self.config.tap_interface);
@@ -175,10 +823,55 @@ impl NetworkManager {
         if result.status.success() {
             debug!("✅ TAP interface brought up successfully");
         } else {
-            error!(
-                "❌ Failed to bring up TAP interface: {}",
-                String::from_utf8_lossy(&result.stderr)
-            );
+            return Err(anyhow!(
+                "Failed to bring up TAP interface {}: {}",
+                self.config.tap_interface,
+                String::from_utf8_lossy(&result.stderr).trim()
+            ));
+        }
+
+        if let Some(mtu) = self.config.tuning.mtu {
+            let result = Command::new("ip")
+                .args([
+                    "link",
+                    "set",
+                    "dev",
+                    &self.config.tap_interface,
+                    "mtu",
+                    &mtu.to_string(),
+                ])
+                .output()
+                .context("Failed to set TAP interface MTU")?;
+            if !result.status.success() {
+                return Err(anyhow!(
+                    "Failed to set MTU of {} to {}: {}",
+                    self.config.tap_interface,
+                    mtu,
+                    String::from_utf8_lossy(&result.stderr).trim()
+                ));
+            }
+            info!("✅ MTU of {} set to {}", self.config.tap_interface, mtu);
+        }
+
+        if self.config.ip_preference != IpPreference::Ipv4Only {
+            if let Some(cidr) = &self.config.guest_ipv6 {
+                parse_ipv6_cidr(cidr)?;
+                let result = Command::new("ip")
+                    .args(["-6", "addr", "add", cidr, "dev", &self.config.tap_interface])
+                    .output()
+                    .context("Failed to configure TAP interface IPv6")?;
+                if result.status.success() {
+                    info!("✅ TAP interface IPv6 configured: {}", cidr);
+                } else {
+                    debug!(
+                        "ℹ️  TAP interface IPv6 configuration: {}",
+                        String::from_utf8_lossy(&result.stderr)
+                    );
+                }
+            }
+        }
+        if self.config.ip_preference == IpPreference::Ipv6Only {
+            return Ok(());
         }
 
         // Configure IP address
@@ -207,8 +900,37 @@ impl NetworkManager {
         Ok(())
     }
 
-    /// Setup routing
+    /// Setup routing with `ip`
     async fn setup_routing(&self) -> Result<()> {
+        if self.config.ip_preference != IpPreference::Ipv4Only {
+            if let Some(gateway) = &self.config.gateway_ipv6 {
+                let result = Command::new("ip")
+                    .args([
+                        "-6",
+                        "route",
+                        "add",
+                        "default",
+                        "via",
+                        gateway,
+                        "dev",
+                        &self.config.tap_interface,
+                    ])
+                    .output()
+                    .context("Failed to add IPv6 default route")?;
+                if result.status.success() {
+                    info!("✅ IPv6 default route configured via {}", gateway);
+                } else {
+                    debug!(
+                        "ℹ️  IPv6 default route configuration: {}",
+                        String::from_utf8_lossy(&result.stderr)
+                    );
+                }
+            }
+        }
+        if self.config.ip_preference == IpPreference::Ipv6Only {
+            return Ok(());
+        }
+
         info!(
             "🔧 Setting up routing via gateway: {}",
             self.config.gateway_ip
@@ -245,22 +967,58 @@ impl NetworkManager {
     async fn setup_dns(&self) -> Result<()> {
         info!("🔧 Setting up DNS configuration");
 
-        let mut resolv_conf = String::new();
-        for dns in &self.config.dns_servers {
-            resolv_conf.push_str(&format!("nameserver {}\n", dns));
+        write_resolv_conf(&self.dns_servers());
+        Ok(())
+    }
+
+    /// MTU of the interface the enclave's traffic leaves through
+    fn interface_mtu(&self) -> Option<u32> {
+        self.config.tuning.mtu.or_else(|| match &self.config.netns {
+            // The interface is in another namespace, so not in this one's sysfs
+            Some(_) => None,
+            None => tuning::interface_mtu(&self.config.tap_interface),
+        })
+    }
+
+    /// Probe the path MTU to the gateway if enabled, warning when it is smaller than the
+    /// interface MTU, since larger packets are then fragmented or dropped on the way
+    async fn check_path_mtu(&self) {
+        if !self.config.tuning.probe_path_mtu {
+            return;
         }
+        let (Some(gateway), Some(mtu)) = (self.gateways().first().copied(), self.interface_mtu())
+        else {
+            warn!("⚠️  Skipping path MTU probe, no gateway or interface MTU");
+            return;
+        };
+
+        info!("🔍 Probing path MTU to {}", gateway);
+        let probed = tokio::task::spawn_blocking(move || tuning::probe_path_mtu(gateway, mtu))
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+        let path_mtu = match probed {
+            Ok(path_mtu) => path_mtu,
+            Err(e) => {
+                warn!("⚠️  {:#}", e);
+                return;
+            }
+        };
+        *self.path_mtu.write().unwrap_or_else(|e| e.into_inner()) = Some(path_mtu);
 
-        // Write DNS configuration
-        if let Err(e) = fs::write("/etc/resolv.conf", &resolv_conf) {
-            warn!("⚠️  Failed to write DNS configuration: {}", e);
-        } else {
-            info!(
-                "✅ DNS configuration written with {} servers",
-                self.config.dns_servers.len()
+        if path_mtu < mtu {
+            warn!(
+                "⚠️  Path MTU to gateway {} is {} but the interface MTU is {}: large payloads \
+                 will be fragmented or dropped, set {}={}",
+                gateway,
+                path_mtu,
+                mtu,
+                tuning::MTU_ENV,
+                path_mtu
             );
+        } else {
+            info!("✅ Path MTU to gateway {} matches MTU {}", gateway, mtu);
         }
-
-        Ok(())
     }
 
     /// Test network connectivity
@@ -286,12 +1044,10 @@ impl NetworkManager {
     async fn test_loopback(&self) -> Result<()> {
         debug!("🔍 Testing loopback connectivity");
 
-        let result = Command::new("ping")
-            .args(["-c", "1", "-W", "2", "127.0.0.1"])
-            .output()
-            .context("Failed to ping loopback")?;
-
-        if result.status.success() {
+        if ping("127.0.0.1", Duration::from_secs(2))
+            .await
+            .context("Failed to ping loopback")?
+        {
             debug!("✅ Loopback connectivity working");
         } else {
             warn!("⚠️  Loopback connectivity failed");
@@ -302,94 +1058,389 @@ impl NetworkManager {
 
     /// Test gateway connectivity
     async fn test_gateway(&self) -> Result<()> {
-        debug!(
-            "🔍 Testing gateway connectivity: {}",
-            self.config.gateway_ip
-        );
-
-        let result = Command::new("ping")
-            .args(["-c", "1", "-W", "5", &self.config.gateway_ip])
-            .output()
-            .context("Failed to ping gateway")?;
-
-        if result.status.success() {
-            info!(
-                "✅ Gateway connectivity working: {}",
-                self.config.gateway_ip
-            );
-        } else {
-            warn!(
-                "⚠️  Gateway connectivity failed: {}",
-                self.config.gateway_ip
-            );
+        match self.probe_gateway().await {
+            Some(_) => info!("✅ Gateway connectivity working"),
+            None => warn!("⚠️  Gateway connectivity failed: {:?}", self.gateways()),
         }
-
         Ok(())
     }
 
     /// Test external connectivity
     async fn test_external(&self) -> Result<()> {
-        debug!("🔍 Testing external connectivity");
-
-        let test_ips = ["8.8.8.8", "1.1.1.1"];
-
-        for ip in &test_ips {
-            let result = Command::new("ping")
-                .args(["-c", "1", "-W", "5", ip])
-                .output()
-                .context("Failed to ping external IP")?;
-
-            if result.status.success() {
-                info!("✅ External connectivity working: {}", ip);
-                return Ok(());
-            }
+        match self.probe_external().await {
+            Some(_) => info!("✅ External connectivity working"),
+            None => warn!("⚠️  External connectivity failed for all test IPs"),
         }
-
-        warn!("⚠️  External connectivity failed for all test IPs");
         Ok(())
     }
 
     /// Test DNS resolution
     async fn test_dns(&self) -> Result<()> {
+        match self.probe_dns().await {
+            Some(_) => info!("✅ DNS resolution working"),
+            None => warn!("⚠️  DNS resolution failed"),
+        }
+        Ok(())
+    }
+
+    /// Round trip to the first reachable gateway
+    async fn probe_gateway(&self) -> Option<Duration> {
+        debug!("🔍 Testing gateway connectivity");
+        first_reply(&self.gateways(), self.config.connectivity.ping_timeout).await
+    }
+
+    /// Round trip to the first reachable external test IP
+    async fn probe_external(&self) -> Option<Duration> {
+        debug!("🔍 Testing external connectivity");
+        let targets = self
+            .config
+            .ip_preference
+            .order(self.config.connectivity.ping_targets.clone());
+        first_reply(&targets, self.config.connectivity.ping_timeout).await
+    }
+
+    /// Lookup time if a test hostname resolves to an allowed address family
+    async fn probe_dns(&self) -> Option<Duration> {
         debug!("🔍 Testing DNS resolution");
 
-        let result = Command::new("nslookup")
-            .args(["google.com"])
-            .output()
-            .context("Failed to test DNS resolution")?;
+        let resolver = Resolver::for_servers(
+            &self.dns_servers(),
+            self.config.dns_timeout,
+            self.config.ip_preference,
+        );
+        let hostname = self.config.connectivity.dns_names.first()?;
+        let lookup = resolver.lookup(hostname).await.ok()?;
+        (!lookup.addresses.is_empty()).then_some(lookup.duration)
+    }
 
-        if result.status.success() {
-            info!("✅ DNS resolution working");
-        } else {
-            warn!("⚠️  DNS resolution failed");
+    /// Run every probe once and record the results in the rolling status
+    pub async fn run_probes(&self) -> MonitorStatus {
+        let results = [
+            (Probe::Gateway, self.probe_gateway().await),
+            (Probe::External, self.probe_external().await),
+            (Probe::Dns, self.probe_dns().await),
+        ];
+        let now = dhcp::unix_now();
+        for (probe, latency) in results {
+            self.monitor.record(probe, latency, now);
         }
+        self.monitor.finish_run(now);
+        self.monitor.status()
+    }
 
-        Ok(())
+    /// Rolling connectivity status kept by the background monitor
+    pub fn monitor_status(&self) -> MonitorStatus {
+        self.monitor.status()
+    }
+
+    /// Subscribe to connectivity transitions seen by the background monitor
+    pub fn subscribe(&self) -> broadcast::Receiver<ConnectivityEvent> {
+        self.monitor.subscribe()
+    }
+
+    /// Probe connectivity every `interval` in the background while the network is available
+    pub fn spawn_monitor(self: &Arc<Self>, interval: Duration) {
+        if let Some(reason) = self.reconfiguration_disabled() {
+            warn!("⚠️  Not monitoring connectivity: {}", reason);
+            return;
+        }
+        info!("🔍 Monitoring connectivity every {:?}", interval);
+
+        // A weak reference lets the manager drop, which stops the task
+        let manager = Arc::downgrade(self);
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                if manager.is_available() {
+                    manager.run_probes().await;
+                }
+            }
+        });
+        let previous = self
+            .monitor_task
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(task);
+        if let Some(previous) = previous {
+            previous.abort();
+        }
+    }
+
+    /// Read the monitor interval from the environment; `None` if monitoring is disabled
+    pub fn monitor_interval_from_env() -> Option<Duration> {
+        match std::env::var(MONITOR_INTERVAL_ENV) {
+            Ok(value) => value
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            Err(_) => Some(monitor::DEFAULT_INTERVAL),
+        }
     }
 
-    /// Get network status information
+    /// Get network status information.
+    ///
+    /// Connectivity probes are skipped while the network is unavailable. Once the background
+    /// monitor has run, gateway, external and DNS connectivity come from its latest results
+    /// instead of being probed again.
     pub async fn get_status(&self) -> NetworkStatus {
+        let state = self.state();
+        let monitor = self.monitor.status();
+        let connectivity = if !state.is_available() {
+            ConnectivityStatus {
+                loopback: false,
+                gateway: false,
+                external: false,
+                dns: false,
+            }
+        } else if monitor.last_run.is_some() {
+            ConnectivityStatus {
+                loopback: self.probe_loopback().await,
+                gateway: monitor.gateway.up == Some(true),
+                external: monitor.external.up == Some(true),
+                dns: monitor.dns.up == Some(true),
+            }
+        } else {
+            self.check_connectivity().await
+        };
+
+        let lease = self.lease();
         NetworkStatus {
             tap_interface: self.config.tap_interface.clone(),
-            guest_ip: self.config.guest_ip.clone(),
-            gateway_ip: self.config.gateway_ip.clone(),
-            connectivity: self.check_connectivity().await,
+            guest_ip: lease
+                .as_ref()
+                .map(|lease| lease.address.to_string())
+                .unwrap_or_else(|| self.config.guest_ip.clone()),
+            gateway_ip: self.gateway_ip(),
+            guest_ipv6: self.config.guest_ipv6.clone(),
+            gateway_ipv6: self.config.gateway_ipv6.clone(),
+            ip_preference: self.config.ip_preference,
+            mtu: self.interface_mtu(),
+            path_mtu: *self.path_mtu.read().unwrap_or_else(|e| e.into_inner()),
+            proxy: self.config.proxy.as_ref().map(|proxy| proxy.to_string()),
+            state,
+            connectivity,
+            lease,
+            monitor,
         }
     }
 
     /// Check connectivity status
     async fn check_connectivity(&self) -> ConnectivityStatus {
-        let loopback = self.test_loopback().await.is_ok();
-        let gateway = self.test_gateway().await.is_ok();
-        let external = self.test_external().await.is_ok();
-        let dns = self.test_dns().await.is_ok();
-
         ConnectivityStatus {
-            loopback,
-            gateway,
-            external,
-            dns,
+            loopback: self.probe_loopback().await,
+            gateway: self.probe_gateway().await.is_some(),
+            external: self.probe_external().await.is_some(),
+            dns: self.probe_dns().await.is_some(),
+        }
+    }
+
+    async fn probe_loopback(&self) -> bool {
+        ping("127.0.0.1", Duration::from_secs(2))
+            .await
+            .unwrap_or(false)
+    }
+}
+
+/// Round trip to the first of `targets` that answers a ping within `timeout`
+async fn first_reply(targets: &[IpAddr], timeout: Duration) -> Option<Duration> {
+    for target in targets {
+        let start = Instant::now();
+        match ping(&target.to_string(), timeout).await {
+            Ok(true) => {
+                debug!("✅ Reply from {}", target);
+                return Some(start.elapsed());
+            }
+            Ok(false) => {}
+            Err(e) => debug!("ℹ️  Failed to ping {}: {}", target, e),
+        }
+    }
+    None
+}
+
+/// Ping `target` once over ICMP, falling back to the `ping` binary when no ICMP socket can be
+/// opened. Errors mean the check could not run, `Ok(false)` that there was no reply.
+pub async fn ping(target: &str, timeout: Duration) -> Result<bool> {
+    let address: IpAddr = target
+        .parse()
+        .with_context(|| format!("Invalid IP address {}", target))?;
+    if icmp::is_available(&address) {
+        let result = tokio::task::spawn_blocking(move || icmp::ping(address, 1, timeout)).await?;
+        if let Err(e) = &result {
+            debug!("ℹ️  {}", e);
+        }
+        return Ok(result.is_ok());
+    }
+
+    let output = Command::new("ping")
+        .args([
+            if address.is_ipv6() { "-6" } else { "-4" },
+            "-c",
+            "1",
+            "-W",
+            &timeout.as_secs().max(1).to_string(),
+            target,
+        ])
+        .output()
+        .context("Failed to execute ping")?;
+    Ok(output.status.success())
+}
+
+impl Drop for NetworkManager {
+    fn drop(&mut self) {
+        for task in [&mut self.renewal, &mut self.monitor_task] {
+            if let Some(task) = task.get_mut().unwrap_or_else(|e| e.into_inner()).take() {
+                task.abort();
+            }
+        }
+    }
+}
+
+fn set_state(state: &RwLock<NetworkState>, value: NetworkState) {
+    *state.write().unwrap_or_else(|e| e.into_inner()) = value;
+}
+
+/// Renew the lease from T1, rebind from T2 and acquire a new one once it has expired
+async fn maintain_lease(
+    client: DhcpClient,
+    index: u32,
+    lease: Arc<RwLock<Option<DhcpLease>>>,
+    state: Arc<RwLock<NetworkState>>,
+) {
+    loop {
+        let Some(mut current) = lease.read().unwrap_or_else(|e| e.into_inner()).clone() else {
+            return;
+        };
+
+        let now = dhcp::unix_now();
+        let wake = match current.state {
+            LeaseState::Bound => current.renew_at(),
+            _ => now + LEASE_RETRY_INTERVAL.as_secs(),
+        };
+        tokio::time::sleep(Duration::from_secs(wake.saturating_sub(now))).await;
+
+        let now = dhcp::unix_now();
+        current.state = if now >= current.expires_at() {
+            LeaseState::Expired
+        } else if now >= current.rebind_at() {
+            LeaseState::Rebinding
+        } else {
+            LeaseState::Renewing
+        };
+        *lease.write().unwrap_or_else(|e| e.into_inner()) = Some(current.clone());
+
+        let result = if current.state == LeaseState::Expired {
+            warn!("⚠️  DHCP lease for {} expired", current.address);
+            set_state(
+                &state,
+                NetworkState::NetworkUnavailable {
+                    reason: "DHCP lease expired".to_string(),
+                    since: now,
+                },
+            );
+            client.acquire().await
+        } else {
+            debug!("🔄 DHCP lease {}", current.state.as_str());
+            client.renew(&current).await
+        };
+
+        let renewed = match result {
+            Ok(renewed) => renewed,
+            Err(e) => {
+                warn!("⚠️  DHCP {} failed: {}", current.state.as_str(), e);
+                continue;
+            }
+        };
+
+        let changed = renewed.address != current.address
+            || renewed.prefix_len != current.prefix_len
+            || renewed.gateway != current.gateway
+            || current.state == LeaseState::Expired;
+        if changed {
+            let applied = match Netlink::connect() {
+                Ok(netlink) => apply_lease(&netlink, index, &renewed).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = applied {
+                warn!("⚠️  Failed to apply DHCP lease {}: {}", renewed.address, e);
+                continue;
+            }
+        }
+        if renewed.dns_servers != current.dns_servers && !renewed.dns_servers.is_empty() {
+            let servers: Vec<String> = renewed
+                .dns_servers
+                .iter()
+                .map(|ip| ip.to_string())
+                .collect();
+            write_resolv_conf(&servers);
         }
+        if current.state == LeaseState::Expired {
+            set_state(&state, NetworkState::Ready);
+        }
+
+        info!(
+            "✅ DHCP lease {} valid for {}s",
+            renewed.address, renewed.lease_secs
+        );
+        *lease.write().unwrap_or_else(|e| e.into_inner()) = Some(renewed);
+    }
+}
+
+/// Configure the leased address and default route on the interface
+async fn apply_lease(netlink: &Netlink, index: u32, lease: &DhcpLease) -> Result<()> {
+    netlink
+        .add_address(index, IpAddr::V4(lease.address), lease.prefix_len)
+        .await?;
+    info!(
+        "✅ TAP interface IP configured: {}/{}",
+        lease.address, lease.prefix_len
+    );
+
+    if let Some(gateway) = lease.gateway {
+        netlink
+            .add_default_route(index, IpAddr::V4(gateway))
+            .await?;
+        info!("✅ Default route configured via {}", gateway);
+    }
+    Ok(())
+}
+
+/// Split `address/prefix_len` into an IPv6 address and prefix length
+pub fn parse_ipv6_cidr(cidr: &str) -> Result<(Ipv6Addr, u8)> {
+    let (address, prefix_len) = cidr
+        .split_once('/')
+        .ok_or_else(|| anyhow!("IPv6 address {} has no prefix length", cidr))?;
+    let address: Ipv6Addr = address
+        .parse()
+        .with_context(|| format!("Invalid IPv6 address {}", cidr))?;
+    let prefix_len: u8 = prefix_len
+        .parse()
+        .ok()
+        .filter(|len| *len <= 128)
+        .ok_or_else(|| anyhow!("Invalid IPv6 prefix length in {}", cidr))?;
+    Ok((address, prefix_len))
+}
+
+fn write_resolv_conf(servers: &[String]) {
+    let mut resolv_conf = String::new();
+    for dns in servers {
+        resolv_conf.push_str(&format!("nameserver {}\n", dns));
+    }
+
+    // Write DNS configuration
+    if let Err(e) = fs::write("/etc/resolv.conf", &resolv_conf) {
+        warn!("⚠️  Failed to write DNS configuration: {}", e);
+    } else {
+        info!(
+            "✅ DNS configuration written with {} servers",
+            servers.len()
+        );
     }
 }
 
@@ -398,7 +1449,21 @@ pub struct NetworkStatus {
     pub tap_interface: String,
     pub guest_ip: String,
     pub gateway_ip: String,
+    pub guest_ipv6: Option<String>,
+    pub gateway_ipv6: Option<String>,
+    pub ip_preference: IpPreference,
+    /// MTU of the guest interface
+    pub mtu: Option<u32>,
+    /// Largest unfragmented packet that reached the gateway, if probed
+    pub path_mtu: Option<u32>,
+    /// Egress proxy without credentials
+    pub proxy: Option<String>,
+    pub state: NetworkState,
     pub connectivity: ConnectivityStatus,
+    /// DHCP lease when the interface uses DHCP addressing
+    pub lease: Option<DhcpLease>,
+    /// Rolling status from the background monitor
+    pub monitor: MonitorStatus,
 }
 
 #[derive(Debug, Clone)]
@@ -408,3 +1473,146 @@ pub struct ConnectivityStatus {
     pub external: bool,
     pub dns: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_status_skips_probes_when_unavailable() {
+        let manager = NetworkManager::new(NetworkConfig::default());
+        assert_eq!(manager.state(), NetworkState::Initializing);
+
+        manager.set_state(NetworkState::NetworkUnavailable {
+            reason: "TAP interface tap0 not found".to_string(),
+            since: 0,
+        });
+        assert!(!manager.is_available());
+
+        let status = manager.get_status().await;
+        assert!(!status.state.is_available());
+        assert!(!status.connectivity.loopback);
+        assert!(!status.connectivity.external);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_reconfiguration() {
+        let manager = Arc::new(NetworkManager::new(NetworkConfig::default()));
+        manager.spawn_monitor(Duration::from_secs(3600));
+        assert_eq!(
+            manager.runtime_capabilities(),
+            (
+                vec![
+                    "network_repair".to_string(),
+                    "connectivity_monitor".to_string()
+                ],
+                Vec::new()
+            )
+        );
+
+        manager.disable_reconfiguration("running unprivileged");
+        assert_eq!(
+            manager.reconfiguration_disabled().as_deref(),
+            Some("running unprivileged")
+        );
+        let (available, unavailable) = manager.runtime_capabilities();
+        assert!(available.is_empty());
+        assert_eq!(unavailable, ["network_repair", "connectivity_monitor"]);
+
+        // The monitor is stopped and repair leaves the state alone
+        tokio::task::yield_now().await;
+        let monitor = manager.monitor_task.lock().unwrap().take().unwrap();
+        assert!(monitor.await.unwrap_err().is_cancelled());
+        assert_eq!(manager.repair().await, NetworkState::Initializing);
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_dhcp_lease() {
+        let manager = NetworkManager::new(NetworkConfig {
+            addressing: Addressing::Dhcp,
+            ..NetworkConfig::default()
+        });
+        manager.set_state(NetworkState::NetworkUnavailable {
+            reason: "DHCP lease expired".to_string(),
+            since: 0,
+        });
+        *manager.lease.write().unwrap() = Some(DhcpLease {
+            address: Ipv4Addr::new(10, 0, 2, 15),
+            prefix_len: 24,
+            gateway: Some(Ipv4Addr::new(10, 0, 2, 2)),
+            dns_servers: vec![Ipv4Addr::new(10, 0, 2, 3)],
+            server: Ipv4Addr::new(10, 0, 2, 2),
+            lease_secs: 86_400,
+            renew_secs: 43_200,
+            rebind_secs: 75_600,
+            acquired_at: 0,
+            state: LeaseState::Expired,
+        });
+
+        let status = manager.get_status().await;
+        assert_eq!(status.guest_ip, "10.0.2.15");
+        assert_eq!(status.gateway_ip, "10.0.2.2");
+        assert_eq!(status.lease.unwrap().state, LeaseState::Expired);
+        assert_eq!(manager.dns_servers(), vec!["10.0.2.3".to_string()]);
+    }
+
+    #[test]
+    fn test_ip_preference_order() {
+        assert_eq!(IpPreference::parse("IPv6"), Some(IpPreference::Ipv6First));
+        assert_eq!(
+            IpPreference::parse("ipv4-only"),
+            Some(IpPreference::Ipv4Only)
+        );
+        assert_eq!(IpPreference::parse("dual"), None);
+
+        let ordered = IpPreference::Ipv6First.order(EXTERNAL_TEST_IPS);
+        assert_eq!(ordered[0], EXTERNAL_TEST_IPS[2]);
+        assert_eq!(ordered[2], EXTERNAL_TEST_IPS[0]);
+        assert_eq!(
+            IpPreference::Ipv4First.order(EXTERNAL_TEST_IPS),
+            EXTERNAL_TEST_IPS
+        );
+        assert_eq!(
+            IpPreference::Ipv6Only.order(EXTERNAL_TEST_IPS),
+            &EXTERNAL_TEST_IPS[2..]
+        );
+    }
+
+    #[test]
+    fn test_parse_ipv6_cidr() {
+        let (address, prefix_len) = parse_ipv6_cidr("fd00:100::2/64").unwrap();
+        assert_eq!(address, "fd00:100::2".parse::<Ipv6Addr>().unwrap());
+        assert_eq!(prefix_len, 64);
+
+        assert!(parse_ipv6_cidr("fd00:100::2").is_err());
+        assert!(parse_ipv6_cidr("fd00:100::2/129").is_err());
+        assert!(parse_ipv6_cidr("192.168.100.2/24").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_status_uses_monitor_results() {
+        let manager = NetworkManager::new(NetworkConfig::default());
+        manager.set_state(NetworkState::Ready);
+        let mut events = manager.subscribe();
+
+        manager
+            .monitor
+            .record(Probe::Gateway, Some(Duration::from_millis(3)), 1);
+        manager.monitor.record(Probe::Gateway, None, 2);
+        manager
+            .monitor
+            .record(Probe::Dns, Some(Duration::from_millis(8)), 2);
+        manager.monitor.finish_run(2);
+
+        let status = manager.get_status().await;
+        assert!(!status.connectivity.gateway);
+        assert!(!status.connectivity.external);
+        assert!(status.connectivity.dns);
+        assert_eq!(status.monitor.gateway.history.len(), 2);
+        assert_eq!(status.monitor.last_run, Some(2));
+
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.probe, Probe::Gateway);
+        assert!(!event.up);
+    }
+}
@@ -0,0 +1,238 @@
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Environment variable for the background connectivity probe interval (seconds, 0 disables)
+pub const MONITOR_INTERVAL_ENV: &str = "RENCLAVE_NETWORK_MONITOR_SECS";
+
+pub(crate) const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Samples kept per probe
+pub const HISTORY_LEN: usize = 20;
+
+/// Connectivity checks run by the monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Probe {
+    Gateway,
+    External,
+    Dns,
+}
+
+impl Probe {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gateway => "gateway",
+            Self::External => "external",
+            Self::Dns => "dns",
+        }
+    }
+}
+
+/// Outcome of one probe
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProbeSample {
+    pub timestamp: u64,
+    pub reachable: bool,
+    /// Round trip for pings, lookup time for DNS; `None` if unreachable
+    pub latency_ms: Option<f64>,
+}
+
+/// Rolling status of one probe
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProbeStatus {
+    /// `None` until the first probe has run
+    pub up: Option<bool>,
+    pub last_checked: Option<u64>,
+    /// When `up` last changed
+    pub since: Option<u64>,
+    pub consecutive_failures: u32,
+    /// Most recent samples, oldest first
+    pub history: VecDeque<ProbeSample>,
+}
+
+impl ProbeStatus {
+    /// Mean latency over the successful samples in the history
+    pub fn average_latency_ms(&self) -> Option<f64> {
+        let latencies: Vec<f64> = self
+            .history
+            .iter()
+            .filter_map(|sample| sample.latency_ms)
+            .collect();
+        (!latencies.is_empty()).then(|| latencies.iter().sum::<f64>() / latencies.len() as f64)
+    }
+}
+
+/// Status of all probes
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MonitorStatus {
+    pub gateway: ProbeStatus,
+    pub external: ProbeStatus,
+    pub dns: ProbeStatus,
+    /// When the last full round of probes finished
+    pub last_run: Option<u64>,
+}
+
+impl MonitorStatus {
+    pub fn probe(&self, probe: Probe) -> &ProbeStatus {
+        match probe {
+            Probe::Gateway => &self.gateway,
+            Probe::External => &self.external,
+            Probe::Dns => &self.dns,
+        }
+    }
+
+    fn probe_mut(&mut self, probe: Probe) -> &mut ProbeStatus {
+        match probe {
+            Probe::Gateway => &mut self.gateway,
+            Probe::External => &mut self.external,
+            Probe::Dns => &mut self.dns,
+        }
+    }
+}
+
+/// A probe changed between reachable and unreachable
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectivityEvent {
+    pub probe: Probe,
+    pub up: bool,
+    pub timestamp: u64,
+}
+
+/// Rolling connectivity status with transition events
+pub struct ConnectivityMonitor {
+    status: RwLock<MonitorStatus>,
+    events: broadcast::Sender<ConnectivityEvent>,
+}
+
+impl Default for ConnectivityMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectivityMonitor {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(16);
+        Self {
+            status: RwLock::new(MonitorStatus::default()),
+            events,
+        }
+    }
+
+    pub fn status(&self) -> MonitorStatus {
+        self.status
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Subscribe to transition events
+    pub fn subscribe(&self) -> broadcast::Receiver<ConnectivityEvent> {
+        self.events.subscribe()
+    }
+
+    /// Record a probe result (`latency` is `None` if unreachable), emitting an event if the
+    /// probe changed state. The first result of a probe is not a transition.
+    pub fn record(
+        &self,
+        probe: Probe,
+        latency: Option<Duration>,
+        timestamp: u64,
+    ) -> Option<ConnectivityEvent> {
+        let up = latency.is_some();
+        let mut status = self.status.write().unwrap_or_else(|e| e.into_inner());
+        let entry = status.probe_mut(probe);
+
+        if entry.history.len() == HISTORY_LEN {
+            entry.history.pop_front();
+        }
+        entry.history.push_back(ProbeSample {
+            timestamp,
+            reachable: up,
+            latency_ms: latency.map(|d| d.as_secs_f64() * 1000.0),
+        });
+        entry.last_checked = Some(timestamp);
+        entry.consecutive_failures = if up {
+            0
+        } else {
+            entry.consecutive_failures + 1
+        };
+
+        let previous = entry.up.replace(up);
+        if previous == Some(up) {
+            return None;
+        }
+        entry.since = Some(timestamp);
+        previous?;
+
+        let event = ConnectivityEvent {
+            probe,
+            up,
+            timestamp,
+        };
+        if up {
+            info!("✅ {} connectivity restored", probe.as_str());
+        } else {
+            warn!("⚠️  {} connectivity lost", probe.as_str());
+        }
+        // No subscribers is fine
+        let _ = self.events.send(event.clone());
+        Some(event)
+    }
+
+    /// Mark a full round of probes as finished
+    pub fn finish_run(&self, timestamp: u64) {
+        self.status
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .last_run = Some(timestamp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions_emit_events() {
+        let monitor = ConnectivityMonitor::new();
+        let mut events = monitor.subscribe();
+        let ms = Duration::from_millis;
+
+        assert_eq!(monitor.record(Probe::Gateway, Some(ms(2)), 1), None);
+        assert_eq!(monitor.record(Probe::Gateway, Some(ms(4)), 2), None);
+
+        let down = monitor.record(Probe::Gateway, None, 3).unwrap();
+        assert!(!down.up);
+        assert_eq!(events.try_recv().unwrap(), down);
+        assert_eq!(monitor.record(Probe::Gateway, None, 4), None);
+
+        let up = monitor.record(Probe::Gateway, Some(ms(6)), 5).unwrap();
+        assert!(up.up);
+
+        let status = monitor.status();
+        assert_eq!(status.gateway.up, Some(true));
+        assert_eq!(status.gateway.since, Some(5));
+        assert_eq!(status.gateway.consecutive_failures, 0);
+        assert_eq!(status.gateway.average_latency_ms(), Some(4.0));
+        assert_eq!(status.dns.up, None);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let monitor = ConnectivityMonitor::new();
+        for timestamp in 0..(HISTORY_LEN as u64 + 5) {
+            monitor.record(Probe::Dns, None, timestamp);
+        }
+
+        let status = monitor.status();
+        assert_eq!(status.dns.history.len(), HISTORY_LEN);
+        assert_eq!(status.dns.history[0].timestamp, 5);
+        assert_eq!(status.dns.consecutive_failures, HISTORY_LEN as u32 + 5);
+        assert_eq!(status.dns.average_latency_ms(), None);
+    }
+}
@@ -0,0 +1,186 @@
+use anyhow::{anyhow, Context, Result};
+use futures::TryStreamExt;
+use log::debug;
+use nix::errno::Errno;
+use rtnetlink::Handle;
+use std::net::{IpAddr, Ipv4Addr};
+use std::os::fd::RawFd;
+
+/// Interface, address and route configuration over rtnetlink, without the `ip` binary
+pub struct Netlink {
+    handle: Handle,
+}
+
+impl Netlink {
+    /// Open a route netlink socket; the connection is driven on the current tokio runtime
+    pub fn connect() -> Result<Self> {
+        let (connection, handle, _) =
+            rtnetlink::new_connection().context("Failed to open netlink socket")?;
+        tokio::spawn(connection);
+        Ok(Self { handle })
+    }
+
+    /// Index of the interface `name`, or `None` if it does not exist
+    pub async fn link_index(&self, name: &str) -> Result<Option<u32>> {
+        let mut links = self
+            .handle
+            .link()
+            .get()
+            .match_name(name.to_string())
+            .execute();
+
+        match links.try_next().await {
+            Ok(link) => Ok(link.map(|link| link.header.index)),
+            Err(e) if errno(&e) == Some(Errno::ENODEV) => Ok(None),
+            Err(e) => Err(anyhow!("Failed to look up interface {}: {}", name, e)),
+        }
+    }
+
+    /// Set the interface `name` administratively up
+    pub async fn set_link_up(&self, name: &str) -> Result<u32> {
+        let index = self
+            .link_index(name)
+            .await?
+            .ok_or_else(|| anyhow!("Interface {} not found", name))?;
+
+        self.handle
+            .link()
+            .set(index)
+            .up()
+            .execute()
+            .await
+            .with_context(|| format!("Failed to bring up {}", name))?;
+        debug!("✅ Interface {} (index {}) is up", name, index);
+        Ok(index)
+    }
+
+    /// Set the MTU of the interface
+    pub async fn set_mtu(&self, index: u32, mtu: u32) -> Result<()> {
+        self.handle
+            .link()
+            .set(index)
+            .mtu(mtu)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to set MTU of interface {} to {}", index, mtu))
+    }
+
+    /// Enslave the interface to the bridge `master`
+    pub async fn set_master(&self, index: u32, master: u32) -> Result<()> {
+        self.handle
+            .link()
+            .set(index)
+            .master(master)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to attach interface {} to {}", index, master))
+    }
+
+    /// Create the veth pair `name` <-> `peer`
+    pub async fn add_veth(&self, name: &str, peer: &str) -> Result<()> {
+        self.handle
+            .link()
+            .add()
+            .veth(name.to_string(), peer.to_string())
+            .execute()
+            .await
+            .with_context(|| format!("Failed to create veth pair {} <-> {}", name, peer))
+    }
+
+    /// Move the interface into the network namespace open at `netns`
+    pub async fn move_to_netns(&self, index: u32, netns: RawFd) -> Result<()> {
+        self.handle
+            .link()
+            .set(index)
+            .setns_by_fd(netns)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to move interface {} to namespace", index))
+    }
+
+    /// Delete the interface; deleting one end of a veth pair deletes both
+    pub async fn delete_link(&self, index: u32) -> Result<()> {
+        self.handle
+            .link()
+            .del(index)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to delete interface {}", index))
+    }
+
+    /// Add `address/prefix_len` to the interface; an existing address is not an error
+    pub async fn add_address(&self, index: u32, address: IpAddr, prefix_len: u8) -> Result<()> {
+        let result = self
+            .handle
+            .address()
+            .add(index, address, prefix_len)
+            .execute()
+            .await;
+
+        match result {
+            Err(e) if errno(&e) == Some(Errno::EEXIST) => {
+                debug!("ℹ️  Address {}/{} already configured", address, prefix_len);
+                Ok(())
+            }
+            other => {
+                other.with_context(|| format!("Failed to add address {}/{}", address, prefix_len))
+            }
+        }
+    }
+
+    /// Add a default route via `gateway` on the interface; an existing route is not an error
+    pub async fn add_default_route(&self, index: u32, gateway: IpAddr) -> Result<()> {
+        let route = self.handle.route().add().output_interface(index);
+        let result = match gateway {
+            IpAddr::V4(gateway) => route.v4().gateway(gateway).execute().await,
+            IpAddr::V6(gateway) => route.v6().gateway(gateway).execute().await,
+        };
+
+        match result {
+            Err(e) if errno(&e) == Some(Errno::EEXIST) => {
+                debug!("ℹ️  Default route already configured");
+                Ok(())
+            }
+            other => other.with_context(|| format!("Failed to add default route via {}", gateway)),
+        }
+    }
+}
+
+/// Errno carried by a netlink NACK
+fn errno(error: &rtnetlink::Error) -> Option<Errno> {
+    match error {
+        rtnetlink::Error::NetlinkError(message) => {
+            message.code.map(|code| Errno::from_i32(code.get().abs()))
+        }
+        _ => None,
+    }
+}
+
+/// Prefix length of a dotted-quad netmask such as `255.255.255.0`
+pub fn prefix_len(netmask: &str) -> Result<u8> {
+    let mask: Ipv4Addr = netmask
+        .parse()
+        .with_context(|| format!("Invalid netmask {}", netmask))?;
+    let bits = u32::from(mask);
+
+    if bits.leading_ones() + bits.trailing_zeros() != 32 {
+        return Err(anyhow!("Netmask {} is not contiguous", netmask));
+    }
+    Ok(bits.leading_ones() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_len() {
+        assert_eq!(prefix_len("255.255.255.0").unwrap(), 24);
+        assert_eq!(prefix_len("255.255.240.0").unwrap(), 20);
+        assert_eq!(prefix_len("255.255.255.255").unwrap(), 32);
+        assert_eq!(prefix_len("0.0.0.0").unwrap(), 0);
+
+        assert!(prefix_len("255.0.255.0").is_err());
+        assert!(prefix_len("255.255.255").is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::marker::PhantomData;
+use std::os::fd::AsRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+use crate::netlink::Netlink;
+use crate::tap::require_net_admin;
+
+/// Environment variable naming the network namespace the enclave runs in
+pub const NETNS_ENV: &str = "RENCLAVE_NETNS";
+/// Environment variable for the host bridge the namespace's veth pair joins
+pub const NETNS_BRIDGE_ENV: &str = "RENCLAVE_NETNS_BRIDGE";
+/// Environment variable for the name of the interface inside the namespace
+pub const NETNS_INTERFACE_ENV: &str = "RENCLAVE_NETNS_INTERFACE";
+
+/// Where named namespaces are mounted, shared with `ip netns`
+const NETNS_DIR: &str = "/var/run/netns";
+/// Longest namespace name; the host end of the veth pair is `ve-<name>` and interface
+/// names are limited to 15 bytes
+const MAX_NAME_LEN: usize = 12;
+
+/// Dedicated network namespace for the enclave, reached over a veth pair
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetnsConfig {
+    pub name: String,
+    /// End of the veth pair inside the namespace, addressed like the TAP interface
+    pub interface: String,
+    /// Host bridge the other end joins; `None` leaves connecting it to the deployment
+    pub bridge: Option<String>,
+}
+
+impl NetnsConfig {
+    pub fn new(name: impl Into<String>) -> Result<Self> {
+        let name = name.into();
+        let valid = !name.is_empty()
+            && name.len() <= MAX_NAME_LEN
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(anyhow!(
+                "Invalid namespace name '{}': use up to {} letters, digits, '-' and '_'",
+                name,
+                MAX_NAME_LEN
+            ));
+        }
+        Ok(Self {
+            name,
+            interface: "eth0".to_string(),
+            bridge: None,
+        })
+    }
+
+    /// Configuration from the environment, `None` unless `RENCLAVE_NETNS` is set
+    pub fn from_env() -> Result<Option<Self>> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let Some(name) = var(NETNS_ENV) else {
+            return Ok(None);
+        };
+
+        let mut config = Self::new(name)?;
+        if let Some(interface) = var(NETNS_INTERFACE_ENV) {
+            config.interface = interface;
+        }
+        config.bridge = var(NETNS_BRIDGE_ENV);
+        Ok(Some(config))
+    }
+
+    /// Host end of the veth pair
+    pub fn host_veth(&self) -> String {
+        format!("ve-{}", self.name)
+    }
+
+    /// Mount point of the namespace, which processes join with setns(2)
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(NETNS_DIR).join(&self.name)
+    }
+}
+
+/// A named network namespace connected to the host over a veth pair
+pub struct NetworkNamespace {
+    config: NetnsConfig,
+}
+
+impl NetworkNamespace {
+    pub fn new(config: NetnsConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &NetnsConfig {
+        &self.config
+    }
+
+    /// Check if the namespace is mounted
+    pub fn exists(&self) -> bool {
+        self.config.path().exists()
+    }
+
+    /// Create the namespace and its veth pair, moving one end inside and attaching the
+    /// other to the bridge. Parts that already exist are left as they are. Needs
+    /// CAP_NET_ADMIN and CAP_SYS_ADMIN.
+    pub async fn create(&self, netlink: &Netlink) -> Result<()> {
+        require_net_admin("Creating a network namespace")?;
+        if self.exists() {
+            info!("ℹ️  Network namespace {} already exists", self.config.name);
+        } else {
+            info!("🔧 Creating network namespace: {}", self.config.name);
+            self.mount()?;
+        }
+
+        let host_veth = self.config.host_veth();
+        if netlink.link_index(&host_veth).await?.is_none() {
+            netlink.add_veth(&host_veth, &self.config.interface).await?;
+            let peer = netlink
+                .link_index(&self.config.interface)
+                .await?
+                .ok_or_else(|| anyhow!("veth peer {} not found", self.config.interface))?;
+            let netns = self.open()?;
+            netlink.move_to_netns(peer, netns.as_raw_fd()).await?;
+        }
+
+        let index = netlink.set_link_up(&host_veth).await?;
+        if let Some(bridge) = &self.config.bridge {
+            let bridge_index = netlink
+                .link_index(bridge)
+                .await?
+                .ok_or_else(|| anyhow!("Bridge {} not found", bridge))?;
+            netlink.set_master(index, bridge_index).await?;
+            info!("✅ veth {} attached to bridge {}", host_veth, bridge);
+        }
+
+        info!(
+            "✅ Network namespace {} ready ({} <-> {})",
+            self.config.name, host_veth, self.config.interface
+        );
+        Ok(())
+    }
+
+    /// Netlink connection operating inside the namespace
+    pub fn netlink(&self) -> Result<Netlink> {
+        let _guard = self.enter()?;
+        // The socket stays in the namespace it was opened in
+        Netlink::connect()
+    }
+
+    /// Move the current thread into the namespace until the guard is dropped.
+    ///
+    /// Namespaces are per thread, so the guard cannot be held across an await.
+    pub fn enter(&self) -> Result<NetnsGuard> {
+        let previous = File::open("/proc/thread-self/ns/net")
+            .context("Failed to open the current network namespace")?;
+        setns(&self.open()?).context("Failed to enter network namespace")?;
+        Ok(NetnsGuard {
+            previous,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Delete the veth pair and unmount the namespace
+    pub async fn remove(&self, netlink: &Netlink) -> Result<()> {
+        info!("🗑️  Removing network namespace: {}", self.config.name);
+        if let Some(index) = netlink.link_index(&self.config.host_veth()).await? {
+            netlink.delete_link(index).await?;
+        }
+        if self.exists() {
+            let path = path_cstring(&self.config.path())?;
+            // SAFETY: `path` is a valid NUL-terminated string
+            if unsafe { libc::umount2(path.as_ptr(), libc::MNT_DETACH) } < 0 {
+                return Err(std::io::Error::last_os_error())
+                    .with_context(|| format!("Failed to unmount {}", path.to_string_lossy()));
+            }
+            fs::remove_file(self.config.path())?;
+        }
+        info!("✅ Network namespace removed: {}", self.config.name);
+        Ok(())
+    }
+
+    fn open(&self) -> Result<File> {
+        File::open(self.config.path()).with_context(|| {
+            format!(
+                "Failed to open network namespace {}",
+                self.config.path().display()
+            )
+        })
+    }
+
+    /// Mount a new namespace at the configured path, like `ip netns add`.
+    ///
+    /// The namespace is unshared on a short-lived thread so the rest of the process keeps
+    /// its own, and outlives that thread through the bind mount.
+    fn mount(&self) -> Result<()> {
+        fs::create_dir_all(NETNS_DIR).with_context(|| format!("Failed to create {}", NETNS_DIR))?;
+        let path = self.config.path();
+        File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let target = path_cstring(&path)?;
+
+        let result = std::thread::spawn(move || -> Result<()> {
+            // SAFETY: unshare only affects the calling thread for CLONE_NEWNET
+            if unsafe { libc::unshare(libc::CLONE_NEWNET) } < 0 {
+                return Err(std::io::Error::last_os_error())
+                    .context("Failed to unshare the network namespace (needs CAP_SYS_ADMIN)");
+            }
+            let source = CString::new("/proc/thread-self/ns/net")?;
+            // SAFETY: both paths are valid NUL-terminated strings and the remaining
+            // arguments may be null for a bind mount
+            let mounted = unsafe {
+                libc::mount(
+                    source.as_ptr(),
+                    target.as_ptr(),
+                    std::ptr::null(),
+                    libc::MS_BIND,
+                    std::ptr::null(),
+                )
+            };
+            if mounted < 0 {
+                return Err(std::io::Error::last_os_error())
+                    .context("Failed to bind mount the network namespace");
+            }
+            Ok(())
+        })
+        .join()
+        .map_err(|_| anyhow!("Namespace thread panicked"))?;
+
+        if result.is_err() {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("⚠️  Failed to clean up {}: {}", path.display(), e);
+            }
+        }
+        result
+    }
+}
+
+/// Returns the current thread to its previous network namespace when dropped
+pub struct NetnsGuard {
+    previous: File,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for NetnsGuard {
+    fn drop(&mut self) {
+        if let Err(e) = setns(&self.previous) {
+            // Carrying on would leave this worker thread in the enclave's namespace
+            panic!("Failed to return to the host network namespace: {}", e);
+        }
+        debug!("🔧 Left network namespace");
+    }
+}
+
+/// Move the calling thread into the network namespace open at `netns`.
+///
+/// Does not allocate, so it is also usable between fork and exec to start a process
+/// inside the namespace.
+pub fn setns(netns: &File) -> std::io::Result<()> {
+    // SAFETY: the descriptor is open for the duration of the call
+    if unsafe { libc::setns(netns.as_raw_fd(), libc::CLONE_NEWNET) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn path_cstring(path: &std::path::Path) -> Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| anyhow!("Invalid path {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let config = NetnsConfig::new("signer").unwrap();
+        assert_eq!(config.host_veth(), "ve-signer");
+        assert_eq!(config.path(), PathBuf::from("/var/run/netns/signer"));
+        assert_eq!(config.interface, "eth0");
+
+        assert!(NetnsConfig::new("").is_err());
+        assert!(NetnsConfig::new("enclave/../x").is_err());
+        assert!(NetnsConfig::new("much-too-long-name").is_err());
+    }
+}
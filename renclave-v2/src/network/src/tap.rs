@@ -1,7 +1,66 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use log::{debug, info, warn};
+use std::fs::{self, File, OpenOptions};
+use std::io::ErrorKind;
+use std::os::fd::AsRawFd;
+use std::path::Path;
 use std::process::Command;
 
+use crate::netlink::Netlink;
+
+/// Environment variable asking the network manager to create the TAP device itself
+pub const TAP_CREATE_ENV: &str = "RENCLAVE_TAP_CREATE";
+/// Environment variable for the uid allowed to attach to a created TAP device, e.g. QEMU's
+pub const TAP_OWNER_ENV: &str = "RENCLAVE_TAP_OWNER";
+/// Environment variable for the bridge a created TAP device joins
+pub const TAP_BRIDGE_ENV: &str = "RENCLAVE_TAP_BRIDGE";
+
+const TUN_DEVICE: &str = "/dev/net/tun";
+/// Bit of CAP_NET_ADMIN in the capability sets of /proc/self/status
+const CAP_NET_ADMIN: u32 = 12;
+
+/// How a TAP device created by renclave is set up
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TapOptions {
+    /// Uid that may attach to the device without CAP_NET_ADMIN
+    pub owner: Option<u32>,
+    /// Gid that may attach to the device without CAP_NET_ADMIN
+    pub group: Option<u32>,
+    /// Bridge the device is enslaved to
+    pub bridge: Option<String>,
+}
+
+impl TapOptions {
+    /// Options from the environment, `None` unless `RENCLAVE_TAP_CREATE` is set
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let create = var(TAP_CREATE_ENV)
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        if !create {
+            return None;
+        }
+
+        let owner = var(TAP_OWNER_ENV).and_then(|v| match v.parse::<u32>() {
+            Ok(uid) => Some(uid),
+            Err(_) => {
+                warn!("⚠️  Ignoring invalid {}: {}", TAP_OWNER_ENV, v);
+                None
+            }
+        });
+        Some(Self {
+            owner,
+            group: None,
+            bridge: var(TAP_BRIDGE_ENV),
+        })
+    }
+}
+
 /// TAP interface management
 pub struct TapInterface {
     name: String,
@@ -12,26 +71,55 @@ impl TapInterface {
         Self { name }
     }
 
-    /// Create TAP interface (usually done by QEMU)
-    pub fn create(&self) -> Result<()> {
-        info!("🔧 Creating TAP interface: {}", self.name);
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 
-        // Note: In production, QEMU creates the TAP interface
-        // This is mainly for testing/development
-        let result = Command::new("ip")
-            .args(["tuntap", "add", "dev", &self.name, "mode", "tap"])
-            .output()
-            .context("Failed to create TAP interface")?;
+    /// Create a persistent TAP device through `/dev/net/tun`.
+    ///
+    /// The device outlives this process's file descriptor so QEMU can attach to it later.
+    /// An existing device is left as it is. Needs CAP_NET_ADMIN.
+    pub fn create(&self, options: &TapOptions) -> Result<()> {
+        if self.exists() {
+            info!("ℹ️  TAP interface {} already exists", self.name);
+            return Ok(());
+        }
+        info!("🔧 Creating TAP interface: {}", self.name);
+        require_net_admin("Creating a TAP interface")?;
 
-        if result.status.success() {
-            info!("✅ TAP interface created: {}", self.name);
-        } else {
-            warn!(
-                "⚠️  TAP interface creation failed: {}",
-                String::from_utf8_lossy(&result.stderr)
-            );
+        let tun = open_tun()?;
+        set_iff(&tun, &self.name)?;
+        if let Some(uid) = options.owner {
+            tun_ioctl(&tun, libc::TUNSETOWNER, uid as libc::c_ulong)
+                .with_context(|| format!("Failed to set owner of {} to uid {}", self.name, uid))?;
+        }
+        if let Some(gid) = options.group {
+            tun_ioctl(&tun, libc::TUNSETGROUP, gid as libc::c_ulong)
+                .with_context(|| format!("Failed to set group of {} to gid {}", self.name, gid))?;
         }
+        tun_ioctl(&tun, libc::TUNSETPERSIST, 1)
+            .with_context(|| format!("Failed to make {} persistent", self.name))?;
+
+        info!("✅ TAP interface created: {}", self.name);
+        Ok(())
+    }
+
+    /// Enslave the device to `bridge`
+    pub async fn attach_to_bridge(&self, netlink: &Netlink, bridge: &str) -> Result<()> {
+        let index = netlink
+            .link_index(&self.name)
+            .await?
+            .ok_or_else(|| anyhow!("TAP interface {} not found", self.name))?;
+        let bridge_index = netlink
+            .link_index(bridge)
+            .await?
+            .ok_or_else(|| anyhow!("Bridge {} not found", bridge))?;
 
+        netlink.set_master(index, bridge_index).await?;
+        info!(
+            "✅ TAP interface {} attached to bridge {}",
+            self.name, bridge
+        );
         Ok(())
     }
 
@@ -74,70 +162,44 @@ impl TapInterface {
         Ok(())
     }
 
-    /// Remove TAP interface
+    /// Remove a persistent TAP device.
+    ///
+    /// The device disappears once every process attached to it, such as QEMU, has closed it.
     pub fn remove(&self) -> Result<()> {
+        if !self.exists() {
+            debug!("ℹ️  TAP interface {} already removed", self.name);
+            return Ok(());
+        }
         info!("🗑️  Removing TAP interface: {}", self.name);
+        require_net_admin("Removing a TAP interface")?;
 
-        let result = Command::new("ip")
-            .args(["link", "delete", &self.name])
-            .output()
-            .context("Failed to remove TAP interface")?;
-
-        if result.status.success() {
-            info!("✅ TAP interface removed: {}", self.name);
-        } else {
-            warn!(
-                "⚠️  TAP interface removal failed: {}",
-                String::from_utf8_lossy(&result.stderr)
-            );
-        }
+        let tun = open_tun()?;
+        set_iff(&tun, &self.name)?;
+        tun_ioctl(&tun, libc::TUNSETPERSIST, 0)
+            .with_context(|| format!("Failed to clear persistence of {}", self.name))?;
 
+        info!("✅ TAP interface removed: {}", self.name);
         Ok(())
     }
 
     /// Check if TAP interface exists
     pub fn exists(&self) -> bool {
-        let result = Command::new("ip")
-            .args(["link", "show", &self.name])
-            .output();
-
-        match result {
-            Ok(output) => output.status.success(),
-            Err(_) => false,
-        }
+        Path::new("/sys/class/net").join(&self.name).exists()
     }
 
     /// Get TAP interface statistics
     pub fn get_stats(&self) -> Result<TapStats> {
-        let result = Command::new("cat")
-            .args([&format!("/sys/class/net/{}/statistics/rx_bytes", self.name)])
-            .output()
-            .context("Failed to read RX bytes")?;
-
-        let rx_bytes = if result.status.success() {
-            String::from_utf8_lossy(&result.stdout)
-                .trim()
-                .parse()
-                .unwrap_or(0)
-        } else {
-            0
-        };
-
-        let result = Command::new("cat")
-            .args([&format!("/sys/class/net/{}/statistics/tx_bytes", self.name)])
-            .output()
-            .context("Failed to read TX bytes")?;
-
-        let tx_bytes = if result.status.success() {
-            String::from_utf8_lossy(&result.stdout)
-                .trim()
-                .parse()
+        let counter = |name: &str| {
+            fs::read_to_string(format!("/sys/class/net/{}/statistics/{}", self.name, name))
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
                 .unwrap_or(0)
-        } else {
-            0
         };
 
-        Ok(TapStats { rx_bytes, tx_bytes })
+        Ok(TapStats {
+            rx_bytes: counter("rx_bytes"),
+            tx_bytes: counter("tx_bytes"),
+        })
     }
 }
 
@@ -146,3 +208,116 @@ pub struct TapStats {
     pub rx_bytes: u64,
     pub tx_bytes: u64,
 }
+
+/// Whether this process holds CAP_NET_ADMIN, which root normally does
+pub fn has_net_admin() -> bool {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .as_deref()
+        .and_then(effective_capabilities)
+        .is_some_and(|caps| caps & (1 << CAP_NET_ADMIN) != 0)
+}
+
+/// `CapEff` mask from the contents of /proc/<pid>/status
+fn effective_capabilities(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+}
+
+pub(crate) fn require_net_admin(action: &str) -> Result<()> {
+    if has_net_admin() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "{} needs CAP_NET_ADMIN: run as root, grant it with `setcap cap_net_admin+ep <binary>`, \
+         or create the device beforehand with `ip tuntap add`",
+        action
+    ))
+}
+
+fn open_tun() -> Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(TUN_DEVICE)
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => anyhow!(
+                "{} not found: load the tun module (`modprobe tun`) or pass the device into \
+                 the container",
+                TUN_DEVICE
+            ),
+            ErrorKind::PermissionDenied => {
+                anyhow!(
+                    "Permission denied opening {}: needs CAP_NET_ADMIN",
+                    TUN_DEVICE
+                )
+            }
+            _ => anyhow!("Failed to open {}: {}", TUN_DEVICE, e),
+        })
+}
+
+/// `name` as a NUL-terminated ifreq name
+fn interface_name(name: &str) -> Result<[libc::c_char; libc::IFNAMSIZ]> {
+    if name.is_empty() || name.len() >= libc::IFNAMSIZ || name.contains(['/', '\0']) {
+        return Err(anyhow!("Invalid interface name '{}'", name));
+    }
+    let mut ifr_name = [0; libc::IFNAMSIZ];
+    for (slot, byte) in ifr_name.iter_mut().zip(name.bytes()) {
+        *slot = byte as libc::c_char;
+    }
+    Ok(ifr_name)
+}
+
+/// Bind `tun` to the TAP device `name`, creating it if needed
+fn set_iff(tun: &File, name: &str) -> Result<()> {
+    // SAFETY: ifreq is plain old data, so all zeroes is a valid value
+    let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+    request.ifr_name = interface_name(name)?;
+    request.ifr_ifru.ifru_flags = (libc::IFF_TAP | libc::IFF_NO_PI) as libc::c_short;
+
+    tun_ioctl(
+        tun,
+        libc::TUNSETIFF,
+        &request as *const libc::ifreq as libc::c_ulong,
+    )
+    .with_context(|| format!("Failed to attach to TAP interface {}", name))
+}
+
+fn tun_ioctl(tun: &File, request: libc::Ioctl, arg: libc::c_ulong) -> Result<()> {
+    // SAFETY: the descriptor is open, and every TUN request used here takes an integer or
+    // a pointer to an ifreq that outlives the call
+    let result = unsafe { libc::ioctl(tun.as_raw_fd(), request, arg) };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_capabilities() {
+        let status = "Name:\thost\nCapInh:\t0000000000000000\nCapEff:\t0000000000001000\n";
+        let caps = effective_capabilities(status).unwrap();
+        assert_ne!(caps & (1 << CAP_NET_ADMIN), 0);
+
+        assert_eq!(effective_capabilities("Name:\thost\n"), None);
+    }
+
+    #[test]
+    fn test_interface_name() {
+        let name = interface_name("tap0").unwrap();
+        assert_eq!(
+            &name[..5],
+            &[b't' as libc::c_char, b'a' as _, b'p' as _, b'0' as _, 0]
+        );
+
+        assert!(interface_name("").is_err());
+        assert!(interface_name("a-very-long-interface-name").is_err());
+        assert!(interface_name("tap/0").is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use std::fs;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::icmp;
+use crate::IpPreference;
+
+/// Environment variable for the MTU set on the guest interface
+pub const MTU_ENV: &str = "RENCLAVE_MTU";
+/// Environment variable enabling path MTU probing towards the gateway during initialization
+pub const PATH_MTU_PROBE_ENV: &str = "RENCLAVE_PATH_MTU_PROBE";
+/// Environment variable for the kernel's TCP MTU probing mode (`net.ipv4.tcp_mtu_probing`)
+pub const TCP_MTU_PROBING_ENV: &str = "RENCLAVE_TCP_MTU_PROBING";
+/// Environment variable for the largest socket receive buffer in bytes
+pub const SOCKET_RECV_BUFFER_ENV: &str = "RENCLAVE_SOCKET_RECV_BUFFER";
+/// Environment variable for the largest socket send buffer in bytes
+pub const SOCKET_SEND_BUFFER_ENV: &str = "RENCLAVE_SOCKET_SEND_BUFFER";
+
+/// Smallest MTU every IPv4 host must accept
+const MIN_MTU_V4: u32 = 576;
+/// Smallest MTU allowed on an IPv6 link
+const MIN_MTU_V6: u32 = 1280;
+const MAX_MTU: u32 = 65535;
+/// Smallest socket buffer accepted, the kernel's own minimum for TCP
+const MIN_SOCKET_BUFFER: usize = 4096;
+/// How long each path MTU probe waits for its reply
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const SYSCTL_DIR: &str = "/proc/sys/net";
+
+/// MTU, path MTU discovery and socket buffer settings for traffic over the TAP path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransportTuning {
+    /// MTU set on the guest interface; `None` keeps the interface's own
+    pub mtu: Option<u32>,
+    /// Probe the largest unfragmented packet that reaches the gateway
+    pub probe_path_mtu: bool,
+    /// TCP packetization-layer MTU probing: 0 off, 1 once a black hole is detected, 2 always
+    pub tcp_mtu_probing: Option<u8>,
+    /// Largest receive buffer a socket may use, which also caps TCP autotuning
+    pub recv_buffer: Option<usize>,
+    /// Largest send buffer a socket may use, which also caps TCP autotuning
+    pub send_buffer: Option<usize>,
+}
+
+impl TransportTuning {
+    /// Settings from the environment
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        fn number<T: std::str::FromStr>(name: &str, value: Option<String>) -> Result<Option<T>> {
+            value
+                .map(|v| {
+                    v.parse()
+                        .map_err(|_| anyhow!("{} must be a number, got '{}'", name, v))
+                })
+                .transpose()
+        }
+
+        Ok(Self {
+            mtu: number(MTU_ENV, var(MTU_ENV))?,
+            probe_path_mtu: var(PATH_MTU_PROBE_ENV)
+                .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            tcp_mtu_probing: number(TCP_MTU_PROBING_ENV, var(TCP_MTU_PROBING_ENV))?,
+            recv_buffer: number(SOCKET_RECV_BUFFER_ENV, var(SOCKET_RECV_BUFFER_ENV))?,
+            send_buffer: number(SOCKET_SEND_BUFFER_ENV, var(SOCKET_SEND_BUFFER_ENV))?,
+        })
+    }
+
+    /// Check the settings against the address families in use
+    pub fn validate(&self, preference: IpPreference) -> Result<()> {
+        if let Some(mtu) = self.mtu {
+            let min = if preference == IpPreference::Ipv4Only {
+                MIN_MTU_V4
+            } else {
+                MIN_MTU_V6
+            };
+            if !(min..=MAX_MTU).contains(&mtu) {
+                return Err(anyhow!(
+                    "{} must be between {} and {}{}, got {}",
+                    MTU_ENV,
+                    min,
+                    MAX_MTU,
+                    if min == MIN_MTU_V6 {
+                        " with IPv6 enabled"
+                    } else {
+                        ""
+                    },
+                    mtu
+                ));
+            }
+        }
+        if self.tcp_mtu_probing.is_some_and(|mode| mode > 2) {
+            return Err(anyhow!("{} must be 0, 1 or 2", TCP_MTU_PROBING_ENV));
+        }
+        for (name, size) in [
+            (SOCKET_RECV_BUFFER_ENV, self.recv_buffer),
+            (SOCKET_SEND_BUFFER_ENV, self.send_buffer),
+        ] {
+            if size.is_some_and(|size| size < MIN_SOCKET_BUFFER) {
+                return Err(anyhow!("{} must be at least {}", name, MIN_SOCKET_BUFFER));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the TCP and socket buffer sysctls. Settings the kernel rejects, e.g. because
+    /// /proc/sys is read-only, are logged and skipped.
+    pub fn apply_sysctls(&self) {
+        for (name, value) in self.sysctls(|name| fs::read_to_string(sysctl_path(name)).ok()) {
+            match fs::write(sysctl_path(&name), &value) {
+                Ok(()) => info!("✅ Set {} = {}", name, value),
+                Err(e) => warn!("⚠️  Failed to set {} = {}: {}", name, value, e),
+            }
+        }
+    }
+
+    /// Sysctls to write, given a reader for their current values
+    fn sysctls(&self, current: impl Fn(&str) -> Option<String>) -> Vec<(String, String)> {
+        let mut sysctls = Vec::new();
+        if let Some(mode) = self.tcp_mtu_probing {
+            sysctls.push(("ipv4/tcp_mtu_probing".to_string(), mode.to_string()));
+        }
+        for (core, tcp, size) in [
+            ("core/rmem_max", "ipv4/tcp_rmem", self.recv_buffer),
+            ("core/wmem_max", "ipv4/tcp_wmem", self.send_buffer),
+        ] {
+            let Some(size) = size else {
+                continue;
+            };
+            sysctls.push((core.to_string(), size.to_string()));
+            match current(tcp)
+                .as_deref()
+                .and_then(|value| with_max(value, size))
+            {
+                Some(value) => sysctls.push((tcp.to_string(), value)),
+                None => debug!("ℹ️  Leaving {} as it is, current value unreadable", tcp),
+            }
+        }
+        sysctls
+    }
+}
+
+/// Current MTU of the interface `name`
+pub fn interface_mtu(name: &str) -> Option<u32> {
+    fs::read_to_string(format!("/sys/class/net/{}/mtu", name))
+        .ok()
+        .and_then(|mtu| mtu.trim().parse().ok())
+}
+
+/// Largest packet up to `max` bytes that reaches `target` unfragmented. Blocking.
+///
+/// Fails if even the smallest MTU of the address family gets no reply, since then `target`
+/// does not answer pings at all.
+pub fn probe_path_mtu(target: IpAddr, max: u32) -> Result<u32> {
+    let min = match target {
+        IpAddr::V4(_) => MIN_MTU_V4,
+        IpAddr::V6(_) => MIN_MTU_V6,
+    };
+    let mut sequence = 0u16;
+    let mut fits = |size: u32| {
+        sequence = sequence.wrapping_add(1);
+        match icmp::ping_unfragmented(target, sequence, size as usize, PROBE_TIMEOUT) {
+            Ok(_) => true,
+            Err(e) => {
+                debug!("ℹ️  {} byte probe to {}: {}", size, target, e);
+                false
+            }
+        }
+    };
+    if !fits(min) {
+        return Err(anyhow!(
+            "Path MTU probe failed: {} does not answer {} byte pings",
+            target,
+            min
+        ));
+    }
+    Ok(largest_fitting(min, max.max(min), fits))
+}
+
+/// Binary search for the largest size in `low..=high` that fits, given that `low` does
+fn largest_fitting(mut low: u32, mut high: u32, mut fits: impl FnMut(u32) -> bool) -> u32 {
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+/// `min default max` buffer sysctl with the maximum set to `max`, lowering the others to
+/// fit under it
+fn with_max(value: &str, max: usize) -> Option<String> {
+    let fields: Vec<usize> = value
+        .split_whitespace()
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    let [min, default, _] = fields[..] else {
+        return None;
+    };
+    Some(format!("{} {} {}", min.min(max), default.min(max), max))
+}
+
+fn sysctl_path(name: &str) -> String {
+    format!("{}/{}", SYSCTL_DIR, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let mut tuning = TransportTuning {
+            mtu: Some(1400),
+            ..Default::default()
+        };
+        assert!(tuning.validate(IpPreference::Ipv4First).is_ok());
+
+        tuning.mtu = Some(1000);
+        assert!(tuning.validate(IpPreference::Ipv4First).is_err());
+        assert!(tuning.validate(IpPreference::Ipv4Only).is_ok());
+
+        tuning.mtu = None;
+        tuning.tcp_mtu_probing = Some(3);
+        assert!(tuning.validate(IpPreference::Ipv4Only).is_err());
+
+        tuning.tcp_mtu_probing = Some(1);
+        tuning.recv_buffer = Some(1024);
+        assert!(tuning.validate(IpPreference::Ipv4Only).is_err());
+    }
+
+    #[test]
+    fn test_largest_fitting() {
+        assert_eq!(largest_fitting(576, 1500, |size| size <= 1400), 1400);
+        assert_eq!(largest_fitting(576, 1500, |_| true), 1500);
+        assert_eq!(largest_fitting(576, 1500, |size| size == 576), 576);
+        assert_eq!(largest_fitting(1280, 1280, |_| false), 1280);
+    }
+
+    #[test]
+    fn test_sysctls() {
+        let tuning = TransportTuning {
+            tcp_mtu_probing: Some(1),
+            recv_buffer: Some(4 << 20),
+            send_buffer: Some(65536),
+            ..Default::default()
+        };
+        let sysctls = tuning.sysctls(|name| match name {
+            "ipv4/tcp_rmem" => Some("4096\t131072\t6291456\n".to_string()),
+            _ => None,
+        });
+        let sysctls: Vec<(&str, &str)> = sysctls
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(
+            sysctls,
+            [
+                ("ipv4/tcp_mtu_probing", "1"),
+                ("core/rmem_max", "4194304"),
+                ("ipv4/tcp_rmem", "4096 131072 4194304"),
+                ("core/wmem_max", "65536"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_max() {
+        assert_eq!(
+            with_max("4096 16384 4194304", 8192).as_deref(),
+            Some("4096 8192 8192")
+        );
+        assert_eq!(with_max("4096 16384", 8192), None);
+        assert_eq!(with_max("", 8192), None);
+    }
+}
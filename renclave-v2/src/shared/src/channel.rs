@@ -0,0 +1,312 @@
+//! End-to-end encrypted channel between a client and the enclave.
+//!
+//! The client sends an ephemeral secp256k1 public key with `OpenChannel`; the enclave answers
+//! with its own ephemeral public key and an attestation binding both keys to the session.
+//! Both sides derive per-direction keys from the ECDH secret with HKDF-SHA256. Payloads are
+//! sealed with ChaCha20-Poly1305 (RFC 8439): the counter is the nonce and the session id the
+//! associated data, so the host only ever forwards ciphertext.
+
+use bitcoin_hashes::{sha256, Hash, HashEngine};
+use borsh::{BorshDeserialize, BorshSerialize};
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce, Tag};
+use hkdf::Hkdf;
+use secp256k1::ecdh::SharedSecret;
+use secp256k1::{PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use utoipa::ToSchema;
+
+use crate::{RenclaveErrorCode, SecretBytes};
+
+/// HKDF info string; changing the key schedule or the cipher requires a new version
+const KDF_INFO: &[u8] = b"renclave-channel-v2";
+
+/// Errors opening or using an encrypted channel
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ChannelError {
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+
+    #[error("Unknown or expired channel session")]
+    UnknownSession,
+
+    #[error("Malformed sealed payload: {0}")]
+    Malformed(String),
+
+    #[error("Sealed payload failed authentication")]
+    Authentication,
+
+    #[error("Sealed payload counter {counter} was already used")]
+    Replay { counter: u64 },
+}
+
+impl ChannelError {
+    /// Enclave error code: unknown sessions are gone, everything else is a bad request
+    pub fn code(&self) -> RenclaveErrorCode {
+        match self {
+            ChannelError::UnknownSession => RenclaveErrorCode::NotFound,
+            _ => RenclaveErrorCode::InvalidRequest,
+        }
+    }
+}
+
+/// Which side sealed a payload; each direction has its own keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    ClientToEnclave,
+    EnclaveToClient,
+}
+
+/// Encrypted, authenticated payload.
+///
+/// `counter` is the nonce and must increase with every request on a session; the enclave
+/// answers with the counter of the request it is responding to.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema,
+)]
+pub struct SealedPayload {
+    pub counter: u64,
+    /// Hex ciphertext
+    pub ciphertext: String,
+    /// Hex Poly1305 tag over the session id and ciphertext
+    pub tag: String,
+}
+
+/// Session keys derived from the handshake
+pub struct ChannelKeys {
+    client_to_enclave: ChaCha20Poly1305,
+    enclave_to_client: ChaCha20Poly1305,
+}
+
+impl ChannelKeys {
+    /// Derive the session keys from our ephemeral secret and the peer's public key.
+    ///
+    /// Both sides pass the client and enclave public keys in the same order, so the
+    /// derivation is bound to this exact handshake.
+    pub fn derive(
+        own_secret: &SecretKey,
+        peer_public: &PublicKey,
+        client_public: &PublicKey,
+        enclave_public: &PublicKey,
+    ) -> Self {
+        let shared = SharedSecret::new(peer_public, own_secret);
+
+        let mut salt = Vec::with_capacity(66);
+        salt.extend_from_slice(&client_public.serialize());
+        salt.extend_from_slice(&enclave_public.serialize());
+
+        let mut okm = SecretBytes::zeroed(64);
+        Hkdf::<Sha256>::new(Some(&salt), &shared.secret_bytes())
+            .expand(KDF_INFO, okm.expose_secret_mut())
+            .expect("64 bytes is a valid HKDF-SHA256 output length");
+
+        let (client_to_enclave, enclave_to_client) = okm.expose_secret().split_at(32);
+        Self {
+            client_to_enclave: ChaCha20Poly1305::new(client_to_enclave.into()),
+            enclave_to_client: ChaCha20Poly1305::new(enclave_to_client.into()),
+        }
+    }
+
+    /// Encrypt and authenticate `plaintext` for `session_id`
+    pub fn seal(
+        &self,
+        direction: Direction,
+        session_id: &str,
+        counter: u64,
+        plaintext: &[u8],
+    ) -> SealedPayload {
+        let mut ciphertext = plaintext.to_vec();
+        let tag = self
+            .cipher(direction)
+            .encrypt_in_place_detached(&nonce(counter), session_id.as_bytes(), &mut ciphertext)
+            .expect("channel payloads are far below the ChaCha20 length limit");
+
+        SealedPayload {
+            counter,
+            ciphertext: hex::encode(ciphertext),
+            tag: hex::encode(tag),
+        }
+    }
+
+    /// Verify and decrypt a payload sealed for `session_id`
+    pub fn open(
+        &self,
+        direction: Direction,
+        session_id: &str,
+        payload: &SealedPayload,
+    ) -> Result<SecretBytes, ChannelError> {
+        let (ciphertext, tag) = decode(payload)?;
+
+        let mut plaintext = SecretBytes::new(ciphertext);
+        self.cipher(direction)
+            .decrypt_in_place_detached(
+                &nonce(payload.counter),
+                session_id.as_bytes(),
+                plaintext.expose_secret_mut(),
+                &tag,
+            )
+            .map_err(|_| ChannelError::Authentication)?;
+        Ok(plaintext)
+    }
+
+    /// Check that a payload was sealed for `session_id` with these keys.
+    ///
+    /// The plaintext is only kept long enough to check the tag.
+    pub fn verify(
+        &self,
+        direction: Direction,
+        session_id: &str,
+        payload: &SealedPayload,
+    ) -> Result<(), ChannelError> {
+        self.open(direction, session_id, payload).map(|_| ())
+    }
+
+    fn cipher(&self, direction: Direction) -> &ChaCha20Poly1305 {
+        match direction {
+            Direction::ClientToEnclave => &self.client_to_enclave,
+            Direction::EnclaveToClient => &self.enclave_to_client,
+        }
+    }
+}
+
+/// 96-bit nonce: four zero bytes followed by the big-endian counter.
+///
+/// Each direction has its own key, so a counter is never used twice with the same key as
+/// long as the enclave rejects replayed counters.
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+fn decode(payload: &SealedPayload) -> Result<(Vec<u8>, Tag), ChannelError> {
+    let ciphertext =
+        hex::decode(&payload.ciphertext).map_err(|e| ChannelError::Malformed(e.to_string()))?;
+    let tag = hex::decode(&payload.tag).map_err(|e| ChannelError::Malformed(e.to_string()))?;
+    if tag.len() != 16 {
+        return Err(ChannelError::Malformed(format!(
+            "tag must be 16 bytes, got {}",
+            tag.len()
+        )));
+    }
+    Ok((ciphertext, *Tag::from_slice(&tag)))
+}
+
+/// Parse a hex-encoded compressed or uncompressed secp256k1 public key
+pub fn parse_public_key(value: &str) -> Result<PublicKey, ChannelError> {
+    let bytes =
+        hex::decode(value.trim()).map_err(|e| ChannelError::InvalidPublicKey(e.to_string()))?;
+    PublicKey::from_slice(&bytes).map_err(|e| ChannelError::InvalidPublicKey(e.to_string()))
+}
+
+/// Hash of the handshake, carried as attestation user data so clients can check that the
+/// attested enclave chose `enclave_public` for their `client_public`
+pub fn handshake_hash(
+    session_id: &str,
+    client_public: &PublicKey,
+    enclave_public: &PublicKey,
+) -> [u8; 32] {
+    let mut engine = sha256::Hash::engine();
+    engine.input(KDF_INFO);
+    engine.input(session_id.as_bytes());
+    engine.input(&client_public.serialize());
+    engine.input(&enclave_public.serialize());
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::Secp256k1;
+
+    fn keypair(byte: u8) -> (SecretKey, PublicKey) {
+        let secret = SecretKey::from_slice(&[byte; 32]).unwrap();
+        (secret, secret.public_key(&Secp256k1::new()))
+    }
+
+    fn handshake() -> (ChannelKeys, ChannelKeys) {
+        let (client_secret, client_public) = keypair(1);
+        let (enclave_secret, enclave_public) = keypair(2);
+
+        let client = ChannelKeys::derive(
+            &client_secret,
+            &enclave_public,
+            &client_public,
+            &enclave_public,
+        );
+        let enclave = ChannelKeys::derive(
+            &enclave_secret,
+            &client_public,
+            &client_public,
+            &enclave_public,
+        );
+        (client, enclave)
+    }
+
+    #[test]
+    fn test_known_answer() {
+        // Computed independently with the Python `cryptography` package from the same
+        // ECDH secret, HKDF salt and info, nonce layout and associated data
+        let (client, enclave) = handshake();
+
+        let sealed = client.seal(Direction::ClientToEnclave, "session", 1, b"seed phrase");
+        assert_eq!(sealed.ciphertext, "507dcf061e0a1ec8576286");
+        assert_eq!(sealed.tag, "cd25330631d6cba89c62b334cf05a28d");
+
+        let reply = enclave.seal(Direction::EnclaveToClient, "session", 2, b"result");
+        assert_eq!(reply.ciphertext, "aa986f15badd");
+        assert_eq!(reply.tag, "5ed57b93237eeff9acf28a7155049d63");
+    }
+
+    #[test]
+    fn test_round_trip_between_client_and_enclave() {
+        let (client, enclave) = handshake();
+
+        let sealed = client.seal(Direction::ClientToEnclave, "session", 1, b"seed phrase");
+        assert_ne!(sealed.ciphertext, hex::encode(b"seed phrase"));
+
+        let opened = enclave
+            .open(Direction::ClientToEnclave, "session", &sealed)
+            .unwrap();
+        assert_eq!(opened.expose_secret(), b"seed phrase");
+
+        let reply = enclave.seal(Direction::EnclaveToClient, "session", 1, b"result");
+        let opened = client
+            .open(Direction::EnclaveToClient, "session", &reply)
+            .unwrap();
+        assert_eq!(opened.expose_secret(), b"result");
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let (client, enclave) = handshake();
+        let sealed = client.seal(Direction::ClientToEnclave, "session", 7, b"payload");
+
+        let mut flipped = sealed.clone();
+        flipped.ciphertext.replace_range(0..2, "ff");
+        let mut recountered = sealed.clone();
+        recountered.counter = 8;
+
+        for payload in [&flipped, &recountered] {
+            assert_eq!(
+                enclave.open(Direction::ClientToEnclave, "session", payload),
+                Err(ChannelError::Authentication)
+            );
+        }
+        let mut truncated = sealed.clone();
+        truncated.tag.truncate(30);
+        assert!(matches!(
+            enclave.open(Direction::ClientToEnclave, "session", &truncated),
+            Err(ChannelError::Malformed(_))
+        ));
+
+        // Bound to the session and the direction
+        assert!(enclave
+            .open(Direction::ClientToEnclave, "other", &sealed)
+            .is_err());
+        assert!(enclave
+            .open(Direction::EnclaveToClient, "session", &sealed)
+            .is_err());
+    }
+}
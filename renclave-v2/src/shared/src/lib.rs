@@ -1,146 +1,1393 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-/// Request types for communication between host and enclave
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub mod channel;
+pub mod redact;
+pub mod secret;
+pub mod telemetry;
+pub mod validation;
+
+pub use channel::{ChannelError, SealedPayload};
+pub use secret::{SecretBytes, SecretString};
+pub use validation::{FieldError, Validate, ValidationErrors};
+
+/// Environment variable for the Unix socket shared by the enclave and the host
+pub const ENCLAVE_SOCKET_ENV: &str = "RENCLAVE_ENCLAVE_SOCKET";
+
+/// Enclave socket used when `RENCLAVE_ENCLAVE_SOCKET` is unset
+pub const DEFAULT_ENCLAVE_SOCKET: &str = "/tmp/enclave.sock";
+
+/// Path of the enclave socket, from the environment or the default
+pub fn enclave_socket_path() -> String {
+    std::env::var(ENCLAVE_SOCKET_ENV)
+        .ok()
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| DEFAULT_ENCLAVE_SOCKET.to_string())
+}
+
+/// Environment variable for the largest request frame the enclave reads from its socket, in
+/// bytes
+pub const MAX_FRAME_BYTES_ENV: &str = "RENCLAVE_ENCLAVE_MAX_FRAME_BYTES";
+
+/// Request frame limit used when `RENCLAVE_ENCLAVE_MAX_FRAME_BYTES` is unset
+pub const DEFAULT_MAX_FRAME_BYTES: usize = 64 * 1024;
+
+/// Part of a request frame reserved for the `EnclaveRequest` envelope around an operation:
+/// the request id, protocol version, trace context and the operation's variant name.
+///
+/// The operation's fields have the same names as the HTTP request they come from, and
+/// re-serializing a JSON string never makes it longer, so a body of at most
+/// `max_frame_bytes - FRAME_ENVELOPE_BYTES` always fits in one frame.
+pub const FRAME_ENVELOPE_BYTES: usize = 1024;
+
+/// Largest HTTP request body the host can forward in a frame of `max_frame_bytes`
+pub const fn max_forwarded_body_bytes(max_frame_bytes: usize) -> usize {
+    max_frame_bytes.saturating_sub(FRAME_ENVELOPE_BYTES)
+}
+
+/// Environment variable for the Unix socket on which the enclave terminates TLS itself;
+/// unset disables it
+pub const ENCLAVE_TLS_SOCKET_ENV: &str = "RENCLAVE_ENCLAVE_TLS_SOCKET";
+
+/// Path of the enclave's TLS socket, if configured
+pub fn enclave_tls_socket_path() -> Option<String> {
+    std::env::var(ENCLAVE_TLS_SOCKET_ENV)
+        .ok()
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+}
+
+/// Request types for communication between host and enclave.
+///
+/// Requests and responses travel as JSON on the enclave socket. They and everything they
+/// contain also have a Borsh encoding for binary transports and attestation user data: struct
+/// fields in declaration order, enum variants as a `u8` declaration index followed by their
+/// fields, strings, vectors and maps as a `u32` little-endian length followed by the items
+/// (maps sorted by key), and `Option` as a `0`/`1` tag. Variants and fields are therefore only
+/// ever appended, never reordered or removed.
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct EnclaveRequest {
     pub id: String,
     pub operation: EnclaveOperation,
+    /// `PROTOCOL_VERSION` of the sender; 0 for senders that predate versioning
+    #[serde(default)]
+    pub protocol_version: u32,
+    /// W3C `traceparent` of the host span waiting on this request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_context: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Version of the host-enclave protocol, bumped whenever operations or results are added.
+///
+/// Peers ignore fields they do not know and answer operations they do not know with code
+/// 501, so hosts and enclaves of different versions keep working during a rolling upgrade.
+/// `GetInfo` reports the enclave's version and operations.
+pub const PROTOCOL_VERSION: u32 = 7;
+
+/// Wire names of the operations this build understands
+pub const SUPPORTED_OPERATIONS: &[&str] = &[
+    "GenerateSeed",
+    "ValidateSeed",
+    "DeriveKey",
+    "DeriveAddress",
+    "DeriveXpub",
+    "SignPsbt",
+    "GetPolicy",
+    "SetPolicy",
+    "GetInfo",
+    "GetMetricsSnapshot",
+    "RepairNetwork",
+    "OpenChannel",
+    "SealedRequest",
+    "VerifySignature",
+    "VerifyCiphertext",
+    "SetLogLevel",
+    "CreateTlsCsr",
+    "InstallTlsCertificate",
+    "SyncTime",
+    "ThresholdSigning",
+    "GetKeyUsage",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum EnclaveOperation {
     GenerateSeed {
         strength: u32,
-        passphrase: Option<String>,
+        passphrase: Option<SecretString>,
+        #[serde(default)]
+        language: SeedLanguage,
+        /// Attach an attestation committing to the result
+        #[serde(default)]
+        attest: bool,
+        /// Generate the attestation now rather than reusing a recent one for the same result
+        #[serde(default)]
+        fresh_attestation: bool,
     },
     ValidateSeed {
-        seed_phrase: String,
+        seed_phrase: SecretString,
+        #[serde(default)]
+        language: SeedLanguage,
+        /// Hex entropy returned by GenerateSeed, to check the phrase against
+        #[serde(default)]
+        entropy: Option<SecretString>,
     },
     DeriveKey {
-        seed_phrase: String,
+        seed_phrase: SecretString,
         path: String,
         curve: String,
+        /// Attach an attestation committing to the result
+        #[serde(default)]
+        attest: bool,
+        /// Generate the attestation now rather than reusing a recent one for the same result
+        #[serde(default)]
+        fresh_attestation: bool,
     },
     DeriveAddress {
-        seed_phrase: String,
+        seed_phrase: SecretString,
         path: String,
         curve: String,
+        #[serde(default)]
+        chain: Option<Chain>,
+    },
+    DeriveXpub {
+        seed_phrase: SecretString,
+        path: String,
+        format: XpubFormat,
+    },
+    SignPsbt {
+        seed_phrase: SecretString,
+        psbt: String,
+    },
+    GetPolicy,
+    SetPolicy {
+        policy: SigningPolicy,
     },
     GetInfo,
+    GetMetricsSnapshot,
+    RepairNetwork,
+    /// Start an encrypted channel with the client's ephemeral secp256k1 key (hex)
+    OpenChannel {
+        client_public_key: String,
+        /// Hex challenge echoed in the attestation
+        #[serde(default)]
+        nonce: Option<String>,
+    },
+    /// An `EnclaveOperation` encrypted on an open channel
+    SealedRequest {
+        session_id: String,
+        payload: SealedPayload,
+    },
+    /// Check a hex signature over a hex message against a public key
+    VerifySignature {
+        curve: String,
+        public_key: String,
+        message: String,
+        signature: String,
+    },
+    /// Check that a sealed request authenticates on its channel, without decrypting it
+    VerifyCiphertext {
+        session_id: String,
+        payload: SealedPayload,
+    },
+    /// Change the enclave's log verbosity; `None` only reports the current level
+    SetLogLevel {
+        #[serde(default)]
+        level: Option<String>,
+    },
+    /// Certificate signing request for the enclave's TLS key, naming `domains`
+    CreateTlsCsr {
+        domains: Vec<String>,
+    },
+    /// Serve the PEM `certificate_chain`, leaf first, which must certify the enclave's TLS key
+    InstallTlsCertificate {
+        certificate_chain: String,
+    },
+    /// Offer the host's wall clock, Unix milliseconds, to the enclave's clock
+    SyncTime {
+        unix_ms: u64,
+    },
+    /// A round message of a threshold signing session coordinated by the enclave
+    ThresholdSigning {
+        message: ThresholdMessage,
+    },
+    /// Signature counts and ages of the keys the enclave has signed with
+    GetKeyUsage,
 }
 
 /// Response types from enclave to host
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct EnclaveResponse {
     pub id: String,
     pub result: EnclaveResult,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum EnclaveResult {
     SeedGenerated {
-        seed_phrase: String,
-        entropy: String,
+        seed_phrase: SecretString,
+        entropy: SecretString,
         strength: u32,
         word_count: usize,
+        /// Attestation whose `user_data` is the `response_digest` of the HTTP response
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attestation: Option<ChannelAttestation>,
     },
     SeedValidated {
         valid: bool,
         word_count: usize,
+        /// Whether the phrase encodes the supplied entropy; absent if none was supplied
+        #[serde(default)]
+        entropy_match: Option<bool>,
     },
     KeyDerived {
-        private_key: String,
+        private_key: SecretString,
         public_key: String,
         address: String,
         path: String,
         curve: String,
+        /// Attestation whose `user_data` is the `response_digest` of the HTTP response
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attestation: Option<ChannelAttestation>,
     },
     AddressDerived {
         address: String,
         path: String,
         curve: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        chain: Option<Chain>,
+    },
+    XpubDerived {
+        xpub: String,
+        path: String,
+        format: XpubFormat,
+        master_fingerprint: String,
+    },
+    PsbtSigned {
+        psbt: String,
+        signed_inputs: Vec<usize>,
+        /// Keys this signature left due for retirement under the signing policy
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        retiring_keys: Vec<KeyUsage>,
+    },
+    Policy {
+        policy: SigningPolicy,
     },
     Info {
         version: String,
         enclave_id: String,
         capabilities: Vec<String>,
+        #[serde(default)]
+        unavailable_capabilities: Vec<String>,
+        /// 0 for enclaves that predate versioning
+        #[serde(default)]
+        protocol_version: u32,
+        /// Wire names of the supported operations; empty for enclaves that predate versioning
+        #[serde(default)]
+        operations: Vec<String>,
+        /// Seconds since the enclave started
+        #[serde(default)]
+        uptime_secs: u64,
+        /// Empty for enclaves that predate build reporting
+        #[serde(default)]
+        build: BuildInfo,
+        /// Hex SHA-256 of the certificate the enclave presents on its TLS socket; absent
+        /// when it does not terminate TLS
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tls_fingerprint: Option<String>,
+        /// Current time by the enclave's clock; absent for enclaves that predate it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        time: Option<EnclaveTime>,
+        /// Memory hardening applied at startup; absent for enclaves that predate it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hardening: Option<MemoryHardening>,
+    },
+    MetricsSnapshot {
+        snapshot: SignedMetricsSnapshot,
+    },
+    NetworkRepaired {
+        state: NetworkState,
+        /// Why the enclave did not attempt the repair; `state` is then unchanged
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unavailable: Option<String>,
+    },
+    ChannelOpened {
+        session_id: String,
+        enclave_public_key: String,
+        expires_at: u64,
+        attestation: ChannelAttestation,
+    },
+    /// An `EnclaveResult` encrypted on the channel of the request
+    SealedResponse {
+        session_id: String,
+        payload: SealedPayload,
+    },
+    SignatureVerified {
+        valid: bool,
+    },
+    CiphertextVerified {
+        valid: bool,
+        /// Why the payload would be rejected; absent when it is valid
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    LogLevel {
+        level: String,
+    },
+    TlsCsr {
+        /// PEM PKCS#10 request
+        csr: String,
+    },
+    TlsCertificate {
+        /// Hex SHA-256 of the DER leaf certificate now served
+        fingerprint: String,
+        /// Unix time the leaf certificate expires
+        not_after: u64,
+    },
+    TimeSynced {
+        /// Whether the offered time was within the enclave clock's error bound. Accepted times
+        /// replace the clock's reading when they are more precise.
+        accepted: bool,
+        /// Time by the enclave's clock after the sync
+        time: EnclaveTime,
+    },
+    ThresholdSession {
+        session: ThresholdSessionStatus,
+    },
+    KeyUsageReport {
+        keys: Vec<KeyUsage>,
     },
     Error {
         message: String,
-        code: u32,
+        code: RenclaveErrorCode,
     },
 }
 
 /// HTTP API request/response types
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GenerateSeedRequest {
     pub strength: Option<u32>,
-    pub passphrase: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub passphrase: Option<SecretString>,
+    /// BIP-39 wordlist; defaults to English
+    #[serde(default)]
+    pub language: SeedLanguage,
+    /// Include an enclave attestation committing to the response
+    #[serde(default)]
+    pub attest: bool,
+    /// Require the attestation to be generated for this request. Otherwise a document issued
+    /// in the last few seconds for an identical response may be returned.
+    #[serde(default)]
+    pub fresh_attestation: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GenerateSeedResponse {
-    pub seed_phrase: String,
-    pub entropy: String,
+    #[schema(value_type = String)]
+    pub seed_phrase: SecretString,
+    #[schema(value_type = String)]
+    pub entropy: SecretString,
     pub strength: u32,
     pub word_count: usize,
+    /// Present when `attest` was requested; `user_data` is the `response_digest` of this
+    /// response without the attestation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<ChannelAttestation>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ValidateSeedRequest {
-    pub seed_phrase: String,
+    #[schema(value_type = String)]
+    pub seed_phrase: SecretString,
+    /// BIP-39 wordlist the phrase is checked against; defaults to English
+    #[serde(default)]
+    pub language: SeedLanguage,
+    /// Hex entropy returned by `/generate-seed`, to confirm the phrase was transcribed intact
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub entropy: Option<SecretString>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ValidateSeedResponse {
     pub valid: bool,
     pub word_count: usize,
+    /// Whether the phrase encodes `entropy`; omitted when no entropy was supplied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entropy_match: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct DeriveKeyRequest {
-    pub seed_phrase: String,
+    #[schema(value_type = String)]
+    pub seed_phrase: SecretString,
     pub path: String,
     pub curve: String,
+    /// Include an enclave attestation committing to the response
+    #[serde(default)]
+    pub attest: bool,
+    /// Require the attestation to be generated for this request. Otherwise a document issued
+    /// in the last few seconds for an identical response may be returned.
+    #[serde(default)]
+    pub fresh_attestation: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct DeriveKeyResponse {
-    pub private_key: String,
+    #[schema(value_type = String)]
+    pub private_key: SecretString,
     pub public_key: String,
     pub address: String,
     pub path: String,
     pub curve: String,
+    /// Present when `attest` was requested; `user_data` is the `response_digest` of this
+    /// response without the attestation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<ChannelAttestation>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct DeriveAddressRequest {
-    pub seed_phrase: String,
+    #[schema(value_type = String)]
+    pub seed_phrase: SecretString,
     pub path: String,
     pub curve: String,
+    /// Address format to derive; omitted for the legacy hex format
+    #[serde(default)]
+    pub chain: Option<Chain>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct DeriveAddressResponse {
     pub address: String,
     pub path: String,
     pub curve: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain: Option<Chain>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeriveXpubRequest {
+    #[schema(value_type = String)]
+    pub seed_phrase: SecretString,
+    /// Account-level path, e.g. `m/84'/0'/0'`
+    pub path: String,
+    /// Serialization format; defaults to `xpub`
+    #[serde(default)]
+    pub format: XpubFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeriveXpubResponse {
+    pub xpub: String,
+    pub path: String,
+    pub format: XpubFormat,
+    /// Fingerprint of the master key, for watch-only wallet descriptors
+    pub master_fingerprint: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SignPsbtRequest {
+    #[schema(value_type = String)]
+    pub seed_phrase: SecretString,
+    /// Base64-encoded BIP-174 PSBT
+    pub psbt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SignPsbtResponse {
+    /// Base64-encoded PSBT with the enclave's signatures added
+    pub psbt: String,
+    /// Indices of the inputs that were signed
+    pub signed_inputs: Vec<usize>,
+    /// Keys that are due for retirement after this signature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retiring_keys: Vec<KeyUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OpenChannelRequest {
+    /// Client ephemeral secp256k1 public key, hex
+    pub client_public_key: String,
+    /// Hex challenge of up to 64 bytes, echoed in the attestation to prove it is fresh
+    #[serde(default)]
+    pub nonce: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OpenChannelResponse {
+    pub session_id: String,
+    /// Enclave ephemeral secp256k1 public key, hex
+    pub enclave_public_key: String,
+    /// Unix time after which the session is discarded
+    pub expires_at: u64,
+    pub attestation: ChannelAttestation,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifySignatureRequest {
+    /// `secp256k1` or `ed25519`
+    pub curve: String,
+    /// Hex public key: 33 or 65 bytes for secp256k1, 32 bytes for ed25519
+    pub public_key: String,
+    /// Hex message; for secp256k1 this is the 32-byte digest that was signed
+    pub message: String,
+    /// Hex signature: compact or DER ECDSA for secp256k1, 64 bytes for ed25519
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifySignatureResponse {
+    pub valid: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyCiphertextResponse {
+    /// Whether the enclave would accept the payload on its session
+    pub valid: bool,
+    /// Why the payload would be rejected; omitted when it is valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Nitro attestation document to verify, with the values it must attest to
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyAttestationRequest {
+    /// Base64 COSE_Sign1 attestation document as produced by the Nitro Secure Module
+    pub document: String,
+    /// Hex manifest hash the document's `user_data` must equal
+    #[serde(default)]
+    pub expected_manifest_hash: Option<String>,
+    /// Hex nonce the document must echo
+    #[serde(default)]
+    pub expected_nonce: Option<String>,
+    /// Hex PCR values by index the document must contain
+    #[serde(default)]
+    pub expected_pcrs: BTreeMap<u8, String>,
+    /// Oldest accepted document age in seconds, 300 by default
+    #[serde(default)]
+    pub max_age_secs: Option<u64>,
+}
+
+/// Outcome of each check on an attestation document, and what the document attests to
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttestationReport {
+    /// Whether every check passed
+    pub valid: bool,
+    pub checks: Vec<AttestationCheck>,
+    pub module_id: String,
+    /// Unix time in milliseconds the document was produced
+    pub timestamp: u64,
+    /// Hex PCR values by index
+    pub pcrs: BTreeMap<u8, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// Signing certificate first, root last
+    pub certificate_chain: Vec<CertificateSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AttestationCheck {
+    /// `signature`, `certificate_chain`, `freshness`, `manifest_hash`, `nonce` or `pcrs`
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CertificateSummary {
+    pub subject: String,
+    pub not_before: String,
+    pub not_after: String,
+    /// Hex SHA-256 of the DER certificate
+    pub fingerprint: String,
+}
+
+/// Message to the enclave's threshold signing coordinator.
+///
+/// Signing follows FROST(Ed25519, SHA-512) from RFC 9591. Participants hold the key shares and
+/// only ever send commitments and signature shares, so the group key is never reconstructed.
+/// Points and scalars are hex in their 32-byte RFC 9591 encodings, and identifiers are the
+/// participants' nonzero share indices.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema,
+)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ThresholdMessage {
+    /// Start a session in which `signers` sign the hex `message` under `group_public_key`
+    Start {
+        group_public_key: String,
+        /// Public key share of each participant, by identifier
+        verifying_shares: BTreeMap<u16, String>,
+        /// Signers needed for a valid signature
+        threshold: u16,
+        signers: Vec<u16>,
+        message: String,
+    },
+    /// Round one: a signer's hiding and binding nonce commitments
+    Commit {
+        session_id: String,
+        identifier: u16,
+        hiding: String,
+        binding: String,
+    },
+    /// Round two: a signer's signature share
+    Sign {
+        session_id: String,
+        identifier: u16,
+        share: String,
+    },
+    /// Read the session without changing it
+    Status { session_id: String },
+}
+
+/// Stage of a threshold signing session
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdRound {
+    /// Collecting nonce commitments
+    Commit,
+    /// Collecting signature shares
+    Sign,
+    /// The aggregate signature is available
+    Complete,
+}
+
+/// A signer's round one nonce commitments, hex encoded points
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema,
+)]
+pub struct ThresholdCommitment {
+    pub hiding: String,
+    pub binding: String,
+}
+
+/// State of a threshold signing session
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema,
+)]
+pub struct ThresholdSessionStatus {
+    pub session_id: String,
+    pub round: ThresholdRound,
+    pub group_public_key: String,
+    /// Hex message being signed
+    pub message: String,
+    pub signers: Vec<u16>,
+    /// Signers the current round still needs a message from
+    pub waiting_for: Vec<u16>,
+    /// Every signer's commitments, once all have committed. Each signer needs the full set
+    /// to compute its signature share.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub commitments: BTreeMap<u16, ThresholdCommitment>,
+    /// Hex 64-byte Ed25519 signature once the session is complete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Unix time after which the session is discarded
+    pub expires_at: u64,
+}
+
+/// Environment variable for the smallest threshold a signing session may use
+pub const THRESHOLD_MIN_ENV: &str = "RENCLAVE_THRESHOLD_MIN";
+/// Environment variable for the smallest threshold as a percentage of all participants
+pub const THRESHOLD_MIN_PERCENT_ENV: &str = "RENCLAVE_THRESHOLD_MIN_PERCENT";
+
+/// Lower bounds on the threshold of a signing session.
+///
+/// The host checks them before forwarding a session start and the enclave enforces them, so a
+/// ceremony that a single participant, or a minority, could complete is refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThresholdPolicy {
+    /// Smallest threshold accepted, at least 2
+    pub min_threshold: u16,
+    /// Smallest threshold as a percentage of the participants, rounded up; 0 disables it
+    pub min_percent: u8,
+}
+
+impl Default for ThresholdPolicy {
+    fn default() -> Self {
+        Self {
+            min_threshold: 2,
+            min_percent: 0,
+        }
+    }
+}
+
+impl ThresholdPolicy {
+    /// Default policy with overrides from the environment; invalid values are an error rather
+    /// than silently weakening the policy
+    pub fn from_env() -> std::result::Result<Self, String> {
+        let mut policy = Self::default();
+        if let Ok(value) = std::env::var(THRESHOLD_MIN_ENV) {
+            policy.min_threshold = value
+                .trim()
+                .parse::<u16>()
+                .ok()
+                .filter(|min| *min >= 2)
+                .ok_or_else(|| format!("{} must be a number of at least 2", THRESHOLD_MIN_ENV))?;
+        }
+        if let Ok(value) = std::env::var(THRESHOLD_MIN_PERCENT_ENV) {
+            policy.min_percent = value
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|percent| *percent <= 100)
+                .ok_or_else(|| {
+                    format!("{} must be between 0 and 100", THRESHOLD_MIN_PERCENT_ENV)
+                })?;
+        }
+        Ok(policy)
+    }
+
+    /// Smallest threshold accepted for `participants` key share holders
+    pub fn required(&self, participants: usize) -> usize {
+        let by_ratio = (participants * usize::from(self.min_percent)).div_ceil(100);
+        by_ratio.max(usize::from(self.min_threshold))
+    }
+
+    /// Check a `threshold` out of `participants` key share holders against the policy
+    pub fn check(&self, threshold: u16, participants: usize) -> std::result::Result<(), String> {
+        let required = self.required(participants);
+        if usize::from(threshold) < required {
+            return Err(format!(
+                "threshold {} of {} participants is below the required {}",
+                threshold, participants, required
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Permission to call a group of host API routes
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+pub enum Scope {
+    /// `/generate-seed`
+    #[serde(rename = "seed:generate")]
+    SeedGenerate,
+    /// `/validate-seed`
+    #[serde(rename = "seed:validate")]
+    SeedValidate,
+    /// `/derive-key`, `/derive-address` and `/derive-xpub`
+    #[serde(rename = "key:derive")]
+    KeyDerive,
+    /// `/enclave/sign-psbt`
+    #[serde(rename = "sign:psbt")]
+    SignPsbt,
+    /// `GET /enclave/policy`
+    #[serde(rename = "policy:read")]
+    PolicyRead,
+    /// `POST /enclave/policy`
+    #[serde(rename = "policy:write")]
+    PolicyWrite,
+    /// `/enclave/channel` and `/enclave/channel/sealed`
+    #[serde(rename = "channel:use")]
+    ChannelUse,
+    /// `/verify-signature` and `/verify-ciphertext`
+    #[serde(rename = "crypto:verify")]
+    CryptoVerify,
+    /// `/network/test` and `/network/repair`
+    #[serde(rename = "network:manage")]
+    NetworkManage,
+    /// `/admin/identities`, and any route without a scope of its own
+    #[serde(rename = "identities:manage")]
+    IdentitiesManage,
+    /// `/admin/log-level` and `/admin/config`
+    #[serde(rename = "config:manage")]
+    ConfigManage,
+    /// `/enclave/threshold`
+    #[serde(rename = "sign:threshold")]
+    SignThreshold,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 12] = [
+        Scope::SeedGenerate,
+        Scope::SeedValidate,
+        Scope::KeyDerive,
+        Scope::SignPsbt,
+        Scope::PolicyRead,
+        Scope::PolicyWrite,
+        Scope::ChannelUse,
+        Scope::CryptoVerify,
+        Scope::NetworkManage,
+        Scope::IdentitiesManage,
+        Scope::ConfigManage,
+        Scope::SignThreshold,
+    ];
+
+    /// Scope name, identical to its JSON encoding
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::SeedGenerate => "seed:generate",
+            Scope::SeedValidate => "seed:validate",
+            Scope::KeyDerive => "key:derive",
+            Scope::SignPsbt => "sign:psbt",
+            Scope::PolicyRead => "policy:read",
+            Scope::PolicyWrite => "policy:write",
+            Scope::ChannelUse => "channel:use",
+            Scope::CryptoVerify => "crypto:verify",
+            Scope::NetworkManage => "network:manage",
+            Scope::IdentitiesManage => "identities:manage",
+            Scope::ConfigManage => "config:manage",
+            Scope::SignThreshold => "sign:threshold",
+        }
+    }
+}
+
+/// An API identity; its key is only returned when the identity is created
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Identity {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Unix time the identity was created
+    pub created_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateIdentityRequest {
+    /// 1-64 ASCII letters, digits, `-`, `_` or `.`, not starting with `.`
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateIdentityResponse {
+    pub identity: Identity,
+    /// Bearer token for the identity; the host keeps only its hash
+    pub api_key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateIdentityRequest {
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SetLogLevelRequest {
+    /// `off`, `error`, `warn`, `info`, `debug` or `trace`
+    pub level: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LogLevelResponse {
+    pub host: String,
+    /// Absent when the enclave could not be reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enclave: Option<String>,
+}
+
+/// Host settings that can be changed without a restart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RuntimeConfig {
+    /// How long to wait for an enclave response once the request is sent
+    pub enclave_timeout_secs: u64,
+    /// Connection attempts per enclave request, including the first
+    pub retry_attempts: u32,
+    /// Backoff after the first failed connection attempt
+    pub retry_backoff_ms: u64,
+}
+
+/// Changes to the runtime config; omitted settings keep their current value
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct UpdateRuntimeConfigRequest {
+    #[serde(default)]
+    pub enclave_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub retry_attempts: Option<u32>,
+    #[serde(default)]
+    pub retry_backoff_ms: Option<u64>,
+}
+
+/// Attestation for a channel handshake or an attested response.
+///
+/// For a handshake `user_data` is the hex `channel::handshake_hash` of the session id and both
+/// public keys; for a response it is the hex `response_digest` of the response.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema,
+)]
+pub struct ChannelAttestation {
+    pub enclave_id: String,
+    pub timestamp: u64,
+    /// Platform measurements by PCR name
+    pub measurements: BTreeMap<String, String>,
+    pub user_data: String,
+    /// Hex challenge supplied by the client, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    pub signature: String,
+    /// Bound in milliseconds on the error of `timestamp`; absent when the enclave's clock has
+    /// never been synchronized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_uncertainty_ms: Option<u64>,
+}
+
+/// Longest attestation nonce the enclave accepts
+pub const MAX_ATTESTATION_NONCE_BYTES: usize = 64;
+
+impl ChannelAttestation {
+    /// Whether the attestation echoes `nonce` and was issued within `max_age_secs` of `now`
+    /// (Unix seconds), in either direction to allow for clock skew
+    pub fn is_fresh(&self, nonce: &[u8], max_age_secs: u64, now: u64) -> bool {
+        let echoed = self
+            .nonce
+            .as_deref()
+            .and_then(|value| hex::decode(value).ok())
+            .is_some_and(|value| value == nonce);
+        echoed && now.abs_diff(self.timestamp) <= max_age_secs
+    }
+}
+
+/// SHA-256 of the compact JSON encoding, with object keys sorted, of an HTTP response whose
+/// `attestation` is unset.
+///
+/// Attested responses carry this digest as attestation `user_data`, so a client checks it by
+/// removing `attestation` from the response it received and hashing it again.
+pub fn response_digest<T: Serialize>(response: &T) -> serde_json::Result<[u8; 32]> {
+    use bitcoin_hashes::{sha256, Hash};
+
+    // `Value` objects are ordered by key, which makes the encoding independent of field order
+    let encoded = serde_json::to_vec(&serde_json::to_value(response)?)?;
+    Ok(sha256::Hash::hash(&encoded).to_byte_array())
+}
+
+/// Encrypted request or response on an open channel
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SealedChannelMessage {
+    pub session_id: String,
+    pub payload: SealedPayload,
+}
+
+/// Constraints the enclave enforces before releasing a signed transaction
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    ToSchema,
+)]
+pub struct SigningPolicy {
+    /// Addresses allowed to receive funds; empty allows any destination
+    #[serde(default)]
+    pub destination_allowlist: Vec<String>,
+    /// Maximum value in satoshis sent to external destinations per UTC day
+    #[serde(default)]
+    pub daily_limit_sats: Option<u64>,
+    /// UTC hours during which signing is allowed
+    #[serde(default)]
+    pub signing_window: Option<SigningWindow>,
+    /// A locked policy cannot be replaced until the enclave restarts
+    #[serde(default)]
+    pub locked: bool,
+    /// When signing keys are due for retirement
+    #[serde(default)]
+    pub key_retirement: Option<KeyRetirementPolicy>,
+}
+
+/// Limits on how long one signing key stays in use.
+///
+/// A key is due for retirement once it has made `max_signatures` signatures or its first
+/// signature is `max_age_days` old. Rotating means signing with a key on a new derivation
+/// path.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    ToSchema,
+)]
+pub struct KeyRetirementPolicy {
+    #[serde(default)]
+    pub max_signatures: Option<u64>,
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    /// Refuse to sign with keys that are due instead of only reporting them
+    #[serde(default)]
+    pub enforce: bool,
+}
+
+/// How much the enclave has signed with one key
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema,
+)]
+pub struct KeyUsage {
+    /// Hex public key: compressed for ECDSA, x-only for Taproot
+    pub public_key: String,
+    pub signatures: u64,
+    /// Unix time of the key's first and latest signatures
+    pub first_used_at: u64,
+    pub last_used_at: u64,
+    /// Whether the key is due for retirement under the current policy
+    pub retirement_due: bool,
+}
+
+/// Range of UTC hours `[start_hour, end_hour)`; wraps past midnight when start > end
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    ToSchema,
+)]
+pub struct SigningWindow {
+    pub start_hour: u8,
+    pub end_hour: u8,
+}
+
+impl SigningWindow {
+    /// Whether `hour` (0-23, UTC) falls inside the window
+    pub fn contains(&self, hour: u8) -> bool {
+        if self.start_hour <= self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// Serialization of an extended public key
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum XpubFormat {
+    /// Base58Check with the BIP-32 `xpub` version
+    #[default]
+    Xpub,
+    /// Base58Check with the BIP-49 `ypub` version (P2WPKH-in-P2SH)
+    Ypub,
+    /// Base58Check with the BIP-84 `zpub` version (native SegWit)
+    Zpub,
+    /// Hex of the raw 78-byte BIP-32 serialization with the `xpub` version
+    Raw,
+}
+
+/// BIP-39 wordlist used for a seed phrase
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedLanguage {
+    #[default]
+    English,
+    ChineseSimplified,
+    ChineseTraditional,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Portuguese,
+    Spanish,
+}
+
+/// Chain whose address format DeriveAddress produces
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Chain {
+    /// Native SegWit (P2WPKH) bech32 address
+    Bitcoin,
+    /// Taproot (P2TR) bech32m address
+    BitcoinTaproot,
+    /// EIP-55 checksummed hex address
+    Ethereum,
+    /// Base58 ed25519 public key, derived with SLIP-10
+    Solana,
+    /// Bech32 address with the `cosmos` prefix
+    Cosmos,
+}
+
+impl Chain {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Chain::Bitcoin => "bitcoin",
+            Chain::BitcoinTaproot => "bitcoin_taproot",
+            Chain::Ethereum => "ethereum",
+            Chain::Solana => "solana",
+            Chain::Cosmos => "cosmos",
+        }
+    }
+
+    /// Whether the chain uses ed25519 keys rather than secp256k1
+    pub fn uses_ed25519(&self) -> bool {
+        matches!(self, Chain::Solana)
+    }
+}
+
+/// Stable, machine-readable error codes returned by the HTTP API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorCode {
+    /// The request was malformed or failed validation
+    InvalidRequest,
+    /// The enclave processed the request but the operation failed
+    EnclaveOperationFailed,
+    /// The enclave answered with a result that does not match the request
+    UnexpectedEnclaveResponse,
+    /// The enclave could not be reached
+    EnclaveUnavailable,
+    /// Network initialization failed and the service runs in degraded mode
+    NetworkUnavailable,
+    /// The request body exceeded the route's size limit
+    PayloadTooLarge,
+    /// The route needs an API key and none or an unknown one was sent
+    Unauthorized,
+    /// The API identity lacks the route's scope
+    Forbidden,
+    /// The addressed resource does not exist
+    NotFound,
+    /// Host storage could not be read or written
+    StorageFailed,
+}
+
+impl ApiErrorCode {
+    /// String label of the code, identical to its JSON encoding
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiErrorCode::InvalidRequest => "invalid_request",
+            ApiErrorCode::EnclaveOperationFailed => "enclave_operation_failed",
+            ApiErrorCode::UnexpectedEnclaveResponse => "unexpected_enclave_response",
+            ApiErrorCode::EnclaveUnavailable => "enclave_unavailable",
+            ApiErrorCode::NetworkUnavailable => "network_unavailable",
+            ApiErrorCode::PayloadTooLarge => "payload_too_large",
+            ApiErrorCode::Unauthorized => "unauthorized",
+            ApiErrorCode::Forbidden => "forbidden",
+            ApiErrorCode::NotFound => "not_found",
+            ApiErrorCode::StorageFailed => "storage_failed",
+        }
+    }
+}
+
+/// Stable error codes returned by the enclave.
+///
+/// Each code has a fixed number and a snake_case label, sent together as
+/// `{"code": 1001, "label": "invalid_request"}`. Bare integers from enclaves that predate
+/// the enum are read with `from_legacy`, and numbers added by newer enclaves read as
+/// `Internal`. Codes are only ever appended.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(into = "LabelledErrorCode", from = "ErrorCodeWire")]
+pub enum RenclaveErrorCode {
+    /// The request was malformed or failed validation
+    InvalidRequest,
+    /// The referenced session or resource does not exist
+    NotFound,
+    /// The enclave does not implement the operation
+    UnsupportedOperation,
+    /// The enclave lacks the keys or configuration the operation needs
+    NotProvisioned,
+    /// The signing policy refused the operation
+    PolicyDenied,
+    /// A cryptographic operation failed
+    CryptoFailure,
+    /// Persistent state could not be read or written
+    StorageFailure,
+    /// A dependency such as the entropy source is unhealthy
+    Unavailable,
+    /// The operation did not finish in time
+    Timeout,
+    /// Any other enclave failure
+    Internal,
+}
+
+impl RenclaveErrorCode {
+    const ALL: [RenclaveErrorCode; 10] = [
+        RenclaveErrorCode::InvalidRequest,
+        RenclaveErrorCode::NotFound,
+        RenclaveErrorCode::UnsupportedOperation,
+        RenclaveErrorCode::NotProvisioned,
+        RenclaveErrorCode::PolicyDenied,
+        RenclaveErrorCode::CryptoFailure,
+        RenclaveErrorCode::StorageFailure,
+        RenclaveErrorCode::Unavailable,
+        RenclaveErrorCode::Timeout,
+        RenclaveErrorCode::Internal,
+    ];
+
+    /// Stable numeric code
+    pub fn code(&self) -> u32 {
+        match self {
+            RenclaveErrorCode::InvalidRequest => 1001,
+            RenclaveErrorCode::NotFound => 1002,
+            RenclaveErrorCode::UnsupportedOperation => 1003,
+            RenclaveErrorCode::NotProvisioned => 1004,
+            RenclaveErrorCode::PolicyDenied => 1005,
+            RenclaveErrorCode::CryptoFailure => 1006,
+            RenclaveErrorCode::StorageFailure => 1007,
+            RenclaveErrorCode::Unavailable => 1008,
+            RenclaveErrorCode::Timeout => 1009,
+            RenclaveErrorCode::Internal => 1010,
+        }
+    }
+
+    /// String label of the code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RenclaveErrorCode::InvalidRequest => "invalid_request",
+            RenclaveErrorCode::NotFound => "not_found",
+            RenclaveErrorCode::UnsupportedOperation => "unsupported_operation",
+            RenclaveErrorCode::NotProvisioned => "not_provisioned",
+            RenclaveErrorCode::PolicyDenied => "policy_denied",
+            RenclaveErrorCode::CryptoFailure => "crypto_failure",
+            RenclaveErrorCode::StorageFailure => "storage_failure",
+            RenclaveErrorCode::Unavailable => "unavailable",
+            RenclaveErrorCode::Timeout => "timeout",
+            RenclaveErrorCode::Internal => "internal",
+        }
+    }
+
+    /// The code with numeric value `code`, if there is one
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.code() == code)
+    }
+
+    /// The code for an HTTP-style integer sent by enclaves that predate the enum
+    pub fn from_legacy(code: u32) -> Self {
+        match code {
+            400 | 413 => RenclaveErrorCode::InvalidRequest,
+            403 => RenclaveErrorCode::PolicyDenied,
+            404 => RenclaveErrorCode::NotFound,
+            501 => RenclaveErrorCode::UnsupportedOperation,
+            503 => RenclaveErrorCode::Unavailable,
+            504 => RenclaveErrorCode::Timeout,
+            _ => RenclaveErrorCode::Internal,
+        }
+    }
+}
+
+/// JSON encoding of a `RenclaveErrorCode`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"code": 1001, "label": "invalid_request"}))]
+pub struct LabelledErrorCode {
+    pub code: u32,
+    pub label: String,
+}
+
+impl From<RenclaveErrorCode> for LabelledErrorCode {
+    fn from(code: RenclaveErrorCode) -> Self {
+        Self {
+            code: code.code(),
+            label: code.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ErrorCodeWire {
+    Labelled(LabelledErrorCode),
+    Legacy(u32),
+}
+
+impl From<ErrorCodeWire> for RenclaveErrorCode {
+    fn from(wire: ErrorCodeWire) -> Self {
+        match wire {
+            ErrorCodeWire::Labelled(labelled) => {
+                Self::from_code(labelled.code).unwrap_or(RenclaveErrorCode::Internal)
+            }
+            ErrorCodeWire::Legacy(code) => Self::from_legacy(code),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: String,
+    /// HTTP status of the response
     pub code: u32,
+    pub error_code: ApiErrorCode,
     pub request_id: Option<String>,
+    /// Enclave error behind an `enclave_operation_failed` response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<LabelledErrorCode>)]
+    pub enclave_error: Option<RenclaveErrorCode>,
+    /// Each request field that failed validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct InfoResponse {
     pub version: String,
     pub service: String,
     pub enclave_id: String,
     pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub unavailable_capabilities: Vec<String>,
     pub network_status: String,
 }
 
@@ -159,6 +1406,99 @@ pub struct SeedValidationResult {
     pub word_count: usize,
 }
 
+/// Provenance of an enclave binary
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+pub struct BuildInfo {
+    /// Git commit the binary was built from, or `unknown`
+    pub git_commit: String,
+    /// `rustc --version` of the compiler
+    pub rustc_version: String,
+    /// Enabled Cargo features of the enclave crate
+    pub features: Vec<String>,
+    /// `debug` or `release`
+    pub profile: String,
+}
+
+/// Protections keeping enclave secrets out of swap and core dumps, as found at startup
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    ToSchema,
+)]
+pub struct MemoryHardening {
+    /// No swap device or file is active
+    pub swap_disabled: bool,
+    /// All current and future pages are locked in RAM (`mlockall`)
+    pub memory_locked: bool,
+    /// The process is not dumpable and its core dump size limit is zero
+    pub core_dumps_disabled: bool,
+}
+
+impl MemoryHardening {
+    /// Whether every protection is in place
+    pub fn is_complete(&self) -> bool {
+        self.swap_disabled && self.memory_locked && self.core_dumps_disabled
+    }
+}
+
+/// Where the enclave's clock last took its time from
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeSource {
+    /// The guest clock, never synchronized; it drifts freely under QEMU
+    Guest,
+    /// An NTP server reached over the enclave's network path
+    Ntp,
+    /// Time offered by the host with `SyncTime`
+    Host,
+}
+
+/// A reading of the enclave's clock with a bound on its error.
+///
+/// The true time lies within `unix_ms ± uncertainty_ms`. The bound is the error of the last
+/// synchronization plus the largest drift since, so it grows until the next sync.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema,
+)]
+pub struct EnclaveTime {
+    pub unix_ms: u64,
+    /// Absent while the clock has never been synchronized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uncertainty_ms: Option<u64>,
+    pub source: TimeSource,
+    /// Unix milliseconds of the last synchronization, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced_at_ms: Option<u64>,
+}
+
+impl EnclaveTime {
+    /// Whole Unix seconds
+    pub fn unix_secs(&self) -> u64 {
+        self.unix_ms / 1000
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EnclaveInfo {
     pub version: String,
@@ -187,13 +1527,370 @@ pub struct ConnectivityResult {
     pub dns: bool,
 }
 
+/// Outcome of a health check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Result of a single readiness check
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthCheck {
+    pub name: String,
+    pub status: HealthStatus,
+    /// A failing critical check makes the service not ready
+    pub critical: bool,
+    pub message: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Aggregated health report returned by `/health/live` and `/health/ready`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    /// Aggregate checks: `fail` if any critical check fails, `warn` if any check is not passing
+    pub fn from_checks(checks: Vec<HealthCheck>) -> Self {
+        let status = if checks
+            .iter()
+            .any(|c| c.critical && c.status == HealthStatus::Fail)
+        {
+            HealthStatus::Fail
+        } else if checks.iter().any(|c| c.status != HealthStatus::Pass) {
+            HealthStatus::Warn
+        } else {
+            HealthStatus::Pass
+        };
+
+        Self { status, checks }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status != HealthStatus::Fail
+    }
+}
+
+/// Capabilities that require a working TAP network
+pub const NETWORK_DEPENDENT_CAPABILITIES: &[&str] = &["network_connectivity"];
+
+/// Initialization state of the TAP network.
+///
+/// When initialization fails the service keeps running in degraded mode and reports
+/// `network_unavailable` until a repair succeeds.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema,
+)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum NetworkState {
+    Initializing,
+    Ready,
+    NetworkUnavailable { reason: String, since: u64 },
+}
+
+impl NetworkState {
+    pub fn is_available(&self) -> bool {
+        matches!(self, NetworkState::Ready)
+    }
+
+    /// Split capabilities into (available, unavailable) for this network state
+    pub fn partition_capabilities(&self, capabilities: Vec<String>) -> (Vec<String>, Vec<String>) {
+        if self.is_available() {
+            return (capabilities, Vec::new());
+        }
+        capabilities
+            .into_iter()
+            .partition(|c| !NETWORK_DEPENDENT_CAPABILITIES.contains(&c.as_str()))
+    }
+}
+
+/// Result of a network repair on the host and the enclave
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NetworkRepairResponse {
+    pub host: NetworkState,
+    /// `None` if the enclave could not be reached
+    pub enclave: Option<NetworkState>,
+    /// Why the enclave did not attempt the repair, e.g. because it dropped its privileges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enclave_unavailable: Option<String>,
+}
+
+/// Status of an enclave served by the host
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EnclaveStatus {
+    /// The enclave's routes are served under `/enclaves/{name}`
+    pub name: String,
+    pub socket_path: String,
+    pub available: bool,
+    /// Id of the current enclave session; absent until the enclave has answered GetInfo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enclave_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EnclavesResponse {
+    pub enclaves: Vec<EnclaveStatus>,
+}
+
+/// State of an enclave process launched and supervised by the host
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct EnclaveProcessStatus {
+    pub command: String,
+    pub running: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// Times the process was started again after exiting
+    pub restarts: u32,
+    /// Unix time the current or last process was started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    /// Exit code of the last process; absent if it was killed by a signal or never exited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_exit_at: Option<u64>,
+    /// Stopped through the API or given up on by the restart policy; stays down until started
+    pub stopped: bool,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ConsoleQuery {
+    /// Most recent lines to return, 100 by default
+    pub lines: Option<usize>,
+}
+
+/// Recent output of the supervised enclave process or QEMU guest console
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConsoleResponse {
+    /// Oldest first
+    pub lines: Vec<String>,
+}
+
+/// ACME issuance of the certificate the enclave serves on its TLS endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AcmeStatus {
+    pub directory_url: String,
+    pub domains: Vec<String>,
+    /// Certificate last installed in the enclave
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<AcmeCertificate>,
+    /// Unix time of the last renewal check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_checked_at: Option<u64>,
+    /// Why the last issuance failed; cleared once one succeeds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AcmeCertificate {
+    /// Hex SHA-256 of the DER leaf certificate
+    pub fingerprint: String,
+    /// Unix time the certificate expires
+    pub not_after: u64,
+    /// Unix time the certificate was issued and installed
+    pub issued_at: u64,
+}
+
+/// Point-in-time view of the enclave operation counters.
+///
+/// Each snapshot carries a monotonically increasing sequence number and the hash of the
+/// previous snapshot, so a verifier holding an older snapshot can detect rollback or gaps.
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema)]
+pub struct MetricsSnapshot {
+    pub enclave_id: String,
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub counters: BTreeMap<String, u64>,
+    pub previous_hash: String,
+    /// Bound in milliseconds on the error of `timestamp`; absent when the enclave's clock has
+    /// never been synchronized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_uncertainty_ms: Option<u64>,
+}
+
+/// Metrics snapshot signed inside the enclave.
+///
+/// `snapshot_hash` is the hex SHA-256 of the JSON encoding of `snapshot` and `signature` is a
+/// compact secp256k1 ECDSA signature over that hash by `public_key`.
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, ToSchema)]
+pub struct SignedMetricsSnapshot {
+    pub snapshot: MetricsSnapshot,
+    pub snapshot_hash: String,
+    pub signature: String,
+    pub public_key: String,
+}
+
+impl MetricsSnapshot {
+    /// SHA-256 of the JSON encoding, which the enclave signs
+    pub fn digest(&self) -> serde_json::Result<[u8; 32]> {
+        use bitcoin_hashes::{sha256, Hash};
+
+        Ok(sha256::Hash::hash(&serde_json::to_vec(self)?).to_byte_array())
+    }
+}
+
+impl SignedMetricsSnapshot {
+    /// Whether `snapshot_hash` matches the snapshot and `signature` is valid for it under
+    /// `public_key`. Malformed hashes, keys and signatures count as invalid.
+    ///
+    /// This does not establish that `public_key` belongs to the enclave; compare it with the key
+    /// the enclave logged at startup or with earlier snapshots.
+    pub fn verify(&self) -> bool {
+        use secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1};
+
+        let Ok(digest) = self.snapshot.digest() else {
+            return false;
+        };
+        if hex::encode(digest) != self.snapshot_hash {
+            return false;
+        }
+        let public_key = hex::decode(&self.public_key)
+            .ok()
+            .and_then(|bytes| PublicKey::from_slice(&bytes).ok());
+        let signature = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_compact(&bytes).ok());
+        let (Some(public_key), Some(signature)) = (public_key, signature) else {
+            return false;
+        };
+        Secp256k1::verification_only()
+            .verify_ecdsa(&Message::from_digest(digest), &signature, &public_key)
+            .is_ok()
+    }
+
+    /// Whether this snapshot directly follows `previous` from the same enclave key, so that
+    /// no snapshot was dropped, replayed or rolled back in between. Both must `verify`.
+    pub fn follows(&self, previous: &SignedMetricsSnapshot) -> bool {
+        self.public_key == previous.public_key
+            && self.snapshot.enclave_id == previous.snapshot.enclave_id
+            && previous.snapshot.sequence.checked_add(1) == Some(self.snapshot.sequence)
+            && self.snapshot.previous_hash == previous.snapshot_hash
+    }
+}
+
+/// Scheduling class of an operation, from highest to lowest priority.
+///
+/// The enclave runs each class in its own lane with its own concurrency limit, so status
+/// calls are never stuck behind signing or seed generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperationClass {
+    /// Status, metrics and configuration
+    Control,
+    /// Validation, public derivation and verification
+    Read,
+    /// Operations producing signatures or private keys
+    Sign,
+    /// Entropy-heavy operations: seed generation and channel handshakes
+    Ceremony,
+}
+
+impl OperationClass {
+    pub const ALL: [OperationClass; 4] = [
+        OperationClass::Control,
+        OperationClass::Read,
+        OperationClass::Sign,
+        OperationClass::Ceremony,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationClass::Control => "control",
+            OperationClass::Read => "read",
+            OperationClass::Sign => "sign",
+            OperationClass::Ceremony => "ceremony",
+        }
+    }
+}
+
+impl EnclaveOperation {
+    /// Stable name of the operation, used for metrics and logging
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnclaveOperation::GenerateSeed { .. } => "generate_seed",
+            EnclaveOperation::ValidateSeed { .. } => "validate_seed",
+            EnclaveOperation::DeriveKey { .. } => "derive_key",
+            EnclaveOperation::DeriveAddress { .. } => "derive_address",
+            EnclaveOperation::DeriveXpub { .. } => "derive_xpub",
+            EnclaveOperation::SignPsbt { .. } => "sign_psbt",
+            EnclaveOperation::GetPolicy => "get_policy",
+            EnclaveOperation::SetPolicy { .. } => "set_policy",
+            EnclaveOperation::GetInfo => "get_info",
+            EnclaveOperation::GetMetricsSnapshot => "get_metrics_snapshot",
+            EnclaveOperation::RepairNetwork => "repair_network",
+            EnclaveOperation::OpenChannel { .. } => "open_channel",
+            EnclaveOperation::SealedRequest { .. } => "sealed_request",
+            EnclaveOperation::VerifySignature { .. } => "verify_signature",
+            EnclaveOperation::VerifyCiphertext { .. } => "verify_ciphertext",
+            EnclaveOperation::SetLogLevel { .. } => "set_log_level",
+            EnclaveOperation::CreateTlsCsr { .. } => "create_tls_csr",
+            EnclaveOperation::InstallTlsCertificate { .. } => "install_tls_certificate",
+            EnclaveOperation::SyncTime { .. } => "sync_time",
+            EnclaveOperation::ThresholdSigning { .. } => "threshold_signing",
+            EnclaveOperation::GetKeyUsage => "get_key_usage",
+        }
+    }
+
+    /// Dispatch class of the operation. A sealed request is dispatched by the operation it
+    /// carries once decrypted.
+    pub fn class(&self) -> OperationClass {
+        match self {
+            EnclaveOperation::GetInfo
+            | EnclaveOperation::GetMetricsSnapshot
+            | EnclaveOperation::GetPolicy
+            | EnclaveOperation::GetKeyUsage
+            | EnclaveOperation::SetPolicy { .. }
+            | EnclaveOperation::RepairNetwork
+            | EnclaveOperation::SetLogLevel { .. }
+            | EnclaveOperation::CreateTlsCsr { .. }
+            | EnclaveOperation::InstallTlsCertificate { .. }
+            | EnclaveOperation::SyncTime { .. } => OperationClass::Control,
+            EnclaveOperation::ValidateSeed { .. }
+            | EnclaveOperation::DeriveAddress { .. }
+            | EnclaveOperation::DeriveXpub { .. }
+            | EnclaveOperation::VerifySignature { .. }
+            | EnclaveOperation::VerifyCiphertext { .. } => OperationClass::Read,
+            EnclaveOperation::DeriveKey { .. }
+            | EnclaveOperation::SignPsbt { .. }
+            | EnclaveOperation::SealedRequest { .. }
+            | EnclaveOperation::ThresholdSigning { .. } => OperationClass::Sign,
+            EnclaveOperation::GenerateSeed { .. } | EnclaveOperation::OpenChannel { .. } => {
+                OperationClass::Ceremony
+            }
+        }
+    }
+}
+
 impl EnclaveRequest {
     pub fn new(operation: EnclaveOperation) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
             operation,
+            protocol_version: PROTOCOL_VERSION,
+            trace_context: None,
         }
     }
+
+    /// For a request that failed to parse, the request id and operation name if the
+    /// operation is one this build does not know, e.g. one added by a newer host
+    pub fn unsupported_operation(json: &str) -> Option<(String, String)> {
+        let value: serde_json::Value = serde_json::from_str(json).ok()?;
+        let id = value.get("id")?.as_str()?.to_string();
+        let operation = match value.get("operation")? {
+            serde_json::Value::String(name) => name.clone(),
+            serde_json::Value::Object(map) if map.len() == 1 => map.keys().next()?.clone(),
+            _ => return None,
+        };
+
+        (!SUPPORTED_OPERATIONS.contains(&operation.as_str())).then_some((id, operation))
+    }
 }
 
 impl EnclaveResponse {
@@ -201,7 +1898,7 @@ impl EnclaveResponse {
         Self { id, result }
     }
 
-    pub fn error(id: String, message: String, code: u32) -> Self {
+    pub fn error(id: String, message: String, code: RenclaveErrorCode) -> Self {
         Self {
             id,
             result: EnclaveResult::Error { message, code },
@@ -253,6 +1950,28 @@ mod tests {
             version: "1.0.0".to_string(),
             enclave_id: "test-enclave".to_string(),
             capabilities: vec!["test".to_string()],
+            unavailable_capabilities: vec![],
+            protocol_version: PROTOCOL_VERSION,
+            operations: vec!["GetInfo".to_string()],
+            uptime_secs: 42,
+            build: BuildInfo {
+                git_commit: "0".repeat(40),
+                rustc_version: "rustc 1.80.0".to_string(),
+                features: vec![],
+                profile: "release".to_string(),
+            },
+            tls_fingerprint: None,
+            time: Some(EnclaveTime {
+                unix_ms: 1_700_000_000_000,
+                uncertainty_ms: Some(40),
+                source: TimeSource::Ntp,
+                synced_at_ms: Some(1_699_999_990_000),
+            }),
+            hardening: Some(MemoryHardening {
+                swap_disabled: true,
+                memory_locked: true,
+                core_dumps_disabled: true,
+            }),
         };
 
         let response = EnclaveResponse::new(id.clone(), result);
@@ -265,7 +1984,7 @@ mod tests {
     fn test_enclave_response_error() {
         let id = "test-id".to_string();
         let message = "Test error".to_string();
-        let code = 500;
+        let code = RenclaveErrorCode::Internal;
 
         let response = EnclaveResponse::error(id.clone(), message.clone(), code);
 
@@ -287,22 +2006,31 @@ mod tests {
         let operations = vec![
             EnclaveOperation::GenerateSeed {
                 strength: 256,
-                passphrase: Some("test123".to_string()),
+                passphrase: Some("test123".into()),
+                language: SeedLanguage::Japanese,
+                attest: true,
+                fresh_attestation: false,
             },
             EnclaveOperation::ValidateSeed {
-                seed_phrase: "test seed".to_string(),
+                seed_phrase: "test seed".into(),
+                language: SeedLanguage::English,
+                entropy: Some("00ff".into()),
             },
             EnclaveOperation::DeriveKey {
-                seed_phrase: "test seed".to_string(),
+                seed_phrase: "test seed".into(),
                 path: "m/44'/0'/0'/0/0".to_string(),
                 curve: "secp256k1".to_string(),
+                attest: false,
+                fresh_attestation: false,
             },
             EnclaveOperation::DeriveAddress {
-                seed_phrase: "test seed".to_string(),
+                seed_phrase: "test seed".into(),
                 path: "m/44'/0'/0'/0/0".to_string(),
                 curve: "secp256k1".to_string(),
+                chain: None,
             },
             EnclaveOperation::GetInfo,
+            EnclaveOperation::GetMetricsSnapshot,
         ];
 
         for operation in operations {
@@ -317,39 +2045,168 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_borsh_round_trip() {
+        let requests = [
+            EnclaveRequest::new(EnclaveOperation::GenerateSeed {
+                strength: 256,
+                passphrase: Some("test123".into()),
+                language: SeedLanguage::Japanese,
+                attest: true,
+                fresh_attestation: false,
+            }),
+            EnclaveRequest::new(EnclaveOperation::SetPolicy {
+                policy: SigningPolicy {
+                    destination_allowlist: vec!["bc1q".to_string()],
+                    daily_limit_sats: Some(100_000),
+                    signing_window: Some(SigningWindow {
+                        start_hour: 22,
+                        end_hour: 6,
+                    }),
+                    locked: true,
+                    key_retirement: Some(KeyRetirementPolicy {
+                        max_signatures: Some(1000),
+                        max_age_days: None,
+                        enforce: true,
+                    }),
+                },
+            }),
+            EnclaveRequest::new(EnclaveOperation::SealedRequest {
+                session_id: "session".to_string(),
+                payload: SealedPayload {
+                    counter: 7,
+                    ciphertext: "00ff".to_string(),
+                    tag: "ab".repeat(32),
+                },
+            }),
+        ];
+        let responses = [
+            EnclaveResponse::new(
+                "1".to_string(),
+                EnclaveResult::KeyDerived {
+                    private_key: "private".into(),
+                    public_key: "public".to_string(),
+                    address: "address".to_string(),
+                    path: "m/44'/0'/0'/0/0".to_string(),
+                    curve: "secp256k1".to_string(),
+                    attestation: Some(ChannelAttestation {
+                        enclave_id: "enclave".to_string(),
+                        timestamp: 1,
+                        measurements: BTreeMap::from([("pcr0".to_string(), "00".to_string())]),
+                        user_data: "ff".to_string(),
+                        nonce: None,
+                        signature: "sig".to_string(),
+                        timestamp_uncertainty_ms: Some(250),
+                    }),
+                },
+            ),
+            EnclaveResponse::new(
+                "2".to_string(),
+                EnclaveResult::NetworkRepaired {
+                    state: NetworkState::NetworkUnavailable {
+                        reason: "no route".to_string(),
+                        since: 5,
+                    },
+                    unavailable: Some("running unprivileged".to_string()),
+                },
+            ),
+            EnclaveResponse::error(
+                "3".to_string(),
+                "failed".to_string(),
+                RenclaveErrorCode::Internal,
+            ),
+        ];
+
+        // The JSON encoding stands in for equality, which the wire types do not implement
+        for request in requests {
+            let decoded: EnclaveRequest =
+                borsh::from_slice(&borsh::to_vec(&request).unwrap()).unwrap();
+            assert_eq!(
+                serde_json::to_string(&decoded).unwrap(),
+                serde_json::to_string(&request).unwrap()
+            );
+        }
+        for response in responses {
+            let decoded: EnclaveResponse =
+                borsh::from_slice(&borsh::to_vec(&response).unwrap()).unwrap();
+            assert_eq!(
+                serde_json::to_string(&decoded).unwrap(),
+                serde_json::to_string(&response).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_borsh_encoding_is_stable() {
+        let request = EnclaveRequest {
+            id: "1".to_string(),
+            operation: EnclaveOperation::GetInfo,
+            protocol_version: 1,
+            trace_context: None,
+        };
+        // Length-prefixed id, the index of GetInfo among the operations, the version, then
+        // the absent trace context
+        assert_eq!(
+            borsh::to_vec(&request).unwrap(),
+            [1, 0, 0, 0, b'1', 8, 1, 0, 0, 0, 0]
+        );
+
+        let payload = SealedPayload {
+            counter: 1,
+            ciphertext: "ab".to_string(),
+            tag: String::new(),
+        };
+        assert_eq!(
+            borsh::to_vec(&payload).unwrap(),
+            [1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, b'a', b'b', 0, 0, 0, 0]
+        );
+    }
+
     #[test]
     fn test_enclave_result_serialization() {
         let results = vec![
             EnclaveResult::SeedGenerated {
-                seed_phrase: "test phrase".to_string(),
-                entropy: "test entropy".to_string(),
+                seed_phrase: "test phrase".into(),
+                entropy: "test entropy".into(),
                 strength: 256,
                 word_count: 24,
+                attestation: None,
             },
             EnclaveResult::SeedValidated {
                 valid: true,
                 word_count: 12,
+                entropy_match: Some(true),
             },
             EnclaveResult::KeyDerived {
-                private_key: "private".to_string(),
+                private_key: "private".into(),
                 public_key: "public".to_string(),
                 address: "address".to_string(),
                 path: "m/44'/0'/0'/0/0".to_string(),
                 curve: "secp256k1".to_string(),
+                attestation: None,
             },
             EnclaveResult::AddressDerived {
                 address: "address".to_string(),
                 path: "m/44'/0'/0'/0/0".to_string(),
                 curve: "secp256k1".to_string(),
+                chain: Some(Chain::Bitcoin),
             },
             EnclaveResult::Info {
                 version: "1.0.0".to_string(),
                 enclave_id: "test".to_string(),
                 capabilities: vec!["test".to_string()],
+                unavailable_capabilities: vec![],
+                protocol_version: 0,
+                operations: vec![],
+                uptime_secs: 0,
+                build: BuildInfo::default(),
+                tls_fingerprint: None,
+                time: None,
+                hardening: None,
             },
             EnclaveResult::Error {
                 message: "test error".to_string(),
-                code: 500,
+                code: RenclaveErrorCode::Internal,
             },
         ];
 
@@ -365,42 +2222,474 @@ mod tests {
         }
     }
 
+    /// One of each operation
+    fn all_operations() -> Vec<EnclaveOperation> {
+        vec![
+            EnclaveOperation::GenerateSeed {
+                strength: 256,
+                passphrase: None,
+                language: SeedLanguage::default(),
+                attest: false,
+                fresh_attestation: false,
+            },
+            EnclaveOperation::ValidateSeed {
+                seed_phrase: SecretString::default(),
+                language: SeedLanguage::default(),
+                entropy: None,
+            },
+            EnclaveOperation::DeriveKey {
+                seed_phrase: SecretString::default(),
+                path: String::new(),
+                curve: String::new(),
+                attest: false,
+                fresh_attestation: false,
+            },
+            EnclaveOperation::DeriveAddress {
+                seed_phrase: SecretString::default(),
+                path: String::new(),
+                curve: String::new(),
+                chain: None,
+            },
+            EnclaveOperation::GetInfo,
+            EnclaveOperation::GetMetricsSnapshot,
+            EnclaveOperation::RepairNetwork,
+            EnclaveOperation::DeriveXpub {
+                seed_phrase: SecretString::default(),
+                path: String::new(),
+                format: XpubFormat::Xpub,
+            },
+            EnclaveOperation::SignPsbt {
+                seed_phrase: SecretString::default(),
+                psbt: String::new(),
+            },
+            EnclaveOperation::GetPolicy,
+            EnclaveOperation::SetPolicy {
+                policy: SigningPolicy::default(),
+            },
+            EnclaveOperation::OpenChannel {
+                client_public_key: String::new(),
+                nonce: None,
+            },
+            EnclaveOperation::SealedRequest {
+                session_id: String::new(),
+                payload: SealedPayload {
+                    counter: 0,
+                    ciphertext: String::new(),
+                    tag: String::new(),
+                },
+            },
+            EnclaveOperation::VerifySignature {
+                curve: String::new(),
+                public_key: String::new(),
+                message: String::new(),
+                signature: String::new(),
+            },
+            EnclaveOperation::VerifyCiphertext {
+                session_id: String::new(),
+                payload: SealedPayload {
+                    counter: 0,
+                    ciphertext: String::new(),
+                    tag: String::new(),
+                },
+            },
+            EnclaveOperation::SetLogLevel { level: None },
+            EnclaveOperation::CreateTlsCsr {
+                domains: vec!["enclave.example".to_string()],
+            },
+            EnclaveOperation::InstallTlsCertificate {
+                certificate_chain: String::new(),
+            },
+            EnclaveOperation::SyncTime { unix_ms: 0 },
+            EnclaveOperation::ThresholdSigning {
+                message: ThresholdMessage::Status {
+                    session_id: String::new(),
+                },
+            },
+            EnclaveOperation::GetKeyUsage,
+        ]
+    }
+
+    #[test]
+    fn test_operation_names_are_unique() {
+        let names: Vec<_> = all_operations().iter().map(|op| op.name()).collect();
+
+        let unique: std::collections::HashSet<_> = names.iter().collect();
+        assert_eq!(unique.len(), names.len());
+    }
+
+    #[test]
+    fn test_supported_operations_match_wire_names() {
+        let operations = all_operations();
+        assert_eq!(operations.len(), SUPPORTED_OPERATIONS.len());
+
+        for operation in operations {
+            let request = serde_json::to_string(&EnclaveRequest::new(operation)).unwrap();
+            assert_eq!(EnclaveRequest::unsupported_operation(&request), None);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_operation() {
+        assert_eq!(
+            EnclaveRequest::unsupported_operation(r#"{"id":"1","operation":{"Rotate":{"x":1}}}"#),
+            Some(("1".to_string(), "Rotate".to_string()))
+        );
+        assert_eq!(
+            EnclaveRequest::unsupported_operation(r#"{"id":"2","operation":"Shutdown"}"#),
+            Some(("2".to_string(), "Shutdown".to_string()))
+        );
+        // Known operations with bad fields are malformed, not unsupported
+        assert_eq!(
+            EnclaveRequest::unsupported_operation(r#"{"id":"3","operation":{"GetInfo":5}}"#),
+            None
+        );
+        assert_eq!(EnclaveRequest::unsupported_operation("not json"), None);
+    }
+
+    #[test]
+    fn test_request_without_protocol_version() {
+        let request: EnclaveRequest =
+            serde_json::from_str(r#"{"id":"1","operation":"GetInfo"}"#).unwrap();
+        assert_eq!(request.protocol_version, 0);
+        assert_eq!(
+            EnclaveRequest::new(EnclaveOperation::GetInfo).protocol_version,
+            PROTOCOL_VERSION
+        );
+    }
+
+    #[test]
+    fn test_signed_metrics_snapshot_serialization() {
+        let mut counters = BTreeMap::new();
+        counters.insert("generate_seed.count".to_string(), 3);
+
+        let signed = SignedMetricsSnapshot {
+            snapshot: MetricsSnapshot {
+                enclave_id: "test-enclave".to_string(),
+                sequence: 7,
+                timestamp: 1_700_000_000,
+                counters,
+                previous_hash: "00".repeat(32),
+                timestamp_uncertainty_ms: None,
+            },
+            snapshot_hash: "ab".repeat(32),
+            signature: "cd".repeat(64),
+            public_key: "02".repeat(33),
+        };
+
+        let serialized = serde_json::to_string(&signed).unwrap();
+        let deserialized: SignedMetricsSnapshot = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.snapshot.sequence, 7);
+        assert_eq!(deserialized.snapshot.counters["generate_seed.count"], 3);
+        // The hash and signature above are placeholders
+        assert!(!deserialized.verify());
+    }
+
+    #[test]
+    fn test_signed_metrics_snapshot_verify() {
+        use secp256k1::{Message, Secp256k1, SecretKey};
+
+        let snapshot = MetricsSnapshot {
+            enclave_id: "test-enclave".to_string(),
+            sequence: 0,
+            timestamp: 1_700_000_000,
+            counters: BTreeMap::new(),
+            previous_hash: "00".repeat(32),
+            timestamp_uncertainty_ms: None,
+        };
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let digest = snapshot.digest().unwrap();
+        let signed = SignedMetricsSnapshot {
+            snapshot,
+            snapshot_hash: hex::encode(digest),
+            signature: hex::encode(
+                secp.sign_ecdsa(&Message::from_digest(digest), &key)
+                    .serialize_compact(),
+            ),
+            public_key: hex::encode(key.public_key(&secp).serialize()),
+        };
+        assert!(signed.verify());
+
+        let mut tampered = signed.clone();
+        tampered.snapshot.timestamp += 1;
+        assert!(!tampered.verify());
+        let mut tampered = signed.clone();
+        tampered.public_key = "zz".to_string();
+        assert!(!tampered.verify());
+        let mut tampered = signed;
+        tampered.signature.truncate(10);
+        assert!(!tampered.verify());
+    }
+
+    #[test]
+    fn test_network_state_serialization() {
+        let state = NetworkState::NetworkUnavailable {
+            reason: "TAP interface tap0 not found".to_string(),
+            since: 1_700_000_000,
+        };
+
+        let value = serde_json::to_value(&state).unwrap();
+        assert_eq!(value["state"], "network_unavailable");
+        assert_eq!(value["reason"], "TAP interface tap0 not found");
+
+        let ready = serde_json::to_value(NetworkState::Ready).unwrap();
+        assert_eq!(ready, serde_json::json!({ "state": "ready" }));
+    }
+
+    #[test]
+    fn test_partition_capabilities_by_network_state() {
+        let capabilities = vec![
+            "seed_generation".to_string(),
+            "network_connectivity".to_string(),
+        ];
+
+        let (available, unavailable) =
+            NetworkState::Ready.partition_capabilities(capabilities.clone());
+        assert_eq!(available.len(), 2);
+        assert!(unavailable.is_empty());
+
+        let degraded = NetworkState::NetworkUnavailable {
+            reason: "down".to_string(),
+            since: 0,
+        };
+        let (available, unavailable) = degraded.partition_capabilities(capabilities);
+        assert_eq!(available, vec!["seed_generation".to_string()]);
+        assert_eq!(unavailable, vec!["network_connectivity".to_string()]);
+    }
+
+    #[test]
+    fn test_info_without_unavailable_capabilities_deserializes() {
+        let json = r#"{"Info":{"version":"1.0.0","enclave_id":"e","capabilities":["a"]}}"#;
+        let result: EnclaveResult = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            result,
+            EnclaveResult::Info { unavailable_capabilities, .. } if unavailable_capabilities.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_health_report_aggregation() {
+        let check = |name: &str, status, critical| HealthCheck {
+            name: name.to_string(),
+            status,
+            critical,
+            message: None,
+            duration_ms: 0,
+        };
+
+        let report = HealthReport::from_checks(vec![check("enclave", HealthStatus::Pass, true)]);
+        assert_eq!(report.status, HealthStatus::Pass);
+
+        let report = HealthReport::from_checks(vec![
+            check("enclave", HealthStatus::Pass, true),
+            check("network", HealthStatus::Fail, false),
+        ]);
+        assert_eq!(report.status, HealthStatus::Warn);
+        assert!(report.is_ready());
+
+        let report = HealthReport::from_checks(vec![
+            check("enclave", HealthStatus::Fail, true),
+            check("network", HealthStatus::Pass, false),
+        ]);
+        assert_eq!(report.status, HealthStatus::Fail);
+        assert!(!report.is_ready());
+    }
+
     #[test]
     fn test_http_request_serialization() {
         // Test GenerateSeedRequest
         let generate_request = GenerateSeedRequest {
             strength: Some(256),
-            passphrase: Some("test123".to_string()),
+            passphrase: Some("test123".into()),
+            language: SeedLanguage::Spanish,
+            attest: false,
+            fresh_attestation: false,
         };
         let serialized = serde_json::to_string(&generate_request).unwrap();
         assert!(!serialized.is_empty());
 
         // Test ValidateSeedRequest
         let validate_request = ValidateSeedRequest {
-            seed_phrase: "test seed".to_string(),
+            seed_phrase: "test seed".into(),
+            language: SeedLanguage::default(),
+            entropy: None,
         };
         let serialized = serde_json::to_string(&validate_request).unwrap();
         assert!(!serialized.is_empty());
 
         // Test DeriveKeyRequest
         let derive_key_request = DeriveKeyRequest {
-            seed_phrase: "test seed".to_string(),
+            seed_phrase: "test seed".into(),
             path: "m/44'/0'/0'/0/0".to_string(),
             curve: "secp256k1".to_string(),
+            attest: false,
+            fresh_attestation: false,
         };
         let serialized = serde_json::to_string(&derive_key_request).unwrap();
         assert!(!serialized.is_empty());
 
         // Test DeriveAddressRequest
         let derive_address_request = DeriveAddressRequest {
-            seed_phrase: "test seed".to_string(),
+            seed_phrase: "test seed".into(),
             path: "m/44'/0'/0'/0/0".to_string(),
             curve: "secp256k1".to_string(),
+            chain: Some(Chain::Ethereum),
         };
         let serialized = serde_json::to_string(&derive_address_request).unwrap();
         assert!(!serialized.is_empty());
     }
 
+    #[test]
+    fn test_derive_address_chain_is_optional() {
+        let request: DeriveAddressRequest = serde_json::from_str(
+            r#"{"seed_phrase":"test seed","path":"m/44'/60'/0'/0/0","curve":"secp256k1"}"#,
+        )
+        .unwrap();
+        assert_eq!(request.chain, None);
+
+        let request: DeriveAddressRequest = serde_json::from_str(
+            r#"{"seed_phrase":"test seed","path":"m/86'/0'/0'/0/0","curve":"secp256k1","chain":"bitcoin_taproot"}"#,
+        )
+        .unwrap();
+        assert_eq!(request.chain, Some(Chain::BitcoinTaproot));
+        assert_eq!(Chain::BitcoinTaproot.as_str(), "bitcoin_taproot");
+    }
+
+    #[test]
+    fn test_attestation_is_optional() {
+        let request: GenerateSeedRequest = serde_json::from_str(r#"{"strength":128}"#).unwrap();
+        assert!(!request.attest);
+
+        let response = GenerateSeedResponse {
+            seed_phrase: "test phrase".into(),
+            entropy: "00".into(),
+            strength: 128,
+            word_count: 12,
+            attestation: None,
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.get("attestation").is_none());
+        assert_eq!(
+            response_digest(&response).unwrap(),
+            response_digest(&json).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_attestation_freshness() {
+        let attestation = ChannelAttestation {
+            enclave_id: "enclave".to_string(),
+            timestamp: 1_000,
+            measurements: BTreeMap::new(),
+            user_data: String::new(),
+            nonce: Some("c0ffee".to_string()),
+            signature: String::new(),
+            timestamp_uncertainty_ms: None,
+        };
+        let nonce = [0xc0, 0xff, 0xee];
+
+        assert!(attestation.is_fresh(&nonce, 60, 1_030));
+        assert!(attestation.is_fresh(&nonce, 60, 990));
+        assert!(!attestation.is_fresh(&nonce, 60, 1_061));
+        assert!(!attestation.is_fresh(&[0xc0, 0xff], 60, 1_030));
+
+        let without_nonce = ChannelAttestation {
+            nonce: None,
+            ..attestation
+        };
+        assert!(!without_nonce.is_fresh(&[], 60, 1_000));
+    }
+
+    #[test]
+    fn test_signing_window_wraps_midnight() {
+        let office_hours = SigningWindow {
+            start_hour: 9,
+            end_hour: 17,
+        };
+        assert!(office_hours.contains(9));
+        assert!(!office_hours.contains(17));
+
+        let overnight = SigningWindow {
+            start_hour: 22,
+            end_hour: 6,
+        };
+        assert!(overnight.contains(23));
+        assert!(overnight.contains(0));
+        assert!(!overnight.contains(12));
+    }
+
+    #[test]
+    fn test_seed_language_defaults_to_english() {
+        let request: GenerateSeedRequest = serde_json::from_str(r#"{"strength":128}"#).unwrap();
+        assert_eq!(request.language, SeedLanguage::English);
+
+        let request: ValidateSeedRequest =
+            serde_json::from_str(r#"{"seed_phrase":"x","language":"chinese_simplified"}"#).unwrap();
+        assert_eq!(request.language, SeedLanguage::ChineseSimplified);
+    }
+
+    #[test]
+    fn test_api_error_code_serialization() {
+        let codes = [
+            ApiErrorCode::InvalidRequest,
+            ApiErrorCode::EnclaveOperationFailed,
+            ApiErrorCode::UnexpectedEnclaveResponse,
+            ApiErrorCode::EnclaveUnavailable,
+            ApiErrorCode::NetworkUnavailable,
+            ApiErrorCode::PayloadTooLarge,
+            ApiErrorCode::Unauthorized,
+            ApiErrorCode::Forbidden,
+            ApiErrorCode::NotFound,
+            ApiErrorCode::StorageFailed,
+        ];
+
+        for code in codes {
+            let serialized = serde_json::to_string(&code).unwrap();
+            assert_eq!(serialized, format!("\"{}\"", code.as_str()));
+            let deserialized: ApiErrorCode = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, code);
+        }
+    }
+
+    #[test]
+    fn test_enclave_error_code_serialization() {
+        let mut numbers = std::collections::HashSet::new();
+        for code in RenclaveErrorCode::ALL {
+            assert!(numbers.insert(code.code()));
+            assert_eq!(RenclaveErrorCode::from_code(code.code()), Some(code));
+
+            let serialized = serde_json::to_value(code).unwrap();
+            assert_eq!(
+                serialized,
+                serde_json::json!({"code": code.code(), "label": code.as_str()})
+            );
+            let deserialized: RenclaveErrorCode = serde_json::from_value(serialized).unwrap();
+            assert_eq!(deserialized, code);
+        }
+
+        // Enclaves that predate the enum send HTTP-style integers
+        let legacy: RenclaveErrorCode = serde_json::from_str("403").unwrap();
+        assert_eq!(legacy, RenclaveErrorCode::PolicyDenied);
+        let legacy: RenclaveErrorCode = serde_json::from_str("500").unwrap();
+        assert_eq!(legacy, RenclaveErrorCode::Internal);
+
+        // Codes added by newer enclaves
+        let unknown: RenclaveErrorCode =
+            serde_json::from_str(r#"{"code": 1999, "label": "quota_exceeded"}"#).unwrap();
+        assert_eq!(unknown, RenclaveErrorCode::Internal);
+    }
+
+    #[test]
+    fn test_scope_serialization() {
+        for scope in Scope::ALL {
+            let serialized = serde_json::to_string(&scope).unwrap();
+            assert_eq!(serialized, format!("\"{}\"", scope.as_str()));
+            let deserialized: Scope = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, scope);
+        }
+        assert!(serde_json::from_str::<Scope>(r#""key:export""#).is_err());
+    }
+
     #[test]
     fn test_renclave_error_display() {
         let errors = vec![
@@ -480,4 +2769,28 @@ mod tests {
             _ => panic!("Expected IO error"),
         }
     }
+
+    #[test]
+    fn test_threshold_policy() {
+        let default = ThresholdPolicy::default();
+        assert!(default.check(1, 3).is_err());
+        assert!(default.check(2, 10).is_ok());
+
+        let majority = ThresholdPolicy {
+            min_threshold: 2,
+            min_percent: 51,
+        };
+        assert_eq!(majority.required(3), 2);
+        assert_eq!(majority.required(4), 3);
+        assert_eq!(majority.required(10), 6);
+        assert!(majority.check(5, 10).is_err());
+        assert!(majority.check(6, 10).is_ok());
+
+        let strict = ThresholdPolicy {
+            min_threshold: 3,
+            min_percent: 0,
+        };
+        assert_eq!(strict.required(2), 3);
+        assert!(strict.check(2, 2).is_err());
+    }
 }
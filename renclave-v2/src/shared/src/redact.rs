@@ -0,0 +1,262 @@
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use crate::secret::REDACTED;
+
+/// Field names whose quoted values are scrubbed from every log line
+pub const SENSITIVE_FIELDS: &[&str] = &[
+    "seed_phrase",
+    "passphrase",
+    "mnemonic",
+    "phrase",
+    "entropy",
+    "private_key",
+    "secret",
+    "seed",
+];
+
+fn is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Scrub the values of sensitive fields from a log message.
+///
+/// Handles JSON (`"seed_phrase":"..."`), escaped JSON inside debug strings and Rust debug
+/// output (`seed_phrase: "..."`, `passphrase: Some("...")`).
+pub fn redact_message(message: &str) -> String {
+    let bytes = message.as_bytes();
+    let mut out = String::with_capacity(message.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if !is_ident(bytes[i]) || (i > 0 && is_ident(bytes[i - 1])) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < bytes.len() && is_ident(bytes[i]) {
+            i += 1;
+        }
+        if !SENSITIVE_FIELDS.contains(&&message[start..i]) {
+            continue;
+        }
+
+        if let Some((value_start, value_end)) = quoted_value_after(bytes, i) {
+            out.push_str(&message[copied..value_start]);
+            out.push_str(REDACTED);
+            copied = value_end;
+            i = value_end;
+        }
+    }
+
+    out.push_str(&message[copied..]);
+    out
+}
+
+/// Locate the quoted value following a field name. Returns the byte range of the value
+/// without quotes; an unterminated value extends to the end of the message.
+fn quoted_value_after(bytes: &[u8], mut i: usize) -> Option<(usize, usize)> {
+    let skip_whitespace = |mut i: usize| {
+        while bytes.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+            i += 1;
+        }
+        i
+    };
+
+    // Closing quote of a JSON key, possibly escaped
+    if bytes.get(i) == Some(&b'\\') {
+        i += 1;
+    }
+    if bytes.get(i) == Some(&b'"') {
+        i += 1;
+    }
+
+    i = skip_whitespace(i);
+    if bytes.get(i) != Some(&b':') {
+        return None;
+    }
+    i = skip_whitespace(i + 1);
+
+    if bytes[i..].starts_with(b"Some(") {
+        i += "Some(".len();
+    }
+
+    let escaped = bytes.get(i) == Some(&b'\\');
+    if escaped {
+        i += 1;
+    }
+    if bytes.get(i) != Some(&b'"') {
+        return None;
+    }
+    i += 1;
+
+    let value_start = i;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if escaped && bytes.get(i + 1) == Some(&b'"') => return Some((value_start, i)),
+            b'\\' => i += 2,
+            b'"' if !escaped => return Some((value_start, i)),
+            _ => i += 1,
+        }
+    }
+
+    Some((value_start, bytes.len()))
+}
+
+/// Logger that scrubs sensitive fields from every record before handing it to `inner`
+pub struct RedactingLogger {
+    inner: Box<dyn Log>,
+}
+
+impl RedactingLogger {
+    pub fn new(inner: Box<dyn Log>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Log for RedactingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = redact_message(&record.args().to_string());
+        self.inner.log(
+            &Record::builder()
+                .metadata(record.metadata().clone())
+                .args(format_args!("{}", message))
+                .module_path(record.module_path())
+                .file(record.file())
+                .line(record.line())
+                .build(),
+        );
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Install `inner` as the global logger behind the redaction filter
+pub fn init_logger(inner: Box<dyn Log>, max_level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_boxed_logger(Box::new(RedactingLogger::new(inner)))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+/// Change the verbosity of the global logger at runtime.
+///
+/// Accepts `off`, `error`, `warn`, `info`, `debug` or `trace`, case-insensitively. Records
+/// above the level are dropped before reaching the inner logger, so the inner logger must
+/// pass everything that may be turned on later.
+pub fn set_log_level(level: &str) -> Result<LevelFilter, String> {
+    let level = level
+        .trim()
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("Unknown log level '{}'", level))?;
+    log::set_max_level(level);
+    Ok(level)
+}
+
+/// Current verbosity of the global logger, as accepted by `set_log_level`
+pub fn log_level() -> String {
+    log::max_level().as_str().to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_redacts_json_fields() {
+        let message = r#"📨 Received request: {"id":"1","operation":{"ValidateSeed":{"seed_phrase":"abandon ability"}}}"#;
+        let redacted = redact_message(message);
+
+        assert!(!redacted.contains("abandon"));
+        assert!(redacted.contains(r#""seed_phrase":"[REDACTED]""#));
+        assert!(redacted.contains(r#""id":"1""#));
+    }
+
+    #[test]
+    fn test_redacts_debug_output() {
+        let message = r#"GenerateSeed { strength: 256, passphrase: Some("hunter2") }"#;
+        assert_eq!(
+            redact_message(message),
+            r#"GenerateSeed { strength: 256, passphrase: Some("[REDACTED]") }"#
+        );
+    }
+
+    #[test]
+    fn test_redacts_escaped_json_inside_debug_string() {
+        let message = r#"body: "{\"private_key\":\"deadbeef\",\"path\":\"m/0\"}""#;
+        let redacted = redact_message(message);
+
+        assert!(!redacted.contains("deadbeef"));
+        assert!(redacted.contains(r#"\"path\":\"m/0\""#));
+    }
+
+    #[test]
+    fn test_leaves_unrelated_text_alone() {
+        let message = "🔑 Generating seed phrase (strength: 256 bits), seeds: 3";
+        assert_eq!(redact_message(message), message);
+
+        let message = r#"{"public_key":"02ab","address":"bc1q"}"#;
+        assert_eq!(redact_message(message), message);
+    }
+
+    #[test]
+    fn test_redacts_unterminated_value() {
+        let message = r#"{"entropy":"00112233"#;
+        assert_eq!(redact_message(message), r#"{"entropy":"[REDACTED]"#);
+    }
+
+    struct CaptureLogger(Arc<Mutex<Vec<String>>>);
+
+    impl Log for CaptureLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn test_set_log_level() {
+        let original = log::max_level();
+
+        assert_eq!(set_log_level(" TRACE "), Ok(LevelFilter::Trace));
+        assert_eq!(log_level(), "trace");
+        assert!(set_log_level("verbose").is_err());
+        assert_eq!(log_level(), "trace");
+
+        log::set_max_level(original);
+    }
+
+    #[test]
+    fn test_redacting_logger_scrubs_records() {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let logger = RedactingLogger::new(Box::new(CaptureLogger(Arc::clone(&captured))));
+
+        logger.log(
+            &Record::builder()
+                .args(format_args!("{}", r#"{"seed_phrase":"abandon ability"}"#))
+                .level(log::Level::Debug)
+                .build(),
+        );
+
+        assert_eq!(
+            captured.lock().unwrap().as_slice(),
+            [r#"{"seed_phrase":"[REDACTED]"}"#]
+        );
+    }
+}
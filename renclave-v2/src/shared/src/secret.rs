@@ -0,0 +1,222 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::io::{self, Read, Write};
+use zeroize::Zeroize;
+
+/// Placeholder printed instead of secret values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Best-effort `mlock` of a heap buffer so it is never written to swap.
+///
+/// Failures (e.g. `RLIMIT_MEMLOCK` exhausted) are ignored: locking is a hardening measure,
+/// not a correctness requirement.
+fn lock_memory(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    // SAFETY: `ptr..ptr+len` is a live heap allocation owned by the caller.
+    #[cfg(unix)]
+    let _ = unsafe { libc::mlock(ptr as *const libc::c_void, len) };
+}
+
+/// Undo `lock_memory`. Page locks are not reference counted, so this also unlocks any
+/// other secret sharing a page with this buffer; such secrets are still zeroized on drop.
+fn unlock_memory(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    // SAFETY: `ptr..ptr+len` is a live heap allocation owned by the caller.
+    #[cfg(unix)]
+    let _ = unsafe { libc::munlock(ptr as *const libc::c_void, len) };
+}
+
+/// String holding secret material such as seed phrases, entropy or private keys.
+///
+/// `Debug` and `Display` never print the value, so a secret cannot reach the logs by being
+/// formatted. The value is (de)serialized transparently as a plain JSON or Borsh string and
+/// must be read explicitly through `expose_secret`. The backing buffer is locked in memory
+/// where possible and zeroized on drop.
+#[derive(Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: impl Into<String>) -> Self {
+        let value = value.into();
+        lock_memory(value.as_ptr(), value.capacity());
+        Self(value)
+    }
+
+    /// Access the secret value
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Clone for SecretString {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+        unlock_memory(self.0.as_ptr(), self.0.capacity());
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as Deserialize>::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl BorshSerialize for SecretString {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.0, writer)
+    }
+}
+
+impl BorshDeserialize for SecretString {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        String::deserialize_reader(reader).map(Self::new)
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Byte buffer holding secret material such as entropy, BIP39 seeds or private keys.
+///
+/// Same guarantees as `SecretString`: redacted formatting, memory locked where possible and
+/// zeroized on drop.
+#[derive(Default, PartialEq, Eq)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    pub fn new(value: impl Into<Vec<u8>>) -> Self {
+        let value = value.into();
+        lock_memory(value.as_ptr(), value.capacity());
+        Self(value)
+    }
+
+    /// Zero-filled buffer of `len` bytes, to be filled in place through `expose_secret_mut`
+    pub fn zeroed(len: usize) -> Self {
+        Self::new(vec![0u8; len])
+    }
+
+    /// Access the secret bytes
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Mutable access to the secret bytes; the length cannot change
+    pub fn expose_secret_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Clone for SecretBytes {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+        unlock_memory(self.0.as_ptr(), self.0.capacity());
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(value: Vec<u8>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(value: &[u8]) -> Self {
+        Self::new(value.to_vec())
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} bytes)", REDACTED, self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_string_formatting_is_redacted() {
+        let secret = SecretString::new("abandon abandon about");
+
+        assert_eq!(format!("{}", secret), REDACTED);
+        assert_eq!(format!("{:?}", secret), REDACTED);
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some([REDACTED])");
+        assert_eq!(secret.expose_secret(), "abandon abandon about");
+    }
+
+    #[test]
+    fn test_secret_string_serializes_transparently() {
+        let secret = SecretString::new("deadbeef");
+        let json = serde_json::to_string(&secret).unwrap();
+        assert_eq!(json, "\"deadbeef\"");
+
+        let parsed: SecretString = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, secret);
+    }
+
+    #[test]
+    fn test_secret_bytes_formatting_is_redacted() {
+        let secret = SecretBytes::new(vec![0xde, 0xad, 0xbe, 0xef]);
+
+        assert_eq!(format!("{:?}", secret), "[REDACTED] (4 bytes)");
+        assert_eq!(secret.expose_secret(), &[0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(secret.len(), 4);
+    }
+
+    #[test]
+    fn test_secret_bytes_fill_in_place() {
+        let mut secret = SecretBytes::zeroed(16);
+        secret.expose_secret_mut().fill(0xaa);
+
+        assert!(secret.expose_secret().iter().all(|&b| b == 0xaa));
+        assert_eq!(secret.clone(), secret);
+    }
+}
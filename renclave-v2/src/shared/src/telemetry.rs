@@ -0,0 +1,90 @@
+//! OpenTelemetry tracing for the host and the enclave.
+//!
+//! Spans are exported over OTLP/HTTP when an endpoint is configured and are no-ops otherwise.
+//! The trace context crosses the enclave socket as a W3C `traceparent` in
+//! `EnclaveRequest::trace_context`, so a host request, its enclave round trip and the enclave
+//! operation form one trace.
+
+use std::collections::HashMap;
+
+use opentelemetry::global::{self, BoxedTracer};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TraceError;
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+
+/// Base URL of the OTLP/HTTP collector; `/v1/traces` is appended
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+/// Full URL of the OTLP/HTTP traces endpoint, overriding `OTEL_EXPORTER_OTLP_ENDPOINT`
+pub const OTLP_TRACES_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT";
+/// Overrides the service name reported with each span
+pub const SERVICE_NAME_ENV: &str = "OTEL_SERVICE_NAME";
+
+const TRACEPARENT: &str = "traceparent";
+
+/// Install a global tracer provider exporting to the configured OTLP endpoint.
+///
+/// Returns `None` and leaves tracing a no-op when no endpoint is set. Must be called inside a
+/// Tokio runtime; shut the provider down on exit to flush batched spans.
+pub fn init_tracing(service_name: &str) -> Result<Option<TracerProvider>, TraceError> {
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+    if env(OTLP_ENDPOINT_ENV).is_none() && env(OTLP_TRACES_ENDPOINT_ENV).is_none() {
+        return Ok(None);
+    }
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .build()?;
+    let service_name = env(SERVICE_NAME_ENV).unwrap_or_else(|| service_name.to_string());
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new("service.name", service_name)]))
+        .build();
+
+    global::set_tracer_provider(provider.clone());
+    Ok(Some(provider))
+}
+
+/// Tracer for renclave spans, a no-op until `init_tracing` installs a provider
+pub fn tracer() -> BoxedTracer {
+    global::tracer("renclave")
+}
+
+/// W3C `traceparent` of the span in `cx`, or `None` if it has no valid span
+pub fn inject(cx: &Context) -> Option<String> {
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(cx, &mut carrier);
+    carrier.remove(TRACEPARENT)
+}
+
+/// Context with `traceparent` as remote parent; empty if it is absent or malformed
+pub fn extract(traceparent: Option<&str>) -> Context {
+    let mut carrier = HashMap::new();
+    if let Some(traceparent) = traceparent {
+        carrier.insert(TRACEPARENT.to_string(), traceparent.to_string());
+    }
+    TraceContextPropagator::new().extract(&carrier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TraceContextExt;
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+        let cx = extract(Some(traceparent));
+        assert!(cx.span().span_context().is_remote());
+        assert_eq!(inject(&cx).as_deref(), Some(traceparent));
+    }
+
+    #[test]
+    fn test_missing_or_malformed_traceparent() {
+        assert_eq!(inject(&extract(None)), None);
+        assert_eq!(inject(&extract(Some("00-not-a-trace-01"))), None);
+    }
+}
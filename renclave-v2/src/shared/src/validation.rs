@@ -0,0 +1,612 @@
+//! Typed validators for request fields.
+//!
+//! The host checks requests with these before they reach the enclave, and the enclave uses the
+//! same validators for the operations it receives, so both accept the same input. Every
+//! failure names the request field it concerns.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+use crate::{
+    DeriveAddressRequest, DeriveKeyRequest, DeriveXpubRequest, GenerateSeedRequest,
+    OpenChannelRequest, SignPsbtRequest, ThresholdMessage, ValidateSeedRequest,
+    VerifySignatureRequest,
+};
+
+/// Deepest BIP-32 path; the depth is stored in a single byte
+pub const MAX_DERIVATION_DEPTH: usize = 255;
+
+/// First index reserved for hardened derivation
+const HARDENED_OFFSET: u64 = 1 << 31;
+
+/// A request field that failed validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, thiserror::Error)]
+#[error("{field}: {message}")]
+pub struct FieldError {
+    /// JSON name of the field
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Every field error found in a request, in field order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    /// Record the error of a failed check, returning the value of a passing one
+    pub fn check<T>(&mut self, result: Result<T, FieldError>) -> Option<T> {
+        result.map_err(|e| self.0.push(e)).ok()
+    }
+
+    /// `Ok` when no check failed
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    pub fn errors(&self) -> &[FieldError] {
+        &self.0
+    }
+
+    pub fn into_errors(self) -> Vec<FieldError> {
+        self.0
+    }
+}
+
+impl From<FieldError> for ValidationErrors {
+    fn from(error: FieldError) -> Self {
+        Self(vec![error])
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// A request whose fields can be checked without the enclave
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// The value, which must contain more than whitespace
+pub fn non_empty<'a>(field: &str, value: &'a str) -> Result<&'a str, FieldError> {
+    match value.trim() {
+        "" => Err(FieldError::new(field, "must not be empty")),
+        trimmed => Ok(trimmed),
+    }
+}
+
+/// BIP-39 entropy size in bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Strength(u32);
+
+impl Strength {
+    pub const ALLOWED: [u32; 5] = [128, 160, 192, 224, 256];
+    /// Used when a request does not name a strength
+    pub const DEFAULT: Strength = Strength(256);
+
+    pub fn new(bits: u32) -> Result<Self, FieldError> {
+        if Self::ALLOWED.contains(&bits) {
+            Ok(Self(bits))
+        } else {
+            Err(FieldError::new(
+                "strength",
+                format!(
+                    "Invalid strength: {}. Must be 128, 160, 192, 224, or 256 bits",
+                    bits
+                ),
+            ))
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Words in a mnemonic of this strength: entropy plus a checksum bit per 32 bits, 11 bits
+    /// per word
+    pub fn word_count(self) -> usize {
+        ((self.0 + self.0 / 32) / 11) as usize
+    }
+}
+
+/// Elliptic curve of a key or signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    Secp256k1,
+    Ed25519,
+}
+
+impl Curve {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Curve::Secp256k1 => "secp256k1",
+            Curve::Ed25519 => "ed25519",
+        }
+    }
+}
+
+/// Case-insensitive, with or without a `CURVE_` prefix, e.g. `secp256k1` or `CURVE_ED25519`
+impl FromStr for Curve {
+    type Err = FieldError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let name = value.trim().to_ascii_lowercase();
+        match name.strip_prefix("curve_").unwrap_or(&name) {
+            "secp256k1" => Ok(Curve::Secp256k1),
+            "ed25519" => Ok(Curve::Ed25519),
+            _ => Err(FieldError::new(
+                "curve",
+                format!(
+                    "Unsupported curve '{}': expected secp256k1 or ed25519",
+                    value.trim()
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One level of a derivation path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChildIndex {
+    /// Below 2^31; hardening is kept separately
+    pub index: u32,
+    pub hardened: bool,
+}
+
+impl ChildIndex {
+    /// Split a component into the rest and whether it ends in a hardened marker
+    pub fn split_hardened(component: &str) -> (&str, bool) {
+        match component.strip_suffix(['\'', 'h', 'H']) {
+            Some(rest) => (rest, true),
+            None => (component, false),
+        }
+    }
+
+    /// Parse a component such as `44'` or `0` found at 1-based `position` in a path
+    pub fn parse(component: &str, position: usize) -> Result<Self, FieldError> {
+        let (digits, hardened) = Self::split_hardened(component);
+        let index = Some(digits)
+            .filter(|d| !d.is_empty() && d.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|d| d.parse::<u64>().ok())
+            .ok_or_else(|| {
+                FieldError::new(
+                    "path",
+                    format!("Invalid index '{}' at position {}", component, position),
+                )
+            })?;
+        if index >= HARDENED_OFFSET {
+            return Err(FieldError::new(
+                "path",
+                format!(
+                    "Index {} at position {} is out of range (must be below 2^31)",
+                    index, position
+                ),
+            ));
+        }
+        Ok(Self {
+            index: index as u32,
+            hardened,
+        })
+    }
+}
+
+/// Syntactically valid BIP-32 path such as `m/44'/60'/0'/0/0`.
+///
+/// Hardened indices may be written with `'`, `h` or `H`. The enclave parses paths with this type
+/// and additionally applies the BIP-44 layout and its derivation policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<ChildIndex>);
+
+impl DerivationPath {
+    pub fn children(&self) -> &[ChildIndex] {
+        &self.0
+    }
+
+    /// Components after the leading `m`, each checked to be non-empty but not parsed.
+    ///
+    /// Shared with formats that extend the path syntax, such as the enclave's path templates.
+    pub fn components(path: &str) -> Result<Vec<&str>, FieldError> {
+        let invalid = |message: String| FieldError::new("path", message);
+        let mut parts = non_empty("path", path)?.split('/');
+        match parts.next() {
+            Some("m") | Some("M") => {}
+            other => {
+                return Err(invalid(format!(
+                    "Derivation path must start with 'm', got '{}'",
+                    other.unwrap_or_default()
+                )))
+            }
+        }
+
+        parts
+            .enumerate()
+            .map(|(i, component)| match component {
+                "" => Err(invalid(format!("Empty component at position {}", i + 1))),
+                component => Ok(component),
+            })
+            .collect()
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = FieldError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let children = Self::components(path)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, component)| ChildIndex::parse(component, i + 1))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if children.len() > MAX_DERIVATION_DEPTH {
+            return Err(FieldError::new(
+                "path",
+                format!(
+                    "Derivation path is {} levels deep, maximum is {}",
+                    children.len(),
+                    MAX_DERIVATION_DEPTH
+                ),
+            ));
+        }
+        Ok(Self(children))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("m")?;
+        for child in &self.0 {
+            write!(f, "/{}", child.index)?;
+            if child.hardened {
+                f.write_str("'")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Signers needed out of a number of participants.
+///
+/// Only the bounds every scheme needs; minimums come from `ThresholdPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold {
+    pub threshold: u16,
+    pub participants: usize,
+}
+
+impl Threshold {
+    /// Most participants in one scheme
+    pub const MAX_PARTICIPANTS: usize = 255;
+
+    pub fn new(threshold: u16, participants: usize) -> Result<Self, FieldError> {
+        let invalid = |message: String| Err(FieldError::new("threshold", message));
+        if threshold == 0 {
+            return invalid("threshold must be at least 1".to_string());
+        }
+        if participants > Self::MAX_PARTICIPANTS {
+            return invalid(format!(
+                "{} participants, at most {} are supported",
+                participants,
+                Self::MAX_PARTICIPANTS
+            ));
+        }
+        if usize::from(threshold) > participants {
+            return invalid(format!(
+                "threshold {} exceeds the {} participants",
+                threshold, participants
+            ));
+        }
+        Ok(Self {
+            threshold,
+            participants,
+        })
+    }
+
+    /// Check that `signers` distinct participants can sign: at least the threshold and at most
+    /// every participant
+    pub fn check_signers(&self, signers: usize) -> Result<(), FieldError> {
+        if signers < usize::from(self.threshold) || signers > self.participants {
+            return Err(FieldError::new(
+                "signers",
+                format!(
+                    "{} signers given, need between {} and {}",
+                    signers, self.threshold, self.participants
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Name used as a path segment or storage key, such as an API identity or enclave name:
+/// 1-64 ASCII letters, digits, `-`, `_` or `.`, not starting with `.`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NamespaceName(String);
+
+impl NamespaceName {
+    pub const MAX_LEN: usize = 64;
+
+    /// Validate `value` as the name in `field`
+    pub fn new(field: &str, value: &str) -> Result<Self, FieldError> {
+        let valid = (1..=Self::MAX_LEN).contains(&value.len())
+            && !value.starts_with('.')
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'));
+        if !valid {
+            return Err(FieldError::new(
+                field,
+                format!(
+                    "'{}' must be 1-{} letters, digits, '-', '_' or '.', not starting with '.'",
+                    value,
+                    Self::MAX_LEN
+                ),
+            ));
+        }
+        Ok(Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for NamespaceName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Validate for GenerateSeedRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if let Some(bits) = self.strength {
+            errors.check(Strength::new(bits));
+        }
+        errors.into_result()
+    }
+}
+
+impl Validate for ValidateSeedRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        non_empty("seed_phrase", self.seed_phrase.expose_secret())?;
+        Ok(())
+    }
+}
+
+impl Validate for DeriveKeyRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check(non_empty("seed_phrase", self.seed_phrase.expose_secret()));
+        errors.check(self.path.parse::<DerivationPath>());
+        errors.check(self.curve.parse::<Curve>());
+        errors.into_result()
+    }
+}
+
+impl Validate for DeriveAddressRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check(non_empty("seed_phrase", self.seed_phrase.expose_secret()));
+        errors.check(self.path.parse::<DerivationPath>());
+        if let Some(curve) = errors.check(self.curve.parse::<Curve>()) {
+            if let Some(chain) = self.chain {
+                if chain.uses_ed25519() != (curve == Curve::Ed25519) {
+                    errors.check::<()>(Err(FieldError::new(
+                        "curve",
+                        format!("Chain {} does not use curve '{}'", chain.as_str(), curve),
+                    )));
+                }
+            }
+        }
+        errors.into_result()
+    }
+}
+
+impl Validate for DeriveXpubRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check(non_empty("seed_phrase", self.seed_phrase.expose_secret()));
+        errors.check(self.path.parse::<DerivationPath>());
+        errors.into_result()
+    }
+}
+
+impl Validate for SignPsbtRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check(non_empty("seed_phrase", self.seed_phrase.expose_secret()));
+        errors.check(non_empty("psbt", &self.psbt));
+        errors.into_result()
+    }
+}
+
+impl Validate for OpenChannelRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        non_empty("client_public_key", &self.client_public_key)?;
+        Ok(())
+    }
+}
+
+impl Validate for VerifySignatureRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check(self.curve.parse::<Curve>());
+        errors.check(non_empty("public_key", &self.public_key));
+        errors.check(non_empty("message", &self.message));
+        errors.check(non_empty("signature", &self.signature));
+        errors.into_result()
+    }
+}
+
+impl Validate for ThresholdMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        match self {
+            ThresholdMessage::Start {
+                group_public_key,
+                verifying_shares,
+                threshold,
+                signers,
+                message,
+            } => {
+                errors.check(non_empty("group_public_key", group_public_key));
+                let mut distinct = signers.clone();
+                distinct.sort_unstable();
+                distinct.dedup();
+                if let Some(threshold) =
+                    errors.check(Threshold::new(*threshold, verifying_shares.len()))
+                {
+                    errors.check(threshold.check_signers(distinct.len()));
+                }
+                errors.check(non_empty("message", message));
+            }
+            ThresholdMessage::Commit { session_id, .. }
+            | ThresholdMessage::Sign { session_id, .. }
+            | ThresholdMessage::Status { session_id } => {
+                errors.check(non_empty("session_id", session_id));
+            }
+        }
+        errors.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chain, SecretString};
+
+    #[test]
+    fn test_strength() {
+        for (bits, words) in [(128, 12), (160, 15), (192, 18), (224, 21), (256, 24)] {
+            assert_eq!(Strength::new(bits).unwrap().word_count(), words);
+        }
+        for bits in [0, 100, 129, 512] {
+            assert_eq!(Strength::new(bits).unwrap_err().field, "strength");
+        }
+        assert_eq!(Strength::DEFAULT.bits(), 256);
+    }
+
+    #[test]
+    fn test_curve_names() {
+        for name in [
+            "secp256k1",
+            "SECP256K1",
+            "CURVE_SECP256K1",
+            " curve_secp256k1 ",
+        ] {
+            assert_eq!(name.parse::<Curve>().unwrap(), Curve::Secp256k1);
+        }
+        for name in ["ed25519", "CURVE_ED25519"] {
+            assert_eq!(name.parse::<Curve>().unwrap(), Curve::Ed25519);
+        }
+        for name in ["", "p256", "secp256k1-ed25519", "curve_"] {
+            assert_eq!(name.parse::<Curve>().unwrap_err().field, "curve");
+        }
+    }
+
+    #[test]
+    fn test_derivation_path() {
+        let path: DerivationPath = "m/44h/60'/0H/0/7".parse().unwrap();
+        assert_eq!(path.to_string(), "m/44'/60'/0'/0/7");
+        assert_eq!(path.children().len(), 5);
+        assert!("m".parse::<DerivationPath>().unwrap().children().is_empty());
+
+        for path in [
+            "",
+            "44'/0'",
+            "m/44'//0",
+            "m/-1",
+            "m/x'",
+            "m/2147483648",
+            "m/'",
+        ] {
+            assert_eq!(
+                path.parse::<DerivationPath>().unwrap_err().field,
+                "path",
+                "{}",
+                path
+            );
+        }
+        let too_deep = format!("m{}", "/0".repeat(MAX_DERIVATION_DEPTH + 1));
+        assert!(too_deep.parse::<DerivationPath>().is_err());
+    }
+
+    #[test]
+    fn test_threshold_bounds() {
+        assert!(Threshold::new(2, 3).is_ok());
+        assert!(Threshold::new(3, 3).is_ok());
+        assert!(Threshold::new(0, 3).is_err());
+        assert!(Threshold::new(4, 3).is_err());
+        assert!(Threshold::new(2, Threshold::MAX_PARTICIPANTS + 1).is_err());
+
+        let scheme = Threshold::new(2, 3).unwrap();
+        assert!(scheme.check_signers(2).is_ok());
+        assert!(scheme.check_signers(3).is_ok());
+        assert_eq!(scheme.check_signers(1).unwrap_err().field, "signers");
+        assert!(scheme.check_signers(4).is_err());
+    }
+
+    #[test]
+    fn test_namespace_name() {
+        for name in ["ops", "backup-1", "team.signer_2"] {
+            assert_eq!(NamespaceName::new("name", name).unwrap().as_str(), name);
+        }
+        for name in ["", ".", "..", ".hidden", "has space", "slash/name"] {
+            assert!(NamespaceName::new("name", name).is_err(), "{}", name);
+        }
+        assert!(NamespaceName::new("name", &"a".repeat(NamespaceName::MAX_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_request_errors_name_every_field() {
+        let request = DeriveAddressRequest {
+            seed_phrase: SecretString::new(" ".to_string()),
+            path: "44'/0'".to_string(),
+            curve: "ed25519".to_string(),
+            chain: Some(Chain::Ethereum),
+        };
+        let errors = request.validate().unwrap_err();
+        let fields: Vec<&str> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["seed_phrase", "path", "curve"]);
+
+        let request = DeriveAddressRequest {
+            seed_phrase: SecretString::new("abandon about".to_string()),
+            path: "m/44'/501'/0'/0'".to_string(),
+            curve: "CURVE_ED25519".to_string(),
+            chain: Some(Chain::Solana),
+        };
+        assert!(request.validate().is_ok());
+    }
+}
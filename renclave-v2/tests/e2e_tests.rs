@@ -2,16 +2,22 @@ use renclave_shared::{
     EnclaveOperation, EnclaveRequest, EnclaveResponse, EnclaveResult, RenclaveError,
 };
 use renclave_enclave::seed_generator::SeedGenerator;
+use renclave_enclave::{ClockConfig, TrustedClock};
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
+/// Unsynchronized clock, enough for anything but time-based signing policies
+fn clock() -> Arc<TrustedClock> {
+    Arc::new(TrustedClock::new(ClockConfig::default()))
+}
+
 /// End-to-End tests for the complete renclave system
 /// These tests simulate real-world usage scenarios
 
 #[tokio::test]
 async fn test_complete_seed_generation_workflow() {
     // Test the complete workflow from request to response
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Simulate a client request
     let operation = EnclaveOperation::GenerateSeed {
@@ -59,7 +65,7 @@ async fn test_complete_seed_generation_workflow() {
 
 #[tokio::test]
 async fn test_seed_validation_workflow() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Generate a seed first
     let seed = seed_generator.generate_seed(128, None).await.unwrap();
@@ -116,7 +122,7 @@ async fn test_seed_validation_workflow() {
 
 #[tokio::test]
 async fn test_error_handling_workflow() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Test invalid strength request
     let operation = EnclaveOperation::GenerateSeed {
@@ -154,7 +160,7 @@ async fn test_error_handling_workflow() {
 
 #[tokio::test]
 async fn test_concurrent_requests_workflow() {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
     
     // Simulate multiple concurrent requests
     let mut handles = vec![];
@@ -207,7 +213,7 @@ async fn test_concurrent_requests_workflow() {
 
 #[tokio::test]
 async fn test_seed_derivation_workflow() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Generate a base seed
     let base_seed = seed_generator.generate_seed(256, None).await.unwrap();
@@ -343,7 +349,7 @@ async fn test_attestation_workflow() {
 
 #[tokio::test]
 async fn test_stress_test_workflow() {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
     
     // Generate many seeds rapidly to test system stability
     let mut handles = vec![];
@@ -394,7 +400,7 @@ async fn test_stress_test_workflow() {
 
 #[tokio::test]
 async fn test_error_recovery_workflow() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Test that the system can recover from errors
     let mut successful_requests = 0;
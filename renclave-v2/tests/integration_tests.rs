@@ -2,15 +2,21 @@ use renclave_shared::{
     EnclaveOperation, EnclaveRequest, EnclaveResponse, EnclaveResult, RenclaveError,
 };
 use renclave_enclave::seed_generator::SeedGenerator;
+use renclave_enclave::{ClockConfig, TrustedClock};
 use std::sync::Arc;
 
+/// Unsynchronized clock, enough for anything but time-based signing policies
+fn clock() -> Arc<TrustedClock> {
+    Arc::new(TrustedClock::new(ClockConfig::default()))
+}
+
 /// Integration tests for renclave components
 /// These tests verify that different parts of the system work together correctly
 
 #[tokio::test]
 async fn test_seed_generation_flow() {
     // Test the complete seed generation flow
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Generate a seed
     let seed_result = seed_generator.generate_seed(256, None).await;
@@ -28,7 +34,7 @@ async fn test_seed_generation_flow() {
 
 #[tokio::test]
 async fn test_seed_validation_integration() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Generate a valid seed
     let seed = seed_generator.generate_seed(128, None).await.unwrap();
@@ -44,7 +50,7 @@ async fn test_seed_validation_integration() {
 
 #[tokio::test]
 async fn test_entropy_consistency() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Generate multiple seeds and verify entropy consistency
     let seed1 = seed_generator.generate_seed(256, None).await.unwrap();
@@ -60,7 +66,7 @@ async fn test_entropy_consistency() {
 
 #[tokio::test]
 async fn test_passphrase_integration() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     let passphrase = "test-passphrase-123";
     
     // Generate seed with passphrase
@@ -80,7 +86,7 @@ async fn test_passphrase_integration() {
 
 #[tokio::test]
 async fn test_error_handling_integration() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Test invalid strength
     let result = seed_generator.generate_seed(0, None).await;
@@ -163,7 +169,7 @@ async fn test_network_integration() {
 
 #[tokio::test]
 async fn test_concurrent_seed_generation() {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
     
     // Generate multiple seeds concurrently
     let mut handles = vec![];
@@ -189,7 +195,7 @@ async fn test_concurrent_seed_generation() {
 
 #[tokio::test]
 async fn test_seed_strength_validation() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Test valid strengths
     let valid_strengths = vec![128, 256];
@@ -215,7 +221,7 @@ async fn test_seed_strength_validation() {
 
 #[tokio::test]
 async fn test_mnemonic_phrase_quality() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Generate multiple seeds and verify quality
     for _ in 0..10 {
@@ -241,7 +247,7 @@ async fn test_mnemonic_phrase_quality() {
 
 #[tokio::test]
 async fn test_seed_phrase_uniqueness() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Generate multiple seeds and ensure they're unique
     let mut phrases = std::collections::HashSet::new();
@@ -254,7 +260,7 @@ async fn test_seed_phrase_uniqueness() {
 
 #[tokio::test]
 async fn test_invalid_input_handling() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Test various invalid inputs
     let invalid_inputs = vec![
@@ -279,7 +285,7 @@ async fn test_invalid_input_handling() {
 
 #[tokio::test]
 async fn test_seed_generation_edge_cases() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Test minimum valid strength
     let result = seed_generator.generate_seed(128, None).await;
@@ -298,7 +304,7 @@ async fn test_seed_generation_edge_cases() {
 
 #[tokio::test]
 async fn test_passphrase_edge_cases() {
-    let seed_generator = SeedGenerator::new().await.unwrap();
+    let seed_generator = SeedGenerator::new(clock()).await.unwrap();
     
     // Test empty passphrase
     let seed1 = seed_generator.generate_seed(256, Some("")).await.unwrap();
@@ -317,7 +323,7 @@ async fn test_passphrase_edge_cases() {
 
 #[tokio::test]
 async fn test_concurrent_validation() {
-    let seed_generator = Arc::new(SeedGenerator::new().await.unwrap());
+    let seed_generator = Arc::new(SeedGenerator::new(clock()).await.unwrap());
     
     // Generate a valid seed
     let seed = seed_generator.generate_seed(256, None).await.unwrap();